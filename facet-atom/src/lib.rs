@@ -34,10 +34,20 @@
 //! # Atom Namespace
 //!
 //! All types use the Atom namespace `http://www.w3.org/2005/Atom` as specified in RFC 4287.
+//!
+//! # RSS
+//!
+//! RSS 2.0 feeds are read into [`Rss`]. Use [`parse_feed`] when the kind of
+//! feed isn't known in advance.
 
 use facet::Facet;
+use facet_dom::{DomEvent, DomParser};
 use facet_xml as xml;
 
+mod rss;
+
+pub use rss::{Channel, Enclosure, Guid, Item, Rss};
+
 pub const ATOM_NS: &str = "http://www.w3.org/2005/Atom";
 
 /// Error type for Atom parsing
@@ -70,6 +80,70 @@ where
     facet_xml::to_string(value)
 }
 
+/// A feed of either kind, as returned by [`parse_feed`].
+#[derive(Debug, Clone)]
+pub enum AnyFeed {
+    /// An RSS 2.0 feed.
+    Rss(Rss),
+    /// An Atom 1.0 feed.
+    Atom(Feed),
+}
+
+impl AnyFeed {
+    /// The name of the feed, if it has one.
+    pub fn title(&self) -> Option<&str> {
+        match self {
+            AnyFeed::Rss(rss) => Some(&rss.channel.title),
+            AnyFeed::Atom(feed) => feed.title.as_ref().and_then(|t| t.content.as_deref()),
+        }
+    }
+}
+
+/// Parse an RSS 2.0 or Atom 1.0 feed, telling them apart by their root
+/// element.
+///
+/// Other documents fail with [`DeserializeError::UnknownElement`](facet_xml::DeserializeError::UnknownElement).
+///
+/// # Example
+///
+/// ```
+/// use facet_atom::{AnyFeed, parse_feed};
+///
+/// let rss = r#"<rss version="2.0"><channel>
+///     <title>News</title><link>https://example.com/</link><description>Daily</description>
+///     <item><title>Hello</title></item>
+/// </channel></rss>"#;
+/// let AnyFeed::Rss(feed) = parse_feed(rss).unwrap() else { panic!("not RSS") };
+/// assert_eq!(feed.channel.items[0].title.as_deref(), Some("Hello"));
+///
+/// let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+///     <id>urn:uuid:1</id><title>News</title><updated>2024-01-01T00:00:00Z</updated>
+/// </feed>"#;
+/// assert_eq!(parse_feed(atom).unwrap().title(), Some("News"));
+/// ```
+pub fn parse_feed(input: &str) -> Result<AnyFeed, Error> {
+    let mut parser = facet_xml::XmlParser::new(input.as_bytes());
+    loop {
+        match parser.next_event().map_err(Error::Parser)? {
+            Some(DomEvent::NodeStart { tag, namespace }) => {
+                return match (&*tag, namespace.as_deref()) {
+                    ("rss", None) => facet_xml::from_str(input).map(AnyFeed::Rss),
+                    ("feed", Some(ATOM_NS)) => facet_xml::from_str(input).map(AnyFeed::Atom),
+                    _ => Err(Error::UnknownElement {
+                        tag: tag.into_owned(),
+                    }),
+                };
+            }
+            Some(_) => {}
+            None => {
+                return Err(Error::UnexpectedEof {
+                    expected: "a root element",
+                });
+            }
+        }
+    }
+}
+
 // =============================================================================
 // Container Elements
 // =============================================================================
@@ -0,0 +1,98 @@
+//! RSS 2.0 types.
+//!
+//! The types cover the elements feed readers rely on; anything else in a
+//! feed is ignored. Dates are kept as written (RFC 822), since feeds in the
+//! wild don't always follow it.
+
+use facet::Facet;
+use facet_xml as xml;
+
+/// An RSS 2.0 document: `<rss version="2.0"><channel>...</channel></rss>`.
+#[derive(Facet, Debug, Clone, Default, PartialEq)]
+#[facet(rename = "rss")]
+pub struct Rss {
+    /// The RSS version, `2.0` for the documents this describes.
+    #[facet(xml::attribute, default)]
+    pub version: String,
+    /// The feed itself.
+    pub channel: Channel,
+}
+
+/// The `<channel>` of an RSS feed.
+#[derive(Facet, Debug, Clone, Default, PartialEq)]
+pub struct Channel {
+    /// The name of the feed.
+    pub title: String,
+    /// The URL of the website the feed belongs to.
+    pub link: String,
+    /// What the feed is about.
+    #[facet(default)]
+    pub description: String,
+    /// Language of the feed, e.g. `en-us`.
+    #[facet(default)]
+    pub language: Option<String>,
+    /// Publication date of the content.
+    #[facet(rename = "pubDate", default)]
+    pub pub_date: Option<String>,
+    /// Last time the content changed.
+    #[facet(rename = "lastBuildDate", default)]
+    pub last_build_date: Option<String>,
+    /// The entries of the feed, newest first by convention.
+    #[facet(rename = "item", default)]
+    pub items: Vec<Item>,
+}
+
+/// An `<item>` of an RSS channel. Every field is optional, though an item
+/// has at least a title or a description.
+#[derive(Facet, Debug, Clone, Default, PartialEq)]
+pub struct Item {
+    /// The title of the item.
+    #[facet(default)]
+    pub title: Option<String>,
+    /// The URL of the item.
+    #[facet(default)]
+    pub link: Option<String>,
+    /// The synopsis of the item, often escaped HTML.
+    #[facet(default)]
+    pub description: Option<String>,
+    /// Email address of the author.
+    #[facet(default)]
+    pub author: Option<String>,
+    /// Categories the item belongs to.
+    #[facet(rename = "category", default)]
+    pub categories: Vec<String>,
+    /// A media file attached to the item.
+    #[facet(default)]
+    pub enclosure: Option<Enclosure>,
+    /// An identifier of the item, unique within the feed.
+    #[facet(default)]
+    pub guid: Option<Guid>,
+    /// When the item was published.
+    #[facet(rename = "pubDate", default)]
+    pub pub_date: Option<String>,
+}
+
+/// A media file attached to an RSS [`Item`], such as a podcast episode.
+#[derive(Facet, Debug, Clone, Default, PartialEq)]
+pub struct Enclosure {
+    /// Where the file is.
+    #[facet(xml::attribute)]
+    pub url: String,
+    /// Its size in bytes.
+    #[facet(xml::attribute, default)]
+    pub length: u64,
+    /// Its MIME type.
+    #[facet(xml::attribute, rename = "type", default)]
+    pub mime_type: String,
+}
+
+/// The `<guid>` of an RSS [`Item`].
+#[derive(Facet, Debug, Clone, Default, PartialEq)]
+pub struct Guid {
+    /// Whether the identifier is also the item's URL (`true` when absent).
+    #[facet(xml::attribute, rename = "isPermaLink", default)]
+    pub is_perma_link: Option<bool>,
+    /// The identifier.
+    #[facet(xml::text, default)]
+    pub value: String,
+}
@@ -243,3 +243,81 @@ fn test_roundtrip_simple_feed() {
     assert_eq!(parsed.updated, feed.updated);
     assert_eq!(parsed.authors.len(), feed.authors.len());
 }
+
+#[test]
+fn test_parse_rss_items_and_attributes() {
+    let xml = indoc! {r#"
+            <?xml version="1.0"?>
+            <rss version="2.0">
+              <channel>
+                <title>Podcast</title>
+                <link>https://example.com/</link>
+                <description>Weekly</description>
+                <item>
+                  <title>Episode 1</title>
+                  <category>tech</category>
+                  <category>news</category>
+                  <enclosure url="https://example.com/1.mp3" length="1024" type="audio/mpeg"/>
+                  <guid isPermaLink="false">ep-1</guid>
+                  <pubDate>Mon, 01 Jan 2024 00:00:00 GMT</pubDate>
+                </item>
+              </channel>
+            </rss>
+        "#};
+
+    let AnyFeed::Rss(rss) = parse_feed(xml).unwrap() else {
+        panic!("expected RSS");
+    };
+    assert_eq!(rss.version, "2.0");
+    let item = &rss.channel.items[0];
+    assert_eq!(item.categories, ["tech", "news"]);
+    assert_eq!(
+        item.enclosure,
+        Some(Enclosure {
+            url: "https://example.com/1.mp3".into(),
+            length: 1024,
+            mime_type: "audio/mpeg".into(),
+        })
+    );
+    assert_eq!(
+        item.guid,
+        Some(Guid {
+            is_perma_link: Some(false),
+            value: "ep-1".into(),
+        })
+    );
+
+    let written = to_string(&rss).unwrap();
+    assert_eq!(from_str::<Rss>(&written).unwrap(), rss);
+}
+
+#[test]
+fn test_parse_feed_tells_atom_from_rss() {
+    let xml = indoc! {r#"
+            <feed xmlns="http://www.w3.org/2005/Atom">
+              <id>urn:uuid:feed</id>
+              <title type="text">Blog</title>
+              <updated>2024-01-02T00:00:00Z</updated>
+              <entry>
+                <id>urn:uuid:1</id>
+                <title>First</title>
+                <updated>2024-01-01T00:00:00Z</updated>
+              </entry>
+            </feed>
+        "#};
+
+    let feed = parse_feed(xml).unwrap();
+    assert_eq!(feed.title(), Some("Blog"));
+    let AnyFeed::Atom(atom) = feed else {
+        panic!("expected Atom");
+    };
+    assert_eq!(atom.entries.len(), 1);
+}
+
+#[test]
+fn test_parse_feed_rejects_other_documents() {
+    let err = parse_feed("<rdf:RDF xmlns:rdf=\"urn:x\"/>").unwrap_err();
+    assert!(matches!(err, Error::UnknownElement { tag } if tag == "RDF"));
+    // A `feed` outside the Atom namespace isn't Atom
+    assert!(parse_feed("<feed/>").is_err());
+}
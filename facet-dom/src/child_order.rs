@@ -0,0 +1,129 @@
+//! Document order of a struct's children, kept for `ordered` containers.
+
+use facet_core::{OxPtrConst, OxPtrUninit, ParseError, Shape, VTableIndirect};
+
+/// The order in which an element's children were read.
+///
+/// A struct marked `ordered` (e.g. `#[facet(xml::ordered)]`) records here,
+/// for each child element and piece of text, the Rust field that received
+/// it. Serializing the struct again writes its children in that order
+/// instead of field declaration order, so elements of different fields,
+/// text and captured unknown elements keep their interleaving.
+///
+/// Give the struct one field of this type, with any name; it isn't read
+/// from or written to the document itself. Children that weren't recorded
+/// (fields filled in after reading, entries of flattened maps) are written
+/// after the recorded ones, in declaration order.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Hash)]
+pub struct ChildOrder {
+    /// Field name and, for text gathered into a single string, the byte
+    /// length of this piece of it
+    slots: Vec<(String, Option<usize>)>,
+}
+
+impl ChildOrder {
+    /// The fields that received the children, in document order.
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.slots.iter().map(|(field, _)| field.as_str())
+    }
+
+    /// Number of children recorded.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns true if no children were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Record a child read into `field`; `text_len` is the length of a piece
+    /// of text appended to a single string field.
+    pub(crate) fn push(&mut self, field: &str, text_len: Option<usize>) {
+        self.slots.push((field.to_string(), text_len));
+    }
+
+    /// Field name and text length of each child, in document order.
+    pub(crate) fn slots(&self) -> impl Iterator<Item = (&str, Option<usize>)> {
+        self.slots
+            .iter()
+            .map(|(field, text_len)| (field.as_str(), *text_len))
+    }
+}
+
+/// Written as space-separated field names, with `:length` after text pieces.
+impl std::fmt::Display for ChildOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (field, text_len)) in self.slots.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(field)?;
+            if let Some(len) = text_len {
+                write!(f, ":{len}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for ChildOrder {
+    type Err = core::convert::Infallible;
+
+    /// Parse the [`Display`](std::fmt::Display) form; malformed text lengths
+    /// are dropped.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let slots = s
+            .split_whitespace()
+            .map(|slot| match slot.split_once(':') {
+                Some((field, len)) => (field.to_string(), len.parse().ok()),
+                None => (slot.to_string(), None),
+            })
+            .collect();
+        Ok(Self { slots })
+    }
+}
+
+unsafe fn display_child_order(
+    source: OxPtrConst,
+    f: &mut core::fmt::Formatter<'_>,
+) -> Option<core::fmt::Result> {
+    let order = unsafe { source.get::<ChildOrder>() };
+    Some(write!(f, "{order}"))
+}
+
+unsafe fn parse_child_order(s: &str, target: OxPtrUninit) -> Option<Result<(), ParseError>> {
+    let Ok(order) = s.parse::<ChildOrder>();
+    unsafe { target.put(order) };
+    Some(Ok(()))
+}
+
+const CHILD_ORDER_VTABLE: VTableIndirect = VTableIndirect {
+    display: Some(display_child_order),
+    parse: Some(parse_child_order),
+    ..VTableIndirect::EMPTY
+};
+
+// Facet impl - scalar with vtable for string conversion
+unsafe impl facet_core::Facet<'_> for ChildOrder {
+    const SHAPE: &'static Shape = &const {
+        facet_core::ShapeBuilder::for_sized::<ChildOrder>("ChildOrder")
+            .def(facet_core::Def::Scalar)
+            .vtable_indirect(&CHILD_ORDER_VTABLE)
+            .build()
+    };
+}
+
+/// Check if a shape is the [`ChildOrder`] type.
+pub(crate) fn is_child_order(shape: &Shape) -> bool {
+    shape.type_identifier == "ChildOrder"
+}
+
+/// Whether the container `shape` is marked `ordered` in the format
+/// namespace.
+pub(crate) fn is_ordered(shape: &Shape, format_ns: Option<&str>) -> bool {
+    shape
+        .attributes
+        .iter()
+        .any(|attr| attr.ns.is_some() && attr.ns == format_ns && attr.key == "ordered")
+}
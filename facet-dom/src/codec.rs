@@ -0,0 +1,258 @@
+//! Text encodings for binary fields, selected per field with `codec = "name"`
+//! or `codec_with = &Codec`.
+//!
+//! A field marked `#[facet(xml::codec = "base64")]` (or the equivalent in
+//! another format namespace) holds bytes (a `Vec<u8>` or other list of
+//! bytes, a `[u8; N]`, or an `Option` of either) that are written as text
+//! through the named codec and decoded from text when read. `base64`,
+//! `base64url`, `hex` and `hexBinary` are built in and can't be replaced.
+//!
+//! Other encodings implement [`TextCodec`] and are named directly on the
+//! field with `codec_with = &MyCodec`, or registered once under a new name
+//! with [`register_codec`].
+//!
+//! `binary = "base64"` and `binary = "hex"` select the encodings of the XML
+//! Schema types `xs:base64Binary` and `xs:hexBinary`, written in their
+//! canonical forms.
+
+use std::sync::RwLock;
+
+use facet_core::Field;
+
+/// Converts bytes to text and back.
+pub trait TextCodec: Send + Sync {
+    /// Encode `bytes` as text.
+    fn encode(&self, bytes: &[u8]) -> String;
+
+    /// Decode text produced by [`encode`](Self::encode).
+    ///
+    /// The error message ends up in [`DomDeserializeError::Codec`](crate::DomDeserializeError::Codec).
+    fn decode(&self, text: &str) -> Result<Vec<u8>, String>;
+
+    /// The name errors refer to the codec by when it's given with
+    /// `codec_with`. Defaults to the type name.
+    fn name(&self) -> &'static str {
+        core::any::type_name::<Self>()
+    }
+}
+
+/// A codec given to a field with `codec_with = &MyCodec`.
+pub type CodecRef = &'static dyn TextCodec;
+
+/// Names of the built-in codecs, which can't be registered again.
+const BUILT_IN: &[&str] = &["base64", "base64url", "hex", "hexBinary"];
+
+static REGISTRY: RwLock<Vec<(&'static str, CodecRef)>> = RwLock::new(Vec::new());
+
+/// [`register_codec`] was given a name that is already taken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodecNameTaken {
+    /// The name that was registered again.
+    pub name: &'static str,
+}
+
+impl core::fmt::Display for CodecNameTaken {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "codec {:?} is already registered", self.name)
+    }
+}
+
+impl std::error::Error for CodecNameTaken {}
+
+/// Make `codec` available to fields marked `codec = "name"`.
+///
+/// Each name can be registered once; the built-in names and names
+/// registered before are rejected, so a codec in use never changes under a
+/// field. Prefer `codec_with = &MyCodec` on the field, which needs no
+/// registration at all.
+pub fn register_codec(name: &'static str, codec: CodecRef) -> Result<(), CodecNameTaken> {
+    if BUILT_IN.contains(&name) {
+        return Err(CodecNameTaken { name });
+    }
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    if registry.iter().any(|(n, _)| *n == name) {
+        return Err(CodecNameTaken { name });
+    }
+    registry.push((name, codec));
+    Ok(())
+}
+
+/// The built-in codec `name`, or the one registered under it.
+pub fn lookup_codec(name: &str) -> Option<CodecRef> {
+    match name {
+        "base64" => return Some(&Base64 { url_safe: false }),
+        "base64url" => return Some(&Base64 { url_safe: true }),
+        "hex" => return Some(&Hex { uppercase: false }),
+        "hexBinary" => return Some(&Hex { uppercase: true }),
+        _ => {}
+    }
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, codec)| *codec)
+}
+
+/// The codec a field asks for.
+#[derive(Clone, Copy)]
+pub(crate) struct FieldCodec {
+    /// The name errors report it by.
+    pub name: &'static str,
+    /// The codec, or `None` if no codec has that name.
+    pub codec: Option<CodecRef>,
+}
+
+/// The codec a field asks for in `format_ns`, if any: given directly with
+/// `codec_with`, by name with `codec`, or as an XML Schema type with
+/// `binary`.
+pub(crate) fn field_codec(field: &Field, format_ns: Option<&str>) -> Option<FieldCodec> {
+    if let Some(codec) = field
+        .get_attr(format_ns, "codec_with")
+        .and_then(|attr| attr.get_as::<CodecRef>().copied())
+    {
+        return Some(FieldCodec {
+            name: codec.name(),
+            codec: Some(codec),
+        });
+    }
+    let name = match field
+        .get_attr(format_ns, "codec")
+        .and_then(|attr| attr.get_as::<&str>().copied())
+    {
+        Some(name) => name,
+        None => match field
+            .get_attr(format_ns, "binary")
+            .and_then(|attr| attr.get_as::<&str>().copied())?
+        {
+            "hex" => "hexBinary",
+            other => other,
+        },
+    };
+    Some(FieldCodec {
+        name,
+        codec: lookup_codec(name),
+    })
+}
+
+/// Standard base64 (RFC 4648 §4) with padding, or the URL-safe alphabet
+/// (§5) without padding. Decoding accepts either padding style, as long as
+/// padding only ends the last group, and ignores ASCII whitespace, so
+/// wrapped values read fine.
+struct Base64 {
+    url_safe: bool,
+}
+
+const BASE64_STANDARD: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL_SAFE: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+impl TextCodec for Base64 {
+    fn encode(&self, bytes: &[u8]) -> String {
+        let alphabet = if self.url_safe {
+            BASE64_URL_SAFE
+        } else {
+            BASE64_STANDARD
+        };
+        let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for chunk in bytes.chunks(3) {
+            let n = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |n, (i, &b)| n | ((b as u32) << (16 - 8 * i)));
+            for i in 0..=chunk.len() {
+                out.push(alphabet[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            }
+            if !self.url_safe {
+                for _ in chunk.len()..3 {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    fn decode(&self, text: &str) -> Result<Vec<u8>, String> {
+        let alphabet = if self.url_safe {
+            BASE64_URL_SAFE
+        } else {
+            BASE64_STANDARD
+        };
+        let mut out = Vec::with_capacity(text.len() / 4 * 3);
+        let mut n = 0u32;
+        let mut bits = 0;
+        let mut digits = 0usize;
+        let mut padding = 0usize;
+        for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+            if c == b'=' {
+                padding += 1;
+                continue;
+            }
+            if padding > 0 {
+                return Err("data after padding".into());
+            }
+            let value = alphabet
+                .iter()
+                .position(|&a| a == c)
+                .ok_or_else(|| format!("invalid character {:?}", c as char))?;
+            n = (n << 6) | value as u32;
+            bits += 6;
+            digits += 1;
+            if bits >= 8 {
+                bits -= 8;
+                out.push((n >> bits) as u8);
+            }
+        }
+        if digits % 4 == 1 {
+            return Err("truncated input".into());
+        }
+        // Padding, if present, completes the last group of four exactly
+        if padding > 0 && (digits % 4 == 0 || (digits + padding) % 4 != 0) {
+            return Err("invalid padding".into());
+        }
+        Ok(out)
+    }
+}
+
+/// Hexadecimal, lowercase or in the uppercase canonical form of
+/// `xs:hexBinary`; decoding accepts either case and ignores ASCII
+/// whitespace.
+struct Hex {
+    uppercase: bool,
+}
+
+impl TextCodec for Hex {
+    fn encode(&self, bytes: &[u8]) -> String {
+        let digits: &[u8; 16] = if self.uppercase {
+            b"0123456789ABCDEF"
+        } else {
+            b"0123456789abcdef"
+        };
+        let mut out = String::with_capacity(bytes.len() * 2);
+        for &b in bytes {
+            out.push(digits[(b >> 4) as usize] as char);
+            out.push(digits[(b & 0xf) as usize] as char);
+        }
+        out
+    }
+
+    fn decode(&self, text: &str) -> Result<Vec<u8>, String> {
+        let digits: Vec<u8> = text
+            .bytes()
+            .filter(|c| !c.is_ascii_whitespace())
+            .map(|c| {
+                (c as char)
+                    .to_digit(16)
+                    .map(|d| d as u8)
+                    .ok_or_else(|| format!("invalid character {:?}", c as char))
+            })
+            .collect::<Result<_, _>>()?;
+        if digits.len() % 2 == 1 {
+            return Err("odd number of digits".into());
+        }
+        Ok(digits
+            .chunks(2)
+            .map(|pair| (pair[0] << 4) | pair[1])
+            .collect())
+    }
+}
@@ -0,0 +1,503 @@
+//! Comparing the DOM mappings of two versions of a type.
+//!
+//! [`diff_shapes`] walks the [descriptions](crate::describe) of an old and a
+//! new type side by side and lists every difference on the wire, each
+//! classified as compatible or breaking. A change is breaking when a
+//! document written with the old type may no longer be read by the new one.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use facet_core::{Shape, Type, UserType};
+
+use crate::describe::{
+    AttributeDescription, Cardinality, ChildDescription, ShapeDescription, describe_shape,
+};
+
+/// One difference between two mappings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappingChange {
+    /// Slash-separated element names from the root to the element that
+    /// changed, as the old type names them.
+    pub path: String,
+    /// What changed.
+    pub kind: ChangeKind,
+}
+
+/// The kinds of difference [`diff_shapes`] reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// The root element has a different name.
+    ElementRenamed {
+        /// The name in the old type.
+        old: String,
+        /// The name in the new type.
+        new: String,
+    },
+    /// The default namespace of the element's children changed.
+    NamespaceChanged {
+        /// The namespace in the old type.
+        old: Option<&'static str>,
+        /// The namespace in the new type.
+        new: Option<&'static str>,
+    },
+    /// A new attribute; breaking if it is required.
+    AttributeAdded {
+        /// The name in the new type.
+        name: String,
+        /// Whether the attribute must be present in the new type.
+        required: bool,
+    },
+    /// An attribute is no longer read or written.
+    AttributeRemoved {
+        /// The name in the old type.
+        name: String,
+    },
+    /// The same field uses a different attribute name. Breaking unless the
+    /// old name is kept as an alias.
+    AttributeRenamed {
+        /// The name in the old type.
+        old: String,
+        /// The name in the new type.
+        new: String,
+        /// Whether the new type still reads the old name.
+        old_name_accepted: bool,
+    },
+    /// An attribute moved to a different namespace.
+    AttributeNamespaceChanged {
+        /// The name in the new type.
+        name: String,
+        /// The namespace in the old type.
+        old: Option<&'static str>,
+        /// The namespace in the new type.
+        new: Option<&'static str>,
+    },
+    /// An attribute became required or optional; breaking if required.
+    AttributeRequirementChanged {
+        /// The name in the new type.
+        name: String,
+        /// Whether the attribute must be present in the new type.
+        required: bool,
+    },
+    /// A new child element; breaking if it must appear.
+    ChildAdded {
+        /// The name in the new type.
+        name: String,
+        /// How often the element may appear.
+        cardinality: Cardinality,
+    },
+    /// A child element is no longer read or written.
+    ChildRemoved {
+        /// The name in the old type.
+        name: String,
+    },
+    /// The same field uses a different element name. Breaking unless the
+    /// old name is kept as an alias.
+    ChildRenamed {
+        /// The name in the old type.
+        old: String,
+        /// The name in the new type.
+        new: String,
+        /// Whether the new type still reads the old name.
+        old_name_accepted: bool,
+    },
+    /// A child element moved to a different namespace.
+    ChildNamespaceChanged {
+        /// The name in the new type.
+        name: String,
+        /// The namespace in the old type.
+        old: Option<&'static str>,
+        /// The namespace in the new type.
+        new: Option<&'static str>,
+    },
+    /// A child element may appear a different number of times; breaking if
+    /// the new cardinality doesn't admit everything the old one did.
+    CardinalityChanged {
+        /// The name in the new type.
+        name: String,
+        /// The cardinality in the old type.
+        old: Cardinality,
+        /// The cardinality in the new type.
+        new: Cardinality,
+    },
+    /// Unknown attributes were captured and no longer are, or the reverse.
+    OtherAttributesChanged {
+        /// Whether the new type captures them.
+        captured: bool,
+    },
+    /// Unknown child elements were captured and no longer are, or the reverse.
+    OtherElementsChanged {
+        /// Whether the new type captures them.
+        captured: bool,
+    },
+}
+
+impl Cardinality {
+    /// Whether every count allowed by `other` is allowed by `self`.
+    pub fn admits(self, other: Cardinality) -> bool {
+        matches!(
+            (self, other),
+            (Cardinality::Many, _)
+                | (
+                    Cardinality::Optional,
+                    Cardinality::Optional | Cardinality::One
+                )
+                | (Cardinality::One, Cardinality::One)
+        )
+    }
+}
+
+impl MappingChange {
+    /// Whether documents written with the old type may fail to read, or
+    /// read differently, with the new one.
+    pub fn is_breaking(&self) -> bool {
+        match &self.kind {
+            ChangeKind::ElementRenamed { .. }
+            | ChangeKind::NamespaceChanged { .. }
+            | ChangeKind::AttributeNamespaceChanged { .. }
+            | ChangeKind::ChildNamespaceChanged { .. } => true,
+            ChangeKind::AttributeAdded { required, .. } => *required,
+            ChangeKind::AttributeRequirementChanged { required, .. } => *required,
+            ChangeKind::AttributeRemoved { .. } | ChangeKind::ChildRemoved { .. } => false,
+            ChangeKind::AttributeRenamed {
+                old_name_accepted, ..
+            }
+            | ChangeKind::ChildRenamed {
+                old_name_accepted, ..
+            } => !old_name_accepted,
+            ChangeKind::ChildAdded { cardinality, .. } => *cardinality == Cardinality::One,
+            ChangeKind::CardinalityChanged { old, new, .. } => !new.admits(*old),
+            // Captured content ends up somewhere else, but still reads
+            ChangeKind::OtherAttributesChanged { .. } | ChangeKind::OtherElementsChanged { .. } => {
+                false
+            }
+        }
+    }
+}
+
+impl fmt::Display for MappingChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = if self.is_breaking() {
+            "breaking"
+        } else {
+            "compatible"
+        };
+        write!(f, "{}: ", self.path)?;
+        match &self.kind {
+            ChangeKind::ElementRenamed { old, new } => {
+                write!(f, "element renamed from `{old}` to `{new}`")?
+            }
+            ChangeKind::NamespaceChanged { old, new } => {
+                write!(f, "namespace changed from {old:?} to {new:?}")?
+            }
+            ChangeKind::AttributeAdded { name, required } => {
+                let required = if *required { "required" } else { "optional" };
+                write!(f, "{required} attribute `{name}` added")?
+            }
+            ChangeKind::AttributeRemoved { name } => write!(f, "attribute `{name}` removed")?,
+            ChangeKind::AttributeRenamed { old, new, .. } => {
+                write!(f, "attribute `{old}` renamed to `{new}`")?
+            }
+            ChangeKind::AttributeNamespaceChanged { name, old, new } => write!(
+                f,
+                "attribute `{name}` namespace changed from {old:?} to {new:?}"
+            )?,
+            ChangeKind::AttributeRequirementChanged { name, required } => {
+                let required = if *required { "required" } else { "optional" };
+                write!(f, "attribute `{name}` is now {required}")?
+            }
+            ChangeKind::ChildAdded { name, cardinality } => {
+                write!(f, "child `{name}` added ({cardinality:?})")?
+            }
+            ChangeKind::ChildRemoved { name } => write!(f, "child `{name}` removed")?,
+            ChangeKind::ChildRenamed { old, new, .. } => {
+                write!(f, "child `{old}` renamed to `{new}`")?
+            }
+            ChangeKind::ChildNamespaceChanged { name, old, new } => write!(
+                f,
+                "child `{name}` namespace changed from {old:?} to {new:?}"
+            )?,
+            ChangeKind::CardinalityChanged { name, old, new } => {
+                write!(f, "child `{name}` changed from {old:?} to {new:?}")?
+            }
+            ChangeKind::OtherAttributesChanged { captured } => {
+                let verb = if *captured { "now" } else { "no longer" };
+                write!(f, "unknown attributes are {verb} captured")?
+            }
+            ChangeKind::OtherElementsChanged { captured } => {
+                let verb = if *captured { "now" } else { "no longer" };
+                write!(f, "unknown elements are {verb} captured")?
+            }
+        }
+        write!(f, " ({severity})")
+    }
+}
+
+/// Every difference between two mappings, in document order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContractDiff {
+    /// The differences found.
+    pub changes: Vec<MappingChange>,
+}
+
+impl ContractDiff {
+    /// Whether every change is compatible (including when there are none).
+    pub fn is_compatible(&self) -> bool {
+        self.changes.iter().all(|change| !change.is_breaking())
+    }
+
+    /// The breaking changes.
+    pub fn breaking(&self) -> impl Iterator<Item = &MappingChange> {
+        self.changes.iter().filter(|change| change.is_breaking())
+    }
+}
+
+impl fmt::Display for ContractDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare how `old` and `new` map onto elements in the format whose
+/// attributes live in `format_ns`, descending into child elements that
+/// both versions describe as structs.
+pub fn diff_shapes(
+    old: &'static Shape,
+    new: &'static Shape,
+    format_ns: Option<&'static str>,
+) -> ContractDiff {
+    let mut differ = Differ {
+        format_ns,
+        visited: HashSet::new(),
+        changes: Vec::new(),
+    };
+    let old_description = describe_shape(old, format_ns);
+    let new_description = describe_shape(new, format_ns);
+    let path = old_description.element.to_string();
+    if old_description.element != new_description.element {
+        differ.push(
+            &path,
+            ChangeKind::ElementRenamed {
+                old: old_description.element.to_string(),
+                new: new_description.element.to_string(),
+            },
+        );
+    }
+    differ.visited.insert(pair_key(old, new));
+    differ.diff(&path, &old_description, &new_description);
+    ContractDiff {
+        changes: differ.changes,
+    }
+}
+
+struct Differ {
+    format_ns: Option<&'static str>,
+    /// Shape pairs already compared, so recursive types terminate
+    visited: HashSet<(usize, usize)>,
+    changes: Vec<MappingChange>,
+}
+
+impl Differ {
+    fn push(&mut self, path: &str, kind: ChangeKind) {
+        self.changes.push(MappingChange {
+            path: path.to_string(),
+            kind,
+        });
+    }
+
+    fn diff(&mut self, path: &str, old: &ShapeDescription, new: &ShapeDescription) {
+        if old.namespace != new.namespace {
+            self.push(
+                path,
+                ChangeKind::NamespaceChanged {
+                    old: old.namespace,
+                    new: new.namespace,
+                },
+            );
+        }
+        self.diff_attributes(path, &old.attributes, &new.attributes);
+        if old.other_attributes != new.other_attributes {
+            self.push(
+                path,
+                ChangeKind::OtherAttributesChanged {
+                    captured: new.other_attributes,
+                },
+            );
+        }
+        self.diff_children(path, &old.children, &new.children);
+        if old.other_elements != new.other_elements {
+            self.push(
+                path,
+                ChangeKind::OtherElementsChanged {
+                    captured: new.other_elements,
+                },
+            );
+        }
+    }
+
+    fn diff_attributes(
+        &mut self,
+        path: &str,
+        old: &[AttributeDescription],
+        new: &[AttributeDescription],
+    ) {
+        let mut matched = vec![false; new.len()];
+        for old_attr in old {
+            let found = new
+                .iter()
+                .position(|attr| attr.name == old_attr.name)
+                .or_else(|| new.iter().position(|attr| attr.field == old_attr.field))
+                .or_else(|| {
+                    new.iter()
+                        .position(|attr| attr.aliases.contains(&old_attr.name))
+                });
+            let Some(i) = found else {
+                self.push(
+                    path,
+                    ChangeKind::AttributeRemoved {
+                        name: old_attr.name.clone(),
+                    },
+                );
+                continue;
+            };
+            matched[i] = true;
+            let new_attr = &new[i];
+            if new_attr.name != old_attr.name {
+                self.push(
+                    path,
+                    ChangeKind::AttributeRenamed {
+                        old: old_attr.name.clone(),
+                        new: new_attr.name.clone(),
+                        old_name_accepted: new_attr.aliases.contains(&old_attr.name),
+                    },
+                );
+            }
+            if new_attr.namespace != old_attr.namespace {
+                self.push(
+                    path,
+                    ChangeKind::AttributeNamespaceChanged {
+                        name: new_attr.name.clone(),
+                        old: old_attr.namespace,
+                        new: new_attr.namespace,
+                    },
+                );
+            }
+            if new_attr.required != old_attr.required {
+                self.push(
+                    path,
+                    ChangeKind::AttributeRequirementChanged {
+                        name: new_attr.name.clone(),
+                        required: new_attr.required,
+                    },
+                );
+            }
+        }
+        for (new_attr, _) in new.iter().zip(matched).filter(|(_, matched)| !matched) {
+            self.push(
+                path,
+                ChangeKind::AttributeAdded {
+                    name: new_attr.name.clone(),
+                    required: new_attr.required,
+                },
+            );
+        }
+    }
+
+    fn diff_children(&mut self, path: &str, old: &[ChildDescription], new: &[ChildDescription]) {
+        let mut matched = vec![false; new.len()];
+        for old_child in old {
+            let found = new
+                .iter()
+                .position(|child| child.name == old_child.name)
+                .or_else(|| {
+                    // A field matching several names (enum variants) is
+                    // only paired by name
+                    let same_field = |child: &&ChildDescription| child.field == old_child.field;
+                    match (
+                        old.iter().filter(|c| c.field == old_child.field).count(),
+                        new.iter().filter(same_field).count(),
+                    ) {
+                        (1, 1) => new.iter().position(|child| child.field == old_child.field),
+                        _ => None,
+                    }
+                })
+                .or_else(|| {
+                    new.iter()
+                        .position(|child| child.aliases.contains(&old_child.name))
+                });
+            let Some(i) = found else {
+                self.push(
+                    path,
+                    ChangeKind::ChildRemoved {
+                        name: old_child.name.clone(),
+                    },
+                );
+                continue;
+            };
+            matched[i] = true;
+            let new_child = &new[i];
+            if new_child.name != old_child.name {
+                self.push(
+                    path,
+                    ChangeKind::ChildRenamed {
+                        old: old_child.name.clone(),
+                        new: new_child.name.clone(),
+                        old_name_accepted: new_child.aliases.contains(&old_child.name),
+                    },
+                );
+            }
+            if new_child.namespace != old_child.namespace {
+                self.push(
+                    path,
+                    ChangeKind::ChildNamespaceChanged {
+                        name: new_child.name.clone(),
+                        old: old_child.namespace,
+                        new: new_child.namespace,
+                    },
+                );
+            }
+            if new_child.cardinality != old_child.cardinality {
+                self.push(
+                    path,
+                    ChangeKind::CardinalityChanged {
+                        name: new_child.name.clone(),
+                        old: old_child.cardinality,
+                        new: new_child.cardinality,
+                    },
+                );
+            }
+            if is_struct(old_child.shape)
+                && is_struct(new_child.shape)
+                && self
+                    .visited
+                    .insert(pair_key(old_child.shape, new_child.shape))
+            {
+                let child_path = format!("{path}/{}", old_child.name);
+                let old_description = describe_shape(old_child.shape, self.format_ns);
+                let new_description = describe_shape(new_child.shape, self.format_ns);
+                self.diff(&child_path, &old_description, &new_description);
+            }
+        }
+        for (new_child, _) in new.iter().zip(matched).filter(|(_, matched)| !matched) {
+            self.push(
+                path,
+                ChangeKind::ChildAdded {
+                    name: new_child.name.clone(),
+                    cardinality: new_child.cardinality,
+                },
+            );
+        }
+    }
+}
+
+fn is_struct(shape: &Shape) -> bool {
+    matches!(shape.ty, Type::User(UserType::Struct(_)))
+}
+
+/// Identity of a pair of shapes, by address.
+fn pair_key(old: &'static Shape, new: &'static Shape) -> (usize, usize) {
+    (old as *const Shape as usize, new as *const Shape as usize)
+}
@@ -0,0 +1,254 @@
+//! Describing how a type maps onto a DOM document.
+//!
+//! [`describe_shape`] reports the element name, attributes and child
+//! elements a struct reads and writes, from the same field map the
+//! deserializer matches names against. Docs can render it and contract
+//! tests can assert the wire format without writing sample documents.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use facet_core::{Def, Shape, Type, UserType};
+
+use crate::deserializer::field_map::{FieldInfo, StructFieldMap, field_dom_key, get_item_shape};
+use crate::naming::{shape_element_name, to_element_name};
+
+/// How many times a child element (or attribute) may appear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cardinality {
+    /// Exactly once.
+    One,
+    /// At most once (an `Option` field).
+    Optional,
+    /// Any number of times (a list, set or array field).
+    Many,
+}
+
+/// An attribute a struct reads and writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeDescription {
+    /// The attribute name written on output.
+    pub name: String,
+    /// Other names accepted on input.
+    pub aliases: Vec<String>,
+    /// The Rust field holding the value.
+    pub field: &'static str,
+    /// The namespace URI the attribute must be in, if any.
+    pub namespace: Option<&'static str>,
+    /// Whether the attribute must be present.
+    pub required: bool,
+}
+
+/// A child element a struct reads and writes.
+#[derive(Debug, Clone)]
+pub struct ChildDescription {
+    /// The element name, or `"*"` for a field taking elements of any name.
+    pub name: String,
+    /// Other names accepted on input (singular forms of list fields, aliases).
+    pub aliases: Vec<String>,
+    /// The Rust field holding the element.
+    pub field: &'static str,
+    /// The namespace URI the element must be in, if any.
+    pub namespace: Option<&'static str>,
+    /// How many times the element may appear.
+    pub cardinality: Cardinality,
+    /// The type each occurrence deserializes into, for describing it in turn.
+    pub shape: &'static Shape,
+}
+
+/// How a type maps onto an element.
+#[derive(Debug, Clone)]
+pub struct ShapeDescription {
+    /// The element name the type uses on its own.
+    pub element: Cow<'static, str>,
+    /// The default namespace of the type's child elements (`ns_all`).
+    pub namespace: Option<&'static str>,
+    /// Named attributes, in field order.
+    pub attributes: Vec<AttributeDescription>,
+    /// Named child elements, in field order.
+    pub children: Vec<ChildDescription>,
+    /// The field holding the element's text content, if any.
+    pub text: Option<&'static str>,
+    /// The field holding the element's tag name, if any.
+    pub tag: Option<&'static str>,
+    /// Whether unknown attributes are captured rather than ignored.
+    pub other_attributes: bool,
+    /// Whether unknown child elements are captured rather than ignored.
+    pub other_elements: bool,
+}
+
+impl ShapeDescription {
+    /// The attribute named `name` (or accepting it as an alias).
+    pub fn attribute(&self, name: &str) -> Option<&AttributeDescription> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.name == name || attr.aliases.iter().any(|a| a == name))
+    }
+
+    /// The child element named `name` (or accepting it as an alias).
+    pub fn child(&self, name: &str) -> Option<&ChildDescription> {
+        self.children
+            .iter()
+            .find(|child| child.name == name || child.aliases.iter().any(|a| a == name))
+    }
+}
+
+/// Describe how `shape` maps onto an element when (de)serialized by the
+/// format whose attributes live in `format_ns` (e.g. `"xml"`).
+///
+/// Types other than structs are described by their element name only.
+pub fn describe_shape(shape: &'static Shape, format_ns: Option<&'static str>) -> ShapeDescription {
+    let namespace = shape
+        .attributes
+        .iter()
+        .find(|attr| attr.ns == Some("xml") && attr.key == "ns_all")
+        .and_then(|attr| attr.get_as::<&str>().copied());
+    let mut description = ShapeDescription {
+        element: shape_element_name(shape),
+        namespace,
+        attributes: Vec::new(),
+        children: Vec::new(),
+        text: None,
+        tag: None,
+        other_attributes: false,
+        other_elements: false,
+    };
+    let Type::User(UserType::Struct(struct_def)) = &shape.ty else {
+        return description;
+    };
+    let map = StructFieldMap::new(struct_def, namespace, None, format_ns, None);
+
+    for names in group_by_field(map.attribute_names()).into_values() {
+        let info = names.info;
+        let cardinality = cardinality(info);
+        let (name, aliases) = names.split();
+        description.attributes.push(AttributeDescription {
+            name,
+            aliases,
+            field: info.field.name,
+            namespace: info.namespace,
+            required: cardinality == Cardinality::One,
+        });
+    }
+
+    // Children keyed by (field position, name) so they come out in field order
+    let mut children: BTreeMap<((usize, Option<usize>), String), ChildDescription> =
+        BTreeMap::new();
+    for (position, names) in group_by_field(map.element_names()) {
+        let info = names.info;
+        let (name, aliases) = names.split();
+        children.insert((position, name.clone()), child(name, aliases, info));
+    }
+    for (name, info) in map.elements_fields.iter() {
+        children.insert(
+            ((info.idx, None), name.to_string()),
+            child(name.to_string(), Vec::new(), info),
+        );
+    }
+    if let Some(info) = &map.catch_all_elements_field {
+        children.insert(
+            ((info.idx, None), "*".to_string()),
+            child("*".to_string(), Vec::new(), info),
+        );
+    }
+    if let Some(flattened) = &map.flattened_enum {
+        let info = &flattened.field_info;
+        let enum_shape = match &info.field.shape().def {
+            Def::Option(option_def) => option_def.t(),
+            _ => info.field.shape(),
+        };
+        if let Type::User(UserType::Enum(enum_def)) = &enum_shape.ty {
+            for variant in enum_def.variants.iter() {
+                let name = if variant.rename.is_some() {
+                    Cow::Borrowed(variant.effective_name())
+                } else {
+                    to_element_name(variant.name)
+                };
+                let mut variant_child = child(name.to_string(), Vec::new(), info);
+                if variant_child.cardinality == Cardinality::One {
+                    // Only one of the variants is present
+                    variant_child.cardinality = Cardinality::Optional;
+                }
+                children.insert(((info.idx, None), name.to_string()), variant_child);
+            }
+        }
+    }
+    description.children = children.into_values().collect();
+
+    description.text = map.text_field.as_ref().map(|info| info.field.name);
+    description.tag = map.tag_field.as_ref().map(|info| info.field.name);
+    description.other_attributes = map.attributes_field.is_some()
+        || !map.flattened_attr_maps.is_empty()
+        || !map.nested_flattened_attr_maps.is_empty();
+    description.other_elements = !map.flattened_maps.is_empty();
+    description
+}
+
+/// The names one field is registered under.
+struct FieldNames<'a> {
+    info: &'a FieldInfo,
+    names: Vec<&'a str>,
+}
+
+impl FieldNames<'_> {
+    /// Split into the name written on output and the other accepted names.
+    fn split(self) -> (String, Vec<String>) {
+        let primary = field_dom_key(self.info.field.name, self.info.field.rename, None);
+        let mut names: Vec<String> = self.names.into_iter().map(str::to_string).collect();
+        let primary = match names.iter().position(|name| *name == *primary) {
+            Some(i) => names.remove(i),
+            None => names.remove(0),
+        };
+        (primary, names)
+    }
+}
+
+/// Group `(name, flattened parent, field)` entries by field, in field order.
+fn group_by_field<'a>(
+    entries: impl Iterator<Item = (&'a str, Option<usize>, &'a FieldInfo)>,
+) -> BTreeMap<(usize, Option<usize>), FieldNames<'a>> {
+    let mut groups: BTreeMap<(usize, Option<usize>), FieldNames<'a>> = BTreeMap::new();
+    for (name, parent, info) in entries {
+        let position = match parent {
+            Some(parent) => (parent, Some(info.idx)),
+            None => (info.idx, None),
+        };
+        groups
+            .entry(position)
+            .or_insert_with(|| FieldNames {
+                info,
+                names: Vec::new(),
+            })
+            .names
+            .push(name);
+    }
+    groups
+}
+
+fn cardinality(info: &FieldInfo) -> Cardinality {
+    if info.is_list || info.is_set || info.is_array {
+        Cardinality::Many
+    } else if matches!(info.field.shape().def, Def::Option(_)) {
+        Cardinality::Optional
+    } else {
+        Cardinality::One
+    }
+}
+
+fn child(name: String, aliases: Vec<String>, info: &FieldInfo) -> ChildDescription {
+    let cardinality = cardinality(info);
+    let field_shape = info.field.shape();
+    let shape = match (cardinality, &field_shape.def) {
+        (Cardinality::Many, _) => get_item_shape(field_shape).unwrap_or(field_shape),
+        (Cardinality::Optional, Def::Option(option_def)) => option_def.t(),
+        _ => field_shape,
+    };
+    ChildDescription {
+        name,
+        aliases,
+        field: info.field.name,
+        namespace: info.namespace,
+        cardinality,
+        shape,
+    }
+}
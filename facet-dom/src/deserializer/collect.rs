@@ -0,0 +1,212 @@
+//! Collecting recoverable errors instead of failing on the first.
+//!
+//! When a deserializer [collects errors](DomDeserializer::collect_errors),
+//! unknown elements and attributes of `deny_unknown_fields` types are
+//! skipped, and scalar values that don't parse leave their field (or list
+//! item) out. Each is recorded and deserialization carries on; only errors
+//! that leave nothing to continue with, such as a missing required field,
+//! still fail.
+
+use std::borrow::Cow;
+
+use facet_core::{Def, Field, Shape};
+use facet_reflect::Partial;
+
+use super::recovery::{Collection, ItemEvents, ItemReplay, begin_item};
+use super::{DomDeserializer, ValueSite, error_text, parse_bool};
+use crate::DomEvent;
+use crate::DomParser;
+use crate::error::{DomDeserializeError, UnknownItem};
+
+impl<'de, const BORROW: bool, P> DomDeserializer<'de, BORROW, P>
+where
+    P: DomParser<'de>,
+{
+    /// Record `error` to report at the end when collecting errors, or
+    /// return it otherwise.
+    pub(crate) fn collect(
+        &mut self,
+        error: DomDeserializeError<P::Error>,
+    ) -> Result<(), DomDeserializeError<P::Error>> {
+        if self.collected.is_none() {
+            return Err(error);
+        }
+        let error = self.locate(error);
+        self.collected.as_mut().expect("checked above").push(error);
+        Ok(())
+    }
+
+    /// Record the unknown element or attribute at `site` when collecting
+    /// unknown fields, or handle `error` as [`collect`](Self::collect) does
+    /// otherwise.
+    pub(crate) fn unknown_field(
+        &mut self,
+        site: ValueSite<'_>,
+        error: DomDeserializeError<P::Error>,
+    ) -> Result<(), DomDeserializeError<P::Error>> {
+        if self.unknown.is_none() {
+            return self.collect(error);
+        }
+        let (name, attribute) = match site {
+            ValueSite::Element(name) => (name, false),
+            ValueSite::Attribute(name) => (name, true),
+            ValueSite::Text => ("", false),
+        };
+        let item = UnknownItem {
+            path: self.value_path(site),
+            name: name.to_string(),
+            attribute,
+        };
+        self.unknown.as_mut().expect("checked above").push(item);
+        Ok(())
+    }
+
+    /// The scalar shape values of `field` are checked against before they
+    /// are set, or `None` if they aren't checked.
+    ///
+    /// Only plain scalars (or optional ones) are checked, and only while
+    /// collecting errors: values going through a proxy, a codec, a
+    /// `deserialize_with` function, a `bool_style` or the parser's
+    /// interceptor are set as usual.
+    pub(crate) fn checked_shape(&self, field: &Field) -> Option<&'static Shape> {
+        self.checked_item_shape(field.shape()).filter(|_| {
+            let format_ns = self.parser.format_namespace();
+            field.effective_proxy(format_ns).is_none()
+                && crate::codec::field_codec(field, format_ns).is_none()
+                && crate::with_fn::field_deserialize_with(field, format_ns).is_none()
+                && field.get_attr(format_ns, "bool_style").is_none()
+        })
+    }
+
+    /// [`checked_shape`](Self::checked_shape) for a list or set item of
+    /// `shape`.
+    pub(crate) fn checked_item_shape(&self, shape: &'static Shape) -> Option<&'static Shape> {
+        if self.collected.is_none() || self.parser.intercepts_values() {
+            return None;
+        }
+        let shape = match &shape.def {
+            Def::Option(option_def) => option_def.t(),
+            _ => shape,
+        };
+        let format_ns = self.parser.format_namespace();
+        (matches!(shape.def, Def::Scalar) && shape.effective_proxy(format_ns).is_none())
+            .then_some(shape)
+    }
+
+    /// Check that `text` parses as `shape`, collecting the error if it
+    /// doesn't. Returns whether it parsed.
+    pub(crate) fn check_value(
+        &mut self,
+        shape: &'static Shape,
+        text: &str,
+        site: ValueSite<'_>,
+    ) -> Result<bool, DomDeserializeError<P::Error>> {
+        if shape.id == <bool as facet_core::Facet>::SHAPE.id && parse_bool(text, false).is_some() {
+            return Ok(true);
+        }
+        let outcome = Partial::alloc_shape(shape)
+            .map_err(DomDeserializeError::from)
+            .and_then(|scratch| {
+                facet_dessert::set_string_value(scratch, Cow::Borrowed(text), None)?;
+                Ok(())
+            });
+        match outcome {
+            Ok(()) => Ok(true),
+            Err(error) => {
+                let error = self.invalid_value(error, site, Some(&error_text(text)));
+                self.collect(error)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Deserialize the scalar element at the cursor into field `idx` of
+    /// `wip`, checking its text against `shape` first.
+    ///
+    /// When the text doesn't parse, the error is collected, the element
+    /// skipped and the field left unset.
+    pub(crate) fn deserialize_checked_field(
+        &mut self,
+        wip: Partial<'de, BORROW>,
+        idx: usize,
+        shape: &'static Shape,
+        expected_name: Cow<'static, str>,
+    ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
+        if self.is_nil() {
+            // Nothing to check: an optional field reads it as `None`
+            let wip = wip.begin_nth_field(idx)?;
+            return Ok(self
+                .deserialize_into_named(wip, Some(expected_name))?
+                .end()?);
+        }
+        let (tag, events) = self.buffer_item()?;
+        if !self.check_value(shape, &element_text(&events), ValueSite::Element(&tag))? {
+            return Ok(wip);
+        }
+        let wip = wip.begin_nth_field(idx)?;
+        Ok(self.replay(events, wip, Some(expected_name))?.end()?)
+    }
+
+    /// Add the scalar element at the cursor as an item of the list or set
+    /// `wip` is building, checking its text against `shape` first.
+    ///
+    /// When the text doesn't parse, the error is collected and the element
+    /// skipped, leaving the item out.
+    pub(crate) fn deserialize_checked_item(
+        &mut self,
+        wip: Partial<'de, BORROW>,
+        shape: &'static Shape,
+        collection: Collection,
+        expected_name: Option<Cow<'static, str>>,
+    ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
+        if self.is_nil() {
+            let wip = begin_item(wip, collection)?;
+            return Ok(self.deserialize_into_named(wip, expected_name)?.end()?);
+        }
+        let (tag, events) = self.buffer_item()?;
+        if !self.check_value(shape, &element_text(&events), ValueSite::Element(&tag))? {
+            return Ok(wip);
+        }
+        let wip = begin_item(wip, collection)?;
+        Ok(self.replay(events, wip, expected_name)?.end()?)
+    }
+
+    /// Deserialize buffered `events` into `wip`.
+    fn replay(
+        &mut self,
+        events: ItemEvents<'de>,
+        wip: Partial<'de, BORROW>,
+        expected_name: Option<Cow<'static, str>>,
+    ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
+        let source: &mut dyn DomParser<'de, Error = P::Error> = &mut self.parser;
+        let mut replay = DomDeserializer::<'de, BORROW, _> {
+            parser: ItemReplay::new(events, source, None),
+            path: std::mem::take(&mut self.path),
+            items: std::mem::take(&mut self.items),
+            collected: self.collected.take(),
+            unknown: self.unknown.take(),
+            _marker: std::marker::PhantomData,
+        };
+        let result = replay.deserialize_into_named(wip, expected_name);
+        self.path = replay.path;
+        self.items = replay.items;
+        self.collected = replay.collected;
+        self.unknown = replay.unknown;
+        result
+    }
+}
+
+/// The text directly inside a buffered element.
+fn element_text(events: &ItemEvents<'_>) -> String {
+    let mut text = String::new();
+    let mut depth = 0usize;
+    for (event, _) in events {
+        match event {
+            DomEvent::NodeStart { .. } => depth += 1,
+            DomEvent::NodeEnd => depth -= 1,
+            DomEvent::Text(chunk) if depth == 1 => text.push_str(chunk),
+            _ => {}
+        }
+    }
+    text
+}
@@ -18,6 +18,10 @@ where
     pub fn new(parser: P) -> Self {
         Self {
             parser,
+            path: Vec::new(),
+            items: Default::default(),
+            collected: None,
+            unknown: None,
             _marker: std::marker::PhantomData,
         }
     }
@@ -31,11 +35,72 @@ where
     pub fn new_owned(parser: P) -> Self {
         Self {
             parser,
+            path: Vec::new(),
+            items: Default::default(),
+            collected: None,
+            unknown: None,
             _marker: std::marker::PhantomData,
         }
     }
 }
 
+impl<'de, const BORROW: bool, P> DomDeserializer<'de, BORROW, P>
+where
+    P: DomParser<'de>,
+{
+    /// Carry on after recoverable errors instead of failing on the first.
+    ///
+    /// Unknown elements and attributes of `deny_unknown_fields` types are
+    /// skipped, and plain scalar values that don't parse leave their field
+    /// unset (so it takes its default) or their item out of its list. The
+    /// errors are kept for [`take_errors`](Self::take_errors). Errors that
+    /// leave nothing to continue with, like a missing required field, still
+    /// fail deserialization.
+    pub fn collect_errors(mut self) -> Self {
+        self.collected = Some(Vec::new());
+        self
+    }
+
+    /// Gather every unknown element and attribute of `deny_unknown_fields`
+    /// types instead of failing on the first.
+    ///
+    /// Unknown items are skipped as they are met, and `deserialize` then
+    /// fails with [`DomDeserializeError::UnknownFields`] listing them all,
+    /// in document order. When there are any, they are reported in place of
+    /// whatever error deserialization ended with, since that is usually a
+    /// consequence (a required field missing because its element was
+    /// renamed).
+    pub fn collect_unknown_fields(mut self) -> Self {
+        self.unknown = Some(Vec::new());
+        self
+    }
+
+    /// Fail with the unknown fields collected, if there are any; otherwise
+    /// pass `result` on.
+    fn report_unknown_fields<T>(
+        &mut self,
+        result: Result<T, DomDeserializeError<P::Error>>,
+    ) -> Result<T, DomDeserializeError<P::Error>> {
+        match self.unknown.as_mut() {
+            Some(unknown) if !unknown.is_empty() => {
+                Err(DomDeserializeError::UnknownFields(std::mem::take(unknown)))
+            }
+            _ => result,
+        }
+    }
+
+    /// The errors collected so far, oldest first.
+    ///
+    /// Always empty unless [`collect_errors`](Self::collect_errors) was
+    /// called.
+    pub fn take_errors(&mut self) -> Vec<DomDeserializeError<P::Error>> {
+        self.collected
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+}
+
 impl<'de, P> DomDeserializer<'de, true, P>
 where
     P: DomParser<'de>,
@@ -46,7 +111,8 @@ where
         T: Facet<'de>,
     {
         let wip: Partial<'de, true> = Partial::alloc::<T>()?;
-        let partial = self.deserialize_into(wip)?;
+        let partial = self.deserialize_into(wip);
+        let partial = self.report_unknown_fields(partial)?;
         let heap_value: HeapValue<'de, true> = partial.build()?;
         Ok(heap_value.materialize::<T>()?)
     }
@@ -71,7 +137,8 @@ where
                 Partial::alloc_owned::<T>()?,
             )
         };
-        let partial = self.deserialize_into(wip)?;
+        let partial = self.deserialize_into(wip);
+        let partial = self.report_unknown_fields(partial)?;
         // SAFETY: Same reasoning - with BORROW=false, HeapValue contains only
         // owned data. The 'de lifetime is phantom and we can safely transmute
         // back to 'static since T: Facet<'static>.
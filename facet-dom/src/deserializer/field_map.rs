@@ -1,18 +1,39 @@
 //! Precomputed field lookup for struct deserialization.
 
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
 
 use facet_core::{Def, Field, StructKind, StructType, Type, UserType};
 
-use crate::naming::{apply_rename_all, dom_key};
-use facet_singularize::singularize;
+use crate::naming::{apply_rename_all, field_singular_form, to_element_name};
+use crate::versioning::field_in_version;
+
+/// Every alias of `field`: the built-in `alias` and each `<ns>::alias`
+/// attribute in the format namespace, in declaration order.
+///
+/// The built-in attribute holds a single name, so further historical
+/// spellings are added with the format's repeatable `alias`.
+pub(crate) fn field_aliases(field: &'static Field, format_ns: Option<&str>) -> Vec<&'static str> {
+    let mut aliases: Vec<&'static str> = field.alias.into_iter().collect();
+    let extra = field
+        .attributes
+        .iter()
+        .filter(|attr| attr.key == "alias" && (attr.ns.is_none() || attr.ns == format_ns))
+        .filter_map(|attr| attr.get_as::<&'static str>().copied());
+    for alias in extra {
+        if !aliases.contains(&alias) {
+            aliases.push(alias);
+        }
+    }
+    aliases
+}
 
 /// Info about a field in a struct for deserialization purposes.
 #[derive(Clone)]
 pub(crate) struct FieldInfo {
     pub idx: usize,
-    #[allow(dead_code)]
     pub field: &'static Field,
     /// True if this field is a list type (Vec, etc.) - NOT an array or set
     pub is_list: bool,
@@ -45,8 +66,7 @@ pub(crate) struct FlattenedChildInfo {
 pub(crate) struct FlattenedEnumInfo {
     /// Index of the flattened enum field in the outer struct
     pub field_idx: usize,
-    /// The field info (kept for potential future use)
-    #[allow(dead_code)]
+    /// The field info
     pub field_info: FieldInfo,
 }
 
@@ -64,6 +84,71 @@ pub(crate) struct NestedFlattenedMapInfo {
     pub parent_is_option: bool,
 }
 
+/// An immutable name-keyed lookup table, stored as a slice sorted by key.
+///
+/// Built once per struct from the field definitions; lookups are a binary
+/// search over `&str` with no hashing or key allocation. Keys borrow the
+/// `'static` names from the shape unless a conversion produced a new one.
+pub(crate) struct NameTable<V> {
+    entries: Box<[(Cow<'static, str>, V)]>,
+}
+
+impl<V> NameTable<V> {
+    /// Look up the value registered under `name`.
+    pub fn get(&self, name: &str) -> Option<&V> {
+        self.entries
+            .binary_search_by(|(key, _)| (**key).cmp(name))
+            .ok()
+            .map(|i| &self.entries[i].1)
+    }
+
+    /// Returns true if no names are registered.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over `(name, value)` pairs in name order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &V)> {
+        self.entries.iter().map(|(key, value)| (&**key, value))
+    }
+
+    /// Iterate over values in name order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+impl<V> From<BTreeMap<Cow<'static, str>, V>> for NameTable<V> {
+    fn from(map: BTreeMap<Cow<'static, str>, V>) -> Self {
+        // Already in key order
+        Self {
+            entries: map.into_iter().collect(),
+        }
+    }
+}
+
+/// What a field map is built from, besides the schema version: the struct
+/// (by address), `ns_all`, `rename_all` and the format namespace.
+type FieldMapKey = (
+    usize,
+    Option<&'static str>,
+    Option<&'static str>,
+    Option<&'static str>,
+);
+
+/// Field maps built so far on this thread, with the
+/// [`singular_generation`](crate::naming::singular_generation) they were
+/// built under.
+#[derive(Default)]
+struct FieldMapCache {
+    generation: usize,
+    maps: HashMap<FieldMapKey, Vec<(Option<Box<str>>, Rc<StructFieldMap>)>>,
+}
+
+thread_local! {
+    static FIELD_MAPS: RefCell<FieldMapCache> = RefCell::default();
+}
+
 /// Precomputed field lookup map for a struct.
 ///
 /// This separates "what fields does this struct have" from the parsing loop,
@@ -71,13 +156,13 @@ pub(crate) struct NestedFlattenedMapInfo {
 pub(crate) struct StructFieldMap {
     /// Fields marked with `xml::attribute`, keyed by lowerCamelCase name or rename.
     /// Multiple fields can have the same name if they have different namespace constraints.
-    attribute_fields: HashMap<String, Vec<FieldInfo>>,
+    attribute_fields: NameTable<Vec<FieldInfo>>,
     /// Fields that are child elements, keyed by lowerCamelCase name or rename.
     /// Multiple fields can have the same name if they have different namespace constraints.
-    element_fields: HashMap<String, Vec<FieldInfo>>,
+    element_fields: NameTable<Vec<FieldInfo>>,
     /// Fields marked with `xml::elements` or `html::elements`, keyed by expected element name.
     /// Each field collects child elements matching its singularized name (or rename).
-    pub elements_fields: HashMap<String, FieldInfo>,
+    pub elements_fields: NameTable<FieldInfo>,
     /// The field marked with `xml::attribute` as a catch-all (collects all unmatched attribute values)
     pub attributes_field: Option<FieldInfo>,
     /// The field marked with `xml::text` (collects text content)
@@ -86,6 +171,10 @@ pub(crate) struct StructFieldMap {
     pub tag_field: Option<FieldInfo>,
     /// The field marked with `xml::doctype` (captures DOCTYPE declaration)
     pub doctype_field: Option<FieldInfo>,
+    /// The field marked with `xml::tag_ns` (captures the element's namespace URI)
+    pub tag_namespace_field: Option<FieldInfo>,
+    /// The field marked with `xml::tag_prefix` (captures the element's namespace prefix)
+    pub tag_prefix_field: Option<FieldInfo>,
     /// The field marked with `#[facet(other)]` (fallback when root doesn't match)
     pub other_field: Option<FieldInfo>,
     /// For tuple structs: fields in order for positional matching.
@@ -93,10 +182,10 @@ pub(crate) struct StructFieldMap {
     pub tuple_fields: Option<Vec<FieldInfo>>,
     /// Flattened child fields - child fields from flattened structs that appear as siblings.
     /// Keyed by the child's lowerCamelCase element name or rename.
-    flattened_children: HashMap<String, Vec<FlattenedChildInfo>>,
+    flattened_children: NameTable<Vec<FlattenedChildInfo>>,
     /// Flattened attribute fields - attribute fields from flattened structs.
     /// Keyed by the lowerCamelCase attribute name or rename.
-    flattened_attributes: HashMap<String, Vec<FlattenedChildInfo>>,
+    flattened_attributes: NameTable<Vec<FlattenedChildInfo>>,
     /// Flattened enum field - enum variants match against child elements directly.
     /// Only one flattened enum is supported per struct.
     pub flattened_enum: Option<FlattenedEnumInfo>,
@@ -110,8 +199,18 @@ pub(crate) struct StructFieldMap {
     pub nested_flattened_attr_maps: Vec<NestedFlattenedMapInfo>,
     /// Whether this struct has any flattened fields (requires deferred mode)
     pub has_flatten: bool,
-    /// Catch-all elements field - matches any tag name (for item types with xml::tag field)
+    /// Catch-all elements field - matches any tag name (for item types with xml::tag field),
+    /// from `xml::elements` or a flattened list such as `Vec<Element>`
     pub catch_all_elements_field: Option<FieldInfo>,
+    /// The [`ChildOrder`](crate::ChildOrder) field recording document order (not read from the document)
+    pub child_order_field: Option<FieldInfo>,
+    /// The element name of each field and flattened child field (rename, else lowerCamelCase),
+    /// keyed by the field's address
+    field_element_names: HashMap<*const Field, Cow<'static, str>>,
+    /// The item element name of each sequence field and flattened child sequence field
+    field_item_names: HashMap<*const Field, Cow<'static, str>>,
+    /// The format namespace the names were computed in
+    format_ns: Option<&'static str>,
 }
 
 /// Compute the effective DOM key for a field, considering `rename_all` from the parent type.
@@ -119,12 +218,12 @@ pub(crate) struct StructFieldMap {
 /// Priority:
 /// 1. Explicit field rename (field.rename) - use as-is
 /// 2. Parent type's rename_all - apply transformation to field.name
-/// 3. Default lowerCamelCase conversion via dom_key
-fn field_dom_key<'a>(
-    field_name: &'a str,
-    field_rename: Option<&'a str>,
+/// 3. Default lowerCamelCase conversion
+pub(crate) fn field_dom_key(
+    field_name: &'static str,
+    field_rename: Option<&'static str>,
     rename_all: Option<&str>,
-) -> Cow<'a, str> {
+) -> Cow<'static, str> {
     if let Some(rename) = field_rename {
         // Explicit rename takes precedence
         Cow::Borrowed(rename)
@@ -133,16 +232,92 @@ fn field_dom_key<'a>(
         Cow::Owned(apply_rename_all(field_name, rename_all))
     } else {
         // Default: lowerCamelCase
-        dom_key(field_name, None)
+        to_element_name(field_name)
+    }
+}
+
+/// The element name of each item of the sequence field `field`.
+///
+/// Priority:
+/// 1. Explicit field rename (field.rename)
+/// 2. The item type's rename (from #[facet(rename = "...")] on the item type)
+/// 3. The item type's default name (type_identifier in lowerCamelCase)
+/// 4. The singular of lowerCamelCase(field.name)
+fn item_element_name(field: &'static Field, format_ns: Option<&str>) -> Cow<'static, str> {
+    if let Some(rename) = field.rename {
+        Cow::Borrowed(rename)
+    } else if let Some(item_rename) = get_item_type_rename(field.shape()) {
+        Cow::Borrowed(item_rename)
+    } else if let Some(item_element_name) = get_item_type_default_element_name(field.shape()) {
+        Cow::Owned(item_element_name)
+    } else {
+        Cow::Owned(field_singular_form(
+            field,
+            &to_element_name(field.name),
+            format_ns,
+        ))
     }
 }
 
 impl StructFieldMap {
+    /// The field map of `struct_def`, built on first use and shared after
+    /// that. See [`new`](Self::new) for the parameters.
+    ///
+    /// Maps are kept per thread for the life of the thread, one for each
+    /// struct and combination of settings it's deserialized with, so
+    /// parsing many elements of a type builds its map once. Registering a
+    /// singular form with [`register_singular`](crate::naming::register_singular)
+    /// discards them, since it changes the element names.
+    pub fn cached(
+        struct_def: &'static StructType,
+        ns_all: Option<&'static str>,
+        rename_all: Option<&'static str>,
+        format_ns: Option<&'static str>,
+        version: Option<&str>,
+    ) -> Rc<Self> {
+        let key = (
+            struct_def as *const StructType as usize,
+            ns_all,
+            rename_all,
+            format_ns,
+        );
+        let generation = crate::naming::singular_generation();
+        let found = FIELD_MAPS.with_borrow_mut(|cache| {
+            if cache.generation != generation {
+                cache.generation = generation;
+                cache.maps.clear();
+            }
+            cache.maps.get(&key).and_then(|versions| {
+                versions
+                    .iter()
+                    .find(|(v, _)| v.as_deref() == version)
+                    .map(|(_, map)| map.clone())
+            })
+        });
+        if let Some(map) = found {
+            return map;
+        }
+
+        let map = Rc::new(Self::new(
+            struct_def, ns_all, rename_all, format_ns, version,
+        ));
+        FIELD_MAPS.with_borrow_mut(|cache| {
+            if cache.generation == generation {
+                cache
+                    .maps
+                    .entry(key)
+                    .or_default()
+                    .push((version.map(Into::into), map.clone()));
+            }
+        });
+        map
+    }
+
     /// Build the field map from a struct definition.
     ///
     /// The `ns_all` parameter is the default namespace for element fields that don't
     /// have an explicit `xml::ns` attribute. When set, fields without `xml::ns` will
-    /// inherit this namespace.
+    /// inherit this namespace, except those marked `xml::no_ns`.
     ///
     /// The `rename_all` parameter, when set, applies a naming transformation to all
     /// fields that don't have explicit renames. This is used to propagate `rename_all`
@@ -150,30 +325,43 @@ impl StructFieldMap {
     ///
     /// The `format_ns` parameter is the format namespace (e.g., "xml") used to resolve
     /// format-specific proxies on item types.
+    ///
+    /// Fields outside the schema `version` (by their `since` / `until` attributes) are
+    /// left out, so their elements and attributes are treated as unknown.
     pub fn new(
         struct_def: &'static StructType,
         ns_all: Option<&'static str>,
         rename_all: Option<&'static str>,
         format_ns: Option<&'static str>,
+        version: Option<&str>,
     ) -> Self {
-        let mut attribute_fields: HashMap<String, Vec<FieldInfo>> = HashMap::new();
-        let mut element_fields: HashMap<String, Vec<FieldInfo>> = HashMap::new();
-        let mut elements_fields: HashMap<String, FieldInfo> = HashMap::new();
+        let mut attribute_fields: BTreeMap<Cow<'static, str>, Vec<FieldInfo>> = BTreeMap::new();
+        let mut element_fields: BTreeMap<Cow<'static, str>, Vec<FieldInfo>> = BTreeMap::new();
+        let mut elements_fields: BTreeMap<Cow<'static, str>, FieldInfo> = BTreeMap::new();
         let mut attributes_field = None;
         let mut text_field = None;
         let mut tag_field = None;
         let mut doctype_field = None;
+        let mut tag_namespace_field = None;
+        let mut tag_prefix_field = None;
         let mut other_field = None;
-        let mut flattened_children: HashMap<String, Vec<FlattenedChildInfo>> = HashMap::new();
-        let mut flattened_attributes: HashMap<String, Vec<FlattenedChildInfo>> = HashMap::new();
+        let mut flattened_children: BTreeMap<Cow<'static, str>, Vec<FlattenedChildInfo>> =
+            BTreeMap::new();
+        let mut flattened_attributes: BTreeMap<Cow<'static, str>, Vec<FlattenedChildInfo>> =
+            BTreeMap::new();
         let mut flattened_enum: Option<FlattenedEnumInfo> = None;
         let mut flattened_maps: Vec<FieldInfo> = Vec::new();
         let mut flattened_attr_maps: Vec<FieldInfo> = Vec::new();
         let mut nested_flattened_attr_maps: Vec<NestedFlattenedMapInfo> = Vec::new();
         let mut has_flatten = false;
         let mut catch_all_elements_field: Option<FieldInfo> = None;
+        let mut child_order_field: Option<FieldInfo> = None;
 
         for (idx, field) in struct_def.fields.iter().enumerate() {
+            if !field_in_version(field, format_ns, version) {
+                continue;
+            }
+
             // Check if this field is flattened
             if field.is_flattened() {
                 has_flatten = true;
@@ -207,6 +395,10 @@ impl StructFieldMap {
                 // Get the inner struct's fields
                 if let Some(inner_struct_def) = get_flattened_struct_def(field) {
                     for (child_idx, child_field) in inner_struct_def.fields.iter().enumerate() {
+                        if !field_in_version(child_field, format_ns, version) {
+                            continue;
+                        }
+
                         // Check if this child field is itself a flattened map
                         // (e.g., #[facet(flatten)] extra: HashMap<String, String>)
                         if child_field.is_flattened() && is_flattened_map(child_field) {
@@ -242,7 +434,7 @@ impl StructFieldMap {
                             .get_attr(Some("xml"), "ns")
                             .and_then(|attr| attr.get_as::<&str>().copied());
                         // Compute child key: rename (as-is) or lowerCamelCase(name)
-                        let child_key = dom_key(child_field.name, child_field.rename);
+                        let child_key = field_dom_key(child_field.name, child_field.rename, None);
 
                         let child_info = FieldInfo {
                             idx: child_idx,
@@ -267,45 +459,65 @@ impl StructFieldMap {
                         if is_attribute {
                             // Register as flattened attribute
                             flattened_attributes
-                                .entry(child_key.clone().into_owned())
+                                .entry(child_key.clone())
                                 .or_default()
                                 .push(flattened_child.clone());
 
-                            // Also register alias if present
-                            if let Some(alias) = child_field.alias {
+                            // Also register aliases
+                            for alias in field_aliases(child_field, format_ns) {
                                 flattened_attributes
-                                    .entry(alias.to_string())
+                                    .entry(Cow::Borrowed(alias))
                                     .or_default()
-                                    .push(flattened_child);
+                                    .push(flattened_child.clone());
                             }
                         } else {
                             // Register as flattened element
                             flattened_children
-                                .entry(child_key.clone().into_owned())
+                                .entry(child_key.clone())
                                 .or_default()
                                 .push(flattened_child.clone());
 
                             // For list/set fields without explicit rename, also register singularized form
                             // (but not for tuples - they use the field name directly)
                             if (is_list || is_set) && !is_tuple && child_field.rename.is_none() {
-                                let singular_key = singularize(&child_key);
+                                let singular_key =
+                                    field_singular_form(child_field, &child_key, format_ns);
                                 if singular_key != *child_key {
                                     flattened_children
-                                        .entry(singular_key)
+                                        .entry(Cow::Owned(singular_key))
                                         .or_default()
                                         .push(flattened_child.clone());
                                 }
                             }
 
-                            // Also register alias if present
-                            if let Some(alias) = child_field.alias {
+                            // Also register aliases
+                            for alias in field_aliases(child_field, format_ns) {
                                 flattened_children
-                                    .entry(alias.to_string())
+                                    .entry(Cow::Borrowed(alias))
                                     .or_default()
-                                    .push(flattened_child);
+                                    .push(flattened_child.clone());
                             }
                         }
                     }
+                } else if item_type_has_tag_field(field.shape()) {
+                    // Flattened list of tagged elements (e.g. Vec<Element>) - captures
+                    // unknown child elements with their full structure, like a catch-all
+                    // xml::elements field
+                    let (is_list, is_array, is_set, is_tuple) =
+                        classify_sequence_shape(field.shape());
+                    let namespace: Option<&'static str> = field
+                        .get_attr(Some("xml"), "ns")
+                        .and_then(|attr| attr.get_as::<&str>().copied());
+
+                    catch_all_elements_field = Some(FieldInfo {
+                        idx,
+                        field,
+                        is_list,
+                        is_array,
+                        is_set,
+                        is_tuple,
+                        namespace,
+                    });
                 } else if is_flattened_map(field) {
                     // Flattened map - captures unknown elements AND attributes as key-value pairs
                     let _shape = field.shape();
@@ -329,6 +541,20 @@ impl StructFieldMap {
                 continue; // Don't register the flattened field itself as an element
             }
 
+            // The document order record isn't part of the document
+            if crate::child_order::is_child_order(field.shape()) {
+                child_order_field = Some(FieldInfo {
+                    idx,
+                    field,
+                    is_list: false,
+                    is_array: false,
+                    is_set: false,
+                    is_tuple: false,
+                    namespace: None,
+                });
+                continue;
+            }
+
             // Check if this field is a list, array, set, or tuple type
             // Need to look through pointers (Arc<[T]>, Box<[T]>, etc.)
             let shape = field.shape();
@@ -361,16 +587,16 @@ impl StructFieldMap {
                     // Named attribute: uses rename > rename_all > lowerCamelCase
                     let attr_key = field_dom_key(field.name, field.rename, rename_all);
                     attribute_fields
-                        .entry(attr_key.into_owned())
+                        .entry(attr_key)
                         .or_default()
                         .push(info.clone());
 
-                    // Also register alias if present (aliases are used as-is, no conversion)
-                    if let Some(alias) = field.alias {
+                    // Also register aliases (used as-is, no conversion)
+                    for alias in field_aliases(field, format_ns) {
                         attribute_fields
-                            .entry(alias.to_string())
+                            .entry(Cow::Borrowed(alias))
                             .or_default()
-                            .push(info);
+                            .push(info.clone());
                     }
                 }
             } else if field.is_elements() {
@@ -395,31 +621,35 @@ impl StructFieldMap {
                     catch_all_elements_field = Some(info);
                 } else if let Some(rename) = field.rename {
                     // Explicit field rename - single key
-                    elements_fields.insert(rename.to_string(), info);
+                    elements_fields.insert(Cow::Borrowed(rename), info);
                 } else if let Some(enum_def) =
                     get_item_type_enum(shape).or_else(|| get_item_type_proxy_enum(shape, format_ns))
                 {
                     // Item type is an enum (or has a proxy that is an enum) - register each variant name
                     // Match the same logic as deserialize_enum: rename.is_some() uses
                     // effective_name(), otherwise apply to_element_name() for lowerCamelCase
-                    for variant in enum_def.variants.iter() {
-                        let variant_key: Cow<'_, str> = if variant.rename.is_some() {
+                    for variant in enum_def.variants.iter().filter(|v| !v.is_custom_element()) {
+                        let variant_key = if variant.rename.is_some() {
                             Cow::Borrowed(variant.effective_name())
                         } else {
-                            dom_key(variant.name, None)
+                            to_element_name(variant.name)
                         };
-                        elements_fields.insert(variant_key.into_owned(), info.clone());
+                        elements_fields.insert(variant_key, info.clone());
                     }
                 } else if let Some(item_rename) = get_item_type_rename(shape) {
                     // Item type has a rename attribute
-                    elements_fields.insert(item_rename.to_string(), info);
+                    elements_fields.insert(Cow::Borrowed(item_rename), info);
                 } else if let Some(item_element_name) = get_item_type_default_element_name(shape) {
                     // Use item type's name as element name (e.g., Vec<SomeInteger> matches <someInteger>)
-                    elements_fields.insert(item_element_name, info);
+                    elements_fields.insert(Cow::Owned(item_element_name), info);
                 } else {
                     // Fallback to singularized field name (with rename_all if present)
-                    let element_key = singularize(&field_dom_key(field.name, None, rename_all));
-                    elements_fields.insert(element_key, info);
+                    let element_key = field_singular_form(
+                        field,
+                        &field_dom_key(field.name, None, rename_all),
+                        format_ns,
+                    );
+                    elements_fields.insert(Cow::Owned(element_key), info);
                 };
             } else if field.is_text() {
                 let info = FieldInfo {
@@ -454,6 +684,28 @@ impl StructFieldMap {
                     namespace,
                 };
                 doctype_field = Some(info);
+            } else if field.get_attr(format_ns, "tag_ns").is_some() {
+                let info = FieldInfo {
+                    idx,
+                    field,
+                    is_list,
+                    is_array,
+                    is_set,
+                    is_tuple,
+                    namespace,
+                };
+                tag_namespace_field = Some(info);
+            } else if field.get_attr(format_ns, "tag_prefix").is_some() {
+                let info = FieldInfo {
+                    idx,
+                    field,
+                    is_list,
+                    is_array,
+                    is_set,
+                    is_tuple,
+                    namespace,
+                };
+                tag_prefix_field = Some(info);
             } else {
                 // Check if this field is marked as "other" - if so, register it as the fallback
                 // for tag mismatches, but ALSO register it as a normal element field so it
@@ -472,8 +724,12 @@ impl StructFieldMap {
                 }
                 // FALL THROUGH to register as element field
                 // Default: unmarked fields and explicit xml::element fields are child elements
-                // Apply ns_all to elements without explicit namespace
-                let effective_namespace = namespace.or(ns_all);
+                // Apply ns_all to elements without explicit namespace, unless they opt out
+                let effective_namespace = if field.get_attr(format_ns, "no_ns").is_some() {
+                    namespace
+                } else {
+                    namespace.or(ns_all)
+                };
                 let info = FieldInfo {
                     idx,
                     field,
@@ -484,7 +740,7 @@ impl StructFieldMap {
                     namespace: effective_namespace,
                 };
                 element_fields
-                    .entry(element_key.clone().into_owned())
+                    .entry(element_key.clone())
                     .or_default()
                     .push(info.clone());
 
@@ -492,22 +748,22 @@ impl StructFieldMap {
                 // e.g., field "tracks" (Vec<T>) also matches element <track>
                 // (but not for tuples - they use the field name directly)
                 if (is_list || is_set) && !is_tuple && field.rename.is_none() {
-                    let singular_key = singularize(&element_key);
+                    let singular_key = field_singular_form(field, &element_key, format_ns);
                     // Only register if singularization actually changed the name
-                    if singular_key != element_key {
+                    if singular_key != *element_key {
                         element_fields
-                            .entry(singular_key)
+                            .entry(Cow::Owned(singular_key))
                             .or_default()
                             .push(info.clone());
                     }
                 }
 
-                // Also register alias if present (aliases are used as-is, no conversion)
-                if let Some(alias) = field.alias {
+                // Also register aliases (used as-is, no conversion)
+                for alias in field_aliases(field, format_ns) {
                     element_fields
-                        .entry(alias.to_string())
+                        .entry(Cow::Borrowed(alias))
                         .or_default()
-                        .push(info);
+                        .push(info.clone());
                 }
             }
         }
@@ -538,24 +794,65 @@ impl StructFieldMap {
             None
         };
 
+        // Names of the fields, and of flattened structs' fields, computed once here
+        // rather than for every element
+        let mut field_element_names = HashMap::new();
+        let mut field_item_names = HashMap::new();
+        let flattened_fields = struct_def
+            .fields
+            .iter()
+            .filter(|field| field.is_flattened())
+            .filter_map(get_flattened_struct_def)
+            .flat_map(|inner| inner.fields.iter());
+        for field in struct_def.fields.iter().chain(flattened_fields) {
+            let key = field as *const Field;
+            field_element_names.insert(key, field_dom_key(field.name, field.rename, None));
+            let (is_list, is_array, is_set, _) = classify_sequence_shape(field.shape());
+            if is_list || is_array || is_set || field.is_elements() {
+                field_item_names.insert(key, item_element_name(field, format_ns));
+            }
+        }
+
         Self {
-            attribute_fields,
-            element_fields,
-            elements_fields,
+            attribute_fields: attribute_fields.into(),
+            element_fields: element_fields.into(),
+            elements_fields: elements_fields.into(),
             attributes_field,
             text_field,
             tag_field,
             doctype_field,
+            tag_namespace_field,
+            tag_prefix_field,
             other_field,
             tuple_fields,
-            flattened_children,
-            flattened_attributes,
+            flattened_children: flattened_children.into(),
+            flattened_attributes: flattened_attributes.into(),
             flattened_enum,
             flattened_maps,
             flattened_attr_maps,
             nested_flattened_attr_maps,
             has_flatten,
             catch_all_elements_field,
+            child_order_field,
+            field_element_names,
+            field_item_names,
+            format_ns,
+        }
+    }
+
+    /// The element name of `field` on its own: its rename, else lowerCamelCase.
+    pub fn element_name(&self, field: &'static Field) -> Cow<'static, str> {
+        match self.field_element_names.get(&(field as *const Field)) {
+            Some(name) => name.clone(),
+            None => field_dom_key(field.name, field.rename, None),
+        }
+    }
+
+    /// The element name of each item of the sequence field `field`.
+    pub fn item_name(&self, field: &'static Field) -> Cow<'static, str> {
+        match self.field_item_names.get(&(field as *const Field)) {
+            Some(name) => name.clone(),
+            None => item_element_name(field, self.format_ns),
         }
     }
 
@@ -580,6 +877,13 @@ impl StructFieldMap {
         })
     }
 
+    /// Find an attribute field declared in exactly `namespace`.
+    pub fn find_attribute_in_namespace(&self, name: &str, namespace: &str) -> Option<&FieldInfo> {
+        self.attribute_fields
+            .get(name)
+            .and_then(|fields| fields.iter().find(|info| info.namespace == Some(namespace)))
+    }
+
     /// Find an element field by tag name and namespace.
     ///
     /// Returns `Some` if the name matches AND the namespace matches:
@@ -660,6 +964,45 @@ impl StructFieldMap {
         self.tuple_fields.is_some()
     }
 
+    /// Iterate over every attribute name this struct accepts, including those of
+    /// flattened structs.
+    ///
+    /// Yields `(name, flattened parent index, field info)`; a field appears once
+    /// per name it is registered under (its key and its alias).
+    pub fn attribute_names(&self) -> impl Iterator<Item = (&str, Option<usize>, &FieldInfo)> {
+        let direct = self
+            .attribute_fields
+            .iter()
+            .flat_map(|(name, fields)| fields.iter().map(move |info| (name, None, info)));
+        let flattened = self
+            .flattened_attributes
+            .iter()
+            .flat_map(|(name, children)| {
+                children
+                    .iter()
+                    .map(move |child| (name, Some(child.parent_idx), &child.child_info))
+            });
+        direct.chain(flattened)
+    }
+
+    /// Iterate over every child element name matched by a single field,
+    /// including those of flattened structs.
+    ///
+    /// Yields `(name, flattened parent index, field info)`; a field appears once
+    /// per name it is registered under (its key, singular form and alias).
+    pub fn element_names(&self) -> impl Iterator<Item = (&str, Option<usize>, &FieldInfo)> {
+        let direct = self
+            .element_fields
+            .iter()
+            .flat_map(|(name, fields)| fields.iter().map(move |info| (name, None, info)));
+        let flattened = self.flattened_children.iter().flat_map(|(name, children)| {
+            children
+                .iter()
+                .map(move |child| (name, Some(child.parent_idx), &child.child_info))
+        });
+        direct.chain(flattened)
+    }
+
     /// Returns unique list/set element field indices that need default initialization
     /// when no matching child elements are found.
     ///
@@ -782,7 +1125,7 @@ fn classify_sequence_shape(shape: &facet_core::Shape) -> (bool, bool, bool, bool
 
 /// Get the item shape for a collection field.
 /// Returns the inner element type for Vec, Set, Slice, Array, and smart pointers to these.
-fn get_item_shape(shape: &facet_core::Shape) -> Option<&'static facet_core::Shape> {
+pub(crate) fn get_item_shape(shape: &facet_core::Shape) -> Option<&'static facet_core::Shape> {
     match &shape.def {
         Def::List(list_def) => Some(list_def.t()),
         Def::Set(set_def) => Some(set_def.t()),
@@ -1,19 +1,24 @@
 //! Tree-based deserializer for DOM documents.
 
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write as _;
 
-use facet_core::{Def, StructKind, Type, UserType};
+use facet_core::{Def, StructKind, Type, UserType, Variant};
 use facet_reflect::Partial;
 
-use crate::error::DomDeserializeError;
+use crate::error::{DomDeserializeError, ErrorLocation, UnknownItem};
 use crate::naming::to_element_name;
-use crate::trace;
 use crate::{AttributeRecord, DomEvent, DomParser, DomParserExt};
+use crate::{enter_span, trace};
 
+mod collect;
 mod entrypoints;
-mod field_map;
+pub(crate) mod field_map;
+mod recovery;
 mod struct_deser;
 
+use recovery::Collection;
 use struct_deser::StructDeserializer;
 
 /// Extension trait for chaining deserialization on `Partial`.
@@ -51,6 +56,50 @@ impl<'de, const BORROW: bool, P: DomParser<'de>> PartialDeserializeExt<'de, BORR
     }
 }
 
+/// Where a scalar value sits relative to the element being deserialized.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ValueSite<'a> {
+    /// Text content of the current element.
+    Text,
+    /// Text content of the named child element.
+    Element(&'a str),
+    /// The named attribute of the current element.
+    Attribute(&'a str),
+}
+
+/// Indices of list items along the deserializer's path, for locating errors.
+#[derive(Debug, Default)]
+pub(crate) struct ItemTrail {
+    /// Index of each element of the path among the items of its list, if
+    /// it is one
+    indices: Vec<Option<usize>>,
+    /// Items read so far under each element of the path, by tag
+    counts: Vec<HashMap<String, usize>>,
+    /// Index of the list item about to be deserialized
+    next: Option<usize>,
+}
+
+impl ItemTrail {
+    fn enter(&mut self) {
+        self.indices.push(self.next.take());
+        self.counts.push(HashMap::new());
+    }
+
+    fn leave(&mut self) {
+        self.indices.pop();
+        self.counts.pop();
+    }
+
+    /// Count an item `tag` of the current element, making it the next one.
+    fn count_item(&mut self, tag: &str) {
+        self.next = self.counts.last_mut().map(|counts| {
+            let count = counts.entry(tag.to_string()).or_default();
+            *count += 1;
+            *count - 1
+        });
+    }
+}
+
 /// DOM deserializer.
 ///
 /// The `BORROW` parameter controls whether strings can be borrowed from the input:
@@ -58,6 +107,14 @@ impl<'de, const BORROW: bool, P: DomParser<'de>> PartialDeserializeExt<'de, BORR
 /// - `BORROW = false`: All strings are owned, input doesn't need to outlive result
 pub struct DomDeserializer<'de, const BORROW: bool, P> {
     parser: P,
+    /// Names of the elements currently being deserialized, outermost first.
+    path: Vec<Cow<'de, str>>,
+    /// List item indices along `path`.
+    items: ItemTrail,
+    /// Recoverable errors met so far, when collecting them
+    collected: Option<Vec<DomDeserializeError<P::Error>>>,
+    /// Unknown elements and attributes met so far, when collecting them
+    unknown: Option<Vec<UnknownItem>>,
     _marker: std::marker::PhantomData<&'de ()>,
 }
 
@@ -95,9 +152,28 @@ where
         &mut self,
         wip: Partial<'de, BORROW>,
         expected_name: Option<Cow<'static, str>>,
+    ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
+        crate::stack::ensure_stack(|| self.deserialize_into_named_unguarded(wip, expected_name))
+    }
+
+    /// [`deserialize_into_named`](Self::deserialize_into_named) without the
+    /// stack check.
+    fn deserialize_into_named_unguarded(
+        &mut self,
+        wip: Partial<'de, BORROW>,
+        expected_name: Option<Cow<'static, str>>,
     ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
         let format_ns = self.parser.format_namespace();
 
+        // Fields with a text codec hold bytes but read like a scalar, and
+        // so do fields built by a `deserialize_with` function
+        if let Some(field) = wip.parent_field()
+            && (crate::codec::field_codec(field, format_ns).is_some()
+                || crate::with_fn::field_deserialize_with(field, format_ns).is_some())
+        {
+            return self.deserialize_scalar(wip);
+        }
+
         // Check for field-level proxy first (e.g., #[facet(xml::proxy = ProxyType)] on a field)
         // This takes precedence over container-level proxies.
         if let Some(field) = wip.parent_field()
@@ -107,9 +183,11 @@ where
                 .begin_custom_deserialization_with_format(format_ns)
                 .map_err(DomDeserializeError::Reflect)?;
             // Deserialize into proxy buffer with the same expected_name
-            let proxy_wip = self.deserialize_into_inner(proxy_wip, expected_name)?;
+            let proxy_wip = self.deserialize_into_inner(proxy_wip, expected_name.clone())?;
             // Convert proxy -> target via TryFrom
-            return proxy_wip.end().map_err(DomDeserializeError::Reflect);
+            return proxy_wip
+                .end()
+                .map_err(|e| self.proxy_error(e.into(), expected_name.as_deref()));
         }
 
         // Check for container-level proxy (e.g., #[facet(xml::proxy = ProxyType)] on the type)
@@ -122,9 +200,11 @@ where
 
             if found {
                 // Deserialize into proxy buffer with the same expected_name
-                let proxy_wip = self.deserialize_into_inner(proxy_wip, expected_name)?;
+                let proxy_wip = self.deserialize_into_inner(proxy_wip, expected_name.clone())?;
                 // Convert proxy -> target via TryFrom
-                return proxy_wip.end().map_err(DomDeserializeError::Reflect);
+                return proxy_wip
+                    .end()
+                    .map_err(|e| self.proxy_error(e.into(), expected_name.as_deref()));
             }
             // Proxy check returned true but begin_custom_deserialization didn't find it
             // (shouldn't happen, but fall through to normal path)
@@ -224,18 +304,8 @@ where
 
         // Use provided expected_name, or compute from shape:
         // rename > rename_all(type_identifier) > lowerCamelCase(type_identifier)
-        let expected_name = expected_name.unwrap_or_else(|| {
-            if let Some(rename) = shape.get_builtin_attr_value::<&str>("rename") {
-                Cow::Borrowed(rename)
-            } else if let Some(rename_all) = shape.get_builtin_attr_value::<&str>("rename_all") {
-                Cow::Owned(crate::naming::apply_rename_all(
-                    shape.type_identifier,
-                    rename_all,
-                ))
-            } else {
-                to_element_name(shape.type_identifier)
-            }
-        });
+        let expected_name =
+            expected_name.unwrap_or_else(|| crate::naming::shape_element_name(shape));
 
         // For regular structs, rename_all is handled by facet-derive setting field.rename
         // So we pass None here - the field map will use field.rename if present
@@ -256,6 +326,8 @@ where
         expected_name: Cow<'static, str>,
         rename_all: Option<&'static str>,
     ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
+        enter_span!("element", tag = %expected_name);
+
         // Extract xml::ns_all attribute from the shape
         let ns_all = wip
             .shape()
@@ -267,7 +339,9 @@ where
         // Check if deny_unknown_fields is set
         let deny_unknown_fields = wip.shape().has_deny_unknown_fields_attr();
 
-        StructDeserializer::new(
+        self.path.push(expected_name.clone());
+        self.items.enter();
+        let result = StructDeserializer::new(
             self,
             struct_def,
             ns_all,
@@ -275,7 +349,35 @@ where
             expected_name,
             deny_unknown_fields,
         )
-        .deserialize(wip)
+        .deserialize(wip);
+        let result = result.map_err(|e| self.locate(e));
+        self.items.leave();
+        self.path.pop();
+        result
+    }
+
+    /// Wrap `error` in [`DomDeserializeError::Located`] with the current
+    /// path and span, if the parser locates errors and it isn't located yet.
+    fn locate(&self, error: DomDeserializeError<P::Error>) -> DomDeserializeError<P::Error> {
+        if !self.parser.locates_errors() || matches!(error, DomDeserializeError::Located { .. }) {
+            return error;
+        }
+        let mut path = String::new();
+        for (name, index) in self.path.iter().zip(&self.items.indices) {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(name);
+            if let Some(index) = index {
+                let _ = write!(path, "[{index}]");
+            }
+        }
+        let location =
+            ErrorLocation::new(path, self.parser.current_span(), self.parser.source_text());
+        DomDeserializeError::Located {
+            location: Box::new(location),
+            source: Box::new(error),
+        }
     }
 
     /// Deserialize an enum type.
@@ -293,10 +395,14 @@ where
     /// # Variant Selection
     ///
     /// For `NodeStart`: The element tag name is matched against variant names (considering
-    /// `#[rename]` attributes). If no match, looks for a variant with `#[xml::custom_element]`.
+    /// `#[rename]` attributes). If no match, the first variant with `#[xml::custom_element]`
+    /// takes the element; the names of such variants are never matched.
     ///
     /// For `Text`: Looks for a variant with `#[xml::text]` attribute.
     ///
+    /// Enums marked `xml::xsi_type` go by the element's `xsi:type` attribute
+    /// instead; see [`deserialize_xsi_typed`](Self::deserialize_xsi_typed).
+    ///
     /// If `expected_name` is `Some`, it's used for untagged enums. For tagged enums,
     /// the element name is determined by the variant.
     fn deserialize_enum(
@@ -304,7 +410,11 @@ where
         mut wip: Partial<'de, BORROW>,
         expected_name: Option<Cow<'static, str>>,
     ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
+        let xsi_typed = crate::xsi_type::is_xsi_typed(wip.shape(), self.parser.format_namespace());
         let event = self.parser.peek_event_or_eof("NodeStart or Text")?;
+        if xsi_typed && matches!(event, DomEvent::NodeStart { .. }) {
+            return self.deserialize_xsi_typed(wip, expected_name);
+        }
 
         match event {
             DomEvent::NodeStart { tag, .. } => {
@@ -337,10 +447,21 @@ where
                     // For tagged enums, match the element tag against variant names.
                     // Compute effective element name: use rename attribute if present,
                     // otherwise convert to lowerCamelCase.
+                    let format_ns = self.parser.format_namespace();
                     enum_def
                         .variants
                         .iter()
                         .position(|v| {
+                            if v.get_attr(format_ns, "comment").is_some()
+                                || v.get_attr(format_ns, "processing_instruction").is_some()
+                            {
+                                // Holds a comment or processing instruction, never an element
+                                return false;
+                            }
+                            if v.is_custom_element() {
+                                // Takes any tag, so its own name means nothing
+                                return false;
+                            }
                             let effective_name: Cow<'_, str> = if v.rename.is_some() {
                                 Cow::Borrowed(v.effective_name())
                             } else {
@@ -375,39 +496,7 @@ where
                     to_element_name(variant.name)
                 };
 
-                // Handle variant based on its kind
-                match variant.data.kind {
-                    StructKind::Unit => {
-                        // Unit variant: just consume the element
-                        self.parser.expect_node_start()?;
-                        // Skip to end of element
-                        let event = self.parser.peek_event_or_eof("ChildrenStart or NodeEnd")?;
-                        if matches!(event, DomEvent::ChildrenStart) {
-                            self.parser.expect_children_start()?;
-                            self.parser.expect_children_end()?;
-                        }
-                        self.parser.expect_node_end()?;
-                    }
-                    StructKind::TupleStruct if variant.data.fields.len() == 1 => {
-                        // Newtype variant (single unnamed field): deserialize the inner type
-                        // Use deserialize_into_named to handle proxies and pass through element name
-                        wip = wip
-                            .begin_nth_field(0)?
-                            .deserialize_with_name(self, variant_element_name)?
-                            .end()?;
-                    }
-                    StructKind::TupleStruct | StructKind::Struct | StructKind::Tuple => {
-                        // Struct variant, tuple variant (2+ fields), or tuple type:
-                        // deserialize using the variant's data as a StructType
-                        // Pass enum's rename_all to apply to variant field names
-                        wip = self.deserialize_struct_innards(
-                            wip,
-                            &variant.data,
-                            variant_element_name,
-                            rename_all,
-                        )?;
-                    }
-                }
+                wip = self.deserialize_variant(wip, variant, variant_element_name, rename_all)?;
             }
             DomEvent::Text(_) => {
                 let text = self.parser.expect_text()?;
@@ -424,6 +513,124 @@ where
         Ok(wip)
     }
 
+    /// Deserialize the content of the selected `variant`, read from the
+    /// element at the cursor.
+    ///
+    /// # Parser State Contract
+    ///
+    /// **Entry:** Parser is positioned before the variant's `NodeStart`.
+    ///
+    /// **Exit:** Parser has consumed through the variant's closing `NodeEnd`.
+    fn deserialize_variant(
+        &mut self,
+        mut wip: Partial<'de, BORROW>,
+        variant: &'static Variant,
+        variant_element_name: Cow<'static, str>,
+        rename_all: Option<&'static str>,
+    ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
+        match variant.data.kind {
+            StructKind::Unit => {
+                // Unit variant: just consume the element
+                self.parser.expect_node_start()?;
+                // Skip to end of element
+                let event = self.parser.peek_event_or_eof("ChildrenStart or NodeEnd")?;
+                if matches!(event, DomEvent::ChildrenStart) {
+                    self.parser.expect_children_start()?;
+                    self.parser.expect_children_end()?;
+                }
+                self.parser.expect_node_end()?;
+            }
+            StructKind::TupleStruct if variant.data.fields.len() == 1 => {
+                // Newtype variant (single unnamed field): deserialize the inner type
+                // Use deserialize_into_named to handle proxies and pass through element name
+                wip = wip
+                    .begin_nth_field(0)?
+                    .deserialize_with_name(self, variant_element_name)?
+                    .end()?;
+            }
+            StructKind::TupleStruct | StructKind::Struct | StructKind::Tuple => {
+                // Struct variant, tuple variant (2+ fields), or tuple type:
+                // deserialize using the variant's data as a StructType
+                // Pass enum's rename_all to apply to variant field names
+                wip = self.deserialize_struct_innards(
+                    wip,
+                    &variant.data,
+                    variant_element_name,
+                    rename_all,
+                )?;
+            }
+        }
+        Ok(wip)
+    }
+
+    /// Deserialize an enum whose variant is named by the element's
+    /// `xsi:type` attribute.
+    ///
+    /// The element is named like an untagged enum's: `expected_name`, or the
+    /// enum's own name. It is read into memory to find the attribute, which
+    /// is then left out when its content is deserialized into the variant.
+    fn deserialize_xsi_typed(
+        &mut self,
+        wip: Partial<'de, BORROW>,
+        expected_name: Option<Cow<'static, str>>,
+    ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
+        let enum_shape = wip.shape();
+        let Type::User(UserType::Enum(enum_def)) = &enum_shape.ty else {
+            return Err(DomDeserializeError::Unsupported(
+                "expected enum type".into(),
+            ));
+        };
+        let rename_all = enum_shape.get_builtin_attr_value::<&str>("rename_all");
+        let element_name =
+            expected_name.unwrap_or_else(|| crate::naming::shape_element_name(enum_shape));
+
+        let (_, mut events) = self.buffer_item()?;
+        // Attributes follow the element's NodeStart
+        let type_attr = events
+            .iter()
+            .skip(1)
+            .take_while(|(event, _)| matches!(event, DomEvent::Attribute { .. }))
+            .position(|(event, _)| {
+                matches!(
+                    event,
+                    DomEvent::Attribute { name, namespace, .. }
+                        if name == "type"
+                            && namespace.as_deref() == Some(crate::xsi_type::XSI_NAMESPACE)
+                )
+            });
+        let Some(type_attr) = type_attr else {
+            return Err(DomDeserializeError::MissingAttribute { name: "xsi:type" });
+        };
+        let (DomEvent::Attribute { value, .. }, _) = events.remove(type_attr + 1) else {
+            unreachable!("found as an attribute above");
+        };
+        let variant_idx =
+            crate::xsi_type::find_variant(enum_def.variants, &value).ok_or_else(|| {
+                DomDeserializeError::UnknownType {
+                    type_name: value.to_string(),
+                }
+            })?;
+        let variant = &enum_def.variants[variant_idx];
+        trace!(type_name = %value, variant_name = variant.name, "selected variant by xsi:type");
+        let wip = wip.select_nth_variant(variant_idx)?;
+
+        let source: &mut dyn DomParser<'de, Error = P::Error> = &mut self.parser;
+        let mut replay = DomDeserializer::<'de, BORROW, _> {
+            parser: recovery::ItemReplay::new(events, source, None),
+            path: std::mem::take(&mut self.path),
+            items: std::mem::take(&mut self.items),
+            collected: self.collected.take(),
+            unknown: self.unknown.take(),
+            _marker: std::marker::PhantomData,
+        };
+        let result = replay.deserialize_variant(wip, variant, element_name, rename_all);
+        self.path = replay.path;
+        self.items = replay.items;
+        self.collected = replay.collected;
+        self.unknown = replay.unknown;
+        result
+    }
+
     /// Deserialize text content into an enum by selecting the `#[xml::text]` variant.
     ///
     /// # Parser State Contract
@@ -546,12 +753,12 @@ where
                 trace!("deserialize_scalar: matched Text arm");
                 let text = self.parser.expect_text()?;
                 // Use set_string_value_with_proxy for format-specific proxy support
-                self.set_string_value_with_proxy(wip, text)
+                self.set_string_value_with_proxy(wip, text, ValueSite::Text)
             }
             DomEvent::NodeStart { .. } => {
                 trace!("deserialize_scalar: matched NodeStart arm");
-                let _tag = self.parser.expect_node_start()?;
-                trace!(tag = %_tag, "deserialize_scalar: consumed NodeStart");
+                let tag = self.parser.expect_node_start()?;
+                trace!(tag = %tag, "deserialize_scalar: consumed NodeStart");
 
                 loop {
                     let event = self
@@ -576,7 +783,11 @@ where
                             self.parser.expect_node_end()?;
                             trace!("deserialize_scalar: void element, returning empty string");
                             // Use set_string_value_with_proxy for format-specific proxy support
-                            return self.set_string_value_with_proxy(wip, Cow::Borrowed(""));
+                            return self.set_string_value_with_proxy(
+                                wip,
+                                Cow::Borrowed(""),
+                                ValueSite::Element(&tag),
+                            );
                         }
                         other => {
                             trace!(other = ?other, "deserialize_scalar: unexpected event in attr loop");
@@ -589,15 +800,19 @@ where
                 }
 
                 trace!("deserialize_scalar: starting text content loop");
-                let mut text_content = String::new();
+                // The first chunk is kept as is, so a single large text node isn't copied
+                let mut text_content: Option<Cow<'de, str>> = None;
                 loop {
                     let event = self.parser.peek_event_or_eof("Text or ChildrenEnd")?;
                     trace!(event = ?event, "deserialize_scalar: in text content loop");
                     match event {
                         DomEvent::Text(_) => {
                             let text = self.parser.expect_text()?;
-                            trace!(text = %text, "deserialize_scalar: got text");
-                            text_content.push_str(&text);
+                            trace!(text_len = text.len(), "deserialize_scalar: got text");
+                            match &mut text_content {
+                                None => text_content = Some(text),
+                                Some(content) => content.to_mut().push_str(&text),
+                            }
                         }
                         DomEvent::ChildrenEnd => {
                             trace!("deserialize_scalar: got ChildrenEnd, breaking text loop");
@@ -625,10 +840,14 @@ where
                 self.parser.expect_children_end()?;
                 trace!("deserialize_scalar: consuming NodeEnd");
                 self.parser.expect_node_end()?;
-                trace!(text_content = %text_content, "deserialize_scalar: setting string value");
+                trace!("deserialize_scalar: setting string value");
 
                 // Use set_string_value_with_proxy for format-specific proxy support
-                self.set_string_value_with_proxy(wip, Cow::Owned(text_content))
+                self.set_string_value_with_proxy(
+                    wip,
+                    text_content.unwrap_or_default(),
+                    ValueSite::Element(&tag),
+                )
             }
             other => Err(DomDeserializeError::TypeMismatch {
                 expected: "Text or NodeStart",
@@ -667,9 +886,7 @@ where
                 break;
             }
 
-            wip = wip.begin_list_item()?;
-            wip = self.deserialize_into_named(wip, expected_name.clone())?;
-            wip = wip.end()?;
+            wip = self.deserialize_collection_item(wip, Collection::List, expected_name.clone())?;
         }
 
         Ok(wip)
@@ -693,9 +910,7 @@ where
                 break;
             }
 
-            wip = wip.begin_set_item()?;
-            wip = self.deserialize_into_named(wip, expected_name.clone())?;
-            wip = wip.end()?;
+            wip = self.deserialize_collection_item(wip, Collection::Set, expected_name.clone())?;
         }
 
         Ok(wip)
@@ -826,6 +1041,9 @@ where
         let event = self.parser.peek_event_or_eof("value")?;
         if matches!(event, DomEvent::ChildrenEnd | DomEvent::NodeEnd) {
             wip = wip.set_default()?;
+        } else if self.is_nil() {
+            self.parser.skip_node()?;
+            wip = wip.set_default()?;
         } else {
             wip = wip.begin_some()?;
             wip = self.deserialize_into_named(wip, expected_name)?;
@@ -834,6 +1052,14 @@ where
         Ok(wip)
     }
 
+    /// Whether the element at the cursor is marked `xsi:nil="true"`, standing
+    /// for `None`.
+    pub(crate) fn is_nil(&self) -> bool {
+        self.parser
+            .peeked_attribute(crate::xsi_type::XSI_NAMESPACE, "nil")
+            .is_some_and(|value| matches!(value.trim(), "true" | "1"))
+    }
+
     /// Deserialize a pointer type (Box, Arc, Rc, etc.).
     ///
     /// # Parser State Contract
@@ -885,6 +1111,25 @@ where
         mut wip: Partial<'de, BORROW>,
         value: Cow<'de, str>,
     ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
+        // Smart pointers (Arc<str>, Box<u32>, ...) take the string through their pointee,
+        // the same way deserialize_pointer does for element content
+        if let Def::Pointer(_) = wip.shape().def {
+            use facet_dessert::{PointerAction, begin_pointer};
+
+            let (wip, action) = begin_pointer(wip)?;
+            return match action {
+                PointerAction::HandleAsScalar => Ok(facet_dessert::set_string_value(
+                    wip,
+                    value,
+                    self.parser.current_span(),
+                )?),
+                PointerAction::SizedPointee => Ok(self.set_string_value(wip, value)?.end()?),
+                PointerAction::SliceBuilder => Err(DomDeserializeError::Unsupported(
+                    "a pointer to a slice can't be read from a single string".into(),
+                )),
+            };
+        }
+
         // Handle enums specially - match variant names with lowerCamelCase conversion
         // Skip Option (now reports as UserType::Enum) - facet_dessert handles it
         if let Type::User(UserType::Enum(enum_def)) = &wip.shape().ty
@@ -913,6 +1158,19 @@ where
             // No match found - fall through to facet_dessert which will give a proper error
         }
 
+        // xs:boolean also allows `1` and `0`, and `bool_style = "yes_no"`
+        // fields read `yes` and `no`
+        if wip.shape().id == <bool as facet_core::Facet>::SHAPE.id {
+            let format_ns = self.parser.format_namespace();
+            let yes_no = wip
+                .parent_field()
+                .and_then(|f| crate::serializer::field_bool_style(f, format_ns))
+                == Some(crate::BoolStyle::YesNo);
+            if let Some(b) = parse_bool(&value, yes_no) {
+                return Ok(wip.set(b)?);
+            }
+        }
+
         Ok(facet_dessert::set_string_value(
             wip,
             value,
@@ -933,13 +1191,41 @@ where
     /// This method supports format-specific proxies: if the parser returns a format
     /// namespace (e.g., "xml"), fields with `#[facet(xml::proxy = ...)]` will use
     /// that proxy instead of the format-agnostic one.
+    ///
+    /// Conversion failures are reported as [`DomDeserializeError::InvalidValue`],
+    /// locating the value at `site` below the current element.
     pub(crate) fn set_string_value_with_proxy(
+        &mut self,
+        wip: Partial<'de, BORROW>,
+        value: Cow<'de, str>,
+        site: ValueSite<'_>,
+    ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
+        let value = self.intercept(value, site, wip.shape(), wip.parent_field());
+        let text = error_text(&value);
+        self.set_string_value_with_proxy_inner(wip, value)
+            .map_err(|e| self.invalid_value(e, site, Some(&text)))
+    }
+
+    fn set_string_value_with_proxy_inner(
         &mut self,
         mut wip: Partial<'de, BORROW>,
         value: Cow<'de, str>,
     ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
-        // Check if the field has a proxy (format-specific or format-agnostic)
         let format_ns = self.parser.format_namespace();
+        if let Some(deserialize) = wip
+            .parent_field()
+            .and_then(|f| crate::with_fn::field_deserialize_with(f, format_ns))
+        {
+            return set_with_fn(wip, deserialize, &value);
+        }
+        if let Some(codec) = wip
+            .parent_field()
+            .and_then(|f| crate::codec::field_codec(f, format_ns))
+        {
+            return set_decoded_bytes(wip, codec, &value);
+        }
+
+        // Check if the field has a proxy (format-specific or format-agnostic)
         let field_proxy = wip
             .parent_field()
             .and_then(|f| f.effective_proxy(format_ns));
@@ -964,4 +1250,214 @@ where
             self.set_string_value(wip, value)
         }
     }
+
+    /// Attach the location of a value (and its text, if known) to a conversion error.
+    ///
+    /// Errors that already carry a location are returned unchanged, so the
+    /// innermost (most precise) location wins.
+    pub(crate) fn invalid_value(
+        &self,
+        error: DomDeserializeError<P::Error>,
+        site: ValueSite<'_>,
+        text: Option<&str>,
+    ) -> DomDeserializeError<P::Error> {
+        if !matches!(
+            error,
+            DomDeserializeError::Reflect(_)
+                | DomDeserializeError::ShapeMismatch(_)
+                | DomDeserializeError::Codec { .. }
+                | DomDeserializeError::DeserializeWith { .. }
+        ) {
+            return error;
+        }
+
+        DomDeserializeError::InvalidValue {
+            path: self.value_path(site),
+            text: text.map(str::to_owned),
+            source: Box::new(error),
+        }
+    }
+
+    /// Where `site` is, as a `/`-separated path with attributes prefixed by `@`.
+    fn value_path(&self, site: ValueSite<'_>) -> String {
+        let mut path = self.path.join("/");
+        match site {
+            ValueSite::Text => {}
+            ValueSite::Element(name) => {
+                if !path.is_empty() {
+                    path.push('/');
+                }
+                path.push_str(name);
+            }
+            ValueSite::Attribute(name) => {
+                if !path.is_empty() {
+                    path.push('/');
+                }
+                path.push('@');
+                path.push_str(name);
+            }
+        }
+
+        path
+    }
+
+    /// Pass `value` through the parser's [`intercept_value`](DomParser::intercept_value),
+    /// if it has one, then through
+    /// [`resolve_in_context`](DomParser::resolve_in_context) if `field` is
+    /// marked `with_context`.
+    pub(crate) fn intercept(
+        &mut self,
+        value: Cow<'de, str>,
+        site: ValueSite<'_>,
+        shape: &'static facet_core::Shape,
+        field: Option<&facet_core::Field>,
+    ) -> Cow<'de, str> {
+        let in_context = self.parser.has_context()
+            && field.is_some_and(|field| {
+                field
+                    .get_attr(self.parser.format_namespace(), "with_context")
+                    .is_some()
+            });
+        if !self.parser.intercepts_values() && !in_context {
+            return value;
+        }
+        let path = self.value_path(site);
+        let value = if self.parser.intercepts_values()
+            && let Some(replacement) = self.parser.intercept_value(&path, &value, shape)
+        {
+            Cow::Owned(replacement)
+        } else {
+            value
+        };
+        if !in_context {
+            return value;
+        }
+        match self.parser.resolve_in_context(&path, &value, shape) {
+            Some(replacement) => Cow::Owned(replacement),
+            None => value,
+        }
+    }
+
+    /// Locate a failed proxy conversion for the element named `name` (or the
+    /// current element when `None`).
+    fn proxy_error(
+        &self,
+        error: DomDeserializeError<P::Error>,
+        name: Option<&str>,
+    ) -> DomDeserializeError<P::Error> {
+        let site = name.map_or(ValueSite::Text, ValueSite::Element);
+        self.invalid_value(error, site, None)
+    }
+}
+
+/// Longest value quoted in full by [`DomDeserializeError::InvalidValue`].
+const ERROR_TEXT_LIMIT: usize = 1024;
+
+/// The text to quote when `value` can't be stored: all of it, or its start
+/// when it is long, so large values aren't copied just in case they fail.
+pub(crate) fn error_text(value: &str) -> String {
+    if value.len() <= ERROR_TEXT_LIMIT {
+        return value.to_string();
+    }
+    let mut end = ERROR_TEXT_LIMIT;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... ({} bytes)", &value[..end], value.len())
+}
+
+/// Decode `text` with a field's codec and store the bytes in `wip`.
+fn set_decoded_bytes<'de, const BORROW: bool, E>(
+    wip: Partial<'de, BORROW>,
+    codec: crate::codec::FieldCodec,
+    text: &str,
+) -> Result<Partial<'de, BORROW>, DomDeserializeError<E>> {
+    let name = codec.name;
+    let bytes = codec
+        .codec
+        .ok_or_else(|| DomDeserializeError::Unsupported(format!("unknown codec {name:?}")))?
+        .decode(text)
+        .map_err(|message| DomDeserializeError::Codec {
+            codec: name,
+            message,
+        })?;
+    set_bytes(wip, name, bytes)
+}
+
+/// The value of a boolean written as `true`, `false`, `1` or `0` (or `yes`
+/// and `no` if `yes_no` is set), ignoring surrounding whitespace.
+pub(crate) fn parse_bool(text: &str, yes_no: bool) -> Option<bool> {
+    match text.trim() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        "yes" if yes_no => Some(true),
+        "no" if yes_no => Some(false),
+        _ => None,
+    }
+}
+
+/// Build the value of `wip` from `text` with a `deserialize_with` function.
+fn set_with_fn<'de, const BORROW: bool, E>(
+    wip: Partial<'de, BORROW>,
+    deserialize: crate::with_fn::DeserializeWithFn,
+    text: &str,
+) -> Result<Partial<'de, BORROW>, DomDeserializeError<E>> {
+    let shape = wip.shape();
+    let mut failure = None;
+    let result = wip.set_from_function(|ptr| {
+        let mut slot = crate::with_fn::ValueSlot::new(ptr, shape);
+        let message = match deserialize(text, &mut slot) {
+            Ok(()) if slot.is_filled() => return Ok(()),
+            Ok(()) => "no value was set".to_string(),
+            Err(message) => message,
+        };
+        failure = Some(message);
+        Err(facet_reflect::ReflectError::OperationFailed {
+            shape,
+            operation: "deserialize_with",
+        })
+    });
+    match failure {
+        Some(message) => Err(DomDeserializeError::DeserializeWith { message }),
+        None => Ok(result?),
+    }
+}
+
+/// Store `bytes` in `wip`, a list or array of `u8` or an `Option` of one.
+fn set_bytes<'de, const BORROW: bool, E>(
+    mut wip: Partial<'de, BORROW>,
+    codec: &'static str,
+    bytes: Vec<u8>,
+) -> Result<Partial<'de, BORROW>, DomDeserializeError<E>> {
+    match wip.shape().def {
+        Def::Option(_) => {
+            wip = wip.begin_some()?;
+            wip = set_bytes(wip, codec, bytes)?;
+            Ok(wip.end()?)
+        }
+        Def::List(list_def) if list_def.t().id == <u8 as facet_core::Facet>::SHAPE.id => {
+            wip = wip.init_list()?;
+            for byte in bytes {
+                wip = wip.begin_list_item()?.set(byte)?.end()?;
+            }
+            Ok(wip)
+        }
+        Def::Array(array_def) if array_def.t().id == <u8 as facet_core::Facet>::SHAPE.id => {
+            if bytes.len() != array_def.n {
+                return Err(DomDeserializeError::Codec {
+                    codec,
+                    message: format!("expected {} bytes, found {}", array_def.n, bytes.len()),
+                });
+            }
+            wip = wip.init_array()?;
+            for (i, byte) in bytes.into_iter().enumerate() {
+                wip = wip.begin_nth_field(i)?.set(byte)?.end()?;
+            }
+            Ok(wip)
+        }
+        _ => Err(DomDeserializeError::Unsupported(format!(
+            "codec {codec:?} needs a byte list, found {}",
+            wip.shape()
+        ))),
+    }
 }
@@ -0,0 +1,295 @@
+//! Skipping collection items that fail to deserialize.
+//!
+//! Items whose type is marked `skip_on_error` in the parser's format
+//! namespace are read into memory first and tried on a scratch value. Only
+//! items that deserialize cleanly are replayed into the collection; the
+//! others are reported through [`DomParser::item_skipped`] and left out.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+
+use facet_core::Shape;
+use facet_reflect::{Partial, Span};
+
+use super::field_map::get_item_shape;
+use super::{DomDeserializer, ValueSite};
+use crate::error::DomDeserializeError;
+use crate::{DomEvent, DomParser, DomParserExt, VecEventParser};
+
+/// The events of a buffered item, with their spans.
+pub(super) type ItemEvents<'de> = Vec<(DomEvent<'de>, Option<Span>)>;
+
+/// The kind of collection an item is added to.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Collection {
+    List,
+    Set,
+}
+
+/// Whether items of type `shape` are left out of their collection when they
+/// fail to deserialize.
+fn skips_on_error(shape: &Shape, format_ns: Option<&str>) -> bool {
+    shape
+        .attributes
+        .iter()
+        .any(|attr| attr.ns.is_some() && attr.ns == format_ns && attr.key == "skip_on_error")
+}
+
+impl<'de, const BORROW: bool, P> DomDeserializer<'de, BORROW, P>
+where
+    P: DomParser<'de>,
+{
+    /// Add the next child as an item of the list or set `wip` is building.
+    pub(crate) fn deserialize_collection_item(
+        &mut self,
+        mut wip: Partial<'de, BORROW>,
+        collection: Collection,
+        expected_name: Option<Cow<'static, str>>,
+    ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
+        let format_ns = self.parser.format_namespace();
+        let locates_errors = self.parser.locates_errors();
+        let is_element = match self.parser.peek_event_or_eof("collection item")? {
+            DomEvent::NodeStart { tag, .. } => {
+                if locates_errors {
+                    self.items.count_item(tag);
+                }
+                true
+            }
+            _ => false,
+        };
+        if is_element
+            && let Some(shape) = get_item_shape(wip.shape())
+                .filter(|shape| !skips_on_error(shape, format_ns))
+                .and_then(|shape| self.checked_item_shape(shape))
+        {
+            return self.deserialize_checked_item(wip, shape, collection, expected_name);
+        }
+        let item_shape = get_item_shape(wip.shape())
+            .filter(|shape| is_element && skips_on_error(shape, format_ns));
+
+        let Some(item_shape) = item_shape else {
+            wip = begin_item(wip, collection)?;
+            let result = self.deserialize_into_named(wip, expected_name);
+            // Scalar items don't claim their index
+            self.items.next = None;
+            return Ok(result?.end()?);
+        };
+
+        let (tag, events) = self.buffer_item()?;
+        let index = self.items.next;
+        let source: &mut dyn DomParser<'de, Error = P::Error> = &mut self.parser;
+
+        // Trial run on a scratch value, recording what the interceptor
+        // returns so the real run sees the same values without calling it again
+        let mut trial = DomDeserializer::<'de, true, _> {
+            parser: ItemReplay::new(events.clone(), source, None),
+            path: std::mem::take(&mut self.path),
+            items: std::mem::take(&mut self.items),
+            // A failing item is skipped as a whole
+            collected: None,
+            // Unknown fields are recorded by the real run
+            unknown: self.unknown.as_ref().map(|_| Vec::new()),
+            _marker: std::marker::PhantomData,
+        };
+        let outcome = Partial::alloc_shape(item_shape)
+            .map_err(DomDeserializeError::from)
+            .and_then(|scratch| trial.deserialize_into_named(scratch, expected_name.clone()))
+            .and_then(|scratch| Ok(scratch.build()?));
+        let intercepted = trial.parser.intercepted;
+        self.path = trial.path;
+        self.items = trial.items;
+        self.items.next = None;
+
+        if let Err(error) = outcome {
+            let path = self.value_path(ValueSite::Element(&tag));
+            self.parser.item_skipped(&path, &error);
+            return Ok(wip);
+        }
+
+        wip = begin_item(wip, collection)?;
+        self.items.next = index;
+        let source: &mut dyn DomParser<'de, Error = P::Error> = &mut self.parser;
+        let mut replay = DomDeserializer::<'de, BORROW, _> {
+            parser: ItemReplay::new(events, source, Some(intercepted)),
+            path: std::mem::take(&mut self.path),
+            items: std::mem::take(&mut self.items),
+            collected: self.collected.take(),
+            unknown: self.unknown.take(),
+            _marker: std::marker::PhantomData,
+        };
+        let result = replay.deserialize_into_named(wip, expected_name);
+        self.path = replay.path;
+        self.items = replay.items;
+        self.collected = replay.collected;
+        self.unknown = replay.unknown;
+        self.items.next = None;
+        Ok(result?.end()?)
+    }
+
+    /// Read the element at the cursor, through its `NodeEnd`, into memory.
+    ///
+    /// Returns the element's tag along with its events.
+    pub(super) fn buffer_item(
+        &mut self,
+    ) -> Result<(String, ItemEvents<'de>), DomDeserializeError<P::Error>> {
+        let mut tag = String::new();
+        let mut events = Vec::new();
+        let mut depth = 0usize;
+        loop {
+            let event = self.parser.next_event_or_eof("end of collection item")?;
+            match &event {
+                DomEvent::NodeStart { tag: name, .. } => {
+                    if depth == 0 {
+                        tag = name.to_string();
+                    }
+                    depth += 1;
+                }
+                DomEvent::NodeEnd => depth -= 1,
+                _ => {}
+            }
+            events.push((event, self.parser.current_span()));
+            if depth == 0 {
+                return Ok((tag, events));
+            }
+        }
+    }
+}
+
+pub(super) fn begin_item<'de, const BORROW: bool>(
+    wip: Partial<'de, BORROW>,
+    collection: Collection,
+) -> Result<Partial<'de, BORROW>, facet_reflect::ReflectError> {
+    match collection {
+        Collection::List => wip.begin_list_item(),
+        Collection::Set => wip.begin_set_item(),
+    }
+}
+
+/// Replays one buffered item, taking every setting from the parser it was
+/// read from.
+pub(super) struct ItemReplay<'p, 'de, E> {
+    events: VecEventParser<'de>,
+    source: &'p mut (dyn DomParser<'de, Error = E> + 'p),
+    /// Interceptor results, recorded by the trial run (`replaying == false`)
+    /// and handed back in the same order by the real one
+    intercepted: VecDeque<Option<String>>,
+    replaying: bool,
+}
+
+impl<'p, 'de, E> ItemReplay<'p, 'de, E> {
+    pub(super) fn new(
+        events: ItemEvents<'de>,
+        source: &'p mut (dyn DomParser<'de, Error = E> + 'p),
+        intercepted: Option<VecDeque<Option<String>>>,
+    ) -> Self {
+        Self {
+            events: VecEventParser::from_events(events.into()),
+            source,
+            replaying: intercepted.is_some(),
+            intercepted: intercepted.unwrap_or_default(),
+        }
+    }
+}
+
+impl<'p, 'de, E> DomParser<'de> for ItemReplay<'p, 'de, E>
+where
+    E: std::error::Error + 'static,
+{
+    type Error = E;
+
+    fn next_event(&mut self) -> Result<Option<DomEvent<'de>>, Self::Error> {
+        self.events.next_event().map_err(|e: Infallible| match e {})
+    }
+
+    fn peek_event(&mut self) -> Result<Option<&DomEvent<'de>>, Self::Error> {
+        self.events.peek_event().map_err(|e: Infallible| match e {})
+    }
+
+    fn skip_node(&mut self) -> Result<(), Self::Error> {
+        self.events.skip_node().map_err(|e: Infallible| match e {})
+    }
+
+    fn current_span(&self) -> Option<Span> {
+        self.events.current_span()
+    }
+
+    fn is_lenient(&self) -> bool {
+        self.source.is_lenient()
+    }
+
+    fn format_namespace(&self) -> Option<&'static str> {
+        self.source.format_namespace()
+    }
+
+    fn schema_version(&self) -> Option<&str> {
+        self.source.schema_version()
+    }
+
+    fn reserved_attribute_namespace(&self) -> Option<&str> {
+        self.source.reserved_attribute_namespace()
+    }
+
+    fn intercepts_values(&self) -> bool {
+        self.source.intercepts_values()
+    }
+
+    fn has_context(&self) -> bool {
+        self.source.has_context()
+    }
+
+    fn keeps_comments(&self) -> bool {
+        self.source.keeps_comments()
+    }
+
+    fn keeps_processing_instructions(&self) -> bool {
+        self.source.keeps_processing_instructions()
+    }
+
+    fn duplicate_policy(&self) -> crate::DuplicatePolicy {
+        self.source.duplicate_policy()
+    }
+
+    fn locates_errors(&self) -> bool {
+        self.source.locates_errors()
+    }
+
+    fn source_text(&self) -> Option<&str> {
+        self.source.source_text()
+    }
+
+    fn peeked_attribute(&self, namespace: &str, name: &str) -> Option<&str> {
+        self.events.peeked_attribute(namespace, name)
+    }
+
+    fn intercept_value(&mut self, path: &str, text: &str, shape: &'static Shape) -> Option<String> {
+        if self.replaying {
+            return self.intercepted.pop_front().flatten();
+        }
+        let replacement = self.source.intercept_value(path, text, shape);
+        self.intercepted.push_back(replacement.clone());
+        replacement
+    }
+
+    fn resolve_in_context(
+        &mut self,
+        path: &str,
+        text: &str,
+        shape: &'static Shape,
+    ) -> Option<String> {
+        // Recorded along with the interceptor's results, in call order
+        if self.replaying {
+            return self.intercepted.pop_front().flatten();
+        }
+        let replacement = self.source.resolve_in_context(path, text, shape);
+        self.intercepted.push_back(replacement.clone());
+        replacement
+    }
+
+    fn item_skipped(&mut self, path: &str, error: &dyn std::error::Error) {
+        // Nested items fail the same way in both runs; report them once
+        if self.replaying {
+            self.source.item_skipped(path, error);
+        }
+    }
+}
@@ -2,19 +2,17 @@
 
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
 use facet_core::{Def, Shape, StructKind, StructType, Type, UserType};
 use facet_reflect::Partial;
 
 use crate::error::DomDeserializeError;
 use crate::trace;
-use crate::{AttributeRecord, DomEvent, DomParser, DomParserExt};
+use crate::{AttributeRecord, ChildOrder, DomEvent, DomParser, DomParserExt, DuplicatePolicy};
 
-use super::PartialDeserializeExt;
-use super::field_map::{
-    FieldInfo, FlattenedChildInfo, StructFieldMap, get_item_type_default_element_name,
-    get_item_type_rename,
-};
+use super::field_map::{FieldInfo, FlattenedChildInfo, StructFieldMap};
+use super::{Collection, PartialDeserializeExt, ValueSite, error_text};
 
 /// State for a flat sequence field being deserialized.
 pub(crate) enum SeqState {
@@ -29,7 +27,7 @@ pub(crate) enum SeqState {
 /// Methods take `wip` as input and return it as output, threading it through.
 pub(crate) struct StructDeserializer<'de, 'p, const BORROW: bool, P: DomParser<'de>> {
     dom_deser: &'p mut super::DomDeserializer<'de, BORROW, P>,
-    field_map: StructFieldMap,
+    field_map: Rc<StructFieldMap>,
     struct_def: &'static StructType,
 
     /// Whether deferred mode is enabled (for flattened fields)
@@ -53,6 +51,9 @@ pub(crate) struct StructDeserializer<'de, 'p, const BORROW: bool, P: DomParser<'
     /// Whether we've started the xml::attribute catch-all list (for `Vec<String>` attribute fields)
     attributes_list_started: bool,
 
+    /// Which single-valued element fields have been read (for duplicate handling)
+    seen_elements: HashSet<usize>,
+
     /// Which flattened element maps have been initialized
     started_flattened_maps: HashSet<usize>,
 
@@ -74,8 +75,15 @@ pub(crate) struct StructDeserializer<'de, 'p, const BORROW: bool, P: DomParser<'
     /// Tag from NodeStart (for tracing and xml::tag field)
     tag: Cow<'de, str>,
 
+    /// Namespace URI and prefix from NodeStart (for xml::tag_ns and xml::tag_prefix fields)
+    tag_namespace: Option<Cow<'de, str>>,
+    tag_prefix: Option<String>,
+
     /// Expected element name for root element validation
     expected_name: Cow<'static, str>,
+
+    /// Document order of the children, for `ordered` structs with a ChildOrder field
+    order: Option<ChildOrder>,
 }
 
 impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p, BORROW, P> {
@@ -88,7 +96,8 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
         deny_unknown_fields: bool,
     ) -> Self {
         let format_ns = dom_deser.parser.format_namespace();
-        let field_map = StructFieldMap::new(struct_def, ns_all, rename_all, format_ns);
+        let version = dom_deser.parser.schema_version();
+        let field_map = StructFieldMap::cached(struct_def, ns_all, rename_all, format_ns, version);
         Self {
             dom_deser,
             field_map,
@@ -100,6 +109,7 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
             started_elements_lists: HashSet::new(),
             text_list_started: false,
             attributes_list_started: false,
+            seen_elements: HashSet::new(),
             started_flattened_maps: HashSet::new(),
             started_flattened_attr_maps: HashSet::new(),
             flattened_enum_list_started: false,
@@ -107,7 +117,10 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
             deny_unknown_fields,
             tuple_position: 0,
             tag: Cow::Borrowed(""),
+            tag_namespace: None,
+            tag_prefix: None,
             expected_name,
+            order: None,
         }
     }
 
@@ -164,7 +177,8 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
             }
         }
 
-        self.tag = self.parser().expect_node_start()?;
+        (self.tag, self.tag_namespace) = self.parser().expect_node_start_with_namespace()?;
+        self.tag_prefix = self.parser().element_prefix().map(str::to_string);
 
         // Validate root element name matches expected, unless struct has a tag field
         // (which means it accepts any element name) or an other field (fallback for mismatches)
@@ -194,17 +208,13 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
             }
         }
 
-        // Set the tag field if present (xml::tag or html::tag)
-        if let Some(info) = &self.field_map.tag_field {
-            let idx = info.idx;
-            trace!("→ .{}", info.field.name);
-            let tag = self.tag.clone();
-            wip = self
-                .dom_deser
-                .set_string_value(wip.begin_nth_field(idx)?, tag)?
-                .end()?;
+        wip = self.set_tag_fields(wip)?;
+        let format_ns = self.dom_deser.parser.format_namespace();
+        if self.field_map.child_order_field.is_some()
+            && crate::child_order::is_ordered(wip.shape(), format_ns)
+        {
+            self.order = Some(ChildOrder::default());
         }
-
         wip = self.process_attributes(wip)?;
 
         self.parser().expect_children_start()?;
@@ -220,6 +230,35 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
         Ok(wip)
     }
 
+    /// Set the fields capturing the element's name: the tag (xml::tag or
+    /// html::tag), and its namespace and prefix when the element has them.
+    fn set_tag_fields(
+        &mut self,
+        mut wip: Partial<'de, BORROW>,
+    ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
+        let captured = [
+            (&self.field_map.tag_field, Some(self.tag.clone())),
+            (
+                &self.field_map.tag_namespace_field,
+                self.tag_namespace.clone(),
+            ),
+            (
+                &self.field_map.tag_prefix_field,
+                self.tag_prefix.clone().map(Cow::Owned),
+            ),
+        ];
+        for (info, value) in captured {
+            if let (Some(info), Some(value)) = (info, value) {
+                trace!("→ .{}", info.field.name);
+                wip = self
+                    .dom_deser
+                    .set_string_value(wip.begin_nth_field(info.idx)?, value)?
+                    .end()?;
+            }
+        }
+        Ok(wip)
+    }
+
     fn process_attributes(
         &mut self,
         mut wip: Partial<'de, BORROW>,
@@ -235,15 +274,51 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
                         value,
                         namespace,
                     } = self.parser().expect_attribute()?;
+                    if let Some(reserved_ns) = self.dom_deser.parser.reserved_attribute_namespace()
+                        && namespace.as_deref() == Some(reserved_ns)
+                    {
+                        // Reserved attributes only go to fields declared in their namespace
+                        let idx = self
+                            .field_map
+                            .find_attribute_in_namespace(&name, reserved_ns)
+                            .map(|info| info.idx);
+                        if let Some(idx) = idx {
+                            wip = self
+                                .dom_deser
+                                .set_string_value_with_proxy(
+                                    wip.begin_nth_field(idx)?,
+                                    value,
+                                    ValueSite::Attribute(&name),
+                                )?
+                                .end()?;
+                        } else {
+                            trace!(name = %name, "dropping unclaimed reserved attribute");
+                        }
+                        continue;
+                    }
+
                     if let Some(info) = self
                         .field_map
                         .find_attribute(&name, namespace.as_ref().map(|c| c.as_ref()))
                     {
                         trace!("→ .{}", info.field.name);
+                        if let Some(shape) = self.dom_deser.checked_shape(info.field)
+                            && !self.dom_deser.check_value(
+                                shape,
+                                &value,
+                                ValueSite::Attribute(&name),
+                            )?
+                        {
+                            continue;
+                        }
                         // Use set_string_value_with_proxy to handle field-level proxies
                         wip = self
                             .dom_deser
-                            .set_string_value_with_proxy(wip.begin_nth_field(info.idx)?, value)?
+                            .set_string_value_with_proxy(
+                                wip.begin_nth_field(info.idx)?,
+                                value,
+                                ValueSite::Attribute(&name),
+                            )?
                             .end()?;
                     } else if let Some(flattened) = self
                         .field_map
@@ -267,6 +342,7 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
                             .set_string_value_with_proxy(
                                 parent_wip.begin_nth_field(child_idx)?,
                                 value,
+                                ValueSite::Attribute(&name),
                             )?
                             .end()?;
                         if flattened.parent_is_option {
@@ -296,14 +372,14 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
                             if let Some(info) = map_info {
                                 trace!("→ .{}[{}]", info.field.name, name);
                                 self.started_flattened_attr_maps.insert(info.idx);
-                                wip = wip
-                                    .begin_nth_field(info.idx)?
-                                    .init_map()?
-                                    .begin_key()?
-                                    .set::<String>(name.to_string())?
-                                    .end()?
-                                    .begin_value()?
-                                    .set::<String>(value.to_string())?
+                                wip = wip.begin_nth_field(info.idx)?.init_map()?;
+                                wip = self
+                                    .dom_deser
+                                    .set_string_value(wip.begin_key()?, name.clone())?
+                                    .end()?;
+                                wip = self
+                                    .dom_deser
+                                    .set_string_value(wip.begin_value()?, value.clone())?
                                     .end()?
                                     .end()?;
                                 handled = true;
@@ -331,14 +407,14 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
                                     wip = wip.begin_some()?;
                                 }
                                 // Always call init_map() - in deferred mode it's idempotent
-                                wip = wip
-                                    .begin_nth_field(info.child_idx)?
-                                    .init_map()?
-                                    .begin_key()?
-                                    .set::<String>(name.to_string())?
-                                    .end()?
-                                    .begin_value()?
-                                    .set::<String>(value.to_string())?
+                                wip = wip.begin_nth_field(info.child_idx)?.init_map()?;
+                                wip = self
+                                    .dom_deser
+                                    .set_string_value(wip.begin_key()?, name.clone())?
+                                    .end()?;
+                                wip = self
+                                    .dom_deser
+                                    .set_string_value(wip.begin_value()?, value.clone())?
                                     .end()?
                                     .end()?;
                                 // End parent (and option if needed)
@@ -351,9 +427,12 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
                         }
 
                         if !handled && self.deny_unknown_fields {
-                            return Err(DomDeserializeError::UnknownAttribute {
-                                name: name.to_string(),
-                            });
+                            self.dom_deser.unknown_field(
+                                ValueSite::Attribute(&name),
+                                DomDeserializeError::UnknownAttribute {
+                                    name: name.to_string(),
+                                },
+                            )?;
                         }
                     }
                 }
@@ -392,8 +471,8 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
                     let namespace = namespace.clone();
                     wip = self.handle_child_element(wip, &tag, namespace.as_deref())?;
                 }
-                DomEvent::Comment(_) => {
-                    self.parser().expect_comment()?;
+                DomEvent::Comment(_) | DomEvent::ProcessingInstruction { .. } => {
+                    wip = self.handle_markup(wip)?;
                 }
                 other => {
                     return Err(DomDeserializeError::TypeMismatch {
@@ -443,6 +522,10 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
                 .unwrap_or(false);
 
             if can_accept || !self.parser().is_lenient() {
+                let field = self.field_map.flattened_enum.as_ref();
+                if let Some(field) = field.map(|info| info.field_info.field.name) {
+                    self.record_child(field, None);
+                }
                 wip = wip.begin_list_item()?;
                 wip = self
                     .dom_deser
@@ -450,9 +533,10 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
                     .end()?;
             }
             // else: lenient mode and no text variant - silently discard
-        } else if let Some(info) = &self.field_map.text_field {
+        } else if let Some(info) = self.field_map.text_field.clone() {
             if info.is_list || info.is_set {
                 // Vec<String> or HashSet<String> with xml::text - each text node is a list item
+                self.record_child(info.field.name, None);
                 if !self.text_list_started {
                     trace!("→ .{}[]", info.field.name);
                     wip = wip.begin_nth_field(info.idx)?.init_list()?;
@@ -462,7 +546,13 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
                 wip = self.dom_deser.set_string_value(wip, text)?.end()?;
             } else {
                 // Single String with xml::text - accumulate text
-                self.text_content.push_str(&text);
+                self.record_child(info.field.name, Some(text.len()));
+                if self.text_content.is_empty() {
+                    // Take the first chunk as is, so a single large text node isn't copied
+                    self.text_content = text.into_owned();
+                } else {
+                    self.text_content.push_str(&text);
+                }
             }
         } else if !self.field_map.elements_fields.is_empty() {
             // html::elements / xml::elements collects child *elements*, not text nodes.
@@ -470,6 +560,7 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
         } else if let Some(enum_info) = &self.field_map.flattened_enum {
             // Flattened enum list with Text variant - start or continue the list
             let field_idx = enum_info.field_idx;
+            let field_name = enum_info.field_info.field.name;
             let is_list = enum_info.field_info.is_list;
 
             // Check if the enum can accept text
@@ -483,6 +574,7 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
             if !can_accept && self.parser().is_lenient() {
                 // Lenient mode and no text variant - silently discard
             } else if is_list {
+                self.record_child(field_name, None);
                 if !self.flattened_enum_list_started {
                     // First text/element: start the list
                     trace!(field_idx, "starting flattened enum list for text");
@@ -519,6 +611,83 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
         Ok(wip)
     }
 
+    /// Handle a comment or processing instruction among the children.
+    ///
+    /// It is kept as an item of the flattened enum list when the parser keeps
+    /// that kind of node and the enum has a variant marked for it (`comment`
+    /// or `processing_instruction`), and skipped otherwise.
+    fn handle_markup(
+        &mut self,
+        mut wip: Partial<'de, BORROW>,
+    ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
+        let event = self
+            .parser()
+            .next_event_or_eof("comment or processing instruction")?;
+        let (key, keep) = match &event {
+            DomEvent::Comment(_) => ("comment", self.parser().keeps_comments()),
+            _ => (
+                "processing_instruction",
+                self.parser().keeps_processing_instructions(),
+            ),
+        };
+        let Some(enum_info) = self
+            .field_map
+            .flattened_enum
+            .as_ref()
+            .filter(|info| keep && info.field_info.is_list)
+        else {
+            return Ok(wip);
+        };
+        let format_ns = self.dom_deser.parser.format_namespace();
+        let variant_idx = Self::get_list_element_shape(enum_info.field_info.field.shape())
+            .and_then(|shape| match &shape.ty {
+                Type::User(UserType::Enum(def)) => def
+                    .variants
+                    .iter()
+                    .position(|v| v.get_attr(format_ns, key).is_some()),
+                _ => None,
+            });
+        let Some(variant_idx) = variant_idx else {
+            return Ok(wip);
+        };
+
+        let field_idx = enum_info.field_idx;
+        let field_name = enum_info.field_info.field.name;
+        self.record_child(field_name, None);
+        if !self.flattened_enum_list_started {
+            trace!(field_idx, "starting flattened enum list for {key}");
+            wip = wip.begin_nth_field(field_idx)?.init_list()?;
+            self.flattened_enum_list_started = true;
+            self.flattened_enum_list_active = true;
+        } else if !self.flattened_enum_list_active {
+            trace!(field_idx, "re-entering flattened enum list for {key}");
+            wip = wip.begin_nth_field(field_idx)?.init_list()?;
+            self.flattened_enum_list_active = true;
+        }
+
+        wip = wip.begin_list_item()?.select_nth_variant(variant_idx)?;
+        match event {
+            DomEvent::ProcessingInstruction { target, data } => {
+                wip = self
+                    .dom_deser
+                    .set_string_value(wip.begin_field("target")?, target)?
+                    .end()?;
+                wip = self
+                    .dom_deser
+                    .set_string_value(wip.begin_field("data")?, data)?
+                    .end()?;
+            }
+            DomEvent::Comment(text) => {
+                wip = self
+                    .dom_deser
+                    .set_string_value(wip.begin_nth_field(0)?, text)?
+                    .end()?;
+            }
+            _ => {}
+        }
+        Ok(wip.end()?)
+    }
+
     fn handle_child_element(
         &mut self,
         wip: Partial<'de, BORROW>,
@@ -526,13 +695,22 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
         namespace: Option<&str>,
     ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
         trace!(tag = %tag, namespace = ?namespace, "got child NodeStart");
+        if self.order.is_some()
+            && let Some(field) = self.child_field(tag, namespace)
+        {
+            self.record_child(field, None);
+        }
 
         if let Some(info) = self.field_map.find_element(tag, namespace) {
             // Check if the field has a field-level proxy - if so, the XML representation
             // is the proxy's shape, not the actual field type. A Vec<u32> with a string proxy
             // should be deserialized as a scalar (string), not as a flat sequence.
+            // The same goes for bytes read through a text codec and values
+            // built by a `deserialize_with` function.
             let format_ns = self.dom_deser.parser.format_namespace();
-            let has_field_proxy = info.field.effective_proxy(format_ns).is_some();
+            let has_field_proxy = info.field.effective_proxy(format_ns).is_some()
+                || crate::codec::field_codec(info.field, format_ns).is_some()
+                || crate::with_fn::field_deserialize_with(info.field, format_ns).is_some();
 
             if !has_field_proxy && (info.is_list || info.is_array || info.is_set || info.is_tuple) {
                 self.handle_flat_sequence(
@@ -567,6 +745,36 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
         }
     }
 
+    /// The field a child element named `tag` goes to, following the same
+    /// order as [`Self::handle_child_element`]. `None` for tuple items and
+    /// entries of flattened maps.
+    fn child_field(&self, tag: &str, namespace: Option<&str>) -> Option<&'static str> {
+        let field_map = &self.field_map;
+        if let Some(info) = field_map.find_element(tag, namespace) {
+            Some(info.field.name)
+        } else if field_map.is_tuple() && tag == "item" {
+            None
+        } else if let Some(flattened) = field_map.find_flattened_child(tag, namespace) {
+            Some(flattened.child_info.field.name)
+        } else if let Some(info) = &field_map.flattened_enum {
+            Some(info.field_info.field.name)
+        } else if let Some(info) = field_map.elements_fields.get(tag) {
+            Some(info.field.name)
+        } else {
+            field_map
+                .catch_all_elements_field
+                .as_ref()
+                .map(|info| info.field.name)
+        }
+    }
+
+    /// Record that a child went to `field`, if document order is kept.
+    fn record_child(&mut self, field: &str, text_len: Option<usize>) {
+        if let Some(order) = &mut self.order {
+            order.push(field, text_len);
+        }
+    }
+
     fn leave_active_sequence(
         &mut self,
         mut wip: Partial<'de, BORROW>,
@@ -686,14 +894,16 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
         // Add item
         if is_list {
             trace!(idx, field_name = %field.name, "adding item to flat list");
-            wip = wip.begin_list_item()?;
-            wip = self.deserialize_sequence_item(wip, field)?;
-            wip = wip.end()?;
+            let name = self.field_map.item_name(field);
+            wip = self
+                .dom_deser
+                .deserialize_collection_item(wip, Collection::List, Some(name))?;
         } else if is_set {
             trace!(idx, field_name = %field.name, "adding item to flat set");
-            wip = wip.begin_set_item()?;
-            wip = self.deserialize_sequence_item(wip, field)?;
-            wip = wip.end()?;
+            let name = self.field_map.item_name(field);
+            wip = self
+                .dom_deser
+                .deserialize_collection_item(wip, Collection::Set, Some(name))?;
         } else if is_tuple {
             // Tuples: access by position using begin_nth_field
             let item_idx = match self.started_seqs.get(&idx) {
@@ -728,36 +938,6 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
         Ok(wip)
     }
 
-    /// Deserialize a sequence item (list/set element).
-    ///
-    /// The element name comes from the field (rename attribute, item type's rename, item type's name,
-    /// or singularized field name).
-    fn deserialize_sequence_item(
-        &mut self,
-        wip: Partial<'de, BORROW>,
-        field: &'static facet_core::Field,
-    ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
-        // Compute expected element name from field:
-        // 1. field.rename (explicit rename on the field)
-        // 2. item type's rename (from #[facet(rename = "...")] on the item type)
-        // 3. item type's default name (type_identifier in lowerCamelCase)
-        // 4. singularized(lowerCamelCase(field.name))
-        let expected_name: Cow<'static, str> = if field.rename.is_some() {
-            Cow::Borrowed(field.effective_name())
-        } else if let Some(item_rename) = get_item_type_rename(field.shape()) {
-            Cow::Borrowed(item_rename)
-        } else if let Some(item_element_name) = get_item_type_default_element_name(field.shape()) {
-            Cow::Owned(item_element_name)
-        } else {
-            // For list fields without rename, use singularized lowerCamelCase
-            let camel = crate::naming::to_element_name(field.name);
-            Cow::Owned(facet_singularize::singularize(&camel))
-        };
-
-        // Use deserialize_with_name - handles proxies and all type variants uniformly
-        wip.deserialize_with_name(self.dom_deser, expected_name)
-    }
-
     fn handle_scalar_element(
         &mut self,
         mut wip: Partial<'de, BORROW>,
@@ -768,12 +948,40 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
 
         let field = &self.struct_def.fields[idx];
 
-        // Compute expected element name from field: rename > lowerCamelCase(field.name)
-        let expected_name: Cow<'static, str> = if field.rename.is_some() {
-            Cow::Borrowed(field.effective_name())
-        } else {
-            crate::naming::to_element_name(field.name)
-        };
+        // Expected element name from field: rename > lowerCamelCase(field.name)
+        let expected_name = self.field_map.element_name(field);
+
+        if !self.seen_elements.insert(idx) {
+            let format_ns = self.dom_deser.parser.format_namespace();
+            let policy = field
+                .get_attr(format_ns, "on_duplicate")
+                .and_then(|attr| attr.get_as::<&str>().copied())
+                .and_then(DuplicatePolicy::from_name)
+                .unwrap_or_else(|| self.dom_deser.parser.duplicate_policy());
+            match policy {
+                DuplicatePolicy::Last => {}
+                DuplicatePolicy::First => {
+                    trace!(idx, "skipping duplicate element");
+                    self.parser()
+                        .skip_node()
+                        .map_err(DomDeserializeError::Parser)?;
+                    return Ok(wip);
+                }
+                DuplicatePolicy::Error => {
+                    return Err(DomDeserializeError::DuplicateElement {
+                        path: self
+                            .dom_deser
+                            .value_path(ValueSite::Element(&expected_name)),
+                    });
+                }
+            }
+        }
+
+        if let Some(shape) = self.dom_deser.checked_shape(field) {
+            return self
+                .dom_deser
+                .deserialize_checked_field(wip, idx, shape, expected_name);
+        }
 
         // Use deserialize_with_name - handles Options, proxies, and all type variants uniformly
         wip = wip
@@ -905,9 +1113,10 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
             self.started_elements_lists.insert(idx);
         }
         trace!("adding element to elements collection");
-        wip = wip.begin_list_item()?;
-        wip = self.deserialize_sequence_item(wip, info.field)?;
-        wip = wip.end()?;
+        let name = self.field_map.item_name(info.field);
+        wip = self
+            .dom_deser
+            .deserialize_collection_item(wip, Collection::List, Some(name))?;
         Ok(wip)
     }
 
@@ -932,14 +1141,14 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
             let element_text = self.read_element_text()?;
 
             self.started_flattened_maps.insert(idx);
-            wip = wip
-                .begin_nth_field(idx)?
-                .init_map()?
-                .begin_key()?
-                .set::<String>(tag.to_string())?
-                .end()?
-                .begin_value()?
-                .set::<String>(element_text)?
+            wip = wip.begin_nth_field(idx)?.init_map()?;
+            wip = self
+                .dom_deser
+                .set_string_value(wip.begin_key()?, Cow::Owned(tag.to_string()))?
+                .end()?;
+            wip = self
+                .dom_deser
+                .set_string_value(wip.begin_value()?, Cow::Owned(element_text))?
                 .end()?
                 .end()?;
             Ok(wip)
@@ -994,9 +1203,12 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
         tag: &str,
     ) -> Result<Partial<'de, BORROW>, DomDeserializeError<P::Error>> {
         if wip.shape().has_deny_unknown_fields_attr() {
-            return Err(DomDeserializeError::UnknownElement {
-                tag: tag.to_string(),
-            });
+            self.dom_deser.unknown_field(
+                ValueSite::Element(tag),
+                DomDeserializeError::UnknownElement {
+                    tag: tag.to_string(),
+                },
+            )?;
         }
         trace!(tag, "skipping unknown element");
         self.parser()
@@ -1064,6 +1276,8 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
 
             // The tag is already consumed, copy it to the inner deserializer
             inner_deser.tag = self.tag.clone();
+            inner_deser.tag_namespace = self.tag_namespace.clone();
+            inner_deser.tag_prefix = self.tag_prefix.clone();
 
             // Enable deferred mode if the inner struct has flatten
             if inner_deser.field_map.has_flatten && !wip.is_deferred() {
@@ -1072,16 +1286,8 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
                 inner_deser.using_deferred = true;
             }
 
-            // Set tag field if the inner type has one
-            if let Some(info) = &inner_deser.field_map.tag_field {
-                let idx = info.idx;
-                trace!("→ .{}", info.field.name);
-                let tag = inner_deser.tag.clone();
-                wip = inner_deser
-                    .dom_deser
-                    .set_string_value(wip.begin_nth_field(idx)?, tag)?
-                    .end()?;
-            }
+            // Set tag fields if the inner type has them
+            wip = inner_deser.set_tag_fields(wip)?;
 
             // Process attributes
             wip = inner_deser.process_attributes(wip)?;
@@ -1145,13 +1351,14 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
 
         // Initialize any list/set element fields that were never started as empty
         // (e.g., Vec<Point> with no matching <point> elements should be empty, not uninitialized)
+        // Fields with a `default` are left for it to fill in, like any other missing field.
         // Collect to Vec first to avoid borrowing conflict with self.started_seqs
         let uninitialized_lists: Vec<_> = self
             .field_map
             .list_set_element_fields()
             .filter(|(idx, info)| {
-                // Skip fields that were already started
-                if self.started_seqs.contains_key(idx) {
+                // Skip fields that were already started or have a default
+                if self.started_seqs.contains_key(idx) || info.field.has_default() {
                     return false;
                 }
                 // Skip fields with field-level proxy (treated as scalars)
@@ -1186,7 +1393,7 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
         // Then initialize any elements fields that were never started as empty lists
         for info in self.field_map.elements_fields.values() {
             let idx = info.idx;
-            if !self.started_elements_lists.contains(&idx) {
+            if !self.started_elements_lists.contains(&idx) && !info.field.has_default() {
                 trace!(idx, field_name = %info.field.name, "initializing empty elements list");
                 wip = wip.begin_nth_field(idx)?.init_list()?.end()?;
             }
@@ -1194,7 +1401,7 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
         // Also handle catch_all_elements_field (xml::elements with item type having xml::tag)
         if let Some(info) = &self.field_map.catch_all_elements_field {
             let idx = info.idx;
-            if !self.started_elements_lists.contains(&idx) {
+            if !self.started_elements_lists.contains(&idx) && !info.field.has_default() {
                 trace!(idx, field_name = %info.field.name, "initializing empty catch-all elements list");
                 wip = wip.begin_nth_field(idx)?.init_list()?.end()?;
             }
@@ -1206,7 +1413,7 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
                 // End the attributes list (Vec<String> with xml::attribute catch-all)
                 trace!(path = %wip.path(), "ending attributes list");
                 wip = wip.end()?;
-            } else {
+            } else if !info.field.has_default() {
                 // Empty attributes list - initialize empty
                 let idx = info.idx;
                 trace!(idx, field_name = %info.field.name, "initializing empty attributes list");
@@ -1221,18 +1428,32 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
                 trace!(path = %wip.path(), "ending text list");
                 wip = wip.end()?;
             } else if info.is_list || info.is_set {
-                // Empty text list - initialize empty
-                let idx = info.idx;
-                trace!(idx, field_name = %info.field.name, "initializing empty text list");
-                wip = wip.begin_nth_field(idx)?.init_list()?.end()?;
+                // Empty text list - initialize empty, unless a default fills it in
+                if !info.field.has_default() {
+                    let idx = info.idx;
+                    trace!(idx, field_name = %info.field.name, "initializing empty text list");
+                    wip = wip.begin_nth_field(idx)?.init_list()?.end()?;
+                }
             } else if !self.text_content.is_empty() {
                 // Single String with accumulated text
                 let idx = info.idx;
                 trace!(idx, field_name = %info.field.name, text_len = self.text_content.len(), "setting text field");
                 let text = std::mem::take(&mut self.text_content);
+                wip = wip.begin_nth_field(idx)?;
+                let text = self.dom_deser.intercept(
+                    Cow::Owned(text),
+                    ValueSite::Text,
+                    wip.shape(),
+                    Some(info.field),
+                );
+                let quoted = error_text(&text);
                 wip = self
                     .dom_deser
-                    .set_string_value(wip.begin_nth_field(idx)?, Cow::Owned(text))?
+                    .set_string_value(wip, text)
+                    .map_err(|e| {
+                        self.dom_deser
+                            .invalid_value(e, ValueSite::Text, Some(&quoted))
+                    })?
                     .end()?;
             }
         }
@@ -1248,7 +1469,7 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
             } else if self.flattened_enum_list_started {
                 // List was started but we left it - it's already closed, nothing to do
                 trace!(path = %wip.path(), "flattened enum list already closed");
-            } else {
+            } else if !enum_info.field_info.field.has_default() {
                 // Empty list - initialize empty
                 let idx = enum_info.field_idx;
                 trace!(idx, "initializing empty flattened enum list");
@@ -1256,6 +1477,14 @@ impl<'de, 'p, const BORROW: bool, P: DomParser<'de>> StructDeserializer<'de, 'p,
             }
         }
 
+        // Store the document order (empty unless the struct is ordered)
+        if let Some(info) = &self.field_map.child_order_field {
+            let idx = info.idx;
+            let order = self.order.take().unwrap_or_default();
+            trace!(idx, children = order.len(), "setting child order field");
+            wip = wip.begin_nth_field(idx)?.set(order)?.end()?;
+        }
+
         Ok(wip)
     }
 }
@@ -49,8 +49,183 @@ pub enum DomDeserializeError<E> {
         name: &'static str,
     },
 
+    /// An `xsi:type` attribute names no variant of its enum.
+    UnknownType {
+        /// The attribute's value, as written.
+        type_name: String,
+    },
+
     /// Unsupported type.
     Unsupported(String),
+
+    /// A value could not be converted to its field's type (parse failure,
+    /// `NonZero*` zero, rejected `TryFrom` proxy, ...).
+    InvalidValue {
+        /// Where the value was found, e.g. `order/item/qty` or `order/item/@id`.
+        path: String,
+        /// The offending text, when known.
+        text: Option<String>,
+        /// The underlying conversion error.
+        source: Box<DomDeserializeError<E>>,
+    },
+
+    /// A field's text codec rejected its text.
+    Codec {
+        /// The codec name, as given in the field's `codec` attribute, or the
+        /// [`TextCodec::name`](crate::codec::TextCodec::name) of a `codec_with` codec.
+        codec: &'static str,
+        /// Why decoding failed.
+        message: String,
+    },
+
+    /// A field's `deserialize_with` function rejected its text.
+    DeserializeWith {
+        /// The message returned by the function.
+        message: String,
+    },
+
+    /// A container-level assertion rejected the deserialized value.
+    AssertionFailed {
+        /// Where the rejected value was found, e.g. `booking/stays[1]`.
+        path: String,
+        /// The message returned by the assertion.
+        message: String,
+    },
+
+    /// An error together with where in the document it happened.
+    ///
+    /// Only produced by parsers that [locate errors](crate::DomParser::locates_errors).
+    Located {
+        /// Where the error happened.
+        location: Box<ErrorLocation>,
+        /// The error itself.
+        source: Box<DomDeserializeError<E>>,
+    },
+
+    /// A field-level validation rejected a value that parsed successfully.
+    Validation {
+        /// Where the rejected value was found, e.g. `order/item[1]/qty` or
+        /// `order/@id`.
+        path: String,
+        /// The message returned by the validation function.
+        message: String,
+    },
+
+    /// An element for a single-valued field appeared again, under
+    /// [`DuplicatePolicy::Error`](crate::DuplicatePolicy::Error).
+    DuplicateElement {
+        /// Where the repeated element was found, e.g. `order/customer`.
+        path: String,
+    },
+
+    /// Every unknown element and attribute of `deny_unknown_fields` types,
+    /// when the deserializer
+    /// [collects unknown fields](crate::DomDeserializer::collect_unknown_fields).
+    UnknownFields(Vec<UnknownItem>),
+}
+
+/// An element or attribute no field accepts, in a `deny_unknown_fields`
+/// type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownItem {
+    /// Where it was found, e.g. `order/item/colour` or `order/@ref`.
+    pub path: String,
+    /// The element's tag or the attribute's name.
+    pub name: String,
+    /// Whether it is an attribute rather than an element.
+    pub attribute: bool,
+}
+
+impl<E> DomDeserializeError<E> {
+    /// Where the error happened, if it was located.
+    pub fn location(&self) -> Option<&ErrorLocation> {
+        match self {
+            Self::Located { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// The error without its location.
+    pub fn into_unlocated(self) -> Self {
+        match self {
+            Self::Located { source, .. } => *source,
+            other => other,
+        }
+    }
+}
+
+/// Where in a document a deserialization error happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorLocation {
+    /// Path of the innermost element being deserialized, e.g.
+    /// `root/items/item[3]`. Items of a list carry their index, from 0.
+    pub path: String,
+    /// Byte span of the last event read before the error, when the parser
+    /// tracks spans.
+    pub span: Option<facet_reflect::Span>,
+    /// Line of the span's start, from 1, when the source text is known.
+    pub line: Option<usize>,
+    /// Column of the span's start in characters, from 1, when the source
+    /// text is known.
+    pub column: Option<usize>,
+    /// The source line containing the span's start.
+    pub snippet: Option<String>,
+}
+
+impl ErrorLocation {
+    /// Locate `span` in `source`, when both are known.
+    pub fn new(path: String, span: Option<facet_reflect::Span>, source: Option<&str>) -> Self {
+        let mut location = Self {
+            path,
+            span,
+            line: None,
+            column: None,
+            snippet: None,
+        };
+        if let (Some(span), Some(source)) = (span, source) {
+            let mut offset = span.offset.min(source.len());
+            while !source.is_char_boundary(offset) {
+                offset -= 1;
+            }
+            let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = source[offset..]
+                .find('\n')
+                .map_or(source.len(), |i| offset + i);
+            location.line = Some(source[..offset].matches('\n').count() + 1);
+            location.column = Some(source[line_start..offset].chars().count() + 1);
+            location.snippet = Some(source[line_start..line_end].trim_end_matches('\r').into());
+        }
+        location
+    }
+}
+
+impl fmt::Display for ErrorLocation {
+    /// Renders `at <path>, line L, column C`, followed by the source line
+    /// with a caret under the span when it is known.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at {}", self.path)?;
+        let (Some(line), Some(column)) = (self.line, self.column) else {
+            if let Some(span) = self.span {
+                write!(f, ", byte {}", span.offset)?;
+            }
+            return Ok(());
+        };
+        write!(f, ", line {line}, column {column}")?;
+        if let Some(snippet) = &self.snippet {
+            let rest = snippet.chars().count().saturating_sub(column - 1);
+            let width = self.span.map_or(1, |span| span.len).clamp(1, rest.max(1));
+            let gutter = " ".repeat(line.to_string().len());
+            write!(f, "\n{gutter} |\n{line} | {snippet}\n{gutter} | ")?;
+            write!(
+                f,
+                "{:indent$}{}",
+                "",
+                "^".repeat(width),
+                indent = column - 1
+            )?;
+        }
+        Ok(())
+    }
 }
 
 impl<E> From<facet_reflect::ReflectError> for DomDeserializeError<E> {
@@ -100,7 +275,35 @@ impl<E: std::error::Error> fmt::Display for DomDeserializeError<E> {
             Self::UnknownElement { tag } => write!(f, "unknown element: <{tag}>"),
             Self::UnknownAttribute { name } => write!(f, "unknown attribute: {name}"),
             Self::MissingAttribute { name } => write!(f, "missing required attribute: {name}"),
+            Self::UnknownType { type_name } => write!(f, "unknown xsi:type: {type_name}"),
             Self::Unsupported(msg) => write!(f, "unsupported: {msg}"),
+            Self::InvalidValue {
+                path,
+                text: Some(text),
+                source,
+            } => write!(f, "invalid value {text:?} at {path}: {source}"),
+            Self::InvalidValue {
+                path,
+                text: None,
+                source,
+            } => write!(f, "invalid value at {path}: {source}"),
+            Self::Codec { codec, message } => write!(f, "{codec} decoding failed: {message}"),
+            Self::DeserializeWith { message } => write!(f, "deserialize_with failed: {message}"),
+            Self::AssertionFailed { path, message } => {
+                write!(f, "assertion failed at {path}: {message}")
+            }
+            Self::Validation { path, message } => {
+                write!(f, "validation failed at {path}: {message}")
+            }
+            Self::Located { location, source } => write!(f, "{source}\n  {location}"),
+            Self::DuplicateElement { path } => write!(f, "duplicate element at {path}"),
+            Self::UnknownFields(items) => {
+                write!(f, "unknown fields:")?;
+                for item in items {
+                    write!(f, " {}", item.path)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -112,6 +315,9 @@ impl<E: std::error::Error + 'static> std::error::Error for DomDeserializeError<E
             Self::Reflect(e) => Some(e),
             Self::Alloc(e) => Some(e),
             Self::ShapeMismatch(e) => Some(e),
+            Self::InvalidValue { source, .. } | Self::Located { source, .. } => {
+                Some(source.as_ref())
+            }
             _ => None,
         }
     }
@@ -8,6 +8,10 @@
 
 #![deny(missing_docs, rustdoc::broken_intra_doc_links)]
 
+mod child_order;
+pub mod codec;
+pub mod contract;
+pub mod describe;
 mod deserializer;
 mod error;
 mod event;
@@ -16,8 +20,14 @@ mod parser;
 mod parser_ext;
 mod raw_markup;
 mod serializer;
+mod stack;
 mod tracing_macros;
+mod vec_parser;
+pub mod versioning;
+mod with_fn;
+mod xsi_type;
 
+pub use child_order::*;
 pub use deserializer::*;
 pub use error::*;
 pub use event::*;
@@ -25,3 +35,5 @@ pub use parser::*;
 pub use parser_ext::*;
 pub use raw_markup::*;
 pub use serializer::*;
+pub use vec_parser::*;
+pub use with_fn::*;
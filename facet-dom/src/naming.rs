@@ -10,6 +10,8 @@
 //! - tuple field `0` → `<_0>` (XML names can't start with digits)
 
 use std::borrow::Cow;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 pub use heck::AsLowerCamelCase;
 use heck::{AsKebabCase, AsPascalCase, AsShoutySnakeCase, AsSnakeCase};
@@ -76,3 +78,82 @@ pub fn apply_rename_all(name: &str, rename_all: &str) -> String {
         _ => name.to_string(),
     }
 }
+
+/// The element name a type uses when it appears as an element on its own.
+///
+/// Priority: `rename` > `rename_all` applied to the type identifier >
+/// lowerCamelCase of the type identifier.
+pub fn shape_element_name(shape: &'static facet_core::Shape) -> Cow<'static, str> {
+    if let Some(rename) = shape.get_builtin_attr_value::<&str>("rename") {
+        Cow::Borrowed(rename)
+    } else if let Some(rename_all) = shape.get_builtin_attr_value::<&str>("rename_all") {
+        Cow::Owned(apply_rename_all(shape.type_identifier, rename_all))
+    } else {
+        to_element_name(shape.type_identifier)
+    }
+}
+
+static SINGULAR_OVERRIDES: RwLock<Vec<(String, String)>> = RwLock::new(Vec::new());
+
+/// Bumped by every [`register_singular`], so names derived from singular
+/// forms can tell they're stale.
+static SINGULAR_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// Make `singular` the element name of each item of a list field whose
+/// element name is `plural`, instead of the guess of
+/// [`facet_singularize::singularize`].
+///
+/// Applies to every type, for domain terms the guess gets wrong; a single
+/// field is better served by `#[facet(<ns>::singular = "...")]`.
+/// Registering `plural` again replaces the previous override.
+///
+/// ```
+/// use facet_dom::naming::{register_singular, singular_form};
+///
+/// assert_eq!(singular_form("media"), "medium");
+/// register_singular("media", "mediaItem");
+/// assert_eq!(singular_form("media"), "mediaItem");
+/// ```
+pub fn register_singular(plural: impl Into<String>, singular: impl Into<String>) {
+    let (plural, singular) = (plural.into(), singular.into());
+    let mut overrides = SINGULAR_OVERRIDES
+        .write()
+        .unwrap_or_else(|e| e.into_inner());
+    match overrides.iter_mut().find(|(p, _)| *p == plural) {
+        Some(entry) => entry.1 = singular,
+        None => overrides.push((plural, singular)),
+    }
+    SINGULAR_GENERATION.fetch_add(1, Ordering::Release);
+}
+
+/// How many times [`register_singular`] was called.
+pub(crate) fn singular_generation() -> usize {
+    SINGULAR_GENERATION.load(Ordering::Acquire)
+}
+
+/// The item element name for the list element name `plural`: the override
+/// registered with [`register_singular`], or else
+/// [`facet_singularize::singularize`]'s guess.
+pub fn singular_form(plural: &str) -> String {
+    let overrides = SINGULAR_OVERRIDES.read().unwrap_or_else(|e| e.into_inner());
+    match overrides.iter().find(|(p, _)| p == plural) {
+        Some((_, singular)) => singular.clone(),
+        None => facet_singularize::singularize(plural),
+    }
+}
+
+/// The item element name of the list field `field`, whose element name is
+/// `plural`: its `<ns>::singular` attribute, or else [`singular_form`].
+pub(crate) fn field_singular_form(
+    field: &facet_core::Field,
+    plural: &str,
+    format_ns: Option<&str>,
+) -> String {
+    match field
+        .get_attr(format_ns, "singular")
+        .and_then(|attr| attr.get_as::<&str>().copied())
+    {
+        Some(singular) => singular.to_string(),
+        None => singular_form(plural),
+    }
+}
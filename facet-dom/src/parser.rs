@@ -2,6 +2,37 @@
 
 use crate::DomEvent;
 
+/// What happens when an element for a single-valued field appears more
+/// than once among its parent's children.
+///
+/// A field's `on_duplicate` attribute (e.g.
+/// `#[facet(xml::on_duplicate = "error")]`) takes precedence over the
+/// parser's [`duplicate_policy`](DomParser::duplicate_policy). Lists, sets
+/// and other repeated fields are unaffected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Fail with [`DomDeserializeError::DuplicateElement`](crate::DomDeserializeError::DuplicateElement).
+    Error,
+    /// Keep the first element and skip the others.
+    First,
+    /// Keep the last element.
+    #[default]
+    Last,
+}
+
+impl DuplicatePolicy {
+    /// The policy named `name` (`"error"`, `"first"` or `"last"`), as written
+    /// in an `on_duplicate` attribute.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "error" => Some(Self::Error),
+            "first" => Some(Self::First),
+            "last" => Some(Self::Last),
+            _ => None,
+        }
+    }
+}
+
 /// A parser that emits DOM events from a tree-structured document.
 ///
 /// Implementations exist for HTML (using html5gum) and XML parsers.
@@ -46,6 +77,142 @@ pub trait DomParser<'de> {
         None
     }
 
+    /// The schema version of the document being parsed, if known.
+    ///
+    /// Fields marked `since` / `until` in this parser's format namespace are
+    /// ignored when the version is outside their range; see
+    /// [`field_in_version`](crate::versioning::field_in_version).
+    fn schema_version(&self) -> Option<&str> {
+        None
+    }
+
+    /// A namespace whose attributes only bind to fields declared in it.
+    ///
+    /// Such attributes never match unqualified fields, catch-all attribute
+    /// fields or flattened maps, and are dropped (rather than reported as
+    /// unknown) when no field claims them. Used for XML's reserved `xml:`
+    /// attributes.
+    fn reserved_attribute_namespace(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether [`intercept_value`](Self::intercept_value) should be called.
+    ///
+    /// Returns `false` by default, so deserializers don't build value paths
+    /// nobody looks at.
+    fn intercepts_values(&self) -> bool {
+        false
+    }
+
+    /// Inspect, and optionally replace, a value before it is stored in a field.
+    ///
+    /// `path` locates the value as in
+    /// [`DomDeserializeError::InvalidValue`](crate::DomDeserializeError::InvalidValue)
+    /// (e.g. `order/card/@number`), `text` is the raw text and `shape` the
+    /// type it is about to be parsed into. Returning `Some` stores the
+    /// returned text instead.
+    fn intercept_value(
+        &mut self,
+        _path: &str,
+        _text: &str,
+        _shape: &'static facet_core::Shape,
+    ) -> Option<String> {
+        None
+    }
+
+    /// Whether [`resolve_in_context`](Self::resolve_in_context) should be
+    /// called.
+    ///
+    /// Returns `false` by default.
+    fn has_context(&self) -> bool {
+        false
+    }
+
+    /// Resolve the value of a field marked `with_context` in the
+    /// [format namespace](Self::format_namespace) against state supplied by
+    /// the caller, before it is stored.
+    ///
+    /// Called in document order, after
+    /// [`intercept_value`](Self::intercept_value) and with the text it
+    /// produced; `path` and `shape` are as there. Returning `Some` stores
+    /// the returned text instead.
+    fn resolve_in_context(
+        &mut self,
+        _path: &str,
+        _text: &str,
+        _shape: &'static facet_core::Shape,
+    ) -> Option<String> {
+        None
+    }
+
+    /// Called when a collection item whose type is marked `skip_on_error`
+    /// failed to deserialize and was left out of the collection.
+    ///
+    /// `path` locates the item's element as in
+    /// [`DomDeserializeError::InvalidValue`](crate::DomDeserializeError::InvalidValue)
+    /// and `error` is why it failed. Does nothing by default.
+    fn item_skipped(&mut self, _path: &str, _error: &dyn std::error::Error) {}
+
+    /// Whether comments among an element's children are kept.
+    ///
+    /// When `true`, a comment becomes an item of the struct's flattened enum
+    /// list, through the variant marked `comment` in the format namespace.
+    /// Returns `false` by default, so comments are skipped.
+    fn keeps_comments(&self) -> bool {
+        false
+    }
+
+    /// Whether processing instructions among an element's children are kept.
+    ///
+    /// When `true`, a processing instruction becomes an item of the struct's
+    /// flattened enum list, through the variant marked
+    /// `processing_instruction` in the format namespace. Returns `false` by
+    /// default, so processing instructions are skipped.
+    fn keeps_processing_instructions(&self) -> bool {
+        false
+    }
+
+    /// What happens to repeated elements of single-valued fields without an
+    /// `on_duplicate` attribute.
+    ///
+    /// Returns [`DuplicatePolicy::Last`] by default.
+    fn duplicate_policy(&self) -> DuplicatePolicy {
+        DuplicatePolicy::Last
+    }
+
+    /// The namespace prefix the most recently read `NodeStart` was written
+    /// with, e.g. `dc` for `<dc:title>`.
+    ///
+    /// Only meaningful right after that event; `None` when the element had
+    /// no prefix or the format has none. Returns `None` by default.
+    fn element_prefix(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether errors are wrapped in
+    /// [`DomDeserializeError::Located`](crate::DomDeserializeError::Located)
+    /// with the path and span where they happened.
+    ///
+    /// Returns `false` by default.
+    fn locates_errors(&self) -> bool {
+        false
+    }
+
+    /// The whole source text, when it is in memory, so located errors can
+    /// report lines and columns. Returns `None` by default.
+    fn source_text(&self) -> Option<&str> {
+        None
+    }
+
+    /// The value of the attribute `name` in `namespace` on the element whose
+    /// `NodeStart` was just peeked, before its attributes are read.
+    ///
+    /// Lets the deserializer choose how to read an element from markers like
+    /// `xsi:nil`. Returns `None` by default, as if the attribute were absent.
+    fn peeked_attribute(&self, _namespace: &str, _name: &str) -> Option<&str> {
+        None
+    }
+
     /// Capture the current node as raw markup and skip past it.
     ///
     /// Must be called right after receiving a NodeStart event. Returns the raw
@@ -56,3 +223,105 @@ pub trait DomParser<'de> {
         Ok(None)
     }
 }
+
+/// Forwarding impl so a parser can be lent to a deserializer and used again afterwards.
+impl<'de, P> DomParser<'de> for &mut P
+where
+    P: DomParser<'de> + ?Sized,
+{
+    type Error = P::Error;
+
+    fn next_event(&mut self) -> Result<Option<DomEvent<'de>>, Self::Error> {
+        (**self).next_event()
+    }
+
+    fn peek_event(&mut self) -> Result<Option<&DomEvent<'de>>, Self::Error> {
+        (**self).peek_event()
+    }
+
+    fn skip_node(&mut self) -> Result<(), Self::Error> {
+        (**self).skip_node()
+    }
+
+    fn current_span(&self) -> Option<facet_reflect::Span> {
+        (**self).current_span()
+    }
+
+    fn is_lenient(&self) -> bool {
+        (**self).is_lenient()
+    }
+
+    fn format_namespace(&self) -> Option<&'static str> {
+        (**self).format_namespace()
+    }
+
+    fn schema_version(&self) -> Option<&str> {
+        (**self).schema_version()
+    }
+
+    fn reserved_attribute_namespace(&self) -> Option<&str> {
+        (**self).reserved_attribute_namespace()
+    }
+
+    fn intercepts_values(&self) -> bool {
+        (**self).intercepts_values()
+    }
+
+    fn intercept_value(
+        &mut self,
+        path: &str,
+        text: &str,
+        shape: &'static facet_core::Shape,
+    ) -> Option<String> {
+        (**self).intercept_value(path, text, shape)
+    }
+
+    fn has_context(&self) -> bool {
+        (**self).has_context()
+    }
+
+    fn resolve_in_context(
+        &mut self,
+        path: &str,
+        text: &str,
+        shape: &'static facet_core::Shape,
+    ) -> Option<String> {
+        (**self).resolve_in_context(path, text, shape)
+    }
+
+    fn item_skipped(&mut self, path: &str, error: &dyn std::error::Error) {
+        (**self).item_skipped(path, error)
+    }
+
+    fn keeps_comments(&self) -> bool {
+        (**self).keeps_comments()
+    }
+
+    fn keeps_processing_instructions(&self) -> bool {
+        (**self).keeps_processing_instructions()
+    }
+
+    fn duplicate_policy(&self) -> DuplicatePolicy {
+        (**self).duplicate_policy()
+    }
+
+    fn element_prefix(&self) -> Option<&str> {
+        (**self).element_prefix()
+    }
+
+    fn locates_errors(&self) -> bool {
+        (**self).locates_errors()
+    }
+
+    fn source_text(&self) -> Option<&str> {
+        (**self).source_text()
+    }
+
+    fn peeked_attribute(&self, namespace: &str, name: &str) -> Option<&str> {
+        (**self).peeked_attribute(namespace, name)
+    }
+
+    fn capture_raw_node(&mut self) -> Result<Option<std::borrow::Cow<'de, str>>, Self::Error> {
+        (**self).capture_raw_node()
+    }
+}
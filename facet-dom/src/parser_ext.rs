@@ -44,6 +44,20 @@ pub trait DomParserExt<'de>: DomParser<'de> {
         }
     }
 
+    /// Expect and consume a NodeStart event, returning the tag name and
+    /// namespace URI.
+    fn expect_node_start_with_namespace(
+        &mut self,
+    ) -> Result<(Cow<'de, str>, Option<Cow<'de, str>>), DomDeserializeError<Self::Error>> {
+        match self.next_event_or_eof("NodeStart")? {
+            DomEvent::NodeStart { tag, namespace } => Ok((tag, namespace)),
+            other => Err(DomDeserializeError::TypeMismatch {
+                expected: "NodeStart",
+                got: format!("{other:?}"),
+            }),
+        }
+    }
+
     /// Expect and consume a ChildrenStart event.
     fn expect_children_start(&mut self) -> Result<(), DomDeserializeError<Self::Error>> {
         match self.next_event_or_eof("ChildrenStart")? {
@@ -3,8 +3,10 @@
 //! This module provides a serializer trait and shared logic for serializing
 //! facet types to tree-based formats like XML and HTML.
 
+mod rewrite;
 mod write_scalar;
 
+pub use rewrite::{AttributeRules, RewriteAttributes};
 pub use write_scalar::{ScalarBuffer, WriteScalar};
 
 extern crate alloc;
@@ -23,7 +25,7 @@ use alloc::string::String;
 use alloc::vec::Vec;
 use core::fmt::Debug;
 
-use facet_core::{Def, StructKind};
+use facet_core::{Def, Field, StructKind};
 use facet_reflect::{HasFields as _, Peek, ReflectError};
 
 use crate::naming::to_element_name;
@@ -54,6 +56,21 @@ pub trait DomSerializer {
         namespace: Option<&str>,
     ) -> Result<(), Self::Error>;
 
+    /// Emit an attribute whose value is a qualified name in
+    /// `value_namespace`, like `xsi:type="geo:Circle"`.
+    ///
+    /// Only valid between `element_start` and `children_start`. The default
+    /// writes `local_name` alone.
+    fn qname_attribute(
+        &mut self,
+        name: &str,
+        namespace: Option<&str>,
+        local_name: &str,
+        _value_namespace: Option<&str>,
+    ) -> Result<(), Self::Error> {
+        self.attribute(name, Peek::new(&local_name), namespace)
+    }
+
     /// Start the children section of the current element.
     fn children_start(&mut self) -> Result<(), Self::Error>;
 
@@ -71,6 +88,11 @@ pub trait DomSerializer {
         Ok(())
     }
 
+    /// Emit a processing instruction, `<?target data?>` in XML.
+    fn processing_instruction(&mut self, _target: &str, _data: &str) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
     /// Emit a DOCTYPE declaration (XML/HTML).
     ///
     /// This is called before the root element when a field marked with
@@ -79,6 +101,30 @@ pub trait DomSerializer {
         Ok(())
     }
 
+    /// The prefix the next element was read with, for structs that capture
+    /// their element's namespace (`tag_ns` in the format namespace).
+    ///
+    /// Called right before `element_start`, which gets the namespace itself;
+    /// `None` means the element was unprefixed. Serializers may use it
+    /// instead of a prefix of their own choosing. Ignored by default.
+    fn element_prefix(&mut self, _prefix: Option<&str>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Called right before the element holding `value` is started.
+    ///
+    /// Together with [`after_element`](Self::after_element) this lets a
+    /// serializer emit auxiliary content (comments, checksums, ...) around
+    /// the elements of structs, maps and scalar fields.
+    fn before_element(&mut self, _tag: &str, _value: Peek<'_, '_>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Called right after the element holding `value` is ended.
+    fn after_element(&mut self, _tag: &str, _value: Peek<'_, '_>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Metadata hooks
     // ─────────────────────────────────────────────────────────────────────────
@@ -135,6 +181,18 @@ pub trait DomSerializer {
         false
     }
 
+    /// Check if the current field is a template whose `{name}` placeholders
+    /// are filled from sibling fields before it is emitted.
+    fn is_raw_template_field(&self) -> bool {
+        false
+    }
+
+    /// Check if the current field is written as an empty element marked
+    /// `xsi:nil="true"` when it is `None`, rather than left out.
+    fn is_nillable_field(&self) -> bool {
+        false
+    }
+
     /// Clear field-related state after a field is serialized.
     fn clear_field_state(&mut self) {}
 
@@ -151,6 +209,18 @@ pub trait DomSerializer {
         value.to_string()
     }
 
+    /// How `bool` values are written, unless a field's `bool_style`
+    /// attribute says otherwise.
+    fn bool_style(&self) -> BoolStyle {
+        BoolStyle::TrueFalse
+    }
+
+    /// Whether map entries (including flattened map entries) are written in
+    /// sorted key order rather than the map's iteration order.
+    fn sort_map_keys(&self) -> bool {
+        false
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Option handling
     // ─────────────────────────────────────────────────────────────────────────
@@ -169,6 +239,64 @@ pub trait DomSerializer {
     fn format_namespace(&self) -> Option<&'static str> {
         None
     }
+
+    /// The schema version to write, if any.
+    ///
+    /// Fields marked `since` / `until` in this serializer's format namespace
+    /// are left out when the version is outside their range.
+    fn schema_version(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// The words `bool` values are written as.
+///
+/// A field's `bool_style` attribute (e.g.
+/// `#[facet(xml::bool_style = "one_zero")]`) takes precedence over the
+/// serializer's [`bool_style`](DomSerializer::bool_style).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoolStyle {
+    /// `true` and `false`.
+    #[default]
+    TrueFalse,
+    /// `1` and `0`.
+    OneZero,
+    /// `yes` and `no`.
+    YesNo,
+}
+
+impl BoolStyle {
+    /// The style named `name` (`"true_false"`, `"one_zero"` or `"yes_no"`),
+    /// as written in a `bool_style` attribute.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "true_false" => Some(Self::TrueFalse),
+            "one_zero" => Some(Self::OneZero),
+            "yes_no" => Some(Self::YesNo),
+            _ => None,
+        }
+    }
+
+    /// The text of `value` in this style.
+    pub fn text(self, value: bool) -> &'static str {
+        match (self, value) {
+            (Self::TrueFalse, true) => "true",
+            (Self::TrueFalse, false) => "false",
+            (Self::OneZero, true) => "1",
+            (Self::OneZero, false) => "0",
+            (Self::YesNo, true) => "yes",
+            (Self::YesNo, false) => "no",
+        }
+    }
+}
+
+/// The `bool_style` attribute of `field` in `format_ns`, if it names a
+/// style.
+pub(crate) fn field_bool_style(field: &Field, format_ns: Option<&str>) -> Option<BoolStyle> {
+    field
+        .get_attr(format_ns, "bool_style")
+        .and_then(|attr| attr.get_as::<&str>().copied())
+        .and_then(BoolStyle::from_name)
 }
 
 /// Error produced by the DOM serializer.
@@ -211,6 +339,18 @@ fn serialize_value<S>(
     value: Peek<'_, '_>,
     element_name: Option<&str>,
 ) -> Result<(), DomSerializeError<S::Error>>
+where
+    S: DomSerializer,
+{
+    crate::stack::ensure_stack(|| serialize_value_unguarded(serializer, value, element_name))
+}
+
+/// [`serialize_value`] without the stack check.
+fn serialize_value_unguarded<S>(
+    serializer: &mut S,
+    value: Peek<'_, '_>,
+    element_name: Option<&str>,
+) -> Result<(), DomSerializeError<S::Error>>
 where
     S: DomSerializer,
 {
@@ -230,6 +370,9 @@ where
     // Handle scalars
     if let Some(s) = value_to_string(value, serializer) {
         if let Some(tag) = element_name {
+            serializer
+                .before_element(tag, value)
+                .map_err(DomSerializeError::Backend)?;
             serializer
                 .element_start(tag, None)
                 .map_err(DomSerializeError::Backend)?;
@@ -243,6 +386,9 @@ where
             serializer
                 .element_end(tag)
                 .map_err(DomSerializeError::Backend)?;
+            serializer
+                .after_element(tag, value)
+                .map_err(DomSerializeError::Backend)?;
         } else {
             serializer.text(&s).map_err(DomSerializeError::Backend)?;
         }
@@ -251,9 +397,30 @@ where
 
     // Handle Option<T>
     if let Ok(opt) = value.into_option() {
-        return match opt.value() {
-            Some(inner) => serialize_value(serializer, inner, element_name),
-            None => serializer
+        return match (opt.value(), element_name) {
+            (Some(inner), _) => serialize_value(serializer, inner, element_name),
+            (None, Some(tag)) if serializer.is_nillable_field() => {
+                serializer
+                    .element_start(tag, None)
+                    .map_err(DomSerializeError::Backend)?;
+                serializer
+                    .attribute(
+                        "nil",
+                        Peek::new(&"true"),
+                        Some(crate::xsi_type::XSI_NAMESPACE),
+                    )
+                    .map_err(DomSerializeError::Backend)?;
+                serializer
+                    .children_start()
+                    .map_err(DomSerializeError::Backend)?;
+                serializer
+                    .children_end()
+                    .map_err(DomSerializeError::Backend)?;
+                serializer
+                    .element_end(tag)
+                    .map_err(DomSerializeError::Backend)
+            }
+            (None, _) => serializer
                 .serialize_none()
                 .map_err(DomSerializeError::Backend),
         };
@@ -275,6 +442,9 @@ where
     // Handle maps
     if let Ok(map) = value.into_map() {
         if let Some(tag) = element_name {
+            serializer
+                .before_element(tag, value)
+                .map_err(DomSerializeError::Backend)?;
             serializer
                 .element_start(tag, None)
                 .map_err(DomSerializeError::Backend)?;
@@ -283,12 +453,22 @@ where
                 .map_err(DomSerializeError::Backend)?;
         }
 
-        for (key, val) in map.iter() {
-            let key_str = if let Some(s) = key.as_str() {
-                Cow::Borrowed(s)
-            } else {
-                Cow::Owned(alloc::format!("{}", key))
-            };
+        let mut entries: Vec<_> = map
+            .iter()
+            .map(|(key, val)| {
+                let key = deref_if_pointer(key);
+                let key_str = if let Some(s) = key.as_str() {
+                    Cow::Borrowed(s)
+                } else {
+                    Cow::Owned(alloc::format!("{}", key))
+                };
+                (key_str, val)
+            })
+            .collect();
+        if serializer.sort_map_keys() {
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        }
+        for (key_str, val) in entries {
             serialize_value(serializer, val, Some(&key_str))?;
         }
 
@@ -299,6 +479,9 @@ where
             serializer
                 .element_end(tag)
                 .map_err(DomSerializeError::Backend)?;
+            serializer
+                .after_element(tag, value)
+                .map_err(DomSerializeError::Backend)?;
         }
 
         return Ok(());
@@ -338,7 +521,22 @@ where
             .map_err(DomSerializeError::Backend)?;
 
         // Collect fields first to check for tag field
-        let fields: Vec<_> = struct_.fields_for_serialize().collect();
+        let mut fields: Vec<_> = struct_.fields_for_serialize().collect();
+        retain_fields_in_version(serializer, &mut fields);
+        if serializer.sort_map_keys() {
+            sort_flattened_map_entries(&mut fields);
+        }
+
+        // Children in the order they were read, for `ordered` structs
+        let order = recorded_child_order(serializer, value.shape(), &fields);
+        let text_pieces = match &order {
+            Some(order) => split_text_field(serializer, order, &fields)?,
+            None => None,
+        };
+        let fields = match &order {
+            Some(order) => in_document_order(order, fields, text_pieces.as_ref()),
+            None => fields,
+        };
 
         // Find the tag field if present (html::tag or xml::tag)
         // and the doctype field if present (xml::doctype)
@@ -396,15 +594,31 @@ where
                 .map_err(DomSerializeError::Backend)?;
         }
 
+        // Namespace and prefix the element was read with (xml::tag_ns, xml::tag_prefix)
+        let tag_namespace = tag_name_part(serializer, &fields, "tag_ns");
+        let tag_prefix = tag_name_part(serializer, &fields, "tag_prefix");
+
         serializer
-            .element_start(&tag, None)
+            .before_element(&tag, value)
             .map_err(DomSerializeError::Backend)?;
+        if let Some(namespace) = &tag_namespace {
+            serializer
+                .element_prefix(tag_prefix.as_ref().and_then(|prefix| prefix.as_deref()))
+                .map_err(DomSerializeError::Backend)?;
+            serializer
+                .element_start(&tag, namespace.as_deref())
+                .map_err(DomSerializeError::Backend)?;
+        } else {
+            serializer
+                .element_start(&tag, None)
+                .map_err(DomSerializeError::Backend)?;
+        }
 
         // Fields were already collected above when checking for tag field
         trace!(field_count = fields.len(), "collected fields for serialize");
 
         // First pass: emit attributes
-        for (field_item, field_value) in &fields {
+        for (field_item, field_value) in attribute_order(serializer, &fields) {
             trace!(field_name = %field_item.name, "processing field for attributes");
             serializer
                 .field_metadata(field_item)
@@ -413,6 +627,19 @@ where
             let is_attr = serializer.is_attribute_field();
             trace!(field_name = %field_item.name, is_attribute = is_attr, "field_metadata result");
 
+            let rendered;
+            let field_value = &if is_attr && serializer.is_raw_template_field() {
+                rendered = render_raw_template(*field_value, &fields, serializer);
+                Peek::new(&rendered)
+            } else if is_attr
+                && let Some(encoded) = custom_text(serializer, field_item, *field_value)?
+            {
+                rendered = encoded;
+                Peek::new(&rendered)
+            } else {
+                *field_value
+            };
+
             if is_attr {
                 trace!(field_name = %field_item.name, "attribute field");
                 // Compute attribute name: rename > lowerCamelCase(field.name)
@@ -471,7 +698,7 @@ where
             }
 
             // Skip tag fields - the value was already used as the element name
-            if serializer.is_tag_field() {
+            if serializer.is_tag_field() || is_tag_name_field(serializer, field_item) {
                 serializer.clear_field_state();
                 continue;
             }
@@ -482,6 +709,23 @@ where
                 continue;
             }
 
+            // Skip the document order record - it isn't part of the document
+            if crate::child_order::is_child_order(field_value.shape()) {
+                serializer.clear_field_state();
+                continue;
+            }
+
+            let rendered;
+            let field_value = &if serializer.is_raw_template_field() {
+                rendered = render_raw_template(*field_value, &fields, serializer);
+                Peek::new(&rendered)
+            } else if let Some(encoded) = custom_text(serializer, field_item, *field_value)? {
+                rendered = encoded;
+                Peek::new(&rendered)
+            } else {
+                *field_value
+            };
+
             if serializer.is_text_field() {
                 if let Some(s) = value_to_string(*field_value, serializer) {
                     serializer.text(&s).map_err(DomSerializeError::Backend)?;
@@ -561,6 +805,9 @@ where
         serializer
             .element_end(&tag)
             .map_err(DomSerializeError::Backend)?;
+        serializer
+            .after_element(&tag, value)
+            .map_err(DomSerializeError::Backend)?;
 
         return Ok(());
     }
@@ -575,6 +822,42 @@ where
             .variant_metadata(variant)
             .map_err(DomSerializeError::Backend)?;
 
+        // Comments and processing instructions kept from the input
+        let format_ns = serializer.format_namespace();
+        if variant.get_attr(format_ns, "comment").is_some() {
+            let text = enum_
+                .fields_for_serialize()
+                .next()
+                .and_then(|(_, inner)| value_to_string(inner, serializer))
+                .unwrap_or_default();
+            serializer
+                .comment(&text)
+                .map_err(DomSerializeError::Backend)?;
+            return Ok(());
+        }
+        if variant
+            .get_attr(format_ns, "processing_instruction")
+            .is_some()
+        {
+            let (mut target, mut data) = (String::new(), String::new());
+            for (field_item, field_value) in enum_.fields_for_serialize() {
+                let slot = match &*field_item.name {
+                    "target" => &mut target,
+                    "data" => &mut data,
+                    _ => continue,
+                };
+                *slot = value_to_string(field_value, serializer).unwrap_or_default();
+            }
+            serializer
+                .processing_instruction(&target, &data)
+                .map_err(DomSerializeError::Backend)?;
+            return Ok(());
+        }
+
+        if crate::xsi_type::is_xsi_typed(value.shape(), format_ns) {
+            return serialize_xsi_typed(serializer, value, enum_, variant, element_name);
+        }
+
         let untagged = value.shape().is_untagged();
         let tag_attr = value.shape().get_tag_attr();
         let content_attr = value.shape().get_content_attr();
@@ -824,23 +1107,113 @@ where
     ))))
 }
 
+/// Serialize an enum whose variant is named by the `xsi:type` attribute of
+/// a single element, named like an untagged enum's.
+///
+/// The element holds the variant's fields, or those of the struct a newtype
+/// variant wraps (or its text, for a scalar).
+fn serialize_xsi_typed<S>(
+    serializer: &mut S,
+    value: Peek<'_, '_>,
+    enum_: facet_reflect::PeekEnum<'_, '_>,
+    variant: &'static facet_core::Variant,
+    element_name: Option<&str>,
+) -> Result<(), DomSerializeError<S::Error>>
+where
+    S: DomSerializer,
+{
+    let shape = value.shape();
+    let tag: Cow<'_, str> = match element_name {
+        Some(name) => Cow::Borrowed(name),
+        None => crate::naming::shape_element_name(shape),
+    };
+    let type_namespace = crate::xsi_type::type_namespace(shape, serializer.format_namespace());
+
+    let newtype = (variant.data.kind == StructKind::TupleStruct && variant.data.fields.len() == 1)
+        .then(|| enum_.fields_for_serialize().next())
+        .flatten()
+        .map(|(_, inner)| deref_if_pointer(inner).innermost_peek());
+    let (fields, text): (Vec<_>, _) = match newtype {
+        Some(inner) => match inner.into_struct() {
+            Ok(struct_) => {
+                serializer
+                    .struct_metadata(inner.shape())
+                    .map_err(DomSerializeError::Backend)?;
+                (struct_.fields_for_serialize().collect(), None)
+            }
+            Err(_) => {
+                serializer
+                    .struct_metadata(shape)
+                    .map_err(DomSerializeError::Backend)?;
+                (Vec::new(), value_to_string(inner, serializer))
+            }
+        },
+        None => {
+            serializer
+                .struct_metadata(shape)
+                .map_err(DomSerializeError::Backend)?;
+            (enum_.fields_for_serialize().collect(), None)
+        }
+    };
+
+    serializer
+        .before_element(&tag, value)
+        .map_err(DomSerializeError::Backend)?;
+    serializer
+        .element_start(&tag, None)
+        .map_err(DomSerializeError::Backend)?;
+    serializer
+        .qname_attribute(
+            "type",
+            Some(crate::xsi_type::XSI_NAMESPACE),
+            crate::xsi_type::type_name(variant),
+            type_namespace,
+        )
+        .map_err(DomSerializeError::Backend)?;
+    serialize_fields(serializer, fields)?;
+    if let Some(text) = text {
+        serializer.text(&text).map_err(DomSerializeError::Backend)?;
+    }
+    serializer
+        .children_end()
+        .map_err(DomSerializeError::Backend)?;
+    serializer
+        .element_end(&tag)
+        .map_err(DomSerializeError::Backend)?;
+    serializer
+        .after_element(&tag, value)
+        .map_err(DomSerializeError::Backend)?;
+    Ok(())
+}
+
 /// Serialize enum variant fields, handling attributes correctly.
+fn serialize_enum_variant_fields<S>(
+    serializer: &mut S,
+    enum_: facet_reflect::PeekEnum<'_, '_>,
+) -> Result<(), DomSerializeError<S::Error>>
+where
+    S: DomSerializer,
+{
+    serialize_fields(serializer, enum_.fields_for_serialize().collect())
+}
+
+/// Serialize the attributes and children of an element holding `fields`,
+/// starting its children section.
 ///
 /// This function implements a two-pass approach similar to struct serialization:
 /// 1. First pass: emit all fields marked with `xml::attribute` as XML attributes
 /// 2. Second pass: emit remaining fields as child elements or text
-fn serialize_enum_variant_fields<S>(
+fn serialize_fields<S>(
     serializer: &mut S,
-    enum_: facet_reflect::PeekEnum<'_, '_>,
+    mut fields: Vec<(facet_reflect::FieldItem, Peek<'_, '_>)>,
 ) -> Result<(), DomSerializeError<S::Error>>
 where
     S: DomSerializer,
 {
-    // Collect all fields into a Vec so we can iterate twice
-    let fields: Vec<_> = enum_.fields_for_serialize().collect();
+    retain_fields_in_version(serializer, &mut fields);
 
     // First pass: emit attributes
-    for (field_item, field_value) in &fields {
+    for (field_item, field_value) in attribute_order(serializer, &fields) {
         serializer
             .field_metadata(field_item)
             .map_err(DomSerializeError::Backend)?;
@@ -901,7 +1274,7 @@ where
         }
 
         // Skip tag fields
-        if serializer.is_tag_field() {
+        if serializer.is_tag_field() || is_tag_name_field(serializer, field_item) {
             serializer.clear_field_state();
             continue;
         }
@@ -912,6 +1285,12 @@ where
             continue;
         }
 
+        // Skip the document order record
+        if crate::child_order::is_child_order(field_value.shape()) {
+            serializer.clear_field_state();
+            continue;
+        }
+
         // Handle text fields
         if serializer.is_text_field() {
             if let Some(s) = value_to_string(*field_value, serializer) {
@@ -1006,7 +1385,7 @@ where
 }
 
 /// Dereference smart pointers (Box, Arc, Rc) to get the inner value.
-fn deref_if_pointer<'mem, 'facet>(value: Peek<'mem, 'facet>) -> Peek<'mem, 'facet> {
+pub(crate) fn deref_if_pointer<'mem, 'facet>(value: Peek<'mem, 'facet>) -> Peek<'mem, 'facet> {
     if let Ok(ptr) = value.into_pointer()
         && let Some(inner) = ptr.borrow_inner()
     {
@@ -1015,10 +1394,405 @@ fn deref_if_pointer<'mem, 'facet>(value: Peek<'mem, 'facet>) -> Peek<'mem, 'face
     value
 }
 
+/// The text a field's `serialize_with` function, `bool_style`, `codec` or
+/// `scale` writes for `value`, or `None` for fields with none of them.
+fn custom_text<S: DomSerializer>(
+    serializer: &S,
+    field_item: &facet_reflect::FieldItem,
+    value: Peek<'_, '_>,
+) -> Result<Option<String>, DomSerializeError<S::Error>> {
+    let format_ns = serializer.format_namespace();
+    if let Some(serialize) = field_item
+        .field
+        .and_then(|field| crate::with_fn::field_serialize_with(&field, format_ns))
+    {
+        let mut out = String::new();
+        serialize(value, &mut out).map_err(|message| {
+            DomSerializeError::Unsupported(Cow::Owned(format!(
+                "serialize_with failed for {}: {message}",
+                field_item.name
+            )))
+        })?;
+        return Ok(Some(out));
+    }
+    if let Some(style) = field_item
+        .field
+        .and_then(|field| field_bool_style(&field, format_ns))
+    {
+        let value = match value.into_option() {
+            Ok(opt) => match opt.value() {
+                Some(inner) => inner,
+                None => return Ok(None),
+            },
+            Err(_) => value,
+        };
+        if let Ok(b) = value.get::<bool>() {
+            return Ok(Some(style.text(*b).to_string()));
+        }
+    }
+    if let Some(scale) = field_item
+        .field
+        .and_then(|field| field.get_attr(format_ns, "scale"))
+        .and_then(|attr| attr.get_as::<i64>().copied())
+        .and_then(|scale| usize::try_from(scale).ok())
+    {
+        return Ok(
+            value_to_string(value, serializer).and_then(|text| rescale_decimal(&text, scale))
+        );
+    }
+    encode_with_codec(serializer, field_item, value)
+}
+
+/// `text`, a decimal number like `-12.345`, with exactly `scale` digits
+/// after the point: padded with zeros, or rounded half to even. Text that
+/// isn't a plain decimal number (exponents, `NaN`, ...) gives `None`.
+fn rescale_decimal(text: &str, scale: usize) -> Option<String> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    if (int.is_empty() && frac.is_empty())
+        || !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let int = if int.is_empty() { "0" } else { int };
+
+    let mut kept: Vec<u8> = int
+        .bytes()
+        .chain(frac.bytes().chain(core::iter::repeat(b'0')).take(scale))
+        .collect();
+    if frac.len() > scale {
+        let next = frac.as_bytes()[scale];
+        let round_up = match next.cmp(&b'5') {
+            core::cmp::Ordering::Greater => true,
+            core::cmp::Ordering::Less => false,
+            core::cmp::Ordering::Equal => {
+                frac.bytes().skip(scale + 1).any(|b| b != b'0')
+                    || kept.last().is_some_and(|d| (d - b'0') % 2 == 1)
+            }
+        };
+        if round_up {
+            let mut carry = true;
+            for digit in kept.iter_mut().rev() {
+                if *digit == b'9' {
+                    *digit = b'0';
+                } else {
+                    *digit += 1;
+                    carry = false;
+                    break;
+                }
+            }
+            if carry {
+                kept.insert(0, b'1');
+            }
+        }
+    }
+
+    let mut out = String::with_capacity(kept.len() + 2);
+    // Rounding can leave nothing but zeros, which has no sign
+    if negative && kept.iter().any(|&d| d != b'0') {
+        out.push('-');
+    }
+    let (int, frac) = kept.split_at(kept.len() - scale);
+    out.extend(int.iter().map(|&d| d as char));
+    if scale > 0 {
+        out.push('.');
+        out.extend(frac.iter().map(|&d| d as char));
+    }
+    Some(out)
+}
+
+/// The text for a field marked with a `codec`, or `None` for other fields
+/// (and for `None` values, which are skipped as usual).
+fn encode_with_codec<S: DomSerializer>(
+    serializer: &S,
+    field_item: &facet_reflect::FieldItem,
+    value: Peek<'_, '_>,
+) -> Result<Option<String>, DomSerializeError<S::Error>> {
+    let format_ns = serializer.format_namespace();
+    let Some(crate::codec::FieldCodec { name, codec }) = field_item
+        .field
+        .and_then(|field| crate::codec::field_codec(&field, format_ns))
+    else {
+        return Ok(None);
+    };
+    let codec = codec.ok_or_else(|| {
+        DomSerializeError::Unsupported(Cow::Owned(format!("unknown codec {name:?}")))
+    })?;
+
+    let value = match value.into_option() {
+        Ok(opt) => match opt.value() {
+            Some(inner) => inner,
+            None => return Ok(None),
+        },
+        Err(_) => value,
+    };
+    let bytes = value
+        .into_list_like()
+        .map_err(DomSerializeError::Reflect)?
+        .iter()
+        .map(|item| item.get::<u8>().copied())
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|e| {
+            DomSerializeError::Unsupported(Cow::Owned(format!(
+                "codec {name:?} needs a byte list: {e}"
+            )))
+        })?;
+    Ok(Some(codec.encode(&bytes)))
+}
+
+/// Drop fields that don't exist in the serializer's schema version.
+fn retain_fields_in_version<S: DomSerializer>(
+    serializer: &S,
+    fields: &mut Vec<(facet_reflect::FieldItem, Peek<'_, '_>)>,
+) {
+    let version = serializer.schema_version();
+    if version.is_some() {
+        let format_ns = serializer.format_namespace();
+        fields.retain(|(item, _)| {
+            item.field
+                .is_none_or(|field| crate::versioning::field_in_version(&field, format_ns, version))
+        });
+    }
+}
+
+/// The [`ChildOrder`](crate::ChildOrder) recorded in `fields`, if the struct
+/// `shape` is marked `ordered`.
+fn recorded_child_order<S: DomSerializer>(
+    serializer: &S,
+    shape: &facet_core::Shape,
+    fields: &[(facet_reflect::FieldItem, Peek<'_, '_>)],
+) -> Option<crate::ChildOrder> {
+    if !crate::child_order::is_ordered(shape, serializer.format_namespace()) {
+        return None;
+    }
+    fields
+        .iter()
+        .find_map(|(_, value)| value.get::<crate::ChildOrder>().ok().cloned())
+}
+
+/// The text field's name and its content cut into the pieces `order`
+/// recorded, if it was read from several.
+///
+/// Text that no longer matches the recorded lengths (edited since) goes
+/// into the last piece.
+fn split_text_field<S: DomSerializer>(
+    serializer: &mut S,
+    order: &crate::ChildOrder,
+    fields: &[(facet_reflect::FieldItem, Peek<'_, '_>)],
+) -> Result<Option<(&'static str, Vec<String>)>, DomSerializeError<S::Error>> {
+    let mut text_field = None;
+    for (field_item, field_value) in fields {
+        serializer
+            .field_metadata(field_item)
+            .map_err(DomSerializeError::Backend)?;
+        let is_text = serializer.is_text_field();
+        serializer.clear_field_state();
+        if is_text && let Some(field) = field_item.field {
+            text_field = Some((field.name, *field_value));
+            break;
+        }
+    }
+    let Some((name, value)) = text_field else {
+        return Ok(None);
+    };
+    let Some(text) = value_to_string(value, serializer) else {
+        return Ok(None);
+    };
+
+    let lengths: Vec<usize> = order
+        .slots()
+        .filter(|(field, _)| *field == name)
+        .filter_map(|(_, len)| len)
+        .collect();
+    if lengths.len() < 2 {
+        return Ok(None);
+    }
+    let mut pieces = Vec::with_capacity(lengths.len());
+    let mut rest = text.as_str();
+    for len in &lengths[..lengths.len() - 1] {
+        let at = if rest.is_char_boundary(*len) {
+            *len
+        } else {
+            rest.len()
+        };
+        let (piece, after) = rest.split_at(at);
+        pieces.push(piece.to_string());
+        rest = after;
+    }
+    pieces.push(rest.to_string());
+    Ok(Some((name, pieces)))
+}
+
+/// `fields` rearranged so the children come in the order `order` recorded.
+///
+/// Lists read from elements that other children came between are split
+/// into their items, and the text field into `text_pieces`. Whatever
+/// wasn't recorded follows, in declaration order.
+fn in_document_order<'mem, 'facet>(
+    order: &crate::ChildOrder,
+    fields: Vec<(facet_reflect::FieldItem, Peek<'mem, 'facet>)>,
+    text_pieces: Option<&'mem (&'static str, Vec<String>)>,
+) -> Vec<(facet_reflect::FieldItem, Peek<'mem, 'facet>)> {
+    use std::collections::{HashMap, VecDeque};
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for field in order.fields() {
+        *counts.entry(field).or_default() += 1;
+    }
+
+    let mut units = Vec::with_capacity(fields.len());
+    for (field_item, field_value) in fields {
+        let name = field_item.field.map(|field| field.name);
+        if let (Some(name), Some((text_field, pieces))) = (name, text_pieces)
+            && name == *text_field
+        {
+            units.extend(
+                pieces
+                    .iter()
+                    .map(|piece| (field_item.clone(), Peek::new(piece))),
+            );
+        } else if name.is_some_and(|name| counts.get(name).is_some_and(|&n| n > 1))
+            && matches!(field_value.shape().def, Def::List(_) | Def::Array(_))
+            && let Ok(list) = field_value.into_list_like()
+        {
+            units.extend(list.iter().map(|item| (field_item.clone(), item)));
+        } else {
+            units.push((field_item, field_value));
+        }
+    }
+
+    let mut queues: HashMap<&str, VecDeque<usize>> = HashMap::new();
+    for (i, (field_item, _)) in units.iter().enumerate() {
+        if let Some(field) = field_item.field {
+            queues.entry(field.name).or_default().push_back(i);
+        }
+    }
+    let mut units: Vec<Option<_>> = units.into_iter().map(Some).collect();
+    let mut ordered = Vec::with_capacity(units.len());
+    for field in order.fields() {
+        if let Some(i) = queues.get_mut(field).and_then(VecDeque::pop_front) {
+            ordered.extend(units[i].take());
+        }
+    }
+    ordered.extend(units.into_iter().flatten());
+    ordered
+}
+
+/// Sort each run of flattened map entries (items without a field definition)
+/// by key, leaving regular fields where they are.
+fn sort_flattened_map_entries(fields: &mut [(facet_reflect::FieldItem, Peek<'_, '_>)]) {
+    for run in fields.chunk_by_mut(|(a, _), (b, _)| a.field.is_none() && b.field.is_none()) {
+        if run.len() > 1 {
+            run.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+        }
+    }
+}
+
+/// Fill `{name}` placeholders in a template field from its sibling fields.
+///
+/// `{{` and `}}` are literal braces. Placeholders that don't name a scalar
+/// sibling are kept verbatim.
+fn render_raw_template<S: DomSerializer>(
+    template: Peek<'_, '_>,
+    fields: &[(facet_reflect::FieldItem, Peek<'_, '_>)],
+    serializer: &S,
+) -> String {
+    let Some(template) = value_to_string(template, serializer) else {
+        return String::new();
+    };
+
+    let lookup = |name: &str| {
+        fields
+            .iter()
+            .find(|(item, _)| item.name == name || item.effective_name() == name)
+            .and_then(|(_, value)| value_to_string(*value, serializer))
+    };
+
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template.as_str();
+    while let Some(pos) = rest.find(['{', '}']) {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if let Some(after) = rest.strip_prefix("{{") {
+            out.push('{');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("}}") {
+            out.push('}');
+            rest = after;
+        } else if let Some(close) = rest.find('}').filter(|_| rest.starts_with('{')) {
+            let name = &rest[1..close];
+            match lookup(name) {
+                Some(value) => out.push_str(&value),
+                None => out.push_str(&rest[..=close]),
+            }
+            rest = &rest[close + 1..];
+        } else {
+            out.push_str(&rest[..1]);
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
 /// Convert a value to a string if it's a scalar type.
+/// Whether `field` holds the namespace (`tag_ns`) or prefix (`tag_prefix`)
+/// of its struct's element rather than content of its own.
+fn is_tag_name_field<S: DomSerializer>(serializer: &S, field: &facet_reflect::FieldItem) -> bool {
+    let format_ns = serializer.format_namespace();
+    field.field.is_some_and(|field| {
+        field.get_attr(format_ns, "tag_ns").is_some()
+            || field.get_attr(format_ns, "tag_prefix").is_some()
+    })
+}
+
+/// Fields in the order their attributes are written: those ranked with
+/// `attr_order` first, lowest rank first, then the rest in declaration order.
+fn attribute_order<'f, 'mem, 'facet, S: DomSerializer>(
+    serializer: &S,
+    fields: &'f [(facet_reflect::FieldItem, Peek<'mem, 'facet>)],
+) -> Vec<&'f (facet_reflect::FieldItem, Peek<'mem, 'facet>)> {
+    let format_ns = serializer.format_namespace();
+    let rank = |item: &facet_reflect::FieldItem| {
+        item.field
+            .and_then(|field| field.get_attr(format_ns, "attr_order"))
+            .and_then(|attr| attr.get_as::<i64>().copied())
+    };
+    let mut ordered: Vec<_> = fields.iter().collect();
+    // Stable, so unranked fields keep declaration order
+    ordered.sort_by_key(|(item, _)| match rank(item) {
+        Some(rank) => (false, rank),
+        None => (true, 0),
+    });
+    ordered
+}
+
+/// The value of the field marked `key` in the serializer's format namespace,
+/// if the struct has one: `Some(None)` when that field is empty.
+fn tag_name_part<S: DomSerializer>(
+    serializer: &S,
+    fields: &[(facet_reflect::FieldItem, Peek<'_, '_>)],
+    key: &str,
+) -> Option<Option<String>> {
+    let format_ns = serializer.format_namespace();
+    fields
+        .iter()
+        .find(|(item, _)| {
+            item.field
+                .is_some_and(|field| field.get_attr(format_ns, key).is_some())
+        })
+        .map(|(_, value)| value_to_string(*value, serializer))
+}
+
 fn value_to_string<S: DomSerializer>(value: Peek<'_, '_>, serializer: &S) -> Option<String> {
     use facet_core::ScalarType;
 
+    // Look through Box<str>, Arc<str>, Rc<str> and other smart pointers
+    let value = deref_if_pointer(value);
+
     // Handle Option<T> by unwrapping if Some, returning None if None
     if let Def::Option(_) = &value.shape().def
         && let Ok(opt) = value.into_option()
@@ -1032,12 +1806,10 @@ fn value_to_string<S: DomSerializer>(value: Peek<'_, '_>, serializer: &S) -> Opt
     if let Some(scalar_type) = value.scalar_type() {
         let s = match scalar_type {
             ScalarType::Unit => return Some("null".into()),
-            ScalarType::Bool => if *value.get::<bool>().ok()? {
-                "true"
-            } else {
-                "false"
-            }
-            .into(),
+            ScalarType::Bool => serializer
+                .bool_style()
+                .text(*value.get::<bool>().ok()?)
+                .into(),
             ScalarType::Char => value.get::<char>().ok()?.to_string(),
             ScalarType::Str | ScalarType::String | ScalarType::CowStr => {
                 value.as_str()?.to_string()
@@ -1064,6 +1836,7 @@ fn value_to_string<S: DomSerializer>(value: Peek<'_, '_>, serializer: &S) -> Opt
             ScalarType::Ipv6Addr => value.get::<core::net::Ipv6Addr>().ok()?.to_string(),
             #[cfg(feature = "net")]
             ScalarType::SocketAddr => value.get::<core::net::SocketAddr>().ok()?.to_string(),
+            _ if value.shape().vtable.has_display() => alloc::format!("{}", value),
             _ => return None,
         };
         return Some(s);
@@ -0,0 +1,301 @@
+//! Adding and removing attributes on the way out, without changing types.
+
+use std::sync::Arc;
+
+use facet_reflect::Peek;
+
+use super::DomSerializer;
+
+/// An attribute written by [`AttributeRules`] rather than by a field.
+#[derive(Clone, Debug)]
+struct Injected {
+    name: String,
+    value: String,
+    namespace: Option<String>,
+}
+
+type Predicate = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Attributes to add to or strip from every document a serializer writes.
+///
+/// Injected attributes go after the element's own ones and are skipped on
+/// elements that already have an attribute of that name. Suppression
+/// applies to attributes written by fields and to flattened map entries,
+/// but not to injected ones.
+///
+/// ```
+/// use facet_dom::AttributeRules;
+///
+/// let rules = AttributeRules::new()
+///     .inject_on_root("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance")
+///     .suppress(|name| name.starts_with("debug-"));
+/// assert!(!rules.is_empty());
+/// ```
+#[derive(Clone, Default)]
+pub struct AttributeRules {
+    root: Vec<Injected>,
+    every: Vec<Injected>,
+    suppress: Vec<Predicate>,
+}
+
+impl core::fmt::Debug for AttributeRules {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AttributeRules")
+            .field("root", &self.root)
+            .field("every", &self.every)
+            .field("suppress", &self.suppress.len())
+            .finish()
+    }
+}
+
+impl AttributeRules {
+    /// Rules that leave attributes alone.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether these rules change nothing.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_empty() && self.every.is_empty() && self.suppress.is_empty()
+    }
+
+    /// Add `name="value"` to the root element.
+    pub fn inject_on_root(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.root.push(Injected {
+            name: name.into(),
+            value: value.into(),
+            namespace: None,
+        });
+        self
+    }
+
+    /// Add `name="value"`, in `namespace`, to the root element.
+    pub fn inject_on_root_ns(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+        namespace: impl Into<String>,
+    ) -> Self {
+        self.root.push(Injected {
+            name: name.into(),
+            value: value.into(),
+            namespace: Some(namespace.into()),
+        });
+        self
+    }
+
+    /// Add `name="value"` to every element.
+    pub fn inject_everywhere(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.every.push(Injected {
+            name: name.into(),
+            value: value.into(),
+            namespace: None,
+        });
+        self
+    }
+
+    /// Leave out attributes whose name matches `predicate`.
+    pub fn suppress(mut self, predicate: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        self.suppress.push(Arc::new(predicate));
+        self
+    }
+
+    /// Only write attributes whose name matches `predicate`.
+    pub fn retain(self, predicate: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        self.suppress(move |name| !predicate(name))
+    }
+
+    /// Wrap `inner` so everything it writes follows these rules.
+    pub fn wrap<'a, S: DomSerializer>(&'a self, inner: &'a mut S) -> RewriteAttributes<'a, S> {
+        RewriteAttributes {
+            inner,
+            rules: self,
+            depth: 0,
+            written: Vec::new(),
+        }
+    }
+
+    fn suppresses(&self, name: &str) -> bool {
+        self.suppress.iter().any(|predicate| predicate(name))
+    }
+}
+
+/// A [`DomSerializer`] applying [`AttributeRules`] to another one.
+///
+/// Created by [`AttributeRules::wrap`]; every other call is forwarded
+/// unchanged.
+pub struct RewriteAttributes<'a, S> {
+    inner: &'a mut S,
+    rules: &'a AttributeRules,
+    /// Elements started and not yet ended
+    depth: usize,
+    /// Attributes written on the current start tag
+    written: Vec<String>,
+}
+
+impl<S: DomSerializer> DomSerializer for RewriteAttributes<'_, S> {
+    type Error = S::Error;
+
+    fn element_start(&mut self, tag: &str, namespace: Option<&str>) -> Result<(), Self::Error> {
+        self.depth += 1;
+        self.written.clear();
+        self.inner.element_start(tag, namespace)
+    }
+
+    fn attribute(
+        &mut self,
+        name: &str,
+        value: Peek<'_, '_>,
+        namespace: Option<&str>,
+    ) -> Result<(), Self::Error> {
+        if self.rules.suppresses(name) {
+            return Ok(());
+        }
+        self.written.push(name.into());
+        self.inner.attribute(name, value, namespace)
+    }
+
+    fn qname_attribute(
+        &mut self,
+        name: &str,
+        namespace: Option<&str>,
+        local_name: &str,
+        value_namespace: Option<&str>,
+    ) -> Result<(), Self::Error> {
+        if self.rules.suppresses(name) {
+            return Ok(());
+        }
+        self.written.push(name.into());
+        self.inner
+            .qname_attribute(name, namespace, local_name, value_namespace)
+    }
+
+    fn children_start(&mut self) -> Result<(), Self::Error> {
+        let rules = self.rules;
+        let root = if self.depth == 1 {
+            &rules.root[..]
+        } else {
+            &[]
+        };
+        for injected in root.iter().chain(&rules.every) {
+            if self.written.contains(&injected.name) {
+                continue;
+            }
+            self.written.push(injected.name.clone());
+            self.inner.attribute(
+                &injected.name,
+                Peek::new(&injected.value),
+                injected.namespace.as_deref(),
+            )?;
+        }
+        self.inner.children_start()
+    }
+
+    fn children_end(&mut self) -> Result<(), Self::Error> {
+        self.inner.children_end()
+    }
+
+    fn element_end(&mut self, tag: &str) -> Result<(), Self::Error> {
+        self.depth = self.depth.saturating_sub(1);
+        self.inner.element_end(tag)
+    }
+
+    fn text(&mut self, content: &str) -> Result<(), Self::Error> {
+        self.inner.text(content)
+    }
+
+    fn comment(&mut self, content: &str) -> Result<(), Self::Error> {
+        self.inner.comment(content)
+    }
+
+    fn processing_instruction(&mut self, target: &str, data: &str) -> Result<(), Self::Error> {
+        self.inner.processing_instruction(target, data)
+    }
+
+    fn doctype(&mut self, content: &str) -> Result<(), Self::Error> {
+        self.inner.doctype(content)
+    }
+
+    fn element_prefix(&mut self, prefix: Option<&str>) -> Result<(), Self::Error> {
+        self.inner.element_prefix(prefix)
+    }
+
+    fn before_element(&mut self, tag: &str, value: Peek<'_, '_>) -> Result<(), Self::Error> {
+        self.inner.before_element(tag, value)
+    }
+
+    fn after_element(&mut self, tag: &str, value: Peek<'_, '_>) -> Result<(), Self::Error> {
+        self.inner.after_element(tag, value)
+    }
+
+    fn struct_metadata(&mut self, shape: &facet_core::Shape) -> Result<(), Self::Error> {
+        self.inner.struct_metadata(shape)
+    }
+
+    fn field_metadata(&mut self, field: &facet_reflect::FieldItem) -> Result<(), Self::Error> {
+        self.inner.field_metadata(field)
+    }
+
+    fn variant_metadata(
+        &mut self,
+        variant: &'static facet_core::Variant,
+    ) -> Result<(), Self::Error> {
+        self.inner.variant_metadata(variant)
+    }
+
+    fn is_attribute_field(&self) -> bool {
+        self.inner.is_attribute_field()
+    }
+
+    fn is_text_field(&self) -> bool {
+        self.inner.is_text_field()
+    }
+
+    fn is_elements_field(&self) -> bool {
+        self.inner.is_elements_field()
+    }
+
+    fn is_tag_field(&self) -> bool {
+        self.inner.is_tag_field()
+    }
+
+    fn is_doctype_field(&self) -> bool {
+        self.inner.is_doctype_field()
+    }
+
+    fn is_raw_template_field(&self) -> bool {
+        self.inner.is_raw_template_field()
+    }
+
+    fn is_nillable_field(&self) -> bool {
+        self.inner.is_nillable_field()
+    }
+
+    fn clear_field_state(&mut self) {
+        self.inner.clear_field_state()
+    }
+
+    fn format_float(&self, value: f64) -> String {
+        self.inner.format_float(value)
+    }
+
+    fn bool_style(&self) -> crate::BoolStyle {
+        self.inner.bool_style()
+    }
+
+    fn sort_map_keys(&self) -> bool {
+        self.inner.sort_map_keys()
+    }
+
+    fn serialize_none(&mut self) -> Result<(), Self::Error> {
+        self.inner.serialize_none()
+    }
+
+    fn format_namespace(&self) -> Option<&'static str> {
+        self.inner.format_namespace()
+    }
+
+    fn schema_version(&self) -> Option<&str> {
+        self.inner.schema_version()
+    }
+}
@@ -15,8 +15,9 @@ pub trait WriteScalar: DomSerializer {
     ///
     /// Returns `Some(string)` if the value is a scalar, `None` otherwise.
     fn format_scalar(&self, value: Peek<'_, '_>) -> Option<String> {
-        // handle transparent types and unwrap all types
-        let value = value.innermost_peek();
+        // handle transparent types and unwrap all types, then look through
+        // smart pointers such as Arc<str>
+        let value = super::deref_if_pointer(value.innermost_peek());
 
         // Handle Option<T> by unwrapping if Some
         if let Def::Option(_) = &value.shape().def
@@ -37,7 +38,7 @@ pub trait WriteScalar: DomSerializer {
                 }
                 ScalarType::Bool => {
                     if let Ok(b) = value.get::<bool>() {
-                        buf.push_str(if *b { "true" } else { "false" });
+                        buf.push_str(self.bool_style().text(*b));
                         true
                     } else {
                         false
@@ -135,7 +136,7 @@ pub trait WriteScalar: DomSerializer {
                 }
                 ScalarType::Bool => {
                     if let Ok(b) = value.get::<bool>() {
-                        buf.push_str(if *b { "true" } else { "false" });
+                        buf.push_str(self.bool_style().text(*b));
                         true
                     } else {
                         false
@@ -0,0 +1,30 @@
+//! Stack growth for deeply nested documents.
+//!
+//! Serialization and deserialization recurse once per element, so a
+//! legitimately deep document (a long `Element` chain, a degenerate tree)
+//! can overflow the thread's stack. With the `stacker` feature, each level
+//! checks the remaining stack first and continues on a freshly allocated
+//! segment when it runs low, so nesting is bounded by memory instead.
+
+/// Stack that must remain before recursing another level.
+#[cfg(feature = "stacker")]
+const RED_ZONE: usize = 128 * 1024;
+
+/// Size of each additional stack segment.
+#[cfg(feature = "stacker")]
+const SEGMENT_SIZE: usize = 2 * 1024 * 1024;
+
+/// Run `f`, on a new stack segment if the current one is nearly exhausted.
+///
+/// Without the `stacker` feature this just calls `f`.
+#[inline]
+pub(crate) fn ensure_stack<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "stacker")]
+    {
+        stacker::maybe_grow(RED_ZONE, SEGMENT_SIZE, f)
+    }
+    #[cfg(not(feature = "stacker"))]
+    {
+        f()
+    }
+}
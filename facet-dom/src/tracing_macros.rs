@@ -19,3 +19,19 @@ macro_rules! trace {
 macro_rules! trace {
     ($($arg:tt)*) => {};
 }
+
+/// Enter a debug-level span that lasts until the end of the enclosing scope.
+#[cfg(any(test, feature = "tracing"))]
+#[macro_export]
+macro_rules! enter_span {
+    ($($arg:tt)*) => {
+        let _span = tracing::debug_span!($($arg)*).entered();
+    };
+}
+
+/// Enter a debug-level span (no-op version).
+#[cfg(not(any(test, feature = "tracing")))]
+#[macro_export]
+macro_rules! enter_span {
+    ($($arg:tt)*) => {};
+}
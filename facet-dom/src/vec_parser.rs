@@ -0,0 +1,160 @@
+//! A DomParser replaying a prepared list of events.
+
+use std::collections::VecDeque;
+use std::convert::Infallible;
+
+use facet_reflect::Span;
+
+use crate::{DomEvent, DomParser};
+
+/// A [`DomParser`] over events that are already in memory.
+///
+/// Lets tests and alternative front-ends (a binary XML decoder, a
+/// hand-built tree, ...) feed the DOM deserializer without implementing a
+/// parser. The events must be well-nested, in the order a parser would
+/// emit them: `NodeStart`, attributes, `ChildrenStart`, children,
+/// `ChildrenEnd`, `NodeEnd`.
+///
+/// ```
+/// use facet_dom::{DomEvent, DomParser, VecEventParser};
+///
+/// let mut parser = VecEventParser::new(vec![
+///     DomEvent::NodeStart { tag: "a".into(), namespace: None },
+///     DomEvent::ChildrenStart,
+///     DomEvent::ChildrenEnd,
+///     DomEvent::NodeEnd,
+/// ]);
+/// assert!(parser.peek_event().unwrap().unwrap().is_node_start());
+/// parser.skip_node().unwrap();
+/// assert!(parser.next_event().unwrap().is_none());
+/// ```
+pub struct VecEventParser<'de> {
+    events: VecDeque<(DomEvent<'de>, Option<Span>)>,
+    /// Event returned by `peek_event`, already counted in `depth`
+    peeked: Option<(DomEvent<'de>, Option<Span>)>,
+    /// Span of the last event returned by `next_event`
+    current_span: Option<Span>,
+    /// Elements opened and not yet closed, counting a peeked `NodeStart`
+    depth: usize,
+    lenient: bool,
+    format_namespace: Option<&'static str>,
+}
+
+impl<'de> VecEventParser<'de> {
+    /// Replay `events`, without source locations.
+    pub fn new(events: Vec<DomEvent<'de>>) -> Self {
+        Self::from_events(events.into_iter().map(|event| (event, None)).collect())
+    }
+
+    /// Replay `events`, each with the span of the source it came from.
+    ///
+    /// [`current_span`](DomParser::current_span) reports the span of the
+    /// last event consumed.
+    pub fn with_spans(events: Vec<(DomEvent<'de>, Span)>) -> Self {
+        Self::from_events(
+            events
+                .into_iter()
+                .map(|(event, span)| (event, Some(span)))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn from_events(events: VecDeque<(DomEvent<'de>, Option<Span>)>) -> Self {
+        Self {
+            events,
+            peeked: None,
+            current_span: None,
+            depth: 0,
+            lenient: false,
+            format_namespace: None,
+        }
+    }
+
+    /// Discard text that no field accepts instead of failing, like an HTML
+    /// parser does.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Act as a parser for `ns` (e.g. `"xml"`), so format-specific field
+    /// attributes like `#[facet(xml::attribute)]` apply.
+    pub fn format_namespace(mut self, ns: &'static str) -> Self {
+        self.format_namespace = Some(ns);
+        self
+    }
+
+    /// Take the next event off the queue, tracking element depth.
+    fn read(&mut self) -> Option<(DomEvent<'de>, Option<Span>)> {
+        let (event, span) = self.events.pop_front()?;
+        match event {
+            DomEvent::NodeStart { .. } => self.depth += 1,
+            DomEvent::NodeEnd => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
+        Some((event, span))
+    }
+}
+
+impl<'de> DomParser<'de> for VecEventParser<'de> {
+    type Error = Infallible;
+
+    fn next_event(&mut self) -> Result<Option<DomEvent<'de>>, Self::Error> {
+        let next = self.peeked.take().or_else(|| self.read());
+        Ok(next.map(|(event, span)| {
+            self.current_span = span;
+            event
+        }))
+    }
+
+    fn peek_event(&mut self) -> Result<Option<&DomEvent<'de>>, Self::Error> {
+        if self.peeked.is_none() {
+            self.peeked = self.read();
+        }
+        Ok(self.peeked.as_ref().map(|(event, _)| event))
+    }
+
+    fn skip_node(&mut self) -> Result<(), Self::Error> {
+        let start_depth = self.depth;
+        while let Some(event) = self.next_event()? {
+            if matches!(event, DomEvent::NodeEnd) && self.depth < start_depth {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn current_span(&self) -> Option<Span> {
+        self.current_span
+    }
+
+    fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+
+    fn format_namespace(&self) -> Option<&'static str> {
+        self.format_namespace
+    }
+
+    fn peeked_attribute(&self, namespace: &str, name: &str) -> Option<&str> {
+        if !matches!(self.peeked, Some((DomEvent::NodeStart { .. }, _))) {
+            return None;
+        }
+        // The element's attributes are still queued after its NodeStart
+        for (event, _) in &self.events {
+            match event {
+                DomEvent::Attribute {
+                    name: attr_name,
+                    value,
+                    namespace: attr_ns,
+                } => {
+                    if attr_name == name && attr_ns.as_deref() == Some(namespace) {
+                        return Some(&**value);
+                    }
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,62 @@
+//! Schema version gating for fields marked `since` / `until`.
+
+use core::cmp::Ordering;
+
+use facet_core::Field;
+
+/// Whether `field` exists in documents of schema `version`.
+///
+/// A field marked `#[facet(<ns>::since = "2.0")]` exists from version 2.0 on,
+/// and one marked `#[facet(<ns>::until = "1.x")]` up to and including every
+/// 1.x version. When the version is unknown every field exists.
+pub fn field_in_version(field: &Field, format_ns: Option<&str>, version: Option<&str>) -> bool {
+    let Some(version) = version else {
+        return true;
+    };
+    let bound = |key: &str| {
+        field
+            .get_attr(format_ns, key)
+            .and_then(|attr| attr.get_as::<&str>().copied())
+    };
+    if let Some(since) = bound("since")
+        && compare_versions(version, since) == Ordering::Less
+    {
+        return false;
+    }
+    if let Some(until) = bound("until")
+        && compare_versions(version, until) == Ordering::Greater
+    {
+        return false;
+    }
+    true
+}
+
+/// Compare dot-separated versions component by component.
+///
+/// Numeric components compare as numbers, others as strings. A component
+/// `x` or `*` matches anything, including the components after it, so
+/// `1.4.2` equals `1.x`. Missing components count as `0`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.split('.');
+    let mut b_parts = b.split('.');
+    loop {
+        let (a_part, b_part) = match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (a_part, b_part) => (a_part.unwrap_or("0"), b_part.unwrap_or("0")),
+        };
+        if is_wildcard(a_part) || is_wildcard(b_part) {
+            return Ordering::Equal;
+        }
+        let ordering = match (a_part.parse::<u64>(), b_part.parse::<u64>()) {
+            (Ok(a_num), Ok(b_num)) => a_num.cmp(&b_num),
+            _ => a_part.cmp(b_part),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+fn is_wildcard(part: &str) -> bool {
+    part == "x" || part == "X" || part == "*"
+}
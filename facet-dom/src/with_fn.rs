@@ -0,0 +1,88 @@
+//! Field-level text hooks, declared with `serialize_with = f` and
+//! `deserialize_with = g`.
+//!
+//! They cover fields whose text form is too ad hoc for a proxy type: the
+//! serialize function writes the field's text directly, and the deserialize
+//! function builds the value from the text it was read from. Either may be
+//! used alone; the other direction then works as usual.
+
+use facet_core::{Facet, Field, PtrUninit, Shape};
+use facet_reflect::Peek;
+
+/// A `serialize_with` function: writes the text of the field `value` to
+/// `out`.
+///
+/// The value is the field itself (an `Option` field passes the `Option`);
+/// get at it with [`Peek::get`]. An error message fails serialization.
+pub type SerializeWithFn = fn(value: Peek<'_, '_>, out: &mut String) -> Result<(), String>;
+
+/// A `deserialize_with` function: builds the field's value from `text` and
+/// stores it in `slot`.
+///
+/// An error message, or returning without storing a value, fails with
+/// [`DomDeserializeError::DeserializeWith`](crate::DomDeserializeError::DeserializeWith).
+pub type DeserializeWithFn = fn(text: &str, slot: &mut ValueSlot) -> Result<(), String>;
+
+/// Where a [`DeserializeWithFn`] stores the value it built.
+pub struct ValueSlot {
+    ptr: PtrUninit,
+    shape: &'static Shape,
+    filled: bool,
+}
+
+impl ValueSlot {
+    pub(crate) fn new(ptr: PtrUninit, shape: &'static Shape) -> Self {
+        Self {
+            ptr,
+            shape,
+            filled: false,
+        }
+    }
+
+    /// The shape of the field being deserialized.
+    pub fn shape(&self) -> &'static Shape {
+        self.shape
+    }
+
+    /// Store `value`, which must be of the field's type.
+    ///
+    /// Fails if the type doesn't match or a value was already stored.
+    pub fn set<'a, T: Facet<'a>>(&mut self, value: T) -> Result<(), String> {
+        if T::SHAPE.id != self.shape.id {
+            return Err(format!("expected {}, got {}", self.shape, T::SHAPE));
+        }
+        if self.filled {
+            return Err("value already set".into());
+        }
+        // SAFETY: the slot points at uninitialized memory for a value of
+        // `self.shape`, which is `T`, and is only written once
+        unsafe { self.ptr.put(value) };
+        self.filled = true;
+        Ok(())
+    }
+
+    /// Whether a value was stored.
+    pub(crate) fn is_filled(&self) -> bool {
+        self.filled
+    }
+}
+
+/// The `serialize_with` function of `field` in `format_ns`, if any.
+pub(crate) fn field_serialize_with(
+    field: &Field,
+    format_ns: Option<&str>,
+) -> Option<SerializeWithFn> {
+    field
+        .get_attr(format_ns, "serialize_with")
+        .and_then(|attr| attr.get_as::<SerializeWithFn>().copied())
+}
+
+/// The `deserialize_with` function of `field` in `format_ns`, if any.
+pub(crate) fn field_deserialize_with(
+    field: &Field,
+    format_ns: Option<&str>,
+) -> Option<DeserializeWithFn> {
+    field
+        .get_attr(format_ns, "deserialize_with")
+        .and_then(|attr| attr.get_as::<DeserializeWithFn>().copied())
+}
@@ -0,0 +1,44 @@
+//! Polymorphic values selecting their enum variant by `xsi:type`.
+//!
+//! An enum marked `xsi_type` in the format namespace is written as a single
+//! element named after its field (or the enum), like an untagged enum. The
+//! element's `xsi:type` attribute names the variant, as SOAP and XSD
+//! derived types do: `<shape xsi:type="geo:Circle">`.
+
+use facet_core::{Shape, Variant};
+
+/// The XML Schema instance namespace, which `xsi:type` and `xsi:nil` are in.
+pub(crate) const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// Whether variants of `shape` are selected by `xsi:type`.
+pub(crate) fn is_xsi_typed(shape: &Shape, format_ns: Option<&str>) -> bool {
+    shape
+        .attributes
+        .iter()
+        .any(|attr| attr.ns.is_some() && attr.ns == format_ns && attr.key == "xsi_type")
+}
+
+/// The namespace of the types the variants of `shape` stand for: the
+/// enum's `ns_all`, if any.
+pub(crate) fn type_namespace(shape: &Shape, format_ns: Option<&str>) -> Option<&'static str> {
+    shape
+        .attributes
+        .iter()
+        .find(|attr| attr.ns.is_some() && attr.ns == format_ns && attr.key == "ns_all")
+        .and_then(|attr| attr.get_as::<&str>().copied())
+}
+
+/// The type name `variant` stands for: its `rename`, or its name as
+/// written.
+pub(crate) fn type_name(variant: &Variant) -> &'static str {
+    variant.effective_name()
+}
+
+/// Index of the variant of `variants` named by the `xsi:type` value
+/// `qname`.
+///
+/// Only the local part is compared: the prefix isn't resolved.
+pub(crate) fn find_variant(variants: &[Variant], qname: &str) -> Option<usize> {
+    let local = qname.rsplit_once(':').map_or(qname, |(_, local)| local);
+    variants.iter().position(|v| type_name(v) == local)
+}
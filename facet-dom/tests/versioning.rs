@@ -0,0 +1,24 @@
+//! Tests for schema version comparison in facet-dom.
+
+use std::cmp::Ordering;
+
+use facet_dom::versioning::compare_versions;
+
+#[test]
+fn test_numeric_components() {
+    assert_eq!(compare_versions("2.0", "2"), Ordering::Equal);
+    assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+    assert_eq!(compare_versions("0.9", "1.0"), Ordering::Less);
+}
+
+#[test]
+fn test_wildcards() {
+    assert_eq!(compare_versions("1.4.2", "1.x"), Ordering::Equal);
+    assert_eq!(compare_versions("2.0", "1.x"), Ordering::Greater);
+    assert_eq!(compare_versions("0.9", "1.*"), Ordering::Less);
+}
+
+#[test]
+fn test_non_numeric_components() {
+    assert_eq!(compare_versions("1.0-beta", "1.0-alpha"), Ordering::Greater);
+}
@@ -0,0 +1,144 @@
+//! Apple XML property lists (`.plist`) for facet types.
+//!
+//! Property lists don't describe values with element names the way plain
+//! XML does: a struct is a `<dict>` of `<key>`/value pairs, a list is an
+//! `<array>`, and each scalar is wrapped in an element naming its type
+//! (`<string>`, `<integer>`, `<real>`, `<true/>`, ...). This crate maps
+//! between the two, so the types, renames and defaults used with
+//! `facet-xml` work unchanged:
+//!
+//! - structs and maps are `<dict>`s, keyed by field name (after `rename`
+//!   and `rename_all`, as element names would be) or map key;
+//! - `Vec`s, arrays and sets are `<array>`s;
+//! - `bool`s are `<true/>` or `<false/>`, integers `<integer>`, floats
+//!   `<real>`, and everything else `<string>`;
+//! - `None` and empty lists are left out.
+//!
+//! Reading accepts `<date>` and `<data>` as well, as text. The top-level
+//! value must be a struct, and lists can't hold lists directly.
+//!
+//! # Example
+//!
+//! ```
+//! use facet::Facet;
+//!
+//! #[derive(Facet, Debug, PartialEq)]
+//! struct Info {
+//!     #[facet(rename = "CFBundleName")]
+//!     name: String,
+//!     #[facet(rename = "CFBundleVersion")]
+//!     build: u32,
+//!     #[facet(rename = "LSRequiresIPhoneOS")]
+//!     requires_ios: bool,
+//!     #[facet(rename = "UIRequiredDeviceCapabilities")]
+//!     capabilities: Vec<String>,
+//! }
+//!
+//! let info = Info {
+//!     name: "Demo".into(),
+//!     build: 42,
+//!     requires_ios: true,
+//!     capabilities: vec!["armv7".into()],
+//! };
+//! let plist = facet_plist_xml::to_string(&info).unwrap();
+//! assert!(plist.contains("\t<key>CFBundleVersion</key>\n\t<integer>42</integer>\n"));
+//! assert!(plist.contains("\t<key>LSRequiresIPhoneOS</key>\n\t<true/>\n"));
+//! assert!(plist.contains("\t<array>\n\t\t<string>armv7</string>\n\t</array>\n"));
+//!
+//! let back: Info = facet_plist_xml::from_str(&plist).unwrap();
+//! assert_eq!(back, info);
+//! ```
+
+mod parser;
+mod serializer;
+
+use core::convert::Infallible;
+use core::fmt;
+
+use facet_core::Facet;
+use facet_dom::{DomDeserializeError, DomDeserializer, DomSerializeError, VecEventParser};
+use facet_reflect::Peek;
+use facet_xml::XmlError;
+
+use crate::serializer::PlistWriter;
+
+/// Error reading a property list.
+#[derive(Debug)]
+pub enum DeserializeError {
+    /// The input isn't well-formed XML.
+    Xml(XmlError),
+    /// The input is XML, but not a property list this crate can read.
+    Plist(String),
+    /// The property list doesn't fit the target type.
+    Value(DomDeserializeError<Infallible>),
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeserializeError::Xml(e) => write!(f, "invalid XML: {e}"),
+            DeserializeError::Plist(msg) => write!(f, "invalid property list: {msg}"),
+            DeserializeError::Value(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for DeserializeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DeserializeError::Xml(e) => Some(e),
+            DeserializeError::Plist(_) => None,
+            DeserializeError::Value(e) => Some(e),
+        }
+    }
+}
+
+/// A value that has no property list form.
+#[derive(Debug)]
+pub struct PlistSerializeError {
+    msg: String,
+}
+
+impl fmt::Display for PlistSerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.msg)
+    }
+}
+
+impl std::error::Error for PlistSerializeError {}
+
+/// Error writing a property list.
+pub type SerializeError = DomSerializeError<PlistSerializeError>;
+
+/// Deserialize a property list from a string.
+pub fn from_str<T>(input: &str) -> Result<T, DeserializeError>
+where
+    T: Facet<'static>,
+{
+    let root = facet_dom::naming::shape_element_name(T::SHAPE);
+    let events = parser::dom_events(input, root)?;
+    DomDeserializer::new_owned(VecEventParser::new(events))
+        .deserialize()
+        .map_err(DeserializeError::Value)
+}
+
+/// Deserialize a property list from bytes.
+pub fn from_slice<T>(input: &[u8]) -> Result<T, DeserializeError>
+where
+    T: Facet<'static>,
+{
+    let input =
+        core::str::from_utf8(input).map_err(|e| DeserializeError::Xml(XmlError::InvalidUtf8(e)))?;
+    from_str(input)
+}
+
+/// Serialize a value to a property list, laid out like Apple's tools do:
+/// one value per line, indented with tabs.
+pub fn to_string<'facet, T>(value: &T) -> Result<String, SerializeError>
+where
+    T: Facet<'facet> + ?Sized,
+{
+    let mut writer = PlistWriter::new();
+    facet_dom::serialize(&mut writer, Peek::new(value))?;
+    writer.finish().map_err(DomSerializeError::Backend)
+}
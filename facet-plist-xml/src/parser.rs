@@ -0,0 +1,163 @@
+//! Reading property lists as the element events of plain XML.
+
+use std::borrow::Cow;
+
+use facet_dom::{DomEvent, DomParser};
+use facet_xml::{DeserializeOptions, Whitespace, XmlError, XmlParser};
+
+use crate::DeserializeError;
+
+/// A significant event of the property list: the start of an element, by
+/// name, or the end of the current one.
+enum Item<'de> {
+    Start(Cow<'de, str>),
+    End,
+}
+
+/// Rewrites `<dict>`/`<key>` pairs into named elements and `<array>`s into
+/// repeated ones, so that the generic DOM deserializer can read them.
+struct Translator<'de> {
+    parser: XmlParser<'de>,
+    events: Vec<DomEvent<'de>>,
+}
+
+/// The events of the property list `input`, with its top-level dict as an
+/// element named `root`.
+pub(crate) fn dom_events<'de>(
+    input: &'de str,
+    root: Cow<'static, str>,
+) -> Result<Vec<DomEvent<'de>>, DeserializeError> {
+    // Whitespace inside <string> is part of the value
+    let options = DeserializeOptions::default().whitespace(Whitespace::Preserve);
+    let mut translator = Translator {
+        parser: XmlParser::with_options(input.as_bytes(), &options),
+        events: Vec::new(),
+    };
+
+    match translator.next_item()? {
+        Some(Item::Start(tag)) if tag == "plist" => {}
+        _ => return Err(invalid("the document element must be <plist>")),
+    }
+    match translator.next_item()? {
+        Some(Item::Start(tag)) if tag == "dict" => translator.value(root, &tag)?,
+        _ => return Err(invalid("the top-level value must be a <dict>")),
+    }
+    if !matches!(translator.next_item()?, Some(Item::End)) {
+        return Err(invalid("<plist> must hold a single value"));
+    }
+    Ok(translator.events)
+}
+
+fn invalid(msg: impl Into<String>) -> DeserializeError {
+    DeserializeError::Plist(msg.into())
+}
+
+impl<'de> Translator<'de> {
+    fn next_event(&mut self) -> Result<Option<DomEvent<'de>>, DeserializeError> {
+        self.parser.next_event().map_err(DeserializeError::Xml)
+    }
+
+    /// The next element start or end, skipping whitespace, comments and
+    /// attributes.
+    fn next_item(&mut self) -> Result<Option<Item<'de>>, DeserializeError> {
+        loop {
+            match self.next_event()? {
+                None => return Ok(None),
+                Some(DomEvent::NodeStart { tag, .. }) => return Ok(Some(Item::Start(tag))),
+                Some(DomEvent::NodeEnd) => return Ok(Some(Item::End)),
+                Some(DomEvent::Text(text)) if !text.trim().is_empty() => {
+                    return Err(invalid(format!("unexpected text {:?}", text.trim())));
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// The text of the element just started, up to its end.
+    fn text(&mut self) -> Result<Cow<'de, str>, DeserializeError> {
+        let mut text = Cow::Borrowed("");
+        loop {
+            match self.next_event()? {
+                None => return Err(DeserializeError::Xml(XmlError::UnexpectedEof)),
+                Some(DomEvent::NodeEnd) => return Ok(text),
+                Some(DomEvent::Text(more)) if text.is_empty() => text = more,
+                Some(DomEvent::Text(more)) => text.to_mut().push_str(&more),
+                Some(DomEvent::NodeStart { tag, .. }) => {
+                    return Err(invalid(format!("unexpected <{tag}> in a scalar value")));
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Translate the value whose element `tag` was just started, naming it
+    /// `name`.
+    fn value(&mut self, name: Cow<'de, str>, tag: &str) -> Result<(), DeserializeError> {
+        match tag {
+            "dict" => {
+                self.start(name);
+                loop {
+                    match self.next_item()? {
+                        Some(Item::End) => break,
+                        Some(Item::Start(tag)) if tag == "key" => {
+                            let key = self.text()?;
+                            match self.next_item()? {
+                                Some(Item::Start(tag)) => self.value(key, &tag)?,
+                                _ => return Err(invalid(format!("key {key:?} has no value"))),
+                            }
+                        }
+                        Some(Item::Start(tag)) => {
+                            return Err(invalid(format!(
+                                "expected <key> in <dict>, found <{tag}>"
+                            )));
+                        }
+                        None => return Err(DeserializeError::Xml(XmlError::UnexpectedEof)),
+                    }
+                }
+                self.end();
+            }
+            "array" => loop {
+                match self.next_item()? {
+                    Some(Item::End) => break,
+                    Some(Item::Start(tag)) if tag == "array" => {
+                        return Err(invalid(format!("{name:?} holds nested arrays")));
+                    }
+                    Some(Item::Start(tag)) => self.value(name.clone(), &tag)?,
+                    None => return Err(DeserializeError::Xml(XmlError::UnexpectedEof)),
+                }
+            },
+            "true" | "false" => {
+                self.text()?;
+                let value = if tag == "true" { "true" } else { "false" };
+                self.scalar(name, Cow::Borrowed(value));
+            }
+            "string" | "integer" | "real" | "date" | "data" => {
+                let text = self.text()?;
+                self.scalar(name, text);
+            }
+            _ => return Err(invalid(format!("unknown value type <{tag}>"))),
+        }
+        Ok(())
+    }
+
+    fn start(&mut self, name: Cow<'de, str>) {
+        self.events.push(DomEvent::NodeStart {
+            tag: name,
+            namespace: None,
+        });
+        self.events.push(DomEvent::ChildrenStart);
+    }
+
+    fn end(&mut self) {
+        self.events.push(DomEvent::ChildrenEnd);
+        self.events.push(DomEvent::NodeEnd);
+    }
+
+    fn scalar(&mut self, name: Cow<'de, str>, text: Cow<'de, str>) {
+        self.start(name);
+        if !text.is_empty() {
+            self.events.push(DomEvent::Text(text));
+        }
+        self.end();
+    }
+}
@@ -0,0 +1,301 @@
+//! Writing the element events of plain XML as a property list.
+
+use facet_core::{Def, ScalarType, Shape, Type, UserType};
+use facet_dom::DomSerializer;
+use facet_reflect::{FieldItem, Peek};
+
+use crate::PlistSerializeError;
+
+const HEADER: &str = concat!(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+    "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" ",
+    "\"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+    "<plist version=\"1.0\">\n",
+);
+
+/// How an element is written.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// A struct or map: `<dict>` with a key per child element
+    Dict,
+    /// A scalar wrapped in this element (`string`, `integer`, ...)
+    Scalar(&'static str),
+    /// A `bool`, written `<true/>` or `<false/>`
+    Bool,
+    /// Unknown until a child element (`<dict>`) or the end (`<string>`)
+    Pending,
+}
+
+/// An element being written.
+struct Frame {
+    kind: Kind,
+    text: String,
+    /// Whether the child elements being written are the items of a list
+    list: bool,
+    /// Key of the `<array>` open among the children
+    array: Option<String>,
+}
+
+/// A [`DomSerializer`] writing property lists.
+///
+/// The generic serializer names each child element after its field or map
+/// key and repeats it for each item of a list; this turns the names into
+/// `<key>`s and the repeated items into `<array>`s. Which elements are
+/// lists comes from the field metadata, and the scalar types from the
+/// values announced by `before_element`.
+pub(crate) struct PlistWriter {
+    out: String,
+    stack: Vec<Frame>,
+    /// Open `<dict>`s and `<array>`s, for indentation
+    depth: usize,
+    /// Kind of the next element and whether its children are list items
+    next: Option<(Kind, bool)>,
+    /// Set from a struct's metadata until its element starts: field
+    /// metadata seen meanwhile describes it, not the open element
+    starting_struct: bool,
+    /// Whether the top-level value was written
+    done: bool,
+}
+
+impl PlistWriter {
+    pub(crate) fn new() -> Self {
+        Self {
+            out: String::new(),
+            stack: Vec::new(),
+            depth: 0,
+            next: None,
+            starting_struct: false,
+            done: false,
+        }
+    }
+
+    pub(crate) fn finish(self) -> Result<String, PlistSerializeError> {
+        if self.done {
+            Ok(self.out)
+        } else {
+            Err(error("the top-level value must be a struct"))
+        }
+    }
+
+    fn line(&mut self, markup: &str) {
+        for _ in 0..self.depth {
+            self.out.push('\t');
+        }
+        self.out.push_str(markup);
+        self.out.push('\n');
+    }
+
+    fn open(&mut self, tag: &str) {
+        self.line(&format!("<{tag}>"));
+        self.depth += 1;
+    }
+
+    fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.line(&format!("</{tag}>"));
+    }
+
+    /// Close the `<array>` open in the innermost element, if any.
+    fn close_array(&mut self) {
+        if let Some(frame) = self.stack.last_mut()
+            && frame.array.take().is_some()
+        {
+            self.close("array");
+        }
+    }
+
+    fn key(&mut self, key: &str) {
+        self.line(&format!("<key>{}</key>", escape(key)));
+    }
+}
+
+fn error(msg: impl Into<String>) -> PlistSerializeError {
+    PlistSerializeError { msg: msg.into() }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `shape` without `Option`s around it.
+fn unwrap_option(mut shape: &'static Shape) -> &'static Shape {
+    while let Def::Option(option) = shape.def {
+        shape = option.t();
+    }
+    shape
+}
+
+fn is_list(shape: &'static Shape) -> bool {
+    matches!(
+        unwrap_option(shape).def,
+        Def::List(_) | Def::Array(_) | Def::Slice(_) | Def::Set(_)
+    )
+}
+
+/// How the element holding `value` is written, and whether its children
+/// are list items (the values of a map of lists).
+fn kind_of(value: Peek<'_, '_>) -> (Kind, bool) {
+    let shape = value.shape();
+    if let Def::Map(map) = shape.def {
+        return (Kind::Dict, is_list(map.v()));
+    }
+    let kind = match value.scalar_type() {
+        Some(ScalarType::Bool) => Kind::Bool,
+        Some(
+            ScalarType::U8
+            | ScalarType::U16
+            | ScalarType::U32
+            | ScalarType::U64
+            | ScalarType::U128
+            | ScalarType::USize
+            | ScalarType::I8
+            | ScalarType::I16
+            | ScalarType::I32
+            | ScalarType::I64
+            | ScalarType::I128
+            | ScalarType::ISize,
+        ) => Kind::Scalar("integer"),
+        Some(ScalarType::F32 | ScalarType::F64) => Kind::Scalar("real"),
+        Some(_) => Kind::Scalar("string"),
+        None if matches!(shape.ty, Type::User(UserType::Struct(_))) => Kind::Dict,
+        None => Kind::Pending,
+    };
+    (kind, false)
+}
+
+impl DomSerializer for PlistWriter {
+    type Error = PlistSerializeError;
+
+    fn element_start(&mut self, tag: &str, _namespace: Option<&str>) -> Result<(), Self::Error> {
+        self.starting_struct = false;
+        let (kind, list) = self.next.take().unwrap_or((Kind::Pending, false));
+        match self.stack.last_mut() {
+            None if self.done => return Err(error("only one top-level value can be written")),
+            None => self.out.push_str(HEADER),
+            Some(parent) => {
+                match parent.kind {
+                    Kind::Dict => {}
+                    Kind::Pending => {
+                        parent.kind = Kind::Dict;
+                        self.open("dict");
+                    }
+                    Kind::Scalar(_) | Kind::Bool => {
+                        return Err(error(format!("<{tag}> inside a scalar value")));
+                    }
+                }
+                let parent = self.stack.last_mut().expect("parent frame");
+                if parent.list {
+                    if parent.array.as_deref() != Some(tag) {
+                        self.close_array();
+                        self.key(tag);
+                        self.open("array");
+                        self.stack.last_mut().expect("parent frame").array = Some(tag.to_string());
+                    }
+                } else {
+                    self.close_array();
+                    self.key(tag);
+                }
+            }
+        }
+        if kind == Kind::Dict {
+            self.open("dict");
+        }
+        self.stack.push(Frame {
+            kind,
+            text: String::new(),
+            list,
+            array: None,
+        });
+        Ok(())
+    }
+
+    fn attribute(
+        &mut self,
+        name: &str,
+        _value: Peek<'_, '_>,
+        _namespace: Option<&str>,
+    ) -> Result<(), Self::Error> {
+        Err(error(format!(
+            "attribute {name:?} has no property list equivalent"
+        )))
+    }
+
+    fn children_start(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn children_end(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn element_end(&mut self, _tag: &str) -> Result<(), Self::Error> {
+        self.close_array();
+        let Some(frame) = self.stack.pop() else {
+            return Ok(());
+        };
+        match frame.kind {
+            Kind::Dict => self.close("dict"),
+            Kind::Bool => self.line(if frame.text == "true" {
+                "<true/>"
+            } else {
+                "<false/>"
+            }),
+            Kind::Scalar(tag) => self.line(&format!("<{tag}>{}</{tag}>", escape(&frame.text))),
+            Kind::Pending => self.line(&format!("<string>{}</string>", escape(&frame.text))),
+        }
+        if self.stack.is_empty() {
+            self.out.push_str("</plist>\n");
+            self.done = true;
+        }
+        Ok(())
+    }
+
+    fn text(&mut self, content: &str) -> Result<(), Self::Error> {
+        match self.stack.last_mut() {
+            Some(frame) if frame.kind != Kind::Dict => {
+                frame.text.push_str(content);
+                Ok(())
+            }
+            _ if content.trim().is_empty() => Ok(()),
+            _ => Err(error("text outside a scalar value")),
+        }
+    }
+
+    fn before_element(&mut self, _tag: &str, value: Peek<'_, '_>) -> Result<(), Self::Error> {
+        self.next = Some(kind_of(value));
+        Ok(())
+    }
+
+    fn struct_metadata(&mut self, _shape: &Shape) -> Result<(), Self::Error> {
+        self.starting_struct = true;
+        Ok(())
+    }
+
+    fn field_metadata(&mut self, field: &FieldItem) -> Result<(), Self::Error> {
+        if !self.starting_struct
+            && let Some(frame) = self.stack.last_mut()
+        {
+            frame.list = field.field.is_some_and(|field| is_list(field.shape()));
+        }
+        Ok(())
+    }
+
+    fn clear_field_state(&mut self) {
+        if !self.starting_struct {
+            self.close_array();
+        }
+    }
+
+    fn sort_map_keys(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+
+use facet::Facet;
+use facet_plist_xml::{DeserializeError, from_str, to_string};
+use facet_testhelpers::test;
+use indoc::indoc;
+
+#[derive(Facet, Debug, PartialEq)]
+struct Document {
+    name: String,
+    count: i64,
+    ratio: f64,
+    enabled: bool,
+    tags: Vec<String>,
+    owner: Person,
+    people: Vec<Person>,
+    nickname: Option<String>,
+    limits: BTreeMap<String, u32>,
+}
+
+#[derive(Facet, Debug, PartialEq)]
+struct Person {
+    name: String,
+    admin: bool,
+}
+
+fn document() -> Document {
+    Document {
+        name: "a < b & c".into(),
+        count: -3,
+        ratio: 0.5,
+        enabled: false,
+        tags: vec!["x".into(), "y".into()],
+        owner: Person {
+            name: "root".into(),
+            admin: true,
+        },
+        people: vec![
+            Person {
+                name: "ann".into(),
+                admin: false,
+            },
+            Person {
+                name: "bob".into(),
+                admin: true,
+            },
+        ],
+        nickname: None,
+        limits: [("cpu".to_string(), 2), ("memory".to_string(), 512)].into(),
+    }
+}
+
+#[test]
+fn writes_apple_layout() {
+    let plist = to_string(&document()).unwrap();
+    assert_eq!(
+        plist,
+        indoc! {r#"
+            <?xml version="1.0" encoding="UTF-8"?>
+            <!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+            <plist version="1.0">
+            <dict>
+            	<key>name</key>
+            	<string>a &lt; b &amp; c</string>
+            	<key>count</key>
+            	<integer>-3</integer>
+            	<key>ratio</key>
+            	<real>0.5</real>
+            	<key>enabled</key>
+            	<false/>
+            	<key>tags</key>
+            	<array>
+            		<string>x</string>
+            		<string>y</string>
+            	</array>
+            	<key>owner</key>
+            	<dict>
+            		<key>name</key>
+            		<string>root</string>
+            		<key>admin</key>
+            		<true/>
+            	</dict>
+            	<key>people</key>
+            	<array>
+            		<dict>
+            			<key>name</key>
+            			<string>ann</string>
+            			<key>admin</key>
+            			<false/>
+            		</dict>
+            		<dict>
+            			<key>name</key>
+            			<string>bob</string>
+            			<key>admin</key>
+            			<true/>
+            		</dict>
+            	</array>
+            	<key>limits</key>
+            	<dict>
+            		<key>cpu</key>
+            		<integer>2</integer>
+            		<key>memory</key>
+            		<integer>512</integer>
+            	</dict>
+            </dict>
+            </plist>
+        "#}
+    );
+    assert_eq!(from_str::<Document>(&plist).unwrap(), document());
+}
+
+#[test]
+fn single_item_lists_are_still_arrays() {
+    let mut doc = document();
+    doc.tags = vec!["only".into()];
+    doc.people.clear();
+    let plist = to_string(&doc).unwrap();
+    assert!(plist.contains("<key>tags</key>\n\t<array>\n\t\t<string>only</string>\n\t</array>"));
+    assert!(!plist.contains("<key>people</key>"));
+    assert_eq!(from_str::<Document>(&plist).unwrap(), doc);
+}
+
+#[test]
+fn reads_property_lists_written_elsewhere() {
+    #[derive(Facet, Debug, PartialEq)]
+    struct Info {
+        #[facet(rename = "CFBundleIdentifier")]
+        identifier: String,
+        #[facet(rename = "CFBundleIcons")]
+        icons: Vec<String>,
+        #[facet(rename = "LSMinimumSystemVersion")]
+        minimum_version: Option<String>,
+        #[facet(rename = "NSHighResolutionCapable")]
+        high_resolution: bool,
+    }
+
+    let plist = indoc! {r#"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+        <plist version="1.0">
+        <dict>
+            <!-- generated -->
+            <key>CFBundleIdentifier</key>
+            <string> com.example.app </string>
+            <key>CFBundleIcons</key>
+            <array>
+                <string>AppIcon</string>
+                <string>AppIcon-dark</string>
+            </array>
+            <key>NSHighResolutionCapable</key>
+            <true/>
+        </dict>
+        </plist>
+    "#};
+    let info: Info = from_str(plist).unwrap();
+    assert_eq!(
+        info,
+        Info {
+            identifier: " com.example.app ".into(),
+            icons: vec!["AppIcon".into(), "AppIcon-dark".into()],
+            minimum_version: None,
+            high_resolution: true,
+        }
+    );
+}
+
+#[test]
+fn rejects_what_has_no_mapping() {
+    let not_a_plist = "<dict><key>name</key><string>a</string></dict>";
+    assert!(matches!(
+        from_str::<Person>(not_a_plist),
+        Err(DeserializeError::Plist(_))
+    ));
+
+    let nested =
+        r#"<plist version="1.0"><dict><key>name</key><array><array/></array></dict></plist>"#;
+    let err = from_str::<Person>(nested).unwrap_err();
+    assert!(err.to_string().contains("nested arrays"), "{err}");
+
+    let wrong_type =
+        r#"<plist version="1.0"><dict><key>count</key><string>many</string></dict></plist>"#;
+    #[derive(Facet, Debug)]
+    struct Counter {
+        count: u32,
+    }
+    assert!(matches!(
+        from_str::<Counter>(wrong_type),
+        Err(DeserializeError::Value(_))
+    ));
+
+    assert!(to_string(&42u32).is_err());
+}
@@ -0,0 +1,126 @@
+//! The value of `style` attributes.
+
+use core::fmt;
+
+use facet::Facet;
+
+/// The declarations of a `style` attribute, such as
+/// `fill:red;stroke-width:2`, in the order they were written.
+///
+/// Parsing splits on `;` and the first `:` of each declaration, trimming
+/// whitespace and skipping empty declarations; written back, declarations
+/// are separated by `;` without spaces.
+///
+/// ```
+/// use facet_svg::InlineStyle;
+///
+/// let mut style: InlineStyle = "fill: red; stroke-width: 2;".parse().unwrap();
+/// assert_eq!(style.get("stroke-width"), Some("2"));
+/// style.set("fill", "blue");
+/// assert_eq!(style.to_string(), "fill:blue;stroke-width:2");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Facet)]
+#[facet(proxy = String)]
+pub struct InlineStyle {
+    declarations: Vec<(String, String)>,
+}
+
+impl InlineStyle {
+    /// A style without declarations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// This style with `property` set to `value`.
+    pub fn with(mut self, property: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set(property, value);
+        self
+    }
+
+    /// The value of `property`, if declared.
+    pub fn get(&self, property: &str) -> Option<&str> {
+        self.declarations
+            .iter()
+            .find(|(name, _)| name == property)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Set `property` to `value`, keeping its place if already declared.
+    pub fn set(&mut self, property: impl Into<String>, value: impl Into<String>) {
+        let property = property.into();
+        let value = value.into();
+        match self
+            .declarations
+            .iter_mut()
+            .find(|(name, _)| *name == property)
+        {
+            Some((_, existing)) => *existing = value,
+            None => self.declarations.push((property, value)),
+        }
+    }
+
+    /// Remove `property`, returning its value.
+    pub fn remove(&mut self, property: &str) -> Option<String> {
+        let index = self
+            .declarations
+            .iter()
+            .position(|(name, _)| name == property)?;
+        Some(self.declarations.remove(index).1)
+    }
+
+    /// The declarations as property-value pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.declarations
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Whether there are no declarations.
+    pub fn is_empty(&self) -> bool {
+        self.declarations.is_empty()
+    }
+}
+
+impl core::str::FromStr for InlineStyle {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut style = InlineStyle::new();
+        for declaration in text.split(';') {
+            if declaration.trim().is_empty() {
+                continue;
+            }
+            let Some((property, value)) = declaration.split_once(':') else {
+                return Err(format!("invalid style declaration {declaration:?}"));
+            };
+            style.set(property.trim(), value.trim());
+        }
+        Ok(style)
+    }
+}
+
+impl fmt::Display for InlineStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (property, value)) in self.declarations.iter().enumerate() {
+            if i > 0 {
+                f.write_str(";")?;
+            }
+            write!(f, "{property}:{value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for InlineStyle {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+
+impl From<&InlineStyle> for String {
+    fn from(style: &InlineStyle) -> Self {
+        style.to_string()
+    }
+}
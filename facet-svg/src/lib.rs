@@ -21,11 +21,15 @@ use facet::Facet;
 use facet_xml as xml;
 use facet_xml::to_vec;
 
+mod inline_style;
 mod path;
 mod points;
+mod serializer;
 
+pub use inline_style::InlineStyle;
 pub use path::{PathCommand, PathData, PathDataProxy};
 pub use points::{Point, Points, PointsProxy};
+pub use serializer::SvgSerializer;
 
 /// SVG namespace URI
 pub const SVG_NS: &str = "http://www.w3.org/2000/svg";
@@ -0,0 +1,235 @@
+//! Writing SVG documents.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use facet::Facet;
+use facet_xml::{SerializeError, SerializeOptions, XmlSerializeError};
+
+/// An XML serializer following the conventions of SVG documents.
+///
+/// Compared with plain [`facet_xml::to_string`]:
+///
+/// - entity references already in strings (`&nbsp;`, `&#x2014;`) are
+///   written as they are rather than escaped;
+/// - elements without content are written `<rect/>`;
+/// - floating-point attributes can be rounded, for all attributes with
+///   [`precision`](Self::precision) or per attribute with
+///   [`attribute_precision`](Self::attribute_precision), so coordinates
+///   don't come out as `10.000000000000002`.
+///
+/// Use [`InlineStyle`](crate::InlineStyle) for `style` attributes.
+///
+/// ```
+/// use facet::Facet;
+/// use facet_svg::{InlineStyle, SvgSerializer};
+/// use facet_xml as xml;
+///
+/// #[derive(Facet)]
+/// #[facet(rename = "svg")]
+/// struct Drawing {
+///     #[facet(xml::attribute)]
+///     width: f64,
+///     #[facet(rename = "circle")]
+///     circles: Vec<Circle>,
+/// }
+///
+/// #[derive(Facet)]
+/// struct Circle {
+///     #[facet(xml::attribute)]
+///     cx: f64,
+///     #[facet(xml::attribute)]
+///     r: f64,
+///     #[facet(xml::attribute)]
+///     style: InlineStyle,
+/// }
+///
+/// let drawing = Drawing {
+///     width: 100.0,
+///     circles: vec![Circle {
+///         cx: 0.1 + 0.2,
+///         r: 2.0 / 3.0,
+///         style: InlineStyle::new().with("fill", "red"),
+///     }],
+/// };
+/// let svg = SvgSerializer::new()
+///     .precision(3)
+///     .attribute_precision("r", 1)
+///     .to_string(&drawing)
+///     .unwrap();
+/// assert_eq!(
+///     svg,
+///     r#"<svg width="100"><circle cx="0.3" r="0.7" style="fill:red"/></svg>"#
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct SvgSerializer {
+    options: SerializeOptions,
+    precision: Option<usize>,
+    attribute_precision: HashMap<String, usize>,
+}
+
+impl Default for SvgSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SvgSerializer {
+    /// A serializer writing compact output with numbers in full.
+    pub fn new() -> Self {
+        Self::with_options(SerializeOptions::new())
+    }
+
+    /// A serializer starting from `options`, with the SVG conventions
+    /// turned on.
+    pub fn with_options(options: SerializeOptions) -> Self {
+        Self {
+            options: options.preserve_entities(true).self_closing(true),
+            precision: None,
+            attribute_precision: HashMap::new(),
+        }
+    }
+
+    /// Pretty-print with the default indentation.
+    pub fn pretty(mut self) -> Self {
+        self.options = self.options.pretty();
+        self
+    }
+
+    /// Round floating-point attribute values to `places` decimal places,
+    /// unless [`attribute_precision`](Self::attribute_precision) says
+    /// otherwise for the attribute.
+    pub fn precision(mut self, places: usize) -> Self {
+        self.precision = Some(places);
+        self
+    }
+
+    /// Round floating-point values of attribute `name` to `places` decimal
+    /// places.
+    pub fn attribute_precision(mut self, name: impl Into<String>, places: usize) -> Self {
+        self.attribute_precision.insert(name.into(), places);
+        self
+    }
+
+    /// The serialization options these settings amount to.
+    pub fn options(&self) -> SerializeOptions {
+        let mut options = self.options.clone();
+        if self.precision.is_some() || !self.attribute_precision.is_empty() {
+            let default = self.precision;
+            let by_name = self.attribute_precision.clone();
+            options =
+                options.attribute_precision(move |name| by_name.get(name).copied().or(default));
+        }
+        options
+    }
+
+    /// Serialize `value` to a string.
+    pub fn to_string<'facet, T>(
+        &self,
+        value: &'_ T,
+    ) -> Result<String, SerializeError<XmlSerializeError>>
+    where
+        T: Facet<'facet> + ?Sized,
+    {
+        facet_xml::to_string_with_options(value, &self.options())
+    }
+
+    /// Serialize `value` into `writer`.
+    pub fn to_writer<'facet, W, T>(
+        &self,
+        writer: &mut W,
+        value: &'_ T,
+    ) -> Result<(), SerializeError<XmlSerializeError>>
+    where
+        W: Write + ?Sized,
+        T: Facet<'facet> + ?Sized,
+    {
+        facet_xml::to_writer_with_options(writer, value, &self.options())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InlineStyle;
+    use facet_xml as xml;
+
+    #[derive(Debug, PartialEq, Facet)]
+    #[facet(rename = "svg", xml::ns_all = "http://www.w3.org/2000/svg")]
+    struct Svg {
+        #[facet(xml::attribute, rename = "viewBox")]
+        view_box: String,
+        #[facet(rename = "rect")]
+        rects: Vec<Rect>,
+        title: String,
+    }
+
+    #[derive(Debug, PartialEq, Facet)]
+    struct Rect {
+        #[facet(xml::attribute)]
+        x: f64,
+        #[facet(xml::attribute)]
+        width: f32,
+        #[facet(xml::attribute)]
+        opacity: Option<f64>,
+        #[facet(xml::attribute)]
+        style: InlineStyle,
+    }
+
+    fn drawing() -> Svg {
+        Svg {
+            view_box: "0 0 10 10".into(),
+            rects: vec![Rect {
+                x: 1.0 / 3.0,
+                width: 2.5,
+                opacity: Some(0.375),
+                style: InlineStyle::new()
+                    .with("fill", "red")
+                    .with("stroke", "none"),
+            }],
+            title: "Caf&eacute; &#x2014; map".into(),
+        }
+    }
+
+    #[test]
+    fn svg_conventions() {
+        let svg = SvgSerializer::new()
+            .precision(2)
+            .attribute_precision("opacity", 1)
+            .to_string(&drawing())
+            .unwrap();
+        assert_eq!(
+            svg,
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10">"#,
+                r#"<rect x="0.33" width="2.5" opacity="0.4" style="fill:red;stroke:none"/>"#,
+                "<title>Caf&eacute; &#x2014; map</title></svg>"
+            )
+        );
+    }
+
+    #[test]
+    fn pretty_output_closes_empty_elements() {
+        let svg = SvgSerializer::new().pretty().to_string(&drawing()).unwrap();
+        assert!(svg.contains("\n  <rect x=\"0.3333333333333333\""));
+        assert!(svg.contains("style=\"fill:red;stroke:none\"/>\n  <title>"));
+    }
+
+    #[test]
+    fn style_roundtrips_through_attributes() {
+        let input = r#"<rect x="1" width="2" style=" fill : red ; ;stroke-width:2 "/>"#;
+        let rect: Rect = crate::from_str(input).unwrap();
+        assert_eq!(rect.style.get("fill"), Some("red"));
+        assert_eq!(rect.style.get("stroke-width"), Some("2"));
+        assert_eq!(
+            SvgSerializer::new().to_string(&rect).unwrap(),
+            r#"<rect x="1" width="2" style="fill:red;stroke-width:2"/>"#
+        );
+
+        assert!("fill".parse::<InlineStyle>().is_err());
+        let mut style: InlineStyle = "a:1;b:2".parse().unwrap();
+        assert_eq!(style.remove("a"), Some("1".into()));
+        assert_eq!(style.iter().collect::<Vec<_>>(), [("b", "2")]);
+    }
+}
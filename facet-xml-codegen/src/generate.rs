@@ -0,0 +1,545 @@
+//! Emitting Rust source for the definitions of a schema.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write as _;
+
+use facet_xml_node::Element;
+
+use crate::CodegenError;
+use crate::naming::{default_xml_name, field_name, literal, plural, type_name};
+use crate::schema::{Schema, TypeRef, children, documentation, split_qname};
+
+/// Namespace of the `xml:` attributes, e.g. `xml:lang`.
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// A struct field being generated.
+struct Field {
+    doc: Option<String>,
+    attrs: Vec<String>,
+    name: String,
+    ty: String,
+}
+
+/// How often a particle may occur.
+#[derive(Clone, Copy)]
+struct Occurs {
+    optional: bool,
+    repeated: bool,
+}
+
+impl Occurs {
+    const ONCE: Occurs = Occurs {
+        optional: false,
+        repeated: false,
+    };
+
+    /// The occurrence of a particle, given the occurrence of its parent.
+    fn of(particle: &Element, parent: Occurs) -> Occurs {
+        let min = particle.get_attr("minOccurs").unwrap_or("1");
+        let max = particle.get_attr("maxOccurs").unwrap_or("1");
+        Occurs {
+            optional: parent.optional || min == "0",
+            repeated: parent.repeated
+                || max == "unbounded"
+                || max.parse::<u64>().is_ok_and(|max| max > 1),
+        }
+    }
+}
+
+/// An anonymous type still to be emitted.
+struct Pending<'a> {
+    name: String,
+    def: &'a Element,
+}
+
+pub(crate) struct Generator<'s, 'a> {
+    schema: &'s Schema<'a>,
+    derives: &'s [String],
+    out: String,
+    /// Rust names given out so far
+    taken: HashSet<String>,
+    /// Rust names of the named complex and simple types
+    type_names: HashMap<&'a str, String>,
+    /// Top-level element each named complex type is the root of
+    root_elements: HashMap<&'a str, &'a str>,
+    /// Anonymous types met while emitting the current definition
+    pending: VecDeque<Pending<'a>>,
+}
+
+impl<'s, 'a> Generator<'s, 'a> {
+    pub(crate) fn new(schema: &'s Schema<'a>, derives: &'s [String]) -> Self {
+        Self {
+            schema,
+            derives,
+            out: String::new(),
+            taken: HashSet::new(),
+            type_names: HashMap::new(),
+            root_elements: HashMap::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn run(mut self) -> Result<String, CodegenError> {
+        self.out.push_str(
+            "// Generated by facet-xml-codegen. Do not edit.\n\n\
+             use facet::Facet;\n\
+             use facet_xml as xml;\n",
+        );
+
+        let root = self.schema.root;
+        for def in root.child_elements() {
+            let Some(name) = def.get_attr("name") else {
+                continue;
+            };
+            match def.tag.as_str() {
+                "complexType" | "simpleType" => {
+                    let rust_name = self.claim(type_name(name));
+                    self.type_names.insert(name, rust_name);
+                }
+                "element" => {
+                    if let Some(ty) = def.get_attr("type")
+                        && let TypeRef::Complex(ty) = self.schema.resolve_type(ty)?
+                    {
+                        self.root_elements.entry(ty).or_insert(name);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for def in root.child_elements() {
+            let Some(name) = def.get_attr("name") else {
+                continue;
+            };
+            match def.tag.as_str() {
+                "complexType" => {
+                    let rust_name = self.type_names[name].clone();
+                    let element = self.root_elements.get(name).copied();
+                    self.emit_struct(&rust_name, def, element, documentation(def))?;
+                }
+                "simpleType" => {
+                    let rust_name = self.type_names[name].clone();
+                    self.emit_simple(&rust_name, def, documentation(def))?;
+                }
+                "element" => self.emit_root_element(name, def)?,
+                _ => {}
+            }
+            while let Some(Pending { name, def }) = self.pending.pop_front() {
+                match def.tag.as_str() {
+                    "complexType" => self.emit_struct(&name, def, None, None)?,
+                    _ => self.emit_simple(&name, def, None)?,
+                }
+            }
+        }
+        Ok(self.out)
+    }
+
+    /// Emit the type for a top-level element with an inline type. Elements
+    /// of a named type are emitted with that type.
+    fn emit_root_element(&mut self, name: &'a str, def: &'a Element) -> Result<(), CodegenError> {
+        let doc = documentation(def);
+        if let Some(ty) = children(def, "complexType").next() {
+            let rust_name = self.claim(type_name(name));
+            return self.emit_struct(&rust_name, ty, Some(name), doc);
+        }
+        if let Some(ty) = children(def, "simpleType").next() {
+            let rust_name = self.claim(type_name(name));
+            return self.emit_simple(&rust_name, ty, doc);
+        }
+        Ok(())
+    }
+
+    fn emit_struct(
+        &mut self,
+        name: &str,
+        def: &'a Element,
+        element: Option<&str>,
+        doc: Option<String>,
+    ) -> Result<(), CodegenError> {
+        let mut fields = Vec::new();
+        self.content(name, def, &mut fields)?;
+
+        // Fields generated from different parts of the schema may collide
+        let mut seen = HashSet::new();
+        for field in &mut fields {
+            let base = field.name.clone();
+            let mut n = 2;
+            while !seen.insert(field.name.clone()) {
+                field.name = format!("{base}_{n}");
+                n += 1;
+            }
+        }
+
+        let mut type_attrs = Vec::new();
+        if self.schema.qualified
+            && let Some(ns) = self.schema.target_namespace
+        {
+            type_attrs.push(format!("xml::ns_all = {}", literal(ns)));
+        }
+        if let Some(element) = element {
+            type_attrs.push(format!("rename = {}", literal(element)));
+        }
+
+        self.out.push('\n');
+        write_doc(&mut self.out, "", doc.as_deref());
+        self.write_derives();
+        if !type_attrs.is_empty() {
+            let _ = writeln!(self.out, "#[facet({})]", type_attrs.join(", "));
+        }
+        if fields.is_empty() {
+            let _ = writeln!(self.out, "pub struct {name} {{}}");
+            return Ok(());
+        }
+        let _ = writeln!(self.out, "pub struct {name} {{");
+        for field in &fields {
+            write_doc(&mut self.out, "    ", field.doc.as_deref());
+            if !field.attrs.is_empty() {
+                let _ = writeln!(self.out, "    #[facet({})]", field.attrs.join(", "));
+            }
+            let _ = writeln!(self.out, "    pub {}: {},", field.name, field.ty);
+        }
+        self.out.push_str("}\n");
+        Ok(())
+    }
+
+    /// Collect the fields of a complex type's content model and attributes.
+    fn content(
+        &mut self,
+        owner: &str,
+        def: &'a Element,
+        fields: &mut Vec<Field>,
+    ) -> Result<(), CodegenError> {
+        if def.get_attr("mixed") == Some("true") {
+            fields.push(Field {
+                doc: None,
+                attrs: vec!["xml::text".to_string(), "default".to_string()],
+                name: "text".to_string(),
+                ty: "String".to_string(),
+            });
+        }
+        for child in def.child_elements() {
+            match child.tag.as_str() {
+                "sequence" | "choice" | "all" | "group" => {
+                    self.particle(owner, child, Occurs::ONCE, fields)?;
+                }
+                "attribute" | "attributeGroup" => self.attribute(owner, child, fields)?,
+                "simpleContent" => {
+                    for derivation in child.child_elements() {
+                        if let Some(base) = derivation.get_attr("base") {
+                            let (ty, codec) = self.type_of(base)?;
+                            let mut attrs = vec!["xml::text".to_string()];
+                            attrs.extend(codec.map(|c| format!("xml::codec = {}", literal(c))));
+                            fields.push(Field {
+                                doc: None,
+                                attrs,
+                                name: "value".to_string(),
+                                ty,
+                            });
+                        }
+                        self.content(owner, derivation, fields)?;
+                    }
+                }
+                "complexContent" => {
+                    for derivation in child.child_elements() {
+                        if derivation.tag == "extension"
+                            && let Some(base) = derivation.get_attr("base")
+                        {
+                            let (ty, _) = self.type_of(base)?;
+                            fields.push(Field {
+                                doc: None,
+                                attrs: vec!["flatten".to_string()],
+                                name: "base".to_string(),
+                                ty,
+                            });
+                        }
+                        self.content(owner, derivation, fields)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Collect the fields for a particle: an element, a model group or a
+    /// group reference.
+    fn particle(
+        &mut self,
+        owner: &str,
+        particle: &'a Element,
+        parent: Occurs,
+        fields: &mut Vec<Field>,
+    ) -> Result<(), CodegenError> {
+        let occurs = Occurs::of(particle, parent);
+        match particle.tag.as_str() {
+            "element" => fields.push(self.element(owner, particle, occurs)?),
+            "sequence" | "all" => {
+                for child in particle.child_elements() {
+                    self.particle(owner, child, occurs, fields)?;
+                }
+            }
+            // Each alternative may be absent
+            "choice" => {
+                let occurs = Occurs {
+                    optional: true,
+                    ..occurs
+                };
+                for child in particle.child_elements() {
+                    self.particle(owner, child, occurs, fields)?;
+                }
+            }
+            "group" => {
+                let group = match particle.get_attr("ref") {
+                    Some(name) => self.schema.group(name)?,
+                    None => particle,
+                };
+                for child in group.child_elements() {
+                    self.particle(owner, child, occurs, fields)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn element(
+        &mut self,
+        owner: &str,
+        particle: &'a Element,
+        occurs: Occurs,
+    ) -> Result<Field, CodegenError> {
+        let def = match particle.get_attr("ref") {
+            Some(name) => self.schema.element(name)?,
+            None => particle,
+        };
+        let xml_name = def.get_attr("name").unwrap_or_default();
+        let doc = documentation(particle).or_else(|| documentation(def));
+
+        let (ty, codec) = if let Some(ty) = def.get_attr("type") {
+            self.type_of(ty)?
+        } else if let Some(inline) = def
+            .child_elements()
+            .find(|c| c.tag == "complexType" || c.tag == "simpleType")
+        {
+            (self.anonymous(owner, xml_name, inline), None)
+        } else {
+            ("String".to_string(), None)
+        };
+
+        let mut attrs = Vec::new();
+        let name;
+        let ty = if occurs.repeated {
+            name = plural(&field_name(xml_name));
+            attrs.push("xml::elements".to_string());
+            attrs.push(format!("rename = {}", literal(xml_name)));
+            format!("Vec<{ty}>")
+        } else {
+            name = field_name(xml_name);
+            attrs.push("xml::element".to_string());
+            if default_xml_name(&name) != xml_name {
+                attrs.push(format!("rename = {}", literal(xml_name)));
+            }
+            // A struct can't hold itself inline
+            let ty = if ty == owner {
+                attrs.push("recursive_type".to_string());
+                format!("Box<{ty}>")
+            } else {
+                ty
+            };
+            if occurs.optional {
+                format!("Option<{ty}>")
+            } else {
+                ty
+            }
+        };
+        attrs.extend(codec.map(|c| format!("xml::codec = {}", literal(c))));
+        Ok(Field {
+            doc,
+            attrs,
+            name,
+            ty,
+        })
+    }
+
+    /// Collect the fields for an attribute or attribute group.
+    fn attribute(
+        &mut self,
+        owner: &str,
+        attribute: &'a Element,
+        fields: &mut Vec<Field>,
+    ) -> Result<(), CodegenError> {
+        if attribute.tag == "attributeGroup" {
+            let group = match attribute.get_attr("ref") {
+                Some(name) => self.schema.attribute_group(name)?,
+                None => attribute,
+            };
+            for child in group.child_elements() {
+                if matches!(child.tag.as_str(), "attribute" | "attributeGroup") {
+                    self.attribute(owner, child, fields)?;
+                }
+            }
+            return Ok(());
+        }
+        if attribute.get_attr("use") == Some("prohibited") {
+            return Ok(());
+        }
+
+        let mut attrs = vec!["xml::attribute".to_string()];
+        let (def, xml_name) = match attribute.get_attr("ref") {
+            Some(reference) => {
+                let (prefix, local) = split_qname(reference);
+                if prefix == Some("xml") {
+                    attrs.push(format!("xml::ns = {}", literal(XML_NS)));
+                }
+                (self.schema.attribute(reference).unwrap_or(attribute), local)
+            }
+            None => (attribute, attribute.get_attr("name").unwrap_or_default()),
+        };
+
+        let (ty, codec) = if let Some(ty) = def.get_attr("type") {
+            self.type_of(ty)?
+        } else if let Some(inline) = children(def, "simpleType").next() {
+            (self.anonymous(owner, xml_name, inline), None)
+        } else {
+            ("String".to_string(), None)
+        };
+
+        let name = field_name(xml_name);
+        if default_xml_name(&name) != xml_name {
+            attrs.push(format!("rename = {}", literal(xml_name)));
+        }
+        attrs.extend(codec.map(|c| format!("xml::codec = {}", literal(c))));
+        let ty = if attribute.get_attr("use") == Some("required") {
+            ty
+        } else {
+            format!("Option<{ty}>")
+        };
+        fields.push(Field {
+            doc: documentation(attribute).or_else(|| documentation(def)),
+            attrs,
+            name,
+            ty,
+        });
+        Ok(())
+    }
+
+    /// Name an inline type and queue it to be emitted after the current
+    /// definition.
+    fn anonymous(&mut self, owner: &str, xml_name: &str, def: &'a Element) -> String {
+        let mut name = type_name(xml_name);
+        if self.taken.contains(&name) {
+            name = format!("{owner}{name}");
+        }
+        let name = self.claim(name);
+        self.pending.push_back(Pending {
+            name: name.clone(),
+            def,
+        });
+        name
+    }
+
+    fn emit_simple(
+        &mut self,
+        name: &str,
+        def: &'a Element,
+        doc: Option<String>,
+    ) -> Result<(), CodegenError> {
+        self.out.push('\n');
+        write_doc(&mut self.out, "", doc.as_deref());
+
+        let restriction = children(def, "restriction").next();
+        let values: Vec<&str> = restriction
+            .into_iter()
+            .flat_map(|r| children(r, "enumeration"))
+            .filter_map(|e| e.get_attr("value"))
+            .collect();
+        if values.is_empty() {
+            // Lists and unions have no single Rust type to map to
+            let ty = match restriction.and_then(|r| r.get_attr("base")) {
+                Some(base) => self.type_of(base)?.0,
+                None => "String".to_string(),
+            };
+            let _ = writeln!(self.out, "pub type {name} = {ty};");
+            return Ok(());
+        }
+
+        self.write_derives();
+        let repr = if values.len() <= 256 { "u8" } else { "u16" };
+        let _ = writeln!(self.out, "#[repr({repr})]");
+        let _ = writeln!(self.out, "pub enum {name} {{");
+        let mut seen = HashSet::new();
+        for value in values {
+            let mut variant = type_name(value);
+            let base = variant.clone();
+            let mut n = 2;
+            while !seen.insert(variant.clone()) {
+                variant = format!("{base}{n}");
+                n += 1;
+            }
+            let _ = writeln!(self.out, "    #[facet(rename = {})]", literal(value));
+            let _ = writeln!(self.out, "    {variant},");
+        }
+        self.out.push_str("}\n");
+        Ok(())
+    }
+
+    /// The Rust type for a `type` or `base` reference, and the
+    /// `xml::codec` its values need, if any.
+    fn type_of(&self, qname: &'a str) -> Result<(String, Option<&'static str>), CodegenError> {
+        Ok(match self.schema.resolve_type(qname)? {
+            TypeRef::Complex(name) | TypeRef::Simple(name) => (self.type_names[name].clone(), None),
+            TypeRef::Builtin(name) => builtin(name),
+        })
+    }
+
+    /// Reserve a Rust type name, numbering it if it is already taken.
+    fn claim(&mut self, name: String) -> String {
+        let mut candidate = name.clone();
+        let mut n = 2;
+        while !self.taken.insert(candidate.clone()) {
+            candidate = format!("{name}{n}");
+            n += 1;
+        }
+        candidate
+    }
+
+    fn write_derives(&mut self) {
+        let _ = writeln!(self.out, "#[derive({})]", self.derives.join(", "));
+    }
+}
+
+/// The Rust type for a built-in XSD type. Types without a closer match,
+/// such as dates and `anyType`, are kept as strings.
+fn builtin(name: &str) -> (String, Option<&'static str>) {
+    let (ty, codec) = match name {
+        "boolean" => ("bool", None),
+        "float" => ("f32", None),
+        "double" | "decimal" => ("f64", None),
+        "integer" | "long" | "negativeInteger" | "nonPositiveInteger" => ("i64", None),
+        "int" => ("i32", None),
+        "short" => ("i16", None),
+        "byte" => ("i8", None),
+        "nonNegativeInteger" | "positiveInteger" | "unsignedLong" => ("u64", None),
+        "unsignedInt" => ("u32", None),
+        "unsignedShort" => ("u16", None),
+        "unsignedByte" => ("u8", None),
+        "base64Binary" => ("Vec<u8>", Some("base64")),
+        "hexBinary" => ("Vec<u8>", Some("hex")),
+        _ => ("String", None),
+    };
+    (ty.to_string(), codec)
+}
+
+/// Write `doc` as `///` lines at `indent`.
+fn write_doc(out: &mut String, indent: &str, doc: Option<&str>) {
+    let Some(doc) = doc else {
+        return;
+    };
+    for line in doc.lines().map(str::trim) {
+        if line.is_empty() {
+            let _ = writeln!(out, "{indent}///");
+        } else {
+            let _ = writeln!(out, "{indent}/// {line}");
+        }
+    }
+}
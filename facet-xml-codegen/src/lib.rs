@@ -0,0 +1,152 @@
+#![doc = include_str!("../README.md.in")]
+#![deny(unsafe_code)]
+
+mod generate;
+mod naming;
+mod schema;
+
+use std::fmt;
+use std::path::Path;
+
+/// Error generating types from a schema.
+#[derive(Debug)]
+pub enum CodegenError {
+    /// The schema isn't well-formed XML.
+    Parse(facet_xml::DeserializeError<facet_xml::XmlError>),
+    /// Reading the schema or writing the generated code failed.
+    Io(std::io::Error),
+    /// The document's root isn't `xs:schema`.
+    NotASchema {
+        /// Tag of the root element.
+        root: String,
+    },
+    /// A `type`, `base`, `ref` or group reference names nothing the schema
+    /// defines.
+    UnknownReference {
+        /// Kind of the reference, e.g. `type` or `group`.
+        kind: &'static str,
+        /// The referenced name, as written.
+        name: String,
+    },
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodegenError::Parse(e) => write!(f, "failed to parse schema: {e}"),
+            CodegenError::Io(e) => write!(f, "I/O error: {e}"),
+            CodegenError::NotASchema { root } => {
+                write!(f, "expected an xs:schema document, found <{root}>")
+            }
+            CodegenError::UnknownReference { kind, name } => {
+                write!(f, "unknown {kind} `{name}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CodegenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CodegenError::Parse(e) => Some(e),
+            CodegenError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CodegenError {
+    fn from(e: std::io::Error) -> Self {
+        CodegenError::Io(e)
+    }
+}
+
+/// Generates Rust types from an XSD schema.
+///
+/// # Example
+///
+/// ```
+/// let xsd = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+///   <xs:element name="note">
+///     <xs:complexType>
+///       <xs:sequence>
+///         <xs:element name="body" type="xs:string"/>
+///       </xs:sequence>
+///       <xs:attribute name="id" type="xs:int" use="required"/>
+///     </xs:complexType>
+///   </xs:element>
+/// </xs:schema>"#;
+///
+/// let code = facet_xml_codegen::Codegen::new().derive("Eq").generate(xsd).unwrap();
+/// assert!(code.contains("#[derive(Facet, Debug, Clone, PartialEq, Eq)]"));
+/// assert!(code.contains("pub struct Note {"));
+/// assert!(code.contains("    #[facet(xml::attribute)]\n    pub id: i32,"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Codegen {
+    derives: Vec<String>,
+}
+
+impl Default for Codegen {
+    fn default() -> Self {
+        Self {
+            derives: ["Facet", "Debug", "Clone", "PartialEq"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+impl Codegen {
+    /// Create a generator deriving `Facet`, `Debug`, `Clone` and `PartialEq`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also derive `name` on every generated type.
+    pub fn derive(mut self, name: impl Into<String>) -> Self {
+        self.derives.push(name.into());
+        self
+    }
+
+    /// Generate the source of a module defining a type for each element and
+    /// type of `xsd`.
+    pub fn generate(&self, xsd: &str) -> Result<String, CodegenError> {
+        let root: facet_xml_node::Element =
+            facet_xml::from_str(xsd).map_err(CodegenError::Parse)?;
+        let schema = schema::Schema::new(&root)?;
+        generate::Generator::new(&schema, &self.derives).run()
+    }
+
+    /// Generate types from the schema at `xsd` into `out`, for use in a
+    /// build script.
+    ///
+    /// Also tells Cargo to rerun the build script when the schema changes.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// // build.rs
+    /// let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("orders.rs");
+    /// facet_xml_codegen::Codegen::new()
+    ///     .generate_file("schemas/orders.xsd", &out)
+    ///     .unwrap();
+    /// // lib.rs: include!(concat!(env!("OUT_DIR"), "/orders.rs"));
+    /// ```
+    pub fn generate_file(
+        &self,
+        xsd: impl AsRef<Path>,
+        out: impl AsRef<Path>,
+    ) -> Result<(), CodegenError> {
+        let xsd = xsd.as_ref();
+        println!("cargo:rerun-if-changed={}", xsd.display());
+        let code = self.generate(&std::fs::read_to_string(xsd)?)?;
+        std::fs::write(out, code)?;
+        Ok(())
+    }
+}
+
+/// Generate Rust types from an XSD schema with the default [`Codegen`].
+pub fn generate(xsd: &str) -> Result<String, CodegenError> {
+    Codegen::new().generate(xsd)
+}
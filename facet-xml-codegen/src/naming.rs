@@ -0,0 +1,102 @@
+//! Turning XML names into Rust identifiers.
+
+/// Words that can't be used as plain identifiers.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod",
+    "move", "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true",
+    "try", "type", "unsafe", "use", "where", "while", "yield",
+];
+
+/// Split an XML name into words at separators and case changes:
+/// `purchaseOrder-ID` becomes `purchase`, `order`, `id`.
+fn words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        let prev = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1);
+        let boundary = c.is_uppercase()
+            && prev.is_some_and(|p| {
+                p.is_lowercase()
+                    || p.is_ascii_digit()
+                    || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+            });
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// `UpperCamelCase` type or variant name for an XML name.
+pub(crate) fn type_name(name: &str) -> String {
+    let name: String = words(name).iter().map(|w| capitalize(w)).collect();
+    match name.chars().next() {
+        None => "Empty".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("V{name}"),
+        Some(_) => name,
+    }
+}
+
+/// `snake_case` field name for an XML name, suffixed with `_` if it would
+/// be a keyword.
+pub(crate) fn field_name(name: &str) -> String {
+    let mut name = words(name).join("_");
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert_str(0, "field_");
+    }
+    if KEYWORDS.contains(&name.as_str()) {
+        name.push('_');
+    }
+    name
+}
+
+/// The element or attribute name facet-xml derives from a field name when
+/// there is no `rename`: lowerCamelCase.
+pub(crate) fn default_xml_name(field: &str) -> String {
+    let mut parts = field.split('_').filter(|part| !part.is_empty());
+    let mut name = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        name.push_str(&capitalize(part));
+    }
+    name
+}
+
+/// Plural of a field name, for fields holding repeated elements.
+pub(crate) fn plural(field: &str) -> String {
+    let base = field.strip_suffix('_').unwrap_or(field);
+    if let Some(stem) = base.strip_suffix('y')
+        && !stem.ends_with(['a', 'e', 'i', 'o', 'u'])
+    {
+        return format!("{stem}ies");
+    }
+    if base.ends_with(['s', 'x', 'z']) || base.ends_with("ch") || base.ends_with("sh") {
+        return format!("{base}es");
+    }
+    format!("{base}s")
+}
+
+/// A Rust string literal for `value`.
+pub(crate) fn literal(value: &str) -> String {
+    format!("{value:?}")
+}
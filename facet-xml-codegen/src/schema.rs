@@ -0,0 +1,159 @@
+//! Index of the top-level definitions of an XSD schema.
+
+use std::collections::HashMap;
+
+use facet_xml_node::Element;
+
+use crate::CodegenError;
+
+/// What a `type` or `base` reference resolves to, by local name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TypeRef<'a> {
+    /// A type built into XSD, e.g. `xs:int`.
+    Builtin(&'a str),
+    /// A named `xs:complexType` of the schema.
+    Complex(&'a str),
+    /// A named `xs:simpleType` of the schema.
+    Simple(&'a str),
+}
+
+/// Top-level definitions of a schema, by name.
+pub(crate) struct Schema<'a> {
+    pub(crate) root: &'a Element,
+    pub(crate) target_namespace: Option<&'a str>,
+    /// Whether local elements are in the target namespace
+    /// (`elementFormDefault="qualified"`)
+    pub(crate) qualified: bool,
+    /// Prefix the schema uses for the XSD namespace, e.g. `xs`
+    xs_prefix: Option<&'a str>,
+    pub(crate) elements: HashMap<&'a str, &'a Element>,
+    pub(crate) complex_types: HashMap<&'a str, &'a Element>,
+    pub(crate) simple_types: HashMap<&'a str, &'a Element>,
+    groups: HashMap<&'a str, &'a Element>,
+    attribute_groups: HashMap<&'a str, &'a Element>,
+    attributes: HashMap<&'a str, &'a Element>,
+}
+
+impl<'a> Schema<'a> {
+    pub(crate) fn new(root: &'a Element) -> Result<Self, CodegenError> {
+        if root.tag != "schema" {
+            return Err(CodegenError::NotASchema {
+                root: root.qualified_name().into_owned(),
+            });
+        }
+        let mut schema = Self {
+            root,
+            target_namespace: root.get_attr("targetNamespace"),
+            qualified: root.get_attr("elementFormDefault") == Some("qualified"),
+            xs_prefix: root.prefix(),
+            elements: HashMap::new(),
+            complex_types: HashMap::new(),
+            simple_types: HashMap::new(),
+            groups: HashMap::new(),
+            attribute_groups: HashMap::new(),
+            attributes: HashMap::new(),
+        };
+        for child in root.child_elements() {
+            let Some(name) = child.get_attr("name") else {
+                continue;
+            };
+            let index = match child.tag.as_str() {
+                "element" => &mut schema.elements,
+                "complexType" => &mut schema.complex_types,
+                "simpleType" => &mut schema.simple_types,
+                "group" => &mut schema.groups,
+                "attributeGroup" => &mut schema.attribute_groups,
+                "attribute" => &mut schema.attributes,
+                _ => continue,
+            };
+            index.insert(name, child);
+        }
+        Ok(schema)
+    }
+
+    /// Resolve the value of a `type` or `base` attribute.
+    pub(crate) fn resolve_type(&self, qname: &'a str) -> Result<TypeRef<'a>, CodegenError> {
+        let (prefix, local) = split_qname(qname);
+        let is_xs_prefix =
+            |prefix: &str| Some(prefix) == self.xs_prefix || matches!(prefix, "xs" | "xsd");
+        if prefix.is_some_and(is_xs_prefix) {
+            return Ok(TypeRef::Builtin(local));
+        }
+        if self.complex_types.contains_key(local) {
+            return Ok(TypeRef::Complex(local));
+        }
+        if self.simple_types.contains_key(local) {
+            return Ok(TypeRef::Simple(local));
+        }
+        // The schema may make XSD its default namespace
+        if prefix.is_none() && self.xs_prefix.is_none() {
+            return Ok(TypeRef::Builtin(local));
+        }
+        Err(unknown("type", qname))
+    }
+
+    /// The top-level element a `ref` names.
+    pub(crate) fn element(&self, qname: &str) -> Result<&'a Element, CodegenError> {
+        lookup(&self.elements, "element", qname)
+    }
+
+    /// The named model group a `group ref` names.
+    pub(crate) fn group(&self, qname: &str) -> Result<&'a Element, CodegenError> {
+        lookup(&self.groups, "group", qname)
+    }
+
+    /// The attribute group an `attributeGroup ref` names.
+    pub(crate) fn attribute_group(&self, qname: &str) -> Result<&'a Element, CodegenError> {
+        lookup(&self.attribute_groups, "attribute group", qname)
+    }
+
+    /// The top-level attribute an `attribute ref` names, if the schema
+    /// defines it (it may be in another schema, e.g. `xml:lang`).
+    pub(crate) fn attribute(&self, qname: &str) -> Option<&'a Element> {
+        self.attributes.get(split_qname(qname).1).copied()
+    }
+}
+
+/// Split `prefix:local` into its parts.
+pub(crate) fn split_qname(qname: &str) -> (Option<&str>, &str) {
+    match qname.split_once(':') {
+        Some((prefix, local)) => (Some(prefix), local),
+        None => (None, qname),
+    }
+}
+
+/// Child elements of `parent` with local name `tag`.
+pub(crate) fn children<'e>(parent: &'e Element, tag: &'e str) -> impl Iterator<Item = &'e Element> {
+    parent
+        .child_elements()
+        .filter(move |child| child.tag == tag)
+}
+
+/// The `xs:annotation/xs:documentation` text of a definition.
+pub(crate) fn documentation(def: &Element) -> Option<String> {
+    let text: Vec<String> = children(def, "annotation")
+        .flat_map(|annotation| children(annotation, "documentation"))
+        .map(|doc| doc.text_content())
+        .collect();
+    let text = text.join("\n");
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn lookup<'a>(
+    index: &HashMap<&'a str, &'a Element>,
+    kind: &'static str,
+    qname: &str,
+) -> Result<&'a Element, CodegenError> {
+    index
+        .get(split_qname(qname).1)
+        .copied()
+        .ok_or_else(|| unknown(kind, qname))
+}
+
+fn unknown(kind: &'static str, name: &str) -> CodegenError {
+    CodegenError::UnknownReference {
+        kind,
+        name: name.to_string(),
+    }
+}
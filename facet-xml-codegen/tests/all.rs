@@ -0,0 +1,141 @@
+use facet_testhelpers::test;
+use facet_xml_codegen::{CodegenError, generate};
+use indoc::indoc;
+
+const ORDERS: &str = indoc! {r#"
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"
+               xmlns:o="urn:orders"
+               targetNamespace="urn:orders"
+               elementFormDefault="qualified">
+      <xs:simpleType name="status">
+        <xs:restriction base="xs:string">
+          <xs:enumeration value="pending"/>
+          <xs:enumeration value="in-transit"/>
+        </xs:restriction>
+      </xs:simpleType>
+      <xs:complexType name="address">
+        <xs:annotation>
+          <xs:documentation>A postal address.</xs:documentation>
+        </xs:annotation>
+        <xs:sequence>
+          <xs:element name="street" type="xs:string"/>
+          <xs:element name="postCode" type="xs:string" minOccurs="0"/>
+        </xs:sequence>
+      </xs:complexType>
+      <xs:complexType name="shippingAddress">
+        <xs:complexContent>
+          <xs:extension base="o:address">
+            <xs:attribute name="carrier" type="xs:string"/>
+          </xs:extension>
+        </xs:complexContent>
+      </xs:complexType>
+      <xs:element name="order">
+        <xs:complexType>
+          <xs:sequence>
+            <xs:element name="shipTo" type="o:shippingAddress"/>
+            <xs:element name="item" maxOccurs="unbounded">
+              <xs:complexType>
+                <xs:simpleContent>
+                  <xs:extension base="xs:decimal">
+                    <xs:attribute name="sku" type="xs:string" use="required"/>
+                  </xs:extension>
+                </xs:simpleContent>
+              </xs:complexType>
+            </xs:element>
+          </xs:sequence>
+          <xs:attribute name="status" type="o:status" use="required"/>
+          <xs:attribute name="order-date" type="xs:date"/>
+        </xs:complexType>
+      </xs:element>
+    </xs:schema>
+"#};
+
+#[test]
+fn test_enumeration_becomes_enum() {
+    let code = generate(ORDERS).unwrap();
+    assert!(code.contains(indoc! {r#"
+        #[derive(Facet, Debug, Clone, PartialEq)]
+        #[repr(u8)]
+        pub enum Status {
+            #[facet(rename = "pending")]
+            Pending,
+            #[facet(rename = "in-transit")]
+            InTransit,
+        }
+    "#}));
+}
+
+#[test]
+fn test_complex_type_becomes_struct() {
+    let code = generate(ORDERS).unwrap();
+    assert!(code.contains(indoc! {r#"
+        /// A postal address.
+        #[derive(Facet, Debug, Clone, PartialEq)]
+        #[facet(xml::ns_all = "urn:orders")]
+        pub struct Address {
+            #[facet(xml::element)]
+            pub street: String,
+            #[facet(xml::element)]
+            pub post_code: Option<String>,
+        }
+    "#}));
+}
+
+#[test]
+fn test_extension_flattens_base() {
+    let code = generate(ORDERS).unwrap();
+    assert!(code.contains(indoc! {r#"
+        pub struct ShippingAddress {
+            #[facet(flatten)]
+            pub base: Address,
+            #[facet(xml::attribute)]
+            pub carrier: Option<String>,
+        }
+    "#}));
+}
+
+#[test]
+fn test_root_element_with_inline_types() {
+    let code = generate(ORDERS).unwrap();
+    assert!(code.contains(indoc! {r#"
+        #[facet(xml::ns_all = "urn:orders", rename = "order")]
+        pub struct Order {
+            #[facet(xml::element)]
+            pub ship_to: ShippingAddress,
+            #[facet(xml::elements, rename = "item")]
+            pub items: Vec<Item>,
+            #[facet(xml::attribute)]
+            pub status: Status,
+            #[facet(xml::attribute, rename = "order-date")]
+            pub order_date: Option<String>,
+        }
+    "#}));
+    assert!(code.contains(indoc! {r#"
+        pub struct Item {
+            #[facet(xml::text)]
+            pub value: f64,
+            #[facet(xml::attribute)]
+            pub sku: String,
+        }
+    "#}));
+}
+
+#[test]
+fn test_unknown_type_is_an_error() {
+    let xsd = indoc! {r#"
+        <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+          <xs:element name="note" type="missing"/>
+        </xs:schema>
+    "#};
+    let err = generate(xsd).unwrap_err();
+    assert!(matches!(
+        err,
+        CodegenError::UnknownReference { kind: "type", ref name } if name == "missing"
+    ));
+}
+
+#[test]
+fn test_not_a_schema() {
+    let err = generate("<note/>").unwrap_err();
+    assert!(matches!(err, CodegenError::NotASchema { ref root } if root == "note"));
+}
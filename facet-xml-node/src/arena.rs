@@ -0,0 +1,205 @@
+//! Element trees allocated in a bump arena.
+
+use std::borrow::Cow;
+
+use bumpalo::Bump;
+use bumpalo::collections::Vec as BumpVec;
+use facet_dom::{DomEvent, DomParser};
+use facet_xml::{DeserializeError, XmlError, XmlParser};
+
+use crate::{Content, Element};
+
+/// A read-only element whose strings and child lists live in a [`Bump`]
+/// arena, as built by [`Element::parse_in`].
+///
+/// Text and attribute values that appear verbatim in the input borrow from
+/// it; everything else (tag names, unescaped values, the node lists) is
+/// copied into the arena. Dropping the tree frees nothing: the arena is
+/// reset or dropped in one go instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElementRef<'a> {
+    /// The element's tag name.
+    pub tag: &'a str,
+    /// The namespace URI the element is in, if any.
+    pub namespace: Option<&'a str>,
+    /// The prefix the element's tag was written with (`None` if unprefixed).
+    pub prefix: Option<&'a str>,
+    /// Attributes as name-value pairs, in document order.
+    pub attrs: &'a [(&'a str, &'a str)],
+    /// Child content, in document order.
+    pub children: &'a [ContentRef<'a>],
+}
+
+/// Content inside an [`ElementRef`]: text or a child element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentRef<'a> {
+    /// Text content.
+    Text(&'a str),
+    /// A child element.
+    Element(ElementRef<'a>),
+}
+
+impl<'a> ContentRef<'a> {
+    /// Returns `Some(&str)` if this is text content.
+    pub fn as_text(&self) -> Option<&'a str> {
+        match self {
+            ContentRef::Text(t) => Some(t),
+            ContentRef::Element(_) => None,
+        }
+    }
+
+    /// Returns `Some(&ElementRef)` if this is an element.
+    pub fn as_element(&self) -> Option<&ElementRef<'a>> {
+        match self {
+            ContentRef::Element(e) => Some(e),
+            ContentRef::Text(_) => None,
+        }
+    }
+}
+
+impl<'a> ElementRef<'a> {
+    /// Get an attribute value by name.
+    pub fn get_attr(&self, name: &str) -> Option<&'a str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| *value)
+    }
+
+    /// Iterate over child elements (skipping text nodes).
+    pub fn child_elements(&self) -> impl Iterator<Item = &ElementRef<'a>> {
+        self.children.iter().filter_map(ContentRef::as_element)
+    }
+
+    /// Get the combined text content (concatenated from all text children).
+    pub fn text_content(&self) -> String {
+        let mut result = String::new();
+        for child in self.children {
+            match child {
+                ContentRef::Text(text) => result.push_str(text),
+                ContentRef::Element(e) => result.push_str(&e.text_content()),
+            }
+        }
+        result
+    }
+
+    /// Copy the tree out of the arena into an owned [`Element`].
+    pub fn to_element(&self) -> Element {
+        Element {
+            tag: self.tag.to_string(),
+            namespace: self.namespace.map(str::to_string),
+            prefix: self.prefix.map(str::to_string),
+            attrs: self
+                .attrs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            children: self
+                .children
+                .iter()
+                .map(|child| match child {
+                    ContentRef::Text(text) => Content::Text(text.to_string()),
+                    ContentRef::Element(e) => Content::Element(e.to_element()),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An element being built: its node lists grow in the arena until it ends.
+struct Building<'a> {
+    tag: &'a str,
+    namespace: Option<&'a str>,
+    prefix: Option<&'a str>,
+    attrs: BumpVec<'a, (&'a str, &'a str)>,
+    children: BumpVec<'a, ContentRef<'a>>,
+}
+
+impl<'a> Building<'a> {
+    fn finish(self) -> ElementRef<'a> {
+        ElementRef {
+            tag: self.tag,
+            namespace: self.namespace,
+            prefix: self.prefix,
+            attrs: self.attrs.into_bump_slice(),
+            children: self.children.into_bump_slice(),
+        }
+    }
+}
+
+/// `value` as a string living for `'a`: borrowed from the input when the
+/// parser could, copied into `bump` otherwise.
+fn in_arena<'a>(bump: &'a Bump, value: Cow<'a, str>) -> &'a str {
+    match value {
+        Cow::Borrowed(value) => value,
+        Cow::Owned(value) => bump.alloc_str(&value),
+    }
+}
+
+impl Element {
+    /// Parse `input` into a tree allocated in `bump`.
+    ///
+    /// Meant for large documents that are queried and thrown away: building
+    /// the tree costs a few pointer bumps per node rather than a heap
+    /// allocation per string and list. Comments and processing instructions
+    /// are skipped, as with `facet_xml::from_str::<Element>`.
+    ///
+    /// ```
+    /// use bumpalo::Bump;
+    /// use facet_xml_node::Element;
+    ///
+    /// let bump = Bump::new();
+    /// let doc = Element::parse_in(r#"<feed><entry id="1">a</entry><entry id="2"/></feed>"#, &bump)
+    ///     .unwrap();
+    /// let ids: Vec<_> = doc.child_elements().filter_map(|e| e.get_attr("id")).collect();
+    /// assert_eq!(ids, ["1", "2"]);
+    /// ```
+    pub fn parse_in<'a>(
+        input: &'a str,
+        bump: &'a Bump,
+    ) -> Result<ElementRef<'a>, DeserializeError<XmlError>> {
+        let mut parser = XmlParser::new(input.as_bytes());
+        // Elements being built; the root is at the bottom
+        let mut stack: Vec<Building<'a>> = Vec::new();
+        loop {
+            let event = parser.next_event().map_err(DeserializeError::Parser)?;
+            match event {
+                None => break,
+                Some(DomEvent::NodeStart { tag, namespace }) => {
+                    stack.push(Building {
+                        tag: in_arena(bump, tag),
+                        namespace: namespace.map(|ns| in_arena(bump, ns)),
+                        prefix: parser
+                            .element_prefix()
+                            .map(|prefix| &*bump.alloc_str(prefix)),
+                        attrs: BumpVec::new_in(bump),
+                        children: BumpVec::new_in(bump),
+                    });
+                }
+                Some(DomEvent::Attribute { name, value, .. }) => {
+                    if let Some(element) = stack.last_mut() {
+                        element
+                            .attrs
+                            .push((in_arena(bump, name), in_arena(bump, value)));
+                    }
+                }
+                Some(DomEvent::Text(text)) => {
+                    if let Some(element) = stack.last_mut() {
+                        element
+                            .children
+                            .push(ContentRef::Text(in_arena(bump, text)));
+                    }
+                }
+                Some(DomEvent::NodeEnd) => {
+                    let element = stack.pop().expect("parser balances elements").finish();
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(ContentRef::Element(element)),
+                        None => return Ok(element),
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        Err(DeserializeError::Parser(XmlError::UnexpectedEof))
+    }
+}
@@ -0,0 +1,265 @@
+//! Parent-aware navigation and editing of Element trees.
+
+use crate::{Content, Element};
+
+/// A position in an [`Element`] tree that can move in every direction and
+/// edit the tree around it.
+///
+/// The cursor starts at the root element and otherwise points at a child
+/// node (element or text) of some element. Movements return `false` and
+/// leave the cursor where it was when there is nowhere to go.
+///
+/// ```
+/// use facet_xml_node::Element;
+///
+/// let mut list = Element::new("ul")
+///     .with_child(Element::new("li").with_text("one"))
+///     .with_child(Element::new("li").with_text("two"));
+///
+/// let mut cursor = list.cursor();
+/// assert!(cursor.down());
+/// assert!(cursor.next_sibling());
+/// cursor.wrap(Element::new("strong"));
+/// assert_eq!(cursor.path(), &[1]);
+///
+/// assert_eq!(
+///     list.to_html(),
+///     "<ul><li>one</li><strong><li>two</li></strong></ul>"
+/// );
+/// ```
+pub struct ElementCursor<'a> {
+    root: &'a mut Element,
+    path: Vec<usize>,
+}
+
+impl Element {
+    /// A cursor positioned at this element.
+    pub fn cursor(&mut self) -> ElementCursor<'_> {
+        ElementCursor {
+            root: self,
+            path: Vec::new(),
+        }
+    }
+}
+
+impl ElementCursor<'_> {
+    /// Child indices leading from the root to the current node.
+    pub fn path(&self) -> &[usize] {
+        &self.path
+    }
+
+    /// Whether the cursor is at the root element.
+    pub fn is_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// The current node, or `None` at the root (which is not a [`Content`]).
+    pub fn content(&self) -> Option<&Content> {
+        let (&idx, parent_path) = self.path.split_last()?;
+        self.element_at(parent_path)?.children.get(idx)
+    }
+
+    /// The current element, or `None` if the cursor is on a text node.
+    pub fn element(&self) -> Option<&Element> {
+        match self.path.split_last() {
+            None => Some(&*self.root),
+            Some(_) => self.content()?.as_element(),
+        }
+    }
+
+    /// The current element, mutably.
+    pub fn element_mut(&mut self) -> Option<&mut Element> {
+        element_at_mut(self.root, &self.path)
+    }
+
+    /// The current text, or `None` if the cursor is on an element.
+    pub fn text(&self) -> Option<&str> {
+        self.content()?.as_text()
+    }
+
+    /// The element containing the current node, or `None` at the root.
+    pub fn parent(&self) -> Option<&Element> {
+        let (_, parent_path) = self.path.split_last()?;
+        self.element_at(parent_path)
+    }
+
+    /// Move to the first child of the current element.
+    pub fn down(&mut self) -> bool {
+        self.child(0)
+    }
+
+    /// Move to child `idx` of the current element.
+    pub fn child(&mut self, idx: usize) -> bool {
+        let has_child = self
+            .element()
+            .is_some_and(|element| idx < element.children.len());
+        if has_child {
+            self.path.push(idx);
+        }
+        has_child
+    }
+
+    /// Move to the parent element.
+    pub fn up(&mut self) -> bool {
+        self.path.pop().is_some()
+    }
+
+    /// Move to the next sibling.
+    pub fn next_sibling(&mut self) -> bool {
+        let Some(&idx) = self.path.last() else {
+            return false;
+        };
+        let siblings = self.parent().map_or(0, |parent| parent.children.len());
+        if idx + 1 < siblings {
+            *self.path.last_mut().unwrap() += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Move to the previous sibling.
+    pub fn prev_sibling(&mut self) -> bool {
+        match self.path.last_mut() {
+            Some(idx) if *idx > 0 => {
+                *idx -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Replace the current node, returning the old one. The root can't be
+    /// replaced as a [`Content`]; use [`element_mut`](Self::element_mut).
+    pub fn replace(&mut self, content: impl Into<Content>) -> Option<Content> {
+        let slot = self.content_mut()?;
+        Some(std::mem::replace(slot, content.into()))
+    }
+
+    /// Remove the current node and return it.
+    ///
+    /// The cursor moves to the node that followed it, or else to the one
+    /// before it, or else to the parent.
+    pub fn remove(&mut self) -> Option<Content> {
+        let (idx, siblings) = self.siblings_mut()?;
+        let removed = siblings.remove(idx);
+        let remaining = siblings.len();
+        if idx < remaining {
+            // The next node slid into this position
+        } else if idx > 0 {
+            *self.path.last_mut().unwrap() -= 1;
+        } else {
+            self.path.pop();
+        }
+        Some(removed)
+    }
+
+    /// Insert `content` before the current node. The cursor stays on the
+    /// current node.
+    pub fn insert_before(&mut self, content: impl Into<Content>) -> bool {
+        let Some((idx, siblings)) = self.siblings_mut() else {
+            return false;
+        };
+        siblings.insert(idx, content.into());
+        *self.path.last_mut().unwrap() += 1;
+        true
+    }
+
+    /// Insert `content` after the current node. The cursor stays on the
+    /// current node.
+    pub fn insert_after(&mut self, content: impl Into<Content>) -> bool {
+        let Some((idx, siblings)) = self.siblings_mut() else {
+            return false;
+        };
+        siblings.insert(idx + 1, content.into());
+        true
+    }
+
+    /// Append `content` to the children of the current element.
+    pub fn append_child(&mut self, content: impl Into<Content>) -> bool {
+        match self.element_mut() {
+            Some(element) => {
+                element.children.push(content.into());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set an attribute of the current element, returning its previous
+    /// value. Returns `None` on a text node, leaving it alone.
+    pub fn set_attr(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Option<Option<String>> {
+        let element = self.element_mut()?;
+        Some(element.attrs.insert(name.into(), value.into()))
+    }
+
+    /// Remove an attribute of the current element, returning its value.
+    pub fn remove_attr(&mut self, name: &str) -> Option<String> {
+        self.element_mut()?.attrs.shift_remove(name)
+    }
+
+    /// Make the current node the last child of `wrapper`, putting `wrapper`
+    /// in its place. The cursor moves to `wrapper`.
+    pub fn wrap(&mut self, mut wrapper: Element) -> bool {
+        let Some(slot) = self.content_mut() else {
+            return false;
+        };
+        let node = std::mem::replace(slot, Content::Text(String::new()));
+        wrapper.children.push(node);
+        *slot = Content::Element(wrapper);
+        true
+    }
+
+    /// Replace the current element by its children, moving the cursor to
+    /// the first of them (or to where the element was, if it had none).
+    pub fn unwrap(&mut self) -> bool {
+        let Some((idx, siblings)) = self.siblings_mut() else {
+            return false;
+        };
+        let Some(element) = siblings[idx].as_element_mut() else {
+            return false;
+        };
+        let children = std::mem::take(&mut element.children);
+        let had_children = !children.is_empty();
+        siblings.splice(idx..=idx, children);
+        if !had_children && idx >= siblings.len() {
+            if idx > 0 {
+                *self.path.last_mut().unwrap() -= 1;
+            } else {
+                self.path.pop();
+            }
+        }
+        true
+    }
+
+    fn element_at(&self, path: &[usize]) -> Option<&Element> {
+        let mut element = &*self.root;
+        for &idx in path {
+            element = element.children.get(idx)?.as_element()?;
+        }
+        Some(element)
+    }
+
+    fn content_mut(&mut self) -> Option<&mut Content> {
+        let (idx, siblings) = self.siblings_mut()?;
+        siblings.get_mut(idx)
+    }
+
+    /// The index of the current node and the list it lives in.
+    fn siblings_mut(&mut self) -> Option<(usize, &mut Vec<Content>)> {
+        let (&idx, parent_path) = self.path.split_last()?;
+        let parent = element_at_mut(self.root, parent_path)?;
+        Some((idx, &mut parent.children))
+    }
+}
+
+fn element_at_mut<'e>(mut element: &'e mut Element, path: &[usize]) -> Option<&'e mut Element> {
+    for &idx in path {
+        element = element.children.get_mut(idx)?.as_element_mut()?;
+    }
+    Some(element)
+}
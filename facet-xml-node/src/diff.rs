@@ -0,0 +1,212 @@
+//! Edit scripts between Element trees.
+
+use crate::{Content, Element, PathError};
+
+/// One change to an [`Element`] tree, as produced by [`diff`] and applied by
+/// [`apply_patch`].
+///
+/// Paths are child indices from the root, as in
+/// [`Element::get_content_mut`], and refer to the tree as it is when the
+/// operation is applied: each operation sees the effect of the ones before
+/// it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditOp {
+    /// Insert `content` so that it ends up at `path`, shifting later
+    /// siblings along.
+    Insert { path: Vec<usize>, content: Content },
+    /// Remove the node at `path`.
+    Delete { path: Vec<usize> },
+    /// Put `content` in place of the node at `path`. An empty path replaces
+    /// the root, which `content` must then be an element for.
+    Replace { path: Vec<usize>, content: Content },
+    /// Set attribute `name` of the element at `path` to `value`.
+    SetAttr {
+        path: Vec<usize>,
+        name: String,
+        value: String,
+    },
+    /// Remove attribute `name` of the element at `path`.
+    RemoveAttr { path: Vec<usize>, name: String },
+}
+
+/// The edits turning `a` into `b`.
+///
+/// Children are matched with a longest common subsequence, so unchanged
+/// nodes are never touched and moved nodes come out as a delete and an
+/// insert. An element replaced by one with the same name is edited in
+/// place instead of replaced whole, down to individual attributes and text
+/// nodes.
+///
+/// Matching is quadratic in the number of children of each element, which
+/// suits documents such as configuration files rather than huge flat
+/// lists.
+///
+/// ```
+/// use facet_xml_node::{EditOp, Element, apply_patch, diff};
+///
+/// let old: Element =
+///     facet_xml::from_str(r#"<config><port>80</port><host name="a"/></config>"#).unwrap();
+/// let new: Element =
+///     facet_xml::from_str(r#"<config><port>8080</port><host name="b"/><tls/></config>"#).unwrap();
+///
+/// let ops = diff(&old, &new);
+/// assert_eq!(
+///     ops[0],
+///     EditOp::Replace { path: vec![0, 0], content: "8080".into() }
+/// );
+/// assert_eq!(ops.len(), 3);
+///
+/// let mut patched = old.clone();
+/// apply_patch(&mut patched, &ops).unwrap();
+/// assert_eq!(patched, new);
+/// ```
+pub fn diff(a: &Element, b: &Element) -> Vec<EditOp> {
+    let mut ops = Vec::new();
+    if same_name(a, b) {
+        diff_element(&mut Vec::new(), a, b, &mut ops);
+    } else {
+        ops.push(EditOp::Replace {
+            path: Vec::new(),
+            content: Content::Element(b.clone()),
+        });
+    }
+    ops
+}
+
+/// Apply `ops` to `root` in order.
+///
+/// Stops at the first operation whose path doesn't lead anywhere, leaving
+/// the ones before it applied.
+pub fn apply_patch(root: &mut Element, ops: &[EditOp]) -> Result<(), PathError> {
+    for op in ops {
+        match op {
+            EditOp::Insert { path, content } => {
+                let Some((&idx, parent)) = path.split_last() else {
+                    return Err(PathError::EmptyPath { path: vec![] });
+                };
+                root.insert_child_at(parent, idx, content.clone())?;
+            }
+            EditOp::Delete { path } => {
+                root.remove_at(path)?;
+            }
+            EditOp::Replace { path, content } if path.is_empty() => match content.as_element() {
+                Some(element) => *root = element.clone(),
+                None => return Err(PathError::EmptyPath { path: vec![] }),
+            },
+            EditOp::Replace { path, content } => {
+                root.replace_at(path, content.clone())?;
+            }
+            EditOp::SetAttr { path, name, value } => {
+                root.set_attr_at(path, name.clone(), value.clone())?;
+            }
+            EditOp::RemoveAttr { path, name } => {
+                root.attrs_mut(path)?.shift_remove(name);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn same_name(a: &Element, b: &Element) -> bool {
+    a.tag == b.tag && a.namespace == b.namespace && a.prefix == b.prefix
+}
+
+/// Edits turning `a`, found at `path`, into `b`, which has the same name.
+fn diff_element(path: &mut Vec<usize>, a: &Element, b: &Element, ops: &mut Vec<EditOp>) {
+    for name in a.attrs.keys() {
+        if !b.attrs.contains_key(name) {
+            ops.push(EditOp::RemoveAttr {
+                path: path.clone(),
+                name: name.clone(),
+            });
+        }
+    }
+    for (name, value) in &b.attrs {
+        if a.attrs.get(name) != Some(value) {
+            ops.push(EditOp::SetAttr {
+                path: path.clone(),
+                name: name.clone(),
+                value: value.clone(),
+            });
+        }
+    }
+    diff_children(path, &a.children, &b.children, ops);
+}
+
+/// Edits turning the children `a` of the element at `path` into `b`.
+fn diff_children(path: &mut Vec<usize>, a: &[Content], b: &[Content], ops: &mut Vec<EditOp>) {
+    // lcs[i][j]: length of the longest common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // Nodes of `a` to drop and of `b` to add since the last common node;
+    // they're paired up into in-place edits where possible
+    let mut removed: Vec<&Content> = Vec::new();
+    let mut added: Vec<&Content> = Vec::new();
+    // Index in the list being edited where the next node of `b` goes
+    let mut at = 0;
+    let (mut i, mut j) = (0, 0);
+    loop {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            flush(path, &mut at, &mut removed, &mut added, ops);
+            at += 1;
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(&b[j]);
+            j += 1;
+        } else if i < a.len() {
+            removed.push(&a[i]);
+            i += 1;
+        } else {
+            flush(path, &mut at, &mut removed, &mut added, ops);
+            return;
+        }
+    }
+}
+
+/// Emit the edits for a run of `removed` nodes giving way to `added` ones
+/// at index `at`.
+fn flush(
+    path: &mut Vec<usize>,
+    at: &mut usize,
+    removed: &mut Vec<&Content>,
+    added: &mut Vec<&Content>,
+    ops: &mut Vec<EditOp>,
+) {
+    let paired = removed.len().min(added.len());
+    for (old, new) in removed.drain(..paired).zip(added.drain(..paired)) {
+        path.push(*at);
+        match (old.as_element(), new.as_element()) {
+            (Some(old), Some(new)) if same_name(old, new) => diff_element(path, old, new, ops),
+            _ => ops.push(EditOp::Replace {
+                path: path.clone(),
+                content: new.clone(),
+            }),
+        }
+        path.pop();
+        *at += 1;
+    }
+    for _ in removed.drain(..) {
+        path.push(*at);
+        ops.push(EditOp::Delete { path: path.clone() });
+        path.pop();
+    }
+    for new in added.drain(..) {
+        path.push(*at);
+        ops.push(EditOp::Insert {
+            path: path.clone(),
+            content: new.clone(),
+        });
+        path.pop();
+        *at += 1;
+    }
+}
@@ -0,0 +1,480 @@
+//! Enveloped XML signatures (XMLDSig).
+//!
+//! [`sign_element`] adds a `<Signature>` over an element as its child, and
+//! [`verify_element`] checks one, the way SAML assertions and signed
+//! invoices carry them. Both use Exclusive XML Canonicalization (without
+//! comments) and SHA-256 digests. The signature algorithm comes from the
+//! key: [`HmacSha256`] is built in, and public-key algorithms (RSA, ECDSA)
+//! plug in by implementing [`SigningKey`] and [`VerifyingKey`] over a
+//! crypto library.
+//!
+//! Canonical forms are computed over XML text, so whitespace, comments and
+//! namespaced attributes (`xml:lang`) count as the specification says.
+//! [`verify_document`] checks a document as received, and is what to use
+//! for documents signed elsewhere; [`sign_element`] and [`verify_element`]
+//! work on the element as [`facet_xml`] serializes it.
+//!
+//! ```
+//! use facet_xml_node::Element;
+//! use facet_xml_node::dsig::{HmacSha256, SignOptions, sign_element, verify_element};
+//!
+//! let key = HmacSha256::new(b"secret");
+//! let mut invoice = Element::new("invoice")
+//!     .with_attr("ID", "inv-1")
+//!     .with_child(Element::new("total").with_text("42.00"));
+//! sign_element(&mut invoice, &key, &SignOptions::new().key_name("billing")).unwrap();
+//!
+//! let xml = facet_xml::to_string(&invoice).unwrap();
+//! let received: Element = facet_xml::from_str(&xml).unwrap();
+//! assert!(verify_element(&received, &key).is_ok());
+//! ```
+
+use std::fmt;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::c14n::Document;
+use crate::{Content, Element};
+
+/// The namespace of XMLDSig elements.
+pub const DSIG_NAMESPACE: &str = "http://www.w3.org/2000/09/xmldsig#";
+/// Exclusive XML Canonicalization 1.0, without comments.
+pub const EXC_C14N: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+/// The transform removing the signature from what it signs.
+pub const ENVELOPED_SIGNATURE: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+/// The SHA-256 digest method.
+pub const SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+/// HMAC with SHA-256, as implemented by [`HmacSha256`].
+pub const HMAC_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#hmac-sha256";
+/// RSA PKCS#1 v1.5 with SHA-256.
+pub const RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+/// ECDSA with SHA-256.
+pub const ECDSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#ecdsa-sha256";
+
+/// A key producing signature values.
+pub trait SigningKey {
+    /// The signature method URI, such as [`RSA_SHA256`].
+    fn algorithm(&self) -> &str;
+
+    /// Sign the canonical `SignedInfo` bytes.
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, DsigError>;
+}
+
+/// A key (or certificate) checking signature values.
+pub trait VerifyingKey {
+    /// The signature method URI this key checks; signatures using another
+    /// method are rejected.
+    fn algorithm(&self) -> &str;
+
+    /// Whether `signature` is a valid signature of the canonical
+    /// `SignedInfo` bytes `data`.
+    fn verify(&self, data: &[u8], signature: &[u8]) -> bool;
+}
+
+/// A shared secret signing and verifying with HMAC-SHA256.
+#[derive(Clone)]
+pub struct HmacSha256 {
+    key: Vec<u8>,
+}
+
+impl HmacSha256 {
+    /// A key from the shared secret.
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            key: secret.to_vec(),
+        }
+    }
+
+    fn mac(&self) -> Hmac<Sha256> {
+        Hmac::new_from_slice(&self.key).expect("HMAC takes keys of any length")
+    }
+}
+
+impl fmt::Debug for HmacSha256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HmacSha256 { .. }")
+    }
+}
+
+impl SigningKey for HmacSha256 {
+    fn algorithm(&self) -> &str {
+        HMAC_SHA256
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>, DsigError> {
+        let mut mac = self.mac();
+        mac.update(data);
+        Ok(mac.finalize().into_bytes().to_vec())
+    }
+}
+
+impl VerifyingKey for HmacSha256 {
+    fn algorithm(&self) -> &str {
+        HMAC_SHA256
+    }
+
+    fn verify(&self, data: &[u8], signature: &[u8]) -> bool {
+        let mut mac = self.mac();
+        mac.update(data);
+        // Compares in constant time
+        mac.verify_slice(signature).is_ok()
+    }
+}
+
+/// How [`sign_element`] writes the signature.
+#[derive(Debug, Clone, Default)]
+pub struct SignOptions {
+    prefix: Option<String>,
+    position: Option<usize>,
+    key_name: Option<String>,
+    certificate: Option<Vec<u8>>,
+}
+
+impl SignOptions {
+    /// Unprefixed signature elements, appended as the last child, without
+    /// `KeyInfo`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write the signature elements with `prefix`, e.g. `ds`.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Insert the signature as child `idx` rather than last, e.g. right
+    /// after a SAML `Issuer`.
+    pub fn position(mut self, idx: usize) -> Self {
+        self.position = Some(idx);
+        self
+    }
+
+    /// Name the key in `KeyInfo/KeyName`.
+    pub fn key_name(mut self, name: impl Into<String>) -> Self {
+        self.key_name = Some(name.into());
+        self
+    }
+
+    /// Embed a DER-encoded X.509 certificate in
+    /// `KeyInfo/X509Data/X509Certificate`.
+    pub fn certificate(mut self, der: impl Into<Vec<u8>>) -> Self {
+        self.certificate = Some(der.into());
+        self
+    }
+}
+
+/// Why signing or verifying failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DsigError {
+    /// The signing key failed.
+    Signing(String),
+    /// There is no `Signature` to check.
+    MissingSignature,
+    /// The signature is missing a part or has one it shouldn't.
+    Malformed(&'static str),
+    /// The signature uses an algorithm that isn't supported, or that the
+    /// verifying key isn't for.
+    UnsupportedAlgorithm(String),
+    /// The reference points somewhere other than the signed element.
+    ReferenceMismatch(String),
+    /// The element changed since it was signed.
+    DigestMismatch,
+    /// The signature value doesn't match `SignedInfo` under the key.
+    SignatureMismatch,
+    /// A digest or signature value isn't valid base64.
+    InvalidBase64(String),
+    /// The signature position is past the end of the element's children.
+    InvalidPosition(usize),
+    /// The XML can't be canonicalized: it isn't well-formed, or uses a DTD
+    /// feature canonicalization doesn't support.
+    Xml(String),
+}
+
+impl fmt::Display for DsigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DsigError::Signing(reason) => write!(f, "signing failed: {reason}"),
+            DsigError::MissingSignature => write!(f, "no Signature found"),
+            DsigError::Malformed(reason) => write!(f, "malformed signature: {reason}"),
+            DsigError::UnsupportedAlgorithm(uri) => write!(f, "unsupported algorithm {uri:?}"),
+            DsigError::ReferenceMismatch(uri) => {
+                write!(f, "reference {uri:?} does not point at the signed element")
+            }
+            DsigError::DigestMismatch => write!(f, "digest does not match the element"),
+            DsigError::SignatureMismatch => write!(f, "signature value does not verify"),
+            DsigError::InvalidBase64(reason) => write!(f, "invalid base64: {reason}"),
+            DsigError::InvalidPosition(idx) => {
+                write!(f, "signature position {idx} is past the last child")
+            }
+            DsigError::Xml(reason) => write!(f, "cannot canonicalize: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for DsigError {}
+
+/// Add an enveloped signature over `element` as one of its children.
+///
+/// The reference is `#` followed by the element's `ID`, `Id` or `id`
+/// attribute, or the empty URI (the whole document) if it has none. Sign
+/// the document element for the latter.
+pub fn sign_element(
+    element: &mut Element,
+    key: &dyn SigningKey,
+    options: &SignOptions,
+) -> Result<(), DsigError> {
+    let position = options.position.unwrap_or(element.children.len());
+    if position > element.children.len() {
+        return Err(DsigError::InvalidPosition(position));
+    }
+    let ds = |tag: &str| Element::new_ns(tag, DSIG_NAMESPACE, options.prefix.as_deref());
+    let algorithm = |tag: &str, uri: &str| ds(tag).with_attr("Algorithm", uri);
+
+    let digest_value = Sha256::digest(canonicalize_element(element)?);
+    let signed_info = ds("SignedInfo")
+        .with_child(algorithm("CanonicalizationMethod", EXC_C14N))
+        .with_child(algorithm("SignatureMethod", key.algorithm()))
+        .with_child(
+            ds("Reference")
+                .with_attr("URI", reference_uri(element))
+                .with_child(
+                    ds("Transforms")
+                        .with_child(algorithm("Transform", ENVELOPED_SIGNATURE))
+                        .with_child(algorithm("Transform", EXC_C14N)),
+                )
+                .with_child(algorithm("DigestMethod", SHA256))
+                .with_child(ds("DigestValue").with_text(BASE64.encode(digest_value))),
+        );
+    let signature_value = key.sign(canonicalize_element(&signed_info)?.as_bytes())?;
+
+    let mut signature = ds("Signature")
+        .with_child(signed_info)
+        .with_child(ds("SignatureValue").with_text(BASE64.encode(&signature_value)));
+    if options.key_name.is_some() || options.certificate.is_some() {
+        let mut key_info = ds("KeyInfo");
+        if let Some(name) = &options.key_name {
+            key_info = key_info.with_child(ds("KeyName").with_text(name.as_str()));
+        }
+        if let Some(der) = &options.certificate {
+            key_info = key_info.with_child(
+                ds("X509Data").with_child(ds("X509Certificate").with_text(BASE64.encode(der))),
+            );
+        }
+        signature = signature.with_child(key_info);
+    }
+    element
+        .children
+        .insert(position, Content::Element(signature));
+    Ok(())
+}
+
+/// Check the enveloped signature among the children of `element` with
+/// `key`.
+///
+/// The element is serialized and checked as by [`verify_document`], and
+/// the reference must be to the element itself.
+pub fn verify_element(element: &Element, key: &dyn VerifyingKey) -> Result<(), DsigError> {
+    let xml = facet_xml::to_string(element).map_err(|e| DsigError::Xml(e.to_string()))?;
+    let doc = Document::parse(&xml).map_err(DsigError::Xml)?;
+    let signature = doc
+        .children(doc.root())
+        .find(|&idx| is_ds(&doc, idx, "Signature"))
+        .ok_or(DsigError::MissingSignature)?;
+    verify_signature(&doc, signature, key, |target| target == doc.root())
+}
+
+/// Check the first `Signature` in `xml`, in document order, with `key`.
+///
+/// Only enveloped signatures are accepted: a single reference to the
+/// document (the empty URI) or to an element enclosing the signature by its
+/// `ID`, `Id` or `id` attribute, with the enveloped-signature and exclusive
+/// canonicalization transforms and a SHA-256 digest, signed with the key's
+/// algorithm. Anything else is an error rather than a signature that is
+/// silently not checked.
+///
+/// ```
+/// use facet_xml_node::dsig::{DsigError, HmacSha256, verify_document};
+///
+/// let xml = r#"<doc><ds:Signature xmlns:ds="http://www.w3.org/2000/09/xmldsig#"/></doc>"#;
+/// let key = HmacSha256::new(b"secret");
+/// assert_eq!(verify_document(xml, &key), Err(DsigError::Malformed("SignedInfo")));
+/// ```
+pub fn verify_document(xml: &str, key: &dyn VerifyingKey) -> Result<(), DsigError> {
+    let doc = Document::parse(xml).map_err(DsigError::Xml)?;
+    let signature = doc
+        .elements()
+        .find(|&idx| is_ds(&doc, idx, "Signature"))
+        .ok_or(DsigError::MissingSignature)?;
+    verify_signature(&doc, signature, key, |_| true)
+}
+
+/// Check `signature` in `doc`, whose reference must point at an element
+/// `accept` agrees to.
+fn verify_signature(
+    doc: &Document,
+    signature: usize,
+    key: &dyn VerifyingKey,
+    accept: impl Fn(usize) -> bool,
+) -> Result<(), DsigError> {
+    let signed_info = ds_child(doc, signature, "SignedInfo")?;
+    let method = ds_child(doc, signed_info, "CanonicalizationMethod")?;
+    expect_algorithm(doc, method, EXC_C14N)?;
+    let signed_info_prefixes = inclusive_prefixes(doc, method);
+    expect_algorithm(
+        doc,
+        ds_child(doc, signed_info, "SignatureMethod")?,
+        key.algorithm(),
+    )?;
+    let mut references = ds_children(doc, signed_info, "Reference");
+    let reference = references
+        .next()
+        .ok_or(DsigError::Malformed("no Reference"))?;
+    if references.next().is_some() {
+        return Err(DsigError::Malformed("more than one Reference"));
+    }
+
+    let uri = doc.attr(reference, "URI").unwrap_or_default();
+    let target = match uri.strip_prefix('#') {
+        None if uri.is_empty() => doc.root(),
+        Some(id) => find_by_id(doc, id)?.ok_or_else(|| DsigError::ReferenceMismatch(uri.into()))?,
+        None => return Err(DsigError::ReferenceMismatch(uri.into())),
+    };
+    if !doc.is_within(signature, target) || !accept(target) {
+        return Err(DsigError::ReferenceMismatch(uri.into()));
+    }
+    let mut enveloped = false;
+    let mut prefixes = Vec::new();
+    if let Ok(transforms) = ds_child(doc, reference, "Transforms") {
+        for transform in ds_children(doc, transforms, "Transform") {
+            match doc.attr(transform, "Algorithm").unwrap_or_default() {
+                ENVELOPED_SIGNATURE => enveloped = true,
+                EXC_C14N => prefixes = inclusive_prefixes(doc, transform),
+                other => return Err(DsigError::UnsupportedAlgorithm(other.to_string())),
+            }
+        }
+    }
+    if !enveloped {
+        return Err(DsigError::Malformed("not an enveloped signature"));
+    }
+    expect_algorithm(doc, ds_child(doc, reference, "DigestMethod")?, SHA256)?;
+
+    let expected = decode_base64(&doc.text(ds_child(doc, reference, "DigestValue")?))?;
+    let canonical = if uri.is_empty() {
+        doc.canonicalize(Some(signature), &prefixes)
+    } else {
+        doc.canonicalize_subtree(target, Some(signature), &prefixes)
+    };
+    if expected.as_slice() != Sha256::digest(canonical).as_slice() {
+        return Err(DsigError::DigestMismatch);
+    }
+
+    let signature_value = decode_base64(&doc.text(ds_child(doc, signature, "SignatureValue")?))?;
+    let canonical = doc.canonicalize_subtree(signed_info, None, &signed_info_prefixes);
+    if key.verify(canonical.as_bytes(), &signature_value) {
+        Ok(())
+    } else {
+        Err(DsigError::SignatureMismatch)
+    }
+}
+
+/// `xml` in Exclusive XML Canonicalization form, without comments.
+///
+/// Namespace declarations appear on the outermost elements that visibly
+/// use them, or that use a prefix in `inclusive_prefixes` (the
+/// InclusiveNamespaces PrefixList, with `#default` for the default
+/// namespace). Attributes are sorted by namespace and name, empty elements
+/// are written with an end tag, and text and attribute values are escaped
+/// as the specification requires.
+///
+/// ```
+/// use facet_xml_node::dsig::canonicalize;
+///
+/// let xml = r#"<a xmlns:x="urn:x" z="1" b="&lt;"><b/><!-- gone --></a>"#;
+/// assert_eq!(canonicalize(xml, &[]).unwrap(), r#"<a b="&lt;" z="1"><b></b></a>"#);
+/// assert_eq!(
+///     canonicalize(xml, &["x"]).unwrap(),
+///     r#"<a xmlns:x="urn:x" b="&lt;" z="1"><b></b></a>"#
+/// );
+/// ```
+pub fn canonicalize(xml: &str, inclusive_prefixes: &[&str]) -> Result<String, DsigError> {
+    let doc = Document::parse(xml).map_err(DsigError::Xml)?;
+    Ok(doc.canonicalize(None, inclusive_prefixes))
+}
+
+/// `element`, serialized, in canonical form.
+fn canonicalize_element(element: &Element) -> Result<String, DsigError> {
+    let xml = facet_xml::to_string(element).map_err(|e| DsigError::Xml(e.to_string()))?;
+    canonicalize(&xml, &[])
+}
+
+/// The reference URI naming `element`: its ID, or the whole document.
+fn reference_uri(element: &Element) -> String {
+    ["ID", "Id", "id"]
+        .iter()
+        .find_map(|name| element.get_attr(name))
+        .map_or_else(String::new, |id| format!("#{id}"))
+}
+
+/// The element whose `ID`, `Id` or `id` is `id`. Duplicates are an error,
+/// so the reference can't be pointed at an element other than the one
+/// checked.
+fn find_by_id(doc: &Document, id: &str) -> Result<Option<usize>, DsigError> {
+    let mut found = doc.elements().filter(|&idx| {
+        ["ID", "Id", "id"]
+            .iter()
+            .any(|name| doc.attr(idx, name) == Some(id))
+    });
+    let first = found.next();
+    if found.next().is_some() {
+        return Err(DsigError::Malformed("duplicate ID"));
+    }
+    Ok(first)
+}
+
+/// The PrefixList of the `InclusiveNamespaces` child of a transform or
+/// canonicalization method.
+fn inclusive_prefixes(doc: &Document, method: usize) -> Vec<&str> {
+    doc.children(method)
+        .find(|&idx| doc.namespace(idx) == EXC_C14N && doc.local_name(idx) == "InclusiveNamespaces")
+        .and_then(|idx| doc.attr(idx, "PrefixList"))
+        .map_or_else(Vec::new, |list| list.split_ascii_whitespace().collect())
+}
+
+/// Decode a `base64Binary` value, which may be wrapped over several
+/// lines.
+fn decode_base64(text: &str) -> Result<Vec<u8>, DsigError> {
+    let compact: String = text.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    BASE64
+        .decode(compact)
+        .map_err(|e| DsigError::InvalidBase64(e.to_string()))
+}
+
+fn is_ds(doc: &Document, idx: usize, tag: &str) -> bool {
+    doc.local_name(idx) == tag && doc.namespace(idx) == DSIG_NAMESPACE
+}
+
+fn ds_children<'d>(
+    doc: &'d Document,
+    idx: usize,
+    tag: &'d str,
+) -> impl Iterator<Item = usize> + 'd {
+    doc.children(idx)
+        .filter(move |&child| is_ds(doc, child, tag))
+}
+
+fn ds_child(doc: &Document, idx: usize, tag: &'static str) -> Result<usize, DsigError> {
+    ds_children(doc, idx, tag)
+        .next()
+        .ok_or(DsigError::Malformed(tag))
+}
+
+fn expect_algorithm(doc: &Document, idx: usize, expected: &str) -> Result<(), DsigError> {
+    match doc.attr(idx, "Algorithm").unwrap_or_default() {
+        algorithm if algorithm == expected => Ok(()),
+        other => Err(DsigError::UnsupportedAlgorithm(other.to_string())),
+    }
+}
@@ -0,0 +1,98 @@
+//! Loading selected subtrees of a document without materializing the rest.
+
+use facet_dom::{DomDeserializer, DomEvent, DomParser};
+use facet_xml::{DeserializeError, XmlError, XmlParser};
+
+use crate::Element;
+
+/// What [`load_filtered`] does with an element it encounters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterAction {
+    /// Materialize the element and its whole subtree as an [`Element`].
+    Load,
+    /// Don't keep the element, but look at its children.
+    Descend,
+    /// Skip the element and its subtree without looking inside.
+    Skip,
+}
+
+/// `true` loads the element, `false` descends into it.
+impl From<bool> for FilterAction {
+    fn from(load: bool) -> Self {
+        if load {
+            FilterAction::Load
+        } else {
+            FilterAction::Descend
+        }
+    }
+}
+
+/// Parse `input`, materializing only the subtrees selected by `filter`.
+///
+/// `filter` is called with the depth (0 for the root) and tag of each element
+/// that isn't inside an already loaded or skipped subtree, and can return a
+/// `bool` (load or descend) or a [`FilterAction`]. Loaded subtrees are
+/// returned in document order. Elements that are skipped or only descended
+/// into are never allocated, and skipped subtrees aren't even inspected,
+/// so a few sections can be pulled out of a very large document cheaply.
+///
+/// ```
+/// use facet_xml_node::{FilterAction, load_filtered};
+///
+/// let xml = r#"<library>
+///     <archive><book id="0"/></archive>
+///     <shelf><book id="1"/><book id="2"/></shelf>
+/// </library>"#;
+///
+/// let books = load_filtered(xml.as_bytes(), |_depth, tag: &str| match tag {
+///     "archive" => FilterAction::Skip,
+///     "book" => FilterAction::Load,
+///     _ => FilterAction::Descend,
+/// })
+/// .unwrap();
+/// let ids: Vec<_> = books.iter().filter_map(|b| b.get_attr("id")).collect();
+/// assert_eq!(ids, ["1", "2"]);
+/// ```
+pub fn load_filtered<F, A>(
+    input: &[u8],
+    mut filter: F,
+) -> Result<Vec<Element>, DeserializeError<XmlError>>
+where
+    F: FnMut(usize, &str) -> A,
+    A: Into<FilterAction>,
+{
+    let mut parser = XmlParser::new(input);
+    let mut loaded = Vec::new();
+    // Depth of the next element start
+    let mut depth = 0usize;
+
+    loop {
+        let action = match parser.peek_event().map_err(DeserializeError::Parser)? {
+            None => break,
+            Some(DomEvent::NodeStart { tag, .. }) => Some(filter(depth, tag).into()),
+            Some(DomEvent::NodeEnd) => {
+                depth = depth.saturating_sub(1);
+                None
+            }
+            Some(_) => None,
+        };
+
+        match action {
+            Some(FilterAction::Load) => {
+                loaded.push(DomDeserializer::new_owned(&mut parser).deserialize::<Element>()?);
+            }
+            Some(FilterAction::Skip) => parser.skip_node().map_err(DeserializeError::Parser)?,
+            Some(FilterAction::Descend) => {
+                parser.next_event().map_err(DeserializeError::Parser)?;
+                depth += 1;
+            }
+            // Attributes, text, comments and structural events of elements
+            // we're only passing through
+            None => {
+                parser.next_event().map_err(DeserializeError::Parser)?;
+            }
+        }
+    }
+
+    Ok(loaded)
+}
@@ -0,0 +1,114 @@
+//! Content digests of Element subtrees.
+
+use std::fmt;
+
+use sha2::{Digest, Sha256};
+
+use crate::{Content, Element};
+
+/// A SHA-256 digest of an element's canonical form. See
+/// [`Element::fingerprint`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    /// The raw digest.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    /// Lowercase hex, 64 characters.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fingerprint({self})")
+    }
+}
+
+impl Element {
+    /// A digest of this element's canonical form, stable across runs,
+    /// platforms and versions of this crate.
+    ///
+    /// Two elements get the same fingerprint exactly when they are equal
+    /// after [`normalize`](Self::normalize): attribute order, how text is
+    /// split into nodes and whether children are shared don't matter;
+    /// everything else, whitespace included, does. Fingerprint a subtree to
+    /// tell whether that section of a document changed between fetches.
+    ///
+    /// ```
+    /// use facet_xml_node::Element;
+    ///
+    /// let a = Element::new("item").with_attr("id", "1").with_attr("rev", "2");
+    /// let b = Element::new("item").with_attr("rev", "2").with_attr("id", "1");
+    /// assert_eq!(a.fingerprint(), b.fingerprint());
+    /// assert_ne!(a.fingerprint(), a.clone().with_text(" ").fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut hasher = Sha256::new();
+        write_canonical(self, &mut hasher);
+        Fingerprint(hasher.finalize().into())
+    }
+}
+
+/// Feed the canonical encoding of `element` to `hasher`.
+///
+/// Every string is length-prefixed and every node tagged, so distinct trees
+/// never encode to the same bytes.
+fn write_canonical(element: &Element, hasher: &mut Sha256) {
+    hasher.update(b"E");
+    write_str(hasher, &element.tag);
+    let mut attrs: Vec<_> = element.attrs.iter().collect();
+    attrs.sort_unstable();
+    hasher.update(&(attrs.len() as u64).to_le_bytes());
+    for (name, value) in attrs {
+        write_str(hasher, name);
+        write_str(hasher, value);
+    }
+    // Adjacent text nodes are merged and empty ones dropped, as by normalize()
+    let mut text = String::new();
+    for child in &element.children {
+        match child {
+            Content::Text(t) => text.push_str(t),
+            Content::Comment(comment) => {
+                flush_text(hasher, &mut text);
+                hasher.update(b"C");
+                write_str(hasher, comment);
+            }
+            Content::ProcessingInstruction { target, data } => {
+                flush_text(hasher, &mut text);
+                hasher.update(b"P");
+                write_str(hasher, target);
+                write_str(hasher, data);
+            }
+            Content::Element(_) | Content::Shared(_) => {
+                let e = child.as_element().expect("element content");
+                flush_text(hasher, &mut text);
+                write_canonical(e, hasher);
+            }
+        }
+    }
+    flush_text(hasher, &mut text);
+    hasher.update(b"Z");
+}
+
+fn flush_text(hasher: &mut Sha256, text: &mut String) {
+    if !text.is_empty() {
+        hasher.update(b"T");
+        write_str(hasher, text);
+        text.clear();
+    }
+}
+
+fn write_str(hasher: &mut Sha256, s: &str) {
+    hasher.update(&(s.len() as u64).to_le_bytes());
+    hasher.update(s.as_bytes());
+}
@@ -1,14 +1,40 @@
 //! Raw XML element types and deserialization from Element trees.
 
+#[cfg(feature = "bumpalo")]
+mod arena;
+#[cfg(feature = "dsig")]
+mod c14n;
+mod cursor;
+mod diff;
+#[cfg(feature = "dsig")]
+pub mod dsig;
+mod filter;
+mod fingerprint;
 mod parser;
+mod pool;
+mod select;
+mod template;
+mod value;
 
 use facet_xml as xml;
-use std::collections::HashMap;
-
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[cfg(feature = "bumpalo")]
+pub use arena::{ContentRef, ElementRef};
+pub use cursor::ElementCursor;
+pub use diff::{EditOp, apply_patch, diff};
+pub use filter::{FilterAction, load_filtered};
+pub use fingerprint::Fingerprint;
+pub use indexmap::IndexMap;
 pub use parser::{
     ElementParseError, ElementParser, ElementSerializeError, ElementSerializer, from_element,
-    to_element,
+    from_element_borrowed, to_element,
 };
+pub use pool::{ElementPool, PooledElement};
+pub use select::Selection;
+pub use template::Slots;
+pub use value::{NotAnElement, Scalar, XmlValue};
 
 /// Error when navigating to a path in an Element tree.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -23,6 +49,11 @@ pub enum PathError {
     },
     /// Tried to navigate through a text node.
     TextNodeHasNoChildren { path: Vec<usize> },
+    /// A slash-separated selector could not be parsed.
+    InvalidSelector {
+        selector: String,
+        reason: &'static str,
+    },
 }
 
 impl std::fmt::Display for PathError {
@@ -38,14 +69,106 @@ impl std::fmt::Display for PathError {
             PathError::TextNodeHasNoChildren { path } => {
                 write!(f, "text node has no children at path {path:?}")
             }
+            PathError::InvalidSelector { selector, reason } => {
+                write!(f, "invalid selector {selector:?}: {reason}")
+            }
         }
     }
 }
 
 impl std::error::Error for PathError {}
 
+/// Error when reading a required, typed attribute from an Element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttrError {
+    /// The attribute is not present on the element.
+    Missing { tag: String, name: String },
+    /// The attribute is present but its value could not be parsed.
+    Invalid {
+        tag: String,
+        name: String,
+        value: String,
+        message: String,
+    },
+}
+
+impl std::fmt::Display for AttrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttrError::Missing { tag, name } => {
+                write!(f, "missing attribute `{name}` on <{tag}>")
+            }
+            AttrError::Invalid {
+                tag,
+                name,
+                value,
+                message,
+            } => write!(
+                f,
+                "invalid value {value:?} for attribute `{name}` on <{tag}>: {message}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AttrError {}
+
+/// A name or character in an Element tree that can't be written as XML.
+///
+/// `path` locates the offending element as a sequence of child indices from
+/// the element being checked (empty for that element itself).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The tag isn't a valid XML name.
+    InvalidTag { path: Vec<usize>, tag: String },
+    /// An attribute name isn't a valid XML name.
+    InvalidAttrName { path: Vec<usize>, name: String },
+    /// An attribute value contains a character XML 1.0 doesn't allow.
+    InvalidAttrValue {
+        path: Vec<usize>,
+        name: String,
+        char: char,
+    },
+    /// A text node contains a character XML 1.0 doesn't allow.
+    InvalidText { path: Vec<usize>, char: char },
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::InvalidTag { path, tag } => {
+                write!(f, "invalid tag name {tag:?} at path {path:?}")
+            }
+            ValidationError::InvalidAttrName { path, name } => {
+                write!(f, "invalid attribute name {name:?} at path {path:?}")
+            }
+            ValidationError::InvalidAttrValue { path, name, char } => write!(
+                f,
+                "character U+{:04X} not allowed in attribute `{name}` at path {path:?}",
+                *char as u32
+            ),
+            ValidationError::InvalidText { path, char } => write!(
+                f,
+                "character U+{:04X} not allowed in text at path {path:?}",
+                *char as u32
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
 /// Content that can appear inside an XML element - either child elements or text.
-#[derive(Debug, Clone, PartialEq, Eq, facet::Facet)]
+///
+/// Comments and processing instructions are only kept when parsing with
+/// [`DeserializeOptions::keep_comments`](facet_xml::DeserializeOptions::keep_comments)
+/// and
+/// [`keep_processing_instructions`](facet_xml::DeserializeOptions::keep_processing_instructions).
+///
+/// Ordered text, comments, processing instructions, then elements, and by
+/// the contained value within each kind. A shared element equals (and
+/// hashes like) an owned element with the same content.
+#[derive(Debug, Clone, facet::Facet)]
 #[repr(u8)]
 pub enum Content {
     /// Text content.
@@ -54,6 +177,26 @@ pub enum Content {
     /// A child element (catch-all for any tag name).
     #[facet(xml::custom_element)]
     Element(Element),
+    /// A child element shared with other trees.
+    ///
+    /// Cloning the tree only bumps the reference count; the subtree is
+    /// copied the first time it's mutated through
+    /// [`as_element_mut`](Content::as_element_mut). Serializes exactly like
+    /// [`Element`](Content::Element); parsing never produces it, since
+    /// unmatched elements go to the first `custom_element` variant.
+    #[facet(xml::custom_element)]
+    Shared(Arc<Element>),
+    /// A comment, without its `<!--` and `-->` delimiters.
+    #[facet(xml::comment)]
+    Comment(String),
+    /// A processing instruction such as `<?xml-stylesheet href="style.xsl"?>`.
+    #[facet(xml::processing_instruction)]
+    ProcessingInstruction {
+        /// The name right after `<?`, e.g. `xml-stylesheet`.
+        target: String,
+        /// Everything after the target, with surrounding whitespace trimmed.
+        data: String,
+    },
 }
 
 impl Content {
@@ -65,28 +208,135 @@ impl Content {
         }
     }
 
-    /// Returns `Some(&Element)` if this is an element.
+    /// Returns `Some(&Element)` if this is an element, owned or shared.
     pub fn as_element(&self) -> Option<&Element> {
         match self {
             Content::Element(e) => Some(e),
+            Content::Shared(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+
+    /// Returns `Some(&mut Element)` if this is an element.
+    ///
+    /// A shared element is first copied, unless this is its only reference,
+    /// so other trees sharing it are unaffected.
+    pub fn as_element_mut(&mut self) -> Option<&mut Element> {
+        match self {
+            Content::Element(e) => Some(e),
+            Content::Shared(e) => Some(Arc::make_mut(e)),
             _ => None,
         }
     }
 }
 
+impl PartialEq for Content {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Content::Text(a), Content::Text(b)) => a == b,
+            (Content::Comment(a), Content::Comment(b)) => a == b,
+            (
+                Content::ProcessingInstruction { target, data },
+                Content::ProcessingInstruction {
+                    target: other_target,
+                    data: other_data,
+                },
+            ) => target == other_target && data == other_data,
+            _ => match (self.as_element(), other.as_element()) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            },
+        }
+    }
+}
+
+impl Eq for Content {}
+
+impl std::hash::Hash for Content {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        match self {
+            Content::Text(t) => {
+                0u8.hash(state);
+                t.hash(state);
+            }
+            Content::Comment(c) => {
+                2u8.hash(state);
+                c.hash(state);
+            }
+            Content::ProcessingInstruction { target, data } => {
+                3u8.hash(state);
+                target.hash(state);
+                data.hash(state);
+            }
+            Content::Element(_) | Content::Shared(_) => {
+                1u8.hash(state);
+                self.as_element().hash(state);
+            }
+        }
+    }
+}
+
+impl PartialOrd for Content {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Content {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        match (self, other) {
+            (Content::Text(a), Content::Text(b)) => a.cmp(b),
+            (Content::Text(_), _) => Ordering::Less,
+            (_, Content::Text(_)) => Ordering::Greater,
+            (Content::Comment(a), Content::Comment(b)) => a.cmp(b),
+            (Content::Comment(_), _) => Ordering::Less,
+            (_, Content::Comment(_)) => Ordering::Greater,
+            (
+                Content::ProcessingInstruction { target, data },
+                Content::ProcessingInstruction {
+                    target: other_target,
+                    data: other_data,
+                },
+            ) => (target, data).cmp(&(other_target, other_data)),
+            (Content::ProcessingInstruction { .. }, _) => Ordering::Less,
+            (_, Content::ProcessingInstruction { .. }) => Ordering::Greater,
+            _ => self.as_element().cmp(&other.as_element()),
+        }
+    }
+}
+
 /// An XML element that captures any tag name, attributes, and children.
 ///
 /// This type can represent arbitrary XML structure without needing
 /// a predefined schema.
+///
+/// Elements read from namespaced documents keep their namespace URI and the
+/// prefix they were written with; `xmlns` declarations themselves aren't
+/// attributes. Serializing declares each namespace again where it isn't
+/// already in scope.
+///
+/// `Hash` and `Ord` are consistent with `Eq`: attributes are compared as a set
+/// (sorted by name), children in document order. Elements are ordered by tag,
+/// then namespace and prefix, then attributes, then children. Call [`normalize`](Self::normalize) first
+/// if trees that differ only in how their text is split should compare equal.
 #[derive(Debug, Clone, PartialEq, Eq, Default, facet::Facet)]
 pub struct Element {
     /// The element's tag name (captured dynamically).
     #[facet(xml::tag, default)]
     pub tag: String,
 
-    /// All attributes as key-value pairs.
+    /// The namespace URI the element is in, if any.
+    #[facet(xml::tag_ns, default)]
+    pub namespace: Option<String>,
+
+    /// The prefix the element's tag was written with (`None` if unprefixed).
+    #[facet(xml::tag_prefix, default)]
+    pub prefix: Option<String>,
+
+    /// All attributes as key-value pairs, in document (or insertion) order.
     #[facet(flatten, default)]
-    pub attrs: HashMap<String, String>,
+    pub attrs: IndexMap<String, String>,
 
     /// Child content (elements and text).
     #[facet(flatten, default)]
@@ -99,11 +349,85 @@ impl Element {
     pub fn new(tag: impl Into<String>) -> Self {
         Self {
             tag: tag.into(),
-            attrs: HashMap::new(),
+            namespace: None,
+            prefix: None,
+            attrs: IndexMap::new(),
             children: Vec::new(),
         }
     }
 
+    /// Create a new element in a namespace, written with `prefix` (or as
+    /// the default namespace for `None`).
+    ///
+    /// ```
+    /// use facet_xml_node::Element;
+    ///
+    /// let title = Element::new_ns("title", "http://purl.org/dc/elements/1.1/", Some("dc"))
+    ///     .with_text("Dune");
+    /// assert_eq!(
+    ///     facet_xml::to_string(&title).unwrap(),
+    ///     r#"<dc:title xmlns:dc="http://purl.org/dc/elements/1.1/">Dune</dc:title>"#
+    /// );
+    /// ```
+    pub fn new_ns(
+        tag: impl Into<String>,
+        namespace: impl Into<String>,
+        prefix: Option<&str>,
+    ) -> Self {
+        Self {
+            namespace: Some(namespace.into()),
+            prefix: prefix.map(str::to_string),
+            ..Self::new(tag)
+        }
+    }
+
+    /// The namespace URI the element is in, if any.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// The prefix the element's tag is written with, if any.
+    pub fn prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    /// The tag as written: `prefix:tag`, or just the tag when unprefixed.
+    pub fn qualified_name(&self) -> std::borrow::Cow<'_, str> {
+        match &self.prefix {
+            Some(prefix) => format!("{prefix}:{}", self.tag).into(),
+            None => self.tag.as_str().into(),
+        }
+    }
+
+    /// Create a new element, checking that `tag` is a valid XML name.
+    ///
+    /// ```
+    /// use facet_xml_node::Element;
+    ///
+    /// assert!(Element::try_new("item").is_ok());
+    /// assert!(Element::try_new("<script>").is_err());
+    /// ```
+    pub fn try_new(tag: impl Into<String>) -> Result<Self, ValidationError> {
+        let tag = tag.into();
+        if !xml::is_xml_name(&tag) {
+            return Err(ValidationError::InvalidTag { path: vec![], tag });
+        }
+        Ok(Self::new(tag))
+    }
+
+    /// Add an attribute, checking that its name is a valid XML name and its
+    /// value only contains characters allowed in XML.
+    pub fn try_with_attr(
+        self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<Self, ValidationError> {
+        let name = name.into();
+        let value = value.into();
+        check_attr(&name, &value, &[])?;
+        Ok(self.with_attr(name, value))
+    }
+
     /// Add an attribute.
     pub fn with_attr(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
         self.attrs.insert(name.into(), value.into());
@@ -116,6 +440,12 @@ impl Element {
         self
     }
 
+    /// Add a child element shared with other trees. See [`Content::Shared`].
+    pub fn with_shared_child(mut self, child: Arc<Element>) -> Self {
+        self.children.push(Content::Shared(child));
+        self
+    }
+
     /// Add text content.
     pub fn with_text(mut self, text: impl Into<String>) -> Self {
         self.children.push(Content::Text(text.into()));
@@ -127,23 +457,195 @@ impl Element {
         self.attrs.get(name).map(|s| s.as_str())
     }
 
+    /// Get an attribute value parsed as `T`.
+    ///
+    /// Returns `None` if the attribute is absent.
+    pub fn get_attr_as<T: FromStr>(&self, name: &str) -> Option<Result<T, T::Err>> {
+        self.get_attr(name).map(str::parse)
+    }
+
+    /// Get an attribute value parsed as `T`, failing if it is absent or invalid.
+    ///
+    /// The error names both the attribute and this element's tag.
+    pub fn require_attr_as<T>(&self, name: &str) -> Result<T, AttrError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        let value = self.get_attr(name).ok_or_else(|| AttrError::Missing {
+            tag: self.tag.clone(),
+            name: name.to_string(),
+        })?;
+        value.parse().map_err(|e: T::Err| AttrError::Invalid {
+            tag: self.tag.clone(),
+            name: name.to_string(),
+            value: value.to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Serialize `value` with [`to_element`] and store it as a child.
+    ///
+    /// Replaces the first child element with the same tag, or appends if
+    /// there is none.
+    pub fn set_child_from<T>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), facet_dom::DomSerializeError<ElementSerializeError>>
+    where
+        T: facet_core::Facet<'static>,
+    {
+        let child = to_element(value)?;
+        let existing = self
+            .children
+            .iter_mut()
+            .find(|c| c.as_element().is_some_and(|e| e.tag == child.tag));
+        match existing {
+            Some(c) => *c = Content::Element(child),
+            None => self.children.push(Content::Element(child)),
+        }
+        Ok(())
+    }
+
+    /// Set an attribute from a scalar value, formatted the same way the
+    /// serializers format attribute values.
+    ///
+    /// Fails if `value` is not a scalar (or is `None`).
+    pub fn set_attr_from<T>(
+        &mut self,
+        name: impl Into<String>,
+        value: &T,
+    ) -> Result<(), ElementSerializeError>
+    where
+        T: facet_core::Facet<'static>,
+    {
+        let value =
+            parser::format_scalar(facet_reflect::Peek::new(value)).ok_or(ElementSerializeError)?;
+        self.attrs.insert(name.into(), value);
+        Ok(())
+    }
+
     /// Iterate over child elements (skipping text nodes).
     pub fn child_elements(&self) -> impl Iterator<Item = &Element> {
         self.children.iter().filter_map(|c| c.as_element())
     }
 
+    /// Deserialize each child element whose tag matches `T`'s element name.
+    ///
+    /// The expected name follows the usual rules: `rename`, then `rename_all`,
+    /// then the lowerCamelCase type name. Other children are skipped.
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml_node::Element;
+    ///
+    /// #[derive(Facet, Debug, PartialEq)]
+    /// struct Row {
+    ///     id: u32,
+    /// }
+    ///
+    /// let table: Element =
+    ///     facet_xml::from_str("<table><row><id>1</id></row><note/><row><id>2</id></row></table>")
+    ///         .unwrap();
+    /// let rows: Vec<Row> = table.children_as::<Row>().collect::<Result<_, _>>().unwrap();
+    /// assert_eq!(rows, vec![Row { id: 1 }, Row { id: 2 }]);
+    /// ```
+    pub fn children_as<T>(
+        &self,
+    ) -> impl Iterator<Item = Result<T, facet_dom::DomDeserializeError<ElementParseError>>> + '_
+    where
+        T: facet_core::Facet<'static>,
+    {
+        let name = facet_dom::naming::shape_element_name(T::SHAPE);
+        self.child_elements()
+            .filter(move |child| child.tag == name)
+            .map(from_element)
+    }
+
     /// Get the combined text content (concatenated from all text children).
     pub fn text_content(&self) -> String {
         let mut result = String::new();
         for child in &self.children {
-            match child {
-                Content::Text(t) => result.push_str(t),
-                Content::Element(e) => result.push_str(&e.text_content()),
+            match child.as_element() {
+                Some(e) => result.push_str(&e.text_content()),
+                None => result.push_str(child.as_text().unwrap_or_default()),
             }
         }
         result
     }
 
+    /// Replace every occurrence of `pattern` in the text nodes of this
+    /// element and its descendants, returning how many were replaced.
+    ///
+    /// Tags and attributes are left alone, and a match never spans two text
+    /// nodes. Shared children are only copied if they contain a match.
+    ///
+    /// ```
+    /// use facet_xml_node::Element;
+    ///
+    /// let mut doc = Element::new("p")
+    ///     .with_attr("title", "colour")
+    ///     .with_text("colour and ")
+    ///     .with_child(Element::new("b").with_text("colour"));
+    /// assert_eq!(doc.replace_text("colour", "color"), 2);
+    /// assert_eq!(doc.text_content(), "color and color");
+    /// assert_eq!(doc.get_attr("title"), Some("colour"));
+    /// ```
+    pub fn replace_text(&mut self, pattern: &str, replacement: &str) -> usize {
+        if pattern.is_empty() {
+            return 0;
+        }
+        self.rewrite_text(&|text| text.matches(pattern).count(), &|text| {
+            text.replace(pattern, replacement)
+        })
+    }
+
+    /// Like [`replace_text`](Self::replace_text), with a regular expression.
+    ///
+    /// `replacement` may refer to capture groups as `$1` or `$name`, as in
+    /// [`Regex::replace_all`](regex::Regex::replace_all).
+    #[cfg(feature = "regex")]
+    pub fn replace_text_regex(&mut self, pattern: &regex::Regex, replacement: &str) -> usize {
+        self.rewrite_text(&|text| pattern.find_iter(text).count(), &|text| {
+            pattern.replace_all(text, replacement).into_owned()
+        })
+    }
+
+    /// Rewrite the text nodes where `count` finds matches, summing the counts.
+    fn rewrite_text(
+        &mut self,
+        count: &dyn Fn(&str) -> usize,
+        rewrite: &dyn Fn(&str) -> String,
+    ) -> usize {
+        let mut total = 0;
+        for child in &mut self.children {
+            match child {
+                Content::Text(text) => {
+                    let n = count(text);
+                    if n > 0 {
+                        *text = rewrite(text);
+                        total += n;
+                    }
+                }
+                Content::Element(e) => total += e.rewrite_text(count, rewrite),
+                Content::Shared(e) => {
+                    if e.has_text_matching(count) {
+                        total += Arc::make_mut(e).rewrite_text(count, rewrite);
+                    }
+                }
+                Content::Comment(_) | Content::ProcessingInstruction { .. } => {}
+            }
+        }
+        total
+    }
+
+    fn has_text_matching(&self, count: &dyn Fn(&str) -> usize) -> bool {
+        self.children.iter().any(|child| match child.as_element() {
+            Some(e) => e.has_text_matching(count),
+            None => child.as_text().is_some_and(|text| count(text) > 0),
+        })
+    }
+
     /// Get a mutable reference to content at a path.
     /// Path is a sequence of child indices.
     pub fn get_content_mut(&mut self, path: &[usize]) -> Result<&mut Content, PathError> {
@@ -166,9 +668,9 @@ impl Element {
             return Ok(child);
         }
 
-        match child {
-            Content::Element(e) => e.get_content_mut(&path[1..]),
-            Content::Text(_) => Err(PathError::TextNodeHasNoChildren {
+        match child.as_element_mut() {
+            Some(e) => e.get_content_mut(&path[1..]),
+            None => Err(PathError::TextNodeHasNoChildren {
                 path: path.to_vec(),
             }),
         }
@@ -179,27 +681,251 @@ impl Element {
         if path.is_empty() {
             return Ok(&mut self.children);
         }
-        match self.get_content_mut(path)? {
-            Content::Element(e) => Ok(&mut e.children),
-            Content::Text(_) => Err(PathError::TextNodeHasNoChildren {
+        match self.get_content_mut(path)?.as_element_mut() {
+            Some(e) => Ok(&mut e.children),
+            None => Err(PathError::TextNodeHasNoChildren {
                 path: path.to_vec(),
             }),
         }
     }
 
     /// Get a mutable reference to the attrs at a path.
-    pub fn attrs_mut(&mut self, path: &[usize]) -> Result<&mut HashMap<String, String>, PathError> {
+    pub fn attrs_mut(
+        &mut self,
+        path: &[usize],
+    ) -> Result<&mut IndexMap<String, String>, PathError> {
         if path.is_empty() {
             return Ok(&mut self.attrs);
         }
-        match self.get_content_mut(path)? {
-            Content::Element(e) => Ok(&mut e.attrs),
-            Content::Text(_) => Err(PathError::TextNodeHasNoChildren {
+        match self.get_content_mut(path)?.as_element_mut() {
+            Some(e) => Ok(&mut e.attrs),
+            None => Err(PathError::TextNodeHasNoChildren {
                 path: path.to_vec(),
             }),
         }
     }
 
+    /// Insert `content` as child `idx` of the element at `path` (the root
+    /// for an empty path), shifting later children along.
+    ///
+    /// `idx` may equal the number of children, to append.
+    ///
+    /// ```
+    /// use facet_xml_node::Element;
+    ///
+    /// let mut doc = Element::new("ul").with_child(Element::new("li").with_text("b"));
+    /// doc.insert_child_at(&[0], 0, "a ").unwrap();
+    /// doc.insert_child_at(&[], 1, Element::new("li").with_text("c")).unwrap();
+    /// doc.set_attr_at(&[1], "class", "last").unwrap();
+    /// assert_eq!(doc.to_html(), r#"<ul><li>a b</li><li class="last">c</li></ul>"#);
+    /// ```
+    pub fn insert_child_at(
+        &mut self,
+        path: &[usize],
+        idx: usize,
+        content: impl Into<Content>,
+    ) -> Result<(), PathError> {
+        let children = self.children_mut(path)?;
+        if idx > children.len() {
+            return Err(PathError::IndexOutOfBounds {
+                path: [path, &[idx]].concat(),
+                index: idx,
+                len: children.len(),
+            });
+        }
+        children.insert(idx, content.into());
+        Ok(())
+    }
+
+    /// Remove the content at `path` and return it.
+    pub fn remove_at(&mut self, path: &[usize]) -> Result<Content, PathError> {
+        let Some((&idx, parent_path)) = path.split_last() else {
+            return Err(PathError::EmptyPath { path: vec![] });
+        };
+        let children = self.children_mut(parent_path)?;
+        if idx >= children.len() {
+            return Err(PathError::IndexOutOfBounds {
+                path: path.to_vec(),
+                index: idx,
+                len: children.len(),
+            });
+        }
+        Ok(children.remove(idx))
+    }
+
+    /// Replace the content at `path`, returning what was there.
+    pub fn replace_at(
+        &mut self,
+        path: &[usize],
+        content: impl Into<Content>,
+    ) -> Result<Content, PathError> {
+        let slot = self.get_content_mut(path)?;
+        Ok(std::mem::replace(slot, content.into()))
+    }
+
+    /// Set an attribute of the element at `path` (the root for an empty
+    /// path), returning its previous value.
+    ///
+    /// A new attribute goes after the existing ones; an existing one keeps
+    /// its position.
+    pub fn set_attr_at(
+        &mut self,
+        path: &[usize],
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<Option<String>, PathError> {
+        Ok(self.attrs_mut(path)?.insert(name.into(), value.into()))
+    }
+
+    /// Check the whole tree for names and characters that can't be written
+    /// as XML, returning the first problem in document order.
+    ///
+    /// Use this on trees assembled from untrusted strings; serializing an
+    /// invalid tree would otherwise produce malformed output.
+    ///
+    /// ```
+    /// use facet_xml_node::{Element, ValidationError};
+    ///
+    /// let tree = Element::new("list").with_child(Element::new("item").with_attr("bad name", "x"));
+    /// assert_eq!(
+    ///     tree.validate(),
+    ///     Err(ValidationError::InvalidAttrName { path: vec![0], name: "bad name".into() })
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_at(&mut Vec::new())
+    }
+
+    fn validate_at(&self, path: &mut Vec<usize>) -> Result<(), ValidationError> {
+        if !xml::is_xml_name(&self.tag) {
+            return Err(ValidationError::InvalidTag {
+                path: path.clone(),
+                tag: self.tag.clone(),
+            });
+        }
+        for (name, value) in self.sorted_attrs() {
+            check_attr(name, value, path)?;
+        }
+        for (idx, child) in self.children.iter().enumerate() {
+            path.push(idx);
+            match child {
+                Content::Element(e) => e.validate_at(path)?,
+                Content::Shared(e) => e.validate_at(path)?,
+                Content::Text(text) | Content::Comment(text) => check_text(text, path)?,
+                Content::ProcessingInstruction { target, data } => {
+                    check_text(target, path)?;
+                    check_text(data, path)?;
+                }
+            }
+            path.pop();
+        }
+        Ok(())
+    }
+
+    /// Merge adjacent text nodes and drop empty ones, recursively.
+    ///
+    /// Two trees that render to the same markup compare equal (and hash the
+    /// same) once both are normalized.
+    pub fn normalize(&mut self) {
+        let children = std::mem::take(&mut self.children);
+        for child in children {
+            match child {
+                Content::Text(text) if text.is_empty() => {}
+                Content::Text(text) => match self.children.last_mut() {
+                    Some(Content::Text(prev)) => prev.push_str(&text),
+                    _ => self.children.push(Content::Text(text)),
+                },
+                Content::Element(mut e) => {
+                    e.normalize();
+                    self.children.push(Content::Element(e));
+                }
+                Content::Shared(mut e) => {
+                    Arc::make_mut(&mut e).normalize();
+                    self.children.push(Content::Shared(e));
+                }
+                other => self.children.push(other),
+            }
+        }
+    }
+
+    /// Return a normalized copy of this element. See [`normalize`](Self::normalize).
+    pub fn normalized(mut self) -> Self {
+        self.normalize();
+        self
+    }
+
+    /// Attributes sorted by name, for hashing and ordering.
+    fn sorted_attrs(&self) -> Vec<(&String, &String)> {
+        let mut attrs: Vec<_> = self.attrs.iter().collect();
+        attrs.sort_unstable();
+        attrs
+    }
+
+    /// Set a value addressed by a slash-separated path relative to this element.
+    ///
+    /// Each segment names a child element by tag; the first matching child is
+    /// followed, and missing children are created. A final `@name` segment sets
+    /// that attribute; otherwise the addressed element's content is replaced
+    /// with `value` as text.
+    ///
+    /// ```
+    /// use facet_xml_node::Element;
+    ///
+    /// let mut config = Element::new("config");
+    /// config.set_at("server/connection/@timeout", "30").unwrap();
+    /// config.set_at("server/name", "primary").unwrap();
+    /// assert_eq!(
+    ///     config.to_html(),
+    ///     r#"<config><server><connection timeout="30"></connection><name>primary</name></server></config>"#
+    /// );
+    /// ```
+    pub fn set_at(&mut self, path: &str, value: impl Into<String>) -> Result<(), PathError> {
+        let invalid = |reason| PathError::InvalidSelector {
+            selector: path.to_string(),
+            reason,
+        };
+
+        let mut segments = path.split('/').peekable();
+        let mut current = self;
+        while let Some(segment) = segments.next() {
+            if segment.is_empty() {
+                return Err(invalid("empty path segment"));
+            }
+            if let Some(attr) = segment.strip_prefix('@') {
+                if segments.peek().is_some() {
+                    return Err(invalid("attribute segment must come last"));
+                }
+                if attr.is_empty() {
+                    return Err(invalid("empty attribute name"));
+                }
+                current.attrs.insert(attr.to_string(), value.into());
+                return Ok(());
+            }
+            current = current.child_or_insert(segment);
+        }
+
+        current.children = vec![Content::Text(value.into())];
+        Ok(())
+    }
+
+    /// The first child element with `tag`, appending a new one if there is none.
+    fn child_or_insert(&mut self, tag: &str) -> &mut Element {
+        let idx = match self
+            .children
+            .iter()
+            .position(|c| c.as_element().is_some_and(|e| e.tag == tag))
+        {
+            Some(idx) => idx,
+            None => {
+                self.children.push(Content::Element(Element::new(tag)));
+                self.children.len() - 1
+            }
+        };
+        self.children[idx]
+            .as_element_mut()
+            .expect("position() matched an element")
+    }
+
     /// Serialize to HTML string.
     pub fn to_html(&self) -> String {
         let mut out = String::new();
@@ -208,12 +934,30 @@ impl Element {
     }
 
     /// Write HTML to a string buffer.
+    ///
+    /// Attributes are written in the order they were read or inserted.
     pub fn write_html(&self, out: &mut String) {
+        self.write_html_ordered(out, None);
+    }
+
+    /// Write HTML to a string buffer, ordering attributes with `compare`.
+    ///
+    /// Uses the same comparator type as [`facet_xml::SerializeOptions::attribute_order`].
+    pub fn write_html_with_attribute_order(
+        &self,
+        out: &mut String,
+        compare: xml::AttributeComparator,
+    ) {
+        self.write_html_ordered(out, Some(compare));
+    }
+
+    fn write_html_ordered(&self, out: &mut String, compare: Option<xml::AttributeComparator>) {
         out.push('<');
         out.push_str(&self.tag);
-        // Sort attrs for deterministic output
         let mut attr_list: Vec<_> = self.attrs.iter().collect();
-        attr_list.sort_by_key(|(k, _)| *k);
+        if let Some(compare) = compare {
+            attr_list.sort_by(|(a, _), (b, _)| compare(a, b));
+        }
         for (k, v) in attr_list {
             out.push(' ');
             out.push_str(k);
@@ -224,8 +968,15 @@ impl Element {
         out.push('>');
         for child in &self.children {
             match child {
-                Content::Text(s) => out.push_str(s),
-                Content::Element(e) => e.write_html(out),
+                Content::Element(e) => e.write_html_ordered(out, compare),
+                Content::Shared(e) => e.write_html_ordered(out, compare),
+                Content::Text(text) => out.push_str(text),
+                Content::Comment(text) => {
+                    out.push_str("<!--");
+                    out.push_str(text);
+                    out.push_str("-->");
+                }
+                Content::ProcessingInstruction { .. } => {}
             }
         }
         out.push_str("</");
@@ -234,6 +985,60 @@ impl Element {
     }
 }
 
+impl std::hash::Hash for Element {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.tag.hash(state);
+        self.namespace.hash(state);
+        self.prefix.hash(state);
+        self.sorted_attrs().hash(state);
+        self.children.hash(state);
+    }
+}
+
+impl PartialOrd for Element {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Element {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.tag
+            .cmp(&other.tag)
+            .then_with(|| self.namespace.cmp(&other.namespace))
+            .then_with(|| self.prefix.cmp(&other.prefix))
+            .then_with(|| self.sorted_attrs().cmp(&other.sorted_attrs()))
+            .then_with(|| self.children.cmp(&other.children))
+    }
+}
+
+fn check_attr(name: &str, value: &str, path: &[usize]) -> Result<(), ValidationError> {
+    if !xml::is_xml_name(name) {
+        return Err(ValidationError::InvalidAttrName {
+            path: path.to_vec(),
+            name: name.to_string(),
+        });
+    }
+    if let Some(char) = value.chars().find(|&c| !xml::is_xml_char(c)) {
+        return Err(ValidationError::InvalidAttrValue {
+            path: path.to_vec(),
+            name: name.to_string(),
+            char,
+        });
+    }
+    Ok(())
+}
+
+fn check_text(text: &str, path: &[usize]) -> Result<(), ValidationError> {
+    match text.chars().find(|&c| !xml::is_xml_char(c)) {
+        Some(char) => Err(ValidationError::InvalidText {
+            path: path.to_vec(),
+            char,
+        }),
+        None => Ok(()),
+    }
+}
+
 fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -247,6 +1052,12 @@ impl From<Element> for Content {
     }
 }
 
+impl From<Arc<Element>> for Content {
+    fn from(e: Arc<Element>) -> Self {
+        Content::Shared(e)
+    }
+}
+
 impl From<String> for Content {
     fn from(s: String) -> Self {
         Content::Text(s)
@@ -282,6 +1093,961 @@ mod tests {
         assert_eq!(child.text_content(), "hello world");
     }
 
+    #[test]
+    fn normalized_elements_hash_and_order() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let a = Element::new("p")
+            .with_attr("a", "1")
+            .with_attr("b", "2")
+            .with_text("hello ")
+            .with_text("")
+            .with_text("world");
+        let b = Element::new("p")
+            .with_attr("b", "2")
+            .with_attr("a", "1")
+            .with_text("hello world");
+
+        // Differently split text is only equal after normalization
+        assert_ne!(a, b);
+        let a = a.normalized();
+        assert_eq!(a, b);
+
+        let hashed: HashSet<Element> = [a.clone(), b.clone()].into_iter().collect();
+        assert_eq!(hashed.len(), 1);
+
+        let ordered: BTreeSet<Element> = [
+            Element::new("p").with_attr("a", "2"),
+            b.clone(),
+            Element::new("div"),
+        ]
+        .into_iter()
+        .collect();
+        let tags: Vec<_> = ordered
+            .iter()
+            .map(|e| (e.tag.as_str(), e.get_attr("a")))
+            .collect();
+        assert_eq!(
+            tags,
+            vec![("div", None), ("p", Some("1")), ("p", Some("2"))]
+        );
+    }
+
+    #[test]
+    fn children_as_filters_by_type_name() {
+        #[derive(Facet, Debug, PartialEq)]
+        #[facet(rename = "entry")]
+        struct Entry {
+            #[facet(xml::attribute)]
+            id: u32,
+        }
+
+        let feed = Element::new("feed")
+            .with_child(Element::new("entry").with_attr("id", "1"))
+            .with_child(Element::new("title").with_text("ignored"))
+            .with_text("ignored too")
+            .with_child(Element::new("entry").with_attr("id", "oops"));
+
+        let entries: Vec<_> = feed.children_as::<Entry>().collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].as_ref().unwrap(), &Entry { id: 1 });
+        assert!(entries[1].is_err());
+    }
+
+    #[test]
+    fn typed_attribute_getters() {
+        let elem = Element::new("server")
+            .with_attr("port", "8080")
+            .with_attr("timeout", "soon");
+
+        assert_eq!(elem.get_attr_as::<u16>("port"), Some(Ok(8080)));
+        assert!(elem.get_attr_as::<u32>("timeout").unwrap().is_err());
+        assert!(elem.get_attr_as::<u32>("missing").is_none());
+
+        assert_eq!(elem.require_attr_as::<u16>("port"), Ok(8080));
+        let err = elem.require_attr_as::<u32>("timeout").unwrap_err();
+        assert!(matches!(err, AttrError::Invalid { .. }));
+        assert!(err.to_string().contains("<server>"), "{err}");
+        let err = elem.require_attr_as::<u32>("missing").unwrap_err();
+        assert_eq!(err.to_string(), "missing attribute `missing` on <server>");
+    }
+
+    #[test]
+    fn typed_mutation() {
+        #[derive(Facet, Debug, PartialEq)]
+        #[facet(rename = "connection")]
+        struct Connection {
+            #[facet(xml::attribute)]
+            timeout: u32,
+        }
+
+        let mut server = Element::new("server")
+            .with_child(Element::new("connection").with_attr("timeout", "5"))
+            .with_child(Element::new("name").with_text("db"));
+
+        server.set_child_from(&Connection { timeout: 30 }).unwrap();
+        assert_eq!(server.child_elements().count(), 2);
+        let conn: Vec<Connection> = server
+            .children_as::<Connection>()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(conn, vec![Connection { timeout: 30 }]);
+
+        server.set_attr_from("port", &8080u16).unwrap();
+        server.set_attr_from("enabled", &true).unwrap();
+        assert_eq!(server.get_attr("port"), Some("8080"));
+        assert_eq!(server.get_attr("enabled"), Some("true"));
+        assert!(
+            server
+                .set_attr_from("bad", &Connection { timeout: 1 })
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn path_based_edits() {
+        let mut doc = Element::new("doc")
+            .with_child(Element::new("p").with_text("a"))
+            .with_text("tail");
+
+        doc.insert_child_at(&[0], 1, Element::new("b")).unwrap();
+        doc.insert_child_at(&[], 0, "head").unwrap();
+        assert_eq!(doc.set_attr_at(&[1], "id", "x").unwrap(), None);
+        assert_eq!(
+            doc.set_attr_at(&[1], "id", "y").unwrap(),
+            Some("x".to_string())
+        );
+        assert_eq!(
+            doc.replace_at(&[1, 0], "A").unwrap(),
+            Content::Text("a".into())
+        );
+        assert_eq!(doc.remove_at(&[2]).unwrap(), Content::Text("tail".into()));
+        assert_eq!(doc.to_html(), r#"<doc>head<p id="y">A<b></b></p></doc>"#);
+
+        // The cursor edits attributes in place as it walks
+        let mut cursor = doc.cursor();
+        assert!(cursor.child(1));
+        assert_eq!(cursor.set_attr("class", "c"), Some(None));
+        assert_eq!(cursor.remove_attr("id"), Some("y".to_string()));
+        assert!(cursor.down());
+        assert_eq!(cursor.set_attr("id", "t"), None);
+        assert_eq!(doc.to_html(), r#"<doc>head<p class="c">A<b></b></p></doc>"#);
+
+        assert_eq!(
+            doc.insert_child_at(&[1], 3, "x"),
+            Err(PathError::IndexOutOfBounds {
+                path: vec![1, 3],
+                index: 3,
+                len: 2
+            })
+        );
+        assert!(matches!(
+            doc.remove_at(&[]),
+            Err(PathError::EmptyPath { .. })
+        ));
+        assert!(matches!(
+            doc.set_attr_at(&[0], "id", "z"),
+            Err(PathError::TextNodeHasNoChildren { .. })
+        ));
+        assert!(matches!(
+            doc.replace_at(&[5], "z"),
+            Err(PathError::IndexOutOfBounds { .. })
+        ));
+    }
+
+    #[test]
+    fn diff_and_patch_round_trip() {
+        let cases = [
+            (
+                r#"<a x="1" y="2"><b>t</b><c/><d>u</d></a>"#,
+                r#"<a y="3" z="4"><c/><b>t</b><d>v<e/></d>w</a>"#,
+            ),
+            ("<a><b/><b/><b/></a>", "<a><b/></a>"),
+            ("<a>one</a>", "<a><x/>one<y/></a>"),
+            ("<a><b/></a>", "<z><b/></z>"),
+            ("<a/>", "<a/>"),
+        ];
+        for (old, new) in cases {
+            let old: Element = facet_xml::from_str(old).unwrap();
+            let new: Element = facet_xml::from_str(new).unwrap();
+            let mut patched = old.clone();
+            apply_patch(&mut patched, &diff(&old, &new)).unwrap();
+            assert_eq!(patched, new);
+        }
+
+        // Only the differences are recorded
+        let old = Element::new("list")
+            .with_child(Element::new("item").with_text("a"))
+            .with_child(Element::new("item").with_text("b"));
+        let mut new = old.clone();
+        new.insert_child_at(&[], 1, Element::new("item").with_text("new"))
+            .unwrap();
+        assert_eq!(
+            diff(&old, &new),
+            [EditOp::Insert {
+                path: vec![1],
+                content: Element::new("item").with_text("new").into(),
+            }]
+        );
+        assert!(diff(&old, &old).is_empty());
+
+        let mut doc = old.clone();
+        assert!(matches!(
+            apply_patch(&mut doc, &[EditOp::Delete { path: vec![9] }]),
+            Err(PathError::IndexOutOfBounds { .. })
+        ));
+    }
+
+    #[cfg(feature = "dsig")]
+    #[test]
+    fn dsig_hmac_known_answers() {
+        use dsig::{HmacSha256, SigningKey, VerifyingKey};
+
+        let hex = |bytes: &[u8]| -> String { bytes.iter().map(|b| format!("{b:02x}")).collect() };
+        // RFC 4231, test cases 1-4, 6 and 7 (5 truncates the output)
+        let cases: [(Vec<u8>, Vec<u8>, &str); 6] = [
+            (
+                vec![0x0b; 20],
+                b"Hi There".to_vec(),
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            ),
+            (
+                b"Jefe".to_vec(),
+                b"what do ya want for nothing?".to_vec(),
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                vec![0xaa; 20],
+                vec![0xdd; 50],
+                "773ea91e36800e46854db8ebd09181a72959098b3ef8c122d9635514ced565fe",
+            ),
+            (
+                (0x01..=0x19).collect(),
+                vec![0xcd; 50],
+                "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+            ),
+            (
+                vec![0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First".to_vec(),
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            ),
+            (
+                vec![0xaa; 131],
+                b"This is a test using a larger than block-size key and a larger than block-size data. The key needs to be hashed before being used by the HMAC algorithm.".to_vec(),
+                "9b09ffa71b942fcb27635fbcd5b0e944bfdc63644f0713938a7f51535c3a35e2",
+            ),
+        ];
+        for (key, data, expected) in cases {
+            let key = HmacSha256::new(&key);
+            let mac = key.sign(&data).unwrap();
+            assert_eq!(hex(&mac), expected);
+            assert!(key.verify(&data, &mac));
+            assert!(!key.verify(&data, &mac[..31]));
+            let mut flipped = mac.clone();
+            flipped[31] ^= 1;
+            assert!(!key.verify(&data, &flipped));
+        }
+    }
+
+    #[cfg(feature = "dsig")]
+    #[test]
+    fn dsig_sign_and_verify() {
+        use dsig::{DsigError, HmacSha256, SignOptions};
+
+        let doc: Element = facet_xml::from_str(
+            r#"<r:response xmlns:r="urn:r" ID="_1"><issuer>me</issuer><amount>10</amount></r:response>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            dsig::canonicalize(&facet_xml::to_string(&doc).unwrap(), &[]).unwrap(),
+            r#"<r:response xmlns:r="urn:r" ID="_1"><issuer>me</issuer><amount>10</amount></r:response>"#
+        );
+
+        let key = HmacSha256::new(b"k");
+        let mut signed = doc.clone();
+        dsig::sign_element(
+            &mut signed,
+            &key,
+            &SignOptions::new().prefix("ds").position(1),
+        )
+        .unwrap();
+        let signature = signed.child_elements().nth(1).unwrap();
+        assert_eq!(signature.qualified_name(), "ds:Signature");
+
+        let received: Element =
+            facet_xml::from_str(&facet_xml::to_string(&signed).unwrap()).unwrap();
+        assert_eq!(dsig::verify_element(&received, &key), Ok(()));
+        assert_eq!(
+            dsig::verify_element(&received, &HmacSha256::new(b"other")),
+            Err(DsigError::SignatureMismatch)
+        );
+
+        let mut tampered = received.clone();
+        tampered.set_at("amount", "1000").unwrap();
+        assert_eq!(
+            dsig::verify_element(&tampered, &key),
+            Err(DsigError::DigestMismatch)
+        );
+        let mut moved = received.clone();
+        moved.attrs.insert("ID".into(), "_2".into());
+        assert!(matches!(
+            dsig::verify_element(&moved, &key),
+            Err(DsigError::ReferenceMismatch(_))
+        ));
+        assert_eq!(
+            dsig::verify_element(&doc, &key),
+            Err(DsigError::MissingSignature)
+        );
+    }
+
+    #[cfg(feature = "dsig")]
+    #[test]
+    fn dsig_verifies_documents_signed_elsewhere() {
+        use dsig::{DsigError, HmacSha256, verify_document};
+
+        // Digest and signature computed over libxml2's exclusive
+        // canonicalization, with the key "secret"
+        let signed = r##"<?xml version="1.0" encoding="UTF-8"?>
+<?xml-stylesheet href="response.xsl" type="text/xsl"?>
+<samlp:Response xmlns:samlp="urn:oasis:names:tc:SAML:2.0:protocol" xmlns:saml="urn:oasis:names:tc:SAML:2.0:assertion" xmlns:unused="urn:example:unused" ID="_r1" xml:lang="en">
+  <saml:Issuer>https://idp.example.org</saml:Issuer>
+  <!-- issued by the test IdP -->
+  <ds:Signature xmlns:ds="http://www.w3.org/2000/09/xmldsig#">
+    <ds:SignedInfo>
+      <ds:CanonicalizationMethod Algorithm="http://www.w3.org/2001/10/xml-exc-c14n#"/>
+      <ds:SignatureMethod Algorithm="http://www.w3.org/2001/04/xmldsig-more#hmac-sha256"/>
+      <ds:Reference URI="#_r1">
+        <ds:Transforms>
+          <ds:Transform Algorithm="http://www.w3.org/2000/09/xmldsig#enveloped-signature"/>
+          <ds:Transform Algorithm="http://www.w3.org/2001/10/xml-exc-c14n#"/>
+        </ds:Transforms>
+        <ds:DigestMethod Algorithm="http://www.w3.org/2001/04/xmlenc#sha256"/>
+        <ds:DigestValue>tBBUKQmw9VHV06SqSKdR93lXMEunVK+RZgLmhVf/kf8=</ds:DigestValue>
+      </ds:Reference>
+    </ds:SignedInfo>
+    <ds:SignatureValue>qFjPjdYQaJ/XCP7P68QkJvIIbIhfBMRiwkw10Lsp
+      qTU=</ds:SignatureValue>
+  </ds:Signature>
+  <samlp:Status>
+    <samlp:StatusCode Value="urn:oasis:names:tc:SAML:2.0:status:Success"/>
+  </samlp:Status>
+</samlp:Response>"##;
+        let key = HmacSha256::new(b"secret");
+        assert_eq!(verify_document(signed, &key), Ok(()));
+        assert_eq!(verify_document(&signed.replace('\n', "\r\n"), &key), Ok(()));
+        // Comments and unused namespace declarations aren't signed
+        assert_eq!(
+            verify_document(&signed.replace("test IdP", "other IdP"), &key),
+            Ok(())
+        );
+        assert_eq!(
+            verify_document(
+                &signed.replace(r#" xmlns:unused="urn:example:unused""#, ""),
+                &key
+            ),
+            Ok(())
+        );
+
+        // Namespaced attributes and whitespace are
+        for tampered in [
+            signed.replace(r#"xml:lang="en""#, r#"xml:lang="fr""#),
+            signed.replace("Success\"/>\n", "Success\"/>\n\n"),
+            signed.replace("idp.example.org", "evil.example.org"),
+        ] {
+            assert_eq!(
+                verify_document(&tampered, &key),
+                Err(DsigError::DigestMismatch)
+            );
+        }
+        assert_eq!(
+            verify_document(signed, &HmacSha256::new(b"other")),
+            Err(DsigError::SignatureMismatch)
+        );
+        // A second element with the signed ID could be what gets read
+        assert_eq!(
+            verify_document(
+                &signed.replace("<samlp:Status>", r#"<samlp:Status ID="_r1">"#),
+                &key
+            ),
+            Err(DsigError::Malformed("duplicate ID"))
+        );
+        assert!(matches!(
+            verify_document("<doc>&undeclared;</doc>", &key),
+            Err(DsigError::Xml(_))
+        ));
+    }
+
+    #[test]
+    fn set_at_paths() {
+        let mut config = Element::new("config").with_child(
+            Element::new("server").with_child(Element::new("connection").with_attr("timeout", "5")),
+        );
+
+        config.set_at("server/connection/@timeout", "30").unwrap();
+        config.set_at("server/connection/@retries", "3").unwrap();
+        config.set_at("server/host", "localhost").unwrap();
+        config.set_at("server/host", "example.com").unwrap();
+
+        let server = config.child_elements().next().unwrap();
+        assert_eq!(server.child_elements().count(), 2);
+        let conn = server.child_elements().next().unwrap();
+        assert_eq!(conn.get_attr("timeout"), Some("30"));
+        assert_eq!(conn.get_attr("retries"), Some("3"));
+        let host = server.child_elements().nth(1).unwrap();
+        assert_eq!(host.text_content(), "example.com");
+
+        config.set_at("@version", "2").unwrap();
+        assert_eq!(config.get_attr("version"), Some("2"));
+
+        for bad in ["", "server//host", "@a/b", "server/@"] {
+            assert!(
+                matches!(
+                    config.set_at(bad, "x"),
+                    Err(PathError::InvalidSelector { .. })
+                ),
+                "{bad:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn select_steps_predicates_and_descendants() {
+        let library: Element = facet_xml::from_str(
+            r#"<library>
+                <shelf name="sf">
+                    <book lang="en"><title>Dune</title><author id="7"><name>Herbert</name></author></book>
+                    <book><title>Solaris</title><author id="3"><name>Lem</name></author></book>
+                </shelf>
+                <shelf name="fantasy">
+                    <book lang="en"><title>Earthsea</title><author id="3"><name>Le Guin</name></author></book>
+                </shelf>
+            </library>"#,
+        )
+        .unwrap();
+
+        let titles = |selector: &str| -> Vec<String> {
+            library
+                .select(selector)
+                .unwrap()
+                .map(|e| e.text_content())
+                .collect()
+        };
+        assert_eq!(titles("shelf/book/title"), ["Dune", "Solaris", "Earthsea"]);
+        assert_eq!(titles("shelf/book[@lang]/title"), ["Dune", "Earthsea"]);
+        assert_eq!(titles("shelf/book[1]/title"), ["Dune", "Earthsea"]);
+        assert_eq!(titles("shelf[@name='sf']/book[2]/title"), ["Solaris"]);
+        assert_eq!(titles("*/book[title=\"Dune\"]/title"), ["Dune"]);
+        assert_eq!(titles("//author[@id='3']/name"), ["Lem", "Le Guin"]);
+        assert_eq!(titles("shelf//name"), ["Herbert", "Lem", "Le Guin"]);
+        assert!(titles("shelf/book[@lang='fr']").is_empty());
+
+        let first = library.select_first("//book[@lang='en']").unwrap().unwrap();
+        assert_eq!(first.select_text("title").unwrap().as_deref(), Some("Dune"));
+        assert!(library.select_first("shelf/film").unwrap().is_none());
+        assert_eq!(
+            library.select_text("shelf[2]/@name").unwrap().as_deref(),
+            Some("fantasy")
+        );
+        assert_eq!(
+            library
+                .select_text("shelf[@name='fantasy']/book/author/@id")
+                .unwrap()
+                .as_deref(),
+            Some("3")
+        );
+        assert_eq!(
+            library.select_text("//title").unwrap().as_deref(),
+            Some("Dune")
+        );
+
+        for bad in [
+            "",
+            "a//",
+            "book[",
+            "book[@id=3]",
+            "@id/book",
+            "book[0]",
+            "[1]",
+            "a/@",
+        ] {
+            assert!(
+                matches!(library.select(bad), Err(PathError::InvalidSelector { .. })),
+                "{bad:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn namespaces_and_prefixes_roundtrip() {
+        const ATOM: &str = "http://www.w3.org/2005/Atom";
+        const DC: &str = "http://purl.org/dc/elements/1.1/";
+
+        let feed: Element = facet_xml::from_str(
+            r#"<feed xmlns="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/elements/1.1/">
+                <title>News</title>
+                <dc:creator>Ann</dc:creator>
+                <plain xmlns="">x</plain>
+            </feed>"#,
+        )
+        .unwrap();
+        assert_eq!((feed.namespace(), feed.prefix()), (Some(ATOM), None));
+        assert!(
+            feed.attrs.is_empty(),
+            "xmlns declarations aren't attributes"
+        );
+
+        let children: Vec<&Element> = feed.child_elements().collect();
+        assert_eq!(
+            (children[0].namespace(), children[0].prefix()),
+            (Some(ATOM), None)
+        );
+        assert_eq!(
+            (children[1].namespace(), children[1].prefix()),
+            (Some(DC), Some("dc"))
+        );
+        assert_eq!(children[1].qualified_name(), "dc:creator");
+        assert_eq!(
+            (children[2].namespace(), children[2].prefix()),
+            (None, None)
+        );
+
+        let xml = facet_xml::to_string(&feed).unwrap();
+        assert_eq!(
+            xml,
+            concat!(
+                r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>News</title>"#,
+                r#"<dc:creator xmlns:dc="http://purl.org/dc/elements/1.1/">Ann</dc:creator>"#,
+                r#"<plain xmlns="">x</plain></feed>"#,
+            )
+        );
+        assert_eq!(facet_xml::from_str::<Element>(&xml).unwrap(), feed);
+
+        // A prefix already bound by an ancestor isn't declared again
+        let meta = Element::new_ns("meta", DC, Some("dc"))
+            .with_child(Element::new_ns("creator", DC, Some("dc")).with_text("Ann"));
+        assert_eq!(
+            facet_xml::to_string(&meta).unwrap(),
+            r#"<dc:meta xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:creator>Ann</dc:creator></dc:meta>"#
+        );
+        assert_eq!(crate::to_element(&meta).unwrap(), meta);
+    }
+
+    #[test]
+    fn attributes_keep_document_order() {
+        let input = r#"<rect y="2" x="1" width="10" height="5"></rect>"#;
+        let mut rect: Element = facet_xml::from_str(input).unwrap();
+        let names: Vec<&str> = rect.attrs.keys().map(String::as_str).collect();
+        assert_eq!(names, ["y", "x", "width", "height"]);
+        assert_eq!(facet_xml::to_string(&rect).unwrap(), input);
+        assert_eq!(rect.to_html(), input);
+
+        rect.attrs.insert("fill".into(), "red".into());
+        assert_eq!(
+            rect.to_html(),
+            r#"<rect y="2" x="1" width="10" height="5" fill="red"></rect>"#
+        );
+        let mut sorted = String::new();
+        rect.write_html_with_attribute_order(&mut sorted, str::cmp);
+        assert_eq!(
+            sorted,
+            r#"<rect fill="red" height="5" width="10" x="1" y="2"></rect>"#
+        );
+
+        // Order doesn't affect equality
+        let reordered: Element =
+            facet_xml::from_str(r#"<rect x="1" y="2" height="5" width="10" fill="red"/>"#).unwrap();
+        assert_eq!(reordered, rect);
+        assert_eq!(reordered.fingerprint(), rect.fingerprint());
+    }
+
+    #[test]
+    fn comments_and_processing_instructions_are_opt_in() {
+        let input = r#"<doc><!-- note --><?xml-stylesheet href="a.xsl"?><p>x</p></doc>"#;
+        let options = facet_xml::DeserializeOptions::new()
+            .keep_comments(true)
+            .keep_processing_instructions(true);
+        let doc: Element = facet_xml::from_str_with_options(input, &options).unwrap();
+        assert_eq!(doc.children.len(), 3);
+        assert_eq!(doc.children[0], Content::Comment(" note ".to_string()));
+        assert_eq!(
+            doc.children[1],
+            Content::ProcessingInstruction {
+                target: "xml-stylesheet".to_string(),
+                data: r#"href="a.xsl""#.to_string(),
+            }
+        );
+        assert_eq!(facet_xml::to_string(&doc).unwrap(), input);
+        assert_eq!(crate::to_element(&doc).unwrap(), doc);
+
+        // Without the options both are dropped, as before
+        let doc: Element = facet_xml::from_str(input).unwrap();
+        assert_eq!(doc.children.len(), 1);
+        assert_eq!(doc.child_elements().next().unwrap().tag, "p");
+    }
+
+    #[test]
+    fn validation_rejects_invalid_names_and_chars() {
+        assert!(Element::try_new("ns:item-1").is_ok());
+        assert!(Element::try_new("élément").is_ok());
+        for bad in ["", "1st", "a b", "a<b", "-x"] {
+            assert_eq!(
+                Element::try_new(bad),
+                Err(ValidationError::InvalidTag {
+                    path: vec![],
+                    tag: bad.into()
+                })
+            );
+        }
+
+        let item = Element::new("item").try_with_attr("id", "1").unwrap();
+        assert_eq!(item.get_attr("id"), Some("1"));
+        assert!(matches!(
+            Element::new("item").try_with_attr("i d", "1"),
+            Err(ValidationError::InvalidAttrName { .. })
+        ));
+        assert!(matches!(
+            Element::new("item").try_with_attr("id", "\u{0}"),
+            Err(ValidationError::InvalidAttrValue { char: '\0', .. })
+        ));
+
+        let tree = Element::new("root")
+            .with_text("ok")
+            .with_child(Element::new("a").with_text("bell\u{7}"));
+        assert_eq!(
+            tree.validate(),
+            Err(ValidationError::InvalidText {
+                path: vec![1, 0],
+                char: '\u{7}'
+            })
+        );
+        assert_eq!(Element::new("root").with_text("fine").validate(), Ok(()));
+    }
+
+    #[test]
+    fn cursor_navigation_and_edits() {
+        let mut doc = Element::new("doc")
+            .with_text("intro")
+            .with_child(Element::new("p").with_text("a"))
+            .with_child(Element::new("p").with_text("b"));
+
+        let mut cursor = doc.cursor();
+        assert!(cursor.is_root());
+        assert!(!cursor.up());
+        assert!(!cursor.next_sibling());
+        assert!(cursor.down());
+        assert_eq!(cursor.text(), Some("intro"));
+        assert!(!cursor.down());
+        assert!(!cursor.prev_sibling());
+        assert!(cursor.next_sibling());
+        assert_eq!(cursor.element().map(|e| e.tag.as_str()), Some("p"));
+        assert!(cursor.down());
+        assert_eq!(cursor.path(), &[1, 0]);
+        assert_eq!(cursor.parent().map(|e| e.tag.as_str()), Some("p"));
+        assert!(cursor.up());
+
+        // Wrap the first <p>, then insert around the wrapper
+        assert!(cursor.wrap(Element::new("section")));
+        assert!(cursor.insert_before(Element::new("hr")));
+        assert!(cursor.insert_after("tail"));
+        assert_eq!(cursor.path(), &[2]);
+        assert!(cursor.append_child(Element::new("footer")));
+        assert_eq!(
+            doc.to_html(),
+            "<doc>intro<hr></hr><section><p>a</p><footer></footer></section>tail<p>b</p></doc>"
+        );
+
+        // Unwrap the section again and remove the trailing <p>
+        let mut cursor = doc.cursor();
+        assert!(cursor.child(2));
+        assert!(cursor.unwrap());
+        assert_eq!(cursor.element().map(|e| e.tag.as_str()), Some("p"));
+        assert!(cursor.child(0));
+        assert_eq!(cursor.replace("A"), Some(Content::Text("a".into())));
+        assert!(cursor.up());
+        while cursor.next_sibling() {}
+        let removed = cursor.remove().unwrap();
+        assert_eq!(removed.as_element().map(|e| e.tag.as_str()), Some("p"));
+        assert_eq!(cursor.text(), Some("tail"));
+        assert_eq!(
+            doc.to_html(),
+            "<doc>intro<hr></hr><p>A</p><footer></footer>tail</doc>"
+        );
+    }
+
+    #[test]
+    fn shared_children_are_copied_on_write() {
+        let header = Arc::new(
+            Element::new("header").with_child(Element::new("ns").with_text("urn:example")),
+        );
+        let mut a = Element::new("doc").with_shared_child(header.clone());
+        let b = Element::new("doc").with_shared_child(header.clone());
+        assert_eq!(Arc::strong_count(&header), 3);
+
+        // Shared and owned children compare and render the same
+        let owned = Element::new("doc").with_child((*header).clone());
+        assert_eq!(a, owned);
+        assert_eq!(a.cmp(&owned), std::cmp::Ordering::Equal);
+        assert_eq!(a.to_html(), owned.to_html());
+        assert_eq!(
+            facet_xml::to_string(&a).unwrap(),
+            facet_xml::to_string(&owned).unwrap()
+        );
+
+        // Editing one tree copies the subtree, leaving the others alone
+        a.set_at("header/ns", "urn:changed").unwrap();
+        assert_eq!(
+            a.to_html(),
+            "<doc><header><ns>urn:changed</ns></header></doc>"
+        );
+        assert_eq!(
+            b.to_html(),
+            "<doc><header><ns>urn:example</ns></header></doc>"
+        );
+        assert_eq!(header.text_content(), "urn:example");
+        assert_eq!(Arc::strong_count(&header), 2);
+
+        // Parsing only produces owned children, whatever the tag
+        let parsed: Element =
+            facet_xml::from_str("<doc><shared/><element/><header/></doc>").unwrap();
+        assert!(
+            parsed
+                .children
+                .iter()
+                .all(|child| matches!(child, Content::Element(_)))
+        );
+    }
+
+    #[test]
+    fn fingerprint_digest_known_answers() {
+        use sha2::{Digest, Sha256};
+
+        // FIPS 180-4 examples, including the 448-bit two-block message
+        for (message, expected) in [
+            (
+                "",
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                "abc",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+                "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            ),
+        ] {
+            let digest: String = Sha256::digest(message)
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect();
+            assert_eq!(digest, expected, "{message:?}");
+        }
+
+        // Fingerprints are stable across versions, so pin one
+        let item = Element::new("item").with_attr("id", "1").with_text("hi");
+        assert_eq!(
+            item.fingerprint().to_string(),
+            "00f0c2e0056fc3fee5776803b9670f8bbcdb08bb37b82f2b7c408b7688cd08e3"
+        );
+    }
+
+    #[test]
+    fn fingerprint_ignores_only_non_semantic_differences() {
+        let section = Element::new("section")
+            .with_attr("id", "s1")
+            .with_attr("lang", "en")
+            .with_text("Hello, ")
+            .with_text("world")
+            .with_child(Element::new("em").with_text("!"));
+        let parsed: Element =
+            facet_xml::from_str(r#"<section lang="en" id="s1">Hello, world<em>!</em></section>"#)
+                .unwrap();
+        assert_eq!(section.fingerprint(), parsed.fingerprint());
+        let shared = Element::new("doc").with_shared_child(Arc::new(section.clone()));
+        let owned = Element::new("doc").with_child(section.clone());
+        assert_eq!(shared.fingerprint(), owned.fingerprint());
+
+        // Text vs. tag boundaries and attribute values all count
+        let moved = Element::new("section")
+            .with_attr("id", "s1")
+            .with_attr("lang", "en")
+            .with_text("Hello, world!");
+        assert_ne!(section.fingerprint(), moved.fingerprint());
+        let edited = section.clone().with_attr("lang", "fr");
+        assert_ne!(section.fingerprint(), edited.fingerprint());
+        assert_eq!(section.fingerprint().to_string().len(), 64);
+    }
+
+    #[test]
+    fn replace_text_only_touches_text_nodes() {
+        let shared = Arc::new(Element::new("sig").with_text("ACME Corp"));
+        let untouched = Arc::new(Element::new("footer").with_text("page 1"));
+        let mut doc = Element::new("letter")
+            .with_attr("from", "ACME Corp")
+            .with_child(Element::new("ACME").with_text("Dear ACME, ACME thanks you"))
+            .with_shared_child(shared.clone())
+            .with_shared_child(untouched.clone());
+
+        assert_eq!(doc.replace_text("ACME", "Initech"), 3);
+        assert_eq!(
+            doc.to_html(),
+            r#"<letter from="ACME Corp"><ACME>Dear Initech, Initech thanks you</ACME><sig>Initech Corp</sig><footer>page 1</footer></letter>"#
+        );
+        // Only the shared subtree with a match was copied
+        assert_eq!(shared.text_content(), "ACME Corp");
+        assert_eq!(Arc::strong_count(&shared), 1);
+        assert_eq!(Arc::strong_count(&untouched), 2);
+        assert_eq!(doc.replace_text("", "x"), 0);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn replace_text_regex_expands_captures() {
+        let mut doc = Element::new("log")
+            .with_child(Element::new("line").with_text("2024-01-31 started"))
+            .with_child(Element::new("line").with_text("2024-02-01 stopped"));
+        let date = regex::Regex::new(r"(\d{4})-(\d{2})-(\d{2})").unwrap();
+        assert_eq!(doc.replace_text_regex(&date, "$3/$2/$1"), 2);
+        assert_eq!(doc.text_content(), "31/01/2024 started01/02/2024 stopped");
+    }
+
+    #[test]
+    fn xml_value_types_leaves_and_round_trips() {
+        let xml = r#"<reading sensor="t1" ok="false" seq="007"><temp>21.5</temp><count>3</count>note</reading>"#;
+        let value: XmlValue = facet_xml::from_str(xml).unwrap();
+
+        assert_eq!(value.tag(), Some("reading"));
+        let attrs: Vec<_> = value.attrs().collect();
+        assert_eq!(
+            attrs,
+            [
+                ("ok", &Scalar::Bool(false)),
+                ("sensor", &Scalar::Str("t1".into())),
+                ("seq", &Scalar::Str("007".into())),
+            ]
+        );
+        let temp = value.child("temp").and_then(XmlValue::text);
+        assert_eq!(temp, Some(&Scalar::Float(21.5)));
+        let count = value.child("count").and_then(XmlValue::text);
+        assert_eq!(count.and_then(Scalar::as_i64), Some(3));
+        assert_eq!(value.children().count(), 3);
+
+        // Written back through the same Element mapping, values unchanged
+        let out = facet_xml::to_string(&value).unwrap();
+        let element: Element = facet_xml::from_str(xml).unwrap();
+        assert_eq!(facet_xml::from_str::<Element>(&out).unwrap(), element);
+        assert_eq!(XmlValue::from(element), value);
+
+        assert_eq!(
+            Element::try_from(&XmlValue::Text(Scalar::Int(1))),
+            Err(NotAnElement)
+        );
+    }
+
+    #[test]
+    fn load_filtered_by_depth() {
+        let xml = r#"<feed><meta><title>t</title></meta><entry n="1"><title>a</title></entry><entry n="2"/></feed>"#;
+        let loaded = load_filtered(xml.as_bytes(), |depth, tag: &str| {
+            depth == 1 && tag == "entry"
+        })
+        .unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].get_attr("n"), Some("1"));
+        assert_eq!(loaded[0].text_content(), "a");
+        assert_eq!(loaded[1].get_attr("n"), Some("2"));
+
+        let titles = load_filtered(xml.as_bytes(), |_, tag: &str| tag == "title").unwrap();
+        let titles: Vec<_> = titles.iter().map(Element::text_content).collect();
+        assert_eq!(titles, ["t", "a"]);
+    }
+
+    #[test]
+    fn pooled_parse_matches_from_str_and_reuses_allocations() {
+        let xml = r#"<order id="7"><item sku="a">2</item><item sku="b"/>note</order>"#;
+        let pool = ElementPool::with_max_retained(16);
+
+        let doc = pool.parse(xml).unwrap();
+        let expected: Element = facet_xml::from_str(xml).unwrap();
+        assert_eq!(*doc, expected);
+        drop(doc);
+        let retained = pool.retained();
+        assert!(retained > 0);
+
+        // The second parse is served from the pool and gives everything back
+        let doc = pool.parse(xml).unwrap();
+        assert!(pool.retained() < retained);
+        drop(doc);
+        assert_eq!(pool.retained(), retained);
+
+        // A detached tree is not returned
+        let owned = pool.parse(xml).unwrap().into_inner();
+        assert_eq!(owned, expected);
+        assert!(pool.retained() < retained);
+
+        assert!(pool.parse("").is_err());
+    }
+
+    #[cfg(feature = "bumpalo")]
+    #[test]
+    fn parse_in_arena_matches_from_str() {
+        let xml =
+            r#"<order id="7" note="a &amp; b"><item sku="a">2</item><item sku="b"/>note</order>"#;
+        let bump = bumpalo::Bump::new();
+
+        let doc = Element::parse_in(xml, &bump).unwrap();
+        let expected: Element = facet_xml::from_str(xml).unwrap();
+        assert_eq!(doc.to_element(), expected);
+        assert_eq!(doc.get_attr("note"), Some("a & b"));
+
+        // Plain values point into the input rather than the arena
+        let id = doc.get_attr("id").unwrap();
+        assert!(xml.as_bytes().as_ptr_range().contains(&id.as_ptr()));
+        let skus: Vec<_> = doc
+            .child_elements()
+            .filter_map(|e| e.get_attr("sku"))
+            .collect();
+        assert_eq!(skus, ["a", "b"]);
+        assert_eq!(doc.text_content(), "2note");
+
+        assert!(Element::parse_in("", &bump).is_err());
+    }
+
+    #[test]
+    fn fill_slots_replaces_placeholders() {
+        #[derive(Facet, Debug, PartialEq)]
+        #[facet(rename = "user")]
+        struct User {
+            #[facet(xml::attribute)]
+            id: u32,
+        }
+
+        let template = Element::new("div")
+            .with_attr("title", "{greeting} {missing}")
+            .with_text("Hi {name}, see {user} and {missing}")
+            .with_child(
+                Element::new("slot")
+                    .with_attr("name", "footer")
+                    .with_text("fallback"),
+            )
+            .with_child(
+                Element::new("section").with_child(Element::new("slot").with_attr("name", "user")),
+            );
+
+        let slots = Slots::new()
+            .text("greeting", "hello")
+            .text("name", "Ada")
+            .value("user", &User { id: 7 })
+            .unwrap();
+        let rendered = template.fill_slots(&slots);
+
+        assert_eq!(
+            rendered.to_html(),
+            r#"<div title="hello {missing}">Hi Ada, see <user id="7"></user> and {missing}fallback<section><user id="7"></user></section></div>"#
+        );
+        // The template itself is untouched
+        assert_eq!(template.get_attr("title"), Some("{greeting} {missing}"));
+    }
+
     #[test]
     fn parse_simple_xml() {
         let xml = r#"<root><child>hello</child></root>"#;
@@ -358,6 +2124,25 @@ mod tests {
         assert_eq!(item.value, "hello");
     }
 
+    #[test]
+    fn from_element_borrowed_points_into_tree() {
+        #[derive(facet::Facet, Debug, PartialEq)]
+        struct Item<'a> {
+            #[facet(xml::attribute)]
+            id: &'a str,
+            value: std::borrow::Cow<'a, str>,
+        }
+
+        let elem = Element::new("item")
+            .with_attr("id", "123")
+            .with_child(Element::new("value").with_text("hello"));
+
+        let item: Item<'_> = from_element_borrowed(&elem).unwrap();
+        assert_eq!(item.id, "123");
+        assert!(std::ptr::eq(item.id, elem.get_attr("id").unwrap()));
+        assert_eq!(item.value, "hello");
+    }
+
     #[test]
     fn to_element_simple() {
         #[derive(facet::Facet, Debug, PartialEq)]
@@ -560,6 +2345,42 @@ mod tests {
         assert!(result.elements.is_empty());
     }
 
+    /// A flattened Vec<Element> keeps unmatched children, structure and all,
+    /// and writes them back out
+    #[test]
+    fn flattened_vec_element_captures_unknown_children() {
+        #[derive(facet::Facet, Debug, PartialEq)]
+        #[facet(rename = "feed")]
+        struct Feed {
+            title: String,
+            #[facet(flatten, default)]
+            extra: Vec<Element>,
+        }
+
+        let xml = r#"<feed><title>News</title><vendor:meta xmlns:vendor="urn:v" level="2"><note>kept</note></vendor:meta><extra/></feed>"#;
+        let feed: Feed = facet_xml::from_str(xml).unwrap();
+
+        assert_eq!(feed.title, "News");
+        assert_eq!(feed.extra.len(), 2);
+        assert_eq!(feed.extra[0].tag, "meta");
+        assert_eq!(feed.extra[0].namespace.as_deref(), Some("urn:v"));
+        assert_eq!(feed.extra[0].get_attr("level"), Some("2"));
+        let note = feed.extra[0].child_elements().next().unwrap();
+        assert_eq!(note.tag, "note");
+        assert_eq!(note.text_content(), "kept");
+        assert_eq!(feed.extra[1].tag, "extra");
+
+        let written = facet_xml::to_string(&feed).unwrap();
+        assert!(written.contains("<title>News</title>"), "{written}");
+        assert!(written.contains("<note>kept</note>"), "{written}");
+        assert!(!written.contains("<element"), "{written}");
+        let back: Feed = facet_xml::from_str(&written).unwrap();
+        assert_eq!(back, feed);
+
+        let plain: Feed = facet_xml::from_str("<feed><title>News</title></feed>").unwrap();
+        assert!(plain.extra.is_empty());
+    }
+
     #[derive(Debug, Facet)]
     #[facet(proxy = StringRepr)]
     struct ConstantName;
@@ -19,6 +19,9 @@ impl fmt::Display for ElementParseError {
 impl std::error::Error for ElementParseError {}
 
 /// Deserialize from an Element tree into a typed value.
+///
+/// The result owns all its data. Use [`from_element_borrowed`] when the target
+/// type borrows strings and the tree outlives it.
 pub fn from_element<T>(
     element: &Element,
 ) -> Result<T, facet_dom::DomDeserializeError<ElementParseError>>
@@ -30,20 +33,39 @@ where
     de.deserialize()
 }
 
+/// Deserialize from an Element tree into a value that borrows from the tree.
+///
+/// Tags, attribute values and text are handed to the deserializer without
+/// copying, so `&str` and `Cow<str>` fields point into `element`.
+pub fn from_element_borrowed<'a, T>(
+    element: &'a Element,
+) -> Result<T, facet_dom::DomDeserializeError<ElementParseError>>
+where
+    T: facet_core::Facet<'a>,
+{
+    let parser = ElementParser::new(element);
+    let mut de = DomDeserializer::new(parser);
+    de.deserialize()
+}
+
 /// Parser that walks an Element tree and emits DomEvents.
+///
+/// Events borrow their strings from the tree.
 pub struct ElementParser<'a> {
     /// Stack of frames - each frame is an element being processed
     stack: Vec<Frame<'a>>,
     /// Peeked event
-    peeked: Option<DomEvent<'static>>,
+    peeked: Option<DomEvent<'a>>,
     /// Current depth for skip_node
     depth: usize,
+    /// Prefix of the element most recently started
+    prefix: Option<&'a str>,
 }
 
 struct Frame<'a> {
     element: &'a Element,
     state: FrameState,
-    attr_iter: std::collections::hash_map::Iter<'a, String, String>,
+    attr_iter: indexmap::map::Iter<'a, String, String>,
     child_idx: usize,
 }
 
@@ -69,10 +91,11 @@ impl<'a> ElementParser<'a> {
             }],
             peeked: None,
             depth: 0,
+            prefix: None,
         }
     }
 
-    fn read_next(&mut self) -> Result<Option<DomEvent<'static>>, ElementParseError> {
+    fn read_next(&mut self) -> Result<Option<DomEvent<'a>>, ElementParseError> {
         loop {
             let frame = match self.stack.last_mut() {
                 Some(f) => f,
@@ -83,16 +106,18 @@ impl<'a> ElementParser<'a> {
                 FrameState::Start => {
                     self.depth += 1;
                     frame.state = FrameState::Attrs;
+                    let element: &'a Element = frame.element;
+                    self.prefix = element.prefix.as_deref();
                     return Ok(Some(DomEvent::NodeStart {
-                        tag: Cow::Owned(frame.element.tag.clone()),
-                        namespace: None,
+                        tag: Cow::Borrowed(element.tag.as_str()),
+                        namespace: element.namespace.as_deref().map(Cow::Borrowed),
                     }));
                 }
                 FrameState::Attrs => {
                     if let Some((name, value)) = frame.attr_iter.next() {
                         return Ok(Some(DomEvent::Attribute {
-                            name: Cow::Owned(name.clone()),
-                            value: Cow::Owned(value.clone()),
+                            name: Cow::Borrowed(name.as_str()),
+                            value: Cow::Borrowed(value.as_str()),
                             namespace: None,
                         }));
                     }
@@ -103,15 +128,26 @@ impl<'a> ElementParser<'a> {
                     return Ok(Some(DomEvent::ChildrenStart));
                 }
                 FrameState::Children => {
-                    if frame.child_idx < frame.element.children.len() {
-                        let child = &frame.element.children[frame.child_idx];
+                    let element: &'a Element = frame.element;
+                    if frame.child_idx < element.children.len() {
+                        let child = &element.children[frame.child_idx];
                         frame.child_idx += 1;
 
                         match child {
                             Content::Text(t) => {
-                                return Ok(Some(DomEvent::Text(Cow::Owned(t.clone()))));
+                                return Ok(Some(DomEvent::Text(Cow::Borrowed(t))));
+                            }
+                            Content::Comment(c) => {
+                                return Ok(Some(DomEvent::Comment(Cow::Borrowed(c))));
                             }
-                            Content::Element(e) => {
+                            Content::ProcessingInstruction { target, data } => {
+                                return Ok(Some(DomEvent::ProcessingInstruction {
+                                    target: Cow::Borrowed(target),
+                                    data: Cow::Borrowed(data),
+                                }));
+                            }
+                            Content::Element(_) | Content::Shared(_) => {
+                                let e = child.as_element().expect("element content");
                                 // Push new frame for child element
                                 self.stack.push(Frame {
                                     element: e,
@@ -143,17 +179,17 @@ impl<'a> ElementParser<'a> {
     }
 }
 
-impl<'a> DomParser<'static> for ElementParser<'a> {
+impl<'a> DomParser<'a> for ElementParser<'a> {
     type Error = ElementParseError;
 
-    fn next_event(&mut self) -> Result<Option<DomEvent<'static>>, Self::Error> {
+    fn next_event(&mut self) -> Result<Option<DomEvent<'a>>, Self::Error> {
         if let Some(event) = self.peeked.take() {
             return Ok(Some(event));
         }
         self.read_next()
     }
 
-    fn peek_event(&mut self) -> Result<Option<&DomEvent<'static>>, Self::Error> {
+    fn peek_event(&mut self) -> Result<Option<&DomEvent<'a>>, Self::Error> {
         if self.peeked.is_none() {
             self.peeked = self.read_next()?;
         }
@@ -175,6 +211,18 @@ impl<'a> DomParser<'static> for ElementParser<'a> {
     fn format_namespace(&self) -> Option<&'static str> {
         Some("xml")
     }
+
+    fn element_prefix(&self) -> Option<&str> {
+        self.prefix
+    }
+
+    fn keeps_comments(&self) -> bool {
+        true
+    }
+
+    fn keeps_processing_instructions(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug)]
@@ -201,6 +249,11 @@ where
     serializer.finish()
 }
 
+/// Format a scalar value the way [`ElementSerializer`] formats attribute values.
+pub(crate) fn format_scalar(value: facet_reflect::Peek<'_, '_>) -> Option<String> {
+    ElementSerializer::default().format_scalar(value)
+}
+
 /// Serializer that builds an Element tree from DomSerializer callbacks.
 #[derive(Default)]
 pub struct ElementSerializer {
@@ -218,6 +271,8 @@ pub struct ElementSerializer {
     is_tag: bool,
     /// Whether the current field is a doctype field
     is_doctype: bool,
+    /// Prefix given for the next element
+    pending_prefix: Option<String>,
 }
 
 impl ElementSerializer {
@@ -229,10 +284,9 @@ impl ElementSerializer {
         }
 
         // Otherwise, pop the last element from the stack
-        if self.stack.len() == 1 {
-            Ok(self.stack.pop().unwrap())
-        } else {
-            Err(facet_dom::DomSerializeError::Backend(ElementSerializeError))
+        match self.stack.pop() {
+            Some(root) if self.stack.is_empty() => Ok(root),
+            _ => Err(facet_dom::DomSerializeError::Backend(ElementSerializeError)),
         }
     }
 }
@@ -240,8 +294,16 @@ impl ElementSerializer {
 impl DomSerializer for ElementSerializer {
     type Error = ElementSerializeError;
 
-    fn element_start(&mut self, tag: &str, _namespace: Option<&str>) -> Result<(), Self::Error> {
-        self.stack.push(Element::new(tag));
+    fn element_start(&mut self, tag: &str, namespace: Option<&str>) -> Result<(), Self::Error> {
+        let mut element = Element::new(tag);
+        element.namespace = namespace.map(str::to_string);
+        element.prefix = self.pending_prefix.take();
+        self.stack.push(element);
+        Ok(())
+    }
+
+    fn element_prefix(&mut self, prefix: Option<&str>) -> Result<(), Self::Error> {
+        self.pending_prefix = prefix.map(str::to_string);
         Ok(())
     }
 
@@ -289,6 +351,21 @@ impl DomSerializer for ElementSerializer {
         Ok(())
     }
 
+    fn comment(&mut self, content: &str) -> Result<(), Self::Error> {
+        let elem = self.stack.last_mut().ok_or(ElementSerializeError)?;
+        elem.children.push(Content::Comment(content.to_string()));
+        Ok(())
+    }
+
+    fn processing_instruction(&mut self, target: &str, data: &str) -> Result<(), Self::Error> {
+        let elem = self.stack.last_mut().ok_or(ElementSerializeError)?;
+        elem.children.push(Content::ProcessingInstruction {
+            target: target.to_string(),
+            data: data.to_string(),
+        });
+        Ok(())
+    }
+
     fn format_namespace(&self) -> Option<&'static str> {
         Some("xml")
     }
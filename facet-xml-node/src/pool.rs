@@ -0,0 +1,250 @@
+//! Reusing the allocations of parsed Element trees.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+use facet_dom::{DomEvent, DomParser};
+use facet_xml::{DeserializeError, XmlError, XmlParser};
+use indexmap::IndexMap;
+
+use crate::{Content, Element};
+
+/// A pool of the strings, child lists and attribute maps that make up
+/// [`Element`] trees.
+///
+/// [`parse`](Self::parse) builds a tree out of pooled allocations and
+/// returns it in a [`PooledElement`] guard; dropping the guard clears the
+/// tree and puts its allocations back. A service parsing many small
+/// documents can share one pool (it is `Sync`) and stop allocating once the
+/// pool has warmed up.
+///
+/// ```
+/// use facet_xml_node::ElementPool;
+///
+/// let pool = ElementPool::new();
+/// for _ in 0..3 {
+///     let doc = pool.parse(r#"<ping seq="1"><from>a</from></ping>"#).unwrap();
+///     assert_eq!(doc.get_attr("seq"), Some("1"));
+/// }
+/// assert!(pool.retained() > 0);
+/// ```
+pub struct ElementPool {
+    free: Mutex<Free>,
+    max_retained: usize,
+}
+
+#[derive(Default)]
+struct Free {
+    strings: Vec<String>,
+    children: Vec<Vec<Content>>,
+    attrs: Vec<IndexMap<String, String>>,
+}
+
+impl Free {
+    fn len(&self) -> usize {
+        self.strings.len() + self.children.len() + self.attrs.len()
+    }
+}
+
+impl Default for ElementPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ElementPool {
+    /// An empty pool keeping up to 4096 allocations of each kind.
+    pub fn new() -> Self {
+        Self::with_max_retained(4096)
+    }
+
+    /// An empty pool keeping up to `max` allocations of each kind; anything
+    /// returned beyond that is freed.
+    pub fn with_max_retained(max: usize) -> Self {
+        Self {
+            free: Mutex::new(Free::default()),
+            max_retained: max,
+        }
+    }
+
+    /// Number of allocations currently waiting to be reused.
+    pub fn retained(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Parse `input` into a tree built from pooled allocations.
+    ///
+    /// The tree is the same as `facet_xml::from_str::<Element>` would give.
+    pub fn parse(&self, input: &str) -> Result<PooledElement<'_>, DeserializeError<XmlError>> {
+        let mut parser = XmlParser::new(input.as_bytes());
+        // Elements being built; the root is at the bottom
+        let mut stack: Vec<Element> = Vec::new();
+        loop {
+            let event = parser.next_event().map_err(DeserializeError::Parser)?;
+            match event {
+                None => break,
+                Some(DomEvent::NodeStart { tag, namespace }) => {
+                    stack.push(Element {
+                        tag: self.string(&tag),
+                        namespace: namespace.map(|ns| self.string(&ns)),
+                        prefix: parser.element_prefix().map(|prefix| self.string(prefix)),
+                        attrs: self.attrs(),
+                        children: self.children(),
+                    });
+                }
+                Some(DomEvent::Attribute { name, value, .. }) => {
+                    if let Some(element) = stack.last_mut() {
+                        element
+                            .attrs
+                            .insert(self.string(&name), self.string(&value));
+                    }
+                }
+                Some(DomEvent::Text(text)) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.children.push(Content::Text(self.string(&text)));
+                    }
+                }
+                Some(DomEvent::NodeEnd) => {
+                    let element = stack.pop().expect("parser balances elements");
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(Content::Element(element)),
+                        None => {
+                            return Ok(PooledElement {
+                                pool: self,
+                                element: Some(element),
+                            });
+                        }
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        for element in stack {
+            self.recycle(element);
+        }
+        Err(DeserializeError::Parser(XmlError::UnexpectedEof))
+    }
+
+    /// Give the allocations of `element` to the pool.
+    ///
+    /// Trees built elsewhere can be recycled too, to seed the pool.
+    pub fn recycle(&self, element: Element) {
+        let mut free = self.lock();
+        self.recycle_into(&mut free, element);
+    }
+
+    fn recycle_into(&self, free: &mut Free, element: Element) {
+        let Element {
+            tag,
+            namespace,
+            prefix,
+            mut attrs,
+            mut children,
+        } = element;
+        self.keep_string(free, tag);
+        for name in namespace.into_iter().chain(prefix) {
+            self.keep_string(free, name);
+        }
+        for (name, value) in attrs.drain(..) {
+            self.keep_string(free, name);
+            self.keep_string(free, value);
+        }
+        if free.attrs.len() < self.max_retained {
+            free.attrs.push(attrs);
+        }
+        for child in children.drain(..) {
+            match child {
+                Content::Text(text) | Content::Comment(text) => self.keep_string(free, text),
+                Content::ProcessingInstruction { target, data } => {
+                    self.keep_string(free, target);
+                    self.keep_string(free, data);
+                }
+                Content::Element(element) => self.recycle_into(free, element),
+                // Other trees may still hold it
+                Content::Shared(element) => {
+                    if let Ok(element) = Arc::try_unwrap(element) {
+                        self.recycle_into(free, element);
+                    }
+                }
+            }
+        }
+        if free.children.len() < self.max_retained {
+            free.children.push(children);
+        }
+    }
+
+    fn keep_string(&self, free: &mut Free, mut s: String) {
+        if free.strings.len() < self.max_retained {
+            s.clear();
+            free.strings.push(s);
+        }
+    }
+
+    fn string(&self, value: &str) -> String {
+        let mut s = self.lock().strings.pop().unwrap_or_default();
+        s.push_str(value);
+        s
+    }
+
+    fn attrs(&self) -> IndexMap<String, String> {
+        self.lock().attrs.pop().unwrap_or_default()
+    }
+
+    fn children(&self) -> Vec<Content> {
+        self.lock().children.pop().unwrap_or_default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Free> {
+        self.free.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// An [`Element`] tree whose allocations return to its [`ElementPool`] when
+/// dropped.
+pub struct PooledElement<'p> {
+    pool: &'p ElementPool,
+    /// Always `Some` until dropped or detached
+    element: Option<Element>,
+}
+
+impl PooledElement<'_> {
+    /// Take the tree out of the pool's care; its allocations are then freed
+    /// normally.
+    pub fn into_inner(mut self) -> Element {
+        self.element
+            .take()
+            .expect("element is present until dropped")
+    }
+}
+
+impl Deref for PooledElement<'_> {
+    type Target = Element;
+
+    fn deref(&self) -> &Element {
+        self.element
+            .as_ref()
+            .expect("element is present until dropped")
+    }
+}
+
+impl DerefMut for PooledElement<'_> {
+    fn deref_mut(&mut self) -> &mut Element {
+        self.element
+            .as_mut()
+            .expect("element is present until dropped")
+    }
+}
+
+impl std::fmt::Debug for PooledElement<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl Drop for PooledElement<'_> {
+    fn drop(&mut self) {
+        if let Some(element) = self.element.take() {
+            self.pool.recycle(element);
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! XPath-like queries over Element trees.
+
+use std::collections::HashSet;
+
+use crate::{Element, PathError};
+
+impl Element {
+    /// Every element matching `selector`, relative to this element.
+    ///
+    /// A selector is a `/`-separated list of steps, each an element name or
+    /// `*` followed by any number of predicates:
+    ///
+    /// - `[@id]`: the element has the attribute
+    /// - `[@id='3']`: the attribute has this value (either quote works)
+    /// - `[title='Dune']`: a child element has this text
+    /// - `[2]`: the second match within its parent, counting from 1
+    ///
+    /// `//` between steps (or at the start) searches all descendants instead
+    /// of only children. A final `@name` step keeps the elements having
+    /// that attribute; [`select_text`](Self::select_text) then returns its
+    /// value.
+    ///
+    /// ```
+    /// use facet_xml_node::Element;
+    ///
+    /// let library: Element = facet_xml::from_str(
+    ///     r#"<library>
+    ///         <book><author id="3"><name>Le Guin</name></author></book>
+    ///         <book><author id="7"><name>Herbert</name></author></book>
+    ///     </library>"#,
+    /// )
+    /// .unwrap();
+    ///
+    /// let names: Vec<String> = library
+    ///     .select("book/author[@id='3']/name")
+    ///     .unwrap()
+    ///     .map(|name| name.text_content())
+    ///     .collect();
+    /// assert_eq!(names, ["Le Guin"]);
+    /// assert_eq!(library.select("//name").unwrap().count(), 2);
+    /// ```
+    pub fn select(&self, selector: &str) -> Result<Selection<'_>, PathError> {
+        let query = Query::parse(selector)?;
+        Ok(Selection {
+            matches: query.evaluate(self).into_iter(),
+        })
+    }
+
+    /// The first element matching `selector`; see [`select`](Self::select).
+    pub fn select_first(&self, selector: &str) -> Result<Option<&Element>, PathError> {
+        Ok(self.select(selector)?.next())
+    }
+
+    /// The text of the first match of `selector`; see [`select`](Self::select).
+    ///
+    /// That is the value of the attribute when the selector ends in an
+    /// `@name` step, and the element's [`text_content`](Self::text_content)
+    /// otherwise.
+    ///
+    /// ```
+    /// use facet_xml_node::Element;
+    ///
+    /// let book: Element =
+    ///     facet_xml::from_str(r#"<book isbn="0441013597"><title>Dune</title></book>"#).unwrap();
+    /// assert_eq!(book.select_text("title").unwrap().as_deref(), Some("Dune"));
+    /// assert_eq!(book.select_text("@isbn").unwrap().as_deref(), Some("0441013597"));
+    /// assert_eq!(book.select_text("author").unwrap(), None);
+    /// ```
+    pub fn select_text(&self, selector: &str) -> Result<Option<String>, PathError> {
+        let query = Query::parse(selector)?;
+        let first = query.evaluate(self).into_iter().next();
+        Ok(first.map(|element| match query.attribute {
+            Some(name) => element.attrs[name].clone(),
+            None => element.text_content(),
+        }))
+    }
+}
+
+/// The elements matched by [`Element::select`].
+pub struct Selection<'a> {
+    matches: std::vec::IntoIter<&'a Element>,
+}
+
+impl<'a> Iterator for Selection<'a> {
+    type Item = &'a Element;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.matches.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.matches.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Selection<'_> {}
+
+/// A parsed selector.
+struct Query<'s> {
+    steps: Vec<Step<'s>>,
+    /// Attribute named by a final `@name` step
+    attribute: Option<&'s str>,
+}
+
+/// One element step: `name` or `*`, with its predicates.
+struct Step<'s> {
+    /// Whether the step searches all descendants (`//`) rather than children
+    descendants: bool,
+    /// `None` for `*`
+    name: Option<&'s str>,
+    predicates: Vec<Predicate<'s>>,
+}
+
+enum Predicate<'s> {
+    HasAttr(&'s str),
+    AttrEquals(&'s str, &'s str),
+    ChildEquals(&'s str, &'s str),
+    /// 1-based position among the matches within one parent
+    Position(usize),
+}
+
+impl<'s> Query<'s> {
+    fn parse(selector: &'s str) -> Result<Self, PathError> {
+        let invalid = |reason| PathError::InvalidSelector {
+            selector: selector.to_string(),
+            reason,
+        };
+
+        let (mut rest, mut descendants) = match selector.strip_prefix("//") {
+            Some(rest) => (rest, true),
+            None => (selector, false),
+        };
+        let mut steps = Vec::new();
+        let mut attribute = None;
+        loop {
+            let end = segment_end(rest).ok_or_else(|| invalid("unbalanced brackets or quotes"))?;
+            let (segment, tail) = rest.split_at(end);
+            if segment.is_empty() {
+                return Err(invalid("empty path segment"));
+            }
+            if let Some(name) = segment.strip_prefix('@') {
+                if !tail.is_empty() {
+                    return Err(invalid("attribute segment must come last"));
+                }
+                if descendants {
+                    return Err(invalid("attribute segment must follow `/`"));
+                }
+                if name.is_empty() {
+                    return Err(invalid("empty attribute name"));
+                }
+                attribute = Some(name);
+                break;
+            }
+            steps.push(Step::parse(segment, descendants).map_err(invalid)?);
+
+            if tail.is_empty() {
+                break;
+            }
+            (rest, descendants) = match tail.strip_prefix("//") {
+                Some(rest) => (rest, true),
+                None => (&tail[1..], false),
+            };
+        }
+
+        Ok(Self { steps, attribute })
+    }
+
+    fn evaluate<'a>(&self, root: &'a Element) -> Vec<&'a Element> {
+        let mut current = vec![root];
+        for step in &self.steps {
+            let contexts = if step.descendants {
+                descendants_or_self(current)
+            } else {
+                current
+            };
+
+            let mut seen = HashSet::new();
+            let mut next = Vec::new();
+            for parent in contexts {
+                let mut candidates: Vec<&Element> = parent
+                    .child_elements()
+                    .filter(|child| step.name.is_none_or(|name| child.tag == name))
+                    .collect();
+                for predicate in &step.predicates {
+                    candidates = match predicate {
+                        Predicate::Position(n) => {
+                            candidates.get(n - 1).copied().into_iter().collect()
+                        }
+                        _ => candidates
+                            .into_iter()
+                            .filter(|candidate| predicate.matches(candidate))
+                            .collect(),
+                    };
+                }
+                for candidate in candidates {
+                    // The same element can be reached from nested contexts
+                    if seen.insert(std::ptr::from_ref(candidate)) {
+                        next.push(candidate);
+                    }
+                }
+            }
+            current = next;
+        }
+
+        if let Some(name) = self.attribute {
+            current.retain(|element| element.attrs.contains_key(name));
+        }
+        current
+    }
+}
+
+impl<'s> Step<'s> {
+    fn parse(segment: &'s str, descendants: bool) -> Result<Self, &'static str> {
+        let (name, mut rest) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+        if name.is_empty() {
+            return Err("missing element name");
+        }
+
+        let mut predicates = Vec::new();
+        while !rest.is_empty() {
+            let Some(inner) = rest.strip_prefix('[') else {
+                return Err("unexpected text after predicate");
+            };
+            let close = predicate_end(inner).ok_or("unbalanced brackets or quotes")?;
+            predicates.push(Predicate::parse(inner[..close].trim())?);
+            rest = &inner[close + 1..];
+        }
+
+        Ok(Self {
+            descendants,
+            name: (name != "*").then_some(name),
+            predicates,
+        })
+    }
+}
+
+impl<'s> Predicate<'s> {
+    fn parse(predicate: &'s str) -> Result<Self, &'static str> {
+        if !predicate.is_empty() && predicate.bytes().all(|b| b.is_ascii_digit()) {
+            return match predicate.parse() {
+                Ok(0) | Err(_) => Err("positions start at 1"),
+                Ok(n) => Ok(Predicate::Position(n)),
+            };
+        }
+        match predicate.split_once('=') {
+            Some((name, value)) => {
+                let name = name.trim();
+                let value = unquote(value.trim()).ok_or("predicate values must be quoted")?;
+                match name.strip_prefix('@') {
+                    Some("") => Err("empty attribute name"),
+                    Some(attr) => Ok(Predicate::AttrEquals(attr, value)),
+                    None if name.is_empty() => Err("missing element name"),
+                    None => Ok(Predicate::ChildEquals(name, value)),
+                }
+            }
+            None => match predicate.strip_prefix('@') {
+                Some("") => Err("empty attribute name"),
+                Some(attr) => Ok(Predicate::HasAttr(attr)),
+                None => Err("unsupported predicate"),
+            },
+        }
+    }
+
+    fn matches(&self, element: &Element) -> bool {
+        match *self {
+            Predicate::HasAttr(name) => element.attrs.contains_key(name),
+            Predicate::AttrEquals(name, value) => element.get_attr(name) == Some(value),
+            Predicate::ChildEquals(tag, value) => element
+                .child_elements()
+                .any(|child| child.tag == tag && child.text_content() == value),
+            Predicate::Position(_) => true,
+        }
+    }
+}
+
+/// `elements` and all their descendant elements, each once, parents first.
+fn descendants_or_self(elements: Vec<&Element>) -> Vec<&Element> {
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    let mut stack: Vec<&Element> = elements.into_iter().rev().collect();
+    while let Some(element) = stack.pop() {
+        if !seen.insert(std::ptr::from_ref(element)) {
+            continue;
+        }
+        out.push(element);
+        let children: Vec<&Element> = element.child_elements().collect();
+        stack.extend(children.into_iter().rev());
+    }
+    out
+}
+
+/// Length of the first segment of `selector`: up to the first `/` outside
+/// brackets and quotes. `None` if a bracket or quote is left open.
+fn segment_end(selector: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote = None;
+    for (idx, c) in selector.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') => depth = depth.checked_sub(1)?,
+            (None, '/') if depth == 0 => return Some(idx),
+            _ => {}
+        }
+    }
+    (depth == 0 && quote.is_none()).then_some(selector.len())
+}
+
+/// Index of the `]` closing a predicate whose `[` was just stripped.
+fn predicate_end(predicate: &str) -> Option<usize> {
+    let mut quote = None;
+    for (idx, c) in predicate.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ']') => return Some(idx),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `value` without its surrounding single or double quotes.
+fn unquote(value: &str) -> Option<&str> {
+    ['\'', '"'].into_iter().find_map(|q| {
+        value
+            .strip_prefix(q)
+            .and_then(|inner| inner.strip_suffix(q))
+    })
+}
@@ -0,0 +1,204 @@
+//! Slot filling for Element trees used as templates.
+
+use std::collections::HashMap;
+
+use crate::{Content, Element, ElementSerializeError, to_element};
+
+/// Values to substitute into a template, keyed by slot name.
+///
+/// A slot holds a sequence of content nodes: serialized Facet values, raw
+/// elements, or text.
+#[derive(Debug, Clone, Default)]
+pub struct Slots {
+    values: HashMap<String, Vec<Content>>,
+}
+
+impl Slots {
+    /// Create an empty set of slot values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fill slot `name` with `value`, serialized via [`to_element`].
+    pub fn value<T>(
+        mut self,
+        name: impl Into<String>,
+        value: &T,
+    ) -> Result<Self, facet_dom::DomSerializeError<ElementSerializeError>>
+    where
+        T: facet_core::Facet<'static>,
+    {
+        let element = to_element(value)?;
+        self.values
+            .insert(name.into(), vec![Content::Element(element)]);
+        Ok(self)
+    }
+
+    /// Fill slot `name` with an element.
+    pub fn element(mut self, name: impl Into<String>, element: Element) -> Self {
+        self.values
+            .insert(name.into(), vec![Content::Element(element)]);
+        self
+    }
+
+    /// Fill slot `name` with text.
+    pub fn text(mut self, name: impl Into<String>, text: impl Into<String>) -> Self {
+        self.values
+            .insert(name.into(), vec![Content::Text(text.into())]);
+        self
+    }
+
+    /// Fill slot `name` with arbitrary content.
+    pub fn content(mut self, name: impl Into<String>, content: Vec<Content>) -> Self {
+        self.values.insert(name.into(), content);
+        self
+    }
+
+    fn get(&self, name: &str) -> Option<&[Content]> {
+        self.values.get(name).map(Vec::as_slice)
+    }
+}
+
+impl Element {
+    /// Render this element as a template, returning a new tree.
+    ///
+    /// Two kinds of placeholder are recognized:
+    /// - `<slot name="x"/>` elements are replaced by the content of slot `x`.
+    ///   If `x` has no value, the slot's own children are kept as fallback.
+    /// - `{x}` inside text nodes is replaced by the content of slot `x`, and
+    ///   inside attribute values by its text content.
+    ///
+    /// Placeholders naming unknown slots are left untouched.
+    ///
+    /// ```
+    /// use facet_xml_node::{Element, Slots};
+    ///
+    /// let template: Element = facet_xml::from_str(
+    ///     r#"<article class="{kind}"><h1>Hello, {name}!</h1><slot name="body">empty</slot></article>"#,
+    /// )
+    /// .unwrap();
+    /// let slots = Slots::new()
+    ///     .text("kind", "post")
+    ///     .text("name", "world")
+    ///     .element("body", Element::new("p").with_text("content"));
+    ///
+    /// assert_eq!(
+    ///     template.fill_slots(&slots).to_html(),
+    ///     r#"<article class="post"><h1>Hello, world!</h1><p>content</p></article>"#
+    /// );
+    /// ```
+    pub fn fill_slots(&self, slots: &Slots) -> Element {
+        Element {
+            tag: self.tag.clone(),
+            namespace: self.namespace.clone(),
+            prefix: self.prefix.clone(),
+            attrs: self
+                .attrs
+                .iter()
+                .map(|(k, v)| (k.clone(), fill_attribute(v, slots)))
+                .collect(),
+            children: fill_children(&self.children, slots),
+        }
+    }
+}
+
+fn fill_children(children: &[Content], slots: &Slots) -> Vec<Content> {
+    let mut out = Vec::with_capacity(children.len());
+    for child in children {
+        match child.as_element() {
+            Some(e) if e.tag == "slot" => {
+                match e.get_attr("name").and_then(|name| slots.get(name)) {
+                    Some(content) => out.extend_from_slice(content),
+                    None => out.extend(fill_children(&e.children, slots)),
+                }
+            }
+            Some(e) => out.push(Content::Element(e.fill_slots(slots))),
+            None => match child.as_text() {
+                Some(text) => fill_text(text, slots, &mut out),
+                None => out.push(child.clone()),
+            },
+        }
+    }
+    out
+}
+
+/// Split `text` around `{name}` placeholders, splicing in slot content.
+fn fill_text(text: &str, slots: &Slots, out: &mut Vec<Content>) {
+    let mut literal = String::new();
+    let mut rest = text;
+    while let Some((before, name, after)) = next_placeholder(rest) {
+        match slots.get(name) {
+            Some(content) => {
+                literal.push_str(before);
+                // Keep text runs merged; only break the text around elements
+                for c in content {
+                    match c {
+                        Content::Text(t) => literal.push_str(t),
+                        _ => {
+                            if !literal.is_empty() {
+                                out.push(Content::Text(std::mem::take(&mut literal)));
+                            }
+                            out.push(c.clone());
+                        }
+                    }
+                }
+            }
+            None => {
+                literal.push_str(before);
+                literal.push('{');
+                literal.push_str(name);
+                literal.push('}');
+            }
+        }
+        rest = after;
+    }
+    literal.push_str(rest);
+    if !literal.is_empty() {
+        out.push(Content::Text(literal));
+    }
+}
+
+fn fill_attribute(value: &str, slots: &Slots) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some((before, name, after)) = next_placeholder(rest) {
+        out.push_str(before);
+        match slots.get(name) {
+            Some(content) => {
+                for c in content {
+                    match c.as_element() {
+                        Some(e) => out.push_str(&e.text_content()),
+                        None => out.push_str(c.as_text().unwrap_or_default()),
+                    }
+                }
+            }
+            None => {
+                out.push('{');
+                out.push_str(name);
+                out.push('}');
+            }
+        }
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Find the next `{name}` placeholder, returning the text before it, the
+/// name, and the text after it. Names are ASCII alphanumerics, `_` and `-`.
+fn next_placeholder(s: &str) -> Option<(&str, &str, &str)> {
+    let mut search_from = 0;
+    loop {
+        let open = search_from + s[search_from..].find('{')?;
+        let close = open + s[open..].find('}')?;
+        let name = &s[open + 1..close];
+        if !name.is_empty()
+            && name
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+        {
+            return Some((&s[..open], name, &s[close + 1..]));
+        }
+        search_from = open + 1;
+    }
+}
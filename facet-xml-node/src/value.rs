@@ -0,0 +1,290 @@
+//! A dynamic document model with typed leaves.
+
+use std::fmt;
+use std::sync::Arc;
+
+use facet_xml as xml;
+
+use crate::{Content, Element};
+
+/// A text or attribute value, typed by its lexical form.
+///
+/// [`Scalar::parse`] only picks a typed variant when writing the value back
+/// gives the same text, so `"007"`, `"1.50"` or `"NaN"` stay strings and
+/// documents survive a round trip unchanged.
+#[derive(Debug, Clone, PartialEq, facet::Facet)]
+#[repr(u8)]
+pub enum Scalar {
+    /// `true` or `false`.
+    Bool(bool),
+    /// An integer in canonical decimal form.
+    Int(i64),
+    /// A finite floating-point number in canonical form.
+    Float(f64),
+    /// Anything else.
+    Str(String),
+}
+
+impl Scalar {
+    /// Type `text` by its lexical form.
+    ///
+    /// ```
+    /// use facet_xml_node::Scalar;
+    ///
+    /// assert_eq!(Scalar::parse("true"), Scalar::Bool(true));
+    /// assert_eq!(Scalar::parse("-12"), Scalar::Int(-12));
+    /// assert_eq!(Scalar::parse("2.5"), Scalar::Float(2.5));
+    /// assert_eq!(Scalar::parse("007"), Scalar::Str("007".into()));
+    /// ```
+    pub fn parse(text: &str) -> Self {
+        match text {
+            "true" => return Scalar::Bool(true),
+            "false" => return Scalar::Bool(false),
+            _ => {}
+        }
+        if let Ok(n) = text.parse::<i64>()
+            && n.to_string() == text
+        {
+            return Scalar::Int(n);
+        }
+        if let Ok(f) = text.parse::<f64>()
+            && f.is_finite()
+            && f.to_string() == text
+        {
+            return Scalar::Float(f);
+        }
+        Scalar::Str(text.to_string())
+    }
+
+    /// The value if this is a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Scalar::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The value if this is an integer.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Scalar::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The value as a float, if this is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Scalar::Int(n) => Some(*n as f64),
+            Scalar::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// The value if this is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Scalar::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scalar::Bool(b) => write!(f, "{b}"),
+            Scalar::Int(n) => write!(f, "{n}"),
+            Scalar::Float(x) => write!(f, "{x}"),
+            Scalar::Str(s) => f.write_str(s),
+        }
+    }
+}
+
+impl From<&str> for Scalar {
+    fn from(text: &str) -> Self {
+        Scalar::parse(text)
+    }
+}
+
+/// A node of a document of unknown schema, with numbers and booleans
+/// already parsed.
+///
+/// Sits between [`Element`], which keeps every value as a string, and
+/// typed structs, which need the schema up front. An element's attributes
+/// are [`Attr`](XmlValue::Attr) nodes at the start of its children, in name
+/// order. Reads and writes through facet-xml like an [`Element`]:
+///
+/// ```
+/// use facet_xml_node::{Scalar, XmlValue};
+///
+/// let doc: XmlValue =
+///     facet_xml::from_str(r#"<stock sym="ACME" open="true"><price>12.5</price></stock>"#)
+///         .unwrap();
+/// assert_eq!(doc.attr("open"), Some(&Scalar::Bool(true)));
+/// let price = doc.child("price").and_then(XmlValue::text).unwrap();
+/// assert_eq!(price.as_f64(), Some(12.5));
+/// ```
+#[derive(Debug, Clone, PartialEq, facet::Facet)]
+#[facet(xml::proxy = Element)]
+#[repr(u8)]
+pub enum XmlValue {
+    /// An element with its attributes and content.
+    Element {
+        /// The tag name.
+        tag: String,
+        /// Attributes first, then text and child elements in document order.
+        #[facet(recursive_type)]
+        children: Vec<XmlValue>,
+    },
+    /// Text content.
+    Text(Scalar),
+    /// An attribute of the enclosing element.
+    Attr {
+        /// The attribute name.
+        name: String,
+        /// The attribute value.
+        value: Scalar,
+    },
+}
+
+impl XmlValue {
+    /// An element with no attributes or content.
+    pub fn element(tag: impl Into<String>) -> Self {
+        XmlValue::Element {
+            tag: tag.into(),
+            children: Vec::new(),
+        }
+    }
+
+    /// The tag name, if this is an element.
+    pub fn tag(&self) -> Option<&str> {
+        match self {
+            XmlValue::Element { tag, .. } => Some(tag),
+            _ => None,
+        }
+    }
+
+    /// The value of a text or attribute node.
+    pub fn as_scalar(&self) -> Option<&Scalar> {
+        match self {
+            XmlValue::Text(value) | XmlValue::Attr { value, .. } => Some(value),
+            XmlValue::Element { .. } => None,
+        }
+    }
+
+    /// An element's attributes as `(name, value)` pairs.
+    pub fn attrs(&self) -> impl Iterator<Item = (&str, &Scalar)> {
+        self.nodes().iter().filter_map(|node| match node {
+            XmlValue::Attr { name, value } => Some((name.as_str(), value)),
+            _ => None,
+        })
+    }
+
+    /// The value of an element's attribute.
+    pub fn attr(&self, name: &str) -> Option<&Scalar> {
+        self.attrs()
+            .find(|(n, _)| *n == name)
+            .map(|(_, value)| value)
+    }
+
+    /// An element's text and child elements, without its attributes.
+    pub fn children(&self) -> impl Iterator<Item = &XmlValue> {
+        self.nodes()
+            .iter()
+            .filter(|node| !matches!(node, XmlValue::Attr { .. }))
+    }
+
+    /// An element's first child element named `tag`.
+    pub fn child(&self, tag: &str) -> Option<&XmlValue> {
+        self.children().find(|child| child.tag() == Some(tag))
+    }
+
+    /// The text of an element with a single text child, or of a text node.
+    pub fn text(&self) -> Option<&Scalar> {
+        match self {
+            XmlValue::Text(value) => Some(value),
+            XmlValue::Element { .. } => {
+                let mut children = self.children();
+                match (children.next(), children.next()) {
+                    (Some(XmlValue::Text(value)), None) => Some(value),
+                    _ => None,
+                }
+            }
+            XmlValue::Attr { .. } => None,
+        }
+    }
+
+    fn nodes(&self) -> &[XmlValue] {
+        match self {
+            XmlValue::Element { children, .. } => children,
+            _ => &[],
+        }
+    }
+}
+
+impl From<Element> for XmlValue {
+    fn from(element: Element) -> Self {
+        let mut attrs: Vec<(String, String)> = element.attrs.into_iter().collect();
+        attrs.sort();
+        let mut children: Vec<XmlValue> = attrs
+            .into_iter()
+            .map(|(name, value)| XmlValue::Attr {
+                name,
+                value: Scalar::parse(&value),
+            })
+            .collect();
+        children.extend(
+            element
+                .children
+                .into_iter()
+                .filter_map(|child| match child {
+                    Content::Text(text) => Some(XmlValue::Text(Scalar::parse(&text))),
+                    Content::Element(element) => Some(XmlValue::from(element)),
+                    Content::Shared(element) => Some(XmlValue::from(Arc::unwrap_or_clone(element))),
+                    Content::Comment(_) | Content::ProcessingInstruction { .. } => None,
+                }),
+        );
+        XmlValue::Element {
+            tag: element.tag,
+            children,
+        }
+    }
+}
+
+/// A text or attribute [`XmlValue`] was given where an element is needed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotAnElement;
+
+impl fmt::Display for NotAnElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("only an element value can be written as an element")
+    }
+}
+
+impl std::error::Error for NotAnElement {}
+
+impl TryFrom<&XmlValue> for Element {
+    type Error = NotAnElement;
+
+    fn try_from(value: &XmlValue) -> Result<Self, Self::Error> {
+        let XmlValue::Element { tag, children } = value else {
+            return Err(NotAnElement);
+        };
+        let mut element = Element::new(tag.clone());
+        for child in children {
+            match child {
+                XmlValue::Attr { name, value } => {
+                    element.attrs.insert(name.clone(), value.to_string());
+                }
+                XmlValue::Text(value) => element.children.push(Content::Text(value.to_string())),
+                XmlValue::Element { .. } => {
+                    element
+                        .children
+                        .push(Content::Element(Element::try_from(child)?));
+                }
+            }
+        }
+        Ok(element)
+    }
+}
@@ -0,0 +1,80 @@
+//! Deserializing many elements of the same struct types, where field lookup
+//! (and building the lookup tables) is a large part of the work.
+
+use divan::{Bencher, black_box};
+use facet::Facet;
+use facet_xml as xml;
+
+fn main() {
+    divan::main();
+}
+
+#[derive(Facet)]
+struct Catalog {
+    #[facet(xml::elements, rename = "product")]
+    products: Vec<Product>,
+}
+
+#[derive(Facet)]
+struct Product {
+    #[facet(xml::attribute)]
+    sku: String,
+    #[facet(xml::attribute)]
+    in_stock: bool,
+    product_name: String,
+    short_description: String,
+    unit_price: f64,
+    weight_grams: u32,
+    manufacturer_name: String,
+    country_of_origin: String,
+    #[facet(xml::elements, rename = "tag")]
+    tags: Vec<String>,
+    dimensions: Dimensions,
+}
+
+#[derive(Facet)]
+struct Dimensions {
+    #[facet(xml::attribute)]
+    width_mm: u32,
+    #[facet(xml::attribute)]
+    height_mm: u32,
+    #[facet(xml::attribute)]
+    depth_mm: u32,
+}
+
+fn catalog(products: usize) -> String {
+    let mut xml = String::from("<catalog>");
+    for i in 0..products {
+        xml.push_str(&format!(
+            r#"<product sku="SKU-{i}" inStock="true">
+                <productName>Product {i}</productName>
+                <shortDescription>A product</shortDescription>
+                <unitPrice>{i}.99</unitPrice>
+                <weightGrams>{i}</weightGrams>
+                <manufacturerName>ACME</manufacturerName>
+                <countryOfOrigin>NL</countryOfOrigin>
+                <tag>new</tag><tag>sale</tag>
+                <dimensions widthMm="10" heightMm="20" depthMm="30"/>
+            </product>"#
+        ));
+    }
+    xml.push_str("</catalog>");
+    xml
+}
+
+#[divan::bench(args = [1, 100, 10_000])]
+fn many_elements(bencher: Bencher, products: usize) {
+    let xml = catalog(products);
+    bencher.bench(|| black_box(xml::from_str::<Catalog>(black_box(&xml)).unwrap()));
+}
+
+/// Many small documents, each needing the field maps of every type.
+#[divan::bench]
+fn many_documents(bencher: Bencher) {
+    let docs: Vec<String> = (0..100).map(|_| catalog(1)).collect();
+    bencher.bench(|| {
+        for doc in &docs {
+            black_box(xml::from_str::<Catalog>(black_box(doc)).unwrap());
+        }
+    });
+}
@@ -0,0 +1,171 @@
+//! Cross-field assertions declared with `#[facet(xml::assert = check_fn)]`
+//! and field validations declared with `#[facet(xml::validate = check_fn)]`.
+
+extern crate alloc;
+
+use alloc::{format, string::String};
+
+use facet_core::{Def, Facet, PtrConst};
+use facet_reflect::Peek;
+
+use crate::{DeserializeError, XmlError};
+
+/// Type-erased form of an `xml::assert` function, as stored in the attribute.
+///
+/// User code writes a `fn(&Self) -> Result<(), String>`; the attribute macro
+/// wraps it into this signature.
+pub type AssertFn = unsafe fn(PtrConst) -> Result<(), String>;
+
+/// Type-erased form of an `xml::validate` function, as stored in the
+/// attribute.
+///
+/// User code writes a `fn(&FieldType) -> Result<(), String>`; the attribute
+/// macro wraps it into this signature.
+pub type ValidateFn = unsafe fn(PtrConst) -> Result<(), String>;
+
+/// Run every `xml::assert` and `xml::validate` in a freshly deserialized
+/// value.
+///
+/// Nested values are checked before the fields and structs containing them,
+/// so the innermost failing rule is the one reported.
+pub(crate) fn check<'facet, T>(value: &T) -> Result<(), DeserializeError<XmlError>>
+where
+    T: Facet<'facet>,
+{
+    let mut path = String::from(facet_dom::naming::shape_element_name(T::SHAPE));
+    walk(Peek::new(value), &mut path)
+}
+
+fn walk(value: Peek<'_, '_>, path: &mut String) -> Result<(), DeserializeError<XmlError>> {
+    if let Def::Scalar = value.shape().def {
+        return Ok(());
+    }
+
+    if let Ok(opt) = value.into_option() {
+        if let Some(inner) = opt.value() {
+            walk(inner, path)?;
+        }
+        return Ok(());
+    }
+
+    if let Ok(ptr) = value.into_pointer() {
+        if let Some(inner) = ptr.borrow_inner() {
+            walk(inner, path)?;
+        }
+        return Ok(());
+    }
+
+    if let Def::List(_) | Def::Array(_) | Def::Slice(_) = value.shape().def
+        && let Ok(list) = value.into_list_like()
+    {
+        for (index, item) in list.iter().enumerate() {
+            let len = path.len();
+            path.push_str(&format!("[{index}]"));
+            walk(item, path)?;
+            path.truncate(len);
+        }
+        return Ok(());
+    }
+
+    if let Ok(map) = value.into_map() {
+        for (key, item) in map.iter() {
+            let len = path.len();
+            path.push_str(&format!("/{key}"));
+            walk(item, path)?;
+            path.truncate(len);
+        }
+        return Ok(());
+    }
+
+    if let Ok(set) = value.into_set() {
+        for item in set.iter() {
+            walk(item, path)?;
+        }
+        return Ok(());
+    }
+
+    if let Ok(struct_) = value.into_struct() {
+        for (field, field_value) in struct_.fields() {
+            walk_field(field, field_value, path)?;
+        }
+        return run_assertions(value, path);
+    }
+
+    if let Ok(enum_) = value.into_enum() {
+        for (field, field_value) in enum_.fields() {
+            walk_field(field, field_value, path)?;
+        }
+        return run_assertions(value, path);
+    }
+
+    Ok(())
+}
+
+fn walk_field(
+    field: facet_core::Field,
+    value: Peek<'_, '_>,
+    path: &mut String,
+) -> Result<(), DeserializeError<XmlError>> {
+    let len = path.len();
+    // Flattened fields have no element of their own
+    if !field.is_flattened() {
+        path.push('/');
+        if field.is_attribute() {
+            path.push('@');
+        }
+        path.push_str(field.effective_name());
+    }
+    walk(value, path)?;
+    run_validations(field, value, path)?;
+    path.truncate(len);
+    Ok(())
+}
+
+fn run_validations(
+    field: facet_core::Field,
+    value: Peek<'_, '_>,
+    path: &str,
+) -> Result<(), DeserializeError<XmlError>> {
+    let validations = field
+        .attributes
+        .iter()
+        .filter(|attr| attr.ns == Some("xml") && attr.key == "validate")
+        .filter_map(|attr| attr.get_as::<ValidateFn>().copied());
+
+    for validate in validations {
+        // SAFETY: the attribute macro generated `validate` for this field's
+        // type, and `value` points at the field's initialized value.
+        #[allow(unsafe_code)]
+        let outcome = unsafe { validate(value.data()) };
+        if let Err(message) = outcome {
+            return Err(DeserializeError::Validation {
+                path: path.into(),
+                message,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn run_assertions(value: Peek<'_, '_>, path: &str) -> Result<(), DeserializeError<XmlError>> {
+    let asserts = value
+        .shape()
+        .attributes
+        .iter()
+        .filter(|attr| attr.ns == Some("xml") && attr.key == "assert")
+        .filter_map(|attr| attr.get_as::<AssertFn>().copied());
+
+    for assert in asserts {
+        // SAFETY: the attribute macro generated `assert` for this very shape,
+        // and `value` points at a fully initialized value of it.
+        #[allow(unsafe_code)]
+        let outcome = unsafe { assert(value.data()) };
+        if let Err(message) = outcome {
+            return Err(DeserializeError::AssertionFailed {
+                path: path.into(),
+                message,
+            });
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Deserialization from an asynchronous reader.
+//!
+//! The deserializer pulls events synchronously, so it runs on tokio's
+//! blocking pool while the calling task reads the input and hands it over
+//! in chunks. Only a few chunks are in flight at a time: the input is never
+//! buffered whole.
+
+use std::io;
+
+use facet_core::Facet;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::mpsc;
+
+use crate::{DeserializeError, DeserializeOptions, XmlError, from_reader_with_options};
+
+/// Size of the chunks read from the async reader.
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Chunks read ahead of the deserializer before reading waits for it.
+const CHUNKS_IN_FLIGHT: usize = 4;
+
+/// Deserialize a value from an asynchronous XML reader.
+///
+/// The document is parsed as it arrives, as with
+/// [`from_reader`](crate::from_reader), without blocking the calling task:
+/// parsing runs on tokio's blocking pool, so this needs a tokio runtime.
+/// For a body that is a `Stream` of `Bytes`, such as a hyper response,
+/// wrap it in `tokio_util::io::StreamReader`.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+///
+/// #[derive(Facet, Debug, PartialEq)]
+/// struct Person {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let body: &[u8] = b"<person><name>Alice</name><age>30</age></person>";
+/// let person: Person = facet_xml::from_async_reader(body).await.unwrap();
+/// assert_eq!(person.name, "Alice");
+/// assert_eq!(person.age, 30);
+/// # });
+/// ```
+pub async fn from_async_reader<T, R>(reader: R) -> Result<T, DeserializeError<XmlError>>
+where
+    T: Facet<'static> + Send + 'static,
+    R: AsyncRead + Unpin,
+{
+    from_async_reader_with_options(reader, &DeserializeOptions::default()).await
+}
+
+/// Deserialize a value from an asynchronous XML reader with custom options.
+///
+/// See [`from_async_reader`].
+pub async fn from_async_reader_with_options<T, R>(
+    mut reader: R,
+    options: &DeserializeOptions,
+) -> Result<T, DeserializeError<XmlError>>
+where
+    T: Facet<'static> + Send + 'static,
+    R: AsyncRead + Unpin,
+{
+    let (tx, rx) = mpsc::channel(CHUNKS_IN_FLIGHT);
+    let options = options.clone();
+    let parse = tokio::task::spawn_blocking(move || {
+        from_reader_with_options(ChannelReader::new(rx), &options)
+    });
+
+    loop {
+        let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+        let chunk = match reader.read_buf(&mut chunk).await {
+            Ok(0) => break,
+            Ok(_) => Ok(chunk),
+            Err(e) => Err(e),
+        };
+        let failed = chunk.is_err();
+        // A closed channel means the deserializer is done with the input,
+        // e.g. because it hit an error
+        if tx.send(chunk).await.is_err() || failed {
+            break;
+        }
+    }
+    drop(tx);
+
+    match parse.await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(DeserializeError::Parser(XmlError::Cancelled)),
+    }
+}
+
+/// Reads the chunks sent by [`from_async_reader_with_options`], blocking
+/// until each one arrives.
+struct ChannelReader {
+    rx: mpsc::Receiver<io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl ChannelReader {
+    fn new(rx: mpsc::Receiver<io::Result<Vec<u8>>>) -> Self {
+        Self {
+            rx,
+            chunk: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl io::Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.chunk.len() {
+            match self.rx.blocking_recv() {
+                Some(chunk) => {
+                    self.chunk = chunk?;
+                    self.pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,250 @@
+//! Self-checking of serializer output, enabled by [`SerializeOptions::audit`].
+//!
+//! [`SerializeOptions::audit`]: crate::SerializeOptions::audit
+
+extern crate alloc;
+
+use alloc::{borrow::Cow, format, string::String, vec::Vec};
+
+use facet_dom::{DomEvent, DomParser};
+
+use crate::XmlParser;
+use crate::chars::{is_xml_char, is_xml_name};
+use crate::serializer::XmlSerializeError;
+
+/// A logical piece of the document, as the serializer meant to write it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AuditEvent {
+    Start(String),
+    Attribute(String, String),
+    Text(String),
+    End,
+}
+
+/// Everything the serializer emitted, to be compared against a re-parse of
+/// the bytes it produced.
+#[derive(Debug, Default)]
+pub(crate) struct AuditLog {
+    events: Vec<AuditEvent>,
+}
+
+impl AuditLog {
+    pub(crate) fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    pub(crate) fn start(&mut self, name: &str) -> Result<(), XmlSerializeError> {
+        check_name("element", name)?;
+        self.events.push(AuditEvent::Start(name.into()));
+        Ok(())
+    }
+
+    pub(crate) fn attribute(&mut self, name: &str, value: &str) -> Result<(), XmlSerializeError> {
+        check_name("attribute", name)?;
+        check_chars(value, || format!("attribute {name:?}"))?;
+        self.events
+            .push(AuditEvent::Attribute(name.into(), value.into()));
+        Ok(())
+    }
+
+    pub(crate) fn text(&mut self, text: &str) -> Result<(), XmlSerializeError> {
+        let element = self.current_element();
+        check_chars(text, || format!("text of <{element}>"))?;
+        self.events.push(AuditEvent::Text(text.into()));
+        Ok(())
+    }
+
+    pub(crate) fn end(&mut self) {
+        self.events.push(AuditEvent::End);
+    }
+
+    /// Re-parse `output` and check that it describes exactly what was logged.
+    ///
+    /// Whitespace-only text is ignored and text is compared trimmed, so
+    /// pretty-printing doesn't count as a difference. With `compare_values`
+    /// off (entity-preserving output), only the element and attribute
+    /// structure is compared.
+    pub(crate) fn verify(
+        &self,
+        output: &[u8],
+        compare_values: bool,
+    ) -> Result<(), XmlSerializeError> {
+        let mut parser = XmlParser::new(output);
+        let mut parsed = Vec::new();
+        loop {
+            let event = parser.next_event().map_err(|e| XmlSerializeError {
+                msg: Cow::Owned(format!("audit: output is not well-formed XML: {e}")),
+            })?;
+            match event {
+                None => break,
+                Some(DomEvent::NodeStart { tag, .. }) => {
+                    parsed.push(AuditEvent::Start(tag.into_owned()));
+                }
+                Some(DomEvent::Attribute { name, value, .. }) => {
+                    parsed.push(AuditEvent::Attribute(name.into_owned(), value.into_owned()));
+                }
+                Some(DomEvent::Text(text)) => parsed.push(AuditEvent::Text(text.into_owned())),
+                Some(DomEvent::NodeEnd) => parsed.push(AuditEvent::End),
+                Some(_) => {}
+            }
+        }
+
+        let expected = normalize(&self.events, compare_values);
+        let actual = normalize(&parsed, compare_values);
+        if let Some(index) = expected
+            .iter()
+            .zip(&actual)
+            .position(|(e, a)| e != a)
+            .or_else(|| {
+                (expected.len() != actual.len()).then_some(expected.len().min(actual.len()))
+            })
+        {
+            return Err(XmlSerializeError {
+                msg: Cow::Owned(format!(
+                    "audit: output does not round-trip: wrote {:?}, re-parsed as {:?}",
+                    expected.get(index),
+                    actual.get(index)
+                )),
+            });
+        }
+        Ok(())
+    }
+
+    fn current_element(&self) -> &str {
+        let mut depth = 0usize;
+        for event in self.events.iter().rev() {
+            match event {
+                AuditEvent::End => depth += 1,
+                AuditEvent::Start(name) if depth == 0 => return name,
+                AuditEvent::Start(_) => depth -= 1,
+                _ => {}
+            }
+        }
+        ""
+    }
+}
+
+/// Merge adjacent text, drop whitespace-only text, trim the rest, and sort
+/// each element's attributes (attribute order isn't significant).
+fn normalize(events: &[AuditEvent], compare_values: bool) -> Vec<AuditEvent> {
+    let mut out: Vec<AuditEvent> = Vec::with_capacity(events.len());
+    let mut text = String::new();
+    let mut attributes_from = None;
+
+    for event in events {
+        match event {
+            AuditEvent::Text(t) => {
+                text.push_str(t);
+                continue;
+            }
+            AuditEvent::Attribute(name, value) => {
+                let value = if compare_values {
+                    value.clone()
+                } else {
+                    String::new()
+                };
+                out.push(AuditEvent::Attribute(name.clone(), value));
+                continue;
+            }
+            _ => {}
+        }
+
+        if let Some(from) = attributes_from.take() {
+            out[from..].sort_by(|a, b| match (a, b) {
+                (AuditEvent::Attribute(a, _), AuditEvent::Attribute(b, _)) => a.cmp(b),
+                _ => core::cmp::Ordering::Equal,
+            });
+        }
+        flush_text(&mut text, &mut out, compare_values);
+        out.push(event.clone());
+        if matches!(event, AuditEvent::Start(_)) {
+            attributes_from = Some(out.len());
+        }
+    }
+    flush_text(&mut text, &mut out, compare_values);
+    out
+}
+
+fn flush_text(text: &mut String, out: &mut Vec<AuditEvent>, compare_values: bool) {
+    let trimmed = text.trim();
+    if !trimmed.is_empty() {
+        let value = if compare_values {
+            trimmed.into()
+        } else {
+            String::new()
+        };
+        out.push(AuditEvent::Text(value));
+    }
+    text.clear();
+}
+
+/// Check that `name` is a valid XML name.
+fn check_name(kind: &str, name: &str) -> Result<(), XmlSerializeError> {
+    if is_xml_name(name) {
+        Ok(())
+    } else {
+        Err(XmlSerializeError {
+            msg: Cow::Owned(format!("audit: {name:?} is not a valid XML {kind} name")),
+        })
+    }
+}
+
+/// Check that `s` only contains characters allowed in XML 1.0 documents.
+fn check_chars(s: &str, context: impl FnOnce() -> String) -> Result<(), XmlSerializeError> {
+    match s.chars().find(|&c| !is_xml_char(c)) {
+        None => Ok(()),
+        Some(c) => Err(XmlSerializeError {
+            msg: Cow::Owned(format!(
+                "audit: character U+{:04X} in {} can't be represented in XML 1.0",
+                c as u32,
+                context()
+            )),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert!(check_name("element", "item").is_ok());
+        assert!(check_name("element", "x-1.a").is_ok());
+        assert!(check_name("element", "ns:item").is_ok());
+        assert!(check_name("element", "").is_err());
+        assert!(check_name("element", "1st").is_err());
+        assert!(check_name("element", "a b").is_err());
+        assert!(check_name("attribute", "a\"b").is_err());
+    }
+
+    #[test]
+    fn control_chars() {
+        assert!(check_chars("tab\tand newline\n", String::new).is_ok());
+        let err = check_chars("bell\u{7}", || "text of <a>".into()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "audit: character U+0007 in text of <a> can't be represented in XML 1.0"
+        );
+    }
+
+    #[test]
+    fn normalization_ignores_layout_and_attribute_order() {
+        let written = [
+            AuditEvent::Start("a".into()),
+            AuditEvent::Attribute("y".into(), "2".into()),
+            AuditEvent::Attribute("x".into(), "1".into()),
+            AuditEvent::Text("hi".into()),
+            AuditEvent::End,
+        ];
+        let parsed = [
+            AuditEvent::Start("a".into()),
+            AuditEvent::Attribute("x".into(), "1".into()),
+            AuditEvent::Attribute("y".into(), "2".into()),
+            AuditEvent::Text("\n  ".into()),
+            AuditEvent::Text("hi\n".into()),
+            AuditEvent::End,
+        ];
+        assert_eq!(normalize(&written, true), normalize(&parsed, true));
+    }
+}
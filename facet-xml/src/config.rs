@@ -0,0 +1,1140 @@
+//! Deserialization options and a combined serialize/deserialize profile.
+
+extern crate alloc;
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::collections::HashMap;
+use std::time::Instant;
+
+use facet_core::{Facet, Shape};
+use facet_dom::{DomDeserializer, DomParser, DuplicatePolicy};
+
+use crate::serializer::{SerializeOptions, XmlSerializeError};
+use crate::{
+    DeserializeError, ParseMetrics, Progress, ProgressInterval, SerializeError, XmlError, XmlParser,
+};
+
+/// Callback receiving the [`ParseMetrics`] of each parsed document.
+pub type MetricsCallback = Arc<dyn Fn(&ParseMetrics) + Send + Sync>;
+
+/// Callback receiving periodic [`Progress`] reports while a document is parsed.
+pub type ProgressCallback = Arc<dyn Fn(&Progress<'_>) + Send + Sync>;
+
+/// Callback receiving the `xml:*` attributes removed by
+/// [`ReservedAttributes::Report`].
+pub type ReservedAttributeCallback = Arc<dyn Fn(&ReservedAttribute<'_>) + Send + Sync>;
+
+/// Callback receiving each [`FieldValue`] before it is stored; returning
+/// `Some` stores the returned text instead.
+pub type ValueInterceptor = Arc<dyn Fn(&FieldValue<'_>) -> Option<String> + Send + Sync>;
+
+/// A value matched to a field, about to be parsed into it.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldValue<'a> {
+    /// Location of the value, e.g. `order/card/number` for an element's text
+    /// or `order/card/@cvv` for an attribute.
+    pub path: &'a str,
+    /// The raw text, entities already resolved.
+    pub text: &'a str,
+    /// The type the text is about to be parsed into.
+    pub shape: &'static Shape,
+}
+
+/// State consulted while deserializing fields marked
+/// `#[facet(xml::with_context)]`, passed to [`from_str_with_context`].
+///
+/// Lets references be resolved as the document is read, rather than in a
+/// second pass over the result: ID/IDREF lookups, interning pools, unit
+/// conversions depending on earlier values, and so on. Closures taking a
+/// [`FieldValue`] implement it.
+pub trait DeserializeContext {
+    /// Called with the value of each `xml::with_context` field, in document
+    /// order, before it is parsed; returning `Some` parses the returned
+    /// text instead.
+    fn resolve(&mut self, value: &FieldValue<'_>) -> Option<String>;
+}
+
+impl<F> DeserializeContext for F
+where
+    F: FnMut(&FieldValue<'_>) -> Option<String>,
+{
+    fn resolve(&mut self, value: &FieldValue<'_>) -> Option<String> {
+        self(value)
+    }
+}
+
+/// Callback receiving each [`SkippedItem`] left out of a collection.
+pub type SkippedItemCallback = Arc<dyn Fn(&SkippedItem<'_>) + Send + Sync>;
+
+/// A collection item marked `xml::skip_on_error` that failed to deserialize
+/// and was left out.
+#[derive(Debug, Clone, Copy)]
+pub struct SkippedItem<'a> {
+    /// Location of the item's element, e.g. `feed/record`.
+    pub path: &'a str,
+    /// Why the item failed.
+    pub error: &'a dyn core::error::Error,
+}
+
+/// Callback returning the replacement text of an [`ExternalEntity`], or
+/// `None` to refuse it.
+pub type ExternalEntityResolver = Arc<dyn Fn(&ExternalEntity<'_>) -> Option<String> + Send + Sync>;
+
+/// An external entity declared in a document's internal DTD subset, e.g.
+/// `<!ENTITY legal SYSTEM "legal.txt">`, referenced from the document.
+#[derive(Debug, Clone, Copy)]
+pub struct ExternalEntity<'a> {
+    /// Name of the entity.
+    pub name: &'a str,
+    /// The public identifier, for `PUBLIC` declarations.
+    pub public_id: Option<&'a str>,
+    /// The system identifier, usually a URI.
+    pub system_id: &'a str,
+}
+
+/// Bounds on expanding the entities a document declares, so that documents
+/// like "billion laughs" fail instead of exhausting memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntityLimits {
+    /// How deeply entity references may nest inside replacement text.
+    ///
+    /// Default: `8`.
+    pub max_depth: usize,
+    /// Total bytes all expansions in a document may produce.
+    ///
+    /// Default: 1 MiB.
+    pub max_expanded_bytes: usize,
+}
+
+impl Default for EntityLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 8,
+            max_expanded_bytes: 1 << 20,
+        }
+    }
+}
+
+/// An `xml:*` attribute (`xml:id`, `xml:lang`, `xml:space`, `xml:base`)
+/// found while parsing.
+#[derive(Debug, Clone, Copy)]
+pub struct ReservedAttribute<'a> {
+    /// Local names of the element carrying the attribute and its ancestors,
+    /// outermost first.
+    pub path: &'a [String],
+    /// Local name of the attribute, e.g. `lang` for `xml:lang`.
+    pub name: &'a str,
+    /// The attribute value.
+    pub value: &'a str,
+}
+
+/// How attributes in the reserved `xml:` namespace are deserialized.
+#[derive(Clone, Default)]
+pub enum ReservedAttributes {
+    /// Like any other attribute: they bind to a field by local name (`lang`),
+    /// fill catch-all attribute fields and maps, and are unknown attributes
+    /// under `deny_unknown_fields`.
+    #[default]
+    Generic,
+    /// They only bind to fields declared in the XML namespace, e.g.
+    /// `#[facet(xml::attribute, xml::ns = "http://www.w3.org/XML/1998/namespace")]`
+    /// (see [`XML_NAMESPACE`](crate::XML_NAMESPACE)). Unclaimed ones are
+    /// dropped.
+    Fields,
+    /// They are dropped.
+    Ignore,
+    /// They are dropped and passed to the callback instead.
+    Report(ReservedAttributeCallback),
+}
+
+impl core::fmt::Debug for ReservedAttributes {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ReservedAttributes::Generic => f.write_str("Generic"),
+            ReservedAttributes::Fields => f.write_str("Fields"),
+            ReservedAttributes::Ignore => f.write_str("Ignore"),
+            ReservedAttributes::Report(_) => f.write_str("Report(...)"),
+        }
+    }
+}
+
+/// A flag that aborts in-progress parsing when set.
+///
+/// Clones share the same flag, so one clone can be handed to the parse and
+/// another kept to cancel it from elsewhere (e.g. a request timeout handler).
+/// Parsing then fails with [`XmlError::Cancelled`] at the next event.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel every parse using this token (or a clone of it).
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`cancel`](Self::cancel) has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// How whitespace in text content is handled, outside elements marked
+/// `xml:space="preserve"` (where it is always kept as written).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Whitespace {
+    /// Text is trimmed at both ends, and whitespace-only text is dropped.
+    ///
+    /// Suits data-oriented documents, where whitespace between elements is
+    /// indentation.
+    #[default]
+    Trim,
+    /// Text is kept exactly as written, whitespace-only text included.
+    ///
+    /// Suits document-oriented formats (DocBook, ODF, XHTML) read into
+    /// mixed content, where the space in `<b>bold</b> <i>italic</i>`
+    /// matters.
+    Preserve,
+    /// Each run of whitespace becomes a single space, kept at the ends of
+    /// the text so words either side of a child element stay apart;
+    /// whitespace-only text is dropped.
+    Normalize,
+}
+
+/// Options for XML deserialization.
+#[derive(Clone, Default)]
+pub struct DeserializeOptions {
+    /// Whether text with no field to receive it is silently discarded.
+    ///
+    /// When `false`, such text is an error.
+    ///
+    /// Default: `false`.
+    pub lenient: bool,
+    /// Called once per document, after deserialization succeeds or fails,
+    /// with counters describing the parse.
+    ///
+    /// Default: `None`.
+    pub on_metrics: Option<MetricsCallback>,
+    /// Called periodically while parsing, at the given interval.
+    ///
+    /// Default: `None`.
+    pub on_progress: Option<(ProgressInterval, ProgressCallback)>,
+    /// Token checked between parser events; once cancelled, parsing fails
+    /// with [`XmlError::Cancelled`].
+    ///
+    /// Default: `None`.
+    pub cancellation: Option<CancellationToken>,
+    /// Point in time after which parsing fails with [`XmlError::Cancelled`].
+    ///
+    /// The clock is checked every few dozen events, so the overrun is bounded
+    /// by the time to parse that many events.
+    ///
+    /// Default: `None`.
+    pub deadline: Option<Instant>,
+    /// Schema version of the documents, selecting fields marked
+    /// `xml::since` / `xml::until`.
+    ///
+    /// Default: `None` (every field is read).
+    pub version: Option<String>,
+    /// Attribute of the root element holding the schema version. When the
+    /// root element has it, it takes precedence over [`version`](Self::version).
+    ///
+    /// Default: `None`.
+    pub version_attribute: Option<String>,
+    /// Handling of `xml:id`, `xml:lang`, `xml:space`, `xml:base` and other
+    /// attributes in the reserved `xml:` namespace.
+    ///
+    /// Default: [`ReservedAttributes::Generic`].
+    pub reserved_attributes: ReservedAttributes,
+    /// Called with every value matched to a field, before it is parsed.
+    ///
+    /// Default: `None`.
+    pub intercept: Option<ValueInterceptor>,
+    /// Called with every collection item skipped because its type is marked
+    /// `xml::skip_on_error`.
+    ///
+    /// Default: `None` (items are skipped silently).
+    pub on_skipped_item: Option<SkippedItemCallback>,
+    /// Whether comments are kept, in types with an `xml::comment` variant.
+    ///
+    /// Default: `false` (comments are skipped).
+    pub keep_comments: bool,
+    /// Whether processing instructions are kept, in types with an
+    /// `xml::processing_instruction` variant.
+    ///
+    /// Default: `false` (processing instructions are skipped).
+    pub keep_processing_instructions: bool,
+    /// Whether references to entities declared in the document's internal
+    /// DTD subset (`<!ENTITY name "text">`) are replaced by their text.
+    ///
+    /// Replacement text is read as character data, not markup. When
+    /// `false`, such references are kept as written.
+    ///
+    /// Default: `false`.
+    pub expand_internal_entities: bool,
+    /// Limits applied while expanding declared entities.
+    ///
+    /// Default: [`EntityLimits::default()`].
+    pub entity_limits: EntityLimits,
+    /// Called with each external entity (`<!ENTITY name SYSTEM "uri">`) the
+    /// document references, to supply its text. Expansion is subject to
+    /// [`entity_limits`](Self::entity_limits).
+    ///
+    /// Default: `None` (nothing is fetched; references are kept as written).
+    pub resolve_external_entity: Option<ExternalEntityResolver>,
+    /// Replacement text for entities the document doesn't declare itself,
+    /// by name, such as `nbsp` or `copy` in XHTML-like content.
+    ///
+    /// Replacement text may refer to other entities and is read as
+    /// character data; expansion is subject to
+    /// [`entity_limits`](Self::entity_limits).
+    ///
+    /// Default: empty (references to undeclared entities are kept as
+    /// written in text and rejected in attribute values).
+    pub entities: Arc<HashMap<String, String>>,
+    /// Whether errors are wrapped in [`DeserializeError::Located`] with the
+    /// path, span, line and column where they happened.
+    ///
+    /// Default: `false`.
+    pub locate_errors: bool,
+    /// Whether every unknown element and attribute of `deny_unknown_fields`
+    /// types is reported at once, in a [`DeserializeError::UnknownFields`],
+    /// instead of failing on the first.
+    ///
+    /// Default: `false`.
+    pub report_unknown_fields: bool,
+    /// What happens when an element for a single-valued field appears more
+    /// than once, for fields without an `xml::on_duplicate` attribute.
+    ///
+    /// Default: [`DuplicatePolicy::Last`] (the last element wins).
+    pub on_duplicate: DuplicatePolicy,
+    /// Handling of whitespace in text content.
+    ///
+    /// Elements marked `xml:space="preserve"`, and their descendants up to
+    /// one marked `xml:space="default"`, always keep their whitespace.
+    ///
+    /// Default: [`Whitespace::Trim`].
+    pub whitespace: Whitespace,
+}
+
+impl core::fmt::Debug for DeserializeOptions {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeserializeOptions")
+            .field("lenient", &self.lenient)
+            .field("on_metrics", &self.on_metrics.as_ref().map(|_| "..."))
+            .field(
+                "on_progress",
+                &self.on_progress.as_ref().map(|(interval, _)| interval),
+            )
+            .field("cancellation", &self.cancellation)
+            .field("deadline", &self.deadline)
+            .field("version", &self.version)
+            .field("version_attribute", &self.version_attribute)
+            .field("reserved_attributes", &self.reserved_attributes)
+            .field("intercept", &self.intercept.as_ref().map(|_| "..."))
+            .field(
+                "on_skipped_item",
+                &self.on_skipped_item.as_ref().map(|_| "..."),
+            )
+            .field("keep_comments", &self.keep_comments)
+            .field(
+                "keep_processing_instructions",
+                &self.keep_processing_instructions,
+            )
+            .field("expand_internal_entities", &self.expand_internal_entities)
+            .field("entity_limits", &self.entity_limits)
+            .field(
+                "resolve_external_entity",
+                &self.resolve_external_entity.as_ref().map(|_| "..."),
+            )
+            .field("entities", &self.entities)
+            .field("locate_errors", &self.locate_errors)
+            .field("report_unknown_fields", &self.report_unknown_fields)
+            .field("on_duplicate", &self.on_duplicate)
+            .field("whitespace", &self.whitespace)
+            .finish()
+    }
+}
+
+impl DeserializeOptions {
+    /// Create new default options (strict).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Discard text that no field accepts instead of failing.
+    pub const fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Report [`ParseMetrics`] for every document parsed with these options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::DeserializeOptions;
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    ///
+    /// static ELEMENTS: AtomicU64 = AtomicU64::new(0);
+    ///
+    /// #[derive(Facet)]
+    /// struct Point {
+    ///     x: i32,
+    /// }
+    ///
+    /// let options = DeserializeOptions::new().on_metrics(|m| {
+    ///     ELEMENTS.fetch_add(m.elements, Ordering::Relaxed);
+    /// });
+    /// let _: Point = facet_xml::from_str_with_options("<point><x>1</x><z/></point>", &options).unwrap();
+    /// assert_eq!(ELEMENTS.load(Ordering::Relaxed), 3);
+    /// ```
+    pub fn on_metrics(mut self, callback: impl Fn(&ParseMetrics) + Send + Sync + 'static) -> Self {
+        self.on_metrics = Some(Arc::new(callback));
+        self
+    }
+
+    /// Report parsing [`Progress`] every `interval` bytes or events.
+    ///
+    /// Each report carries the byte offset and the path of open elements,
+    /// which is enough to drive a progress bar or to notice a stalled import.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::{DeserializeOptions, ProgressInterval};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    ///
+    /// static REPORTS: AtomicU64 = AtomicU64::new(0);
+    ///
+    /// #[derive(Facet)]
+    /// struct Point {
+    ///     x: i32,
+    ///     y: i32,
+    /// }
+    ///
+    /// let options = DeserializeOptions::new().on_progress(ProgressInterval::Events(4), |p| {
+    ///     assert_eq!(p.path.first().map(String::as_str), Some("point"));
+    ///     REPORTS.fetch_add(1, Ordering::Relaxed);
+    /// });
+    /// let _: Point = facet_xml::from_str_with_options("<point><x>1</x><y>2</y></point>", &options).unwrap();
+    /// assert!(REPORTS.load(Ordering::Relaxed) > 0);
+    /// ```
+    pub fn on_progress(
+        mut self,
+        interval: ProgressInterval,
+        callback: impl Fn(&Progress<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_progress = Some((interval, Arc::new(callback)));
+        self
+    }
+
+    /// Abort parsing once `token` is cancelled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::{CancellationToken, DeserializeError, DeserializeOptions, XmlError};
+    ///
+    /// #[derive(Facet, Debug)]
+    /// struct Point {
+    ///     x: i32,
+    /// }
+    ///
+    /// let token = CancellationToken::new();
+    /// let options = DeserializeOptions::new().cancellation(token.clone());
+    /// token.cancel();
+    ///
+    /// let err = facet_xml::from_str_with_options::<Point>("<point><x>1</x></point>", &options)
+    ///     .unwrap_err();
+    /// assert!(matches!(err, DeserializeError::Parser(XmlError::Cancelled)));
+    /// ```
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Abort parsing once `deadline` has passed.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Read documents as schema `version`.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Take the schema version from the root element's `name` attribute.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::{self as xml, DeserializeOptions};
+    ///
+    /// #[derive(Facet, Debug)]
+    /// struct Contact {
+    ///     #[facet(xml::attribute)]
+    ///     version: String,
+    ///     #[facet(xml::until = "1.x")]
+    ///     phone: Option<String>,
+    ///     #[facet(xml::since = "2.0")]
+    ///     mobile: Option<String>,
+    /// }
+    ///
+    /// let options = DeserializeOptions::new().version_attribute("version");
+    /// let v1: Contact = facet_xml::from_str_with_options(
+    ///     r#"<contact version="1.2"><phone>555</phone><mobile>556</mobile></contact>"#,
+    ///     &options,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(v1.phone.as_deref(), Some("555"));
+    /// assert!(v1.mobile.is_none());
+    /// ```
+    pub fn version_attribute(mut self, name: impl Into<String>) -> Self {
+        self.version_attribute = Some(name.into());
+        self
+    }
+
+    /// Choose how `xml:*` attributes are handled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::{self as xml, DeserializeOptions, ReservedAttributes};
+    ///
+    /// #[derive(Facet, Debug)]
+    /// #[facet(deny_unknown_fields)]
+    /// struct Para {
+    ///     #[facet(xml::attribute, xml::ns = "http://www.w3.org/XML/1998/namespace")]
+    ///     lang: Option<String>,
+    ///     #[facet(xml::text)]
+    ///     text: String,
+    /// }
+    ///
+    /// let options = DeserializeOptions::new().reserved_attributes(ReservedAttributes::Fields);
+    /// let para: Para = facet_xml::from_str_with_options(
+    ///     r#"<para xml:lang="fr" xml:space="preserve">Bonjour</para>"#,
+    ///     &options,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(para.lang.as_deref(), Some("fr"));
+    /// ```
+    pub fn reserved_attributes(mut self, policy: ReservedAttributes) -> Self {
+        self.reserved_attributes = policy;
+        self
+    }
+
+    /// See, and optionally replace, every value before it is parsed into its
+    /// field.
+    ///
+    /// The callback gets the value's path, raw text and target type, so
+    /// auditing code can log or redact sensitive fields as they are read
+    /// without wrapping their types in proxies. Returning `Some` stores the
+    /// returned text instead of the document's.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::{self as xml, DeserializeOptions};
+    ///
+    /// #[derive(Facet, Debug)]
+    /// struct Card {
+    ///     #[facet(xml::attribute)]
+    ///     holder: String,
+    ///     number: String,
+    /// }
+    ///
+    /// let options = DeserializeOptions::new().intercept(|value| {
+    ///     (value.path == "card/number").then(|| "****".to_string())
+    /// });
+    /// let card: Card = facet_xml::from_str_with_options(
+    ///     r#"<card holder="Ada"><number>4111111111111111</number></card>"#,
+    ///     &options,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(card.number, "****");
+    /// assert_eq!(card.holder, "Ada");
+    /// ```
+    pub fn intercept(
+        mut self,
+        callback: impl Fn(&FieldValue<'_>) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.intercept = Some(Arc::new(callback));
+        self
+    }
+
+    /// Report items left out of collections because their type is marked
+    /// `#[facet(xml::skip_on_error)]` and they failed to deserialize.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::{self as xml, DeserializeOptions};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// #[derive(Facet, Debug)]
+    /// #[facet(xml::skip_on_error)]
+    /// struct Entry {
+    ///     #[facet(xml::attribute)]
+    ///     id: u32,
+    /// }
+    ///
+    /// #[derive(Facet, Debug)]
+    /// struct Feed {
+    ///     entries: Vec<Entry>,
+    /// }
+    ///
+    /// let skipped = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = skipped.clone();
+    /// let options = DeserializeOptions::new().on_skipped_item(move |item| {
+    ///     sink.lock().unwrap().push(item.path.to_string());
+    /// });
+    /// let feed: Feed = facet_xml::from_str_with_options(
+    ///     r#"<feed><entry id="1"/><entry id="x"/><entry id="3"/></feed>"#,
+    ///     &options,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(feed.entries.len(), 2);
+    /// assert_eq!(*skipped.lock().unwrap(), ["feed/entry"]);
+    /// ```
+    pub fn on_skipped_item(
+        mut self,
+        callback: impl Fn(&SkippedItem<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_skipped_item = Some(Arc::new(callback));
+        self
+    }
+
+    /// Set how whitespace in text content is handled; see
+    /// [`whitespace`](Self::whitespace).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::{self as xml, DeserializeOptions, Whitespace};
+    ///
+    /// #[derive(Facet, Debug)]
+    /// struct Para {
+    ///     #[facet(xml::text)]
+    ///     text: Vec<String>,
+    /// }
+    ///
+    /// let input = "<para> Hello  <b>bold</b> world </para>";
+    /// let parse = |whitespace| -> Para {
+    ///     let options = DeserializeOptions::new().whitespace(whitespace);
+    ///     facet_xml::from_str_with_options(input, &options).unwrap()
+    /// };
+    /// assert_eq!(parse(Whitespace::Trim).text, ["Hello", "world"]);
+    /// assert_eq!(parse(Whitespace::Preserve).text, [" Hello  ", " world "]);
+    /// assert_eq!(parse(Whitespace::Normalize).text, [" Hello ", " world "]);
+    /// ```
+    pub const fn whitespace(mut self, whitespace: Whitespace) -> Self {
+        self.whitespace = whitespace;
+        self
+    }
+
+    /// Keep comments among an element's children, for types that have an
+    /// `xml::comment` variant (such as `facet_xml_node::Content`).
+    pub const fn keep_comments(mut self, keep: bool) -> Self {
+        self.keep_comments = keep;
+        self
+    }
+
+    /// Keep processing instructions among an element's children, for types
+    /// that have an `xml::processing_instruction` variant (such as
+    /// `facet_xml_node::Content`).
+    pub const fn keep_processing_instructions(mut self, keep: bool) -> Self {
+        self.keep_processing_instructions = keep;
+        self
+    }
+
+    /// Replace references to entities declared in the document's internal
+    /// DTD subset by their text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::{self as xml, DeserializeOptions};
+    ///
+    /// #[derive(Facet, Debug)]
+    /// struct Note {
+    ///     #[facet(xml::attribute)]
+    ///     from: String,
+    ///     body: String,
+    /// }
+    ///
+    /// let xml = r#"<!DOCTYPE note [<!ENTITY co "ACME Corp">]>
+    /// <note from="Sales, &co;"><body>&co;</body></note>"#;
+    /// let options = DeserializeOptions::new().expand_internal_entities(true);
+    /// let note: Note = facet_xml::from_str_with_options(xml, &options).unwrap();
+    /// assert_eq!(note.from, "Sales, ACME Corp");
+    /// assert_eq!(note.body, "ACME Corp");
+    /// ```
+    pub const fn expand_internal_entities(mut self, expand: bool) -> Self {
+        self.expand_internal_entities = expand;
+        self
+    }
+
+    /// Limit how deeply entity references may nest within replacement text.
+    pub const fn max_entity_depth(mut self, depth: usize) -> Self {
+        self.entity_limits.max_depth = depth;
+        self
+    }
+
+    /// Limit the total bytes entity expansion may produce per document.
+    pub const fn max_entity_expansion(mut self, bytes: usize) -> Self {
+        self.entity_limits.max_expanded_bytes = bytes;
+        self
+    }
+
+    /// Supply the text of external entities the document references.
+    ///
+    /// Returning `None` refuses the entity, failing with
+    /// [`XmlError::UnresolvedEntity`]. Nothing is fetched unless this is
+    /// set.
+    pub fn resolve_external_entity(
+        mut self,
+        resolver: impl Fn(&ExternalEntity<'_>) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.resolve_external_entity = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Supply the replacement text of entities the document uses without
+    /// declaring them, adding to any supplied before.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::DeserializeOptions;
+    ///
+    /// #[derive(Facet, Debug)]
+    /// struct Footer {
+    ///     text: String,
+    /// }
+    ///
+    /// let xml = "<footer><text>&copy;&nbsp;2024 ACME</text></footer>";
+    /// let options =
+    ///     DeserializeOptions::new().with_entities([("copy", "\u{a9}"), ("nbsp", "\u{a0}")]);
+    /// let footer: Footer = facet_xml::from_str_with_options(xml, &options).unwrap();
+    /// assert_eq!(footer.text, "\u{a9}\u{a0}2024 ACME");
+    /// ```
+    pub fn with_entities<K, V>(mut self, entities: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        Arc::make_mut(&mut self.entities)
+            .extend(entities.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Report where errors happened, as a [`DeserializeError::Located`]
+    /// around the error.
+    ///
+    /// The location has the path of the innermost element being read, with
+    /// the index of list items, and the span of the markup read last. When
+    /// parsing a string or slice, it also has the line and column, and the
+    /// error's `Display` shows the source line with a caret under the span.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::{DeserializeOptions, from_str_with_options};
+    ///
+    /// #[derive(Facet, Debug)]
+    /// struct Item {
+    ///     price: u32,
+    /// }
+    ///
+    /// #[derive(Facet, Debug)]
+    /// struct Order {
+    ///     #[facet(rename = "item")]
+    ///     items: Vec<Item>,
+    /// }
+    ///
+    /// let xml = "<order>\n  <item><price>3</price></item>\n  <item><price>x</price></item>\n</order>";
+    /// let options = DeserializeOptions::default().locate_errors(true);
+    /// let err = from_str_with_options::<Order>(xml, &options).unwrap_err();
+    /// let location = err.location().unwrap();
+    /// assert_eq!(location.path, "order/item[1]");
+    /// assert_eq!(location.line, Some(3));
+    /// ```
+    pub const fn locate_errors(mut self, locate: bool) -> Self {
+        self.locate_errors = locate;
+        self
+    }
+
+    /// Report every unknown element and attribute of `deny_unknown_fields`
+    /// types in one [`DeserializeError::UnknownFields`], with their paths.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::{self as xml, DeserializeError, DeserializeOptions};
+    ///
+    /// #[derive(Facet, Debug)]
+    /// #[facet(deny_unknown_fields)]
+    /// struct Item {
+    ///     #[facet(xml::attribute)]
+    ///     sku: String,
+    ///     qty: u32,
+    /// }
+    ///
+    /// let xml = r#"<item sku="a1" colour="red"><qty>2</qty><note>gift</note></item>"#;
+    /// let options = DeserializeOptions::new().report_unknown_fields(true);
+    /// let err = xml::from_str_with_options::<Item>(xml, &options).unwrap_err();
+    /// let DeserializeError::UnknownFields(unknown) = err else {
+    ///     panic!("{err}");
+    /// };
+    /// let paths: Vec<_> = unknown.iter().map(|item| item.path.as_str()).collect();
+    /// assert_eq!(paths, ["item/@colour", "item/note"]);
+    /// ```
+    pub const fn report_unknown_fields(mut self, report: bool) -> Self {
+        self.report_unknown_fields = report;
+        self
+    }
+
+    /// Choose what happens when an element for a single-valued field
+    /// appears more than once. Fields marked `xml::on_duplicate` keep their
+    /// own policy.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::{DeserializeError, DeserializeOptions, DuplicatePolicy};
+    ///
+    /// #[derive(Facet, Debug)]
+    /// struct Contact {
+    ///     email: String,
+    /// }
+    ///
+    /// let xml = "<contact><email>a@example.com</email><email>b@example.com</email></contact>";
+    /// let contact: Contact = facet_xml::from_str(xml).unwrap();
+    /// assert_eq!(contact.email, "b@example.com");
+    ///
+    /// let options = DeserializeOptions::new().on_duplicate(DuplicatePolicy::First);
+    /// let contact: Contact = facet_xml::from_str_with_options(xml, &options).unwrap();
+    /// assert_eq!(contact.email, "a@example.com");
+    ///
+    /// let options = DeserializeOptions::new().on_duplicate(DuplicatePolicy::Error);
+    /// let err = facet_xml::from_str_with_options::<Contact>(xml, &options).unwrap_err();
+    /// assert!(matches!(err, DeserializeError::DuplicateElement { .. }));
+    /// ```
+    pub const fn on_duplicate(mut self, policy: DuplicatePolicy) -> Self {
+        self.on_duplicate = policy;
+        self
+    }
+}
+
+/// Deserialize a value from an XML string with custom options.
+pub fn from_str_with_options<T>(
+    input: &str,
+    options: &DeserializeOptions,
+) -> Result<T, DeserializeError<XmlError>>
+where
+    T: Facet<'static>,
+{
+    from_slice_with_options(input.as_bytes(), options)
+}
+
+/// Deserialize a value from an XML string, resolving the values of fields
+/// marked `#[facet(xml::with_context)]` through `context` as they are read.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+/// use facet_xml::{self as xml, DeserializeContext, FieldValue};
+///
+/// /// Node names, interned into indices
+/// #[derive(Default)]
+/// struct Names(Vec<String>);
+///
+/// impl DeserializeContext for Names {
+///     fn resolve(&mut self, value: &FieldValue<'_>) -> Option<String> {
+///         let index = match self.0.iter().position(|name| name == value.text) {
+///             Some(index) => index,
+///             None => {
+///                 self.0.push(value.text.to_string());
+///                 self.0.len() - 1
+///             }
+///         };
+///         Some(index.to_string())
+///     }
+/// }
+///
+/// #[derive(Facet, Debug, PartialEq)]
+/// struct Edge {
+///     #[facet(xml::attribute, xml::with_context)]
+///     from: usize,
+///     #[facet(xml::attribute, xml::with_context)]
+///     to: usize,
+/// }
+///
+/// #[derive(Facet, Debug)]
+/// #[facet(rename = "graph")]
+/// struct Graph {
+///     #[facet(rename = "edge")]
+///     edges: Vec<Edge>,
+/// }
+///
+/// let mut names = Names::default();
+/// let graph: Graph = xml::from_str_with_context(
+///     r#"<graph><edge from="a" to="b"/><edge from="b" to="c"/></graph>"#,
+///     &mut names,
+/// )
+/// .unwrap();
+/// assert_eq!(graph.edges, [Edge { from: 0, to: 1 }, Edge { from: 1, to: 2 }]);
+/// assert_eq!(names.0, ["a", "b", "c"]);
+/// ```
+pub fn from_str_with_context<T, C>(
+    input: &str,
+    context: &mut C,
+) -> Result<T, DeserializeError<XmlError>>
+where
+    T: Facet<'static>,
+    C: DeserializeContext,
+{
+    from_str_with_context_and_options(input, context, &DeserializeOptions::default())
+}
+
+/// Deserialize a value from an XML string with custom options, resolving
+/// the values of fields marked `#[facet(xml::with_context)]` through
+/// `context` as they are read.
+///
+/// See [`from_str_with_context`].
+pub fn from_str_with_context_and_options<T, C>(
+    input: &str,
+    context: &mut C,
+    options: &DeserializeOptions,
+) -> Result<T, DeserializeError<XmlError>>
+where
+    T: Facet<'static>,
+    C: DeserializeContext,
+{
+    enter_span!("xml_document", bytes = input.len());
+    let mut parser = XmlParser::with_options(input.as_bytes(), options).with_context(context);
+    let result = deserializer(&mut parser, options)
+        .deserialize()
+        .and_then(|value| crate::assertions::check(&value).map(|()| value));
+    if let Some(callback) = &options.on_metrics {
+        callback(&parser.metrics());
+    }
+    result
+}
+
+/// Deserialize a value from XML bytes with custom options.
+pub fn from_slice_with_options<T>(
+    input: &[u8],
+    options: &DeserializeOptions,
+) -> Result<T, DeserializeError<XmlError>>
+where
+    T: Facet<'static>,
+{
+    enter_span!("xml_document", bytes = input.len());
+    let mut parser = XmlParser::with_options(input, options);
+    let result = deserializer(&mut parser, options)
+        .deserialize()
+        .and_then(|value| crate::assertions::check(&value).map(|()| value));
+    if let Some(callback) = &options.on_metrics {
+        callback(&parser.metrics());
+    }
+    result
+}
+
+/// A deserializer reading from `parser`, set up as `options` ask.
+fn deserializer<'de, P>(parser: P, options: &DeserializeOptions) -> DomDeserializer<'de, false, P>
+where
+    P: DomParser<'de>,
+{
+    let deserializer = DomDeserializer::new_owned(parser);
+    if options.report_unknown_fields {
+        deserializer.collect_unknown_fields()
+    } else {
+        deserializer
+    }
+}
+
+/// Deserialize a value from an XML string with custom options, collecting
+/// every recoverable error instead of stopping at the first.
+///
+/// See [`from_str_lenient_collect`](crate::from_str_lenient_collect).
+pub fn from_str_lenient_collect_with_options<T>(
+    input: &str,
+    options: &DeserializeOptions,
+) -> (Option<T>, Vec<DeserializeError<XmlError>>)
+where
+    T: Facet<'static>,
+{
+    enter_span!("xml_document", bytes = input.len());
+    let mut parser = XmlParser::with_options(input.as_bytes(), options);
+    let mut deserializer = deserializer(&mut parser, options).collect_errors();
+    let result = deserializer.deserialize::<T>();
+    let mut errors = deserializer.take_errors();
+    let value = match result {
+        Ok(value) => {
+            if let Err(e) = crate::assertions::check(&value) {
+                errors.push(e);
+            }
+            Some(value)
+        }
+        Err(e) => {
+            errors.push(e);
+            None
+        }
+    };
+    if let Some(callback) = &options.on_metrics {
+        callback(&parser.metrics());
+    }
+    (value, errors)
+}
+
+/// Deserialize a value from an XML reader with custom options.
+///
+/// See [`from_reader`](crate::from_reader).
+pub fn from_reader_with_options<T, R>(
+    reader: R,
+    options: &DeserializeOptions,
+) -> Result<T, DeserializeError<XmlError>>
+where
+    T: Facet<'static>,
+    R: std::io::Read,
+{
+    enter_span!("xml_document_reader");
+    let mut parser = XmlParser::from_reader_with_options(std::io::BufReader::new(reader), options);
+    let result = deserializer(&mut parser, options)
+        .deserialize()
+        .and_then(|value| crate::assertions::check(&value).map(|()| value));
+    if let Some(callback) = &options.on_metrics {
+        callback(&parser.metrics());
+    }
+    result
+}
+
+/// A complete XML profile: serialization and deserialization options together.
+///
+/// Define one profile for a service and share it, e.g. in a `static` or an
+/// `Arc<Config>`. Cloning is cheap: the options hold only flags, function
+/// pointers and (usually borrowed) strings.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+/// use facet_xml::{Config, DeserializeOptions, SerializeOptions};
+/// use std::sync::Arc;
+///
+/// #[derive(Facet, Debug, PartialEq)]
+/// struct Ping {
+///     seq: u32,
+/// }
+///
+/// let config = Arc::new(
+///     Config::new()
+///         .serialize(SerializeOptions::new().pretty())
+///         .deserialize(DeserializeOptions::new().lenient(true)),
+/// );
+///
+/// let xml = config.to_string(&Ping { seq: 1 }).unwrap();
+/// assert!(xml.contains("\n  <seq>"));
+/// assert_eq!(config.from_str::<Ping>(&xml).unwrap(), Ping { seq: 1 });
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    serialize: SerializeOptions,
+    deserialize: DeserializeOptions,
+}
+
+impl Config {
+    /// Create a profile with default options on both sides.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `options` for serialization.
+    pub fn serialize(mut self, options: SerializeOptions) -> Self {
+        self.serialize = options;
+        self
+    }
+
+    /// Use `options` for deserialization.
+    pub fn deserialize(mut self, options: DeserializeOptions) -> Self {
+        self.deserialize = options;
+        self
+    }
+
+    /// The serialization options of this profile.
+    pub fn serialize_options(&self) -> &SerializeOptions {
+        &self.serialize
+    }
+
+    /// The deserialization options of this profile.
+    pub fn deserialize_options(&self) -> &DeserializeOptions {
+        &self.deserialize
+    }
+
+    /// Serialize a value to an XML string.
+    pub fn to_string<'facet, T>(
+        &self,
+        value: &T,
+    ) -> Result<String, SerializeError<XmlSerializeError>>
+    where
+        T: Facet<'facet> + ?Sized,
+    {
+        crate::to_string_with_options(value, &self.serialize)
+    }
+
+    /// Serialize a value to XML bytes.
+    pub fn to_vec<'facet, T>(&self, value: &T) -> Result<Vec<u8>, SerializeError<XmlSerializeError>>
+    where
+        T: Facet<'facet> + ?Sized,
+    {
+        crate::to_vec_with_options(value, &self.serialize)
+    }
+
+    /// Deserialize a value from an XML string.
+    pub fn from_str<T>(&self, input: &str) -> Result<T, DeserializeError<XmlError>>
+    where
+        T: Facet<'static>,
+    {
+        from_str_with_options(input, &self.deserialize)
+    }
+
+    /// Deserialize a value from XML bytes.
+    pub fn from_slice<T>(&self, input: &[u8]) -> Result<T, DeserializeError<XmlError>>
+    where
+        T: Facet<'static>,
+    {
+        from_slice_with_options(input, &self.deserialize)
+    }
+
+    /// Deserialize a value from an XML reader.
+    pub fn from_reader<T, R>(&self, reader: R) -> Result<T, DeserializeError<XmlError>>
+    where
+        T: Facet<'static>,
+        R: std::io::Read,
+    {
+        from_reader_with_options(reader, &self.deserialize)
+    }
+}
@@ -0,0 +1,292 @@
+//! Incremental writer for documents whose children are produced one at a time.
+
+extern crate alloc;
+
+use alloc::{borrow::Cow, format, string::String, vec::Vec};
+use std::io::Write;
+
+use facet_core::Facet;
+use facet_dom::DomSerializeError;
+
+use crate::escaping::EscapingWriter;
+use crate::serializer::{SerializeOptions, XmlSerializeError, XmlSerializer};
+use crate::{is_xml_char, is_xml_name};
+
+/// Writes an XML document piece by piece: declaration, root start tag,
+/// any number of typed children, then the closing root tag.
+///
+/// Each child is serialized exactly like [`to_vec`](crate::to_vec) would
+/// serialize it, so the finished document deserializes into a struct whose
+/// root contains a `Vec` of the child type.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+/// use facet_xml::DocumentWriter;
+///
+/// #[derive(Facet)]
+/// struct Item {
+///     id: u32,
+/// }
+///
+/// let mut writer = DocumentWriter::new(Vec::new());
+/// writer.declaration().unwrap();
+/// writer.start_root("items").unwrap();
+/// for id in 0..2 {
+///     writer.write_child(&Item { id }).unwrap();
+/// }
+/// let out = writer.finish().unwrap();
+/// assert_eq!(
+///     String::from_utf8(out).unwrap(),
+///     r#"<?xml version="1.0" encoding="UTF-8"?><items><item><id>0</id></item><item><id>1</id></item></items>"#
+/// );
+/// ```
+pub struct DocumentWriter<W: Write> {
+    out: W,
+    serializer: XmlSerializer,
+    root: Option<String>,
+    wrote_declaration: bool,
+}
+
+impl<W: Write> DocumentWriter<W> {
+    /// Create a document writer with default options.
+    pub fn new(out: W) -> Self {
+        Self::with_options(out, SerializeOptions::default())
+    }
+
+    /// Create a document writer with the given options.
+    pub fn with_options(out: W, options: SerializeOptions) -> Self {
+        Self {
+            out,
+            serializer: XmlSerializer::with_options(options),
+            root: None,
+            wrote_declaration: false,
+        }
+    }
+
+    /// Write the `<?xml version="1.0" encoding="UTF-8"?>` declaration.
+    ///
+    /// Must be called before [`start_root`](Self::start_root), if at all.
+    pub fn declaration(&mut self) -> Result<(), DomSerializeError<XmlSerializeError>> {
+        if self.wrote_declaration || self.root.is_some() {
+            return Err(state_error(
+                "declaration must be written once, before the root element",
+            ));
+        }
+        self.write_raw(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        self.write_newline()?;
+        self.wrote_declaration = true;
+        Ok(())
+    }
+
+    /// Open the root element `name`, without attributes.
+    ///
+    /// Fails if `name` isn't an XML name.
+    pub fn start_root(&mut self, name: &str) -> Result<(), DomSerializeError<XmlSerializeError>> {
+        self.start_root_with(RootElement::new(name))
+    }
+
+    /// Open the root element with the namespaces and attributes of `root`.
+    ///
+    /// Fails if a name isn't an XML name, a value has a character XML
+    /// doesn't allow, or an attribute or prefix is given twice.
+    ///
+    /// ```
+    /// use facet_xml::{DocumentWriter, RootElement};
+    ///
+    /// let mut writer = DocumentWriter::new(Vec::new());
+    /// writer
+    ///     .start_root_with(
+    ///         RootElement::new("feed")
+    ///             .default_namespace("http://www.w3.org/2005/Atom")
+    ///             .namespace("dc", "http://purl.org/dc/elements/1.1/")
+    ///             .attribute("xml:lang", "en"),
+    ///     )
+    ///     .unwrap();
+    /// let out = writer.finish().unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(out).unwrap(),
+    ///     r#"<feed xmlns="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/elements/1.1/" xml:lang="en"></feed>"#
+    /// );
+    /// ```
+    pub fn start_root_with(
+        &mut self,
+        root: RootElement,
+    ) -> Result<(), DomSerializeError<XmlSerializeError>> {
+        if self.root.is_some() {
+            return Err(state_error("root element already started"));
+        }
+        let tag = root.start_tag()?;
+        self.write_raw(&tag)?;
+        self.write_newline()?;
+        self.root = Some(root.name);
+        Ok(())
+    }
+
+    /// Serialize `value` as the next child of the root element.
+    pub fn write_child<'facet, T>(
+        &mut self,
+        value: &'_ T,
+    ) -> Result<(), DomSerializeError<XmlSerializeError>>
+    where
+        T: Facet<'facet> + ?Sized,
+    {
+        if self.root.is_none() {
+            return Err(state_error("write_child() called before start_root()"));
+        }
+        self.serializer.serialize_at_depth(value, 1, &mut self.out)
+    }
+
+    /// Close the root element, flush, and return the underlying writer.
+    pub fn finish(mut self) -> Result<W, DomSerializeError<XmlSerializeError>> {
+        let Some(root) = self.root.take() else {
+            return Err(state_error("finish() called before start_root()"));
+        };
+        self.write_raw(b"</")?;
+        self.write_raw(root.as_bytes())?;
+        self.write_raw(b">")?;
+        self.write_newline()?;
+        self.out.flush().map_err(io_error)?;
+        Ok(self.out)
+    }
+
+    fn write_newline(&mut self) -> Result<(), DomSerializeError<XmlSerializeError>> {
+        let options = self.serializer.options();
+        if options.pretty {
+            self.out
+                .write_all(options.newline.as_bytes())
+                .map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    fn write_raw(&mut self, bytes: &[u8]) -> Result<(), DomSerializeError<XmlSerializeError>> {
+        self.out.write_all(bytes).map_err(io_error)
+    }
+}
+
+fn state_error(msg: &'static str) -> DomSerializeError<XmlSerializeError> {
+    DomSerializeError::Backend(XmlSerializeError {
+        msg: Cow::Borrowed(msg),
+    })
+}
+
+fn io_error(e: std::io::Error) -> DomSerializeError<XmlSerializeError> {
+    DomSerializeError::Backend(XmlSerializeError {
+        msg: Cow::Owned(format!("write error: {e}")),
+    })
+}
+
+/// The root element opened by [`DocumentWriter::start_root_with`]: its
+/// name, namespace declarations and attributes, written in the order given.
+#[derive(Debug, Clone)]
+pub struct RootElement {
+    name: String,
+    /// `(prefix, uri)`; an empty prefix declares the default namespace.
+    namespaces: Vec<(String, String)>,
+    attributes: Vec<(String, String)>,
+}
+
+impl RootElement {
+    /// A root element named `name`, which may be prefixed (`atom:feed`).
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            namespaces: Vec::new(),
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Declare `uri` as the default namespace (`xmlns="uri"`).
+    pub fn default_namespace(mut self, uri: impl Into<String>) -> Self {
+        self.namespaces.push((String::new(), uri.into()));
+        self
+    }
+
+    /// Bind `prefix` to `uri` (`xmlns:prefix="uri"`).
+    pub fn namespace(mut self, prefix: impl Into<String>, uri: impl Into<String>) -> Self {
+        self.namespaces.push((prefix.into(), uri.into()));
+        self
+    }
+
+    /// Add the attribute `name="value"`; the value is escaped as needed.
+    pub fn attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.push((name.into(), value.into()));
+        self
+    }
+
+    /// The validated start tag, `<name ...>`.
+    fn start_tag(&self) -> Result<Vec<u8>, DomSerializeError<XmlSerializeError>> {
+        if !is_xml_name(&self.name) {
+            return Err(invalid(format!(
+                "invalid root element name {:?}",
+                self.name
+            )));
+        }
+        let mut tag = Vec::new();
+        tag.push(b'<');
+        tag.extend_from_slice(self.name.as_bytes());
+        for (i, (prefix, uri)) in self.namespaces.iter().enumerate() {
+            if !prefix.is_empty()
+                && (!is_xml_name(prefix)
+                    || prefix.contains(':')
+                    || prefix.eq_ignore_ascii_case("xml")
+                    || prefix.eq_ignore_ascii_case("xmlns"))
+            {
+                return Err(invalid(format!("invalid namespace prefix {prefix:?}")));
+            }
+            if self.namespaces[..i].iter().any(|(p, _)| p == prefix) {
+                return Err(invalid(format!(
+                    "namespace prefix {prefix:?} declared twice"
+                )));
+            }
+            if prefix.is_empty() {
+                tag.extend_from_slice(b" xmlns=\"");
+            } else {
+                tag.extend_from_slice(b" xmlns:");
+                tag.extend_from_slice(prefix.as_bytes());
+                tag.extend_from_slice(b"=\"");
+            }
+            write_attribute_value(&mut tag, uri)?;
+        }
+        for (i, (name, value)) in self.attributes.iter().enumerate() {
+            if !is_xml_name(name) || name == "xmlns" || name.starts_with("xmlns:") {
+                return Err(invalid(format!("invalid attribute name {name:?}")));
+            }
+            if self.attributes[..i].iter().any(|(n, _)| n == name) {
+                return Err(invalid(format!("attribute {name:?} given twice")));
+            }
+            tag.push(b' ');
+            tag.extend_from_slice(name.as_bytes());
+            tag.extend_from_slice(b"=\"");
+            write_attribute_value(&mut tag, value)?;
+        }
+        tag.push(b'>');
+        Ok(tag)
+    }
+}
+
+/// Write `value` escaped, followed by the closing quote.
+fn write_attribute_value(
+    out: &mut Vec<u8>,
+    value: &str,
+) -> Result<(), DomSerializeError<XmlSerializeError>> {
+    if let Some(c) = value.chars().find(|&c| !is_xml_char(c)) {
+        return Err(invalid(format!(
+            "character U+{:04X} not allowed in attribute values",
+            c as u32
+        )));
+    }
+    EscapingWriter::attribute(out)
+        .write_all(value.as_bytes())
+        .map_err(io_error)?;
+    out.push(b'"');
+    Ok(())
+}
+
+fn invalid(msg: String) -> DomSerializeError<XmlSerializeError> {
+    DomSerializeError::Backend(XmlSerializeError {
+        msg: Cow::Owned(msg),
+    })
+}
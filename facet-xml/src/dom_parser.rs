@@ -6,14 +6,26 @@ use alloc::borrow::Cow;
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::fmt;
-use std::io::Cursor;
+use std::io::{BufRead, Cursor};
 
-use facet_dom::{DomEvent, DomParser};
+use facet_dom::{DomEvent, DomParser, DuplicatePolicy};
+use facet_reflect::Span;
 use quick_xml::NsReader;
 use quick_xml::escape::resolve_xml_entity;
 use quick_xml::events::Event;
 use quick_xml::name::ResolveResult;
 
+use crate::chars::is_xml_char;
+use crate::config::{
+    CancellationToken, DeserializeContext, DeserializeOptions, FieldValue, ProgressCallback,
+    ReservedAttribute, ReservedAttributes, SkippedItem, SkippedItemCallback, ValueInterceptor,
+    Whitespace,
+};
+use crate::entities::Entities;
+
+/// Largest event buffer kept for reuse between events.
+const BUF_RETAIN_LIMIT: usize = 64 * 1024;
+
 /// XML parsing error.
 #[derive(Debug, Clone)]
 pub enum XmlError {
@@ -25,6 +37,27 @@ pub enum XmlError {
     UnbalancedTags,
     /// Invalid UTF-8.
     InvalidUtf8(core::str::Utf8Error),
+    /// The input has bytes that aren't UTF-8, or a character outside the XML
+    /// `Char` production (e.g. a control character).
+    InvalidInput {
+        /// Byte offset of the first offending byte.
+        offset: u64,
+        /// The offending character, or `None` when the bytes aren't UTF-8.
+        character: Option<char>,
+        /// Hex dump of the bytes around `offset`, the offending ones in
+        /// brackets.
+        snippet: String,
+    },
+    /// Parsing was cancelled or ran past its deadline.
+    Cancelled,
+    /// Expanding a declared entity went past the configured
+    /// [`EntityLimits`](crate::EntityLimits).
+    EntityLimit {
+        /// The entity whose expansion hit the limit.
+        entity: String,
+    },
+    /// The external entity resolver refused an entity.
+    UnresolvedEntity(String),
 }
 
 impl fmt::Display for XmlError {
@@ -34,17 +67,122 @@ impl fmt::Display for XmlError {
             XmlError::UnexpectedEof => write!(f, "Unexpected end of XML"),
             XmlError::UnbalancedTags => write!(f, "Unbalanced XML tags"),
             XmlError::InvalidUtf8(e) => write!(f, "Invalid UTF-8 in XML: {}", e),
+            XmlError::InvalidInput {
+                offset,
+                character: None,
+                snippet,
+            } => write!(f, "Invalid UTF-8 in XML at byte {offset}: {snippet}"),
+            XmlError::InvalidInput {
+                offset,
+                character: Some(c),
+                snippet,
+            } => write!(
+                f,
+                "Character U+{:04X} not allowed in XML at byte {offset}: {snippet}",
+                *c as u32
+            ),
+            XmlError::Cancelled => write!(f, "XML parsing cancelled"),
+            XmlError::EntityLimit { entity } => {
+                write!(f, "Expanding entity `{entity}` exceeds the entity limits")
+            }
+            XmlError::UnresolvedEntity(name) => {
+                write!(f, "External entity `{name}` could not be resolved")
+            }
         }
     }
 }
 
 impl std::error::Error for XmlError {}
 
+/// Counters describing the work done by an [`XmlParser`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseMetrics {
+    /// Bytes of input consumed so far.
+    pub bytes_parsed: u64,
+    /// Elements opened so far, including self-closing ones.
+    pub elements: u64,
+    /// Elements skipped without being deserialized (e.g. unknown elements).
+    pub skipped_elements: u64,
+}
+
+/// How often a progress callback is invoked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressInterval {
+    /// After every `n` bytes of input.
+    Bytes(u64),
+    /// After every `n` parser events.
+    Events(u64),
+}
+
+/// Position of the parser, reported to a progress callback.
+#[derive(Debug, Clone, Copy)]
+pub struct Progress<'a> {
+    /// Byte offset into the input.
+    pub offset: u64,
+    /// Parser events emitted so far.
+    pub events: u64,
+    /// Local names of the currently open elements, outermost first.
+    pub path: &'a [String],
+}
+
+/// Progress reporting state of an [`XmlParser`].
+struct ProgressState {
+    interval: ProgressInterval,
+    callback: ProgressCallback,
+    events: u64,
+    /// Offset or event count at which the callback fires next
+    next_report: u64,
+    path: Vec<String>,
+}
+
+impl ProgressState {
+    fn new(interval: ProgressInterval, callback: ProgressCallback) -> Self {
+        let step = match interval {
+            ProgressInterval::Bytes(n) | ProgressInterval::Events(n) => n.max(1),
+        };
+        Self {
+            interval,
+            callback,
+            events: 0,
+            next_report: step,
+            path: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, event: &DomEvent<'_>, offset: u64) {
+        self.events += 1;
+        match event {
+            DomEvent::NodeStart { tag, .. } => self.path.push(tag.to_string()),
+            DomEvent::NodeEnd => {
+                self.path.pop();
+            }
+            _ => {}
+        }
+
+        let (position, step) = match self.interval {
+            ProgressInterval::Bytes(n) => (offset, n.max(1)),
+            ProgressInterval::Events(n) => (self.events, n.max(1)),
+        };
+        if position >= self.next_report {
+            // Skip thresholds passed in one go (e.g. a large text node)
+            self.next_report = (position / step + 1) * step;
+            (self.callback)(&Progress {
+                offset,
+                events: self.events,
+                path: &self.path,
+            });
+        }
+    }
+}
+
 /// Streaming XML parser implementing `DomParser`.
-pub struct XmlParser<'de> {
-    reader: NsReader<Cursor<&'de [u8]>>,
-    /// Original input for raw capture
-    input: &'de [u8],
+///
+/// Parses a byte slice by default, or any [`BufRead`] opened with
+/// [`from_reader`](XmlParser::from_reader).
+pub struct XmlParser<'de, R = Cursor<&'de [u8]>> {
+    reader: NsReader<R>,
+    /// Original input for raw capture, when parsing a slice
+    input: Option<&'de [u8]>,
     /// Buffer for quick-xml events
     buf: Vec<u8>,
     /// Buffer for peeked event
@@ -52,7 +190,7 @@ pub struct XmlParser<'de> {
     /// Stack tracking element depth for skip_node
     depth: usize,
     /// Pending attributes from the current element
-    pending_attrs: Vec<(Option<String>, String, String)>,
+    pending_attrs: Vec<(Option<String>, String, Cow<'de, str>)>,
     /// Index into pending_attrs
     attr_idx: usize,
     /// State machine for event generation
@@ -61,6 +199,58 @@ pub struct XmlParser<'de> {
     is_empty_element: bool,
     /// Position where current node started (for raw capture)
     node_start_pos: u64,
+    /// Whether unclaimed text is discarded rather than an error
+    lenient: bool,
+    /// Elements opened so far
+    elements_seen: u64,
+    /// Elements passed to skip_node so far
+    elements_skipped: u64,
+    /// Progress reporting, if a callback was registered
+    progress: Option<ProgressState>,
+    /// Token checked before every event
+    cancellation: Option<CancellationToken>,
+    /// Time after which parsing fails with `XmlError::Cancelled`
+    deadline: Option<std::time::Instant>,
+    /// Events read since the deadline was last checked (the first is always checked)
+    events_since_deadline_check: u32,
+    /// Schema version gating `xml::since` / `xml::until` fields
+    schema_version: Option<String>,
+    /// Attribute of the root element overriding `schema_version`
+    version_attribute: Option<String>,
+    /// Handling of `xml:*` attributes
+    reserved_attributes: ReservedAttributes,
+    /// Open elements, tracked only to report `xml:*` attributes
+    open_elements: Vec<String>,
+    /// Callback seeing every value before it is stored in a field
+    intercept: Option<ValueInterceptor>,
+    /// State resolving `xml::with_context` fields
+    context: Option<&'de mut dyn DeserializeContext>,
+    /// Callback told about collection items skipped after failing
+    on_skipped_item: Option<SkippedItemCallback>,
+    /// Prefix of the element most recently started
+    element_prefix: Option<String>,
+    /// Whether comments are kept for `xml::comment` variants
+    keep_comments: bool,
+    /// Whether processing instructions are kept for `xml::processing_instruction` variants
+    keep_processing_instructions: bool,
+    /// What happens to repeated elements of single-valued fields
+    on_duplicate: DuplicatePolicy,
+    /// Entities declared by the document's DOCTYPE
+    entities: Entities,
+    /// Error found by the up-front scan of the input, returned by the first read
+    invalid_input: Option<XmlError>,
+    /// Whether errors are located
+    locate_errors: bool,
+    /// Span of the markup the most recently read event came from
+    read_span: Option<Span>,
+    /// Span of the peeked event
+    peeked_span: Option<Span>,
+    /// Span of the last event returned by `next_event`
+    span: Option<Span>,
+    /// Handling of whitespace in text content
+    whitespace: Whitespace,
+    /// Per open element, whether `xml:space="preserve"` is in effect
+    space_preserve: Vec<bool>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -86,12 +276,46 @@ impl<'de> XmlParser<'de> {
     pub fn new(input: &'de [u8]) -> Self {
         trace!(input_len = input.len(), "creating XML parser");
 
-        let mut reader = NsReader::from_reader(Cursor::new(input));
-        reader.config_mut().trim_text(true);
+        let mut parser = Self::with_reader(Cursor::new(input));
+        parser.input = Some(input);
+        parser.invalid_input = check_input(input).err();
+        parser
+    }
+
+    /// Create a new streaming XML parser configured by `options`.
+    pub fn with_options(input: &'de [u8], options: &DeserializeOptions) -> Self {
+        let mut parser = Self::new(input);
+        parser.configure(options);
+        parser
+    }
+}
+
+impl<'de, R: BufRead> XmlParser<'de, R> {
+    /// Create a streaming XML parser reading from `reader`.
+    ///
+    /// Input is read as events are requested, so only the current element's
+    /// data is held in memory. Unlike a parser over a slice, it can't capture
+    /// [`RawMarkup`](crate::RawMarkup), and invalid UTF-8 is reported only
+    /// where it is decoded rather than located up front.
+    pub fn from_reader(reader: R) -> Self {
+        Self::with_reader(reader)
+    }
+
+    /// Create a streaming XML parser reading from `reader`, configured by
+    /// `options`.
+    pub fn from_reader_with_options(reader: R, options: &DeserializeOptions) -> Self {
+        let mut parser = Self::from_reader(reader);
+        parser.configure(options);
+        parser
+    }
+
+    fn with_reader(reader: R) -> Self {
+        // Whitespace is trimmed (or not) as text is read, per `whitespace`
+        let reader = NsReader::from_reader(reader);
 
         Self {
             reader,
-            input,
+            input: None,
             buf: Vec::new(),
             peeked: None,
             depth: 0,
@@ -100,12 +324,77 @@ impl<'de> XmlParser<'de> {
             state: ParserState::Ready,
             is_empty_element: false,
             node_start_pos: 0,
+            lenient: false,
+            elements_seen: 0,
+            elements_skipped: 0,
+            progress: None,
+            cancellation: None,
+            deadline: None,
+            events_since_deadline_check: 0,
+            schema_version: None,
+            version_attribute: None,
+            reserved_attributes: ReservedAttributes::Generic,
+            open_elements: Vec::new(),
+            intercept: None,
+            context: None,
+            on_skipped_item: None,
+            element_prefix: None,
+            keep_comments: false,
+            keep_processing_instructions: false,
+            on_duplicate: DuplicatePolicy::Last,
+            entities: Entities::default(),
+            invalid_input: None,
+            locate_errors: false,
+            read_span: None,
+            peeked_span: None,
+            span: None,
+            whitespace: Whitespace::Trim,
+            space_preserve: Vec::new(),
+        }
+    }
+
+    /// Resolve the values of fields marked `xml::with_context` through
+    /// `context`; see [`from_str_with_context`](crate::from_str_with_context).
+    pub fn with_context(mut self, context: &'de mut dyn DeserializeContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Apply `options` to a freshly created parser.
+    fn configure(&mut self, options: &DeserializeOptions) {
+        self.lenient = options.lenient;
+        self.progress = options
+            .on_progress
+            .as_ref()
+            .map(|(interval, callback)| ProgressState::new(*interval, callback.clone()));
+        self.cancellation = options.cancellation.clone();
+        self.deadline = options.deadline;
+        // The root element's version attribute, if any, replaces this once read
+        self.schema_version = options.version.clone();
+        self.version_attribute = options.version_attribute.clone();
+        self.reserved_attributes = options.reserved_attributes.clone();
+        self.intercept = options.intercept.clone();
+        self.on_skipped_item = options.on_skipped_item.clone();
+        self.keep_comments = options.keep_comments;
+        self.keep_processing_instructions = options.keep_processing_instructions;
+        self.on_duplicate = options.on_duplicate;
+        self.entities.configure(options);
+        self.locate_errors = options.locate_errors;
+        self.whitespace = options.whitespace;
+    }
+
+    /// Counters for the input parsed so far.
+    pub fn metrics(&self) -> ParseMetrics {
+        ParseMetrics {
+            bytes_parsed: self.reader.buffer_position(),
+            elements: self.elements_seen,
+            skipped_elements: self.elements_skipped,
         }
     }
 
     /// Capture the current node as raw XML and skip past it.
     /// Must be called right after a NodeStart event has been consumed.
-    fn do_capture_raw_node(&mut self) -> Result<Cow<'de, str>, XmlError> {
+    fn do_capture_raw_node(&mut self, input: &'de [u8]) -> Result<Cow<'de, str>, XmlError> {
         // Save start position before it gets overwritten by child elements
         let start = self.node_start_pos as usize;
         let start_depth = self.depth;
@@ -127,24 +416,62 @@ impl<'de> XmlParser<'de> {
         }
 
         let end = self.reader.buffer_position() as usize;
-        let raw = &self.input[start..end];
+        let raw = &input[start..end];
         let s = core::str::from_utf8(raw).map_err(XmlError::InvalidUtf8)?;
         Ok(Cow::Borrowed(s))
     }
 
-    /// Read the next raw event from quick-xml and convert to DomEvent.
+    /// Read the next event, reporting progress if a callback is registered.
     fn read_next(&mut self) -> Result<Option<DomEvent<'de>>, XmlError> {
+        if let Some(error) = self.invalid_input.take() {
+            self.state = ParserState::Done;
+            return Err(error);
+        }
+        self.check_cancelled()?;
+        let event = self.read_event()?;
+        if let (Some(progress), Some(event)) = (&mut self.progress, &event) {
+            progress.record(event, self.reader.buffer_position());
+        }
+        Ok(event)
+    }
+
+    /// Fail with `XmlError::Cancelled` if the token fired or the deadline passed.
+    ///
+    /// The clock is only read every `DEADLINE_CHECK_INTERVAL` events.
+    fn check_cancelled(&mut self) -> Result<(), XmlError> {
+        const DEADLINE_CHECK_INTERVAL: u32 = 64;
+
+        if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Err(XmlError::Cancelled);
+        }
+        if let Some(deadline) = self.deadline {
+            if self.events_since_deadline_check == 0 && std::time::Instant::now() >= deadline {
+                return Err(XmlError::Cancelled);
+            }
+            self.events_since_deadline_check =
+                (self.events_since_deadline_check + 1) % DEADLINE_CHECK_INTERVAL;
+        }
+        Ok(())
+    }
+
+    /// Read the next raw event from quick-xml and convert to DomEvent.
+    fn read_event(&mut self) -> Result<Option<DomEvent<'de>>, XmlError> {
         loop {
             match self.state {
                 ParserState::Done => return Ok(None),
 
                 ParserState::EmittingAttrs => {
                     if self.attr_idx < self.pending_attrs.len() {
-                        let (ns, name, value) = &self.pending_attrs[self.attr_idx];
+                        let (ns, name, value) =
+                            std::mem::take(&mut self.pending_attrs[self.attr_idx]);
                         let event = DomEvent::Attribute {
-                            name: Cow::Owned(name.clone()),
-                            value: Cow::Owned(value.clone()),
-                            namespace: ns.clone().map(Cow::Owned),
+                            name: Cow::Owned(name),
+                            value,
+                            namespace: ns.map(Cow::Owned),
                         };
                         self.attr_idx += 1;
                         return Ok(Some(event));
@@ -172,6 +499,8 @@ impl<'de> XmlParser<'de> {
 
                 ParserState::NeedNodeEnd => {
                     self.depth -= 1;
+                    self.open_elements.pop();
+                    self.space_preserve.pop();
                     self.state = if self.depth == 0 {
                         ParserState::Done
                     } else {
@@ -184,11 +513,22 @@ impl<'de> XmlParser<'de> {
                     // Record position before reading (for raw capture)
                     let pos_before = self.reader.buffer_position();
 
-                    self.buf.clear();
+                    // Don't keep a multi-megabyte text node's buffer around for the
+                    // rest of the document
+                    if self.buf.capacity() > BUF_RETAIN_LIMIT {
+                        self.buf = Vec::new();
+                    } else {
+                        self.buf.clear();
+                    }
                     let (resolve, event) = self
                         .reader
                         .read_resolved_event_into(&mut self.buf)
                         .map_err(|e| XmlError::Parse(e.to_string()))?;
+                    let pos_after = self.reader.buffer_position();
+                    self.read_span = Some(Span::new(
+                        pos_before as usize,
+                        (pos_after - pos_before) as usize,
+                    ));
 
                     // Resolve element namespace upfront
                     let elem_ns = resolve_namespace(resolve)?;
@@ -204,10 +544,21 @@ impl<'de> XmlParser<'de> {
                             let local = core::str::from_utf8(local_name.as_ref())
                                 .map_err(XmlError::InvalidUtf8)?;
                             let local_owned = local.to_string();
+                            self.element_prefix = match e.name().prefix() {
+                                Some(prefix) => Some(
+                                    core::str::from_utf8(prefix.as_ref())
+                                        .map_err(XmlError::InvalidUtf8)?
+                                        .to_string(),
+                                ),
+                                None => None,
+                            };
 
                             // Collect attributes
                             self.pending_attrs.clear();
                             self.attr_idx = 0;
+                            let mut reported = Vec::new();
+                            let mut space_preserve =
+                                self.space_preserve.last().copied().unwrap_or(false);
 
                             for attr in e.attributes() {
                                 let attr = attr.map_err(|e| XmlError::Parse(e.to_string()))?;
@@ -229,18 +580,72 @@ impl<'de> XmlParser<'de> {
                                 let attr_local_name = key.local_name();
                                 let attr_local = core::str::from_utf8(attr_local_name.as_ref())
                                     .map_err(XmlError::InvalidUtf8)?;
-                                let value = attr
-                                    .unescape_value()
-                                    .map_err(|e| XmlError::Parse(e.to_string()))?;
-
-                                self.pending_attrs.push((
-                                    attr_ns,
-                                    attr_local.to_string(),
-                                    value.into_owned(),
-                                ));
+                                // Values without references are borrowed from the input
+                                let borrowed = if attr.value.contains(&b'&') {
+                                    None
+                                } else {
+                                    borrow_input(self.input, &attr.value, pos_before, pos_after)
+                                };
+                                let value: Cow<'de, str> = if let Some(value) = borrowed {
+                                    Cow::Borrowed(value)
+                                } else if self.entities.is_empty() {
+                                    Cow::Owned(
+                                        attr.unescape_value()
+                                            .map_err(|e| XmlError::Parse(e.to_string()))?
+                                            .into_owned(),
+                                    )
+                                } else {
+                                    let raw = core::str::from_utf8(&attr.value)
+                                        .map_err(XmlError::InvalidUtf8)?;
+                                    Cow::Owned(self.entities.unescape_attribute(raw)?)
+                                };
+
+                                if self.depth == 0
+                                    && self.version_attribute.as_deref().map(str::as_bytes)
+                                        == Some(key.as_ref())
+                                {
+                                    self.schema_version = Some(value.to_string());
+                                }
+
+                                if attr_ns.as_deref() == Some(crate::XML_NAMESPACE) {
+                                    if attr_local == "space" {
+                                        match value.as_ref() {
+                                            "preserve" => space_preserve = true,
+                                            "default" => space_preserve = false,
+                                            _ => {}
+                                        }
+                                    }
+                                    match self.reserved_attributes {
+                                        ReservedAttributes::Ignore => continue,
+                                        ReservedAttributes::Report(_) => {
+                                            reported
+                                                .push((attr_local.to_string(), value.into_owned()));
+                                            continue;
+                                        }
+                                        ReservedAttributes::Generic
+                                        | ReservedAttributes::Fields => {}
+                                    }
+                                }
+
+                                self.pending_attrs
+                                    .push((attr_ns, attr_local.to_string(), value));
+                            }
+
+                            if let ReservedAttributes::Report(callback) = &self.reserved_attributes
+                            {
+                                self.open_elements.push(local_owned.clone());
+                                for (name, value) in &reported {
+                                    callback(&ReservedAttribute {
+                                        path: &self.open_elements,
+                                        name,
+                                        value,
+                                    });
+                                }
                             }
 
                             self.depth += 1;
+                            self.space_preserve.push(space_preserve);
+                            self.elements_seen += 1;
                             self.is_empty_element = is_empty;
 
                             if self.pending_attrs.is_empty() {
@@ -259,16 +664,44 @@ impl<'de> XmlParser<'de> {
                         }
                         Event::Text(e) => {
                             let text = e.decode().map_err(|e| XmlError::Parse(e.to_string()))?;
-                            let trimmed = text.trim();
-                            if !trimmed.is_empty() {
-                                return Ok(Some(DomEvent::Text(Cow::Owned(trimmed.to_string()))));
+                            let whitespace = match self.space_preserve.last() {
+                                Some(true) => Whitespace::Preserve,
+                                _ => self.whitespace,
+                            };
+                            let kept = match whitespace {
+                                // Whitespace around the root element is never content
+                                _ if self.depth == 0 => Cow::Borrowed(""),
+                                Whitespace::Preserve => Cow::Borrowed(&*text),
+                                Whitespace::Trim => Cow::Borrowed(text.trim()),
+                                Whitespace::Normalize => normalize_whitespace(&text),
+                            };
+                            if !kept.is_empty() {
+                                let text = match borrow_input(
+                                    self.input,
+                                    kept.as_bytes(),
+                                    pos_before,
+                                    pos_after,
+                                ) {
+                                    Some(text) => Cow::Borrowed(text),
+                                    None => Cow::Owned(kept.into_owned()),
+                                };
+                                return Ok(Some(DomEvent::Text(text)));
                             }
                         }
                         Event::CData(e) => {
                             let text =
                                 core::str::from_utf8(e.as_ref()).map_err(XmlError::InvalidUtf8)?;
                             if !text.is_empty() {
-                                return Ok(Some(DomEvent::Text(Cow::Owned(text.to_string()))));
+                                let text = match borrow_input(
+                                    self.input,
+                                    text.as_bytes(),
+                                    pos_before,
+                                    pos_after,
+                                ) {
+                                    Some(text) => Cow::Borrowed(text),
+                                    None => Cow::Owned(text.to_string()),
+                                };
+                                return Ok(Some(DomEvent::Text(text)));
                             }
                         }
                         Event::Comment(e) => {
@@ -294,6 +727,7 @@ impl<'de> XmlParser<'de> {
                             // Parse DOCTYPE declaration and emit as DomEvent
                             let text =
                                 core::str::from_utf8(e.as_ref()).map_err(XmlError::InvalidUtf8)?;
+                            self.entities.declare_from_doctype(text);
                             return Ok(Some(DomEvent::Doctype(Cow::Owned(text.to_string()))));
                         }
                         Event::Eof => {
@@ -302,7 +736,10 @@ impl<'de> XmlParser<'de> {
                         }
                         Event::GeneralRef(e) => {
                             let raw = e.decode().map_err(|e| XmlError::Parse(e.to_string()))?;
-                            let resolved = resolve_entity(&raw)?;
+                            let resolved = match self.entities.expand(&raw)? {
+                                Some(expanded) => expanded,
+                                None => resolve_entity(&raw)?,
+                            };
                             return Ok(Some(DomEvent::Text(Cow::Owned(resolved))));
                         }
                     }
@@ -312,25 +749,30 @@ impl<'de> XmlParser<'de> {
     }
 }
 
-impl<'de> DomParser<'de> for XmlParser<'de> {
+impl<'de, R: BufRead> DomParser<'de> for XmlParser<'de, R> {
     type Error = XmlError;
 
     fn next_event(&mut self) -> Result<Option<DomEvent<'de>>, Self::Error> {
         if let Some(event) = self.peeked.take() {
+            self.span = self.peeked_span;
             return Ok(Some(event));
         }
-        self.read_next()
+        let event = self.read_next()?;
+        self.span = self.read_span;
+        Ok(event)
     }
 
     fn peek_event(&mut self) -> Result<Option<&DomEvent<'de>>, Self::Error> {
         if self.peeked.is_none() {
             self.peeked = self.read_next()?;
+            self.peeked_span = self.read_span;
         }
         Ok(self.peeked.as_ref())
     }
 
     fn skip_node(&mut self) -> Result<(), Self::Error> {
         let start_depth = self.depth;
+        self.elements_skipped += 1;
 
         loop {
             let event = self.next_event()?;
@@ -348,17 +790,210 @@ impl<'de> DomParser<'de> for XmlParser<'de> {
         Ok(())
     }
 
-    fn current_span(&self) -> Option<facet_reflect::Span> {
-        None
+    fn current_span(&self) -> Option<Span> {
+        self.span
+    }
+
+    fn is_lenient(&self) -> bool {
+        self.lenient
     }
 
     fn format_namespace(&self) -> Option<&'static str> {
         Some("xml")
     }
 
+    fn schema_version(&self) -> Option<&str> {
+        self.schema_version.as_deref()
+    }
+
+    fn reserved_attribute_namespace(&self) -> Option<&str> {
+        match self.reserved_attributes {
+            ReservedAttributes::Fields => Some(crate::XML_NAMESPACE),
+            _ => None,
+        }
+    }
+
+    fn intercepts_values(&self) -> bool {
+        self.intercept.is_some()
+    }
+
+    fn intercept_value(
+        &mut self,
+        path: &str,
+        text: &str,
+        shape: &'static facet_core::Shape,
+    ) -> Option<String> {
+        let callback = self.intercept.as_ref()?;
+        callback(&FieldValue { path, text, shape })
+    }
+
+    fn has_context(&self) -> bool {
+        self.context.is_some()
+    }
+
+    fn resolve_in_context(
+        &mut self,
+        path: &str,
+        text: &str,
+        shape: &'static facet_core::Shape,
+    ) -> Option<String> {
+        let context = self.context.as_deref_mut()?;
+        context.resolve(&FieldValue { path, text, shape })
+    }
+
+    fn item_skipped(&mut self, path: &str, error: &dyn core::error::Error) {
+        if let Some(callback) = &self.on_skipped_item {
+            callback(&SkippedItem { path, error });
+        }
+    }
+
+    fn keeps_comments(&self) -> bool {
+        self.keep_comments
+    }
+
+    fn keeps_processing_instructions(&self) -> bool {
+        self.keep_processing_instructions
+    }
+
+    fn duplicate_policy(&self) -> DuplicatePolicy {
+        self.on_duplicate
+    }
+
+    fn locates_errors(&self) -> bool {
+        self.locate_errors
+    }
+
+    fn source_text(&self) -> Option<&str> {
+        // The input was checked for invalid UTF-8 up front
+        self.input
+            .and_then(|input| core::str::from_utf8(input).ok())
+    }
+
+    fn element_prefix(&self) -> Option<&str> {
+        self.element_prefix.as_deref()
+    }
+
+    fn peeked_attribute(&self, namespace: &str, name: &str) -> Option<&str> {
+        if !matches!(self.peeked, Some(DomEvent::NodeStart { .. })) {
+            return None;
+        }
+        self.pending_attrs
+            .iter()
+            .find(|(attr_ns, attr_name, _)| {
+                attr_name == name && attr_ns.as_deref() == Some(namespace)
+            })
+            .map(|(_, _, value)| value.as_ref())
+    }
+
     fn capture_raw_node(&mut self) -> Result<Option<Cow<'de, str>>, Self::Error> {
-        Ok(Some(self.do_capture_raw_node()?))
+        match self.input {
+            Some(input) => Ok(Some(self.do_capture_raw_node(input)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// `bytes`, read from the input between offsets `start` and `end`, as the
+/// same bytes of the input when parsing a slice, so values can borrow from
+/// it without a copy.
+///
+/// Returns `None` when reading from a stream, or when the bytes aren't in
+/// the input verbatim (e.g. after unescaping).
+fn borrow_input<'de>(
+    input: Option<&'de [u8]>,
+    bytes: &[u8],
+    start: u64,
+    end: u64,
+) -> Option<&'de str> {
+    let window = input?.get(usize::try_from(start).ok()?..usize::try_from(end).ok()?)?;
+    let offset = window
+        .windows(bytes.len().max(1))
+        .position(|candidate| candidate == bytes)?;
+    // The input was checked for invalid UTF-8 up front
+    core::str::from_utf8(&window[offset..offset + bytes.len()]).ok()
+}
+
+/// `text` with each run of whitespace collapsed to a single space, or empty
+/// if it is all whitespace.
+fn normalize_whitespace(text: &str) -> Cow<'_, str> {
+    let is_space = |c: char| matches!(c, ' ' | '\t' | '\n' | '\r');
+    if text.chars().all(is_space) {
+        return Cow::Borrowed("");
+    }
+    let mut normalized = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if is_space(c) {
+            if !in_space {
+                normalized.push(' ');
+            }
+            in_space = true;
+        } else {
+            normalized.push(c);
+            in_space = false;
+        }
+    }
+    if normalized == text {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(normalized)
+    }
+}
+
+/// Check that `input` is UTF-8 made of XML characters.
+///
+/// quick-xml only reports invalid UTF-8 where it decodes a name or value,
+/// and not at all in skipped content, so the whole input is scanned once up
+/// front to locate bad bytes exactly.
+fn check_input(input: &[u8]) -> Result<(), XmlError> {
+    let text = match core::str::from_utf8(input) {
+        Ok(text) => text,
+        Err(e) => {
+            let offset = e.valid_up_to();
+            let len = e.error_len().unwrap_or(input.len() - offset);
+            return Err(XmlError::InvalidInput {
+                offset: offset as u64,
+                character: None,
+                snippet: hex_snippet(input, offset, len),
+            });
+        }
+    };
+    match text.char_indices().find(|&(_, c)| !crate::is_xml_char(c)) {
+        Some((offset, c)) => Err(XmlError::InvalidInput {
+            offset: offset as u64,
+            character: Some(c),
+            snippet: hex_snippet(input, offset, c.len_utf8()),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Hex dump of up to 8 bytes either side of `input[offset..offset + len]`,
+/// which is bracketed, e.g. `3c 61 3e [ff] 3c 2f 61 3e`.
+fn hex_snippet(input: &[u8], offset: usize, len: usize) -> String {
+    use core::fmt::Write;
+
+    const CONTEXT: usize = 8;
+    let end = offset + len;
+    let mut out = String::new();
+    for (i, byte) in input
+        .iter()
+        .enumerate()
+        .take(end + CONTEXT)
+        .skip(offset.saturating_sub(CONTEXT))
+    {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        if i == offset {
+            out.push('[');
+        }
+        let _ = write!(out, "{byte:02x}");
+        if i + 1 == end {
+            out.push(']');
+        }
     }
+    out
 }
 
 /// Resolve a namespace from quick-xml's ResolveResult.
@@ -371,7 +1006,7 @@ fn resolve_namespace(resolve: ResolveResult<'_>) -> Result<Option<String>, XmlEr
 }
 
 /// Resolve a general entity reference.
-fn resolve_entity(raw: &str) -> Result<String, XmlError> {
+pub(crate) fn resolve_entity(raw: &str) -> Result<String, XmlError> {
     if let Some(resolved) = resolve_xml_entity(raw) {
         return Ok(resolved.into());
     }
@@ -385,8 +1020,10 @@ fn resolve_entity(raw: &str) -> Result<String, XmlError> {
                 .map_err(|_| XmlError::Parse(format!("Invalid decimal entity: #{}", rest)))?
         };
 
+        // References can't smuggle in characters the document itself can't hold
         let ch = char::from_u32(code)
-            .ok_or_else(|| XmlError::Parse(format!("Invalid Unicode: {}", code)))?;
+            .filter(|&c| is_xml_char(c))
+            .ok_or_else(|| XmlError::Parse(format!("Invalid character reference: #{}", rest)))?;
         return Ok(ch.to_string());
     }
 
@@ -0,0 +1,264 @@
+//! General entities declared in a document's internal DTD subset or
+//! supplied with the options.
+
+extern crate alloc;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use std::collections::HashMap;
+
+use crate::config::{DeserializeOptions, EntityLimits, ExternalEntity, ExternalEntityResolver};
+use crate::dom_parser::{XmlError, resolve_entity};
+
+/// A general entity declaration.
+enum Declaration {
+    /// `<!ENTITY name "replacement text">`
+    Internal(String),
+    /// `<!ENTITY name SYSTEM "uri">` or `<!ENTITY name PUBLIC "id" "uri">`
+    External {
+        public_id: Option<String>,
+        system_id: String,
+    },
+}
+
+/// Expands references to the entities a document declares, within the
+/// configured limits.
+#[derive(Default)]
+pub(crate) struct Entities {
+    declarations: HashMap<String, Declaration>,
+    expand_internal: bool,
+    resolve_external: Option<ExternalEntityResolver>,
+    /// Replacement text of entities supplied with the options
+    supplied: Arc<HashMap<String, String>>,
+    limits: EntityLimits,
+    /// Bytes produced by expansions so far in this document
+    expanded: usize,
+}
+
+impl Entities {
+    pub(crate) fn configure(&mut self, options: &DeserializeOptions) {
+        self.expand_internal = options.expand_internal_entities;
+        self.resolve_external = options.resolve_external_entity.clone();
+        self.supplied = options.entities.clone();
+        self.limits = options.entity_limits;
+    }
+
+    /// Whether neither the document nor the options declare any entities, so
+    /// references can only be to predefined entities and characters.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.declarations.is_empty() && self.supplied.is_empty()
+    }
+
+    /// Record the general entities declared in the internal subset of a
+    /// `<!DOCTYPE ...>` (given without the `<!DOCTYPE` and `>`).
+    ///
+    /// Parameter entities and unparsed (`NDATA`) entities are ignored. When
+    /// an entity is declared twice, the first declaration wins.
+    pub(crate) fn declare_from_doctype(&mut self, doctype: &str) {
+        let Some(start) = doctype.find('[') else {
+            return;
+        };
+        let mut rest = &doctype[start + 1..];
+        loop {
+            // Comments may mention declarations without making them
+            let next_decl = rest.find("<!ENTITY");
+            if let Some(comment) = rest.find("<!--")
+                && next_decl.is_none_or(|decl| comment < decl)
+            {
+                match rest[comment..].find("-->") {
+                    Some(end) => rest = &rest[comment + end + 3..],
+                    None => return,
+                }
+                continue;
+            }
+            let Some(decl) = next_decl else {
+                return;
+            };
+            rest = &rest[decl + "<!ENTITY".len()..];
+            if let Some((name, declaration, after)) = parse_declaration(rest) {
+                self.declarations.entry(name).or_insert(declaration);
+                rest = after;
+            }
+        }
+    }
+
+    /// Expand a reference to `name` in element content, or return `None`
+    /// when neither the document nor the options declare it, or it isn't to
+    /// be expanded.
+    pub(crate) fn expand(&mut self, name: &str) -> Result<Option<String>, XmlError> {
+        let mut out = String::new();
+        if !self.expand_into(name, 0, &mut out)? {
+            return Ok(None);
+        }
+        self.expanded += out.len();
+        Ok(Some(out))
+    }
+
+    /// Replace the references in an attribute value.
+    ///
+    /// References to predefined entities and characters are resolved,
+    /// declared entities expanded; a reference to a declared entity that
+    /// isn't to be expanded is kept as written, and any other reference is
+    /// an error.
+    pub(crate) fn unescape_attribute(&mut self, raw: &str) -> Result<String, XmlError> {
+        let mut out = String::with_capacity(raw.len());
+        let mut rest = raw;
+        while let Some(amp) = rest.find('&') {
+            out.push_str(&rest[..amp]);
+            let Some(end) = rest[amp..].find(';') else {
+                return Err(XmlError::Parse(format!(
+                    "unterminated entity reference in attribute value `{raw}`"
+                )));
+            };
+            let name = &rest[amp + 1..amp + end];
+            if is_predefined(name) {
+                out.push_str(&resolve_entity(name)?);
+            } else if let Some(expanded) = self.expand(name)? {
+                out.push_str(&expanded);
+            } else if self.declarations.contains_key(name) {
+                out.push_str(&rest[amp..=amp + end]);
+            } else {
+                return Err(XmlError::Parse(format!("unrecognized entity `{name}`")));
+            }
+            rest = &rest[amp + end + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    /// Append the expansion of `name` to `out`, returning `false` if it isn't
+    /// declared or isn't to be expanded.
+    fn expand_into(
+        &mut self,
+        name: &str,
+        depth: usize,
+        out: &mut String,
+    ) -> Result<bool, XmlError> {
+        let declaration = match self.declarations.get(name) {
+            Some(Declaration::Internal(_)) if !self.expand_internal => return Ok(false),
+            Some(Declaration::External { .. }) if self.resolve_external.is_none() => {
+                return Ok(false);
+            }
+            Some(declaration) => Some(declaration),
+            None if self.supplied.contains_key(name) => None,
+            None => return Ok(false),
+        };
+        // Also stops entities that refer to themselves
+        if depth >= self.limits.max_depth {
+            return Err(XmlError::EntityLimit {
+                entity: name.to_string(),
+            });
+        }
+        let replacement = match declaration {
+            None => self.supplied[name].clone(),
+            Some(Declaration::Internal(text)) => text.clone(),
+            Some(Declaration::External {
+                public_id,
+                system_id,
+            }) => {
+                let resolve = self.resolve_external.as_ref().expect("checked above");
+                resolve(&ExternalEntity {
+                    name,
+                    public_id: public_id.as_deref(),
+                    system_id,
+                })
+                .ok_or_else(|| XmlError::UnresolvedEntity(name.to_string()))?
+            }
+        };
+
+        let mut rest = replacement.as_str();
+        while let Some(amp) = rest.find('&') {
+            out.push_str(&rest[..amp]);
+            let reference = rest[amp..]
+                .find(';')
+                .map(|end| (&rest[amp + 1..amp + end], amp + end + 1));
+            match reference {
+                Some((inner, after)) => {
+                    if is_predefined(inner) {
+                        out.push_str(&resolve_entity(inner)?);
+                    } else if !self.expand_into(inner, depth + 1, out)? {
+                        out.push_str(&rest[amp..after]);
+                    }
+                    rest = &rest[after..];
+                }
+                None => {
+                    out.push('&');
+                    rest = &rest[amp + 1..];
+                }
+            }
+            self.check_size(name, out)?;
+        }
+        out.push_str(rest);
+        self.check_size(name, out)?;
+        Ok(true)
+    }
+
+    fn check_size(&self, name: &str, out: &str) -> Result<(), XmlError> {
+        if self.expanded + out.len() > self.limits.max_expanded_bytes {
+            return Err(XmlError::EntityLimit {
+                entity: name.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Whether `name` is a character reference or one of the five entities
+/// every XML document has.
+fn is_predefined(name: &str) -> bool {
+    name.starts_with('#') || matches!(name, "amp" | "lt" | "gt" | "quot" | "apos")
+}
+
+/// Parse the rest of an `<!ENTITY` declaration, returning the entity name,
+/// its declaration and the input after the closing `>`, or `None` for a
+/// malformed declaration or one that is ignored.
+fn parse_declaration(input: &str) -> Option<(String, Declaration, &str)> {
+    let input = input.trim_start();
+    // Parameter entities only matter inside the DTD itself
+    let parameter = input.starts_with('%');
+    let input = input.strip_prefix('%').unwrap_or(input).trim_start();
+
+    let name_end = input.find(|c: char| c.is_whitespace())?;
+    let (name, input) = input.split_at(name_end);
+    let input = input.trim_start();
+
+    let (declaration, input) = if let Some(rest) = input.strip_prefix("SYSTEM") {
+        let (system_id, rest) = quoted(rest)?;
+        (
+            Declaration::External {
+                public_id: None,
+                system_id,
+            },
+            rest,
+        )
+    } else if let Some(rest) = input.strip_prefix("PUBLIC") {
+        let (public_id, rest) = quoted(rest)?;
+        let (system_id, rest) = quoted(rest)?;
+        (
+            Declaration::External {
+                public_id: Some(public_id),
+                system_id,
+            },
+            rest,
+        )
+    } else {
+        let (value, rest) = quoted(input)?;
+        (Declaration::Internal(value), rest)
+    };
+
+    let end = input.find('>')?;
+    if parameter || input[..end].contains("NDATA") {
+        return None;
+    }
+    Some((name.to_string(), declaration, &input[end + 1..]))
+}
+
+/// Read a single- or double-quoted literal after optional whitespace.
+fn quoted(input: &str) -> Option<(String, &str)> {
+    let input = input.trim_start();
+    let quote = input.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let body = &input[1..];
+    let end = body.find(quote)?;
+    Some((body[..end].to_string(), &body[end + 1..]))
+}
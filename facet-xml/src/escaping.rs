@@ -6,6 +6,7 @@ use std::io::{self, Write};
 pub struct EscapingWriter<'a> {
     inner: &'a mut dyn Write,
     escape_quotes: bool,
+    escape_non_ascii: bool,
 }
 
 impl<'a> EscapingWriter<'a> {
@@ -15,6 +16,7 @@ impl<'a> EscapingWriter<'a> {
         Self {
             inner,
             escape_quotes: false,
+            escape_non_ascii: false,
         }
     }
 
@@ -24,21 +26,59 @@ impl<'a> EscapingWriter<'a> {
         Self {
             inner,
             escape_quotes: true,
+            escape_non_ascii: false,
         }
     }
+
+    /// Also write non-ASCII characters as numeric character references
+    /// (`&#xE9;`), so the output is pure ASCII.
+    pub fn non_ascii(mut self, escape: bool) -> Self {
+        self.escape_non_ascii = escape;
+        self
+    }
+
+    /// Write `buf`, escaping the markup characters.
+    fn write_escaped(&mut self, buf: &[u8]) -> io::Result<()> {
+        // Copy runs of bytes that need no escaping in one go
+        let mut start = 0;
+        for (i, &b) in buf.iter().enumerate() {
+            let escaped: &[u8] = match b {
+                b'&' => b"&amp;",
+                b'<' => b"&lt;",
+                b'>' => b"&gt;",
+                b'"' if self.escape_quotes => b"&quot;",
+                _ => continue,
+            };
+            self.inner.write_all(&buf[start..i])?;
+            self.inner.write_all(escaped)?;
+            start = i + 1;
+        }
+        self.inner.write_all(&buf[start..])
+    }
 }
 
 impl Write for EscapingWriter<'_> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        for &b in buf {
-            match b {
-                b'&' => self.inner.write_all(b"&amp;")?,
-                b'<' => self.inner.write_all(b"&lt;")?,
-                b'>' => self.inner.write_all(b"&gt;")?,
-                b'"' if self.escape_quotes => self.inner.write_all(b"&quot;")?,
-                _ => self.inner.write_all(&[b])?,
+        if !self.escape_non_ascii || buf.is_ascii() {
+            self.write_escaped(buf)?;
+            return Ok(buf.len());
+        }
+
+        let valid = match core::str::from_utf8(buf) {
+            Ok(text) => text,
+            Err(e) => core::str::from_utf8(&buf[..e.valid_up_to()]).expect("valid prefix"),
+        };
+        let mut start = 0;
+        for (i, c) in valid.char_indices() {
+            if !c.is_ascii() {
+                self.write_escaped(&valid.as_bytes()[start..i])?;
+                write!(self.inner, "&#x{:X};", c as u32)?;
+                start = i + c.len_utf8();
             }
         }
+        self.write_escaped(&valid.as_bytes()[start..])?;
+        // Bytes that aren't UTF-8 have no character to refer to
+        self.write_escaped(&buf[valid.len()..])?;
         Ok(buf.len())
     }
 
@@ -144,4 +184,32 @@ mod tests {
         writer.write_all(b"c").unwrap();
         assert_eq!(buf, b"a &lt; b &amp; c");
     }
+
+    #[test]
+    fn non_ascii_becomes_character_references() {
+        let mut buf = Vec::new();
+        EscapingWriter::attribute(&mut buf)
+            .non_ascii(true)
+            .write_all("café & 😀\"".as_bytes())
+            .unwrap();
+        assert_eq!(buf, b"caf&#xE9; &amp; &#x1F600;&quot;");
+    }
+
+    #[test]
+    fn non_ascii_is_kept_by_default() {
+        let mut buf = Vec::new();
+        EscapingWriter::text(&mut buf)
+            .write_all("café".as_bytes())
+            .unwrap();
+        assert_eq!(buf, "café".as_bytes());
+    }
+
+    #[test]
+    fn escapes_at_boundaries_and_adjacent() {
+        let mut buf = Vec::new();
+        EscapingWriter::attribute(&mut buf)
+            .write_all(b"<\"&&>x\"")
+            .unwrap();
+        assert_eq!(buf, b"&lt;&quot;&amp;&amp;&gt;x&quot;");
+    }
 }
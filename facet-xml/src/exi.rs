@@ -0,0 +1,640 @@
+//! Efficient XML Interchange (EXI) input, enabled by the `exi` feature.
+//!
+//! Decodes schema-less EXI streams (built-in grammars, bit-packed alignment,
+//! default options) into the same [`DomEvent`]s [`XmlParser`](crate::XmlParser)
+//! produces, so any type that deserializes from XML deserializes from EXI.
+//! Streams that carry an options header, use schema-informed grammars, or
+//! preserve comments, processing instructions, DTDs or prefixes are rejected
+//! with [`ExiError::Unsupported`].
+
+extern crate alloc;
+
+use alloc::{
+    borrow::Cow,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+use std::collections::HashMap;
+
+use facet_core::Facet;
+use facet_dom::{DomEvent, DomParser, VecEventParser};
+
+use crate::{DeserializeError, XML_NAMESPACE};
+
+const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// Error decoding an EXI stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExiError {
+    /// The stream doesn't start with a valid EXI header.
+    InvalidHeader(&'static str),
+    /// The stream uses an EXI feature this decoder doesn't implement.
+    Unsupported(&'static str),
+    /// The stream ended in the middle of an event.
+    UnexpectedEof,
+    /// The stream is not valid EXI.
+    Malformed(String),
+}
+
+impl fmt::Display for ExiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExiError::InvalidHeader(msg) => write!(f, "invalid EXI header: {msg}"),
+            ExiError::Unsupported(what) => write!(f, "unsupported EXI feature: {what}"),
+            ExiError::UnexpectedEof => write!(f, "unexpected end of EXI stream"),
+            ExiError::Malformed(msg) => write!(f, "malformed EXI stream: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for ExiError {}
+
+/// A [`DomParser`] over an EXI stream.
+///
+/// The whole stream is decoded when the parser is created; events are then
+/// replayed from memory.
+pub struct ExiParser {
+    events: VecEventParser<'static>,
+}
+
+impl ExiParser {
+    /// Decode `input`, which may start with the optional `$EXI` cookie.
+    pub fn new(input: &[u8]) -> Result<Self, ExiError> {
+        let events = Decoder::new(input).decode()?;
+        Ok(Self {
+            events: VecEventParser::new(events).format_namespace("xml"),
+        })
+    }
+}
+
+impl<'de> DomParser<'de> for ExiParser {
+    type Error = ExiError;
+
+    fn next_event(&mut self) -> Result<Option<DomEvent<'de>>, Self::Error> {
+        self.events.next_event().map_err(|e| match e {})
+    }
+
+    fn peek_event(&mut self) -> Result<Option<&DomEvent<'de>>, Self::Error> {
+        self.events.peek_event().map_err(|e| match e {})
+    }
+
+    fn skip_node(&mut self) -> Result<(), Self::Error> {
+        self.events.skip_node().map_err(|e| match e {})
+    }
+
+    fn format_namespace(&self) -> Option<&'static str> {
+        self.events.format_namespace()
+    }
+}
+
+/// Deserialize a value from an EXI stream into an owned type.
+///
+/// The type is mapped exactly as for [`from_slice`](crate::from_slice): an
+/// EXI encoding of a document deserializes to the same value as the
+/// document's text.
+pub fn from_exi<T>(input: &[u8]) -> Result<T, DeserializeError<ExiError>>
+where
+    T: Facet<'static>,
+{
+    enter_span!("exi_document", bytes = input.len());
+    let parser = ExiParser::new(input).map_err(DeserializeError::Parser)?;
+    let value = facet_dom::DomDeserializer::new_owned(parser).deserialize()?;
+    crate::assertions::check(&value).map_err(|e| match e {
+        DeserializeError::AssertionFailed { path, message } => {
+            DeserializeError::AssertionFailed { path, message }
+        }
+        DeserializeError::Validation { path, message } => {
+            DeserializeError::Validation { path, message }
+        }
+        // The checks fail with nothing else
+        _ => unreachable!("unexpected assertion error: {e}"),
+    })?;
+    Ok(value)
+}
+
+/// Number of bits needed to tell `n` choices apart.
+fn bits_for(n: usize) -> u32 {
+    if n <= 1 {
+        0
+    } else {
+        usize::BITS - (n - 1).leading_zeros()
+    }
+}
+
+/// Reads a bit-packed stream, most significant bit first.
+struct BitReader<'a> {
+    input: &'a [u8],
+    /// Position in bits
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn read_bits(&mut self, n: u32) -> Result<u32, ExiError> {
+        let mut value = 0;
+        for _ in 0..n {
+            let byte = *self
+                .input
+                .get(self.pos / 8)
+                .ok_or(ExiError::UnexpectedEof)?;
+            let bit = (byte >> (7 - self.pos % 8)) & 1;
+            value = (value << 1) | u32::from(bit);
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    /// An Unsigned Integer: 7-bit groups, least significant first, each with
+    /// a continuation flag in its high bit.
+    fn read_unsigned(&mut self) -> Result<u64, ExiError> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let group = self.read_bits(8)?;
+            if shift > 63 {
+                return Err(ExiError::Malformed("unsigned integer too large".into()));
+            }
+            value |= u64::from(group & 0x7f) << shift;
+            if group & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn read_length(&mut self) -> Result<usize, ExiError> {
+        usize::try_from(self.read_unsigned()?)
+            .map_err(|_| ExiError::Malformed("length too large".into()))
+    }
+
+    /// A String of `len` code points, each an Unsigned Integer.
+    fn read_chars(&mut self, len: usize) -> Result<String, ExiError> {
+        let mut s = String::with_capacity(len);
+        for _ in 0..len {
+            let code = self.read_unsigned()?;
+            let c = u32::try_from(code)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| ExiError::Malformed(format!("invalid code point {code:#x}")))?;
+            s.push(c);
+        }
+        Ok(s)
+    }
+
+    fn read_string(&mut self) -> Result<String, ExiError> {
+        let len = self.read_length()?;
+        self.read_chars(len)
+    }
+}
+
+/// An expanded name, as indices into the URI and local-name tables.
+type QName = (usize, usize);
+
+/// A production of a built-in element grammar, learned from the stream.
+#[derive(Debug, Clone, Copy)]
+enum Production {
+    Attribute(QName),
+    Element(QName),
+    Characters,
+    EndElement,
+}
+
+/// The learned productions of one element's grammars, most recent first.
+#[derive(Debug, Default)]
+struct ElementGrammar {
+    start_tag: Vec<Production>,
+    content: Vec<Production>,
+}
+
+impl ElementGrammar {
+    fn productions(&mut self, in_content: bool) -> &mut Vec<Production> {
+        if in_content {
+            &mut self.content
+        } else {
+            &mut self.start_tag
+        }
+    }
+}
+
+/// An open element.
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    qname: QName,
+    /// Past the attributes, in ElementContent
+    in_content: bool,
+}
+
+struct Decoder<'a> {
+    bits: BitReader<'a>,
+    uris: Vec<String>,
+    /// Local names, partitioned by URI
+    local_names: Vec<Vec<String>>,
+    global_values: Vec<String>,
+    /// Indices into `global_values`, partitioned by qname
+    local_values: HashMap<QName, Vec<usize>>,
+    grammars: HashMap<QName, ElementGrammar>,
+    stack: Vec<Frame>,
+    events: Vec<DomEvent<'static>>,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        let mut local_names = alloc::vec![Vec::new(); 3];
+        local_names[1] = ["base", "id", "lang", "space"].map(String::from).to_vec();
+        local_names[2] = ["nil", "type"].map(String::from).to_vec();
+        Self {
+            bits: BitReader { input, pos: 0 },
+            uris: alloc::vec![String::new(), XML_NAMESPACE.into(), XSI_NAMESPACE.into()],
+            local_names,
+            global_values: Vec::new(),
+            local_values: HashMap::new(),
+            grammars: HashMap::new(),
+            stack: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    fn decode(mut self) -> Result<Vec<DomEvent<'static>>, ExiError> {
+        self.read_header()?;
+
+        // The document grammar has a single choice at each step (SD, then
+        // SE(*), then ED once the root closes), so none of them take bits
+        let root = self.read_qname()?;
+        self.start_element(root);
+        while let Some(&frame) = self.stack.last() {
+            let production = self.next_production(frame)?;
+            self.apply(frame, production)?;
+        }
+        Ok(self.events)
+    }
+
+    fn read_header(&mut self) -> Result<(), ExiError> {
+        if self.bits.input.starts_with(b"$EXI") {
+            self.bits.pos = 32;
+        }
+        if self.bits.read_bits(2)? != 0b10 {
+            return Err(ExiError::InvalidHeader("missing distinguishing bits"));
+        }
+        if self.bits.read_bits(1)? == 1 {
+            return Err(ExiError::Unsupported("options in header"));
+        }
+        if self.bits.read_bits(1)? == 1 {
+            return Err(ExiError::Unsupported("preview version"));
+        }
+        let mut version = 1;
+        loop {
+            let chunk = self.bits.read_bits(4)?;
+            version += chunk;
+            if chunk != 15 {
+                break;
+            }
+        }
+        if version != 1 {
+            return Err(ExiError::Unsupported("format version other than 1"));
+        }
+        Ok(())
+    }
+
+    /// Read an event code in the current grammar of `frame`, learning the
+    /// production when it was matched through a wildcard.
+    fn next_production(&mut self, frame: Frame) -> Result<Production, ExiError> {
+        let learned = self
+            .grammars
+            .entry(frame.qname)
+            .or_default()
+            .productions(frame.in_content)
+            .len();
+
+        // StartTagContent: learned productions, then the escape to the
+        // second level. ElementContent: learned productions, EE, escape.
+        let escape = if frame.in_content {
+            learned + 1
+        } else {
+            learned
+        };
+        let code = self.bits.read_bits(bits_for(escape + 1))? as usize;
+        if code < learned {
+            let grammar = self.grammars.get_mut(&frame.qname).expect("created above");
+            return Ok(grammar.productions(frame.in_content)[code]);
+        }
+        if code != escape {
+            return Ok(Production::EndElement);
+        }
+
+        let production = if frame.in_content {
+            match self.bits.read_bits(1)? {
+                0 => Production::Element(self.read_qname()?),
+                _ => Production::Characters,
+            }
+        } else {
+            match self.bits.read_bits(2)? {
+                0 => Production::EndElement,
+                1 => Production::Attribute(self.read_qname()?),
+                2 => Production::Element(self.read_qname()?),
+                _ => Production::Characters,
+            }
+        };
+        let grammar = self.grammars.get_mut(&frame.qname).expect("created above");
+        grammar.productions(frame.in_content).insert(0, production);
+        Ok(production)
+    }
+
+    fn apply(&mut self, frame: Frame, production: Production) -> Result<(), ExiError> {
+        if let Production::Attribute(qname) = production {
+            let value = self.read_attribute_value(qname)?;
+            let (name, namespace) = self.names(qname);
+            self.events.push(DomEvent::Attribute {
+                name,
+                value: Cow::Owned(value),
+                namespace,
+            });
+            return Ok(());
+        }
+
+        if !frame.in_content {
+            self.events.push(DomEvent::ChildrenStart);
+            if let Some(top) = self.stack.last_mut() {
+                top.in_content = true;
+            }
+        }
+        match production {
+            Production::Element(qname) => self.start_element(qname),
+            Production::Characters => {
+                let text = self.read_value(frame.qname)?;
+                let trimmed = text.trim();
+                if !trimmed.is_empty() {
+                    self.events
+                        .push(DomEvent::Text(Cow::Owned(trimmed.to_string())));
+                }
+            }
+            Production::EndElement => {
+                self.events.push(DomEvent::ChildrenEnd);
+                self.events.push(DomEvent::NodeEnd);
+                self.stack.pop();
+            }
+            Production::Attribute(_) => unreachable!("handled above"),
+        }
+        Ok(())
+    }
+
+    fn start_element(&mut self, qname: QName) {
+        let (tag, namespace) = self.names(qname);
+        self.events.push(DomEvent::NodeStart { tag, namespace });
+        self.stack.push(Frame {
+            qname,
+            in_content: false,
+        });
+    }
+
+    fn names(&self, (uri, local): QName) -> (Cow<'static, str>, Option<Cow<'static, str>>) {
+        let namespace = &self.uris[uri];
+        (
+            Cow::Owned(self.local_names[uri][local].clone()),
+            (!namespace.is_empty()).then(|| Cow::Owned(namespace.clone())),
+        )
+    }
+
+    fn read_qname(&mut self) -> Result<QName, ExiError> {
+        let uri = self.read_uri()?;
+        let local = self.read_local_name(uri)?;
+        Ok((uri, local))
+    }
+
+    fn read_uri(&mut self) -> Result<usize, ExiError> {
+        let code = self.bits.read_bits(bits_for(self.uris.len() + 1))? as usize;
+        match code {
+            0 => {
+                let uri = self.bits.read_string()?;
+                self.uris.push(uri);
+                self.local_names.push(Vec::new());
+                Ok(self.uris.len() - 1)
+            }
+            hit if hit <= self.uris.len() => Ok(hit - 1),
+            _ => Err(ExiError::Malformed(format!("URI id {code} out of range"))),
+        }
+    }
+
+    fn read_local_name(&mut self, uri: usize) -> Result<usize, ExiError> {
+        let partition = &self.local_names[uri];
+        match self.bits.read_length()? {
+            0 => {
+                let id = self.bits.read_bits(bits_for(partition.len()))? as usize;
+                if id < partition.len() {
+                    Ok(id)
+                } else {
+                    Err(ExiError::Malformed(format!(
+                        "local name id {id} out of range"
+                    )))
+                }
+            }
+            len => {
+                let name = self.bits.read_chars(len - 1)?;
+                let partition = &mut self.local_names[uri];
+                partition.push(name);
+                Ok(partition.len() - 1)
+            }
+        }
+    }
+
+    fn read_attribute_value(&mut self, qname: QName) -> Result<String, ExiError> {
+        // xsi:type holds a QName and xsi:nil a Boolean, not a string
+        match self.names(qname) {
+            (name, Some(ns)) if ns == XSI_NAMESPACE && name == "type" => {
+                let type_name = self.read_qname()?;
+                Ok(self.names(type_name).0.into_owned())
+            }
+            (name, Some(ns)) if ns == XSI_NAMESPACE && name == "nil" => {
+                Ok(if self.bits.read_bits(1)? == 1 {
+                    "true".into()
+                } else {
+                    "false".into()
+                })
+            }
+            _ => self.read_value(qname),
+        }
+    }
+
+    /// A value, through the local table of `qname` or the global table.
+    fn read_value(&mut self, qname: QName) -> Result<String, ExiError> {
+        match self.bits.read_length()? {
+            0 => {
+                let local = self.local_values.get(&qname).map_or(&[][..], Vec::as_slice);
+                let id = self.bits.read_bits(bits_for(local.len()))? as usize;
+                local
+                    .get(id)
+                    .map(|&global| self.global_values[global].clone())
+                    .ok_or_else(|| ExiError::Malformed(format!("local value id {id} out of range")))
+            }
+            1 => {
+                let id = self.bits.read_bits(bits_for(self.global_values.len()))? as usize;
+                self.global_values.get(id).cloned().ok_or_else(|| {
+                    ExiError::Malformed(format!("global value id {id} out of range"))
+                })
+            }
+            len => {
+                let value = self.bits.read_chars(len - 2)?;
+                if !value.is_empty() {
+                    self.local_values
+                        .entry(qname)
+                        .or_default()
+                        .push(self.global_values.len());
+                    self.global_values.push(value.clone());
+                }
+                Ok(value)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the bit-packed encoding a schema-less EXI encoder would produce.
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        len: usize,
+    }
+
+    impl BitWriter {
+        fn bits(&mut self, value: u32, n: u32) -> &mut Self {
+            for i in (0..n).rev() {
+                if self.len % 8 == 0 {
+                    self.bytes.push(0);
+                }
+                let bit = ((value >> i) & 1) as u8;
+                *self.bytes.last_mut().unwrap() |= bit << (7 - self.len % 8);
+                self.len += 1;
+            }
+            self
+        }
+
+        fn unsigned(&mut self, mut value: u32) -> &mut Self {
+            loop {
+                let group = value & 0x7f;
+                value >>= 7;
+                if value == 0 {
+                    return self.bits(group, 8);
+                }
+                self.bits(group | 0x80, 8);
+            }
+        }
+
+        fn chars(&mut self, s: &str) -> &mut Self {
+            for c in s.chars() {
+                self.unsigned(c as u32);
+            }
+            self
+        }
+
+        /// A local-name miss in the empty namespace.
+        fn new_name(&mut self, uri_bits: u32, name: &str) -> &mut Self {
+            self.bits(1, uri_bits)
+                .unsigned(name.chars().count() as u32 + 1)
+                .chars(name)
+        }
+
+        /// A value miss.
+        fn new_value(&mut self, value: &str) -> &mut Self {
+            self.unsigned(value.chars().count() as u32 + 2).chars(value)
+        }
+    }
+
+    fn events(input: &[u8]) -> Result<Vec<DomEvent<'static>>, ExiError> {
+        Decoder::new(input).decode()
+    }
+
+    #[test]
+    fn decodes_elements_attributes_and_text() {
+        // <point x="3"><label>origin</label></point>
+        let mut w = BitWriter::default();
+        w.bits(0b1000_0000, 8) // header: no options, version 1
+            .new_name(2, "point")
+            .bits(0b01, 2) // AT(*)
+            .new_name(2, "x")
+            .new_value("3")
+            .bits(1, 1) // escape past the learned AT(x)
+            .bits(0b10, 2) // SE(*)
+            .new_name(2, "label")
+            .bits(0b11, 2) // CH
+            .new_value("origin")
+            .bits(0, 1) // EE </label>
+            .bits(0, 1); // EE </point>
+
+        let node = |tag: &str| DomEvent::NodeStart {
+            tag: Cow::Owned(tag.into()),
+            namespace: None,
+        };
+        assert_eq!(
+            events(&w.bytes).unwrap(),
+            [
+                node("point"),
+                DomEvent::Attribute {
+                    name: "x".into(),
+                    value: "3".into(),
+                    namespace: None,
+                },
+                DomEvent::ChildrenStart,
+                node("label"),
+                DomEvent::ChildrenStart,
+                DomEvent::Text("origin".into()),
+                DomEvent::ChildrenEnd,
+                DomEvent::NodeEnd,
+                DomEvent::ChildrenEnd,
+                DomEvent::NodeEnd,
+            ]
+        );
+
+        // Same stream behind the cookie
+        let mut with_cookie = b"$EXI".to_vec();
+        with_cookie.extend_from_slice(&w.bytes);
+        assert_eq!(events(&with_cookie), events(&w.bytes));
+    }
+
+    #[test]
+    fn reuses_learned_productions_and_string_tables() {
+        // <list><item>a</item><item>a</item></list>
+        let mut w = BitWriter::default();
+        w.bits(0b1000_0000, 8)
+            .new_name(2, "list")
+            .bits(0b10, 2) // SE(*) from StartTagContent
+            .new_name(2, "item")
+            .bits(0b11, 2) // CH
+            .new_value("a")
+            .bits(0, 1) // EE </item>
+            .bits(1, 1) // escape: list's ElementContent hasn't learned anything yet
+            .bits(0, 1) // SE(*)
+            .bits(1, 2) // URI hit: ""
+            .unsigned(0) // local-name hit...
+            .bits(1, 1) // ...second name in the "" partition
+            .bits(0, 1) // learned CH
+            .unsigned(0) // local value hit, the only value of item (no id bits)
+            .bits(0, 1) // EE </item>
+            .bits(1, 2); // EE </list>, after the learned SE(item)
+
+        let texts: Vec<_> = events(&w.bytes)
+            .unwrap()
+            .into_iter()
+            .filter_map(|event| match event {
+                DomEvent::Text(text) => Some(text.into_owned()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, ["a", "a"]);
+    }
+
+    #[test]
+    fn rejects_headers_it_cannot_decode() {
+        assert_eq!(
+            events(&[0b0000_0000]),
+            Err(ExiError::InvalidHeader("missing distinguishing bits"))
+        );
+        assert_eq!(
+            events(&[0b1010_0000]),
+            Err(ExiError::Unsupported("options in header"))
+        );
+        assert_eq!(events(&[]), Err(ExiError::UnexpectedEof));
+    }
+}
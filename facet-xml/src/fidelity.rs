@@ -0,0 +1,378 @@
+//! Reports what a type loses when a document round-trips through it.
+
+extern crate alloc;
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use std::collections::HashMap;
+
+use facet_core::Facet;
+use facet_dom::{DomEvent, DomParser};
+
+use crate::serializer::XmlSerializeError;
+use crate::{DeserializeError, SerializeError, XmlError, XmlParser};
+
+/// A piece of information present in the input but missing or different
+/// after deserializing into a type and serializing back.
+///
+/// `path` locates the element, as slash-separated tags from the root; an
+/// index like `item[1]` is added where a tag repeats among siblings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Loss {
+    /// An attribute that wasn't written back.
+    DroppedAttribute {
+        path: String,
+        name: String,
+        value: String,
+    },
+    /// An attribute written back with a different value.
+    ChangedAttribute {
+        path: String,
+        name: String,
+        before: String,
+        after: String,
+    },
+    /// An element (with its whole subtree) that wasn't written back.
+    DroppedElement { path: String },
+    /// Text content that wasn't written back.
+    DroppedText { path: String, text: String },
+    /// Text content written back differently.
+    ChangedText {
+        path: String,
+        before: String,
+        after: String,
+    },
+    /// A comment that wasn't written back.
+    DroppedComment { path: String, text: String },
+    /// A processing instruction that wasn't written back.
+    DroppedProcessingInstruction { path: String, target: String },
+    /// Child elements written back in a different order.
+    ReorderedChildren { path: String },
+}
+
+impl core::fmt::Display for Loss {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Loss::DroppedAttribute { path, name, value } => {
+                write!(f, "{path}: attribute `{name}` dropped (was {value:?})")
+            }
+            Loss::ChangedAttribute {
+                path,
+                name,
+                before,
+                after,
+            } => write!(
+                f,
+                "{path}: attribute `{name}` changed from {before:?} to {after:?}"
+            ),
+            Loss::DroppedElement { path } => write!(f, "{path}: element dropped"),
+            Loss::DroppedText { path, text } => write!(f, "{path}: text {text:?} dropped"),
+            Loss::ChangedText {
+                path,
+                before,
+                after,
+            } => write!(f, "{path}: text changed from {before:?} to {after:?}"),
+            Loss::DroppedComment { path, text } => write!(f, "{path}: comment {text:?} dropped"),
+            Loss::DroppedProcessingInstruction { path, target } => {
+                write!(f, "{path}: processing instruction `{target}` dropped")
+            }
+            Loss::ReorderedChildren { path } => write!(f, "{path}: child elements reordered"),
+        }
+    }
+}
+
+/// Everything lost by one round trip, in document order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FidelityReport {
+    /// The differences found.
+    pub losses: Vec<Loss>,
+}
+
+impl FidelityReport {
+    /// Whether the round trip preserved everything that is compared.
+    pub fn is_lossless(&self) -> bool {
+        self.losses.is_empty()
+    }
+}
+
+impl core::fmt::Display for FidelityReport {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.losses.is_empty() {
+            return f.write_str("lossless round trip");
+        }
+        for loss in &self.losses {
+            writeln!(f, "{loss}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error from [`fidelity_report`]: the round trip itself failed.
+#[derive(Debug)]
+pub enum FidelityError {
+    /// The input couldn't be deserialized into the type.
+    Deserialize(DeserializeError<XmlError>),
+    /// The deserialized value couldn't be serialized again.
+    Serialize(SerializeError<XmlSerializeError>),
+}
+
+impl core::fmt::Display for FidelityError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FidelityError::Deserialize(e) => write!(f, "deserialization failed: {e}"),
+            FidelityError::Serialize(e) => write!(f, "serialization failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for FidelityError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FidelityError::Deserialize(e) => Some(e),
+            FidelityError::Serialize(e) => Some(e),
+        }
+    }
+}
+
+/// Deserialize `input` as `T`, serialize the result, and report what the
+/// round trip lost.
+///
+/// Attributes are compared as sets, text is compared trimmed, and
+/// whitespace-only text is ignored. Elements are matched to their
+/// counterparts by tag, in order. Attributes, elements or text added by the
+/// round trip (e.g. defaults) are not reported.
+///
+/// Use this to find which catch-all fields (flattened maps, `xml::elements`
+/// collections, ...) a type still needs to pass a document through unchanged.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+/// use facet_xml::{self as xml, Loss};
+///
+/// #[derive(Facet)]
+/// struct Point {
+///     #[facet(xml::attribute)]
+///     x: i32,
+/// }
+///
+/// let report = facet_xml::fidelity_report::<Point>(r#"<point x="1" y="2"><!-- origin --></point>"#)
+///     .unwrap();
+/// assert_eq!(
+///     report.losses,
+///     [
+///         Loss::DroppedAttribute { path: "point".into(), name: "y".into(), value: "2".into() },
+///         Loss::DroppedComment { path: "point".into(), text: " origin ".into() },
+///     ]
+/// );
+/// ```
+pub fn fidelity_report<T>(input: &str) -> Result<FidelityReport, FidelityError>
+where
+    T: Facet<'static>,
+{
+    let value: T = crate::from_str(input).map_err(FidelityError::Deserialize)?;
+    let output = crate::to_string(&value).map_err(FidelityError::Serialize)?;
+
+    let before = Node::parse(input).map_err(FidelityError::Deserialize)?;
+    let after = Node::parse(&output).map_err(FidelityError::Deserialize)?;
+
+    let mut report = FidelityReport::default();
+    compare(&before, &after, "", &mut report.losses);
+    Ok(report)
+}
+
+/// A parsed element, or the document itself (with an empty tag).
+#[derive(Debug, Default)]
+struct Node {
+    tag: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Child>,
+}
+
+#[derive(Debug)]
+enum Child {
+    Element(Node),
+    Text(String),
+    Comment(String),
+    ProcessingInstruction(String),
+}
+
+impl Node {
+    fn parse(input: &str) -> Result<Node, DeserializeError<XmlError>> {
+        let mut parser = XmlParser::new(input.as_bytes());
+        let mut stack = alloc::vec![Node::default()];
+        while let Some(event) = parser.next_event().map_err(DeserializeError::Parser)? {
+            let current = stack.last_mut().expect("document node is never popped");
+            match event {
+                DomEvent::NodeStart { tag, .. } => stack.push(Node {
+                    tag: tag.into_owned(),
+                    ..Node::default()
+                }),
+                DomEvent::Attribute { name, value, .. } => {
+                    current
+                        .attributes
+                        .push((name.into_owned(), value.into_owned()));
+                }
+                DomEvent::Text(text) => current.children.push(Child::Text(text.into_owned())),
+                DomEvent::Comment(text) => {
+                    current.children.push(Child::Comment(text.into_owned()));
+                }
+                DomEvent::ProcessingInstruction { target, .. } => {
+                    current
+                        .children
+                        .push(Child::ProcessingInstruction(target.into_owned()));
+                }
+                DomEvent::NodeEnd => {
+                    if stack.len() > 1 {
+                        let node = stack.pop().expect("checked above");
+                        let parent = stack.last_mut().expect("checked above");
+                        parent.children.push(Child::Element(node));
+                    }
+                }
+                DomEvent::ChildrenStart | DomEvent::ChildrenEnd | DomEvent::Doctype(_) => {}
+            }
+        }
+        stack.truncate(1);
+        Ok(stack.pop().unwrap_or_default())
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Node> {
+        self.children.iter().filter_map(|child| match child {
+            Child::Element(node) => Some(node),
+            _ => None,
+        })
+    }
+
+    fn text(&self) -> String {
+        let mut text = String::new();
+        for child in &self.children {
+            if let Child::Text(t) = child {
+                text.push_str(t);
+            }
+        }
+        text.trim().to_string()
+    }
+}
+
+fn compare(before: &Node, after: &Node, path: &str, losses: &mut Vec<Loss>) {
+    for (name, value) in &before.attributes {
+        match after.attributes.iter().find(|(n, _)| n == name) {
+            None => losses.push(Loss::DroppedAttribute {
+                path: path.into(),
+                name: name.clone(),
+                value: value.clone(),
+            }),
+            Some((_, after_value)) if after_value != value => {
+                losses.push(Loss::ChangedAttribute {
+                    path: path.into(),
+                    name: name.clone(),
+                    before: value.clone(),
+                    after: after_value.clone(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+
+    let (before_text, after_text) = (before.text(), after.text());
+    if !before_text.is_empty() && before_text != after_text {
+        losses.push(if after_text.is_empty() {
+            Loss::DroppedText {
+                path: path.into(),
+                text: before_text,
+            }
+        } else {
+            Loss::ChangedText {
+                path: path.into(),
+                before: before_text,
+                after: after_text,
+            }
+        });
+    }
+
+    let mut after_comments: Vec<&str> = after
+        .children
+        .iter()
+        .filter_map(|child| match child {
+            Child::Comment(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    for child in &before.children {
+        match child {
+            Child::Comment(text) => match after_comments.iter().position(|c| c == text) {
+                Some(idx) => {
+                    after_comments.remove(idx);
+                }
+                None => losses.push(Loss::DroppedComment {
+                    path: path.into(),
+                    text: text.clone(),
+                }),
+            },
+            Child::ProcessingInstruction(target)
+                if !after
+                    .children
+                    .iter()
+                    .any(|c| matches!(c, Child::ProcessingInstruction(t) if t == target)) =>
+            {
+                losses.push(Loss::DroppedProcessingInstruction {
+                    path: path.into(),
+                    target: target.clone(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    // Pair the k-th `<tag>` child before with the k-th `<tag>` child after
+    let mut after_by_tag: HashMap<&str, Vec<(usize, &Node)>> = HashMap::new();
+    for (position, node) in after.elements().enumerate() {
+        after_by_tag
+            .entry(node.tag.as_str())
+            .or_default()
+            .push((position, node));
+    }
+    let mut tag_counts: HashMap<&str, usize> = HashMap::new();
+    for node in before.elements() {
+        *tag_counts.entry(node.tag.as_str()).or_default() += 1;
+    }
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    let mut matched_positions = Vec::new();
+    let mut nested = Vec::new();
+    for node in before.elements() {
+        let k = seen.entry(node.tag.as_str()).or_default();
+        let child_path = match (path.is_empty(), tag_counts[node.tag.as_str()] > 1) {
+            (true, _) => node.tag.clone(),
+            (false, false) => format!("{path}/{}", node.tag),
+            (false, true) => format!("{path}/{}[{k}]", node.tag),
+        };
+        match after_by_tag
+            .get(node.tag.as_str())
+            .and_then(|nodes| nodes.get(*k))
+        {
+            Some(&(position, counterpart)) => {
+                matched_positions.push(position);
+                nested.push((node, counterpart, child_path));
+            }
+            None => nested.push((node, node, child_path)),
+        }
+        *k += 1;
+    }
+
+    if matched_positions.windows(2).any(|w| w[0] > w[1]) {
+        losses.push(Loss::ReorderedChildren { path: path.into() });
+    }
+
+    for (node, counterpart, child_path) in nested {
+        if core::ptr::eq(node, counterpart) {
+            losses.push(Loss::DroppedElement { path: child_path });
+        } else {
+            compare(node, counterpart, &child_path, losses);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! User callbacks run around the elements the serializer writes.
+
+use facet_reflect::Peek;
+
+use crate::XmlSerializer;
+
+/// Callbacks run around each element written for a struct, a map or a
+/// scalar field.
+///
+/// Content emitted in [`on_element_start`](Self::on_element_start) goes
+/// right before the element's start tag, and content emitted in
+/// [`on_element_end`](Self::on_element_end) right after its end tag. Both
+/// receive the value being written, so hooks can add provenance comments
+/// or checksums computed from it without a custom serializer.
+///
+/// ```
+/// use facet::Facet;
+/// use facet_reflect::Peek;
+/// use facet_xml::{HookOutput, SerializeHooks, SerializeOptions};
+///
+/// struct Provenance;
+///
+/// impl SerializeHooks for Provenance {
+///     fn on_element_start(&self, _tag: &str, value: Peek<'_, '_>, out: &mut HookOutput<'_>) {
+///         if value.shape().type_identifier == "Order" {
+///             out.comment("generated by order-export");
+///         }
+///     }
+/// }
+///
+/// #[derive(Facet)]
+/// struct Order {
+///     id: u32,
+/// }
+///
+/// let options = SerializeOptions::new().hooks(Provenance);
+/// let xml = facet_xml::to_string_with_options(&Order { id: 1 }, &options).unwrap();
+/// assert_eq!(xml, "<!--generated by order-export--><order><id>1</id></order>");
+/// ```
+pub trait SerializeHooks: Send + Sync {
+    /// Called before the start tag of the element `tag` holding `value`.
+    fn on_element_start(&self, _tag: &str, _value: Peek<'_, '_>, _out: &mut HookOutput<'_>) {}
+
+    /// Called after the end tag of the element `tag` holding `value`.
+    fn on_element_end(&self, _tag: &str, _value: Peek<'_, '_>, _out: &mut HookOutput<'_>) {}
+}
+
+/// Where [`SerializeHooks`] write their content.
+pub struct HookOutput<'a> {
+    pub(crate) serializer: &'a mut XmlSerializer,
+}
+
+impl HookOutput<'_> {
+    /// Write `<!--text-->`.
+    ///
+    /// `--` can't appear in a comment, so it is written as `- -`.
+    pub fn comment(&mut self, text: &str) {
+        self.serializer.write_comment(text);
+    }
+
+    /// Write `<?target data?>`.
+    ///
+    /// `?>` can't appear in the data, so it is written as `? >`.
+    pub fn processing_instruction(&mut self, target: &str, data: &str) {
+        self.serializer.write_processing_instruction(target, data);
+    }
+}
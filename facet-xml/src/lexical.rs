@@ -0,0 +1,185 @@
+//! Scalars that remember how they were written.
+
+use core::fmt;
+use core::ops::Deref;
+
+use facet::Facet;
+
+/// A scalar with a lexical form that [`Lexical`] can keep.
+///
+/// Implemented for the integer and float types and `bool`. Parsing is as
+/// lenient as XML Schema allows for the type (`01.50`, ` 7 `, `TRUE`, `1`);
+/// [`canonical`](Self::canonical) is what a value without an original form
+/// is written as.
+pub trait LexicalValue: Sized {
+    /// Parse `text`, or explain why it isn't a valid value.
+    fn parse_lexical(text: &str) -> Result<Self, String>;
+
+    /// The canonical text of this value.
+    fn canonical(&self) -> String;
+}
+
+macro_rules! impl_lexical_value {
+    ($($ty:ty),*) => {$(
+        impl LexicalValue for $ty {
+            fn parse_lexical(text: &str) -> Result<Self, String> {
+                let trimmed = text.trim();
+                trimmed
+                    .strip_prefix('+')
+                    .unwrap_or(trimmed)
+                    .parse()
+                    .map_err(|e| format!("invalid {} {text:?}: {e}", stringify!($ty)))
+            }
+
+            fn canonical(&self) -> String {
+                self.to_string()
+            }
+        }
+    )*};
+}
+
+impl_lexical_value!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64
+);
+
+impl LexicalValue for bool {
+    fn parse_lexical(text: &str) -> Result<Self, String> {
+        let trimmed = text.trim();
+        if trimmed.eq_ignore_ascii_case("true") || trimmed == "1" {
+            Ok(true)
+        } else if trimmed.eq_ignore_ascii_case("false") || trimmed == "0" {
+            Ok(false)
+        } else {
+            Err(format!("invalid bool {text:?}"))
+        }
+    }
+
+    fn canonical(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// A parsed value together with the text it was parsed from.
+///
+/// Serializing writes the original text back unchanged, so a document can
+/// be read, edited elsewhere and written again without `01.50` becoming
+/// `1.5` or `TRUE` becoming `true`. Replacing the value with
+/// [`set`](Self::set) drops the original text, and the new value is written
+/// in canonical form.
+///
+/// ```
+/// use facet::Facet;
+/// use facet_xml::Lexical;
+///
+/// #[derive(Facet)]
+/// struct Price {
+///     amount: Lexical<f64>,
+///     taxed: Lexical<bool>,
+/// }
+///
+/// let input = "<price><amount>01.50</amount><taxed>TRUE</taxed></price>";
+/// let mut price: Price = facet_xml::from_str(input).unwrap();
+/// assert_eq!(*price.amount, 1.5);
+/// assert!(*price.taxed);
+/// assert_eq!(facet_xml::to_string(&price).unwrap(), input);
+///
+/// price.amount.set(2.25);
+/// assert_eq!(
+///     facet_xml::to_string(&price).unwrap(),
+///     "<price><amount>2.25</amount><taxed>TRUE</taxed></price>"
+/// );
+/// ```
+#[derive(Debug, Clone, Facet)]
+#[facet(proxy = String)]
+pub struct Lexical<T: LexicalValue> {
+    value: T,
+    text: Option<String>,
+}
+
+impl<T: LexicalValue> Lexical<T> {
+    /// A value without an original form, written in canonical form.
+    pub fn new(value: T) -> Self {
+        Self { value, text: None }
+    }
+
+    /// Parse `text`, keeping it to write back later.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Ok(Self {
+            value: T::parse_lexical(text)?,
+            text: Some(text.to_string()),
+        })
+    }
+
+    /// The parsed value.
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Replace the value, dropping the original text.
+    pub fn set(&mut self, value: T) {
+        self.value = value;
+        self.text = None;
+    }
+
+    /// The text the value was parsed from, if it still holds that value.
+    pub fn original(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    /// The text this value is written as.
+    pub fn to_text(&self) -> String {
+        match &self.text {
+            Some(text) => text.clone(),
+            None => self.value.canonical(),
+        }
+    }
+
+    /// The parsed value, dropping the original text.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: LexicalValue> Deref for Lexical<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: LexicalValue> From<T> for Lexical<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Values compare equal regardless of how they were written.
+impl<T: LexicalValue + PartialEq> PartialEq for Lexical<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: LexicalValue> fmt::Display for Lexical<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_text())
+    }
+}
+
+impl<T: LexicalValue> TryFrom<String> for Lexical<T> {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Ok(Self {
+            value: T::parse_lexical(&text)?,
+            text: Some(text),
+        })
+    }
+}
+
+impl<T: LexicalValue> From<&Lexical<T>> for String {
+    fn from(lexical: &Lexical<T>) -> Self {
+        lexical.to_text()
+    }
+}
@@ -4,27 +4,168 @@
 #[macro_use]
 mod tracing_macros;
 
+mod assertions;
+mod audit;
+mod chars;
+mod config;
+mod document_writer;
 mod dom_parser;
+mod entities;
 mod escaping;
+mod fidelity;
+mod hooks;
+mod lexical;
+mod record_reader;
+mod reformat;
 mod serializer;
+mod subscribe;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+#[cfg(feature = "async")]
+mod async_reader;
 
 #[cfg(feature = "axum")]
 mod axum;
 
-pub use dom_parser::{XmlError, XmlParser};
+#[cfg(feature = "exi")]
+mod exi;
+
+#[cfg(feature = "wbxml")]
+mod wbxml;
+
+#[cfg(feature = "xmlrpc")]
+pub mod xmlrpc;
+
+pub use assertions::{AssertFn, ValidateFn};
+pub use chars::{is_name_char, is_name_start_char, is_xml_char, is_xml_name};
+pub use config::{
+    CancellationToken, Config, DeserializeContext, DeserializeOptions, EntityLimits,
+    ExternalEntity, ExternalEntityResolver, FieldValue, MetricsCallback, ProgressCallback,
+    ReservedAttribute, ReservedAttributeCallback, ReservedAttributes, SkippedItem,
+    SkippedItemCallback, ValueInterceptor, Whitespace, from_reader_with_options,
+    from_slice_with_options, from_str_lenient_collect_with_options, from_str_with_context,
+    from_str_with_context_and_options, from_str_with_options,
+};
+pub use document_writer::{DocumentWriter, RootElement};
+pub use dom_parser::{ParseMetrics, Progress, ProgressInterval, XmlError, XmlParser};
+pub use fidelity::{FidelityError, FidelityReport, Loss, fidelity_report};
+pub use hooks::{HookOutput, SerializeHooks};
+pub use lexical::{Lexical, LexicalValue};
+pub use record_reader::{IterChildren, RecordReader, Records, iter_children};
+pub use reformat::reformat;
+pub use subscribe::subscribe;
+
+#[cfg(feature = "async")]
+pub use async_reader::{from_async_reader, from_async_reader_with_options};
 
 #[cfg(feature = "axum")]
 pub use axum::{Xml, XmlRejection};
 
+#[cfg(feature = "exi")]
+pub use exi::{ExiError, ExiParser, from_exi};
+
+#[cfg(feature = "wbxml")]
+pub use wbxml::{CodePages, WbxmlError, WbxmlParser, from_wbxml, to_wbxml};
+
 pub use serializer::{
-    FloatFormatter, SerializeOptions, XmlSerializeError, XmlSerializer, to_string,
-    to_string_pretty, to_string_with_options, to_vec, to_vec_with_options,
+    AttributeComparator, AttributePrecision, FloatFormatter, SerializeOptions, XmlSerializeError,
+    XmlSerializer, to_fmt_writer, to_fmt_writer_with_options, to_string, to_string_pretty,
+    to_string_with_options, to_vec, to_vec_with_options, to_writer, to_writer_with_options,
 };
 
+/// The namespace bound to the reserved `xml:` prefix (`xml:lang`, `xml:id`, ...).
+pub const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+pub use facet_dom::AttributeRules;
+pub use facet_dom::BoolStyle;
+pub use facet_dom::ChildOrder;
+
 // Re-export error types for convenience
 pub use facet_dom::DomDeserializeError as DeserializeError;
 pub use facet_dom::DomSerializeError as SerializeError;
+pub use facet_dom::DuplicatePolicy;
+pub use facet_dom::ErrorLocation;
 pub use facet_dom::RawMarkup;
+pub use facet_dom::UnknownItem;
+pub use facet_dom::codec::{CodecNameTaken, CodecRef, TextCodec, register_codec};
+pub use facet_dom::contract::{ChangeKind, ContractDiff, MappingChange};
+pub use facet_dom::describe::{
+    AttributeDescription, Cardinality, ChildDescription, ShapeDescription,
+};
+pub use facet_dom::naming::register_singular;
+pub use facet_dom::{DeserializeWithFn, SerializeWithFn, ValueSlot};
+
+/// Describe how `T` maps onto XML: its element name, attributes, child
+/// elements and how often each may appear.
+///
+/// The description comes from the same name table the deserializer uses, so
+/// tests can pin the wire format of a type without round-tripping documents.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+/// use facet_xml as xml;
+///
+/// #[derive(Facet)]
+/// struct Book {
+///     #[facet(xml::attribute)]
+///     isbn: String,
+///     title: String,
+///     authors: Vec<String>,
+/// }
+///
+/// let description = xml::describe::<Book>();
+/// assert_eq!(description.element, "book");
+/// assert!(description.attribute("isbn").unwrap().required);
+/// let authors = description.child("author").unwrap();
+/// assert_eq!(authors.name, "authors");
+/// assert_eq!(authors.cardinality, xml::Cardinality::Many);
+/// ```
+pub fn describe<T: facet_core::Facet<'static>>() -> ShapeDescription {
+    facet_dom::describe::describe_shape(T::SHAPE, Some("xml"))
+}
+
+/// Compare the XML mappings of two versions of a type.
+///
+/// Every difference in element names, attributes, child elements,
+/// namespaces and cardinality is listed and classified; a change is
+/// breaking when documents written with `Old` may not read with `New`.
+///
+/// ```
+/// use facet::Facet;
+/// use facet_xml as xml;
+///
+/// mod v1 {
+///     use facet::Facet;
+///     #[derive(Facet)]
+///     pub struct Order {
+///         pub items: Vec<String>,
+///     }
+/// }
+///
+/// mod v2 {
+///     use facet::Facet;
+///     #[derive(Facet)]
+///     pub struct Order {
+///         pub items: Option<String>,
+///         pub note: Option<String>,
+///     }
+/// }
+///
+/// let diff = xml::contract_diff::<v1::Order, v2::Order>();
+/// assert!(!diff.is_compatible());
+/// assert_eq!(diff.breaking().count(), 1);
+/// ```
+pub fn contract_diff<Old, New>() -> ContractDiff
+where
+    Old: facet_core::Facet<'static>,
+    New: facet_core::Facet<'static>,
+{
+    facet_dom::contract::diff_shapes(Old::SHAPE, New::SHAPE, Some("xml"))
+}
 
 /// Deserialize a value from an XML string into an owned type.
 ///
@@ -57,6 +198,56 @@ where
     from_slice(input.as_bytes())
 }
 
+/// Deserialize a value from an XML string, reporting every recoverable
+/// error instead of stopping at the first.
+///
+/// Unknown elements and attributes (of `deny_unknown_fields` types) are
+/// skipped, and scalar values that don't parse leave their field unset, so
+/// it takes its default, or drop their list item. Text with no field to
+/// receive it is discarded, as with
+/// [`lenient`](DeserializeOptions::lenient). Each problem is recorded, with
+/// its [location](DeserializeError::location), and deserialization carries
+/// on.
+///
+/// The value is returned whenever it could be built, together with every
+/// error found. It is `None` only when an error left nothing to continue
+/// with, such as a missing required field; that error comes last.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+/// use facet_xml::DeserializeError;
+///
+/// #[derive(Facet, Debug, PartialEq)]
+/// #[facet(deny_unknown_fields)]
+/// struct Settings {
+///     #[facet(default = 8080)]
+///     port: u16,
+///     #[facet(default)]
+///     verbose: bool,
+/// }
+///
+/// let xml = "<settings><port>http</port><colour>red</colour><verbose>true</verbose></settings>";
+/// let (settings, errors) = facet_xml::from_str_lenient_collect::<Settings>(xml);
+/// assert_eq!(settings, Some(Settings { port: 8080, verbose: true }));
+/// assert_eq!(errors.len(), 2);
+/// assert!(errors.iter().all(|e| e.location().is_some()));
+///
+/// let mut errors = errors.into_iter().map(DeserializeError::into_unlocated);
+/// assert!(matches!(errors.next(), Some(DeserializeError::InvalidValue { .. })));
+/// assert!(matches!(errors.next(), Some(DeserializeError::UnknownElement { .. })));
+/// ```
+pub fn from_str_lenient_collect<T>(input: &str) -> (Option<T>, Vec<DeserializeError<XmlError>>)
+where
+    T: facet_core::Facet<'static>,
+{
+    let options = DeserializeOptions::default()
+        .lenient(true)
+        .locate_errors(true);
+    from_str_lenient_collect_with_options(input, &options)
+}
+
 /// Deserialize a value from XML bytes into an owned type.
 ///
 /// This is the recommended default for most use cases. The input does not need
@@ -85,9 +276,40 @@ pub fn from_slice<T>(input: &[u8]) -> Result<T, DeserializeError<XmlError>>
 where
     T: facet_core::Facet<'static>,
 {
-    let parser = XmlParser::new(input);
-    let mut de = facet_dom::DomDeserializer::new_owned(parser);
-    de.deserialize()
+    from_slice_with_options(input, &DeserializeOptions::default())
+}
+
+/// Deserialize a value from an XML reader into an owned type.
+///
+/// The document is parsed as it is read, so only the element being
+/// deserialized is held in memory, not the whole input: use this for files
+/// too large to load into a `String`. The reader is buffered internally.
+///
+/// [`RawMarkup`] fields are not supported, since there is no input slice
+/// to capture them from.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+///
+/// #[derive(Facet, Debug, PartialEq)]
+/// struct Person {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// let file = std::io::Cursor::new(b"<person><name>Alice</name><age>30</age></person>");
+/// let person: Person = facet_xml::from_reader(file).unwrap();
+/// assert_eq!(person.name, "Alice");
+/// assert_eq!(person.age, 30);
+/// ```
+pub fn from_reader<T, R>(reader: R) -> Result<T, DeserializeError<XmlError>>
+where
+    T: facet_core::Facet<'static>,
+    R: std::io::Read,
+{
+    from_reader_with_options(reader, &DeserializeOptions::default())
 }
 
 /// Deserialize a value from an XML string, allowing borrowing from the input.
@@ -95,6 +317,36 @@ where
 /// Use this when the deserialized type can borrow from the input string
 /// (e.g., contains `&'a str` fields). The input must outlive the result.
 ///
+/// Attribute values and text are borrowed from the input without a copy
+/// when they appear in it verbatim. A value that has to be rewritten is
+/// owned instead, and fails to deserialize into `&str` (a `Cow<str>` takes
+/// either):
+///
+/// - attribute values containing entity or character references
+///   (`a="x &amp; y"`),
+/// - text containing references, which is split around them and joined
+///   (`<a>x &amp; y</a>`).
+///
+/// CDATA sections are borrowed as they are never escaped.
+///
+/// ```
+/// use facet::Facet;
+/// use facet_xml as xml;
+/// use std::borrow::Cow;
+///
+/// #[derive(Facet)]
+/// struct Link<'a> {
+///     #[facet(xml::attribute)]
+///     href: &'a str,
+///     #[facet(xml::text)]
+///     label: Cow<'a, str>,
+/// }
+///
+/// let link: Link = facet_xml::from_str_borrowed(r#"<link href="/a">Tom &amp; Jerry</link>"#).unwrap();
+/// assert_eq!(link.href, "/a");
+/// assert!(matches!(link.label, Cow::Owned(_)));
+/// ```
+///
 /// For most use cases, prefer [`from_str`] which produces owned types.
 pub fn from_str_borrowed<'input, T>(input: &'input str) -> Result<T, DeserializeError<XmlError>>
 where
@@ -108,14 +360,19 @@ where
 /// Use this when the deserialized type can borrow from the input bytes
 /// (e.g., contains `&'a str` fields). The input must outlive the result.
 ///
+/// Values are borrowed under the same conditions as [`from_str_borrowed`].
+///
 /// For most use cases, prefer [`from_slice`] which produces owned types.
 pub fn from_slice_borrowed<'input, T>(input: &'input [u8]) -> Result<T, DeserializeError<XmlError>>
 where
     T: facet_core::Facet<'input>,
 {
+    enter_span!("xml_document", bytes = input.len());
     let parser = XmlParser::new(input);
     let mut de = facet_dom::DomDeserializer::new(parser);
-    de.deserialize()
+    let value = de.deserialize()?;
+    assertions::check(&value)?;
+    Ok(value)
 }
 
 // XML extension attributes for use with #[facet(xml::attr)] syntax.
@@ -144,6 +401,15 @@ facet::define_attr_grammar! {
         Elements,
         /// Marks a field as an XML attribute (on the element tag)
         Attribute,
+        /// Sets where an attribute is written within its start tag.
+        ///
+        /// Usage: `#[facet(xml::attribute, xml::attr_order = 0)]`
+        ///
+        /// Ranked attributes come first, lowest rank first, followed by the
+        /// others in field declaration order. An
+        /// [`attribute_order`](crate::SerializeOptions::attribute_order)
+        /// comparator still takes precedence.
+        AttrOrder(i64),
         /// Marks a field as the text content of the element
         Text,
         /// Marks a field as storing the XML element tag name dynamically.
@@ -151,6 +417,24 @@ facet::define_attr_grammar! {
         /// Used on a `String` field to capture the tag name of an element
         /// during deserialization. When serializing, this value becomes the element's tag.
         Tag,
+        /// Marks a field as storing the namespace URI of the element's tag.
+        ///
+        /// Usage: `#[facet(xml::tag_ns, default)]` on an `Option<String>`
+        ///
+        /// Goes with `xml::tag`. When deserializing, the field captures the
+        /// namespace the element is in. When serializing, the element is
+        /// written in that namespace, declaring it where it isn't in scope;
+        /// an empty field keeps the element out of any namespace.
+        TagNs,
+        /// Marks a field as storing the namespace prefix of the element's tag.
+        ///
+        /// Usage: `#[facet(xml::tag_prefix, default)]` on an `Option<String>`
+        ///
+        /// Goes with `xml::tag_ns`. When deserializing, the field captures the
+        /// prefix the element was written with (`None` if unprefixed). When
+        /// serializing, that prefix is used for the namespace again, and an
+        /// empty field makes the namespace the default one.
+        TagPrefix,
         /// Specifies the XML namespace URI for this field.
         ///
         /// Usage: `#[facet(xml::ns = "http://example.com/ns")]`
@@ -166,6 +450,25 @@ facet::define_attr_grammar! {
         /// This sets the default namespace for all fields that don't have their own
         /// `xml::ns` attribute. Individual fields can override this with `xml::ns`.
         NsAll(&'static str),
+        /// Sets the prefix a field's namespace is written with.
+        ///
+        /// Usage: `#[facet(xml::ns = "http://schemas.xmlsoap.org/soap/envelope/", xml::prefix = "soap")]`
+        ///
+        /// When serializing, the field's element or attribute is written as
+        /// `soap:name`, declaring `xmlns:soap` unless an enclosing element
+        /// already binds that prefix to the namespace; without it, prefixes
+        /// are reused or generated (`ns0`, `ns1`, ...). Deserialization
+        /// matches the namespace URI, so any prefix is accepted.
+        Prefix(&'static str),
+        /// Keeps a child element out of the container's `xml::ns_all` namespace.
+        ///
+        /// Usage: `#[facet(xml::no_ns)]`
+        ///
+        /// For schemas mixing qualified and unqualified children under one
+        /// parent: the field matches the unqualified element on input and is
+        /// written without a namespace (undeclaring the default one with
+        /// `xmlns=""` where needed).
+        NoNs,
         /// Marks an enum variant as a catch-all for unknown XML elements.
         ///
         /// Usage: `#[facet(xml::custom_element)]`
@@ -173,6 +476,23 @@ facet::define_attr_grammar! {
         /// When deserializing, if no variant name matches the element tag,
         /// this variant is selected. Use with `xml::tag` to capture the tag name.
         CustomElement,
+        /// Selects an enum's variant by the `xsi:type` attribute of its element.
+        ///
+        /// Usage: `#[facet(xml::xsi_type)]` on an enum.
+        ///
+        /// For SOAP and XSD polymorphic payloads, where an element of a base
+        /// type holds a derived one: `<shape xsi:type="geo:Circle">`. The
+        /// element is named after the field (or the enum, like an untagged
+        /// enum), and the variant whose name (or `rename`) matches the local
+        /// part of `xsi:type` receives its content. Newtype variants hand the
+        /// content to the type they wrap.
+        ///
+        /// When serializing, the variant's name is written as `xsi:type`,
+        /// qualified with the enum's `xml::ns_all` namespace if it has one,
+        /// along with the namespace declarations it needs. A missing
+        /// `xsi:type` fails with [`DeserializeError::MissingAttribute`], and
+        /// one naming no variant with [`DeserializeError::UnknownType`].
+        XsiType,
         /// Marks a field as capturing the DOCTYPE declaration from the XML document.
         ///
         /// Usage: `#[facet(xml::doctype)]`
@@ -183,5 +503,209 @@ facet::define_attr_grammar! {
         ///
         /// The field type should be `Option<String>` to handle documents without DOCTYPE.
         Doctype,
+        /// Marks a string field as a template interpolated from sibling fields.
+        ///
+        /// Usage: `#[facet(xml::raw_template)]`
+        ///
+        /// When serializing, each `{name}` in the field's value is replaced by the
+        /// scalar value of the sibling field `name` (Rust name or effective name).
+        /// `{{` and `}}` produce literal braces; unknown names are left as-is.
+        /// The result is escaped like any other string. Deserialization reads the
+        /// rendered string unchanged.
+        RawTemplate,
+        /// Writes a `None` field as an empty element marked `xsi:nil="true"`.
+        ///
+        /// Usage: `#[facet(xml::nillable)]` on an `Option` field (or a list of
+        /// them) serialized as a child element.
+        ///
+        /// Without it, `None` fields are left out. Deserialization reads any
+        /// element marked `xsi:nil="true"` as `None`, with or without the
+        /// attribute, instead of as an empty value.
+        Nillable,
+        /// Checks a rule spanning several fields once the container is deserialized.
+        ///
+        /// Usage: `#[facet(xml::assert = check_fn)]` on a struct or enum, where
+        /// `check_fn: fn(&Self) -> Result<(), String>`.
+        ///
+        /// Assertions run after the whole document has been deserialized,
+        /// innermost values first. The first failure is returned as
+        /// [`DeserializeError::AssertionFailed`] with the element path of the
+        /// rejected value. The attribute may be repeated.
+        Assert(validator AssertFn),
+        /// Checks a field's value once the document is deserialized.
+        ///
+        /// Usage: `#[facet(xml::validate = check_fn)]` on a field, where
+        /// `check_fn: fn(&FieldType) -> Result<(), String>`. Use it for
+        /// constraints the type can't express, like ranges or string patterns.
+        ///
+        /// Validations run with the [`assert`](Attr::Assert)s, innermost values
+        /// first. The first failure is returned as
+        /// [`DeserializeError::Validation`] with the path of the rejected
+        /// value, e.g. `order/item[1]/qty` or `order/@id`. The attribute may be
+        /// repeated.
+        Validate(validator ValidateFn),
+        /// Limits a field to documents of this schema version or later.
+        ///
+        /// Usage: `#[facet(xml::since = "2.0")]`
+        ///
+        /// The schema version comes from [`DeserializeOptions::version`],
+        /// [`DeserializeOptions::version_attribute`] or
+        /// [`SerializeOptions::version`]. Outside its range the field is neither
+        /// read nor written, so it should be an `Option` or have a default.
+        /// Versions compare component by component; see
+        /// [`facet_dom::versioning::compare_versions`].
+        Since(&'static str),
+        /// Limits a field to documents of this schema version or earlier.
+        ///
+        /// Usage: `#[facet(xml::until = "1.x")]`
+        ///
+        /// An `x` component matches any value, so `"1.x"` keeps the field for
+        /// every 1.* version. Works like [`since`](Attr::Since) otherwise.
+        Until(&'static str),
+        /// Writes a byte field as text through a named codec.
+        ///
+        /// Usage: `#[facet(xml::codec = "base64")]` on a `Vec<u8>` or
+        /// `Option<Vec<u8>>` field, as an attribute or a child element.
+        ///
+        /// `base64`, `base64url` (unpadded) and `hex` are built in; other
+        /// encodings are given with [`codec_with`](Attr::CodecWith), or
+        /// registered by name with [`register_codec`]. Text the codec
+        /// rejects fails with [`DeserializeError::Codec`].
+        Codec(&'static str),
+        /// Writes a byte field as text through the given codec.
+        ///
+        /// Usage: `#[facet(xml::codec_with = &MyCodec)]`, where `MyCodec`
+        /// implements [`TextCodec`]; otherwise works like
+        /// [`codec`](Attr::Codec). Errors name the codec by
+        /// [`TextCodec::name`].
+        CodecWith(CodecRef),
+        /// Writes a byte field as `xs:base64Binary` or `xs:hexBinary` text.
+        ///
+        /// Usage: `#[facet(xml::binary = "base64")]` or
+        /// `#[facet(xml::binary = "hex")]` on a `Vec<u8>`, `[u8; N]` or other
+        /// byte list (or an `Option` of one), as an attribute or a child
+        /// element.
+        ///
+        /// Values are written in the type's canonical form (padded base64,
+        /// uppercase hex). Reading accepts either case for hex and whitespace
+        /// anywhere in the text; text that doesn't decode, or a byte count
+        /// that doesn't fit an array, fails with [`DeserializeError::Codec`].
+        Binary(&'static str),
+        /// Writes a number with exactly this many digits after the point.
+        ///
+        /// Usage: `#[facet(xml::scale = 2)]` on a decimal or float field (or
+        /// an `Option` of one), as an attribute or a child element.
+        ///
+        /// For `xs:decimal` amounts in invoices and payment messages: `10.5`
+        /// is written as `10.50`, and extra digits are rounded half to even
+        /// (`2.345` becomes `2.34`). Values whose text isn't a plain decimal
+        /// number, like `NaN`, are written unchanged. Reading is unaffected.
+        Scale(i64),
+        /// Chooses the words a `bool` field is written as.
+        ///
+        /// Usage: `#[facet(xml::bool_style = "one_zero")]`, or `"true_false"`
+        /// or `"yes_no"`, on a `bool` or `Option<bool>` field.
+        ///
+        /// Overrides [`SerializeOptions::bool_style`] for this field. Reading
+        /// always accepts `true`, `false`, `1` and `0`, the `xs:boolean`
+        /// forms; fields marked `"yes_no"` also accept `yes` and `no`.
+        BoolStyle(&'static str),
+        /// Names the item elements of a list field collecting repeated children.
+        ///
+        /// Usage: `#[facet(xml::singular = "medium")]` on a `Vec` or set field.
+        ///
+        /// Without it, items of a field `tracks` also match `<track>`, the
+        /// singular guessed from the field's element name (see
+        /// [`register_singular`] to correct a guess for every type).
+        Singular(&'static str),
+        /// Another name a field accepts on input, for an element or an attribute.
+        ///
+        /// Usage: `#[facet(xml::alias = "old-name", xml::alias = "legacy_name")]`
+        ///
+        /// Unlike the built-in `alias`, which holds one name, this may be
+        /// repeated, so a field can accept several historical spellings during
+        /// a migration. Aliases are matched as written; output always uses the
+        /// field's own name.
+        Alias(&'static str),
+        /// Chooses what happens when a single-valued field's element repeats.
+        ///
+        /// Usage: `#[facet(xml::on_duplicate = "error")]`, or `"first"` or
+        /// `"last"`.
+        ///
+        /// `"error"` fails with [`DeserializeError::DuplicateElement`],
+        /// `"first"` keeps the first element and skips the rest, and `"last"`
+        /// keeps the last one. Fields without it follow
+        /// [`DeserializeOptions::on_duplicate`].
+        OnDuplicate(&'static str),
+        /// Leaves an item out of its list or set when it fails to deserialize.
+        ///
+        /// Usage: `#[facet(xml::skip_on_error)]` on the item type.
+        ///
+        /// Each item is read into memory and tried before it is added, so one
+        /// malformed record in a large feed doesn't fail the whole document.
+        /// Skipped items are reported to
+        /// [`DeserializeOptions::on_skipped_item`].
+        SkipOnError,
+        /// Keeps a struct's children in document order across a round trip.
+        ///
+        /// Usage: `#[facet(xml::ordered)]` on a struct with a [`ChildOrder`]
+        /// field.
+        ///
+        /// When deserializing, the field records which field each child
+        /// element and piece of text went to. When serializing, children are
+        /// written in that order rather than grouped by field, so elements of
+        /// different fields, text and unknown elements captured by a
+        /// flattened `Vec<Element>` keep their original interleaving. Without
+        /// the attribute the `ChildOrder` field stays empty.
+        Ordered,
+        /// Marks a newtype enum variant as holding a comment.
+        ///
+        /// Usage: `#[facet(xml::comment)]` on a variant like `Comment(String)`
+        /// of an enum collected by a flattened list.
+        ///
+        /// Comments among the children become this variant when parsing with
+        /// [`DeserializeOptions::keep_comments`], and it is written back as
+        /// `<!--text-->`.
+        Comment,
+        /// Marks an enum variant as holding a processing instruction.
+        ///
+        /// Usage: `#[facet(xml::processing_instruction)]` on a variant with
+        /// `target` and `data` string fields, of an enum collected by a
+        /// flattened list.
+        ///
+        /// Processing instructions among the children become this variant
+        /// when parsing with
+        /// [`DeserializeOptions::keep_processing_instructions`], and it is
+        /// written back as `<?target data?>`.
+        ProcessingInstruction,
+        /// Resolves a field's value through the caller's context as it is read.
+        ///
+        /// Usage: `#[facet(xml::with_context)]` on a field, deserialized with
+        /// [`from_str_with_context`].
+        ///
+        /// The field's text goes through [`DeserializeContext::resolve`]
+        /// before it is parsed, so references (ID/IDREF, interned names) are
+        /// resolved during parsing. Elsewhere the attribute has no effect.
+        WithContext,
+        /// Writes a field's text with a function instead of its type.
+        ///
+        /// Usage: `#[facet(xml::serialize_with = write_fn)]` on a field, where
+        /// `write_fn` is a [`SerializeWithFn`].
+        ///
+        /// For one-off text forms where a proxy type would be overkill: the
+        /// function gets the field's value and appends its text, which is
+        /// written as the element's text or the attribute's value. Pair it
+        /// with [`deserialize_with`](Attr::DeserializeWith) to read it back.
+        SerializeWith(SerializeWithFn),
+        /// Builds a field's value from its text with a function.
+        ///
+        /// Usage: `#[facet(xml::deserialize_with = read_fn)]` on a field, where
+        /// `read_fn` is a [`DeserializeWithFn`].
+        ///
+        /// The element's text or the attribute's value is handed to the
+        /// function, which stores the value in the [`ValueSlot`]. A rejected
+        /// text fails with [`DeserializeError::DeserializeWith`], wrapped in
+        /// [`DeserializeError::InvalidValue`] with its path.
+        DeserializeWith(DeserializeWithFn),
     }
 }
@@ -0,0 +1,292 @@
+//! Streaming reader for documents made of one root element and many records.
+
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+use core::marker::PhantomData;
+
+use facet_core::Facet;
+use facet_dom::{DomDeserializer, DomEvent, DomParser, DomParserExt};
+
+use crate::{DeserializeError, XmlError, XmlParser};
+
+/// Reads a document of the shape `<root ...><record/><record/>...</root>`
+/// one child at a time.
+///
+/// Opening the reader consumes the prolog and the root start tag, so the
+/// root's name and attributes can be inspected before any record is read.
+/// Records are then deserialized on demand; nothing after the current record
+/// has been parsed yet.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+/// use facet_xml::RecordReader;
+///
+/// #[derive(Facet, Debug, PartialEq)]
+/// struct Row {
+///     id: u32,
+/// }
+///
+/// let xml = r#"<rows version="2"><row><id>1</id></row><row><id>2</id></row></rows>"#;
+/// let mut reader = RecordReader::new(xml.as_bytes()).unwrap();
+/// assert_eq!(reader.root_tag(), "rows");
+/// assert_eq!(reader.attribute("version"), Some("2"));
+///
+/// let rows: Vec<Row> = reader.records().collect::<Result<_, _>>().unwrap();
+/// assert_eq!(rows, vec![Row { id: 1 }, Row { id: 2 }]);
+/// ```
+pub struct RecordReader<'de> {
+    parser: XmlParser<'de>,
+    root_tag: String,
+    root_namespace: Option<String>,
+    attributes: Vec<(String, String)>,
+    finished: bool,
+}
+
+impl<'de> RecordReader<'de> {
+    /// Open a document and read up to (and including) the root start tag.
+    pub fn new(input: &'de [u8]) -> Result<Self, DeserializeError<XmlError>> {
+        let mut parser = XmlParser::new(input);
+
+        // Skip anything in the prolog: declaration, comments, doctype.
+        let (root_tag, root_namespace) = loop {
+            match parser.next_event_or_eof("root element")? {
+                DomEvent::NodeStart { tag, namespace } => {
+                    break (tag.into_owned(), namespace.map(|ns| ns.into_owned()));
+                }
+                DomEvent::Comment(_)
+                | DomEvent::ProcessingInstruction { .. }
+                | DomEvent::Doctype(_) => continue,
+                other => {
+                    return Err(DeserializeError::TypeMismatch {
+                        expected: "root element",
+                        got: format!("{other:?}"),
+                    });
+                }
+            }
+        };
+
+        let mut attributes = Vec::new();
+        loop {
+            match parser.next_event_or_eof("ChildrenStart")? {
+                DomEvent::Attribute { name, value, .. } => {
+                    attributes.push((name.into_owned(), value.into_owned()));
+                }
+                DomEvent::ChildrenStart => break,
+                other => {
+                    return Err(DeserializeError::TypeMismatch {
+                        expected: "ChildrenStart",
+                        got: format!("{other:?}"),
+                    });
+                }
+            }
+        }
+
+        Ok(Self {
+            parser,
+            root_tag,
+            root_namespace,
+            attributes,
+            finished: false,
+        })
+    }
+
+    /// The root element's tag name.
+    pub fn root_tag(&self) -> &str {
+        &self.root_tag
+    }
+
+    /// The root element's namespace URI, if any.
+    pub fn root_namespace(&self) -> Option<&str> {
+        self.root_namespace.as_deref()
+    }
+
+    /// All attributes of the root element, in document order.
+    pub fn attributes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.attributes
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Look up a root attribute by name.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Deserialize the next child of the root, or return `None` once the
+    /// root element has been closed.
+    pub fn next_record<T>(&mut self) -> Result<Option<T>, DeserializeError<XmlError>>
+    where
+        T: Facet<'static>,
+    {
+        self.next_matching(None)
+    }
+
+    /// Deserialize the next child of the root named `tag`, skipping children
+    /// with other names, or return `None` once the root element has been
+    /// closed.
+    pub fn next_record_named<T>(
+        &mut self,
+        tag: &str,
+    ) -> Result<Option<T>, DeserializeError<XmlError>>
+    where
+        T: Facet<'static>,
+    {
+        self.next_matching(Some(tag))
+    }
+
+    fn next_matching<T>(
+        &mut self,
+        tag: Option<&str>,
+    ) -> Result<Option<T>, DeserializeError<XmlError>>
+    where
+        T: Facet<'static>,
+    {
+        if self.finished {
+            return Ok(None);
+        }
+
+        loop {
+            match self.parser.peek_event_or_eof("record or end of root")? {
+                DomEvent::NodeStart { tag: name, .. } => {
+                    if tag.is_none_or(|tag| name == tag) {
+                        break;
+                    }
+                    self.parser.skip_node().map_err(DeserializeError::Parser)?;
+                }
+                DomEvent::ChildrenEnd => {
+                    self.parser.expect_children_end()?;
+                    self.parser.expect_node_end()?;
+                    self.finished = true;
+                    return Ok(None);
+                }
+                // Text, comments and processing instructions between records are ignored.
+                _ => {
+                    self.parser.next_event().map_err(DeserializeError::Parser)?;
+                }
+            }
+        }
+
+        let mut de = DomDeserializer::new_owned(&mut self.parser);
+        let record = de.deserialize()?;
+        crate::assertions::check(&record)?;
+        Ok(Some(record))
+    }
+
+    /// Iterate over the remaining records, deserializing each as `T`.
+    ///
+    /// Iteration stops after the first error.
+    pub fn records<T>(&mut self) -> Records<'_, 'de, T>
+    where
+        T: Facet<'static>,
+    {
+        Records {
+            reader: self,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Iterator over the records of a [`RecordReader`].
+pub struct Records<'r, 'de, T> {
+    reader: &'r mut RecordReader<'de>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Iterator for Records<'_, '_, T>
+where
+    T: Facet<'static>,
+{
+    type Item = Result<T, DeserializeError<XmlError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.reader.next_record() {
+            Ok(Some(record)) => Some(Ok(record)),
+            Ok(None) => None,
+            Err(e) => {
+                self.reader.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Iterate over the children of the root element named `tag`, deserializing
+/// each as `T`.
+///
+/// The document itself must already be in memory, but only the current
+/// child is deserialized at a time, so this suits documents of the shape
+/// `<records><record/>...</records>` whose records together are too large
+/// to collect into a `Vec`. Children with other names are skipped. An error opening the
+/// document is returned by the first call to `next`, and iteration stops
+/// after the first error.
+///
+/// Use [`RecordReader`] to also inspect the root element.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+///
+/// #[derive(Facet, Debug, PartialEq)]
+/// struct Record {
+///     id: u32,
+/// }
+///
+/// let xml = "<records><record><id>1</id></record><note/><record><id>2</id></record></records>";
+/// let ids: Vec<u32> = facet_xml::iter_children::<Record>(xml, "record")
+///     .map(|record| record.map(|r| r.id))
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(ids, [1, 2]);
+/// ```
+pub fn iter_children<'de, T>(input: &'de str, tag: &str) -> IterChildren<'de, T>
+where
+    T: Facet<'static>,
+{
+    let (reader, error) = match RecordReader::new(input.as_bytes()) {
+        Ok(reader) => (Some(reader), None),
+        Err(e) => (None, Some(e)),
+    };
+    IterChildren {
+        reader,
+        error,
+        tag: tag.into(),
+        _marker: PhantomData,
+    }
+}
+
+/// Iterator returned by [`iter_children`].
+pub struct IterChildren<'de, T> {
+    reader: Option<RecordReader<'de>>,
+    error: Option<DeserializeError<XmlError>>,
+    tag: String,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Iterator for IterChildren<'_, T>
+where
+    T: Facet<'static>,
+{
+    type Item = Result<T, DeserializeError<XmlError>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.error.take() {
+            return Some(Err(e));
+        }
+        let reader = self.reader.as_mut()?;
+        match reader.next_record_named(&self.tag) {
+            Ok(Some(record)) => Some(Ok(record)),
+            Ok(None) => None,
+            Err(e) => {
+                reader.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
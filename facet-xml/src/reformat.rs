@@ -0,0 +1,247 @@
+//! Re-indenting XML documents without a schema.
+
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+
+use crate::{SerializeOptions, XmlError};
+
+/// A node of the document, as written in the input.
+enum Node<'a> {
+    Element {
+        start: BytesStart<'a>,
+        /// Written as `<tag/>`
+        empty: bool,
+        children: Vec<Node<'a>>,
+    },
+    /// Escaped text, references included
+    Text(String),
+    CData(String),
+    Comment(String),
+    /// Processing instructions and the XML declaration
+    ProcessingInstruction(String),
+    DocType(String),
+}
+
+impl Node<'_> {
+    fn is_blank(&self) -> bool {
+        matches!(self, Node::Text(text) if text.trim().is_empty())
+    }
+
+    fn is_text(&self) -> bool {
+        matches!(self, Node::Text(_) | Node::CData(_)) && !self.is_blank()
+    }
+}
+
+/// Re-emit the XML document `input` laid out according to `options`,
+/// without deserializing it into a type.
+///
+/// With [`pretty`](SerializeOptions::pretty), each element goes on its own
+/// line, indented with [`indent`](SerializeOptions::indent) and separated
+/// by [`newline`](SerializeOptions::newline), and start tags are broken up
+/// per [`wrap_attributes`](SerializeOptions::wrap_attributes). Without it,
+/// the whitespace between elements is removed.
+///
+/// Only whitespace between elements changes. Elements containing text
+/// (mixed content) are written as they are, as whitespace inside them is
+/// significant; names, prefixes, namespace declarations, references,
+/// comments, CDATA sections, processing instructions and the XML
+/// declaration are kept as written.
+///
+/// # Example
+///
+/// ```
+/// use facet_xml::SerializeOptions;
+///
+/// let xml = r#"<config><server host="a"><port>80</port></server><!-- note --><empty/></config>"#;
+/// let pretty = facet_xml::reformat(xml, &SerializeOptions::new().indent("    ")).unwrap();
+/// assert_eq!(
+///     pretty,
+///     "<config>\n    <server host=\"a\">\n        <port>80</port>\n    </server>\n    <!-- note -->\n    <empty/>\n</config>\n"
+/// );
+/// assert_eq!(facet_xml::reformat(&pretty, &SerializeOptions::new()).unwrap(), xml);
+/// ```
+pub fn reformat(input: &str, options: &SerializeOptions) -> Result<String, XmlError> {
+    let nodes = parse(input)?;
+    let mut out = String::with_capacity(input.len());
+    for node in nodes.iter().filter(|node| !node.is_blank()) {
+        write_node(node, 0, false, options, &mut out)?;
+        if options.pretty {
+            out.push_str(&options.newline);
+        }
+    }
+    Ok(out)
+}
+
+fn parse(input: &str) -> Result<Vec<Node<'_>>, XmlError> {
+    let mut reader = Reader::from_str(input);
+    reader.config_mut().check_end_names = true;
+    let as_str = |bytes: &[u8]| -> Result<String, XmlError> {
+        Ok(core::str::from_utf8(bytes)
+            .map_err(XmlError::InvalidUtf8)?
+            .to_string())
+    };
+
+    // Children of the open elements; the document's nodes are at the bottom
+    let mut stack: Vec<(Option<BytesStart<'_>>, Vec<Node<'_>>)> = vec![(None, Vec::new())];
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| XmlError::Parse(e.to_string()))?;
+        let children = &mut stack.last_mut().expect("document level").1;
+        let node = match event {
+            Event::Start(start) => {
+                stack.push((Some(start), Vec::new()));
+                continue;
+            }
+            Event::End(_) => {
+                let (start, children) = stack.pop().expect("document level");
+                let start = start.ok_or(XmlError::UnbalancedTags)?;
+                Node::Element {
+                    start,
+                    empty: false,
+                    children,
+                }
+            }
+            Event::Empty(start) => Node::Element {
+                start,
+                empty: true,
+                children: Vec::new(),
+            },
+            Event::Text(text) => {
+                let text = as_str(&text)?;
+                if let Some(Node::Text(previous)) = children.last_mut() {
+                    previous.push_str(&text);
+                    continue;
+                }
+                Node::Text(text)
+            }
+            Event::GeneralRef(reference) => {
+                let reference = format!("&{};", as_str(&reference)?);
+                if let Some(Node::Text(previous)) = children.last_mut() {
+                    previous.push_str(&reference);
+                    continue;
+                }
+                Node::Text(reference)
+            }
+            Event::CData(cdata) => Node::CData(as_str(&cdata)?),
+            Event::Comment(comment) => Node::Comment(as_str(&comment)?),
+            Event::PI(pi) => Node::ProcessingInstruction(as_str(&pi)?),
+            Event::Decl(decl) => Node::ProcessingInstruction(as_str(&decl)?),
+            Event::DocType(doctype) => Node::DocType(as_str(&doctype)?),
+            Event::Eof => break,
+        };
+        stack.last_mut().expect("document level").1.push(node);
+    }
+    match stack.pop() {
+        Some((None, nodes)) if stack.is_empty() => Ok(nodes),
+        _ => Err(XmlError::UnexpectedEof),
+    }
+}
+
+/// Write `node` at `depth`. Inside mixed content, `verbatim` keeps
+/// everything as it was written.
+fn write_node(
+    node: &Node<'_>,
+    depth: usize,
+    verbatim: bool,
+    options: &SerializeOptions,
+    out: &mut String,
+) -> Result<(), XmlError> {
+    let pretty = options.pretty && !verbatim;
+    let indent = |out: &mut String, depth: usize| {
+        if pretty {
+            for _ in 0..depth {
+                out.push_str(&options.indent);
+            }
+        }
+    };
+    indent(out, depth);
+    match node {
+        Node::Text(text) => out.push_str(text),
+        Node::CData(text) => {
+            out.push_str("<![CDATA[");
+            out.push_str(text);
+            out.push_str("]]>");
+        }
+        Node::Comment(text) => {
+            out.push_str("<!--");
+            out.push_str(text);
+            out.push_str("-->");
+        }
+        Node::ProcessingInstruction(text) => {
+            out.push_str("<?");
+            out.push_str(text);
+            out.push_str("?>");
+        }
+        Node::DocType(text) => {
+            out.push_str("<!DOCTYPE ");
+            out.push_str(text.trim_start());
+            out.push('>');
+        }
+        Node::Element {
+            start,
+            empty,
+            children,
+        } => {
+            let name =
+                core::str::from_utf8(start.name().into_inner()).map_err(XmlError::InvalidUtf8)?;
+            let mut attributes = Vec::new();
+            for attribute in start.attributes() {
+                let attribute = attribute.map_err(|e| XmlError::Parse(e.to_string()))?;
+                let key = core::str::from_utf8(attribute.key.into_inner())
+                    .map_err(XmlError::InvalidUtf8)?;
+                let value =
+                    core::str::from_utf8(&attribute.value).map_err(XmlError::InvalidUtf8)?;
+                // Single-quoted values may contain double quotes
+                attributes.push(format!("{key}=\"{}\"", value.replace('"', "&quot;")));
+            }
+
+            let tag_start = out.len();
+            out.push('<');
+            out.push_str(name);
+            let line = out.len() - out[..tag_start].rfind('\n').map_or(0, |i| i + 1);
+            let width: usize = attributes.iter().map(|a| a.len() + 1).sum();
+            let closing = if *empty { 2 } else { 1 };
+            let wrap = pretty
+                && options
+                    .wrap_attributes
+                    .is_some_and(|max| line + width + closing > max);
+            for attribute in &attributes {
+                if wrap {
+                    out.push_str(&options.newline);
+                    indent(out, depth + 1);
+                } else {
+                    out.push(' ');
+                }
+                out.push_str(attribute);
+            }
+            if *empty {
+                out.push_str("/>");
+                return Ok(());
+            }
+            out.push('>');
+
+            let verbatim = verbatim || children.iter().any(Node::is_text);
+            let pretty = options.pretty && !verbatim;
+            let mut wrote_child = false;
+            for child in children {
+                if !verbatim && child.is_blank() {
+                    continue;
+                }
+                if pretty {
+                    out.push_str(&options.newline);
+                }
+                write_node(child, depth + 1, verbatim, options, out)?;
+                wrote_child = true;
+            }
+            if pretty && wrote_child {
+                out.push_str(&options.newline);
+                indent(out, depth);
+            }
+            out.push_str("</");
+            out.push_str(name);
+            out.push('>');
+        }
+    }
+    Ok(())
+}
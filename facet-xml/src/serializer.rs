@@ -3,23 +3,51 @@ extern crate alloc;
 use alloc::{borrow::Cow, format, string::String, vec::Vec};
 use std::collections::HashMap;
 use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use facet_core::{Def, Facet, ScalarType};
-use facet_dom::{DomSerializeError, DomSerializer};
+use facet_core::{Def, Facet, ScalarType, Shape};
+use facet_dom::{AttributeRules, BoolStyle, DomSerializeError, DomSerializer};
 use facet_reflect::Peek;
 
+use crate::audit::AuditLog;
 use crate::escaping::EscapingWriter;
+use crate::hooks::{HookOutput, SerializeHooks};
 
 pub use facet_dom::FloatFormatter;
 
+/// Comparator deciding the order of attributes within a start tag.
+///
+/// Receives two qualified attribute names and returns their relative order.
+pub type AttributeComparator = fn(&str, &str) -> core::cmp::Ordering;
+
+/// Number of decimal places to write the floating-point value of an
+/// attribute with, by attribute name; `None` leaves the value as it is.
+pub type AttributePrecision = Arc<dyn Fn(&str) -> Option<usize> + Send + Sync>;
+
+/// Read a scalar of type `T` out of `value`.
+///
+/// A value whose shape disagrees with its reported scalar type is reported as
+/// an error instead of panicking.
+fn get_scalar<'mem, 'facet, T>(value: Peek<'mem, 'facet>) -> Result<&'mem T, XmlSerializeError>
+where
+    T: Facet<'facet>,
+{
+    value.get::<T>().map_err(|e| XmlSerializeError {
+        msg: Cow::Owned(format!("cannot read scalar value: {e}")),
+    })
+}
+
 /// Write a scalar value directly to a writer.
 /// Returns `Ok(true)` if the value was a scalar and was written,
-/// `Ok(false)` if not a scalar, `Err` if write failed.
-fn write_scalar_value(
+/// `Ok(false)` if not a scalar, `Err` if the value couldn't be read or the
+/// write failed.
+pub(crate) fn write_scalar_value(
     out: &mut dyn Write,
     value: Peek<'_, '_>,
     float_formatter: Option<FloatFormatter>,
-) -> std::io::Result<bool> {
+    bool_style: BoolStyle,
+) -> Result<bool, XmlSerializeError> {
     // Unwrap transparent wrappers (e.g., PointsProxy -> String)
     let value = value.innermost_peek();
 
@@ -28,11 +56,19 @@ fn write_scalar_value(
         && let Ok(opt) = value.into_option()
     {
         return match opt.value() {
-            Some(inner) => write_scalar_value(out, inner, float_formatter),
+            Some(inner) => write_scalar_value(out, inner, float_formatter, bool_style),
             None => Ok(false),
         };
     }
 
+    // Look through smart pointers (Box<str>, Arc<str>, Rc<str>, ...)
+    if let Def::Pointer(_) = &value.shape().def
+        && let Ok(ptr) = value.into_pointer()
+        && let Some(inner) = ptr.borrow_inner()
+    {
+        return write_scalar_value(out, inner, float_formatter, bool_style);
+    }
+
     let Some(scalar_type) = value.scalar_type() else {
         // Try Display for Def::Scalar types (SmolStr, etc.)
         if matches!(value.shape().def, Def::Scalar) && value.shape().vtable.has_display() {
@@ -63,21 +99,23 @@ fn write_scalar_value(
             out.write_all(b"null")?;
         }
         ScalarType::Bool => {
-            let b = value.get::<bool>().unwrap();
-            out.write_all(if *b { b"true" } else { b"false" })?;
+            let b = get_scalar::<bool>(value)?;
+            out.write_all(bool_style.text(*b).as_bytes())?;
         }
         ScalarType::Char => {
-            let c = value.get::<char>().unwrap();
+            let c = get_scalar::<char>(value)?;
             let mut buf = [0u8; 4];
             let s = c.encode_utf8(&mut buf);
             out.write_all(s.as_bytes())?;
         }
         ScalarType::Str | ScalarType::String | ScalarType::CowStr => {
-            let s = value.as_str().unwrap();
+            let s = value.as_str().ok_or_else(|| XmlSerializeError {
+                msg: Cow::Borrowed("cannot read string scalar value"),
+            })?;
             out.write_all(s.as_bytes())?;
         }
         ScalarType::F32 => {
-            let v = value.get::<f32>().unwrap();
+            let v = get_scalar::<f32>(value)?;
             if let Some(fmt) = float_formatter {
                 fmt(*v as f64, out)?;
             } else {
@@ -85,38 +123,65 @@ fn write_scalar_value(
             }
         }
         ScalarType::F64 => {
-            let v = value.get::<f64>().unwrap();
+            let v = get_scalar::<f64>(value)?;
             if let Some(fmt) = float_formatter {
                 fmt(*v, out)?;
             } else {
                 write!(out, "{}", v)?;
             }
         }
-        ScalarType::U8 => write!(out, "{}", value.get::<u8>().unwrap())?,
-        ScalarType::U16 => write!(out, "{}", value.get::<u16>().unwrap())?,
-        ScalarType::U32 => write!(out, "{}", value.get::<u32>().unwrap())?,
-        ScalarType::U64 => write!(out, "{}", value.get::<u64>().unwrap())?,
-        ScalarType::U128 => write!(out, "{}", value.get::<u128>().unwrap())?,
-        ScalarType::USize => write!(out, "{}", value.get::<usize>().unwrap())?,
-        ScalarType::I8 => write!(out, "{}", value.get::<i8>().unwrap())?,
-        ScalarType::I16 => write!(out, "{}", value.get::<i16>().unwrap())?,
-        ScalarType::I32 => write!(out, "{}", value.get::<i32>().unwrap())?,
-        ScalarType::I64 => write!(out, "{}", value.get::<i64>().unwrap())?,
-        ScalarType::I128 => write!(out, "{}", value.get::<i128>().unwrap())?,
-        ScalarType::ISize => write!(out, "{}", value.get::<isize>().unwrap())?,
+        ScalarType::U8 => write!(out, "{}", get_scalar::<u8>(value)?)?,
+        ScalarType::U16 => write!(out, "{}", get_scalar::<u16>(value)?)?,
+        ScalarType::U32 => write!(out, "{}", get_scalar::<u32>(value)?)?,
+        ScalarType::U64 => write!(out, "{}", get_scalar::<u64>(value)?)?,
+        ScalarType::U128 => write!(out, "{}", get_scalar::<u128>(value)?)?,
+        ScalarType::USize => write!(out, "{}", get_scalar::<usize>(value)?)?,
+        ScalarType::I8 => write!(out, "{}", get_scalar::<i8>(value)?)?,
+        ScalarType::I16 => write!(out, "{}", get_scalar::<i16>(value)?)?,
+        ScalarType::I32 => write!(out, "{}", get_scalar::<i32>(value)?)?,
+        ScalarType::I64 => write!(out, "{}", get_scalar::<i64>(value)?)?,
+        ScalarType::I128 => write!(out, "{}", get_scalar::<i128>(value)?)?,
+        ScalarType::ISize => write!(out, "{}", get_scalar::<isize>(value)?)?,
         #[cfg(feature = "net")]
-        ScalarType::IpAddr => write!(out, "{}", value.get::<core::net::IpAddr>().unwrap())?,
+        ScalarType::IpAddr => write!(out, "{}", get_scalar::<core::net::IpAddr>(value)?)?,
         #[cfg(feature = "net")]
-        ScalarType::Ipv4Addr => write!(out, "{}", value.get::<core::net::Ipv4Addr>().unwrap())?,
+        ScalarType::Ipv4Addr => write!(out, "{}", get_scalar::<core::net::Ipv4Addr>(value)?)?,
         #[cfg(feature = "net")]
-        ScalarType::Ipv6Addr => write!(out, "{}", value.get::<core::net::Ipv6Addr>().unwrap())?,
+        ScalarType::Ipv6Addr => write!(out, "{}", get_scalar::<core::net::Ipv6Addr>(value)?)?,
         #[cfg(feature = "net")]
-        ScalarType::SocketAddr => write!(out, "{}", value.get::<core::net::SocketAddr>().unwrap())?,
+        ScalarType::SocketAddr => write!(out, "{}", get_scalar::<core::net::SocketAddr>(value)?)?,
+        // Other scalars (uuid, url, ...) are written with their Display impl
+        _ if value.shape().vtable.has_display() => write!(out, "{}", value)?,
         _ => return Ok(false),
     }
     Ok(true)
 }
 
+/// `value` as a float if it is an `f32` or `f64`, looking through `Option`.
+fn float_value(value: Peek<'_, '_>) -> Option<f64> {
+    let value = value.innermost_peek();
+    if let Def::Option(_) = value.shape().def {
+        return value.into_option().ok()?.value().and_then(float_value);
+    }
+    match value.scalar_type()? {
+        ScalarType::F32 => value.get::<f32>().ok().map(|v| f64::from(*v)),
+        ScalarType::F64 => value.get::<f64>().ok().copied(),
+        _ => None,
+    }
+}
+
+/// `value` rounded to `places` decimal places, without trailing zeros.
+fn round_float(value: f64, places: usize) -> String {
+    let mut rounded = format!("{value:.places$}");
+    if rounded.contains('.') {
+        rounded.truncate(rounded.trim_end_matches('0').trim_end_matches('.').len());
+    }
+    if rounded == "-0" {
+        rounded.remove(0);
+    }
+    rounded
+}
+
 /// Options for XML serialization.
 #[derive(Clone)]
 pub struct SerializeOptions {
@@ -124,6 +189,16 @@ pub struct SerializeOptions {
     pub pretty: bool,
     /// Indentation string for pretty-printing (default: "  ")
     pub indent: Cow<'static, str>,
+    /// Line break written between lines when pretty-printing (default: "\n")
+    pub newline: Cow<'static, str>,
+    /// Width above which a start tag is broken up, one attribute per line.
+    ///
+    /// A start tag whose line (indentation included) would be longer than
+    /// this many bytes has each attribute written on its own line, indented
+    /// one level deeper than the element. Only applies when pretty-printing.
+    ///
+    /// Default: `None` (start tags are never broken up).
+    pub wrap_attributes: Option<usize>,
     /// Custom formatter for floating-point numbers (f32 and f64).
     /// If `None`, uses the default `Display` implementation.
     pub float_formatter: Option<FloatFormatter>,
@@ -135,6 +210,89 @@ pub struct SerializeOptions {
     ///
     /// Default: `false` (all `&` characters are escaped to `&amp;`).
     pub preserve_entities: bool,
+    /// Whether non-ASCII characters in text and attribute values are written
+    /// as numeric character references (`&#xE9;`), keeping the output pure
+    /// ASCII for consumers that can't handle anything else. Element and
+    /// attribute names are written as they are.
+    ///
+    /// Default: `false`.
+    pub escape_non_ascii: bool,
+    /// Whether elements without content are written as `<tag/>` instead of
+    /// `<tag></tag>`.
+    ///
+    /// Default: `false`.
+    pub self_closing: bool,
+    /// Decimal places to write `f32` and `f64` attribute values with.
+    ///
+    /// Called with the attribute's local name; values are rounded to the
+    /// returned number of places, then trailing zeros are dropped. Takes
+    /// precedence over [`float_formatter`](Self::float_formatter).
+    ///
+    /// Default: `None` (values are written in full).
+    pub attribute_precision: Option<AttributePrecision>,
+    /// Number of bytes to reserve for the output buffer up front.
+    ///
+    /// When `None`, one-shot serialization reserves a running estimate of
+    /// what recent documents of the same type needed.
+    ///
+    /// Default: `None`.
+    pub initial_capacity: Option<usize>,
+    /// Order in which attributes of each element are written.
+    ///
+    /// The comparator receives qualified attribute names (`prefix:name` for
+    /// namespaced attributes). When `None`, attributes are written in field
+    /// declaration order (map entries in iteration order).
+    ///
+    /// Default: `None`.
+    pub attribute_order: Option<AttributeComparator>,
+    /// Whether map entries are written in sorted key order.
+    ///
+    /// Applies to map fields and to maps flattened into attributes, so
+    /// `HashMap` output is reproducible without switching to `BTreeMap`.
+    ///
+    /// Default: `false` (the map's iteration order).
+    pub sort_map_keys: bool,
+    /// Words `bool` values are written as: `true`/`false`, `1`/`0` or
+    /// `yes`/`no`. A field's `xml::bool_style` takes precedence.
+    ///
+    /// Default: [`BoolStyle::TrueFalse`].
+    pub bool_style: BoolStyle,
+    /// Whether the namespaces a document may use are all declared on its
+    /// root element.
+    ///
+    /// The namespaces are those of the `xml::ns` fields reachable from the
+    /// root type, each bound to a prefix once for the whole document.
+    /// Otherwise a namespace is declared on the first element using it, and
+    /// again only where that declaration is out of scope.
+    ///
+    /// Default: `false`.
+    pub hoist_namespaces: bool,
+    /// Whether to check the output while it is generated.
+    ///
+    /// Element and attribute names must be valid XML names (map keys become
+    /// names), and text and attribute values may only contain characters
+    /// allowed in XML 1.0; a violation fails serialization at the offending
+    /// item. Once done, the output is re-parsed and must describe the same
+    /// elements, attributes and text that were written, which catches
+    /// escaping bugs. This roughly doubles the cost of serialization, so it
+    /// is meant for tests and debug builds.
+    ///
+    /// Default: `false`.
+    pub audit: bool,
+    /// Schema version to write: fields marked `xml::since` / `xml::until`
+    /// outside it are left out.
+    ///
+    /// Default: `None` (every field is written).
+    pub version: Option<Cow<'static, str>>,
+    /// Attributes added to or stripped from the output regardless of the
+    /// types being written.
+    ///
+    /// Default: empty (attributes are written as the types describe them).
+    pub attribute_rules: AttributeRules,
+    /// Callbacks run around each element written.
+    ///
+    /// Default: `None`.
+    pub hooks: Option<Arc<dyn SerializeHooks>>,
 }
 
 impl Default for SerializeOptions {
@@ -142,8 +300,22 @@ impl Default for SerializeOptions {
         Self {
             pretty: false,
             indent: Cow::Borrowed("  "),
+            newline: Cow::Borrowed("\n"),
+            wrap_attributes: None,
             float_formatter: None,
             preserve_entities: false,
+            escape_non_ascii: false,
+            self_closing: false,
+            attribute_precision: None,
+            initial_capacity: None,
+            attribute_order: None,
+            sort_map_keys: false,
+            bool_style: BoolStyle::TrueFalse,
+            hoist_namespaces: false,
+            audit: false,
+            version: None,
+            attribute_rules: AttributeRules::default(),
+            hooks: None,
         }
     }
 }
@@ -153,8 +325,25 @@ impl core::fmt::Debug for SerializeOptions {
         f.debug_struct("SerializeOptions")
             .field("pretty", &self.pretty)
             .field("indent", &self.indent)
+            .field("newline", &self.newline)
+            .field("wrap_attributes", &self.wrap_attributes)
             .field("float_formatter", &self.float_formatter.map(|_| "..."))
             .field("preserve_entities", &self.preserve_entities)
+            .field("escape_non_ascii", &self.escape_non_ascii)
+            .field("self_closing", &self.self_closing)
+            .field(
+                "attribute_precision",
+                &self.attribute_precision.as_ref().map(|_| "..."),
+            )
+            .field("initial_capacity", &self.initial_capacity)
+            .field("attribute_order", &self.attribute_order.map(|_| "..."))
+            .field("sort_map_keys", &self.sort_map_keys)
+            .field("bool_style", &self.bool_style)
+            .field("hoist_namespaces", &self.hoist_namespaces)
+            .field("audit", &self.audit)
+            .field("version", &self.version)
+            .field("attribute_rules", &self.attribute_rules)
+            .field("hooks", &self.hooks.as_ref().map(|_| "..."))
             .finish()
     }
 }
@@ -178,6 +367,39 @@ impl SerializeOptions {
         self
     }
 
+    /// Set the line break used when pretty-printing, e.g. `"\r\n"`.
+    pub fn newline(mut self, newline: impl Into<Cow<'static, str>>) -> Self {
+        self.newline = newline.into();
+        self
+    }
+
+    /// Break start tags longer than `width` into one attribute per line;
+    /// see [`wrap_attributes`](Self::wrap_attributes).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use facet::Facet;
+    /// # use facet_xml as xml;
+    /// # use facet_xml::{to_string_with_options, SerializeOptions};
+    /// #[derive(Facet)]
+    /// struct Link {
+    ///     #[facet(xml::attribute)]
+    ///     href: String,
+    ///     #[facet(xml::attribute)]
+    ///     title: String,
+    /// }
+    ///
+    /// let link = Link { href: "/docs/getting-started".into(), title: "Getting started".into() };
+    /// let options = SerializeOptions::new().pretty().wrap_attributes(40);
+    /// let xml = to_string_with_options(&link, &options).unwrap();
+    /// assert!(xml.starts_with("<link\n  href=\"/docs/getting-started\"\n  title=\"Getting started\">"));
+    /// ```
+    pub const fn wrap_attributes(mut self, width: usize) -> Self {
+        self.wrap_attributes = Some(width);
+        self
+    }
+
     /// Set a custom formatter for floating-point numbers (f32 and f64).
     ///
     /// The formatter function receives the value as `f64` (f32 values are upcast)
@@ -227,6 +449,341 @@ impl SerializeOptions {
         self.preserve_entities = preserve;
         self
     }
+
+    /// Write non-ASCII characters in text and attribute values as numeric
+    /// character references.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use facet::Facet;
+    /// # use facet_xml::{to_string_with_options, SerializeOptions};
+    /// #[derive(Facet)]
+    /// struct Greeting {
+    ///     text: String,
+    /// }
+    ///
+    /// let greeting = Greeting { text: "Grüße 😀".into() };
+    /// let options = SerializeOptions::new().escape_non_ascii(true);
+    /// let xml = to_string_with_options(&greeting, &options).unwrap();
+    /// assert_eq!(xml, "<greeting><text>Gr&#xFC;&#xDF;e &#x1F600;</text></greeting>");
+    /// ```
+    pub const fn escape_non_ascii(mut self, escape: bool) -> Self {
+        self.escape_non_ascii = escape;
+        self
+    }
+
+    /// Write elements without content as `<tag/>`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use facet::Facet;
+    /// # use facet_xml::{to_string_with_options, SerializeOptions};
+    /// #[derive(Facet)]
+    /// struct Form {
+    ///     name: String,
+    ///     note: String,
+    /// }
+    ///
+    /// let form = Form { name: "a".into(), note: String::new() };
+    /// let options = SerializeOptions::new().self_closing(true);
+    /// let xml = to_string_with_options(&form, &options).unwrap();
+    /// assert_eq!(xml, "<form><name>a</name><note/></form>");
+    /// ```
+    pub const fn self_closing(mut self, self_closing: bool) -> Self {
+        self.self_closing = self_closing;
+        self
+    }
+
+    /// Round floating-point attribute values to the number of decimal
+    /// places `precision` returns for the attribute's name; see
+    /// [`attribute_precision`](Self::attribute_precision).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use facet::Facet;
+    /// # use facet_xml as xml;
+    /// # use facet_xml::{to_string_with_options, SerializeOptions};
+    /// #[derive(Facet)]
+    /// struct Point {
+    ///     #[facet(xml::attribute)]
+    ///     x: f64,
+    ///     #[facet(xml::attribute)]
+    ///     weight: f64,
+    /// }
+    ///
+    /// let point = Point { x: 1.0 / 3.0, weight: 2.50 };
+    /// let options = SerializeOptions::new()
+    ///     .attribute_precision(|name| (name == "x").then_some(2));
+    /// let xml = to_string_with_options(&point, &options).unwrap();
+    /// assert_eq!(xml, r#"<point x="0.33" weight="2.5"></point>"#);
+    /// ```
+    pub fn attribute_precision(
+        mut self,
+        precision: impl Fn(&str) -> Option<usize> + Send + Sync + 'static,
+    ) -> Self {
+        self.attribute_precision = Some(Arc::new(precision));
+        self
+    }
+
+    /// Write map entries in sorted key order instead of iteration order.
+    pub const fn sort_map_keys(mut self, sort: bool) -> Self {
+        self.sort_map_keys = sort;
+        self
+    }
+
+    /// Set the words `bool` values are written as.
+    ///
+    /// Reading accepts `true`, `false`, `1` and `0` whatever the style;
+    /// `yes` and `no` are only read back into fields marked
+    /// `xml::bool_style = "yes_no"`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use facet::Facet;
+    /// # use facet_xml::{to_string_with_options, BoolStyle, SerializeOptions};
+    /// #[derive(Facet)]
+    /// struct Flags {
+    ///     enabled: bool,
+    ///     hidden: bool,
+    /// }
+    ///
+    /// let flags = Flags { enabled: true, hidden: false };
+    /// let options = SerializeOptions::new().bool_style(BoolStyle::OneZero);
+    /// let xml = to_string_with_options(&flags, &options).unwrap();
+    /// assert_eq!(xml, "<flags><enabled>1</enabled><hidden>0</hidden></flags>");
+    /// ```
+    pub const fn bool_style(mut self, style: BoolStyle) -> Self {
+        self.bool_style = style;
+        self
+    }
+
+    /// Declare every namespace on the root element; see
+    /// [`hoist_namespaces`](Self::hoist_namespaces).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::{self as xml, SerializeOptions};
+    ///
+    /// #[derive(Facet)]
+    /// struct Item {
+    ///     #[facet(xml::ns = "urn:dc")]
+    ///     title: String,
+    /// }
+    ///
+    /// #[derive(Facet)]
+    /// struct Catalog {
+    ///     #[facet(rename = "item")]
+    ///     items: Vec<Item>,
+    /// }
+    ///
+    /// let catalog = Catalog {
+    ///     items: vec![Item { title: "a".into() }, Item { title: "b".into() }],
+    /// };
+    /// let options = SerializeOptions::new().hoist_namespaces(true);
+    /// assert_eq!(
+    ///     xml::to_string_with_options(&catalog, &options).unwrap(),
+    ///     r#"<catalog xmlns:ns0="urn:dc"><item><ns0:title>a</ns0:title></item><item><ns0:title>b</ns0:title></item></catalog>"#
+    /// );
+    /// ```
+    pub const fn hoist_namespaces(mut self, hoist: bool) -> Self {
+        self.hoist_namespaces = hoist;
+        self
+    }
+
+    /// Check names, characters and escaping of the output; see [`audit`](Self::audit).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::SerializeOptions;
+    /// use std::collections::BTreeMap;
+    ///
+    /// #[derive(Facet)]
+    /// struct Labels {
+    ///     labels: BTreeMap<String, String>,
+    /// }
+    ///
+    /// let labels = Labels {
+    ///     labels: [("not a name".to_string(), "x".to_string())].into(),
+    /// };
+    /// let options = SerializeOptions::new().audit(true);
+    /// let err = facet_xml::to_string_with_options(&labels, &options).unwrap_err();
+    /// assert!(err.to_string().contains("\"not a name\" is not a valid XML element name"));
+    /// ```
+    pub const fn audit(mut self, audit: bool) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Write documents of schema `version`, leaving out fields marked
+    /// `xml::since` / `xml::until` that don't belong to it.
+    pub fn version(mut self, version: impl Into<Cow<'static, str>>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Reserve `capacity` bytes for the output before serializing.
+    ///
+    /// Useful when the approximate output size is known, to avoid repeated
+    /// reallocation while a large document is written.
+    pub const fn initial_capacity(mut self, capacity: usize) -> Self {
+        self.initial_capacity = Some(capacity);
+        self
+    }
+
+    /// Set the order in which attributes are written within each start tag.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use facet::Facet;
+    /// # use facet_xml as xml;
+    /// # use facet_xml::{to_string_with_options, SerializeOptions};
+    /// #[derive(Facet)]
+    /// struct Node {
+    ///     #[facet(xml::attribute)]
+    ///     name: String,
+    ///     #[facet(xml::attribute)]
+    ///     id: String,
+    /// }
+    ///
+    /// // Canonical form: `id` first, then alphabetical
+    /// fn id_first(a: &str, b: &str) -> std::cmp::Ordering {
+    ///     (a != "id").cmp(&(b != "id")).then(a.cmp(b))
+    /// }
+    ///
+    /// let node = Node { name: "n".into(), id: "x".into() };
+    /// let options = SerializeOptions::new().attribute_order(id_first);
+    /// let xml = to_string_with_options(&node, &options).unwrap();
+    /// assert_eq!(xml, r#"<node id="x" name="n"></node>"#);
+    /// ```
+    pub fn attribute_order(mut self, compare: AttributeComparator) -> Self {
+        self.attribute_order = Some(compare);
+        self
+    }
+
+    /// Write attributes sorted alphabetically by qualified name.
+    pub fn sorted_attributes(self) -> Self {
+        self.attribute_order(str::cmp)
+    }
+
+    /// Inject or suppress attributes on every document written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use facet::Facet;
+    /// # use facet_xml as xml;
+    /// # use facet_xml::{AttributeRules, SerializeOptions, to_string_with_options};
+    /// #[derive(Facet)]
+    /// struct Report {
+    ///     #[facet(xml::attribute, rename = "debug-trace")]
+    ///     trace: String,
+    ///     title: String,
+    /// }
+    ///
+    /// let rules = AttributeRules::new()
+    ///     .inject_on_root("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance")
+    ///     .suppress(|name| name.starts_with("debug-"));
+    /// let report = Report { trace: "t-42".into(), title: "Q3".into() };
+    /// let options = SerializeOptions::new().attribute_rules(rules);
+    /// let xml = to_string_with_options(&report, &options).unwrap();
+    /// assert_eq!(
+    ///     xml,
+    ///     r#"<report xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><title>Q3</title></report>"#
+    /// );
+    /// ```
+    pub fn attribute_rules(mut self, rules: AttributeRules) -> Self {
+        self.attribute_rules = rules;
+        self
+    }
+
+    /// Run `hooks` around each element written; see [`SerializeHooks`].
+    pub fn hooks(mut self, hooks: impl SerializeHooks + 'static) -> Self {
+        self.hooks = Some(Arc::new(hooks));
+        self
+    }
+}
+
+/// Number of output-size estimates kept, shared by all shapes.
+const SIZE_HINT_SLOTS: usize = 64;
+
+/// Largest output size remembered, so one huge document doesn't make every
+/// later one reserve as much.
+const MAX_SIZE_HINT: usize = 1 << 20;
+
+/// Running estimates of one-shot output sizes, used to reserve the output
+/// buffer when no explicit capacity is configured.
+///
+/// Shapes share a fixed set of slots picked by address; a collision only
+/// costs a worse estimate.
+static SIZE_HINTS: [AtomicUsize; SIZE_HINT_SLOTS] =
+    [const { AtomicUsize::new(0) }; SIZE_HINT_SLOTS];
+
+fn size_hint_slot(shape: &'static Shape) -> &'static AtomicUsize {
+    let addr = core::ptr::from_ref(shape) as usize;
+    &SIZE_HINTS[((addr >> 3) ^ (addr >> 11)) % SIZE_HINT_SLOTS]
+}
+
+fn size_hint(shape: &'static Shape) -> usize {
+    size_hint_slot(shape).load(Ordering::Relaxed)
+}
+
+/// Fold `len` into the estimate for `shape`, weighting it by a quarter.
+fn record_size_hint(shape: &'static Shape, len: usize) {
+    let len = len.min(MAX_SIZE_HINT);
+    let _ = size_hint_slot(shape).fetch_update(Ordering::Relaxed, Ordering::Relaxed, |hint| {
+        let next = if hint == 0 {
+            len
+        } else {
+            hint - hint / 4 + len / 4
+        };
+        // Steady sizes leave the slot untouched
+        (next != hint).then_some(next)
+    });
+}
+
+/// The namespaces of the `xml::ns` fields reachable from `shape`, in the
+/// order they're found.
+///
+/// Children in their parent's `xml::ns_all` namespace are left out, as
+/// they're written in the default namespace.
+fn document_namespaces(shape: &'static facet_core::Shape) -> Vec<&'static str> {
+    fn visit(
+        shape: &'static facet_core::Shape,
+        seen: &mut Vec<&'static facet_core::Shape>,
+        namespaces: &mut Vec<&'static str>,
+    ) {
+        if seen.iter().any(|s| core::ptr::eq(*s, shape)) {
+            return;
+        }
+        seen.push(shape);
+        let description = facet_dom::describe::describe_shape(shape, Some("xml"));
+        let attributes = description.attributes.iter().map(|a| a.namespace);
+        let children = description
+            .children
+            .iter()
+            .map(|c| c.namespace.filter(|ns| Some(*ns) != description.namespace));
+        for ns in attributes.chain(children).flatten() {
+            if !namespaces.contains(&ns) {
+                namespaces.push(ns);
+            }
+        }
+        for child in &description.children {
+            visit(child.shape, seen, namespaces);
+        }
+    }
+
+    let mut namespaces = Vec::new();
+    visit(shape, &mut Vec::new(), &mut namespaces);
+    namespaces
 }
 
 /// Well-known XML namespace URIs and their conventional prefixes.
@@ -245,7 +802,7 @@ const WELL_KNOWN_NAMESPACES: &[(&str, &str)] = &[
 
 #[derive(Debug)]
 pub struct XmlSerializeError {
-    msg: Cow<'static, str>,
+    pub(crate) msg: Cow<'static, str>,
 }
 
 impl core::fmt::Display for XmlSerializeError {
@@ -256,6 +813,14 @@ impl core::fmt::Display for XmlSerializeError {
 
 impl std::error::Error for XmlSerializeError {}
 
+impl From<std::io::Error> for XmlSerializeError {
+    fn from(e: std::io::Error) -> Self {
+        XmlSerializeError {
+            msg: Cow::Owned(format!("write error: {e}")),
+        }
+    }
+}
+
 /// XML serializer with configurable output options.
 ///
 /// The output is designed to round-trip through `facet-xml`'s parser:
@@ -285,8 +850,27 @@ pub struct XmlSerializer {
     pending_is_doctype: bool,
     /// True if the current field is a tag field (xml::tag)
     pending_is_tag: bool,
+    /// True if the current field is a template field (xml::raw_template)
+    pending_is_raw_template: bool,
+    /// True if the current field is written with xsi:nil when None (xml::nillable)
+    pending_is_nillable: bool,
     /// Pending namespace for the next field
     pending_namespace: Option<String>,
+    /// Namespace URI and prefix chosen by the current field's `xml::prefix`
+    pending_prefix: Option<(String, String)>,
+    /// True if the next element opts out of ns_all (xml::no_ns) and must be unqualified
+    pending_no_ns: bool,
+    /// Default namespaces to restore when the element at the given stack
+    /// depth ends, pushed when an element declares or undeclares it
+    default_ns_restore: Vec<(usize, Option<String>)>,
+    /// Prefix requested for the next element (xml::tag_prefix); `Some(None)`
+    /// asks for it to be unprefixed
+    pending_element_prefix: Option<Option<String>>,
+    /// Prefixes declared on open elements: stack depth of the declaring
+    /// element, prefix and namespace URI
+    prefix_scope: Vec<(usize, String, String)>,
+    /// Namespaces to declare on the root element (`options.hoist_namespaces`)
+    hoisted_namespaces: Vec<&'static str>,
     /// Serialization options (pretty-printing, float formatting, etc.)
     options: SerializeOptions,
     /// Current indentation depth for pretty-printing
@@ -295,6 +879,16 @@ pub struct XmlSerializer {
     collecting_attributes: bool,
     /// True if the next element should establish a default namespace (from ns_all)
     pending_establish_default_ns: bool,
+    /// Qualified name and byte range in `out` of each attribute written for the
+    /// current start tag. Only tracked when `options.attribute_order` is set.
+    attribute_spans: Vec<(String, usize, usize)>,
+    /// What was written, for checking the output. Only tracked when
+    /// `options.audit` is set.
+    audit_log: AuditLog,
+    /// Position in `out` of the `>` ending the last start tag, while the
+    /// element may still be closed as `<tag/>`. Only tracked when
+    /// `options.self_closing` is set.
+    open_tag_end: Option<usize>,
 }
 
 impl XmlSerializer {
@@ -317,11 +911,22 @@ impl XmlSerializer {
             pending_is_elements: false,
             pending_is_doctype: false,
             pending_is_tag: false,
+            pending_is_raw_template: false,
+            pending_is_nillable: false,
             pending_namespace: None,
+            pending_prefix: None,
+            pending_no_ns: false,
+            default_ns_restore: Vec::new(),
+            pending_element_prefix: None,
+            prefix_scope: Vec::new(),
+            hoisted_namespaces: Vec::new(),
             options,
             depth: 0,
             collecting_attributes: false,
             pending_establish_default_ns: false,
+            attribute_spans: Vec::new(),
+            audit_log: AuditLog::default(),
+            open_tag_end: None,
         }
     }
 
@@ -329,6 +934,153 @@ impl XmlSerializer {
         self.out
     }
 
+    /// The options this serializer was created with.
+    pub fn options(&self) -> &SerializeOptions {
+        &self.options
+    }
+
+    /// Clear all per-document state so the serializer can be used again.
+    ///
+    /// Buffers keep their allocated capacity, so reusing one serializer for
+    /// many small documents avoids re-allocating on every call.
+    pub fn reset(&mut self) {
+        self.out.clear();
+        self.element_stack.clear();
+        self.declared_namespaces.clear();
+        self.next_ns_index = 0;
+        self.current_default_ns = None;
+        self.default_ns_restore.clear();
+        self.pending_element_prefix = None;
+        self.prefix_scope.clear();
+        self.hoisted_namespaces.clear();
+        self.current_ns_all = None;
+        self.clear_field_state_impl();
+        self.depth = 0;
+        self.collecting_attributes = false;
+        self.pending_establish_default_ns = false;
+        self.attribute_spans.clear();
+        self.audit_log.clear();
+        self.open_tag_end = None;
+    }
+
+    /// Re-parse the output and compare it with what was written, if auditing.
+    fn verify_output(&self) -> Result<(), DomSerializeError<XmlSerializeError>> {
+        if self.options.audit {
+            self.audit_log
+                .verify(&self.out, !self.options.preserve_entities)
+                .map_err(DomSerializeError::Backend)?;
+        }
+        Ok(())
+    }
+
+    /// Serialize `value` as a complete document and write it to `out`.
+    ///
+    /// The serializer is reset before each call, so it can be kept around and
+    /// reused for any number of documents.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use facet::Facet;
+    /// use facet_xml::{SerializeOptions, XmlSerializer};
+    ///
+    /// #[derive(Facet)]
+    /// struct Ping {
+    ///     seq: u32,
+    /// }
+    ///
+    /// let mut serializer = XmlSerializer::with_options(SerializeOptions::new());
+    /// let mut out = Vec::new();
+    /// for seq in 0..3 {
+    ///     out.clear();
+    ///     serializer.serialize(&Ping { seq }, &mut out).unwrap();
+    ///     assert_eq!(out, format!("<ping><seq>{seq}</seq></ping>").as_bytes());
+    /// }
+    /// ```
+    pub fn serialize<'facet, T, W>(
+        &mut self,
+        value: &'_ T,
+        out: &mut W,
+    ) -> Result<(), DomSerializeError<XmlSerializeError>>
+    where
+        T: Facet<'facet> + ?Sized,
+        W: Write + ?Sized,
+    {
+        self.serialize_at_depth(value, 0, out)
+    }
+
+    /// Serialize `value` as if it were nested `depth` levels deep, so that
+    /// pretty-printed output lines up with an enclosing element written elsewhere.
+    pub(crate) fn serialize_at_depth<'facet, T, W>(
+        &mut self,
+        value: &'_ T,
+        depth: usize,
+        out: &mut W,
+    ) -> Result<(), DomSerializeError<XmlSerializeError>>
+    where
+        T: Facet<'facet> + ?Sized,
+        W: Write + ?Sized,
+    {
+        self.reset();
+        self.depth = depth;
+        if let Some(capacity) = self.options.initial_capacity {
+            self.out.reserve(capacity);
+        }
+        // The rules wrap `self`, so they're moved out for the call
+        let rules = core::mem::take(&mut self.options.attribute_rules);
+        let result =
+            self.serialize_streaming(Peek::new(value), (!rules.is_empty()).then_some(&rules), out);
+        self.options.attribute_rules = rules;
+        result
+    }
+
+    /// Serialize `value`, writing the output to `out` in chunks as it is
+    /// produced rather than all at once at the end.
+    fn serialize_streaming<W>(
+        &mut self,
+        value: Peek<'_, '_>,
+        rules: Option<&AttributeRules>,
+        out: &mut W,
+    ) -> Result<(), DomSerializeError<XmlSerializeError>>
+    where
+        W: Write + ?Sized,
+    {
+        self.start_document(value.shape());
+        let mut spilling = Spilling {
+            serializer: &mut *self,
+            out: &mut *out,
+        };
+        match rules {
+            Some(rules) => facet_dom::serialize(&mut rules.wrap(&mut spilling), value)?,
+            None => facet_dom::serialize(&mut spilling, value)?,
+        }
+        self.verify_output()?;
+        let result = out.write_all(&self.out);
+        self.out.clear();
+        result.map_err(|e| DomSerializeError::Backend(e.into()))
+    }
+
+    /// Set up the state a document of type `shape` starts with. Every entry
+    /// point calls this before serializing.
+    fn start_document(&mut self, shape: &'static facet_core::Shape) {
+        if self.options.hoist_namespaces {
+            self.hoisted_namespaces = document_namespaces(shape);
+        }
+    }
+
+    /// Move the output buffered so far into `out` once it is large enough,
+    /// unless it may still change: the attributes of an open start tag can
+    /// be reordered, and audited output is checked as a whole at the end.
+    fn spill<W: Write + ?Sized>(&mut self, out: &mut W) -> std::io::Result<()> {
+        if self.collecting_attributes || self.options.audit || self.out.len() < SPILL_THRESHOLD {
+            return Ok(());
+        }
+        out.write_all(&self.out)?;
+        self.out.clear();
+        self.open_tag_end = None;
+        Ok(())
+    }
+
     /// Write the opening part of an element tag: `<tag` (without the closing `>`)
     /// This allows attributes to be written directly afterwards.
     fn write_element_tag_start(&mut self, name: &str, namespace: Option<&str>) {
@@ -354,18 +1106,18 @@ impl XmlSerializer {
                 self.pending_establish_default_ns = false;
                 close_tag = name.to_string();
             } else {
-                // Field-level namespace - use prefix
-                let prefix = self.get_or_create_prefix(ns_uri);
+                // Field-level namespace - use prefix, declared unless an
+                // enclosing element already binds it
+                let (prefix, declare) = self.prefix_for(ns_uri);
                 self.out.extend_from_slice(prefix.as_bytes());
                 self.out.push(b':');
                 self.out.extend_from_slice(name.as_bytes());
-                // Write xmlns declaration for this prefix
-                self.out.extend_from_slice(b" xmlns:");
-                self.out.extend_from_slice(prefix.as_bytes());
-                self.out.extend_from_slice(b"=\"");
-                self.out.extend_from_slice(ns_uri.as_bytes());
-                self.out.push(b'"');
                 close_tag = format!("{}:{}", prefix, name);
+                if declare {
+                    self.write_namespace_declaration(&prefix, ns_uri);
+                    let depth = self.element_stack.len() + 1;
+                    self.prefix_scope.push((depth, prefix, ns_uri.to_string()));
+                }
             }
         } else {
             self.out.extend_from_slice(name.as_bytes());
@@ -376,50 +1128,182 @@ impl XmlSerializer {
         self.element_stack.push(close_tag);
     }
 
+    /// Write the opening part of an element tag in `ns_uri` using the
+    /// requested `prefix`, or no prefix for `None`.
+    ///
+    /// The namespace is declared unless that prefix (or the default
+    /// namespace) is already bound to it by an enclosing element.
+    fn write_element_tag_start_with_prefix(
+        &mut self,
+        name: &str,
+        ns_uri: &str,
+        prefix: Option<&str>,
+    ) {
+        self.write_indent();
+        self.out.push(b'<');
+
+        let depth = self.element_stack.len() + 1;
+        let close_tag = match prefix {
+            None => {
+                self.out.extend_from_slice(name.as_bytes());
+                if self.current_default_ns.as_deref() != Some(ns_uri) {
+                    self.out.extend_from_slice(b" xmlns=\"");
+                    self.out.extend_from_slice(ns_uri.as_bytes());
+                    self.out.push(b'"');
+                    let previous = self.current_default_ns.replace(ns_uri.to_string());
+                    self.default_ns_restore.push((depth, previous));
+                }
+                name.to_string()
+            }
+            Some(prefix) => {
+                let close_tag = format!("{prefix}:{name}");
+                self.out.extend_from_slice(close_tag.as_bytes());
+                if self.bound_namespace(prefix) != Some(ns_uri) {
+                    self.write_namespace_declaration(prefix, ns_uri);
+                    self.prefix_scope
+                        .push((depth, prefix.to_string(), ns_uri.to_string()));
+                }
+                close_tag
+            }
+        };
+
+        self.element_stack.push(close_tag);
+    }
+
     /// Write an attribute directly to the output: ` name="escaped_value"`
     /// Returns Ok(true) if written, Ok(false) if value wasn't a scalar (attribute skipped).
+    ///
+    /// The value is escaped straight into the output buffer. If it turns out not to
+    /// be a scalar, everything written for this attribute is rolled back.
     fn write_attribute(
         &mut self,
         name: &str,
         value: Peek<'_, '_>,
         namespace: Option<&str>,
-    ) -> std::io::Result<bool> {
-        // First, write the value to a temporary buffer to check if it's a scalar
-        let mut value_buf = Vec::new();
-        let written = write_scalar_value(
-            &mut EscapingWriter::attribute(&mut value_buf),
-            value,
-            self.options.float_formatter,
-        )?;
-
-        if !written {
-            // Not a scalar (e.g., None) - skip the attribute entirely
-            return Ok(false);
-        }
-
-        // Now write the attribute
+    ) -> Result<bool, XmlSerializeError> {
+        let mark = self.out.len();
+        let next_ns_index = self.next_ns_index;
+        let scope_len = self.prefix_scope.len();
+        let ns_was_declared =
+            namespace.is_some_and(|ns_uri| self.declared_namespaces.contains_key(ns_uri));
+
+        let mut qualified_name = None;
         self.out.push(b' ');
         if let Some(ns_uri) = namespace {
-            let prefix = self.get_or_create_prefix(ns_uri);
-            // Write xmlns declaration
-            self.out.extend_from_slice(b"xmlns:");
-            self.out.extend_from_slice(prefix.as_bytes());
-            self.out.extend_from_slice(b"=\"");
-            self.out.extend_from_slice(ns_uri.as_bytes());
-            self.out.extend_from_slice(b"\" ");
+            let (prefix, declare) = self.prefix_for(ns_uri);
+            if self.options.attribute_order.is_some() {
+                qualified_name = Some(format!("{prefix}:{name}"));
+            }
+            // Write xmlns declaration (the `xml` prefix is bound implicitly)
+            if declare && ns_uri != crate::XML_NAMESPACE {
+                self.out.extend_from_slice(b"xmlns:");
+                self.out.extend_from_slice(prefix.as_bytes());
+                self.out.extend_from_slice(b"=\"");
+                self.out.extend_from_slice(ns_uri.as_bytes());
+                self.out.extend_from_slice(b"\" ");
+                let depth = self.element_stack.len();
+                self.prefix_scope
+                    .push((depth, prefix.clone(), ns_uri.to_string()));
+            }
             // Write prefixed attribute
             self.out.extend_from_slice(prefix.as_bytes());
             self.out.push(b':');
         }
         self.out.extend_from_slice(name.as_bytes());
         self.out.extend_from_slice(b"=\"");
-        self.out.extend_from_slice(&value_buf);
+
+        let rounded = self
+            .options
+            .attribute_precision
+            .as_ref()
+            .and_then(|precision| precision(name))
+            .and_then(|places| Some(round_float(float_value(value)?, places)));
+        let written = match rounded {
+            Some(rounded) => {
+                self.out.extend_from_slice(rounded.as_bytes());
+                true
+            }
+            None => write_scalar_value(
+                &mut EscapingWriter::attribute(&mut self.out)
+                    .non_ascii(self.options.escape_non_ascii),
+                value,
+                self.options.float_formatter,
+                self.options.bool_style,
+            )?,
+        };
+
+        if !written {
+            // Not a scalar (e.g., None) - skip the attribute entirely
+            self.out.truncate(mark);
+            self.prefix_scope.truncate(scope_len);
+            if let Some(ns_uri) = namespace
+                && !ns_was_declared
+            {
+                self.declared_namespaces.remove(ns_uri);
+                self.next_ns_index = next_ns_index;
+            }
+            return Ok(false);
+        }
+
         self.out.push(b'"');
+        if self.options.attribute_order.is_some() || self.options.wrap_attributes.is_some() {
+            let qualified_name = qualified_name.unwrap_or_else(|| name.to_string());
+            self.attribute_spans
+                .push((qualified_name, mark, self.out.len()));
+        }
         Ok(true)
     }
 
+    /// Reorder the attributes of the current start tag according to
+    /// `options.attribute_order`, and put each on its own line if the tag is
+    /// longer than `options.wrap_attributes`. Attributes are contiguous at
+    /// the end of `out`.
+    fn arrange_attributes(&mut self) {
+        let spans = core::mem::take(&mut self.attribute_spans);
+        let Some(&(_, base, _)) = spans.first() else {
+            return;
+        };
+        let wrap = self.options.pretty
+            && self.options.wrap_attributes.is_some_and(|width| {
+                let line_start = self
+                    .out
+                    .iter()
+                    .rposition(|&b| b == b'\n')
+                    .map_or(0, |i| i + 1);
+                // The tag isn't closed yet
+                self.out.len() + 1 - line_start > width
+            });
+        let sort = self.options.attribute_order.filter(|_| spans.len() > 1);
+        if !wrap && sort.is_none() {
+            return;
+        }
+
+        let written = self.out.split_off(base);
+        let mut spans = spans;
+        if let Some(compare) = sort {
+            spans.sort_by(|(a, _, _), (b, _, _)| compare(a, b));
+        }
+        for (_, start, end) in &spans {
+            let attribute = &written[start - base..end - base];
+            if wrap {
+                // Replace the separating space by a line break
+                self.write_newline();
+                self.depth += 1;
+                self.write_indent();
+                self.depth -= 1;
+                self.out.extend_from_slice(&attribute[1..]);
+            } else {
+                self.out.extend_from_slice(attribute);
+            }
+        }
+    }
+
     /// Finish the element opening tag by writing `>` and incrementing depth.
     fn write_element_tag_end(&mut self) {
+        self.arrange_attributes();
+        if self.options.self_closing {
+            self.open_tag_end = Some(self.out.len());
+        }
         self.out.push(b'>');
         self.write_newline();
         self.depth += 1;
@@ -427,6 +1311,15 @@ impl XmlSerializer {
 
     fn write_close_tag(&mut self, name: &str) {
         self.depth = self.depth.saturating_sub(1);
+        // Nothing was written since the start tag: turn it into `<tag/>`
+        if let Some(tag_end) = self.open_tag_end.take()
+            && self.out.len() == tag_end + 1 + self.newline_len()
+        {
+            self.out.truncate(tag_end);
+            self.out.extend_from_slice(b"/>");
+            self.write_newline();
+            return;
+        }
         self.write_indent();
         self.out.extend_from_slice(b"</");
         self.out.extend_from_slice(name.as_bytes());
@@ -434,14 +1327,56 @@ impl XmlSerializer {
         self.write_newline();
     }
 
+    /// Write `<!--text-->`, breaking up any `--` inside.
+    pub(crate) fn write_comment(&mut self, text: &str) {
+        let mut text = text.to_string();
+        while text.contains("--") {
+            text = text.replace("--", "- -");
+        }
+        if text.ends_with('-') {
+            text.push(' ');
+        }
+        self.write_indent();
+        self.out.extend_from_slice(b"<!--");
+        self.out.extend_from_slice(text.as_bytes());
+        self.out.extend_from_slice(b"-->");
+        self.write_newline();
+    }
+
+    /// Write `<?target data?>`, breaking up any `?>` inside the data.
+    pub(crate) fn write_processing_instruction(&mut self, target: &str, data: &str) {
+        self.write_indent();
+        self.out.extend_from_slice(b"<?");
+        self.out.extend_from_slice(target.as_bytes());
+        if !data.is_empty() {
+            self.out.push(b' ');
+            self.out
+                .extend_from_slice(data.replace("?>", "? >").as_bytes());
+        }
+        self.out.extend_from_slice(b"?>");
+        self.write_newline();
+    }
+
     fn write_text_escaped(&mut self, text: &str) {
         use std::io::Write;
         if self.options.preserve_entities {
             let escaped = escape_preserving_entities(text, false);
-            self.out.extend_from_slice(escaped.as_bytes());
+            if self.options.escape_non_ascii {
+                for c in escaped.chars() {
+                    if c.is_ascii() {
+                        self.out.push(c as u8);
+                    } else {
+                        let _ = write!(self.out, "&#x{:X};", c as u32);
+                    }
+                }
+            } else {
+                self.out.extend_from_slice(escaped.as_bytes());
+            }
         } else {
             // Use EscapingWriter for consistency with attribute escaping
-            let _ = EscapingWriter::text(&mut self.out).write_all(text.as_bytes());
+            let _ = EscapingWriter::text(&mut self.out)
+                .non_ascii(self.options.escape_non_ascii)
+                .write_all(text.as_bytes());
         }
     }
 
@@ -454,10 +1389,77 @@ impl XmlSerializer {
         }
     }
 
+    /// Length of the line break written by [`write_newline`](Self::write_newline).
+    fn newline_len(&self) -> usize {
+        if self.options.pretty {
+            self.options.newline.len()
+        } else {
+            0
+        }
+    }
+
     /// Write a newline (if pretty-printing is enabled).
     fn write_newline(&mut self) {
         if self.options.pretty {
-            self.out.push(b'\n');
+            self.out.extend_from_slice(self.options.newline.as_bytes());
+        }
+    }
+
+    /// Write ` xmlns:prefix="ns_uri"`.
+    fn write_namespace_declaration(&mut self, prefix: &str, ns_uri: &str) {
+        self.out.extend_from_slice(b" xmlns:");
+        self.out.extend_from_slice(prefix.as_bytes());
+        self.out.extend_from_slice(b"=\"");
+        self.out.extend_from_slice(ns_uri.as_bytes());
+        self.out.push(b'"');
+    }
+
+    /// The namespace `prefix` is bound to by the open elements, if any.
+    fn bound_namespace(&self, prefix: &str) -> Option<&str> {
+        self.prefix_scope
+            .iter()
+            .rev()
+            .find(|(_, declared, _)| declared == prefix)
+            .map(|(_, _, uri)| uri.as_str())
+    }
+
+    /// A prefix the open elements bind to `ns_uri`, if any.
+    fn scoped_prefix(&self, ns_uri: &str) -> Option<String> {
+        self.prefix_scope
+            .iter()
+            .rev()
+            .find(|(_, prefix, uri)| uri == ns_uri && self.bound_namespace(prefix) == Some(ns_uri))
+            .map(|(_, prefix, _)| prefix.clone())
+    }
+
+    /// The prefix to write a name in `ns_uri` with, and whether it needs
+    /// declaring: the field's `xml::prefix` if it is for this namespace, or
+    /// else one bound by the open elements, or else a new one.
+    fn prefix_for(&mut self, ns_uri: &str) -> (String, bool) {
+        if let Some((uri, prefix)) = &self.pending_prefix
+            && uri == ns_uri
+        {
+            return (prefix.clone(), self.bound_namespace(prefix) != Some(ns_uri));
+        }
+        match self.scoped_prefix(ns_uri) {
+            Some(prefix) => (prefix, false),
+            None => (self.get_or_create_prefix(ns_uri), true),
+        }
+    }
+
+    /// Declare the namespaces collected for `options.hoist_namespaces` on
+    /// the root element, whose start tag has just been written.
+    fn declare_hoisted_namespaces(&mut self) {
+        for ns_uri in core::mem::take(&mut self.hoisted_namespaces) {
+            if ns_uri == crate::XML_NAMESPACE
+                || self.current_default_ns.as_deref() == Some(ns_uri)
+                || self.scoped_prefix(ns_uri).is_some()
+            {
+                continue;
+            }
+            let prefix = self.get_or_create_prefix(ns_uri);
+            self.write_namespace_declaration(&prefix, ns_uri);
+            self.prefix_scope.push((1, prefix, ns_uri.to_string()));
         }
     }
 
@@ -500,7 +1502,11 @@ impl XmlSerializer {
         self.pending_is_elements = false;
         self.pending_is_doctype = false;
         self.pending_is_tag = false;
+        self.pending_is_raw_template = false;
+        self.pending_is_nillable = false;
         self.pending_namespace = None;
+        self.pending_prefix = None;
+        self.pending_no_ns = false;
     }
 }
 
@@ -515,13 +1521,43 @@ impl DomSerializer for XmlSerializer {
 
     fn element_start(&mut self, tag: &str, namespace: Option<&str>) -> Result<(), Self::Error> {
         // Priority: explicit namespace > pending_namespace > current_ns_all (for struct roots)
+        // Fields marked xml::no_ns skip the ns_all fallback
+        // An element that was read without a namespace stays without one
+        let requested_prefix = self.pending_element_prefix.take();
+        let no_ns = core::mem::take(&mut self.pending_no_ns)
+            || (requested_prefix.is_some() && namespace.is_none());
         let ns = namespace
             .map(|s| s.to_string())
             .or_else(|| self.pending_namespace.take())
-            .or_else(|| self.current_ns_all.clone());
+            .or_else(|| {
+                if no_ns {
+                    None
+                } else {
+                    self.current_ns_all.clone()
+                }
+            });
+
+        if self.options.audit {
+            self.audit_log.start(tag)?;
+        }
 
         // Write the opening tag immediately: `<tag` (attributes will follow)
-        self.write_element_tag_start(tag, ns.as_deref());
+        match (requested_prefix, ns.as_deref()) {
+            (Some(prefix), Some(ns_uri)) => {
+                self.write_element_tag_start_with_prefix(tag, ns_uri, prefix.as_deref())
+            }
+            _ => self.write_element_tag_start(tag, ns.as_deref()),
+        }
+        if no_ns && ns.is_none() && self.current_default_ns.is_some() {
+            // Undeclare the default namespace so the element stays unqualified
+            self.out.extend_from_slice(b" xmlns=\"\"");
+            let depth = self.element_stack.len();
+            self.default_ns_restore
+                .push((depth, self.current_default_ns.take()));
+        }
+        if self.element_stack.len() == 1 && !self.hoisted_namespaces.is_empty() {
+            self.declare_hoisted_namespaces();
+        }
         self.collecting_attributes = true;
 
         Ok(())
@@ -547,10 +1583,60 @@ impl DomSerializer for XmlSerializer {
         };
 
         // Write directly to output
-        self.write_attribute(name, value, ns.as_deref())
-            .map_err(|e| XmlSerializeError {
-                msg: Cow::Owned(format!("write error: {}", e)),
+        let written = self.write_attribute(name, value, ns.as_deref())?;
+        if written && self.options.audit {
+            let mut raw = Vec::new();
+            write_scalar_value(
+                &mut raw,
+                value,
+                self.options.float_formatter,
+                self.options.bool_style,
+            )?;
+            let raw = core::str::from_utf8(&raw).map_err(|_| XmlSerializeError {
+                msg: Cow::Borrowed("attribute value is not valid UTF-8"),
             })?;
+            self.audit_log.attribute(name, raw)?;
+        }
+        Ok(())
+    }
+
+    fn qname_attribute(
+        &mut self,
+        name: &str,
+        namespace: Option<&str>,
+        local_name: &str,
+        value_namespace: Option<&str>,
+    ) -> Result<(), Self::Error> {
+        // Names in the default namespace need no prefix
+        let Some(value_ns) =
+            value_namespace.filter(|ns| self.current_default_ns.as_deref() != Some(*ns))
+        else {
+            return self.attribute(name, Peek::new(&local_name), namespace);
+        };
+        if !self.collecting_attributes {
+            return Err(XmlSerializeError {
+                msg: Cow::Borrowed("attribute() called after children_start()"),
+            });
+        }
+
+        let mark = self.out.len();
+        let prefix = match self.scoped_prefix(value_ns) {
+            Some(prefix) => prefix,
+            None => {
+                let prefix = self.get_or_create_prefix(value_ns);
+                self.write_namespace_declaration(&prefix, value_ns);
+                let depth = self.element_stack.len();
+                self.prefix_scope
+                    .push((depth, prefix.clone(), value_ns.to_string()));
+                prefix
+            }
+        };
+        let qname = format!("{prefix}:{local_name}");
+        self.attribute(name, Peek::new(&qname), namespace)?;
+        // Keep the declaration with the attribute if attributes are reordered
+        if let Some((_, start, _)) = self.attribute_spans.last_mut() {
+            *start = mark;
+        }
         Ok(())
     }
 
@@ -568,15 +1654,44 @@ impl DomSerializer for XmlSerializer {
     fn element_end(&mut self, _tag: &str) -> Result<(), Self::Error> {
         if let Some(close_tag) = self.element_stack.pop() {
             self.write_close_tag(&close_tag);
+            if let Some((depth, _)) = self.default_ns_restore.last()
+                && *depth == self.element_stack.len() + 1
+                && let Some((_, default_ns)) = self.default_ns_restore.pop()
+            {
+                self.current_default_ns = default_ns;
+            }
+            while self
+                .prefix_scope
+                .last()
+                .is_some_and(|(depth, _, _)| *depth > self.element_stack.len())
+            {
+                self.prefix_scope.pop();
+            }
+            if self.options.audit {
+                self.audit_log.end();
+            }
         }
         Ok(())
     }
 
     fn text(&mut self, content: &str) -> Result<(), Self::Error> {
+        if self.options.audit {
+            self.audit_log.text(content)?;
+        }
         self.write_text_escaped(content);
         Ok(())
     }
 
+    fn comment(&mut self, content: &str) -> Result<(), Self::Error> {
+        self.write_comment(content);
+        Ok(())
+    }
+
+    fn processing_instruction(&mut self, target: &str, data: &str) -> Result<(), Self::Error> {
+        self.write_processing_instruction(target, data);
+        Ok(())
+    }
+
     fn struct_metadata(&mut self, shape: &facet_core::Shape) -> Result<(), Self::Error> {
         // Extract xml::ns_all attribute from the struct
         self.current_ns_all = shape
@@ -600,6 +1715,8 @@ impl DomSerializer for XmlSerializer {
             self.pending_is_elements = false;
             self.pending_is_doctype = false;
             self.pending_is_tag = false;
+            self.pending_is_raw_template = false;
+            self.pending_is_nillable = false;
             return Ok(());
         };
 
@@ -613,12 +1730,20 @@ impl DomSerializer for XmlSerializer {
         self.pending_is_doctype = field_def.get_attr(Some("xml"), "doctype").is_some();
         // Check if this field is a tag field
         self.pending_is_tag = field_def.get_attr(Some("xml"), "tag").is_some();
+        // Check if this field is a template filled from sibling fields
+        self.pending_is_raw_template = field_def.get_attr(Some("xml"), "raw_template").is_some();
+        // Check if this field is written with xsi:nil when None
+        self.pending_is_nillable = field_def.get_attr(Some("xml"), "nillable").is_some();
 
         // Extract xml::ns attribute from the field
         if let Some(ns_attr) = field_def.get_attr(Some("xml"), "ns")
             && let Some(ns_uri) = ns_attr.get_as::<&str>().copied()
         {
             self.pending_namespace = Some(ns_uri.to_string());
+        } else if field_def.get_attr(Some("xml"), "no_ns").is_some() {
+            // Unqualified element even inside an ns_all container
+            self.pending_namespace = None;
+            self.pending_no_ns = !self.pending_is_attribute && !self.pending_is_text;
         } else if !self.pending_is_attribute && !self.pending_is_text {
             // Apply ns_all to elements only (or None if no ns_all)
             self.pending_namespace = self.current_ns_all.clone();
@@ -626,6 +1751,11 @@ impl DomSerializer for XmlSerializer {
             // Attributes and text don't get namespace from ns_all
             self.pending_namespace = None;
         }
+        self.pending_prefix = field_def
+            .get_attr(Some("xml"), "prefix")
+            .and_then(|attr| attr.get_as::<&str>().copied())
+            .zip(self.pending_namespace.clone())
+            .map(|(prefix, ns_uri)| (ns_uri, prefix.to_string()));
 
         Ok(())
     }
@@ -657,14 +1787,39 @@ impl DomSerializer for XmlSerializer {
         self.pending_is_tag
     }
 
+    fn is_raw_template_field(&self) -> bool {
+        self.pending_is_raw_template
+    }
+
+    fn is_nillable_field(&self) -> bool {
+        self.pending_is_nillable
+    }
+
+    fn element_prefix(&mut self, prefix: Option<&str>) -> Result<(), Self::Error> {
+        self.pending_element_prefix = Some(prefix.map(str::to_string));
+        Ok(())
+    }
+
+    fn before_element(&mut self, tag: &str, value: Peek<'_, '_>) -> Result<(), Self::Error> {
+        if let Some(hooks) = self.options.hooks.clone() {
+            hooks.on_element_start(tag, value, &mut HookOutput { serializer: self });
+        }
+        Ok(())
+    }
+
+    fn after_element(&mut self, tag: &str, value: Peek<'_, '_>) -> Result<(), Self::Error> {
+        if let Some(hooks) = self.options.hooks.clone() {
+            hooks.on_element_end(tag, value, &mut HookOutput { serializer: self });
+        }
+        Ok(())
+    }
+
     fn doctype(&mut self, content: &str) -> Result<(), Self::Error> {
         // Emit DOCTYPE declaration
-        self.out.write_all(b"<!DOCTYPE ").unwrap();
-        self.out.write_all(content.as_bytes()).unwrap();
-        self.out.write_all(b">").unwrap();
-        if self.options.pretty {
-            self.out.write_all(b"\n").unwrap();
-        }
+        self.out.extend_from_slice(b"<!DOCTYPE ");
+        self.out.extend_from_slice(content.as_bytes());
+        self.out.push(b'>');
+        self.write_newline();
         Ok(())
     }
 
@@ -672,6 +1827,14 @@ impl DomSerializer for XmlSerializer {
         self.clear_field_state_impl();
     }
 
+    fn sort_map_keys(&self) -> bool {
+        self.options.sort_map_keys
+    }
+
+    fn bool_style(&self) -> BoolStyle {
+        self.options.bool_style
+    }
+
     fn format_float(&self, value: f64) -> String {
         if let Some(formatter) = self.options.float_formatter {
             let mut buf = Vec::new();
@@ -693,6 +1856,168 @@ impl DomSerializer for XmlSerializer {
     fn format_namespace(&self) -> Option<&'static str> {
         Some("xml")
     }
+
+    fn schema_version(&self) -> Option<&str> {
+        self.options.version.as_deref()
+    }
+}
+
+/// Buffered output size at which [`XmlSerializer`] writes to its
+/// `io::Write` destination.
+const SPILL_THRESHOLD: usize = 8 * 1024;
+
+/// A [`DomSerializer`] passing the output of an [`XmlSerializer`] on to a
+/// writer as it is produced.
+///
+/// Every call is forwarded; after those that complete a piece of markup the
+/// buffered output is spilled into `out`.
+struct Spilling<'a, W: ?Sized> {
+    serializer: &'a mut XmlSerializer,
+    out: &'a mut W,
+}
+
+impl<W: Write + ?Sized> DomSerializer for Spilling<'_, W> {
+    type Error = XmlSerializeError;
+
+    fn element_start(&mut self, tag: &str, namespace: Option<&str>) -> Result<(), Self::Error> {
+        self.serializer.element_start(tag, namespace)
+    }
+
+    fn attribute(
+        &mut self,
+        name: &str,
+        value: Peek<'_, '_>,
+        namespace: Option<&str>,
+    ) -> Result<(), Self::Error> {
+        self.serializer.attribute(name, value, namespace)
+    }
+
+    fn qname_attribute(
+        &mut self,
+        name: &str,
+        namespace: Option<&str>,
+        local_name: &str,
+        value_namespace: Option<&str>,
+    ) -> Result<(), Self::Error> {
+        self.serializer
+            .qname_attribute(name, namespace, local_name, value_namespace)
+    }
+
+    fn children_start(&mut self) -> Result<(), Self::Error> {
+        self.serializer.children_start()?;
+        Ok(self.serializer.spill(self.out)?)
+    }
+
+    fn children_end(&mut self) -> Result<(), Self::Error> {
+        self.serializer.children_end()
+    }
+
+    fn element_end(&mut self, tag: &str) -> Result<(), Self::Error> {
+        self.serializer.element_end(tag)?;
+        Ok(self.serializer.spill(self.out)?)
+    }
+
+    fn text(&mut self, content: &str) -> Result<(), Self::Error> {
+        self.serializer.text(content)?;
+        Ok(self.serializer.spill(self.out)?)
+    }
+
+    fn comment(&mut self, content: &str) -> Result<(), Self::Error> {
+        self.serializer.comment(content)?;
+        Ok(self.serializer.spill(self.out)?)
+    }
+
+    fn processing_instruction(&mut self, target: &str, data: &str) -> Result<(), Self::Error> {
+        self.serializer.processing_instruction(target, data)?;
+        Ok(self.serializer.spill(self.out)?)
+    }
+
+    fn doctype(&mut self, content: &str) -> Result<(), Self::Error> {
+        self.serializer.doctype(content)
+    }
+
+    fn element_prefix(&mut self, prefix: Option<&str>) -> Result<(), Self::Error> {
+        self.serializer.element_prefix(prefix)
+    }
+
+    fn before_element(&mut self, tag: &str, value: Peek<'_, '_>) -> Result<(), Self::Error> {
+        self.serializer.before_element(tag, value)
+    }
+
+    fn after_element(&mut self, tag: &str, value: Peek<'_, '_>) -> Result<(), Self::Error> {
+        self.serializer.after_element(tag, value)
+    }
+
+    fn struct_metadata(&mut self, shape: &facet_core::Shape) -> Result<(), Self::Error> {
+        self.serializer.struct_metadata(shape)
+    }
+
+    fn field_metadata(&mut self, field: &facet_reflect::FieldItem) -> Result<(), Self::Error> {
+        self.serializer.field_metadata(field)
+    }
+
+    fn variant_metadata(
+        &mut self,
+        variant: &'static facet_core::Variant,
+    ) -> Result<(), Self::Error> {
+        self.serializer.variant_metadata(variant)
+    }
+
+    fn is_attribute_field(&self) -> bool {
+        self.serializer.is_attribute_field()
+    }
+
+    fn is_text_field(&self) -> bool {
+        self.serializer.is_text_field()
+    }
+
+    fn is_elements_field(&self) -> bool {
+        self.serializer.is_elements_field()
+    }
+
+    fn is_tag_field(&self) -> bool {
+        self.serializer.is_tag_field()
+    }
+
+    fn is_doctype_field(&self) -> bool {
+        self.serializer.is_doctype_field()
+    }
+
+    fn is_raw_template_field(&self) -> bool {
+        self.serializer.is_raw_template_field()
+    }
+
+    fn is_nillable_field(&self) -> bool {
+        self.serializer.is_nillable_field()
+    }
+
+    fn clear_field_state(&mut self) {
+        self.serializer.clear_field_state()
+    }
+
+    fn format_float(&self, value: f64) -> String {
+        self.serializer.format_float(value)
+    }
+
+    fn sort_map_keys(&self) -> bool {
+        self.serializer.sort_map_keys()
+    }
+
+    fn bool_style(&self) -> BoolStyle {
+        self.serializer.bool_style()
+    }
+
+    fn serialize_none(&mut self) -> Result<(), Self::Error> {
+        self.serializer.serialize_none()
+    }
+
+    fn format_namespace(&self) -> Option<&'static str> {
+        self.serializer.format_namespace()
+    }
+
+    fn schema_version(&self) -> Option<&str> {
+        self.serializer.schema_version()
+    }
 }
 
 /// Serialize a value to XML bytes with default options.
@@ -711,19 +2036,41 @@ pub fn to_vec_with_options<'facet, T>(
 where
     T: Facet<'facet> + ?Sized,
 {
+    let peek = Peek::new(value);
+    let shape = peek.shape();
+    enter_span!("xml_serialize", type_identifier = shape.type_identifier);
     let mut serializer = XmlSerializer::with_options(options.clone());
-    facet_dom::serialize(&mut serializer, Peek::new(value))?;
+    serializer
+        .out
+        .reserve(options.initial_capacity.unwrap_or_else(|| size_hint(shape)));
+    serializer.start_document(shape);
+    if options.attribute_rules.is_empty() {
+        facet_dom::serialize(&mut serializer, peek)?;
+    } else {
+        facet_dom::serialize(&mut options.attribute_rules.wrap(&mut serializer), peek)?;
+    }
+    serializer.verify_output()?;
+    if options.initial_capacity.is_none() {
+        record_size_hint(shape, serializer.out.len());
+    }
     Ok(serializer.finish())
 }
 
+/// The serializer only writes UTF-8 itself, but a custom [`FloatFormatter`]
+/// can write arbitrary bytes.
+fn invalid_utf8() -> DomSerializeError<XmlSerializeError> {
+    DomSerializeError::Backend(XmlSerializeError {
+        msg: Cow::Borrowed("serialized XML is not valid UTF-8"),
+    })
+}
+
 /// Serialize a value to an XML string with default options.
 pub fn to_string<'facet, T>(value: &'_ T) -> Result<String, DomSerializeError<XmlSerializeError>>
 where
     T: Facet<'facet> + ?Sized,
 {
     let bytes = to_vec(value)?;
-    // SAFETY: XmlSerializer produces valid UTF-8
-    Ok(String::from_utf8(bytes).expect("XmlSerializer produces valid UTF-8"))
+    String::from_utf8(bytes).map_err(|_| invalid_utf8())
 }
 
 /// Serialize a value to a pretty-printed XML string with default indentation.
@@ -745,8 +2092,168 @@ where
     T: Facet<'facet> + ?Sized,
 {
     let bytes = to_vec_with_options(value, options)?;
-    // SAFETY: XmlSerializer produces valid UTF-8
-    Ok(String::from_utf8(bytes).expect("XmlSerializer produces valid UTF-8"))
+    String::from_utf8(bytes).map_err(|_| invalid_utf8())
+}
+
+/// Serialize a value into an [`std::io::Write`] sink with default options.
+///
+/// Output is written in chunks as it is produced, so a large document is
+/// never held in memory as a whole. Wrap unbuffered writers such as a
+/// `File` in a `BufWriter`.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+///
+/// #[derive(Facet)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let mut out = Vec::new();
+/// facet_xml::to_writer(&mut out, &Point { x: 1, y: 2 }).unwrap();
+/// assert_eq!(out, b"<point><x>1</x><y>2</y></point>");
+/// ```
+pub fn to_writer<'facet, W, T>(
+    writer: &mut W,
+    value: &'_ T,
+) -> Result<(), DomSerializeError<XmlSerializeError>>
+where
+    W: Write + ?Sized,
+    T: Facet<'facet> + ?Sized,
+{
+    to_writer_with_options(writer, value, &SerializeOptions::default())
+}
+
+/// Serialize a value into an [`std::io::Write`] sink with custom options.
+pub fn to_writer_with_options<'facet, W, T>(
+    writer: &mut W,
+    value: &'_ T,
+    options: &SerializeOptions,
+) -> Result<(), DomSerializeError<XmlSerializeError>>
+where
+    W: Write + ?Sized,
+    T: Facet<'facet> + ?Sized,
+{
+    let peek = Peek::new(value);
+    enter_span!(
+        "xml_serialize",
+        type_identifier = peek.shape().type_identifier
+    );
+    let mut serializer = XmlSerializer::with_options(options.clone());
+    let rules = (!options.attribute_rules.is_empty()).then_some(&options.attribute_rules);
+    serializer.serialize_streaming(peek, rules, writer)
+}
+
+/// Serialize a value into a [`core::fmt::Write`] sink with default options.
+///
+/// Useful for rendering into a `String`, a `Formatter`, or any other sink
+/// that doesn't implement `std::io::Write`.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+///
+/// #[derive(Facet)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// let mut out = String::from("point: ");
+/// facet_xml::to_fmt_writer(&mut out, &Point { x: 1, y: 2 }).unwrap();
+/// assert_eq!(out, "point: <point><x>1</x><y>2</y></point>");
+/// ```
+pub fn to_fmt_writer<'facet, W, T>(
+    writer: &mut W,
+    value: &'_ T,
+) -> Result<(), DomSerializeError<XmlSerializeError>>
+where
+    W: core::fmt::Write + ?Sized,
+    T: Facet<'facet> + ?Sized,
+{
+    to_fmt_writer_with_options(writer, value, &SerializeOptions::default())
+}
+
+/// Serialize a value into a [`core::fmt::Write`] sink with custom options.
+///
+/// Output is passed on to `writer` as it is produced, like
+/// [`to_writer_with_options`], rather than after the whole document is
+/// built.
+pub fn to_fmt_writer_with_options<'facet, W, T>(
+    writer: &mut W,
+    value: &'_ T,
+    options: &SerializeOptions,
+) -> Result<(), DomSerializeError<XmlSerializeError>>
+where
+    W: core::fmt::Write + ?Sized,
+    T: Facet<'facet> + ?Sized,
+{
+    let peek = Peek::new(value);
+    enter_span!(
+        "xml_serialize",
+        type_identifier = peek.shape().type_identifier
+    );
+    let mut serializer = XmlSerializer::with_options(options.clone());
+    let rules = (!options.attribute_rules.is_empty()).then_some(&options.attribute_rules);
+    let mut adapter = FmtAdapter {
+        writer,
+        pending: Vec::new(),
+    };
+    serializer.serialize_streaming(peek, rules, &mut adapter)?;
+    if !adapter.pending.is_empty() {
+        return Err(invalid_utf8());
+    }
+    Ok(())
+}
+
+/// A [`core::fmt::Write`] sink seen as a [`Write`], so output can be spilled
+/// into it as it is produced.
+///
+/// Markup is handed over in whole pieces, but a custom [`FloatFormatter`]
+/// can write arbitrary bytes, so an incomplete UTF-8 sequence at the end of
+/// a write is held back until the next one.
+struct FmtAdapter<'a, W: ?Sized> {
+    writer: &'a mut W,
+    pending: Vec<u8>,
+}
+
+impl<W: core::fmt::Write + ?Sized> Write for FmtAdapter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut pending = core::mem::take(&mut self.pending);
+        let bytes = if pending.is_empty() {
+            buf
+        } else {
+            pending.extend_from_slice(buf);
+            &pending[..]
+        };
+        let (text, rest) = match core::str::from_utf8(bytes) {
+            Ok(text) => (text, &[][..]),
+            Err(e) if e.error_len().is_none() => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                let text = core::str::from_utf8(valid).expect("valid up to here");
+                (text, rest)
+            }
+            Err(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "serialized XML is not valid UTF-8",
+                ));
+            }
+        };
+        self.writer
+            .write_str(text)
+            .map_err(|_| std::io::Error::other("formatter error while writing XML"))?;
+        self.pending = rest.to_vec();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
 
 /// Escape special characters while preserving entity references.
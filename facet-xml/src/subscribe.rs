@@ -0,0 +1,114 @@
+//! Event-driven deserialization of the elements at a path.
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use facet_core::Facet;
+use facet_dom::{DomDeserializer, DomEvent, DomParser};
+
+use crate::{DeserializeError, XmlError, XmlParser};
+
+/// Deserialize every element at `path` as a `T`, handing each one to
+/// `on_item` as soon as its closing tag has been read.
+///
+/// `path` is a slash-separated list of tags starting at the root element,
+/// e.g. `"feed/entry"`; a `*` segment matches any tag. Elements off the path
+/// are skipped without being deserialized, and only one `T` is in memory at
+/// a time, so arbitrarily large documents can be processed in constant
+/// space. Returns the number of items delivered.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+///
+/// #[derive(Facet, Debug, PartialEq)]
+/// struct Entry {
+///     title: String,
+/// }
+///
+/// let xml = r#"<feed>
+///     <meta><entry><title>not this one</title></entry></meta>
+///     <entry><title>first</title></entry>
+///     <entry><title>second</title></entry>
+/// </feed>"#;
+///
+/// let mut titles = Vec::new();
+/// let count = facet_xml::subscribe(xml.as_bytes(), "feed/entry", |entry: Entry| {
+///     titles.push(entry.title);
+/// })
+/// .unwrap();
+/// assert_eq!(count, 2);
+/// assert_eq!(titles, ["first", "second"]);
+/// ```
+pub fn subscribe<T, F>(
+    input: &[u8],
+    path: &str,
+    mut on_item: F,
+) -> Result<usize, DeserializeError<XmlError>>
+where
+    T: Facet<'static>,
+    F: FnMut(T),
+{
+    let segments: Vec<&str> = path.split('/').collect();
+    if segments.iter().any(|segment| segment.is_empty()) {
+        return Err(DeserializeError::Unsupported(alloc::format!(
+            "invalid subscription path {path:?}: empty segment"
+        )));
+    }
+
+    let mut parser = XmlParser::new(input);
+    let mut count = 0;
+    // Number of currently open elements; all of them match a path prefix
+    let mut depth = 0usize;
+
+    loop {
+        enum Step {
+            Deliver,
+            Descend,
+            Skip,
+            Close,
+            Pass,
+        }
+
+        let step = match parser.peek_event().map_err(DeserializeError::Parser)? {
+            None => break,
+            Some(DomEvent::NodeStart { tag, .. }) => match segments.get(depth) {
+                Some(&segment) if segment == "*" || segment == tag.as_ref() => {
+                    if depth + 1 == segments.len() {
+                        Step::Deliver
+                    } else {
+                        Step::Descend
+                    }
+                }
+                _ => Step::Skip,
+            },
+            Some(DomEvent::NodeEnd) => Step::Close,
+            Some(_) => Step::Pass,
+        };
+
+        match step {
+            Step::Deliver => {
+                let item = DomDeserializer::new_owned(&mut parser).deserialize::<T>()?;
+                crate::assertions::check(&item)?;
+                on_item(item);
+                count += 1;
+            }
+            Step::Skip => parser.skip_node().map_err(DeserializeError::Parser)?,
+            Step::Descend => {
+                parser.next_event().map_err(DeserializeError::Parser)?;
+                depth += 1;
+            }
+            Step::Close => {
+                parser.next_event().map_err(DeserializeError::Parser)?;
+                depth = depth.saturating_sub(1);
+            }
+            Step::Pass => {
+                parser.next_event().map_err(DeserializeError::Parser)?;
+            }
+        }
+    }
+
+    Ok(count)
+}
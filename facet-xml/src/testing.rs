@@ -0,0 +1,560 @@
+//! Helpers for comparing XML documents in tests.
+
+extern crate alloc;
+
+use alloc::{borrow::Cow, format, string::String, vec, vec::Vec};
+use core::fmt::Write as _;
+
+use std::io::IsTerminal as _;
+
+use facet_dom::{DomEvent, DomParser};
+use owo_colors::{OwoColorize as _, Style};
+
+use crate::XmlParser;
+
+/// Lines of unchanged context shown around each change.
+const CONTEXT: usize = 3;
+
+/// Render a unified diff of two XML documents, for use in assertion messages.
+///
+/// Both documents are first normalized to one node per line: elements are
+/// indented by depth, attributes are sorted by name, whitespace-only text is
+/// dropped and an element whose only child is text is kept on one line. This
+/// makes compact and pretty-printed documents comparable, and a change shows
+/// up on the line of the node it affects rather than as one long changed line.
+///
+/// Removed lines are red, added lines green. Color is only used when stderr,
+/// where assertion messages end up, is a terminal and the `NO_COLOR`
+/// environment variable is not set. A document that fails to parse is
+/// compared as raw text lines.
+///
+/// Returns an empty string when the normalized documents are equal.
+///
+/// ```
+/// let diff = facet_xml::testing::diff_pretty(
+///     r#"<a x="1" y="2"><b>one</b></a>"#,
+///     r#"<a y="2" x="1"><b>two</b></a>"#,
+/// );
+/// assert!(diff.contains("<b>one</b>"));
+/// assert!(diff.contains("<b>two</b>"));
+/// // Attribute order is ignored, so `<a>` is unchanged context
+/// assert!(!diff.contains("-<a"));
+///
+/// assert!(facet_xml::testing::diff_pretty("<a><b/></a>", "<a>\n  <b></b>\n</a>").is_empty());
+/// ```
+pub fn diff_pretty(expected: &str, actual: &str) -> String {
+    let color = std::env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal();
+    render_diff(&normalize(expected), &normalize(actual), color)
+}
+
+/// How a value is replaced before a snapshot is compared.
+#[derive(Debug, Clone)]
+enum Redaction {
+    /// Replace the value of every attribute with this (local) name.
+    Attribute { name: String, replacement: String },
+    /// Replace the text content of every element with this tag.
+    Text {
+        element: String,
+        replacement: String,
+    },
+}
+
+/// Settings for [`assert_xml_snapshot!`](crate::assert_xml_snapshot).
+///
+/// A snapshot is stored as `<dir>/<name>.xml`, where `dir` defaults to
+/// `tests/snapshots` in the calling crate. The stored form is the normalized
+/// document produced for [`diff_pretty`], so attribute order and formatting
+/// never cause spurious failures.
+///
+/// A missing snapshot fails the assertion, and a mismatch panics with a diff.
+/// With `UPDATE_SNAPSHOTS=1` set, the snapshot is written instead, unless the
+/// `CI` environment variable is also set: CI never creates or updates
+/// snapshots.
+#[derive(Debug, Clone)]
+pub struct SnapshotSettings {
+    name: String,
+    dir: Option<std::path::PathBuf>,
+    redactions: Vec<Redaction>,
+}
+
+impl SnapshotSettings {
+    /// Settings for the snapshot called `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            dir: None,
+            redactions: Vec::new(),
+        }
+    }
+
+    /// Store the snapshot in `dir` instead of `tests/snapshots`.
+    ///
+    /// Relative paths are resolved against the calling crate's manifest directory.
+    pub fn dir(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// Replace the value of every attribute named `name` with `replacement`.
+    pub fn redact_attribute(
+        mut self,
+        name: impl Into<String>,
+        replacement: impl Into<String>,
+    ) -> Self {
+        self.redactions.push(Redaction::Attribute {
+            name: name.into(),
+            replacement: replacement.into(),
+        });
+        self
+    }
+
+    /// Replace the text of every `<element>` with `replacement`.
+    pub fn redact_text(
+        mut self,
+        element: impl Into<String>,
+        replacement: impl Into<String>,
+    ) -> Self {
+        self.redactions.push(Redaction::Text {
+            element: element.into(),
+            replacement: replacement.into(),
+        });
+        self
+    }
+}
+
+impl From<&str> for SnapshotSettings {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+/// Serialize `value`, redact it and compare it with a stored snapshot.
+///
+/// ```no_run
+/// use facet::Facet;
+/// use facet_xml as xml;
+/// use facet_xml::testing::SnapshotSettings;
+///
+/// #[derive(Facet)]
+/// struct Event {
+///     #[facet(xml::attribute)]
+///     id: u32,
+///     created: String,
+/// }
+///
+/// let event = Event { id: 1, created: "2024-05-01T12:00:00Z".into() };
+/// facet_xml::assert_xml_snapshot!(
+///     event,
+///     SnapshotSettings::new("event").redact_text("created", "[timestamp]")
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_xml_snapshot {
+    ($value:expr, $settings:expr $(,)?) => {
+        $crate::testing::assert_snapshot(
+            &$value,
+            $crate::testing::SnapshotSettings::from($settings),
+            env!("CARGO_MANIFEST_DIR"),
+        )
+    };
+}
+
+/// Implementation of [`assert_xml_snapshot!`](crate::assert_xml_snapshot).
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_snapshot<'facet, T>(value: &T, settings: SnapshotSettings, manifest_dir: &str)
+where
+    T: facet_core::Facet<'facet> + ?Sized,
+{
+    let xml = match crate::to_string(value) {
+        Ok(xml) => xml,
+        Err(e) => panic!("snapshot `{}`: serialization failed: {e}", settings.name),
+    };
+    let mut actual = normalize_with(&xml, &settings.redactions).join("\n");
+    actual.push('\n');
+
+    let dir = std::path::Path::new(manifest_dir).join(
+        settings
+            .dir
+            .as_deref()
+            .unwrap_or("tests/snapshots".as_ref()),
+    );
+    let path = dir.join(format!("{}.xml", settings.name));
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some_and(|v| v == "1")
+        && std::env::var_os("CI").is_none();
+
+    let write = |reason: &str| {
+        if let Err(e) = std::fs::create_dir_all(&dir).and_then(|()| std::fs::write(&path, &actual))
+        {
+            panic!(
+                "snapshot `{}`: cannot write {}: {e}",
+                settings.name,
+                path.display()
+            );
+        }
+        std::eprintln!("snapshot `{}`: {reason} {}", settings.name, path.display());
+    };
+
+    let expected = match std::fs::read_to_string(&path) {
+        Ok(expected) => expected,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if update {
+                return write("created");
+            }
+            panic!(
+                "snapshot `{}` is missing: {}\n{actual}\nrerun with UPDATE_SNAPSHOTS=1 to create it",
+                settings.name,
+                path.display()
+            );
+        }
+        Err(e) => panic!(
+            "snapshot `{}`: cannot read {}: {e}",
+            settings.name,
+            path.display()
+        ),
+    };
+
+    let diff = diff_pretty(&expected, &actual);
+    if diff.is_empty() {
+        return;
+    }
+    if update {
+        return write("updated");
+    }
+    panic!(
+        "snapshot `{}` does not match {}\n{diff}\nrerun with UPDATE_SNAPSHOTS=1 to accept the new output",
+        settings.name,
+        path.display()
+    );
+}
+
+/// Normalize a document to the line form used by [`diff_pretty`].
+fn normalize(input: &str) -> Vec<String> {
+    normalize_with(input, &[])
+}
+
+/// Normalize a document, replacing redacted values along the way.
+fn normalize_with(input: &str, redactions: &[Redaction]) -> Vec<String> {
+    match collect_events(input) {
+        Some(events) => {
+            let mut lines = Vec::new();
+            let cx = Normalizer {
+                events: &events,
+                redactions,
+            };
+            cx.render_nodes(&mut 0, 0, None, &mut lines);
+            lines
+        }
+        None => input.lines().map(String::from).collect(),
+    }
+}
+
+fn collect_events(input: &str) -> Option<Vec<DomEvent<'_>>> {
+    let mut parser = XmlParser::new(input.as_bytes());
+    let mut events = Vec::new();
+    while let Some(event) = parser.next_event().ok()? {
+        events.push(event);
+    }
+    Some(events)
+}
+
+/// Walks parsed events and renders them as normalized lines.
+struct Normalizer<'e, 'r> {
+    events: &'e [DomEvent<'e>],
+    redactions: &'r [Redaction],
+}
+
+impl<'e, 'r> Normalizer<'e, 'r> {
+    /// Render sibling nodes starting at `events[*pos]` until the enclosing
+    /// `ChildrenEnd` (or the end of the document).
+    fn render_nodes(
+        &self,
+        pos: &mut usize,
+        depth: usize,
+        redact_text: Option<&str>,
+        lines: &mut Vec<String>,
+    ) {
+        let indent = "  ".repeat(depth);
+        while let Some(event) = self.events.get(*pos) {
+            *pos += 1;
+            match event {
+                DomEvent::NodeStart { tag, namespace } => {
+                    let open = self.render_open_tag(tag, namespace.as_deref(), pos);
+                    self.render_element(tag, open, pos, depth, lines);
+                }
+                DomEvent::Text(text) => {
+                    let text = text.trim();
+                    if !text.is_empty() {
+                        let text = redact_text.unwrap_or(text);
+                        lines.push(format!("{indent}{}", escape(text)));
+                    }
+                }
+                DomEvent::Comment(text) => lines.push(format!("{indent}<!--{text}-->")),
+                DomEvent::ProcessingInstruction { target, data } => {
+                    lines.push(format!("{indent}<?{target} {data}?>"))
+                }
+                DomEvent::Doctype(text) => lines.push(format!("{indent}<!DOCTYPE {text}>")),
+                DomEvent::ChildrenEnd => return,
+                DomEvent::Attribute { .. } | DomEvent::ChildrenStart | DomEvent::NodeEnd => {}
+            }
+        }
+    }
+
+    /// Consume the attributes and `ChildrenStart` after a `NodeStart`, returning
+    /// the opening tag without its closing `>`.
+    fn render_open_tag(&self, tag: &str, namespace: Option<&str>, pos: &mut usize) -> String {
+        let mut attrs: Vec<(Cow<'_, str>, &str)> = Vec::new();
+        while let Some(event) = self.events.get(*pos) {
+            match event {
+                DomEvent::Attribute {
+                    name,
+                    value,
+                    namespace: Some(ns),
+                } => attrs.push((
+                    Cow::Owned(format!("{{{ns}}}{name}")),
+                    self.redacted_attr(name).unwrap_or(value.as_ref()),
+                )),
+                DomEvent::Attribute { name, value, .. } => attrs.push((
+                    Cow::Borrowed(name.as_ref()),
+                    self.redacted_attr(name).unwrap_or(value.as_ref()),
+                )),
+                DomEvent::ChildrenStart => {
+                    *pos += 1;
+                    break;
+                }
+                _ => break,
+            }
+            *pos += 1;
+        }
+        attrs.sort();
+
+        let mut open = format!("<{tag}");
+        if let Some(ns) = namespace {
+            let _ = write!(open, " xmlns=\"{}\"", escape(ns));
+        }
+        for (name, value) in attrs {
+            let _ = write!(open, " {name}=\"{}\"", escape(value));
+        }
+        open
+    }
+
+    fn render_element(
+        &self,
+        tag: &str,
+        open: String,
+        pos: &mut usize,
+        depth: usize,
+        lines: &mut Vec<String>,
+    ) {
+        let indent = "  ".repeat(depth);
+        let start = lines.len();
+        lines.push(String::new());
+        self.render_nodes(pos, depth + 1, self.redacted_text(tag), lines);
+        if matches!(self.events.get(*pos), Some(DomEvent::NodeEnd)) {
+            *pos += 1;
+        }
+
+        match lines.len() - start {
+            1 => lines[start] = format!("{indent}{open}/>"),
+            // A single text child stays on the element's line
+            2 if !lines[start + 1][indent.len() + 2..].starts_with('<') => {
+                let text = lines.pop().unwrap_or_default();
+                lines[start] = format!("{indent}{open}>{}</{tag}>", text.trim_start());
+            }
+            _ => {
+                lines[start] = format!("{indent}{open}>");
+                lines.push(format!("{indent}</{tag}>"));
+            }
+        }
+    }
+
+    fn redacted_attr(&self, name: &str) -> Option<&'r str> {
+        self.redactions.iter().find_map(|r| match r {
+            Redaction::Attribute {
+                name: n,
+                replacement,
+            } if n == name => Some(replacement.as_str()),
+            _ => None,
+        })
+    }
+
+    fn redacted_text(&self, tag: &str) -> Option<&'r str> {
+        self.redactions.iter().find_map(|r| match r {
+            Redaction::Text {
+                element,
+                replacement,
+            } if element == tag => Some(replacement.as_str()),
+            _ => None,
+        })
+    }
+}
+
+fn escape(s: &str) -> Cow<'_, str> {
+    if !s.contains(['&', '<', '>', '"']) {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len() + 8);
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Same,
+    Removed,
+    Added,
+}
+
+/// Line-level edit script between `a` and `b` via longest common subsequence.
+fn diff_lines(a: &[String], b: &[String]) -> Vec<(Op, usize, usize)> {
+    let (n, m) = (a.len(), b.len());
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            ops.push((Op::Same, i, j));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push((Op::Removed, i, j));
+            i += 1;
+        } else {
+            ops.push((Op::Added, i, j));
+            j += 1;
+        }
+    }
+    ops
+}
+
+fn render_diff(expected: &[String], actual: &[String], color: bool) -> String {
+    let ops = diff_lines(expected, actual);
+    if ops.iter().all(|(op, ..)| *op == Op::Same) {
+        return String::new();
+    }
+
+    let mut out = String::new();
+    let mut line = |style: Style, text: &str| {
+        let _ = if color {
+            writeln!(out, "{}", text.style(style))
+        } else {
+            writeln!(out, "{text}")
+        };
+    };
+    line(Style::new().red(), "--- expected");
+    line(Style::new().green(), "+++ actual");
+
+    // Group changes into hunks, merging those separated by little context
+    let changes: Vec<usize> = (0..ops.len()).filter(|&k| ops[k].0 != Op::Same).collect();
+    let mut k = 0;
+    while k < changes.len() {
+        let start = changes[k].saturating_sub(CONTEXT);
+        let mut end = changes[k];
+        while k < changes.len() && changes[k] <= end + 2 * CONTEXT {
+            end = changes[k];
+            k += 1;
+        }
+        let end = (end + CONTEXT + 1).min(ops.len());
+        let hunk = &ops[start..end];
+
+        let old_len = hunk.iter().filter(|(op, ..)| *op != Op::Added).count();
+        let new_len = hunk.iter().filter(|(op, ..)| *op != Op::Removed).count();
+        let (_, old_start, new_start) = hunk[0];
+        line(
+            Style::new().cyan(),
+            &format!(
+                "@@ -{},{old_len} +{},{new_len} @@",
+                old_start + 1,
+                new_start + 1
+            ),
+        );
+        for &(op, i, j) in hunk {
+            match op {
+                Op::Same => line(Style::new().dimmed(), &format!(" {}", expected[i])),
+                Op::Removed => line(Style::new().red(), &format!("-{}", expected[i])),
+                Op::Added => line(Style::new().green(), &format!("+{}", actual[j])),
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redactions_replace_attributes_and_text() {
+        let redactions = [
+            Redaction::Attribute {
+                name: "at".into(),
+                replacement: "[ts]".into(),
+            },
+            Redaction::Text {
+                element: "created".into(),
+                replacement: "[ts]".into(),
+            },
+        ];
+        let lines = normalize_with(
+            r#"<e at="12:00" id="1"><created>12:00</created><other>12:00</other></e>"#,
+            &redactions,
+        );
+        assert_eq!(
+            lines,
+            [
+                r#"<e at="[ts]" id="1">"#,
+                "  <created>[ts]</created>",
+                "  <other>12:00</other>",
+                "</e>",
+            ]
+        );
+    }
+
+    #[test]
+    fn normalizes_structure() {
+        let lines = normalize(r#"<a z="1" b="2"><b>text</b><c/><d><e/></d></a>"#);
+        assert_eq!(
+            lines,
+            [
+                r#"<a b="2" z="1">"#,
+                "  <b>text</b>",
+                "  <c/>",
+                "  <d>",
+                "    <e/>",
+                "  </d>",
+                "</a>",
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_diff_has_hunk_and_markers() {
+        let diff = render_diff(
+            &normalize("<a><b>1</b><c/></a>"),
+            &normalize("<a><b>2</b><c/></a>"),
+            false,
+        );
+        assert_eq!(
+            diff,
+            "--- expected\n+++ actual\n@@ -1,4 +1,4 @@\n <a>\n-  <b>1</b>\n+  <b>2</b>\n   <c/>\n </a>\n"
+        );
+    }
+}
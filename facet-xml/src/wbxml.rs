@@ -0,0 +1,855 @@
+//! WAP Binary XML (WBXML) input and output, enabled by the `wbxml` feature.
+//!
+//! WBXML replaces tag and attribute names with one-byte tokens from
+//! application-defined code pages (ActiveSync, OMA provisioning, SyncML,
+//! ...). The code pages are supplied as [`CodePages`]; names missing from
+//! them are carried as literals in the string table, so any document can
+//! be written.
+
+extern crate alloc;
+
+use alloc::{
+    borrow::Cow,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use facet_core::Facet;
+use facet_dom::{DomEvent, DomParser, VecEventParser};
+
+use crate::serializer::XmlSerializeError;
+use crate::{DeserializeError, SerializeError, XmlParser};
+
+const SWITCH_PAGE: u8 = 0x00;
+const END: u8 = 0x01;
+const ENTITY: u8 = 0x02;
+const STR_I: u8 = 0x03;
+const LITERAL: u8 = 0x04;
+const PI: u8 = 0x43;
+const STR_T: u8 = 0x83;
+const OPAQUE: u8 = 0xC3;
+
+/// Tag token flag: the element has attributes.
+const HAS_ATTRIBUTES: u8 = 0x80;
+/// Tag token flag: the element has content.
+const HAS_CONTENT: u8 = 0x40;
+
+/// IANA MIBenum of UTF-8, the only charset written.
+const UTF8: u32 = 106;
+/// IANA MIBenum of US-ASCII, a subset of UTF-8.
+const US_ASCII: u32 = 3;
+/// Public identifier meaning "unknown or missing".
+const UNKNOWN_PUBLIC_ID: u32 = 1;
+
+/// Token tables of one WBXML application.
+///
+/// # Example
+///
+/// ```
+/// use facet_xml::CodePages;
+///
+/// // A slice of the ActiveSync AirSync code page
+/// let pages = CodePages::new()
+///     .public_id(1)
+///     .tag_page(0, Some("AirSync:"), &[(0x05, "Sync"), (0x0F, "Collection")]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CodePages {
+    public_id: Option<u32>,
+    tags: Vec<TagPage>,
+    attribute_starts: Vec<(u8, &'static [(u8, &'static str, &'static str)])>,
+    attribute_values: Vec<(u8, &'static [(u8, &'static str)])>,
+}
+
+#[derive(Debug, Clone)]
+struct TagPage {
+    page: u8,
+    namespace: Option<&'static str>,
+    tokens: &'static [(u8, &'static str)],
+}
+
+impl CodePages {
+    /// No code pages: every name is written as a literal.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The public identifier written in the header (default: 1, "unknown").
+    pub fn public_id(mut self, id: u32) -> Self {
+        self.public_id = Some(id);
+        self
+    }
+
+    /// Add tag code page `page`, mapping tokens `0x05..=0x3F` to tag names.
+    ///
+    /// Elements read through this page get `namespace`; when writing, an
+    /// element in `namespace` is looked up in this page first.
+    pub fn tag_page(
+        mut self,
+        page: u8,
+        namespace: Option<&'static str>,
+        tokens: &'static [(u8, &'static str)],
+    ) -> Self {
+        self.tags.push(TagPage {
+            page,
+            namespace,
+            tokens,
+        });
+        self
+    }
+
+    /// Add attribute code page `page`.
+    ///
+    /// `starts` maps attribute-start tokens (`0x05..=0x7F`) to an attribute
+    /// name and the beginning of its value (often empty); `values` maps
+    /// attribute-value tokens (`0x85..=0xFF`) to the text they stand for.
+    pub fn attribute_page(
+        mut self,
+        page: u8,
+        starts: &'static [(u8, &'static str, &'static str)],
+        values: &'static [(u8, &'static str)],
+    ) -> Self {
+        self.attribute_starts.push((page, starts));
+        self.attribute_values.push((page, values));
+        self
+    }
+
+    fn tag(&self, page: u8, token: u8) -> Option<(&'static str, Option<&'static str>)> {
+        self.tags.iter().filter(|p| p.page == page).find_map(|p| {
+            p.tokens
+                .iter()
+                .find(|(t, _)| *t == token)
+                .map(|&(_, name)| (name, p.namespace))
+        })
+    }
+
+    /// The `(page, token)` to write for a tag, preferring pages of its
+    /// namespace, then the current page.
+    fn tag_token(&self, name: &str, namespace: Option<&str>, current: u8) -> Option<(u8, u8)> {
+        let find = |page: &TagPage| {
+            page.tokens
+                .iter()
+                .find(|(_, n)| *n == name)
+                .map(|&(token, _)| (page.page, token))
+        };
+        let in_namespace = |page: &&TagPage| namespace.is_some() && page.namespace == namespace;
+        self.tags
+            .iter()
+            .filter(in_namespace)
+            .find_map(find)
+            .or_else(|| {
+                self.tags
+                    .iter()
+                    .filter(|p| p.page == current)
+                    .find_map(find)
+            })
+            .or_else(|| self.tags.iter().find_map(find))
+    }
+
+    fn attribute_start(&self, page: u8, token: u8) -> Option<(&'static str, &'static str)> {
+        self.attribute_starts
+            .iter()
+            .filter(|(p, _)| *p == page)
+            .find_map(|(_, starts)| {
+                starts
+                    .iter()
+                    .find(|(t, _, _)| *t == token)
+                    .map(|&(_, name, prefix)| (name, prefix))
+            })
+    }
+
+    /// The `(page, token)` of an attribute start for `name` with no value
+    /// prefix.
+    fn attribute_start_token(&self, name: &str) -> Option<(u8, u8)> {
+        self.attribute_starts.iter().find_map(|(page, starts)| {
+            starts
+                .iter()
+                .find(|(_, n, prefix)| *n == name && prefix.is_empty())
+                .map(|&(token, _, _)| (*page, token))
+        })
+    }
+
+    fn attribute_value(&self, page: u8, token: u8) -> Option<&'static str> {
+        self.attribute_values
+            .iter()
+            .filter(|(p, _)| *p == page)
+            .find_map(|(_, values)| {
+                values
+                    .iter()
+                    .find(|(t, _)| *t == token)
+                    .map(|&(_, value)| value)
+            })
+    }
+}
+
+/// Error decoding a WBXML document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WbxmlError {
+    /// The document ended in the middle of a token.
+    UnexpectedEof,
+    /// A tag or attribute token missing from the code pages.
+    UnknownToken { page: u8, token: u8 },
+    /// The document declares a charset other than UTF-8 or US-ASCII.
+    UnsupportedCharset(u32),
+    /// The document uses a WBXML feature this reader doesn't implement.
+    Unsupported(&'static str),
+    /// A string is not valid UTF-8.
+    InvalidUtf8(core::str::Utf8Error),
+    /// The document is not valid WBXML.
+    Malformed(String),
+}
+
+impl fmt::Display for WbxmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WbxmlError::UnexpectedEof => write!(f, "unexpected end of WBXML document"),
+            WbxmlError::UnknownToken { page, token } => {
+                write!(f, "token {token:#04x} is not in code page {page}")
+            }
+            WbxmlError::UnsupportedCharset(mib) => {
+                write!(f, "unsupported charset (MIBenum {mib})")
+            }
+            WbxmlError::Unsupported(what) => write!(f, "unsupported WBXML feature: {what}"),
+            WbxmlError::InvalidUtf8(e) => write!(f, "invalid UTF-8: {e}"),
+            WbxmlError::Malformed(msg) => write!(f, "malformed WBXML document: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for WbxmlError {}
+
+/// A [`DomParser`] over a WBXML document.
+///
+/// The whole document is decoded when the parser is created; events are
+/// then replayed from memory. `OPAQUE` data is surfaced as base64 text, so
+/// it can be read into a `#[facet(xml::codec = "base64")]` field.
+pub struct WbxmlParser {
+    events: VecEventParser<'static>,
+}
+
+impl WbxmlParser {
+    /// Decode `input` with the tokens of `pages`.
+    pub fn new(input: &[u8], pages: &CodePages) -> Result<Self, WbxmlError> {
+        let events = Reader {
+            input,
+            pos: 0,
+            pages,
+            string_table: &[],
+        }
+        .decode()?;
+        Ok(Self {
+            events: VecEventParser::new(events).format_namespace("xml"),
+        })
+    }
+}
+
+impl<'de> DomParser<'de> for WbxmlParser {
+    type Error = WbxmlError;
+
+    fn next_event(&mut self) -> Result<Option<DomEvent<'de>>, Self::Error> {
+        self.events.next_event().map_err(|e| match e {})
+    }
+
+    fn peek_event(&mut self) -> Result<Option<&DomEvent<'de>>, Self::Error> {
+        self.events.peek_event().map_err(|e| match e {})
+    }
+
+    fn skip_node(&mut self) -> Result<(), Self::Error> {
+        self.events.skip_node().map_err(|e| match e {})
+    }
+
+    fn format_namespace(&self) -> Option<&'static str> {
+        self.events.format_namespace()
+    }
+}
+
+/// Deserialize a value from a WBXML document into an owned type.
+///
+/// The type is mapped exactly as for [`from_slice`](crate::from_slice), with
+/// tag names resolved through `pages`.
+pub fn from_wbxml<T>(input: &[u8], pages: &CodePages) -> Result<T, DeserializeError<WbxmlError>>
+where
+    T: Facet<'static>,
+{
+    enter_span!("wbxml_document", bytes = input.len());
+    let parser = WbxmlParser::new(input, pages).map_err(DeserializeError::Parser)?;
+    let value = facet_dom::DomDeserializer::new_owned(parser).deserialize()?;
+    crate::assertions::check(&value).map_err(|e| match e {
+        DeserializeError::AssertionFailed { path, message } => {
+            DeserializeError::AssertionFailed { path, message }
+        }
+        DeserializeError::Validation { path, message } => {
+            DeserializeError::Validation { path, message }
+        }
+        // The checks fail with nothing else
+        _ => unreachable!("unexpected assertion error: {e}"),
+    })?;
+    Ok(value)
+}
+
+/// Serialize a value to a WBXML 1.3 document, UTF-8 encoded.
+///
+/// The value is laid out exactly as [`to_vec`](crate::to_vec) would write
+/// it; tag and attribute names found in `pages` are tokenized, the others
+/// are written as literals. Text and attribute values are written inline.
+pub fn to_wbxml<'facet, T>(
+    value: &T,
+    pages: &CodePages,
+) -> Result<Vec<u8>, SerializeError<XmlSerializeError>>
+where
+    T: Facet<'facet> + ?Sized,
+{
+    let xml = crate::to_vec(value)?;
+    let mut parser = XmlParser::new(&xml);
+    let mut events = Vec::new();
+    while let Some(event) = parser.next_event().map_err(|e| {
+        SerializeError::Backend(XmlSerializeError {
+            msg: Cow::Owned(format!("wbxml: can't re-read serialized XML: {e}")),
+        })
+    })? {
+        events.push(event);
+    }
+    Ok(encode(&events, pages))
+}
+
+/// Reads an `mb_u_int32`: 7-bit groups, most significant first, each with a
+/// continuation flag in its high bit.
+fn read_mb_u_int32(input: &[u8], pos: &mut usize) -> Result<u32, WbxmlError> {
+    let mut value = 0u32;
+    for _ in 0..5 {
+        let byte = *input.get(*pos).ok_or(WbxmlError::UnexpectedEof)?;
+        *pos += 1;
+        value = (value << 7) | u32::from(byte & 0x7f);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(WbxmlError::Malformed(
+        "mb_u_int32 longer than 5 bytes".into(),
+    ))
+}
+
+fn write_mb_u_int32(out: &mut Vec<u8>, value: u32) {
+    let mut shift = 28;
+    while shift > 0 && value >> shift == 0 {
+        shift -= 7;
+    }
+    loop {
+        let group = ((value >> shift) & 0x7f) as u8;
+        if shift == 0 {
+            out.push(group);
+            return;
+        }
+        out.push(group | 0x80);
+        shift -= 7;
+    }
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    pos: usize,
+    pages: &'a CodePages,
+    string_table: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn decode(mut self) -> Result<Vec<DomEvent<'static>>, WbxmlError> {
+        let version = self.byte()?;
+        if self.mb_u_int32()? == 0 {
+            // Public identifier given as a string table reference
+            self.mb_u_int32()?;
+        }
+        // WBXML 1.0 has no charset field
+        if version > 0 {
+            let charset = self.mb_u_int32()?;
+            if !matches!(charset, 0 | US_ASCII | UTF8) {
+                return Err(WbxmlError::UnsupportedCharset(charset));
+            }
+        }
+        let len = self.mb_u_int32()? as usize;
+        self.string_table = self
+            .input
+            .get(self.pos..self.pos + len)
+            .ok_or(WbxmlError::UnexpectedEof)?;
+        self.pos += len;
+
+        let mut events = Vec::new();
+        let mut text = String::new();
+        let mut tag_page = 0;
+        let mut attribute_page = 0;
+        let mut open = 0usize;
+        let mut seen_root = false;
+        while !seen_root || open > 0 {
+            let token = self.byte()?;
+            match token {
+                SWITCH_PAGE => tag_page = self.byte()?,
+                END => {
+                    if open == 0 {
+                        return Err(WbxmlError::Malformed("END outside of an element".into()));
+                    }
+                    flush_text(&mut text, &mut events);
+                    events.push(DomEvent::ChildrenEnd);
+                    events.push(DomEvent::NodeEnd);
+                    open -= 1;
+                }
+                ENTITY | STR_I | STR_T | OPAQUE => self.append_string(token, &mut text)?,
+                PI => return Err(WbxmlError::Unsupported("processing instructions")),
+                0x40..=0x42 | 0x80..=0x82 | 0xC0..=0xC2 => {
+                    return Err(WbxmlError::Unsupported("extension tokens"));
+                }
+                _ => {
+                    if open == 0 && seen_root {
+                        return Err(WbxmlError::Malformed("more than one root element".into()));
+                    }
+                    flush_text(&mut text, &mut events);
+                    let (tag, namespace) = if token & 0x3f == LITERAL {
+                        (self.table_string()?.to_string(), None)
+                    } else {
+                        let (name, namespace) = self.pages.tag(tag_page, token & 0x3f).ok_or(
+                            WbxmlError::UnknownToken {
+                                page: tag_page,
+                                token: token & 0x3f,
+                            },
+                        )?;
+                        (name.to_string(), namespace)
+                    };
+                    events.push(DomEvent::NodeStart {
+                        tag: Cow::Owned(tag),
+                        namespace: namespace.map(Cow::Borrowed),
+                    });
+                    if token & HAS_ATTRIBUTES != 0 {
+                        self.read_attributes(&mut attribute_page, &mut events)?;
+                    }
+                    events.push(DomEvent::ChildrenStart);
+                    if token & HAS_CONTENT != 0 {
+                        open += 1;
+                    } else {
+                        events.push(DomEvent::ChildrenEnd);
+                        events.push(DomEvent::NodeEnd);
+                    }
+                    seen_root = true;
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    fn read_attributes(
+        &mut self,
+        page: &mut u8,
+        events: &mut Vec<DomEvent<'static>>,
+    ) -> Result<(), WbxmlError> {
+        let mut current: Option<(String, String)> = None;
+        let mut push = |current: &mut Option<(String, String)>| {
+            if let Some((name, value)) = current.take() {
+                events.push(DomEvent::Attribute {
+                    name: Cow::Owned(name),
+                    value: Cow::Owned(value),
+                    namespace: None,
+                });
+            }
+        };
+        loop {
+            let token = self.byte()?;
+            match token {
+                END => {
+                    push(&mut current);
+                    return Ok(());
+                }
+                SWITCH_PAGE => *page = self.byte()?,
+                LITERAL => {
+                    push(&mut current);
+                    current = Some((self.table_string()?.to_string(), String::new()));
+                }
+                ENTITY | STR_I | STR_T | OPAQUE => {
+                    let (_, value) = current.as_mut().ok_or_else(|| {
+                        WbxmlError::Malformed("attribute value without a name".into())
+                    })?;
+                    self.append_string(token, value)?;
+                }
+                0x40..=0x42 | 0x80..=0x82 | 0xC0..=0xC2 => {
+                    return Err(WbxmlError::Unsupported("extension tokens"));
+                }
+                0x05..=0x7F => {
+                    push(&mut current);
+                    let (name, prefix) = self
+                        .pages
+                        .attribute_start(*page, token)
+                        .ok_or(WbxmlError::UnknownToken { page: *page, token })?;
+                    current = Some((name.to_string(), prefix.to_string()));
+                }
+                _ => {
+                    let text = self
+                        .pages
+                        .attribute_value(*page, token)
+                        .ok_or(WbxmlError::UnknownToken { page: *page, token })?;
+                    let (_, value) = current.as_mut().ok_or_else(|| {
+                        WbxmlError::Malformed("attribute value without a name".into())
+                    })?;
+                    value.push_str(text);
+                }
+            }
+        }
+    }
+
+    /// Append the text of an `ENTITY`, `STR_I`, `STR_T` or `OPAQUE` token.
+    fn append_string(&mut self, token: u8, out: &mut String) -> Result<(), WbxmlError> {
+        match token {
+            ENTITY => {
+                let code = self.mb_u_int32()?;
+                let c = char::from_u32(code)
+                    .ok_or_else(|| WbxmlError::Malformed(format!("invalid entity {code:#x}")))?;
+                out.push(c);
+            }
+            STR_I => {
+                let rest = &self.input[self.pos..];
+                let len = rest
+                    .iter()
+                    .position(|&b| b == 0)
+                    .ok_or(WbxmlError::UnexpectedEof)?;
+                out.push_str(core::str::from_utf8(&rest[..len]).map_err(WbxmlError::InvalidUtf8)?);
+                self.pos += len + 1;
+            }
+            STR_T => out.push_str(self.table_string()?),
+            OPAQUE => {
+                let len = self.mb_u_int32()? as usize;
+                let data = self
+                    .input
+                    .get(self.pos..self.pos + len)
+                    .ok_or(WbxmlError::UnexpectedEof)?;
+                self.pos += len;
+                let base64 = facet_dom::codec::lookup_codec("base64").expect("built in");
+                out.push_str(&base64.encode(data));
+            }
+            _ => unreachable!("not a string token"),
+        }
+        Ok(())
+    }
+
+    /// The null-terminated string at the string table offset read next.
+    fn table_string(&mut self) -> Result<&'a str, WbxmlError> {
+        let offset = self.mb_u_int32()? as usize;
+        let rest = self.string_table.get(offset..).ok_or_else(|| {
+            WbxmlError::Malformed(format!("string table offset {offset} out of range"))
+        })?;
+        let len = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+        core::str::from_utf8(&rest[..len]).map_err(WbxmlError::InvalidUtf8)
+    }
+
+    fn byte(&mut self) -> Result<u8, WbxmlError> {
+        let byte = *self.input.get(self.pos).ok_or(WbxmlError::UnexpectedEof)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn mb_u_int32(&mut self) -> Result<u32, WbxmlError> {
+        read_mb_u_int32(self.input, &mut self.pos)
+    }
+}
+
+fn flush_text(text: &mut String, events: &mut Vec<DomEvent<'static>>) {
+    if !text.is_empty() {
+        events.push(DomEvent::Text(Cow::Owned(core::mem::take(text))));
+    }
+}
+
+/// An element rebuilt from events, so flags can be computed before writing
+/// its tag.
+#[derive(Default)]
+struct Node<'e> {
+    tag: &'e str,
+    namespace: Option<&'e str>,
+    attributes: Vec<(&'e str, &'e str)>,
+    children: Vec<Child<'e>>,
+}
+
+enum Child<'e> {
+    Element(Node<'e>),
+    Text(&'e str),
+}
+
+fn build_tree<'e>(events: &'e [DomEvent<'_>]) -> Vec<Node<'e>> {
+    let mut stack = alloc::vec![Node::default()];
+    for event in events {
+        let current = stack.last_mut().expect("document node is never popped");
+        match event {
+            DomEvent::NodeStart { tag, namespace } => stack.push(Node {
+                tag,
+                namespace: namespace.as_deref(),
+                ..Node::default()
+            }),
+            DomEvent::Attribute { name, value, .. } => {
+                current.attributes.push((name.as_ref(), value.as_ref()));
+            }
+            DomEvent::Text(text) => current.children.push(Child::Text(text)),
+            DomEvent::NodeEnd if stack.len() > 1 => {
+                let node = stack.pop().expect("checked above");
+                let parent = stack.last_mut().expect("checked above");
+                parent.children.push(Child::Element(node));
+            }
+            _ => {}
+        }
+    }
+    stack.truncate(1);
+    let document = stack.pop().unwrap_or_default();
+    document
+        .children
+        .into_iter()
+        .filter_map(|child| match child {
+            Child::Element(node) => Some(node),
+            Child::Text(_) => None,
+        })
+        .collect()
+}
+
+/// Tokenizes a document; literal names go to the string table.
+struct Writer<'p> {
+    pages: &'p CodePages,
+    body: Vec<u8>,
+    string_table: Vec<u8>,
+    /// Offsets of the names already in the string table
+    literals: Vec<(String, u32)>,
+    tag_page: u8,
+    attribute_page: u8,
+}
+
+fn encode(events: &[DomEvent<'_>], pages: &CodePages) -> Vec<u8> {
+    let mut writer = Writer {
+        pages,
+        body: Vec::new(),
+        string_table: Vec::new(),
+        literals: Vec::new(),
+        tag_page: 0,
+        attribute_page: 0,
+    };
+    if let Some(root) = build_tree(events).first() {
+        writer.element(root);
+    }
+
+    // Version 1.3
+    let mut out = alloc::vec![0x03];
+    write_mb_u_int32(&mut out, pages.public_id.unwrap_or(UNKNOWN_PUBLIC_ID));
+    write_mb_u_int32(&mut out, UTF8);
+    write_mb_u_int32(&mut out, writer.string_table.len() as u32);
+    out.extend_from_slice(&writer.string_table);
+    out.extend_from_slice(&writer.body);
+    out
+}
+
+impl Writer<'_> {
+    fn element(&mut self, node: &Node<'_>) {
+        let mut flags = 0;
+        if !node.attributes.is_empty() {
+            flags |= HAS_ATTRIBUTES;
+        }
+        if !node.children.is_empty() {
+            flags |= HAS_CONTENT;
+        }
+        match self
+            .pages
+            .tag_token(node.tag, node.namespace, self.tag_page)
+        {
+            Some((page, token)) => {
+                if page != self.tag_page {
+                    self.body.extend_from_slice(&[SWITCH_PAGE, page]);
+                    self.tag_page = page;
+                }
+                self.body.push(token | flags);
+            }
+            None => {
+                self.body.push(LITERAL | flags);
+                self.literal(node.tag);
+            }
+        }
+
+        if !node.attributes.is_empty() {
+            for &(name, value) in &node.attributes {
+                match self.pages.attribute_start_token(name) {
+                    Some((page, token)) => {
+                        if page != self.attribute_page {
+                            self.body.extend_from_slice(&[SWITCH_PAGE, page]);
+                            self.attribute_page = page;
+                        }
+                        self.body.push(token);
+                    }
+                    None => {
+                        self.body.push(LITERAL);
+                        self.literal(name);
+                    }
+                }
+                if !value.is_empty() {
+                    self.inline_string(value);
+                }
+            }
+            self.body.push(END);
+        }
+
+        if !node.children.is_empty() {
+            for child in &node.children {
+                match child {
+                    Child::Element(element) => self.element(element),
+                    Child::Text(text) => self.inline_string(text),
+                }
+            }
+            self.body.push(END);
+        }
+    }
+
+    fn inline_string(&mut self, s: &str) {
+        self.body.push(STR_I);
+        self.body.extend_from_slice(s.as_bytes());
+        self.body.push(0);
+    }
+
+    /// Write a reference to `name` in the string table, adding it if needed.
+    fn literal(&mut self, name: &str) {
+        let offset = match self.literals.iter().find(|(n, _)| n == name) {
+            Some(&(_, offset)) => offset,
+            None => {
+                let offset = self.string_table.len() as u32;
+                self.string_table.extend_from_slice(name.as_bytes());
+                self.string_table.push(0);
+                self.literals.push((name.into(), offset));
+                offset
+            }
+        };
+        write_mb_u_int32(&mut self.body, offset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AIRSYNC: &[(u8, &str)] = &[
+        (0x05, "Sync"),
+        (0x0B, "SyncKey"),
+        (0x0F, "Collection"),
+        (0x12, "CollectionId"),
+    ];
+
+    fn pages() -> CodePages {
+        CodePages::new().tag_page(0, None, AIRSYNC).attribute_page(
+            0,
+            &[(0x05, "kind", ""), (0x06, "kind", "mail")],
+            &[(0x85, ".box")],
+        )
+    }
+
+    fn decode(input: &[u8], pages: &CodePages) -> Result<Vec<DomEvent<'static>>, WbxmlError> {
+        Reader {
+            input,
+            pos: 0,
+            pages,
+            string_table: &[],
+        }
+        .decode()
+    }
+
+    fn node(tag: &str) -> DomEvent<'static> {
+        DomEvent::NodeStart {
+            tag: Cow::Owned(tag.into()),
+            namespace: None,
+        }
+    }
+
+    #[test]
+    fn decodes_tokens_literals_and_attribute_values() {
+        // <Sync><Collection kind="mail.box"><x-note>hi</x-note><SyncKey/></Collection></Sync>
+        let input = [
+            0x03, 0x01, 0x6A, 0x07, b'x', b'-', b'n', b'o', b't', b'e', 0x00, // header
+            0x45, // Sync, content
+            0xCF, 0x06, 0x85, 0x01, // Collection, attributes and content
+            0x44, 0x00, 0x03, b'h', b'i', 0x00, 0x01, // literal x-note
+            0x0B, // SyncKey, empty
+            0x01, 0x01,
+        ];
+        let attribute = DomEvent::Attribute {
+            name: "kind".into(),
+            value: "mail.box".into(),
+            namespace: None,
+        };
+        assert_eq!(
+            decode(&input, &pages()).unwrap(),
+            [
+                node("Sync"),
+                DomEvent::ChildrenStart,
+                node("Collection"),
+                attribute,
+                DomEvent::ChildrenStart,
+                node("x-note"),
+                DomEvent::ChildrenStart,
+                DomEvent::Text("hi".into()),
+                DomEvent::ChildrenEnd,
+                DomEvent::NodeEnd,
+                node("SyncKey"),
+                DomEvent::ChildrenStart,
+                DomEvent::ChildrenEnd,
+                DomEvent::NodeEnd,
+                DomEvent::ChildrenEnd,
+                DomEvent::NodeEnd,
+                DomEvent::ChildrenEnd,
+                DomEvent::NodeEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn encoded_documents_decode_to_the_same_events() {
+        let events = [
+            node("Sync"),
+            DomEvent::ChildrenStart,
+            node("CollectionId"),
+            DomEvent::Attribute {
+                name: "kind".into(),
+                value: "inbox".into(),
+                namespace: None,
+            },
+            DomEvent::Attribute {
+                name: "owner".into(),
+                value: "me".into(),
+                namespace: None,
+            },
+            DomEvent::ChildrenStart,
+            DomEvent::Text("7".into()),
+            DomEvent::ChildrenEnd,
+            DomEvent::NodeEnd,
+            node("unknown"),
+            DomEvent::ChildrenStart,
+            DomEvent::ChildrenEnd,
+            DomEvent::NodeEnd,
+            DomEvent::ChildrenEnd,
+            DomEvent::NodeEnd,
+        ];
+        let bytes = encode(&events, &pages());
+        assert_eq!(bytes[..4], [0x03, 0x01, 0x6A, 0x0E]);
+        assert_eq!(&bytes[4..18], b"owner\0unknown\0");
+        assert_eq!(decode(&bytes, &pages()).unwrap(), events);
+    }
+
+    #[test]
+    fn unknown_tokens_are_errors() {
+        let input = [0x03, 0x01, 0x6A, 0x00, 0x07];
+        assert_eq!(
+            decode(&input, &pages()),
+            Err(WbxmlError::UnknownToken {
+                page: 0,
+                token: 0x07
+            })
+        );
+    }
+
+    #[test]
+    fn mb_u_int32_round_trips() {
+        for value in [0, 0x7f, 0x80, 0x3fff, 0x4000, u32::MAX] {
+            let mut out = Vec::new();
+            write_mb_u_int32(&mut out, value);
+            assert_eq!(read_mb_u_int32(&out, &mut 0).unwrap(), value);
+        }
+        let mut out = Vec::new();
+        write_mb_u_int32(&mut out, 0xA0);
+        assert_eq!(out, [0x81, 0x20]);
+    }
+}
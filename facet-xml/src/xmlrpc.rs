@@ -0,0 +1,718 @@
+//! XML-RPC requests and responses, enabled by the `xmlrpc` feature.
+//!
+//! XML-RPC doesn't name values after their fields: every value sits in a
+//! `<value>` element and is wrapped in one naming its type (`<int>`,
+//! `<string>`, `<struct>`, `<array>`, ...). [`method_call`] writes a
+//! `<methodCall>` from any facet value, and [`parse_response`] reads a
+//! `<methodResponse>` into one, by rewriting the value into the element
+//! layout the regular deserializer reads:
+//!
+//! - structs and maps are `<struct>`s, with a `<member>` per field (named
+//!   after `rename`, as elements would be) or map entry;
+//! - `Vec`s, arrays, sets and tuples are `<array>`s;
+//! - `bool`s are `<boolean>`, integers `<int>` (`<i8>` when they may not
+//!   fit in 32 bits), floats `<double>`, and everything else `<string>`;
+//! - `None` fields are left out, and other `None`s are `<nil/>`.
+//!
+//! `<dateTime.iso8601>` and `<base64>` values are read as text. A
+//! `<fault>` response is returned as [`XmlRpcError::Fault`].
+
+extern crate alloc;
+
+use alloc::{borrow::Cow, format, string::String, vec, vec::Vec};
+use core::convert::Infallible;
+use core::fmt;
+use std::io::Write;
+
+use facet::Facet;
+use facet_core::{Def, ScalarType, StructKind};
+use facet_dom::{BoolStyle, DomDeserializer, DomEvent, DomParser, VecEventParser};
+use facet_reflect::{HasFields as _, Peek};
+
+use crate::escaping::EscapingWriter;
+use crate::serializer::write_scalar_value;
+use crate::{
+    DeserializeError, DeserializeOptions, SerializeError, Whitespace, XmlError, XmlParser,
+    XmlSerializeError,
+};
+
+/// The error a server reports instead of a result.
+#[derive(Facet, Debug, Clone, PartialEq)]
+pub struct Fault {
+    /// The server's code for the error.
+    #[facet(rename = "faultCode")]
+    pub code: i32,
+    /// What went wrong.
+    #[facet(rename = "faultString")]
+    pub message: String,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fault {}: {}", self.code, self.message)
+    }
+}
+
+/// Error reading an XML-RPC response.
+#[derive(Debug)]
+pub enum XmlRpcError {
+    /// The input isn't well-formed XML.
+    Xml(XmlError),
+    /// The input is XML, but not an XML-RPC response.
+    Protocol(String),
+    /// The server answered with a fault.
+    Fault(Fault),
+    /// The result doesn't fit the target type.
+    Value(DeserializeError<Infallible>),
+}
+
+impl fmt::Display for XmlRpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XmlRpcError::Xml(e) => write!(f, "invalid XML: {e}"),
+            XmlRpcError::Protocol(msg) => write!(f, "invalid XML-RPC response: {msg}"),
+            XmlRpcError::Fault(fault) => write!(f, "{fault}"),
+            XmlRpcError::Value(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for XmlRpcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            XmlRpcError::Xml(e) => Some(e),
+            XmlRpcError::Value(e) => Some(e),
+            XmlRpcError::Protocol(_) | XmlRpcError::Fault(_) => None,
+        }
+    }
+}
+
+/// Serialize a call of the method `name` to a `<methodCall>` document.
+///
+/// A tuple passes each of its elements as a parameter and `()` passes
+/// none; any other value is the single parameter.
+///
+/// # Example
+///
+/// ```
+/// let call = facet_xml::xmlrpc::method_call("sample.add", &(2, 3)).unwrap();
+/// assert_eq!(
+///     call,
+///     "<?xml version=\"1.0\"?><methodCall><methodName>sample.add</methodName><params>\
+///      <param><value><int>2</int></value></param>\
+///      <param><value><int>3</int></value></param>\
+///      </params></methodCall>"
+/// );
+/// ```
+pub fn method_call<'facet, T>(
+    name: &str,
+    args: &T,
+) -> Result<String, SerializeError<XmlSerializeError>>
+where
+    T: Facet<'facet> + ?Sized,
+{
+    let mut out = Vec::new();
+    out.extend_from_slice(b"<?xml version=\"1.0\"?><methodCall><methodName>");
+    write_text(&mut out, name)?;
+    out.extend_from_slice(b"</methodName><params>");
+
+    let args = Peek::new(args).innermost_peek();
+    let params = match args.into_struct() {
+        Ok(tuple) if tuple.ty().kind == StructKind::Tuple => {
+            tuple.fields_for_serialize().map(|(_, arg)| arg).collect()
+        }
+        _ if args.scalar_type() == Some(ScalarType::Unit) => Vec::new(),
+        _ => vec![args],
+    };
+    for param in params {
+        out.extend_from_slice(b"<param>");
+        write_value(&mut out, param)?;
+        out.extend_from_slice(b"</param>");
+    }
+
+    out.extend_from_slice(b"</params></methodCall>");
+    String::from_utf8(out).map_err(|_| {
+        SerializeError::Backend(XmlSerializeError {
+            msg: Cow::Borrowed("invalid UTF-8 in XML-RPC output"),
+        })
+    })
+}
+
+/// Deserialize the result of a `<methodResponse>` document.
+///
+/// # Example
+///
+/// ```
+/// use facet::Facet;
+///
+/// #[derive(Facet, Debug, PartialEq)]
+/// struct Sum {
+///     total: i32,
+/// }
+///
+/// let response = r#"<?xml version="1.0"?>
+/// <methodResponse>
+///   <params>
+///     <param>
+///       <value><struct>
+///         <member><name>total</name><value><int>5</int></value></member>
+///       </struct></value>
+///     </param>
+///   </params>
+/// </methodResponse>"#;
+/// let sum: Sum = facet_xml::xmlrpc::parse_response(response).unwrap();
+/// assert_eq!(sum, Sum { total: 5 });
+/// ```
+pub fn parse_response<T>(input: &str) -> Result<T, XmlRpcError>
+where
+    T: Facet<'static>,
+{
+    /// The result, as the one child of the response element.
+    #[derive(Facet)]
+    #[facet(rename = "methodResponse")]
+    struct Response<V> {
+        value: V,
+    }
+
+    // Whitespace inside <string> (or an untyped <value>) is part of it
+    let options = DeserializeOptions::default().whitespace(Whitespace::Preserve);
+    let mut translator = Translator {
+        parser: XmlParser::with_options(input.as_bytes(), &options),
+        events: Vec::new(),
+    };
+
+    match translator.next_item()? {
+        Some(Item::Start(tag)) if tag == "methodResponse" => {}
+        _ => return Err(invalid("the document element must be <methodResponse>")),
+    }
+    match translator.next_item()? {
+        Some(Item::Start(tag)) if tag == "params" => {
+            translator.expect_start("param")?;
+            translator.expect_start("value")?;
+            translator.start(Cow::Borrowed("methodResponse"));
+            translator.value(Cow::Borrowed("value"), false)?;
+            translator.end();
+            translator.expect_end("param")?;
+            translator.expect_end("params")?;
+            translator.expect_end("methodResponse")?;
+            let response: Response<T> = translator.deserialize()?;
+            Ok(response.value)
+        }
+        Some(Item::Start(tag)) if tag == "fault" => {
+            translator.expect_start("value")?;
+            translator.value(Cow::Borrowed("fault"), false)?;
+            Err(XmlRpcError::Fault(translator.deserialize()?))
+        }
+        _ => Err(invalid("expected <params> or <fault>")),
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> XmlRpcError {
+    XmlRpcError::Protocol(msg.into())
+}
+
+fn unsupported(msg: String) -> SerializeError<XmlSerializeError> {
+    SerializeError::Unsupported(Cow::Owned(msg))
+}
+
+fn write_text(out: &mut Vec<u8>, text: &str) -> Result<(), SerializeError<XmlSerializeError>> {
+    EscapingWriter::text(out)
+        .write_all(text.as_bytes())
+        .map_err(|e| SerializeError::Backend(e.into()))
+}
+
+/// Write `value` as a `<value>` element.
+fn write_value(
+    out: &mut Vec<u8>,
+    value: Peek<'_, '_>,
+) -> Result<(), SerializeError<XmlSerializeError>> {
+    let value = value.innermost_peek();
+
+    if let Def::Pointer(_) = value.shape().def
+        && let Ok(ptr) = value.into_pointer()
+        && let Some(inner) = ptr.borrow_inner()
+    {
+        return write_value(out, inner);
+    }
+
+    if let Ok(opt) = value.into_option() {
+        return match opt.value() {
+            Some(inner) => write_value(out, inner),
+            None => {
+                out.extend_from_slice(b"<value><nil/></value>");
+                Ok(())
+            }
+        };
+    }
+
+    out.extend_from_slice(b"<value>");
+    match value.scalar_type() {
+        Some(ScalarType::Unit) => out.extend_from_slice(b"<nil/>"),
+        Some(ScalarType::Bool) => {
+            let b = value.get::<bool>().map_err(SerializeError::Reflect)?;
+            out.extend_from_slice(if *b {
+                b"<boolean>1</boolean>"
+            } else {
+                b"<boolean>0</boolean>"
+            });
+        }
+        Some(scalar_type) => {
+            let tag = match scalar_type {
+                ScalarType::I8
+                | ScalarType::I16
+                | ScalarType::I32
+                | ScalarType::U8
+                | ScalarType::U16 => "int",
+                ScalarType::I64
+                | ScalarType::I128
+                | ScalarType::ISize
+                | ScalarType::U32
+                | ScalarType::U64
+                | ScalarType::U128
+                | ScalarType::USize => "i8",
+                ScalarType::F32 | ScalarType::F64 => "double",
+                _ => "string",
+            };
+            write!(out, "<{tag}>").map_err(|e| SerializeError::Backend(e.into()))?;
+            write_scalar_value(
+                &mut EscapingWriter::text(out),
+                value,
+                None,
+                BoolStyle::TrueFalse,
+            )
+            .map_err(SerializeError::Backend)?;
+            write!(out, "</{tag}>").map_err(|e| SerializeError::Backend(e.into()))?;
+        }
+        None => write_compound(out, value)?,
+    }
+    out.extend_from_slice(b"</value>");
+    Ok(())
+}
+
+/// Write the contents of a `<value>` holding something other than a
+/// primitive type.
+fn write_compound(
+    out: &mut Vec<u8>,
+    value: Peek<'_, '_>,
+) -> Result<(), SerializeError<XmlSerializeError>> {
+    if let Def::List(_) | Def::Array(_) | Def::Slice(_) = value.shape().def {
+        let list = value.into_list_like().map_err(SerializeError::Reflect)?;
+        return write_array(out, list.iter());
+    }
+
+    if let Ok(set) = value.into_set() {
+        return write_array(out, set.iter());
+    }
+
+    if let Ok(map) = value.into_map() {
+        out.extend_from_slice(b"<struct>");
+        for (key, val) in map.iter() {
+            let key = match key.as_str() {
+                Some(s) => Cow::Borrowed(s),
+                None => Cow::Owned(format!("{key}")),
+            };
+            write_member(out, &key, val)?;
+        }
+        out.extend_from_slice(b"</struct>");
+        return Ok(());
+    }
+
+    if let Ok(struct_) = value.into_struct() {
+        if struct_.ty().kind == StructKind::Tuple {
+            return write_array(out, struct_.fields_for_serialize().map(|(_, item)| item));
+        }
+        out.extend_from_slice(b"<struct>");
+        for (field_item, field_value) in struct_.fields_for_serialize() {
+            // Absent optional members are the XML-RPC norm; <nil/> isn't
+            // understood everywhere
+            if let Ok(opt) = field_value.innermost_peek().into_option()
+                && opt.is_none()
+            {
+                continue;
+            }
+            let name = match field_item.field.and_then(|f| f.rename) {
+                Some(rename) => Cow::Borrowed(rename),
+                None => facet_dom::naming::to_element_name(&field_item.name),
+            };
+            write_member(out, &name, field_value)?;
+        }
+        out.extend_from_slice(b"</struct>");
+        return Ok(());
+    }
+
+    // Unit enum variants are written as their name
+    let mut name = Vec::new();
+    if write_scalar_value(
+        &mut EscapingWriter::text(&mut name),
+        value,
+        None,
+        BoolStyle::TrueFalse,
+    )
+    .map_err(SerializeError::Backend)?
+    {
+        out.extend_from_slice(b"<string>");
+        out.extend_from_slice(&name);
+        out.extend_from_slice(b"</string>");
+        return Ok(());
+    }
+
+    Err(unsupported(format!(
+        "{} has no XML-RPC representation",
+        value.shape().type_identifier
+    )))
+}
+
+fn write_array<'mem, 'facet>(
+    out: &mut Vec<u8>,
+    items: impl Iterator<Item = Peek<'mem, 'facet>>,
+) -> Result<(), SerializeError<XmlSerializeError>> {
+    out.extend_from_slice(b"<array><data>");
+    for item in items {
+        write_value(out, item)?;
+    }
+    out.extend_from_slice(b"</data></array>");
+    Ok(())
+}
+
+fn write_member(
+    out: &mut Vec<u8>,
+    name: &str,
+    value: Peek<'_, '_>,
+) -> Result<(), SerializeError<XmlSerializeError>> {
+    out.extend_from_slice(b"<member><name>");
+    write_text(out, name)?;
+    out.extend_from_slice(b"</name>");
+    write_value(out, value)?;
+    out.extend_from_slice(b"</member>");
+    Ok(())
+}
+
+/// A significant event of the response: the start of an element, by name,
+/// or the end of the current one.
+enum Item<'de> {
+    Start(Cow<'de, str>),
+    End,
+}
+
+/// Rewrites `<struct>` members into named elements and `<array>` items into
+/// repeated ones, so that the generic DOM deserializer can read them.
+struct Translator<'de> {
+    parser: XmlParser<'de>,
+    events: Vec<DomEvent<'de>>,
+}
+
+impl<'de> Translator<'de> {
+    fn next_event(&mut self) -> Result<Option<DomEvent<'de>>, XmlRpcError> {
+        self.parser.next_event().map_err(XmlRpcError::Xml)
+    }
+
+    /// The next element start or end, skipping whitespace, comments and
+    /// attributes.
+    fn next_item(&mut self) -> Result<Option<Item<'de>>, XmlRpcError> {
+        loop {
+            match self.next_event()? {
+                None => return Ok(None),
+                Some(DomEvent::NodeStart { tag, .. }) => return Ok(Some(Item::Start(tag))),
+                Some(DomEvent::NodeEnd) => return Ok(Some(Item::End)),
+                Some(DomEvent::Text(text)) if !text.trim().is_empty() => {
+                    return Err(invalid(format!("unexpected text {:?}", text.trim())));
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn expect_start(&mut self, expected: &str) -> Result<(), XmlRpcError> {
+        match self.next_item()? {
+            Some(Item::Start(tag)) if tag == expected => Ok(()),
+            Some(Item::Start(tag)) => Err(invalid(format!("expected <{expected}>, found <{tag}>"))),
+            Some(Item::End) => Err(invalid(format!("missing <{expected}>"))),
+            None => Err(XmlRpcError::Xml(XmlError::UnexpectedEof)),
+        }
+    }
+
+    fn expect_end(&mut self, tag: &str) -> Result<(), XmlRpcError> {
+        match self.next_item()? {
+            Some(Item::End) => Ok(()),
+            Some(Item::Start(other)) => Err(invalid(format!("unexpected <{other}> in <{tag}>"))),
+            None => Err(XmlRpcError::Xml(XmlError::UnexpectedEof)),
+        }
+    }
+
+    /// The text of the element just started, up to its end.
+    fn text(&mut self) -> Result<Cow<'de, str>, XmlRpcError> {
+        let mut text = Cow::Borrowed("");
+        loop {
+            match self.next_event()? {
+                None => return Err(XmlRpcError::Xml(XmlError::UnexpectedEof)),
+                Some(DomEvent::NodeEnd) => return Ok(text),
+                Some(DomEvent::Text(more)) if text.is_empty() => text = more,
+                Some(DomEvent::Text(more)) => text.to_mut().push_str(&more),
+                Some(DomEvent::NodeStart { tag, .. }) => {
+                    return Err(invalid(format!("unexpected <{tag}> in a scalar value")));
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Translate the `<value>` element just started, naming it `name`.
+    /// `in_array` is set for the items of an `<array>`, which can't be
+    /// arrays themselves.
+    fn value(&mut self, name: Cow<'de, str>, in_array: bool) -> Result<(), XmlRpcError> {
+        let mut text = Cow::Borrowed("");
+        loop {
+            match self.next_event()? {
+                None => return Err(XmlRpcError::Xml(XmlError::UnexpectedEof)),
+                // A <value> without a type element is a string
+                Some(DomEvent::NodeEnd) => {
+                    self.scalar(name, text);
+                    return Ok(());
+                }
+                Some(DomEvent::Text(more)) if text.is_empty() => text = more,
+                Some(DomEvent::Text(more)) => text.to_mut().push_str(&more),
+                Some(DomEvent::NodeStart { tag, .. }) => {
+                    if !text.trim().is_empty() {
+                        return Err(invalid(format!("unexpected text {:?}", text.trim())));
+                    }
+                    self.typed(name, &tag, in_array)?;
+                    return self.expect_end("value");
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Translate the type element `tag` of a value, just started.
+    fn typed(&mut self, name: Cow<'de, str>, tag: &str, in_array: bool) -> Result<(), XmlRpcError> {
+        match tag {
+            "struct" => {
+                self.start(name);
+                loop {
+                    match self.next_item()? {
+                        Some(Item::End) => break,
+                        Some(Item::Start(tag)) if tag == "member" => {
+                            self.expect_start("name")?;
+                            let key = self.text()?;
+                            self.expect_start("value")?;
+                            self.value(key, false)?;
+                            self.expect_end("member")?;
+                        }
+                        Some(Item::Start(tag)) => {
+                            return Err(invalid(format!(
+                                "expected <member> in <struct>, found <{tag}>"
+                            )));
+                        }
+                        None => return Err(XmlRpcError::Xml(XmlError::UnexpectedEof)),
+                    }
+                }
+                self.end();
+            }
+            "array" => {
+                if in_array {
+                    return Err(invalid(format!("{name:?} holds nested arrays")));
+                }
+                self.expect_start("data")?;
+                loop {
+                    match self.next_item()? {
+                        Some(Item::End) => break,
+                        Some(Item::Start(tag)) if tag == "value" => {
+                            self.value(name.clone(), true)?
+                        }
+                        Some(Item::Start(tag)) => {
+                            return Err(invalid(format!(
+                                "expected <value> in <data>, found <{tag}>"
+                            )));
+                        }
+                        None => return Err(XmlRpcError::Xml(XmlError::UnexpectedEof)),
+                    }
+                }
+                self.expect_end("array")?;
+            }
+            "nil" => {
+                self.text()?;
+            }
+            "boolean" => {
+                let text = self.text()?;
+                let value = match text.trim() {
+                    "1" => "true",
+                    "0" => "false",
+                    other => return Err(invalid(format!("invalid boolean {other:?}"))),
+                };
+                self.scalar(name, Cow::Borrowed(value));
+            }
+            "int" | "i4" | "i8" | "double" | "string" | "dateTime.iso8601" | "base64" => {
+                let text = self.text()?;
+                self.scalar(name, text);
+            }
+            _ => return Err(invalid(format!("unknown value type <{tag}>"))),
+        }
+        Ok(())
+    }
+
+    fn start(&mut self, name: Cow<'de, str>) {
+        self.events.push(DomEvent::NodeStart {
+            tag: name,
+            namespace: None,
+        });
+        self.events.push(DomEvent::ChildrenStart);
+    }
+
+    fn end(&mut self) {
+        self.events.push(DomEvent::ChildrenEnd);
+        self.events.push(DomEvent::NodeEnd);
+    }
+
+    fn scalar(&mut self, name: Cow<'de, str>, text: Cow<'de, str>) {
+        self.start(name);
+        if !text.is_empty() {
+            self.events.push(DomEvent::Text(text));
+        }
+        self.end();
+    }
+
+    /// Deserialize the translated events.
+    fn deserialize<T: Facet<'static>>(self) -> Result<T, XmlRpcError> {
+        DomDeserializer::new_owned(VecEventParser::new(self.events))
+            .deserialize()
+            .map_err(XmlRpcError::Value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    #[derive(Facet, Debug, Clone, PartialEq)]
+    struct Post {
+        title: String,
+        #[facet(rename = "post_id")]
+        id: u64,
+        draft: bool,
+        #[facet(default)]
+        tags: Vec<String>,
+        rating: Option<f64>,
+    }
+
+    fn post() -> Post {
+        Post {
+            title: "a < b".into(),
+            id: 7,
+            draft: false,
+            tags: vec!["x".into(), "y".into()],
+            rating: None,
+        }
+    }
+
+    #[test]
+    fn calls_wrap_each_argument() {
+        let call = method_call("blog.newPost", &(1i32, "me", post())).unwrap();
+        assert_eq!(
+            call,
+            concat!(
+                "<?xml version=\"1.0\"?><methodCall><methodName>blog.newPost</methodName><params>",
+                "<param><value><int>1</int></value></param>",
+                "<param><value><string>me</string></value></param>",
+                "<param><value><struct>",
+                "<member><name>title</name><value><string>a &lt; b</string></value></member>",
+                "<member><name>post_id</name><value><i8>7</i8></value></member>",
+                "<member><name>draft</name><value><boolean>0</boolean></value></member>",
+                "<member><name>tags</name><value><array><data>",
+                "<value><string>x</string></value><value><string>y</string></value>",
+                "</data></array></value></member>",
+                "</struct></value></param>",
+                "</params></methodCall>",
+            )
+        );
+
+        let no_args = method_call("system.listMethods", &()).unwrap();
+        assert!(
+            no_args.ends_with("<params></params></methodCall>"),
+            "{no_args}"
+        );
+
+        let map: BTreeMap<String, Option<i32>> = [("a".to_string(), None)].into();
+        let call = method_call("m", &map).unwrap();
+        assert!(
+            call.contains("<struct><member><name>a</name><value><nil/></value></member></struct>"),
+            "{call}"
+        );
+    }
+
+    #[test]
+    fn responses_unwrap_typed_values() {
+        let response = r#"<?xml version="1.0"?>
+<methodResponse>
+  <params>
+    <param>
+      <value>
+        <array>
+          <data>
+            <value><struct>
+              <member><name>title</name><value> untyped </value></member>
+              <member><name>post_id</name><value><i4>7</i4></value></member>
+              <member><name>draft</name><value><boolean>1</boolean></value></member>
+              <member><name>tags</name><value><array><data>
+                <value><string>x</string></value>
+              </data></array></value></member>
+              <member><name>rating</name><value><double>4.5</double></value></member>
+            </struct></value>
+            <value><struct>
+              <member><name>title</name><value><string>b</string></value></member>
+              <member><name>post_id</name><value><int>8</int></value></member>
+              <member><name>draft</name><value><boolean>0</boolean></value></member>
+              <member><name>rating</name><value><nil/></value></member>
+            </struct></value>
+          </data>
+        </array>
+      </value>
+    </param>
+  </params>
+</methodResponse>"#;
+        let posts: Vec<Post> = parse_response(response).unwrap();
+        assert_eq!(
+            posts,
+            vec![
+                Post {
+                    title: " untyped ".into(),
+                    id: 7,
+                    draft: true,
+                    tags: vec!["x".into()],
+                    rating: Some(4.5),
+                },
+                Post {
+                    title: "b".into(),
+                    id: 8,
+                    draft: false,
+                    tags: vec![],
+                    rating: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn faults_are_errors() {
+        let response = r#"<methodResponse><fault><value><struct>
+            <member><name>faultCode</name><value><int>4</int></value></member>
+            <member><name>faultString</name><value><string>Too many parameters.</string></value></member>
+        </struct></value></fault></methodResponse>"#;
+        match parse_response::<String>(response) {
+            Err(XmlRpcError::Fault(fault)) => assert_eq!(
+                fault,
+                Fault {
+                    code: 4,
+                    message: "Too many parameters.".into(),
+                }
+            ),
+            other => panic!("expected a fault, got {other:?}"),
+        }
+
+        assert!(matches!(
+            parse_response::<String>("<methodCall/>"),
+            Err(XmlRpcError::Protocol(_))
+        ));
+    }
+}
@@ -85,3 +85,140 @@ fn doctype_with_public_id() {
     assert_eq!(parsed.doctype, doc.doctype);
     assert_eq!(parsed.title, "My Page");
 }
+
+#[test]
+fn internal_entities_expand_only_when_enabled() {
+    #[derive(Facet, Debug, PartialEq)]
+    struct Letter {
+        #[facet(xml::attribute)]
+        from: String,
+        signature: String,
+    }
+
+    let input = r#"<!DOCTYPE letter [
+  <!-- <!ENTITY co "commented out"> -->
+  <!ENTITY co "ACME &amp; Sons">
+  <!ENTITY sig "&co; &#x2014; Sales">
+  <!ENTITY co "declared twice">
+  <!ENTITY % param "ignored">
+]>
+<letter from="&co;"><signature>&sig;</signature></letter>"#;
+
+    let options = xml::DeserializeOptions::new().expand_internal_entities(true);
+    let letter: Letter = xml::from_str_with_options(input, &options).unwrap();
+    assert_eq!(letter.from, "ACME & Sons");
+    assert_eq!(letter.signature, "ACME & Sons \u{2014} Sales");
+
+    // By default references are kept as written
+    let letter: Letter = xml::from_str(input).unwrap();
+    assert_eq!(letter.from, "&co;");
+    assert_eq!(letter.signature, "&sig;");
+}
+
+#[test]
+fn supplied_entities_fill_in_undeclared_ones() {
+    #[derive(Facet, Debug, PartialEq)]
+    struct Footer {
+        #[facet(xml::attribute)]
+        title: String,
+        text: String,
+    }
+
+    let input = r#"<!DOCTYPE footer [<!ENTITY co "ACME">]>
+<footer title="&co;&trade;"><text>&copy;&nbsp;&co;&amp;&unknown;</text></footer>"#;
+    let options = xml::DeserializeOptions::new()
+        .expand_internal_entities(true)
+        .with_entities([("copy", "\u{a9}"), ("nbsp", "\u{a0}")])
+        .with_entities([("trade", "\u{2122}"), ("co", "not used")]);
+    let footer: Footer = xml::from_str_with_options(input, &options).unwrap();
+    assert_eq!(
+        footer,
+        Footer {
+            // The document's own declaration wins
+            title: "ACME\u{2122}".into(),
+            text: "\u{a9}\u{a0}ACME&&unknown;".into(),
+        }
+    );
+
+    // Without them, an undeclared entity in an attribute is an error
+    let input = r#"<footer title="&trade;"><text/></footer>"#;
+    assert!(xml::from_str::<Footer>(input).is_err());
+    let footer: Footer = xml::from_str_with_options(input, &options).unwrap();
+    assert_eq!(footer.title, "\u{2122}");
+}
+
+#[test]
+fn entity_expansion_is_bounded() {
+    #[derive(Facet, Debug)]
+    struct Lolz {
+        text: String,
+    }
+
+    let mut input = String::from("<!DOCTYPE lolz [\n<!ENTITY lol0 \"lol\">\n");
+    for i in 1..10 {
+        let prev = format!("&lol{};", i - 1);
+        input.push_str(&format!("<!ENTITY lol{i} \"{}\">\n", prev.repeat(10)));
+    }
+    input.push_str("]>\n<lolz><text>&lol9;</text></lolz>");
+
+    let options = xml::DeserializeOptions::new()
+        .expand_internal_entities(true)
+        .max_entity_depth(16);
+    let err = xml::from_str_with_options::<Lolz>(&input, &options).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            xml::DeserializeError::Parser(xml::XmlError::EntityLimit { .. })
+        ),
+        "{err}"
+    );
+
+    // Nesting alone is limited too, which also catches self-reference
+    let input = r#"<!DOCTYPE a [<!ENTITY a "&a;">]><lolz><text>&a;</text></lolz>"#;
+    let err = xml::from_str_with_options::<Lolz>(input, &options).unwrap_err();
+    assert!(err.to_string().contains("entity limits"), "{err}");
+
+    // Small expansions stay well within the defaults
+    let input = "<!DOCTYPE lolz [<!ENTITY lol0 \"lol\"><!ENTITY lol1 \"&lol0;&lol0;\">]>\
+        <lolz><text>&lol1;</text></lolz>";
+    let lolz: Lolz = xml::from_str_with_options(input, &options).unwrap();
+    assert_eq!(lolz.text, "lollol");
+}
+
+#[test]
+fn external_entities_go_through_the_resolver() {
+    #[derive(Facet, Debug)]
+    struct Doc {
+        legal: String,
+    }
+
+    let input = r#"<!DOCTYPE doc [
+  <!ENTITY legal SYSTEM "legal.txt">
+  <!ENTITY secret SYSTEM "file:///etc/passwd">
+]>
+<doc><legal>&legal;</legal></doc>"#;
+
+    // Nothing is fetched without a resolver
+    let doc: Doc = xml::from_str(input).unwrap();
+    assert_eq!(doc.legal, "&legal;");
+
+    let options = xml::DeserializeOptions::new().resolve_external_entity(|entity| {
+        (entity.system_id == "legal.txt" && entity.public_id.is_none())
+            .then(|| "All rights reserved".to_string())
+    });
+    let doc: Doc = xml::from_str_with_options(input, &options).unwrap();
+    assert_eq!(doc.legal, "All rights reserved");
+
+    let err = xml::from_str_with_options::<Doc>(
+        &input.replace("<legal>&legal;", "<legal>&secret;"),
+        &options,
+    )
+    .unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            xml::DeserializeError::Parser(xml::XmlError::UnresolvedEntity(name)) if name == "secret"
+        ),
+        "{err}"
+    );
+}
@@ -0,0 +1,95 @@
+//! Tests for EXI input against streams produced outside this crate.
+//!
+//! The fixtures in `tests/fixtures/exi` are schema-less, bit-packed EXI 1.0
+//! streams with default options, encoded from the documents quoted below by
+//! an encoder written separately from the spec's built-in grammars. They
+//! exercise grammar learning, the URI, local-name and value string tables,
+//! the `$EXI` cookie and the predefined `xml:` namespace.
+
+#![cfg(feature = "exi")]
+
+use facet::Facet;
+use facet_testhelpers::test;
+use facet_xml::{self as xml, ExiError};
+
+/// The example document of the EXI Primer.
+const NOTEBOOK_XML: &str = r#"<notebook date="2007-09-12"><note category="EXI" date="2007-07-23"><subject>EXI</subject><body>Do not forget it!</body></note><note date="2007-09-12"><subject>shopping list</subject><body>milk, honey</body></note></notebook>"#;
+const NOTEBOOK_EXI: &[u8] = include_bytes!("fixtures/exi/notebook.exi");
+
+const ATOM_XML: &str = r#"<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="en"><title>Example</title><entry><title>First</title></entry><entry><title>Second</title></entry></feed>"#;
+const ATOM_EXI: &[u8] = include_bytes!("fixtures/exi/atom-feed.exi");
+
+#[derive(Facet, Debug, PartialEq)]
+#[facet(rename = "notebook")]
+struct Notebook {
+    #[facet(xml::attribute)]
+    date: String,
+    #[facet(rename = "note")]
+    notes: Vec<Note>,
+}
+
+#[derive(Facet, Debug, PartialEq)]
+struct Note {
+    #[facet(xml::attribute)]
+    category: Option<String>,
+    #[facet(xml::attribute)]
+    date: String,
+    subject: String,
+    body: String,
+}
+
+#[derive(Facet, Debug, PartialEq)]
+#[facet(rename = "feed", xml::ns_all = "http://www.w3.org/2005/Atom")]
+struct Feed {
+    #[facet(xml::attribute, xml::ns = "http://www.w3.org/XML/1998/namespace")]
+    lang: String,
+    title: String,
+    #[facet(rename = "entry")]
+    entries: Vec<Entry>,
+}
+
+#[derive(Facet, Debug, PartialEq)]
+#[facet(xml::ns_all = "http://www.w3.org/2005/Atom")]
+struct Entry {
+    title: String,
+}
+
+#[test]
+fn notebook_decodes_like_its_text() {
+    let notebook: Notebook = xml::from_exi(NOTEBOOK_EXI).unwrap();
+    assert_eq!(notebook, xml::from_str::<Notebook>(NOTEBOOK_XML).unwrap());
+
+    // The second note's date and the first subject are string table hits
+    assert_eq!(notebook.notes[1].date, "2007-09-12");
+    assert_eq!(notebook.notes[0].subject, "EXI");
+    assert_eq!(notebook.notes[1].body, "milk, honey");
+}
+
+#[test]
+fn namespaced_feed_decodes_like_its_text() {
+    assert!(ATOM_EXI.starts_with(b"$EXI"));
+    let feed: Feed = xml::from_exi(ATOM_EXI).unwrap();
+    assert_eq!(feed, xml::from_str::<Feed>(ATOM_XML).unwrap());
+    assert_eq!(feed.lang, "en");
+    assert_eq!(
+        feed.entries,
+        [
+            Entry {
+                title: "First".into()
+            },
+            Entry {
+                title: "Second".into()
+            },
+        ]
+    );
+}
+
+#[test]
+fn truncated_stream_is_an_error() {
+    let truncated = &NOTEBOOK_EXI[..NOTEBOOK_EXI.len() / 2];
+    let err = xml::from_exi::<Notebook>(truncated).unwrap_err();
+    assert!(
+        matches!(err, xml::DeserializeError::Parser(ExiError::UnexpectedEof)),
+        "{err}"
+    );
+}
@@ -1631,3 +1631,168 @@ fn test_serialize_options_preserve_entities() {
         "With preserve_entities, &amp; should be preserved: {xml_preserved}"
     );
 }
+
+#[test]
+fn test_none_namespaced_attribute_leaves_no_declaration() {
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "item")]
+    struct Item {
+        #[facet(xml::attribute, xml::ns = "http://example.com/a")]
+        skipped: Option<String>,
+        #[facet(xml::attribute, xml::ns = "http://example.com/b")]
+        kept: Option<String>,
+    }
+
+    let data = Item {
+        skipped: None,
+        kept: Some("a\"b".into()),
+    };
+    let xml_output = to_string(&data).unwrap();
+
+    // The skipped attribute must not leave a dangling xmlns declaration,
+    // and must not consume a generated prefix.
+    assert!(
+        !xml_output.contains("http://example.com/a"),
+        "skipped attribute should not be declared: {xml_output}"
+    );
+    assert!(
+        xml_output.contains(r#"xmlns:ns0="http://example.com/b" ns0:kept="a&quot;b""#),
+        "kept attribute should use the first prefix: {xml_output}"
+    );
+
+    let parsed: Item = from_str(&xml_output).unwrap();
+    assert_eq!(parsed, data);
+}
+
+#[test]
+fn test_no_ns_field_stays_unqualified_under_ns_all() {
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(xml::ns_all = "urn:orders")]
+    struct Order {
+        id: String,
+        #[facet(xml::no_ns)]
+        note: String,
+    }
+
+    let order = Order {
+        id: "7".into(),
+        note: "leave at door".into(),
+    };
+    let xml_output = to_string(&order).unwrap();
+    assert_eq!(
+        xml_output,
+        r#"<order xmlns="urn:orders"><id>7</id><note xmlns="">leave at door</note></order>"#
+    );
+    assert_eq!(from_str::<Order>(&xml_output).unwrap(), order);
+
+    // Mixed qualified and unqualified children under one parent
+    let parsed: Order = from_str(
+        r#"<o:order xmlns:o="urn:orders"><o:id>7</o:id><note>leave at door</note></o:order>"#,
+    )
+    .unwrap();
+    assert_eq!(parsed, order);
+}
+
+#[test]
+fn test_declarations_in_scope_are_reused() {
+    #[derive(Facet, Debug, PartialEq)]
+    struct Inner {
+        #[facet(xml::ns = "urn:x")]
+        leaf: String,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "outer")]
+    struct Outer {
+        #[facet(xml::attribute, xml::ns = "urn:x")]
+        id: String,
+        #[facet(xml::ns = "urn:x")]
+        inner: Inner,
+    }
+
+    let outer = Outer {
+        id: "1".into(),
+        inner: Inner { leaf: "v".into() },
+    };
+    let xml_output = to_string(&outer).unwrap();
+    assert_eq!(
+        xml_output,
+        r#"<outer xmlns:ns0="urn:x" ns0:id="1"><ns0:inner><ns0:leaf>v</ns0:leaf></ns0:inner></outer>"#
+    );
+    assert_eq!(from_str::<Outer>(&xml_output).unwrap(), outer);
+}
+
+#[test]
+fn test_hoisted_namespaces_are_declared_once_on_the_root() {
+    #[derive(Facet, Debug, PartialEq)]
+    struct Entry {
+        #[facet(xml::ns = "urn:dc")]
+        title: String,
+        #[facet(xml::attribute, xml::ns = "urn:meta")]
+        lang: String,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "feed")]
+    struct Feed {
+        #[facet(rename = "entry")]
+        entries: Vec<Entry>,
+    }
+
+    let feed = Feed {
+        entries: vec![
+            Entry {
+                title: "a".into(),
+                lang: "en".into(),
+            },
+            Entry {
+                title: "b".into(),
+                lang: "fr".into(),
+            },
+        ],
+    };
+
+    // Siblings each declare what they use
+    let xml_output = to_string(&feed).unwrap();
+    assert_eq!(xml_output.matches("xmlns:").count(), 4, "{xml_output}");
+
+    let options = xml::SerializeOptions::new().hoist_namespaces(true);
+    let xml_output = xml::to_string_with_options(&feed, &options).unwrap();
+    assert_eq!(
+        xml_output,
+        r#"<feed xmlns:ns0="urn:meta" xmlns:ns1="urn:dc"><entry ns0:lang="en"><ns1:title>a</ns1:title></entry><entry ns0:lang="fr"><ns1:title>b</ns1:title></entry></feed>"#
+    );
+    assert_eq!(from_str::<Feed>(&xml_output).unwrap(), feed);
+}
+
+#[test]
+fn test_field_prefix_is_written_and_any_prefix_is_read() {
+    #[derive(Facet, Debug, PartialEq)]
+    struct Body {
+        #[facet(xml::ns = "urn:acme", xml::prefix = "acme")]
+        ping: String,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "envelope")]
+    struct Envelope {
+        #[facet(xml::attribute, xml::ns = "urn:acme", xml::prefix = "acme")]
+        version: String,
+        #[facet(xml::ns = "urn:soap", xml::prefix = "soap")]
+        body: Body,
+    }
+
+    let envelope = Envelope {
+        version: "2".into(),
+        body: Body { ping: "hi".into() },
+    };
+    let xml_output = to_string(&envelope).unwrap();
+    assert_eq!(
+        xml_output,
+        r#"<envelope xmlns:acme="urn:acme" acme:version="2"><soap:body xmlns:soap="urn:soap"><acme:ping>hi</acme:ping></soap:body></envelope>"#
+    );
+    assert_eq!(from_str::<Envelope>(&xml_output).unwrap(), envelope);
+
+    let renamed = r#"<envelope xmlns:a="urn:acme" a:version="2"><s:body xmlns:s="urn:soap"><a:ping>hi</a:ping></s:body></envelope>"#;
+    assert_eq!(from_str::<Envelope>(renamed).unwrap(), envelope);
+}
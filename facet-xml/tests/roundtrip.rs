@@ -296,6 +296,62 @@ fn attr_default_function() {
     assert_eq!(parsed.magic_number, 42);
 }
 
+#[test]
+fn missing_fields_take_their_defaults() {
+    use facet_xml as xml;
+
+    fn first_page() -> u32 {
+        1
+    }
+    fn default_limit() -> Option<u32> {
+        Some(10)
+    }
+    fn default_tags() -> Vec<String> {
+        vec!["misc".into()]
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "query")]
+    struct Query {
+        #[facet(xml::attribute, default = first_page())]
+        page: u32,
+        #[facet(default = default_limit())]
+        limit: Option<u32>,
+        offset: Option<u32>,
+        #[facet(rename = "tag", default = default_tags())]
+        tags: Vec<String>,
+        #[facet(rename = "sort")]
+        sorts: Vec<String>,
+    }
+
+    let parsed: Query = xml::from_str("<query/>").unwrap();
+    assert_eq!(
+        parsed,
+        Query {
+            page: 1,
+            limit: Some(10),
+            offset: None,
+            tags: vec!["misc".into()],
+            sorts: vec![],
+        }
+    );
+
+    let parsed: Query = xml::from_str(
+        r#"<query page="3"><limit>5</limit><offset>2</offset><tag>a</tag><sort>b</sort></query>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        parsed,
+        Query {
+            page: 3,
+            limit: Some(5),
+            offset: Some(2),
+            tags: vec!["a".into()],
+            sorts: vec!["b".into()],
+        }
+    );
+}
+
 #[test]
 fn option_none() {
     #[derive(Facet, Debug, PartialEq)]
@@ -875,6 +931,47 @@ fn string_escapes() {
     assert!(parsed.text.contains('\\'));
 }
 
+#[test]
+fn character_references_round_trip_as_ascii() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "record")]
+    struct Record {
+        #[facet(xml::attribute)]
+        label: String,
+        text: String,
+    }
+
+    let xml = r#"<record label="&#xE9;t&#233; &lt;&apos;&quot;&gt;"><text>&#x1F600; &amp; &#X263A;</text></record>"#;
+    let parsed: Record = facet_xml::from_str(xml).unwrap();
+    assert_eq!(
+        parsed,
+        Record {
+            label: "été <'\">".into(),
+            text: "😀 & ☺".into(),
+        }
+    );
+
+    let options = xml::SerializeOptions::new().escape_non_ascii(true);
+    let out = xml::to_string_with_options(&parsed, &options).unwrap();
+    assert!(out.is_ascii(), "{out}");
+    assert_eq!(
+        out,
+        r#"<record label="&#xE9;t&#xE9; &lt;'&quot;&gt;"><text>&#x1F600; &amp; &#x263A;</text></record>"#
+    );
+    assert_eq!(facet_xml::from_str::<Record>(&out).unwrap(), parsed);
+
+    // References to characters XML can't hold are rejected
+    assert!(
+        facet_xml::from_str::<Record>(r#"<record label=""><text>&#0;</text></record>"#).is_err()
+    );
+    assert!(
+        facet_xml::from_str::<Record>(r#"<record label=""><text>&#xD800;</text></record>"#)
+            .is_err()
+    );
+}
+
 // ══════════════════════════════════════════════════════════════════════════════
 // Unit struct tests
 // ══════════════════════════════════════════════════════════════════════════════
@@ -1271,3 +1368,3187 @@ fn flatten_hashmap_captures_both_attributes_and_elements() {
     // Known attribute NOT in extras
     assert_eq!(parsed.extras.get("name"), None);
 }
+
+// ══════════════════════════════════════════════════════════════════════════════
+// Output sink tests
+// ══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn to_fmt_writer_appends_to_string() {
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "record")]
+    struct Record {
+        name: String,
+    }
+
+    let value = Record {
+        name: "a < b".into(),
+    };
+    let mut out = String::from("prefix:");
+    facet_xml::to_fmt_writer(&mut out, &value).unwrap();
+    assert_eq!(out, "prefix:<record><name>a &lt; b</name></record>");
+
+    let parsed: Record = facet_xml::from_str(&out["prefix:".len()..]).unwrap();
+    assert_eq!(parsed, value);
+}
+
+#[test]
+fn to_fmt_writer_matches_to_string_with_options() {
+    #[derive(Facet, Debug)]
+    #[facet(rename = "record")]
+    struct Record {
+        name: String,
+        count: u32,
+    }
+
+    let value = Record {
+        name: "facet".into(),
+        count: 3,
+    };
+    let options = facet_xml::SerializeOptions::new().pretty();
+    let mut out = String::new();
+    facet_xml::to_fmt_writer_with_options(&mut out, &value, &options).unwrap();
+    assert_eq!(
+        out,
+        facet_xml::to_string_with_options(&value, &options).unwrap()
+    );
+}
+
+#[test]
+fn to_fmt_writer_streams_large_documents() {
+    #[derive(Facet, Debug)]
+    #[facet(rename = "log")]
+    struct Log {
+        #[facet(rename = "line")]
+        lines: Vec<String>,
+    }
+
+    /// Records how much was written by each call.
+    #[derive(Default)]
+    struct Chunks {
+        text: String,
+        sizes: Vec<usize>,
+    }
+
+    impl core::fmt::Write for Chunks {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            self.text.push_str(s);
+            self.sizes.push(s.len());
+            Ok(())
+        }
+    }
+
+    let value = Log {
+        lines: (0..10_000).map(|i| format!("entry {i} – ok")).collect(),
+    };
+    let mut out = Chunks::default();
+    facet_xml::to_fmt_writer(&mut out, &value).unwrap();
+    assert_eq!(out.text, facet_xml::to_string(&value).unwrap());
+    assert!(out.sizes.len() > 1, "{:?}", out.sizes);
+    assert!(out.sizes.iter().all(|&size| size < out.text.len() / 2));
+}
+
+#[test]
+fn reusable_serializer_produces_independent_documents() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug)]
+    #[facet(rename = "entry")]
+    struct Entry {
+        #[facet(xml::attribute, xml::ns = "urn:test")]
+        id: u32,
+        body: String,
+    }
+
+    let mut serializer = facet_xml::XmlSerializer::new();
+    for id in 0..3 {
+        let value = Entry {
+            id,
+            body: format!("body {id}"),
+        };
+        let mut out = Vec::new();
+        serializer.serialize(&value, &mut out).unwrap();
+        // Namespace declarations and other state must not leak between documents
+        assert_eq!(out, facet_xml::to_vec(&value).unwrap());
+    }
+}
+
+#[test]
+fn document_writer_streams_children() {
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "item")]
+    struct Item {
+        id: u32,
+        label: String,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "export")]
+    struct Export {
+        #[facet(rename = "item")]
+        items: Vec<Item>,
+    }
+
+    let mut writer = facet_xml::DocumentWriter::with_options(
+        Vec::new(),
+        facet_xml::SerializeOptions::new().pretty(),
+    );
+    writer.declaration().unwrap();
+    writer.start_root("export").unwrap();
+    for id in 0..3 {
+        writer
+            .write_child(&Item {
+                id,
+                label: format!("#{id}"),
+            })
+            .unwrap();
+    }
+    let out = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert!(out.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<export>\n  <item>"));
+    assert!(out.ends_with("</item>\n</export>\n"));
+
+    let parsed: Export = facet_xml::from_str(&out).unwrap();
+    assert_eq!(parsed.items.len(), 3);
+    assert_eq!(parsed.items[2].label, "#2");
+}
+
+#[test]
+fn document_writer_uses_the_configured_newline() {
+    #[derive(Facet)]
+    #[facet(rename = "item")]
+    struct Item {
+        id: u32,
+        label: String,
+    }
+
+    let mut writer = facet_xml::DocumentWriter::with_options(
+        Vec::new(),
+        facet_xml::SerializeOptions::new().pretty().newline("\r\n"),
+    );
+    writer.declaration().unwrap();
+    writer.start_root("export").unwrap();
+    for id in 0..2 {
+        writer
+            .write_child(&Item {
+                id,
+                label: format!("#{id}"),
+            })
+            .unwrap();
+    }
+    let out = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert!(
+        out.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\r\n<export>\r\n  <item>\r\n"),
+        "{out:?}"
+    );
+    assert!(out.ends_with("</item>\r\n</export>\r\n"), "{out:?}");
+    // Every line ending is the configured one, inside children too
+    assert_eq!(
+        out.matches('\n').count(),
+        out.matches("\r\n").count(),
+        "{out:?}"
+    );
+}
+
+#[test]
+fn document_writer_root_takes_namespaces_and_attributes() {
+    use facet_xml::{self as xml, DocumentWriter, RootElement};
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(xml::ns_all = "urn:export")]
+    struct Item {
+        id: u32,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "export", xml::ns_all = "urn:export")]
+    struct Export {
+        #[facet(xml::attribute)]
+        version: String,
+        #[facet(rename = "item")]
+        items: Vec<Item>,
+    }
+
+    let mut writer = DocumentWriter::new(Vec::new());
+    writer
+        .start_root_with(
+            RootElement::new("export")
+                .default_namespace("urn:export")
+                .attribute("version", "1 & \"2\""),
+        )
+        .unwrap();
+    writer.write_child(&Item { id: 7 }).unwrap();
+    let out = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert!(
+        out.starts_with(r#"<export xmlns="urn:export" version="1 &amp; &quot;2&quot;">"#),
+        "{out}"
+    );
+    let parsed: Export = facet_xml::from_str(&out).unwrap();
+    assert_eq!(parsed.version, r#"1 & "2""#);
+    assert_eq!(parsed.items, [Item { id: 7 }]);
+
+    for root in [
+        RootElement::new("1st"),
+        RootElement::new("a b"),
+        RootElement::new("root").attribute("bad name", "x"),
+        RootElement::new("root").attribute("xmlns:p", "urn:p"),
+        RootElement::new("root")
+            .attribute("a", "1")
+            .attribute("a", "2"),
+        RootElement::new("root").attribute("a", "\u{1}"),
+        RootElement::new("root").namespace("xmlns", "urn:p"),
+        RootElement::new("root")
+            .namespace("p", "urn:a")
+            .namespace("p", "urn:b"),
+    ] {
+        let mut writer = DocumentWriter::new(Vec::new());
+        assert!(writer.start_root_with(root.clone()).is_err(), "{root:?}");
+        // Nothing was written, so the root can still be started
+        writer.start_root("root").unwrap();
+        assert_eq!(writer.finish().unwrap(), b"<root></root>");
+    }
+}
+
+#[test]
+fn document_writer_rejects_child_before_root() {
+    #[derive(Facet)]
+    struct Item {
+        id: u32,
+    }
+
+    let mut writer = facet_xml::DocumentWriter::new(Vec::new());
+    assert!(writer.write_child(&Item { id: 1 }).is_err());
+}
+
+// ══════════════════════════════════════════════════════════════════════════════
+// Streaming reader tests
+// ══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn record_reader_exposes_root_and_yields_records() {
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "entry")]
+    struct Entry {
+        id: u32,
+        title: String,
+    }
+
+    let xml = r#"<?xml version="1.0"?>
+<!-- export -->
+<feed version="3" source="test">
+    <entry><id>1</id><title>one</title></entry>
+    <!-- separator -->
+    <entry><id>2</id><title>two</title></entry>
+</feed>"#;
+
+    let mut reader = facet_xml::RecordReader::new(xml.as_bytes()).unwrap();
+    assert_eq!(reader.root_tag(), "feed");
+    assert_eq!(reader.attribute("version"), Some("3"));
+    assert_eq!(
+        reader.attributes().collect::<Vec<_>>(),
+        vec![("version", "3"), ("source", "test")]
+    );
+
+    let first: Entry = reader.next_record().unwrap().unwrap();
+    assert_eq!(first.title, "one");
+    let second: Entry = reader.next_record().unwrap().unwrap();
+    assert_eq!(second.id, 2);
+    assert_eq!(reader.next_record::<Entry>().unwrap(), None);
+    assert_eq!(reader.next_record::<Entry>().unwrap(), None);
+}
+
+#[test]
+fn record_reader_empty_root() {
+    #[derive(Facet, Debug, PartialEq)]
+    struct Entry {
+        id: u32,
+    }
+
+    let mut reader = facet_xml::RecordReader::new(b"<feed/>").unwrap();
+    assert_eq!(reader.records::<Entry>().count(), 0);
+}
+
+#[test]
+fn record_reader_reports_bad_record() {
+    #[derive(Facet, Debug, PartialEq)]
+    struct Entry {
+        id: u32,
+    }
+
+    let xml = r#"<feed><entry><id>1</id></entry><entry><id>nope</id></entry></feed>"#;
+    let mut reader = facet_xml::RecordReader::new(xml.as_bytes()).unwrap();
+    let results: Vec<_> = reader.records::<Entry>().collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+}
+
+#[test]
+fn iter_children_yields_matching_children_only() {
+    #[derive(Facet, Debug, PartialEq)]
+    struct Record {
+        id: u32,
+    }
+
+    let xml = r#"<records count="3">
+    <record><id>1</id></record>
+    <summary><total>3</total></summary>
+    <record><id>2</id></record>
+    <!-- trailing -->
+    <record><id>3</id></record>
+</records>"#;
+    let ids: Vec<u32> = facet_xml::iter_children::<Record>(xml, "record")
+        .map(|r| r.unwrap().id)
+        .collect();
+    assert_eq!(ids, [1, 2, 3]);
+
+    let mut bad = facet_xml::iter_children::<Record>(
+        "<records><record><id>x</id></record><record><id>2</id></record></records>",
+        "record",
+    );
+    assert!(bad.next().unwrap().is_err());
+    assert!(bad.next().is_none());
+
+    let mut unopened = facet_xml::iter_children::<Record>("not xml", "record");
+    assert!(unopened.next().unwrap().is_err());
+    assert!(unopened.next().is_none());
+}
+
+#[test]
+fn initial_capacity_does_not_change_output() {
+    #[derive(Facet, Debug)]
+    #[facet(rename = "record")]
+    struct Record {
+        names: Vec<String>,
+    }
+
+    let value = Record {
+        names: (0..50).map(|i| format!("name {i}")).collect(),
+    };
+    let expected = facet_xml::to_string(&value).unwrap();
+    // Second call reuses the size hint recorded by the first
+    assert_eq!(facet_xml::to_string(&value).unwrap(), expected);
+
+    let options = facet_xml::SerializeOptions::new().initial_capacity(16);
+    assert_eq!(
+        facet_xml::to_string_with_options(&value, &options).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn attribute_order_option_sorts_attributes() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug)]
+    #[facet(rename = "node")]
+    struct Node {
+        #[facet(xml::attribute)]
+        zeta: u32,
+        #[facet(xml::attribute, xml::ns = "http://www.w3.org/1999/xlink")]
+        href: String,
+        #[facet(xml::attribute)]
+        alpha: Option<u32>,
+        #[facet(flatten, default)]
+        extra: HashMap<String, String>,
+        child: String,
+    }
+
+    let mut extra = HashMap::new();
+    extra.insert("m".to_string(), "3".to_string());
+    extra.insert("b".to_string(), "4".to_string());
+    let value = Node {
+        zeta: 1,
+        href: "#x".into(),
+        alpha: None,
+        extra,
+        child: "c".into(),
+    };
+
+    let options = facet_xml::SerializeOptions::new().sorted_attributes();
+    let out = facet_xml::to_string_with_options(&value, &options).unwrap();
+    assert_eq!(
+        out,
+        r##"<node b="4" m="3" xmlns:xlink="http://www.w3.org/1999/xlink" xlink:href="#x" zeta="1"><child>c</child></node>"##
+    );
+}
+
+#[test]
+fn attr_order_ranks_attributes_ahead_of_declaration_order() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "rect")]
+    struct Rect {
+        #[facet(xml::attribute)]
+        width: u32,
+        #[facet(xml::attribute, xml::attr_order = 1)]
+        y: u32,
+        #[facet(xml::attribute)]
+        height: u32,
+        #[facet(xml::attribute, xml::attr_order = 0)]
+        x: u32,
+    }
+
+    let rect = Rect {
+        width: 10,
+        y: 2,
+        height: 5,
+        x: 1,
+    };
+    let out = facet_xml::to_string(&rect).unwrap();
+    assert_eq!(out, r#"<rect x="1" y="2" width="10" height="5"></rect>"#);
+    assert_eq!(facet_xml::from_str::<Rect>(&out).unwrap(), rect);
+
+    // An explicit comparator wins
+    let options = facet_xml::SerializeOptions::new().sorted_attributes();
+    assert_eq!(
+        facet_xml::to_string_with_options(&rect, &options).unwrap(),
+        r#"<rect height="5" width="10" x="1" y="2"></rect>"#
+    );
+}
+
+// ══════════════════════════════════════════════════════════════════════════════
+// Custom element (hyphenated tag) tests
+// ══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn hyphenated_tag_claimed_by_rename_roundtrips() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "my-widget")]
+    struct MyWidget {
+        #[facet(xml::attribute, rename = "data-size")]
+        size: u32,
+        #[facet(rename = "widget-label")]
+        label: String,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "page")]
+    struct Page {
+        #[facet(xml::elements)]
+        widgets: Vec<MyWidget>,
+    }
+
+    let xml = r#"<page><my-widget data-size="2"><widget-label>a</widget-label></my-widget><my-widget data-size="3"><widget-label>b</widget-label></my-widget></page>"#;
+    let parsed: Page = facet_xml::from_str(xml).unwrap();
+    assert_eq!(parsed.widgets.len(), 2);
+    assert_eq!(parsed.widgets[1].size, 3);
+    assert_eq!(parsed.widgets[1].label, "b");
+
+    let out = facet_xml::to_string(&parsed).unwrap();
+    assert_eq!(out, xml);
+}
+
+#[test]
+fn hyphenated_tag_self_closing_is_regular_element() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "x-slot")]
+    struct Slot {
+        #[facet(xml::attribute)]
+        name: String,
+        #[facet(xml::text, default)]
+        fallback: String,
+    }
+
+    let parsed: Slot = facet_xml::from_str(r#"<x-slot name="header"/>"#).unwrap();
+    assert_eq!(
+        parsed,
+        Slot {
+            name: "header".into(),
+            fallback: String::new(),
+        }
+    );
+}
+
+// ══════════════════════════════════════════════════════════════════════════════
+// Raw template tests
+// ══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn raw_template_interpolates_sibling_fields() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "link")]
+    struct Link {
+        #[facet(xml::attribute)]
+        id: u32,
+        #[facet(xml::attribute, rename = "user-name")]
+        user: String,
+        #[facet(xml::attribute, xml::raw_template)]
+        href: String,
+        #[facet(xml::raw_template)]
+        label: String,
+    }
+
+    let link = Link {
+        id: 7,
+        user: "a&b".into(),
+        href: "/users/{id}?u={user-name}".into(),
+        label: "{user} {{literal}} {missing}".into(),
+    };
+
+    let out = facet_xml::to_string(&link).unwrap();
+    assert_eq!(
+        out,
+        r#"<link id="7" user-name="a&amp;b" href="/users/7?u=a&amp;b"><label>a&amp;b {literal} {missing}</label></link>"#
+    );
+
+    // The rendered values read back as plain strings
+    let parsed: Link = facet_xml::from_str(&out).unwrap();
+    assert_eq!(parsed.href, "/users/7?u=a&b");
+    assert_eq!(parsed.label, "a&b {literal} {missing}");
+}
+
+// ══════════════════════════════════════════════════════════════════════════════
+// Snapshot tests
+// ══════════════════════════════════════════════════════════════════════════════
+
+#[cfg(feature = "testing")]
+#[test]
+fn snapshot_with_redacted_timestamp() {
+    use facet_xml as xml;
+    use facet_xml::testing::SnapshotSettings;
+
+    #[derive(Facet)]
+    struct Event {
+        #[facet(xml::attribute)]
+        id: u32,
+        created: String,
+    }
+
+    let event = Event {
+        id: 1,
+        created: "2024-05-01T12:00:00Z".into(),
+    };
+    facet_xml::assert_xml_snapshot!(
+        event,
+        SnapshotSettings::new("redacted_event").redact_text("created", "[timestamp]")
+    );
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn missing_snapshot_fails() {
+    use facet_xml::testing::SnapshotSettings;
+
+    #[derive(Facet)]
+    struct Event {
+        id: u32,
+    }
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        return;
+    }
+    let dir = std::env::temp_dir().join("facet-xml-missing-snapshot");
+    let result = std::panic::catch_unwind(|| {
+        facet_xml::assert_xml_snapshot!(
+            Event { id: 1 },
+            SnapshotSettings::new("missing").dir(&dir)
+        );
+    });
+    let message = result.expect_err("a missing snapshot must fail");
+    let message = message
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .unwrap_or_default();
+    assert!(message.contains("is missing"), "{message}");
+    assert!(!dir.join("missing.xml").exists());
+}
+
+// ══════════════════════════════════════════════════════════════════════════════
+// Serializer error tests
+// ══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn failing_float_formatter_is_an_error() {
+    use facet_xml as xml;
+
+    #[derive(Facet)]
+    struct Reading {
+        #[facet(xml::attribute)]
+        value: f64,
+    }
+
+    fn broken(_: f64, _: &mut dyn std::io::Write) -> std::io::Result<()> {
+        Err(std::io::Error::other("formatter refused"))
+    }
+
+    fn not_utf8(_: f64, out: &mut dyn std::io::Write) -> std::io::Result<()> {
+        out.write_all(&[0xff])
+    }
+
+    let reading = Reading { value: 1.5 };
+
+    let options = facet_xml::SerializeOptions::new().float_formatter(broken);
+    let err = facet_xml::to_string_with_options(&reading, &options).unwrap_err();
+    assert!(
+        matches!(err, facet_xml::SerializeError::Backend(e) if e.to_string().contains("formatter refused"))
+    );
+
+    let options = facet_xml::SerializeOptions::new().float_formatter(not_utf8);
+    assert!(facet_xml::to_string_with_options(&reading, &options).is_err());
+}
+
+// ══════════════════════════════════════════════════════════════════════════════
+// Configuration tests
+// ══════════════════════════════════════════════════════════════════════════════
+
+#[test]
+fn lenient_option_discards_stray_text() {
+    #[derive(Facet, Debug, PartialEq)]
+    struct Settings {
+        level: u32,
+    }
+
+    let xml = "<settings>stray<level>3</level></settings>";
+    assert!(facet_xml::from_str::<Settings>(xml).is_err());
+
+    let options = facet_xml::DeserializeOptions::new().lenient(true);
+    let parsed: Settings = facet_xml::from_str_with_options(xml, &options).unwrap();
+    assert_eq!(parsed, Settings { level: 3 });
+}
+
+#[test]
+fn shared_config_applies_both_directions() {
+    use std::sync::Arc;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Settings {
+        level: u32,
+    }
+
+    let config = Arc::new(
+        facet_xml::Config::new()
+            .serialize(facet_xml::SerializeOptions::new().indent("\t"))
+            .deserialize(facet_xml::DeserializeOptions::new().lenient(true)),
+    );
+
+    let worker = {
+        let config = Arc::clone(&config);
+        std::thread::spawn(move || config.to_string(&Settings { level: 2 }).unwrap())
+    };
+    let xml = worker.join().unwrap();
+    assert!(xml.contains("\n\t<level>"));
+    assert_eq!(
+        config.from_str::<Settings>(&xml).unwrap(),
+        Settings { level: 2 }
+    );
+
+    let parsed: Settings = config
+        .from_str("<settings>x<level>2</level></settings>")
+        .unwrap();
+    assert_eq!(parsed, Settings { level: 2 });
+}
+
+#[test]
+fn metrics_callback_counts_elements_and_skips() {
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Settings {
+        level: u32,
+    }
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let options = {
+        let seen = Arc::clone(&seen);
+        facet_xml::DeserializeOptions::new().on_metrics(move |m| seen.lock().unwrap().push(*m))
+    };
+
+    let xml = "<settings><unknown><deep/></unknown><level>3</level></settings>";
+    let parsed: Settings = facet_xml::from_str_with_options(xml, &options).unwrap();
+    assert_eq!(parsed, Settings { level: 3 });
+
+    // A failed parse still reports
+    assert!(
+        facet_xml::from_str_with_options::<Settings>(
+            "<settings><level>x</level></settings>",
+            &options
+        )
+        .is_err()
+    );
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 2);
+    assert_eq!(seen[0].elements, 4);
+    assert_eq!(seen[0].skipped_elements, 1);
+    assert_eq!(seen[0].bytes_parsed, xml.len() as u64);
+}
+
+#[test]
+fn progress_callback_reports_offset_and_path() {
+    use facet_xml::ProgressInterval;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Import {
+        row: Vec<u32>,
+    }
+
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let options = {
+        let reports = Arc::clone(&reports);
+        facet_xml::DeserializeOptions::new().on_progress(ProgressInterval::Bytes(16), move |p| {
+            reports.lock().unwrap().push((p.offset, p.path.join("/")));
+        })
+    };
+
+    let xml = format!("<import>{}</import>", "<row>1</row>".repeat(10));
+    let _ = facet_xml::from_str_with_options::<Import>(&xml, &options);
+
+    let reports = reports.lock().unwrap();
+    assert!(!reports.is_empty());
+    // Offsets only grow, and at most one report per 16-byte step
+    assert!(reports.windows(2).all(|w| w[0].0 / 16 < w[1].0 / 16));
+    assert!(reports.iter().any(|(_, path)| path == "import/row"));
+}
+
+#[test]
+fn expired_deadline_cancels_parsing() {
+    use std::time::{Duration, Instant};
+
+    #[derive(Facet, Debug)]
+    struct Settings {
+        level: u32,
+    }
+
+    let options =
+        facet_xml::DeserializeOptions::new().deadline(Instant::now() - Duration::from_millis(1));
+    let err = facet_xml::from_str_with_options::<Settings>(
+        "<settings><level>1</level></settings>",
+        &options,
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        facet_xml::DeserializeError::Parser(facet_xml::XmlError::Cancelled)
+    ));
+
+    let options =
+        facet_xml::DeserializeOptions::new().deadline(Instant::now() + Duration::from_secs(3600));
+    assert!(
+        facet_xml::from_str_with_options::<Settings>(
+            "<settings><level>1</level></settings>",
+            &options
+        )
+        .is_ok()
+    );
+}
+
+#[test]
+fn sort_map_keys_makes_hashmap_output_deterministic() {
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "record")]
+    struct Record {
+        #[facet(flatten, default)]
+        extra: HashMap<String, String>,
+        data: HashMap<String, u32>,
+    }
+
+    let keys = [
+        "delta", "alpha", "echo", "charlie", "bravo", "golf", "foxtrot",
+    ];
+    let record = Record {
+        extra: keys
+            .iter()
+            .map(|k| (format!("x-{k}"), k.to_string()))
+            .collect(),
+        data: keys
+            .iter()
+            .zip(0..)
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+    };
+
+    let options = facet_xml::SerializeOptions::new().sort_map_keys(true);
+    let out = facet_xml::to_string_with_options(&record, &options).unwrap();
+    assert_eq!(
+        out,
+        concat!(
+            r#"<record x-alpha="alpha" x-bravo="bravo" x-charlie="charlie" x-delta="delta" x-echo="echo" x-foxtrot="foxtrot" x-golf="golf">"#,
+            "<data><alpha>1</alpha><bravo>4</bravo><charlie>3</charlie><delta>0</delta><echo>2</echo><foxtrot>6</foxtrot><golf>5</golf></data>",
+            "</record>"
+        )
+    );
+
+    let parsed: Record = facet_xml::from_str(&out).unwrap();
+    assert_eq!(parsed, record);
+}
+
+#[test]
+fn smart_pointer_strings_as_attributes_and_map_keys() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "entry")]
+    struct Entry {
+        #[facet(xml::attribute)]
+        id: Arc<str>,
+        #[facet(xml::attribute)]
+        kind: Rc<str>,
+        #[facet(xml::attribute)]
+        label: Box<str>,
+        #[facet(flatten, default)]
+        extra: HashMap<Arc<str>, Arc<str>>,
+        tags: HashMap<Arc<str>, Box<str>>,
+    }
+
+    let entry = Entry {
+        id: "e1".into(),
+        kind: "note".into(),
+        label: "First".into(),
+        extra: [("x-owner".into(), "ops".into())].into_iter().collect(),
+        tags: [("color".into(), "red".into())].into_iter().collect(),
+    };
+
+    let xml = facet_xml::to_string(&entry).unwrap();
+    assert_eq!(
+        xml,
+        r#"<entry id="e1" kind="note" label="First" x-owner="ops"><tags><color>red</color></tags></entry>"#
+    );
+
+    let parsed: Entry = facet_xml::from_str(&xml).unwrap();
+    assert_eq!(parsed, entry);
+}
+
+#[test]
+fn conversion_errors_report_value_path_and_text() {
+    use facet_xml as xml;
+    use std::num::NonZeroU32;
+
+    #[derive(Facet, Debug)]
+    struct Item {
+        #[facet(xml::attribute)]
+        sku: NonZeroU32,
+        qty: NonZeroU32,
+    }
+
+    #[derive(Facet, Debug)]
+    #[facet(rename = "order")]
+    struct Order {
+        #[facet(rename = "item")]
+        items: Vec<Item>,
+    }
+
+    let err = facet_xml::from_str::<Order>(
+        r#"<order><item sku="1"><qty>2</qty></item><item sku="7"><qty>0</qty></item></order>"#,
+    )
+    .unwrap_err();
+    match &err {
+        facet_xml::DeserializeError::InvalidValue { path, text, .. } => {
+            assert_eq!(path, "order/item/qty");
+            assert_eq!(text.as_deref(), Some("0"));
+        }
+        other => panic!("expected InvalidValue, got {other:?}"),
+    }
+    assert!(
+        err.to_string()
+            .starts_with(r#"invalid value "0" at order/item/qty: "#)
+    );
+
+    let err = facet_xml::from_str::<Order>(r#"<order><item sku="abc"><qty>1</qty></item></order>"#)
+        .unwrap_err();
+    match &err {
+        facet_xml::DeserializeError::InvalidValue { path, text, .. } => {
+            assert_eq!(path, "order/item/@sku");
+            assert_eq!(text.as_deref(), Some("abc"));
+        }
+        other => panic!("expected InvalidValue, got {other:?}"),
+    }
+}
+
+#[test]
+fn container_assertions_reject_inconsistent_values() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(xml::assert = stay_is_ordered)]
+    struct Stay {
+        #[facet(xml::attribute)]
+        start: u32,
+        #[facet(xml::attribute)]
+        end: u32,
+    }
+
+    fn stay_is_ordered(stay: &Stay) -> Result<(), String> {
+        if stay.end > stay.start {
+            Ok(())
+        } else {
+            Err(format!(
+                "end ({}) must be after start ({})",
+                stay.end, stay.start
+            ))
+        }
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "booking")]
+    struct Booking {
+        #[facet(rename = "stay")]
+        stays: Vec<Stay>,
+    }
+
+    let booking: Booking =
+        facet_xml::from_str(r#"<booking><stay start="1" end="3"/></booking>"#).unwrap();
+    assert_eq!(booking.stays, vec![Stay { start: 1, end: 3 }]);
+
+    let err = facet_xml::from_str::<Booking>(
+        r#"<booking><stay start="1" end="3"/><stay start="5" end="4"/></booking>"#,
+    )
+    .unwrap_err();
+    match &err {
+        facet_xml::DeserializeError::AssertionFailed { path, message } => {
+            assert_eq!(path, "booking/stay[1]");
+            assert_eq!(message, "end (4) must be after start (5)");
+        }
+        other => panic!("expected AssertionFailed, got {other:?}"),
+    }
+}
+
+#[test]
+fn field_validations_reject_out_of_range_values() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Item {
+        #[facet(xml::attribute, xml::validate = is_sku)]
+        sku: String,
+        #[facet(xml::validate = is_positive)]
+        qty: u32,
+    }
+
+    // Validators receive the field as declared
+    #[allow(clippy::ptr_arg)]
+    fn is_sku(sku: &String) -> Result<(), String> {
+        if sku.len() == 6 && sku.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            Ok(())
+        } else {
+            Err(format!("{sku:?} is not a six-character SKU"))
+        }
+    }
+
+    fn is_positive(qty: &u32) -> Result<(), String> {
+        if *qty > 0 {
+            Ok(())
+        } else {
+            Err("quantity must be positive".to_string())
+        }
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "order")]
+    struct Order {
+        #[facet(rename = "item")]
+        items: Vec<Item>,
+    }
+
+    let order: Order =
+        facet_xml::from_str(r#"<order><item sku="AB12CD"><qty>2</qty></item></order>"#).unwrap();
+    assert_eq!(order.items[0].qty, 2);
+
+    let err = facet_xml::from_str::<Order>(
+        r#"<order><item sku="AB12CD"><qty>2</qty></item><item sku="AB12CD"><qty>0</qty></item></order>"#,
+    )
+    .unwrap_err();
+    match &err {
+        facet_xml::DeserializeError::Validation { path, message } => {
+            assert_eq!(path, "order/item[1]/qty");
+            assert_eq!(message, "quantity must be positive");
+        }
+        other => panic!("expected Validation, got {other:?}"),
+    }
+
+    let err =
+        facet_xml::from_str::<Order>(r#"<order><item sku="AB-1"><qty>1</qty></item></order>"#)
+            .unwrap_err();
+    match &err {
+        facet_xml::DeserializeError::Validation { path, .. } => {
+            assert_eq!(path, "order/item[0]/@sku");
+        }
+        other => panic!("expected Validation, got {other:?}"),
+    }
+}
+
+#[test]
+fn located_errors_carry_path_line_and_column() {
+    #[derive(Facet, Debug)]
+    struct Item {
+        price: u32,
+    }
+
+    #[derive(Facet, Debug)]
+    #[facet(rename = "order")]
+    struct Order {
+        #[facet(rename = "item")]
+        items: Vec<Item>,
+    }
+
+    let xml =
+        "<order>\n  <item><price>3</price></item>\n  <item><price>abc</price></item>\n</order>";
+
+    // Errors aren't located unless asked
+    let err = facet_xml::from_str::<Order>(xml).unwrap_err();
+    assert!(err.location().is_none());
+
+    let options = facet_xml::DeserializeOptions::default().locate_errors(true);
+    let err = facet_xml::from_str_with_options::<Order>(xml, &options).unwrap_err();
+    let location = err.location().expect("error should be located");
+    assert_eq!(location.path, "order/item[1]");
+    assert_eq!(location.line, Some(3));
+    assert_eq!(location.column, Some(16));
+    assert_eq!(
+        location.snippet.as_deref(),
+        Some("  <item><price>abc</price></item>")
+    );
+
+    let rendered = err.to_string();
+    assert!(rendered.contains("at order/item[1], line 3, column 16"));
+    assert!(rendered.contains("3 |   <item><price>abc</price></item>"));
+    assert!(rendered.ends_with("  |                ^^^"));
+
+    assert!(matches!(
+        err.into_unlocated(),
+        facet_xml::DeserializeError::InvalidValue { .. }
+    ));
+}
+
+#[test]
+fn lenient_collect_reports_every_recoverable_error() {
+    use facet_xml as xml;
+    use facet_xml::DeserializeError;
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(deny_unknown_fields, rename = "config")]
+    struct Config {
+        #[facet(xml::attribute)]
+        retries: Option<u32>,
+        #[facet(default)]
+        timeout: u32,
+        name: String,
+        #[facet(rename = "port")]
+        ports: Vec<u16>,
+    }
+
+    let input = r#"<config retries="many">
+  <timeout>soon</timeout>
+  <colour>red</colour>
+  <name>db</name>
+  <port>80</port>
+  <port>99999</port>
+  <port>443</port>
+</config>"#;
+
+    let (config, errors) = xml::from_str_lenient_collect::<Config>(input);
+    assert_eq!(
+        config,
+        Some(Config {
+            retries: None,
+            timeout: 0,
+            name: "db".into(),
+            ports: vec![80, 443],
+        })
+    );
+    let lines: Vec<_> = errors
+        .iter()
+        .map(|e| e.location().expect("collected errors are located").line)
+        .collect();
+    assert_eq!(lines, [Some(1), Some(2), Some(3), Some(6)]);
+    let errors: Vec<_> = errors
+        .into_iter()
+        .map(DeserializeError::into_unlocated)
+        .collect();
+    assert!(matches!(errors[0], DeserializeError::InvalidValue { .. }));
+    assert!(matches!(errors[1], DeserializeError::InvalidValue { .. }));
+    assert!(matches!(errors[2], DeserializeError::UnknownElement { .. }));
+    assert!(matches!(errors[3], DeserializeError::InvalidValue { .. }));
+
+    // A missing required field leaves no value, and is reported last
+    let (config, errors) = xml::from_str_lenient_collect::<Config>(
+        "<config><colour>red</colour><port>1</port></config>",
+    );
+    assert_eq!(config, None);
+    let errors: Vec<_> = errors
+        .into_iter()
+        .map(DeserializeError::into_unlocated)
+        .collect();
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(errors[0], DeserializeError::UnknownElement { .. }));
+
+    // Strict deserialization still stops at the first error
+    assert!(xml::from_str::<Config>(input).is_err());
+}
+
+#[test]
+fn unknown_fields_are_reported_together() {
+    use facet_xml as xml;
+    use facet_xml::{DeserializeError, DeserializeOptions, UnknownItem};
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(deny_unknown_fields)]
+    struct Line {
+        #[facet(xml::attribute)]
+        sku: String,
+        qty: u32,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(deny_unknown_fields)]
+    struct Order {
+        #[facet(xml::attribute)]
+        id: u32,
+        customer: String,
+        #[facet(rename = "line")]
+        lines: Vec<Line>,
+    }
+
+    // `customer` was renamed `client`: the missing field isn't what's reported
+    let input = r#"<order id="7" version="2">
+  <client>ACME</client>
+  <line sku="a" colour="red"><qty>1</qty></line>
+  <line sku="b"><qty>2</qty><note>gift</note></line>
+</order>"#;
+    let options = DeserializeOptions::new().report_unknown_fields(true);
+    let err = xml::from_str_with_options::<Order>(input, &options).unwrap_err();
+    let DeserializeError::UnknownFields(unknown) = err else {
+        panic!("expected unknown fields, got {err}");
+    };
+    let item = |path: &str, name: &str, attribute| UnknownItem {
+        path: path.into(),
+        name: name.into(),
+        attribute,
+    };
+    assert_eq!(
+        unknown,
+        [
+            item("order/@version", "version", true),
+            item("order/client", "client", false),
+            item("order/line/@colour", "colour", true),
+            item("order/line/note", "note", false),
+        ]
+    );
+
+    // Documents with nothing unknown deserialize as usual
+    let input =
+        r#"<order id="7"><customer>ACME</customer><line sku="a"><qty>1</qty></line></order>"#;
+    let order: Order = xml::from_str_with_options(input, &options).unwrap();
+    assert_eq!(order.lines.len(), 1);
+
+    // Without the option, the first unknown item fails
+    let input = r#"<order id="7" version="2"><customer>ACME</customer></order>"#;
+    assert!(matches!(
+        xml::from_str::<Order>(input),
+        Err(DeserializeError::UnknownAttribute { .. })
+    ));
+}
+
+#[test]
+fn duplicate_elements_follow_their_policy() {
+    use facet_xml as xml;
+    use facet_xml::{DeserializeError, DeserializeOptions, DuplicatePolicy};
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Record {
+        #[facet(xml::on_duplicate = "error")]
+        id: u32,
+        #[facet(xml::on_duplicate = "first")]
+        name: String,
+        note: Option<String>,
+    }
+
+    let input =
+        "<record><id>1</id><name>a</name><name>b</name><note>x</note><note>y</note></record>";
+    let record: Record = xml::from_str(input).unwrap();
+    assert_eq!(record.name, "a");
+    assert_eq!(record.note.as_deref(), Some("y"));
+
+    // The field's own policy wins over the options
+    let options = DeserializeOptions::new().on_duplicate(DuplicatePolicy::Error);
+    let err = xml::from_str_with_options::<Record>(input, &options).unwrap_err();
+    match err {
+        DeserializeError::DuplicateElement { path } => assert_eq!(path, "record/note"),
+        other => panic!("expected a duplicate element, got {other}"),
+    }
+
+    let input = "<record><id>1</id><id>2</id><name>a</name></record>";
+    let err = xml::from_str::<Record>(input).unwrap_err();
+    assert!(matches!(err, DeserializeError::DuplicateElement { .. }));
+    assert_eq!(err.to_string(), "duplicate element at record/id");
+}
+
+#[test]
+fn ordered_structs_keep_document_order() {
+    use facet_xml as xml;
+    use facet_xml::ChildOrder;
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(xml::ordered)]
+    struct Section {
+        title: String,
+        #[facet(rename = "para")]
+        paras: Vec<String>,
+        #[facet(rename = "note")]
+        notes: Vec<String>,
+        order: ChildOrder,
+    }
+
+    let input =
+        "<section><para>one</para><title>Intro</title><note>aside</note><para>two</para></section>";
+    let section: Section = xml::from_str(input).unwrap();
+    assert_eq!(section.title, "Intro");
+    assert_eq!(section.paras, ["one", "two"]);
+    assert_eq!(
+        section.order.fields().collect::<Vec<_>>(),
+        ["paras", "title", "notes", "paras"]
+    );
+    assert_eq!(xml::to_string(&section).unwrap(), input);
+
+    // Text between elements goes back where it was read
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(xml::ordered)]
+    struct P {
+        #[facet(xml::text, default)]
+        text: String,
+        #[facet(rename = "b", default)]
+        bold: Vec<String>,
+        order: ChildOrder,
+    }
+
+    let input = "<p>a<b>x</b>c</p>";
+    let p: P = xml::from_str(input).unwrap();
+    assert_eq!(p.text, "ac");
+    assert_eq!(xml::to_string(&p).unwrap(), input);
+
+    // Without the attribute, nothing is recorded and fields are grouped
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "section")]
+    struct Plain {
+        title: String,
+        #[facet(rename = "para")]
+        paras: Vec<String>,
+        order: ChildOrder,
+    }
+
+    let plain: Plain =
+        xml::from_str("<section><para>one</para><title>Intro</title><para>two</para></section>")
+            .unwrap();
+    assert!(plain.order.is_empty());
+    assert_eq!(
+        xml::to_string(&plain).unwrap(),
+        "<section><title>Intro</title><para>one</para><para>two</para></section>"
+    );
+}
+
+#[test]
+fn xsi_type_selects_the_enum_variant() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Circle {
+        #[facet(xml::attribute)]
+        radius: u32,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(xml::xsi_type)]
+    #[repr(u8)]
+    enum Shape {
+        Circle(Circle),
+        Square {
+            side: u32,
+        },
+        #[facet(rename = "EmptyShape")]
+        Empty,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "drawing")]
+    struct Drawing {
+        #[facet(rename = "shape")]
+        shapes: Vec<Shape>,
+    }
+
+    let input = r#"<drawing xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:geo="urn:geo">
+  <shape xsi:type="geo:Circle" radius="2"/>
+  <shape xsi:type="Square"><side>3</side></shape>
+  <shape xsi:type="geo:EmptyShape"/>
+</drawing>"#;
+    let drawing: Drawing = xml::from_str(input).unwrap();
+    assert_eq!(
+        drawing.shapes,
+        vec![
+            Shape::Circle(Circle { radius: 2 }),
+            Shape::Square { side: 3 },
+            Shape::Empty,
+        ]
+    );
+
+    let output = xml::to_string(&drawing).unwrap();
+    assert!(output.contains(r#"xsi:type="Circle""#), "{output}");
+    assert!(output.contains(r#"xsi:type="EmptyShape""#), "{output}");
+    assert_eq!(xml::from_str::<Drawing>(&output).unwrap(), drawing);
+
+    let err = xml::from_str::<Drawing>(
+        r#"<drawing><shape xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="Triangle"/></drawing>"#,
+    )
+    .unwrap_err();
+    assert!(
+        matches!(&err, xml::DeserializeError::UnknownType { type_name } if type_name == "Triangle"),
+        "{err}"
+    );
+    let err = xml::from_str::<Drawing>("<drawing><shape/></drawing>").unwrap_err();
+    assert!(
+        matches!(
+            err,
+            xml::DeserializeError::MissingAttribute { name: "xsi:type" }
+        ),
+        "{err}"
+    );
+
+    // Type names are qualified with the enum's namespace
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(xml::xsi_type, xml::ns_all = "urn:geo", rename = "shape")]
+    #[repr(u8)]
+    enum GeoShape {
+        Circle(Circle),
+    }
+
+    let shape = GeoShape::Circle(Circle { radius: 5 });
+    let output = xml::to_string(&shape).unwrap();
+    let prefix = output
+        .split_once(r#"="urn:geo""#)
+        .and_then(|(before, _)| before.rsplit_once("xmlns:"))
+        .map(|(_, prefix)| prefix)
+        .expect("type namespace should be declared");
+    assert!(
+        output.contains(&format!(r#"xsi:type="{prefix}:Circle""#)),
+        "{output}"
+    );
+    assert_eq!(xml::from_str::<GeoShape>(&output).unwrap(), shape);
+}
+
+#[test]
+fn xsi_nil_elements_are_none() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "contact")]
+    struct Contact {
+        name: Option<String>,
+        #[facet(xml::nillable)]
+        phone: Option<String>,
+        #[facet(xml::nillable)]
+        age: Option<u32>,
+        #[facet(xml::nillable, rename = "score")]
+        scores: Vec<Option<u32>>,
+    }
+
+    let input = r#"<contact xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <name xsi:nil="true"/>
+  <phone xsi:nil="false">555</phone>
+  <age xsi:nil="1"></age>
+  <score>4</score>
+  <score xsi:nil="true"/>
+</contact>"#;
+    let contact: Contact = xml::from_str(input).unwrap();
+    assert_eq!(
+        contact,
+        Contact {
+            name: None,
+            phone: Some("555".into()),
+            age: None,
+            scores: vec![Some(4), None],
+        }
+    );
+
+    // Empty elements without the marker are still empty values
+    let contact: Contact = xml::from_str("<contact><name/></contact>").unwrap();
+    assert_eq!(contact.name, Some(String::new()));
+
+    let contact = Contact {
+        name: None,
+        phone: None,
+        age: Some(30),
+        scores: vec![None],
+    };
+    let output = xml::to_string(&contact).unwrap();
+    assert!(!output.contains("<name"), "{output}");
+    assert!(output.contains(r#"xsi:nil="true""#), "{output}");
+    assert_eq!(output.matches(r#"xsi:nil="true""#).count(), 2, "{output}");
+    assert_eq!(xml::from_str::<Contact>(&output).unwrap(), contact);
+}
+
+#[test]
+fn borrowed_values_point_into_the_input_unless_escaped() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug)]
+    struct Entry<'a> {
+        #[facet(xml::attribute)]
+        id: &'a str,
+        #[facet(xml::attribute)]
+        title: Cow<'a, str>,
+        #[facet(xml::element)]
+        body: Cow<'a, str>,
+        #[facet(xml::element)]
+        code: &'a str,
+    }
+
+    let input =
+        r#"<entry id="e1" title="Plain"><body>Text</body><code><![CDATA[a < b]]></code></entry>"#;
+    let entry: Entry = facet_xml::from_str_borrowed(input).unwrap();
+    let within = |s: &str| input.as_bytes().as_ptr_range().contains(&s.as_ptr());
+    assert!(within(entry.id));
+    assert!(matches!(entry.title, Cow::Borrowed(title) if within(title)));
+    assert!(matches!(entry.body, Cow::Borrowed(body) if within(body)));
+    assert_eq!(entry.code, "a < b");
+    assert!(within(entry.code));
+
+    let input = r#"<entry id="e1" title="A &amp; B"><body>x &lt; y</body><code>c</code></entry>"#;
+    let entry: Entry = facet_xml::from_str_borrowed(input).unwrap();
+    assert!(matches!(entry.title, Cow::Owned(ref title) if title == "A & B"));
+    assert!(matches!(entry.body, Cow::Owned(_)));
+
+    // A `&str` can't hold an unescaped copy
+    #[derive(Facet, Debug)]
+    struct Name<'a> {
+        #[facet(xml::attribute)]
+        value: &'a str,
+    }
+    assert!(facet_xml::from_str_borrowed::<Name>(r#"<name value="a &amp; b"/>"#).is_err());
+    assert_eq!(
+        facet_xml::from_str_borrowed::<Name>(r#"<name value="ab"/>"#)
+            .unwrap()
+            .value,
+        "ab"
+    );
+}
+
+#[test]
+fn reformat_reindents_without_touching_content() {
+    use facet_xml::SerializeOptions;
+
+    let input = concat!(
+        r#"<?xml version="1.0"?>"#,
+        "\n<doc xmlns:x=\"urn:x\">  <x:item id='a\"b' name=\"long-enough-to-wrap\"/>",
+        "<p>Some <b>bold</b> &amp; <i> spaced </i> text</p><code><![CDATA[<raw>]]></code></doc>",
+    );
+    let options = SerializeOptions::new()
+        .pretty()
+        .newline("\r\n")
+        .wrap_attributes(30);
+    assert_eq!(
+        facet_xml::reformat(input, &options).unwrap(),
+        concat!(
+            "<?xml version=\"1.0\"?>\r\n",
+            "<doc xmlns:x=\"urn:x\">\r\n",
+            "  <x:item\r\n",
+            "    id=\"a&quot;b\"\r\n",
+            "    name=\"long-enough-to-wrap\"/>\r\n",
+            "  <p>Some <b>bold</b> &amp; <i> spaced </i> text</p>\r\n",
+            "  <code><![CDATA[<raw>]]></code>\r\n",
+            "</doc>\r\n",
+        )
+    );
+
+    assert!(facet_xml::reformat("<a><b></a>", &options).is_err());
+    assert!(facet_xml::reformat("<a>", &options).is_err());
+}
+
+#[test]
+fn whitespace_options_and_xml_space() {
+    use facet_xml as xml;
+    use facet_xml::{DeserializeOptions, Whitespace};
+
+    #[derive(Facet, Debug)]
+    struct Para {
+        #[facet(xml::text)]
+        text: Vec<String>,
+    }
+
+    #[derive(Facet, Debug)]
+    #[facet(rename = "doc")]
+    struct Doc {
+        #[facet(rename = "para")]
+        paras: Vec<Para>,
+    }
+
+    let input = "<doc>\n  <para> a  <b>b</b>\tc\n</para>\n  <para xml:space=\"preserve\"> a  <b>b</b> </para>\n</doc>";
+    let parse = |whitespace| -> Vec<Vec<String>> {
+        let options = DeserializeOptions::new().whitespace(whitespace);
+        let doc: Doc = facet_xml::from_str_with_options(input, &options).unwrap();
+        doc.paras.into_iter().map(|p| p.text).collect()
+    };
+
+    // xml:space="preserve" wins over the option
+    assert_eq!(parse(Whitespace::Trim), [vec!["a", "c"], vec![" a  ", " "]]);
+    assert_eq!(
+        parse(Whitespace::Normalize),
+        [vec![" a ", " c "], vec![" a  ", " "]]
+    );
+    assert_eq!(
+        parse(Whitespace::Preserve),
+        [vec![" a  ", "\tc\n"], vec![" a  ", " "]]
+    );
+}
+
+#[test]
+fn context_resolves_references_while_parsing() {
+    use facet_xml as xml;
+    use std::collections::HashMap;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Person {
+        #[facet(xml::attribute, xml::with_context)]
+        id: usize,
+        name: String,
+        #[facet(xml::with_context)]
+        manager: Option<usize>,
+    }
+
+    #[derive(Facet, Debug)]
+    #[facet(rename = "staff")]
+    struct Staff {
+        #[facet(rename = "person")]
+        people: Vec<Person>,
+    }
+
+    // IDs become indices; IDREFs resolve to the index of an earlier ID
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut seen = Vec::new();
+    let mut resolve = |value: &xml::FieldValue<'_>| -> Option<String> {
+        seen.push(value.path.to_string());
+        if value.path.ends_with("/@id") {
+            let index = ids.len();
+            ids.insert(value.text.to_string(), index);
+            Some(index.to_string())
+        } else {
+            ids.get(value.text).map(usize::to_string)
+        }
+    };
+
+    let input = r#"<staff><person id="ada"><name>Ada</name></person><person id="bob"><name>Bob</name><manager>ada</manager></person></staff>"#;
+    let staff: Staff = xml::from_str_with_context(input, &mut resolve).unwrap();
+    assert_eq!(
+        staff.people,
+        [
+            Person {
+                id: 0,
+                name: "Ada".into(),
+                manager: None
+            },
+            Person {
+                id: 1,
+                name: "Bob".into(),
+                manager: Some(0)
+            },
+        ]
+    );
+
+    // A dangling reference is left as it is, and fails to parse
+    let input =
+        r#"<staff><person id="cy"><name>Cy</name><manager>nobody</manager></person></staff>"#;
+    assert!(xml::from_str_with_context::<Staff, _>(input, &mut resolve).is_err());
+
+    // Only marked fields go through the context
+    let ids = seen.iter().filter(|path| path.ends_with("/@id")).count();
+    let managers = seen
+        .iter()
+        .filter(|path| path.ends_with("/manager"))
+        .count();
+    assert_eq!((ids, managers), (3, 2));
+}
+
+#[test]
+fn context_is_combined_with_options() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "ref")]
+    struct Ref {
+        #[facet(xml::attribute, xml::with_context)]
+        to: u32,
+    }
+
+    let mut resolve = |value: &xml::FieldValue<'_>| -> Option<String> {
+        (value.text == "answer").then(|| "42".to_string())
+    };
+    let input = r#"<ref to="answer">stray text</ref>"#;
+
+    // Stray text is an error by default...
+    assert!(xml::from_str_with_context::<Ref, _>(input, &mut resolve).is_err());
+
+    // ...but the options are honored alongside the context
+    let options = xml::DeserializeOptions::new().lenient(true);
+    let parsed: Ref =
+        xml::from_str_with_context_and_options(input, &mut resolve, &options).unwrap();
+    assert_eq!(parsed, Ref { to: 42 });
+}
+
+#[test]
+fn audit_accepts_well_escaped_output_and_flags_bad_characters() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "note")]
+    struct Note {
+        #[facet(xml::attribute)]
+        title: String,
+        body: String,
+        tags: HashMap<String, String>,
+    }
+
+    let note = Note {
+        title: r#"a "quoted" <title> & more"#.into(),
+        body: "1 < 2 && 3 > 2".into(),
+        tags: [("kind".to_string(), "x&y".to_string())]
+            .into_iter()
+            .collect(),
+    };
+    for options in [
+        facet_xml::SerializeOptions::new().audit(true),
+        facet_xml::SerializeOptions::new().pretty().audit(true),
+    ] {
+        let out = facet_xml::to_string_with_options(&note, &options).unwrap();
+        assert_eq!(facet_xml::from_str::<Note>(&out).unwrap(), note);
+    }
+
+    let bad = Note {
+        body: "ring \u{7} bell".into(),
+        ..note
+    };
+    let options = facet_xml::SerializeOptions::new().audit(true);
+    let err = facet_xml::to_string_with_options(&bad, &options).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("character U+0007 in text of <body> can't be represented in XML 1.0"),
+        "{err}"
+    );
+}
+
+#[test]
+fn subscribe_delivers_items_at_a_nested_path() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Item {
+        #[facet(xml::attribute)]
+        sku: String,
+        qty: u32,
+    }
+
+    let xml = r#"<orders>
+        <order id="1"><item sku="a"><qty>1</qty></item><note>skip me</note></order>
+        <summary><item sku="ignored"><qty>0</qty></item></summary>
+        <order id="2"><item sku="b"><qty>2</qty></item><item sku="c"><qty>3</qty></item></order>
+    </orders>"#;
+
+    let mut items = Vec::new();
+    let count = facet_xml::subscribe(xml.as_bytes(), "orders/order/item", |item: Item| {
+        items.push(item)
+    })
+    .unwrap();
+    assert_eq!(count, 3);
+    assert_eq!(
+        items
+            .iter()
+            .map(|i| (i.sku.as_str(), i.qty))
+            .collect::<Vec<_>>(),
+        [("a", 1), ("b", 2), ("c", 3)]
+    );
+
+    let count = facet_xml::subscribe(xml.as_bytes(), "orders/*/item", |_: Item| {}).unwrap();
+    assert_eq!(count, 4);
+
+    assert!(facet_xml::subscribe(xml.as_bytes(), "orders//item", |_: Item| {}).is_err());
+}
+
+#[test]
+fn fidelity_report_lists_what_the_type_drops() {
+    use facet_xml as xml;
+    use facet_xml::Loss;
+
+    #[derive(Facet, Debug)]
+    struct Order {
+        #[facet(xml::attribute)]
+        id: u32,
+        title: String,
+        qty: u32,
+    }
+
+    let report = facet_xml::fidelity_report::<Order>(
+        r#"<order id="1" rev="3"><!-- imported --><qty>02</qty><title>Tea</title><note>fragile</note></order>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        report.losses,
+        [
+            Loss::DroppedAttribute {
+                path: "order".into(),
+                name: "rev".into(),
+                value: "3".into(),
+            },
+            Loss::DroppedComment {
+                path: "order".into(),
+                text: " imported ".into(),
+            },
+            Loss::ReorderedChildren {
+                path: "order".into(),
+            },
+            Loss::ChangedText {
+                path: "order/qty".into(),
+                before: "02".into(),
+                after: "2".into(),
+            },
+            Loss::DroppedElement {
+                path: "order/note".into(),
+            },
+        ]
+    );
+    assert!(!report.is_lossless());
+    assert!(report.to_string().contains("order/note: element dropped"));
+
+    let report = facet_xml::fidelity_report::<Order>(
+        r#"<order id="1"><title>Tea</title><qty>2</qty></order>"#,
+    )
+    .unwrap();
+    assert!(report.is_lossless(), "{report}");
+}
+
+#[test]
+fn since_and_until_gate_fields_by_schema_version() {
+    use facet_xml as xml;
+    use facet_xml::{DeserializeOptions, SerializeOptions};
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Contact {
+        name: String,
+        #[facet(xml::until = "1.x")]
+        phone: Option<String>,
+        #[facet(xml::since = "2.0", xml::attribute)]
+        mobile: Option<String>,
+    }
+
+    let contact = Contact {
+        name: "Ada".into(),
+        phone: Some("555".into()),
+        mobile: Some("556".into()),
+    };
+
+    let v1 = facet_xml::to_string_with_options(&contact, &SerializeOptions::new().version("1.3"))
+        .unwrap();
+    assert_eq!(v1, "<contact><name>Ada</name><phone>555</phone></contact>");
+    let v2 = facet_xml::to_string_with_options(&contact, &SerializeOptions::new().version("2.1"))
+        .unwrap();
+    assert_eq!(v2, r#"<contact mobile="556"><name>Ada</name></contact>"#);
+
+    let both = r#"<contact mobile="556"><name>Ada</name><phone>555</phone></contact>"#;
+    let read_v1: Contact =
+        facet_xml::from_str_with_options(both, &DeserializeOptions::new().version("1.0")).unwrap();
+    assert_eq!(read_v1.phone.as_deref(), Some("555"));
+    assert_eq!(read_v1.mobile, None);
+
+    let read_v2: Contact = facet_xml::from_str_with_options(
+        r#"<contact schema="2.0" mobile="556"><name>Ada</name><phone>555</phone></contact>"#,
+        &DeserializeOptions::new()
+            .version("1.0")
+            .version_attribute("schema"),
+    )
+    .unwrap();
+    assert_eq!(read_v2.phone, None);
+    assert_eq!(read_v2.mobile.as_deref(), Some("556"));
+
+    // Without a version every field is read
+    let unversioned: Contact = facet_xml::from_str(both).unwrap();
+    assert_eq!(unversioned, contact);
+}
+
+#[test]
+fn codec_fields_round_trip_bytes_as_text() {
+    use facet_xml as xml;
+    use facet_xml::{DeserializeError, TextCodec};
+
+    /// Bytes as dot-separated decimals, e.g. `1.2.255`.
+    struct Dotted;
+
+    impl TextCodec for Dotted {
+        fn encode(&self, bytes: &[u8]) -> String {
+            bytes
+                .iter()
+                .map(u8::to_string)
+                .collect::<Vec<_>>()
+                .join(".")
+        }
+
+        fn decode(&self, text: &str) -> Result<Vec<u8>, String> {
+            text.split('.')
+                .map(|part| part.parse().map_err(|e| format!("{part:?}: {e}")))
+                .collect()
+        }
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Blob {
+        #[facet(xml::attribute, xml::codec = "hex")]
+        digest: Vec<u8>,
+        #[facet(xml::codec = "base64")]
+        data: Vec<u8>,
+        #[facet(xml::codec = "base64url")]
+        token: Option<Vec<u8>>,
+        #[facet(xml::codec_with = &Dotted)]
+        address: Vec<u8>,
+    }
+
+    let blob = Blob {
+        digest: vec![0xde, 0xad, 0xbe, 0xef],
+        data: b"hello!?".to_vec(),
+        token: Some(vec![0xfb, 0xff]),
+        address: vec![10, 0, 0, 1],
+    };
+    let xml = facet_xml::to_string(&blob).unwrap();
+    assert_eq!(
+        xml,
+        r#"<blob digest="deadbeef"><data>aGVsbG8hPw==</data><token>-_8</token><address>10.0.0.1</address></blob>"#
+    );
+    assert_eq!(facet_xml::from_str::<Blob>(&xml).unwrap(), blob);
+
+    let err = facet_xml::from_str::<Blob>(
+        r#"<blob digest="xyz"><data></data><address>1</address></blob>"#,
+    )
+    .unwrap_err();
+    match err {
+        DeserializeError::InvalidValue { path, source, .. } => {
+            assert_eq!(path, "blob/@digest");
+            assert!(matches!(
+                *source,
+                DeserializeError::Codec { codec: "hex", .. }
+            ));
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+
+    let err = facet_xml::from_str::<Blob>(
+        r#"<blob digest="00"><data></data><address>1.x</address></blob>"#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Dotted decoding failed"), "{err}");
+
+    // Names are registered once, and the built-in ones not at all
+    facet_xml::register_codec("dotted", &Dotted).unwrap();
+    assert_eq!(
+        facet_xml::register_codec("dotted", &Dotted),
+        Err(facet_xml::CodecNameTaken { name: "dotted" })
+    );
+    assert!(facet_xml::register_codec("base64", &Dotted).is_err());
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Registered {
+        #[facet(xml::codec = "dotted")]
+        address: Vec<u8>,
+    }
+    assert_eq!(
+        facet_xml::from_str::<Registered>("<registered><address>10.0.0.1</address></registered>")
+            .unwrap(),
+        Registered {
+            address: vec![10, 0, 0, 1]
+        }
+    );
+}
+
+#[test]
+fn base64_padding_must_end_the_last_group() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Data {
+        #[facet(xml::codec = "base64")]
+        data: Vec<u8>,
+    }
+
+    for (text, bytes) in [
+        ("QQ==", &b"A"[..]),
+        ("QUI=", b"AB"),
+        ("QQ", b"A"),
+        ("QU\n I=\n", b"AB"),
+    ] {
+        let xml = format!("<data><data>{text}</data></data>");
+        assert_eq!(
+            facet_xml::from_str::<Data>(&xml).unwrap().data,
+            bytes,
+            "{text:?}"
+        );
+    }
+    for text in ["A=", "=", "QQ=", "QQ===", "QUJD=", "QQ= =QQ", "QQ==QQ=="] {
+        let xml = format!("<data><data>{text}</data></data>");
+        assert!(facet_xml::from_str::<Data>(&xml).is_err(), "{text:?}");
+    }
+}
+
+#[test]
+fn binary_fields_use_xml_schema_encodings() {
+    use facet_xml as xml;
+    use facet_xml::DeserializeError;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Certificate {
+        #[facet(xml::attribute, xml::binary = "hex")]
+        fingerprint: [u8; 4],
+        #[facet(xml::binary = "base64")]
+        der: Vec<u8>,
+        #[facet(xml::binary = "hex")]
+        serial: Option<Vec<u8>>,
+    }
+
+    let cert = Certificate {
+        fingerprint: [0x0a, 0xbc, 0xde, 0xf0],
+        der: vec![0x30, 0x82, 0x01],
+        serial: Some(vec![0x7f, 0xff]),
+    };
+    let xml = facet_xml::to_string(&cert).unwrap();
+    assert_eq!(
+        xml,
+        r#"<certificate fingerprint="0ABCDEF0"><der>MIIB</der><serial>7FFF</serial></certificate>"#
+    );
+    assert_eq!(facet_xml::from_str::<Certificate>(&xml).unwrap(), cert);
+
+    // Lowercase hex and wrapped base64 read the same
+    let relaxed = "<certificate fingerprint=\"0abcdef0\"><der>\n  MI\n  IB\n</der><serial>7fff</serial></certificate>";
+    assert_eq!(facet_xml::from_str::<Certificate>(relaxed).unwrap(), cert);
+
+    let err = facet_xml::from_str::<Certificate>(
+        r#"<certificate fingerprint="0ABCDE"><der>MIIB</der></certificate>"#,
+    )
+    .unwrap_err();
+    match err {
+        DeserializeError::InvalidValue { path, source, .. } => {
+            assert_eq!(path, "certificate/@fingerprint");
+            assert!(matches!(
+                *source,
+                DeserializeError::Codec {
+                    codec: "hexBinary",
+                    ..
+                }
+            ));
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+#[test]
+fn serialize_with_and_deserialize_with_write_and_read_field_text() {
+    use facet_reflect::Peek;
+    use facet_xml as xml;
+    use facet_xml::{DeserializeError, ValueSlot};
+
+    #[derive(Facet, Debug, PartialEq, Clone, Copy)]
+    struct Rgb {
+        r: u8,
+        g: u8,
+        b: u8,
+    }
+
+    fn write_rgb(value: Peek<'_, '_>, out: &mut String) -> Result<(), String> {
+        let rgb = value.get::<Rgb>().map_err(|e| e.to_string())?;
+        out.push_str(&format!("#{:02x}{:02x}{:02x}", rgb.r, rgb.g, rgb.b));
+        Ok(())
+    }
+
+    fn read_rgb(text: &str, slot: &mut ValueSlot) -> Result<(), String> {
+        let hex = text.strip_prefix('#').ok_or("missing #")?;
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| format!("bad color {text:?}"))
+        };
+        slot.set(Rgb {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        })
+    }
+
+    fn write_ports(value: Peek<'_, '_>, out: &mut String) -> Result<(), String> {
+        let ports = value.get::<Vec<u16>>().map_err(|e| e.to_string())?;
+        let ports: Vec<String> = ports.iter().map(u16::to_string).collect();
+        out.push_str(&ports.join(","));
+        Ok(())
+    }
+
+    fn read_ports(text: &str, slot: &mut ValueSlot) -> Result<(), String> {
+        let ports = text
+            .split(',')
+            .map(|port| port.trim().parse::<u16>().map_err(|e| e.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        slot.set(ports)
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Theme {
+        #[facet(
+            xml::attribute,
+            xml::serialize_with = write_rgb,
+            xml::deserialize_with = read_rgb
+        )]
+        accent: Rgb,
+        #[facet(xml::serialize_with = write_rgb, xml::deserialize_with = read_rgb)]
+        background: Rgb,
+        #[facet(xml::serialize_with = write_ports, xml::deserialize_with = read_ports)]
+        ports: Vec<u16>,
+    }
+
+    let theme = Theme {
+        accent: Rgb {
+            r: 255,
+            g: 136,
+            b: 0,
+        },
+        background: Rgb {
+            r: 16,
+            g: 16,
+            b: 16,
+        },
+        ports: vec![80, 443],
+    };
+    let xml = facet_xml::to_string(&theme).unwrap();
+    assert_eq!(
+        xml,
+        r##"<theme accent="#ff8800"><background>#101010</background><ports>80,443</ports></theme>"##
+    );
+    assert_eq!(facet_xml::from_str::<Theme>(&xml).unwrap(), theme);
+
+    let err = facet_xml::from_str::<Theme>(
+        r##"<theme accent="#ff8800"><background>grey</background><ports>80</ports></theme>"##,
+    )
+    .unwrap_err();
+    match err {
+        DeserializeError::InvalidValue { path, source, .. } => {
+            assert_eq!(path, "theme/background");
+            assert!(matches!(*source, DeserializeError::DeserializeWith { .. }));
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+#[test]
+fn reserved_xml_attributes_follow_the_configured_policy() {
+    use facet_xml as xml;
+    use facet_xml::{DeserializeOptions, ReservedAttributes};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Note {
+        #[facet(xml::attribute)]
+        lang: Option<String>,
+        #[facet(xml::attribute, xml::ns = "http://www.w3.org/XML/1998/namespace")]
+        id: Option<String>,
+        #[facet(flatten)]
+        extra: HashMap<String, String>,
+        body: String,
+    }
+
+    let input =
+        r#"<note xml:id="n1" xml:lang="en" xml:space="preserve" lang="fr"><body>hi</body></note>"#;
+
+    // Generic: xml:lang competes with the plain `lang` attribute and
+    // xml:space lands in the catch-all map
+    let generic: Note = facet_xml::from_str(input).unwrap();
+    assert_eq!(generic.id.as_deref(), Some("n1"));
+    assert!(generic.extra.contains_key("space"));
+
+    // Fields: only the field in the XML namespace sees them
+    let fields: Note = facet_xml::from_str_with_options(
+        input,
+        &DeserializeOptions::new().reserved_attributes(ReservedAttributes::Fields),
+    )
+    .unwrap();
+    assert_eq!(fields.id.as_deref(), Some("n1"));
+    assert_eq!(fields.lang.as_deref(), Some("fr"));
+    assert!(fields.extra.is_empty());
+
+    // Ignore: they never reach the value
+    let ignored: Note = facet_xml::from_str_with_options(
+        input,
+        &DeserializeOptions::new().reserved_attributes(ReservedAttributes::Ignore),
+    )
+    .unwrap();
+    assert_eq!(ignored.id, None);
+    assert_eq!(ignored.lang.as_deref(), Some("fr"));
+    assert!(ignored.extra.is_empty());
+
+    // Report: they go to the side channel instead
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = seen.clone();
+    let options = DeserializeOptions::new().reserved_attributes(ReservedAttributes::Report(
+        Arc::new(move |attr| {
+            sink.lock().unwrap().push(format!(
+                "{}@{}={}",
+                attr.path.join("/"),
+                attr.name,
+                attr.value
+            ));
+        }),
+    ));
+    let reported: Note = facet_xml::from_str_with_options(input, &options).unwrap();
+    assert_eq!(reported.id, None);
+    assert_eq!(
+        *seen.lock().unwrap(),
+        ["note@id=n1", "note@lang=en", "note@space=preserve"]
+    );
+
+    // Fields in the XML namespace are written with the predeclared prefix
+    let xml = facet_xml::to_string(&fields).unwrap();
+    assert_eq!(xml, r#"<note lang="fr" xml:id="n1"><body>hi</body></note>"#);
+}
+
+#[test]
+fn deserialize_from_prepared_events() {
+    use facet_dom::{DomDeserializer, DomEvent, VecEventParser};
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Point {
+        #[facet(xml::attribute)]
+        x: i32,
+        label: String,
+    }
+
+    let events = vec![
+        DomEvent::NodeStart {
+            tag: "point".into(),
+            namespace: None,
+        },
+        DomEvent::Attribute {
+            name: "x".into(),
+            value: "3".into(),
+            namespace: None,
+        },
+        DomEvent::ChildrenStart,
+        DomEvent::NodeStart {
+            tag: "ignored".into(),
+            namespace: None,
+        },
+        DomEvent::ChildrenStart,
+        DomEvent::Text("skipped".into()),
+        DomEvent::ChildrenEnd,
+        DomEvent::NodeEnd,
+        DomEvent::NodeStart {
+            tag: "label".into(),
+            namespace: None,
+        },
+        DomEvent::ChildrenStart,
+        DomEvent::Text("origin".into()),
+        DomEvent::ChildrenEnd,
+        DomEvent::NodeEnd,
+        DomEvent::ChildrenEnd,
+        DomEvent::NodeEnd,
+    ];
+
+    let parser = VecEventParser::new(events).format_namespace("xml");
+    let point: Point = DomDeserializer::new_owned(parser).deserialize().unwrap();
+    assert_eq!(
+        point,
+        Point {
+            x: 3,
+            label: "origin".into()
+        }
+    );
+}
+
+#[test]
+fn describe_reports_the_wire_format() {
+    use facet_xml as xml;
+    use facet_xml::Cardinality;
+
+    #[derive(Facet)]
+    struct Entry {
+        #[facet(xml::attribute, alias = "ident")]
+        id: u32,
+        #[facet(xml::attribute)]
+        lang: Option<String>,
+        title: String,
+        summary: Option<String>,
+        tags: Vec<String>,
+    }
+
+    #[derive(Facet)]
+    #[facet(rename = "note")]
+    struct Note {
+        #[facet(xml::text)]
+        body: String,
+    }
+
+    let entry = xml::describe::<Entry>();
+    assert_eq!(entry.element, "entry");
+    assert_eq!(entry.text, None);
+    assert!(!entry.other_attributes && !entry.other_elements);
+
+    let attributes: Vec<_> = entry
+        .attributes
+        .iter()
+        .map(|attr| (attr.name.as_str(), attr.field, attr.required))
+        .collect();
+    assert_eq!(attributes, [("id", "id", true), ("lang", "lang", false)]);
+    assert_eq!(entry.attribute("ident").unwrap().name, "id");
+
+    let children: Vec<_> = entry
+        .children
+        .iter()
+        .map(|child| (child.name.as_str(), child.cardinality))
+        .collect();
+    assert_eq!(
+        children,
+        [
+            ("title", Cardinality::One),
+            ("summary", Cardinality::Optional),
+            ("tags", Cardinality::Many),
+        ]
+    );
+    let tags = entry.child("tag").unwrap();
+    assert_eq!(tags.aliases, ["tag"]);
+    assert_eq!(tags.shape.type_identifier, "String");
+
+    let note = xml::describe::<Note>();
+    assert_eq!(note.element, "note");
+    assert_eq!(note.text, Some("body"));
+    assert!(note.children.is_empty());
+}
+
+#[test]
+fn contract_diff_classifies_changes() {
+    use facet_xml as xml;
+    use facet_xml::{Cardinality, ChangeKind};
+
+    mod v1 {
+        use facet::Facet;
+        use facet_xml as xml;
+
+        #[derive(Facet)]
+        #[facet(rename = "order")]
+        pub struct Order {
+            #[facet(xml::attribute)]
+            pub id: u32,
+            pub customer: Customer,
+            pub lines: Vec<String>,
+            pub comment: Option<String>,
+        }
+
+        #[derive(Facet)]
+        pub struct Customer {
+            pub name: String,
+        }
+    }
+
+    mod v2 {
+        use facet::Facet;
+        use facet_xml as xml;
+
+        #[derive(Facet)]
+        #[facet(rename = "order")]
+        pub struct Order {
+            #[facet(xml::attribute)]
+            pub id: u32,
+            pub customer: Customer,
+            pub lines: Option<String>,
+            #[facet(rename = "remark", alias = "comment")]
+            pub comment: Option<String>,
+            pub priority: Option<u8>,
+        }
+
+        #[derive(Facet)]
+        pub struct Customer {
+            #[facet(rename = "fullName")]
+            pub name: String,
+        }
+    }
+
+    let diff = xml::contract_diff::<v1::Order, v1::Order>();
+    assert!(diff.changes.is_empty());
+
+    let diff = xml::contract_diff::<v1::Order, v2::Order>();
+    let kinds: Vec<_> = diff
+        .changes
+        .iter()
+        .map(|change| (change.path.as_str(), &change.kind, change.is_breaking()))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            (
+                "order/customer",
+                &ChangeKind::ChildRenamed {
+                    old: "name".into(),
+                    new: "fullName".into(),
+                    old_name_accepted: false,
+                },
+                true
+            ),
+            (
+                "order",
+                &ChangeKind::CardinalityChanged {
+                    name: "lines".into(),
+                    old: Cardinality::Many,
+                    new: Cardinality::Optional,
+                },
+                true
+            ),
+            (
+                "order",
+                &ChangeKind::ChildRenamed {
+                    old: "comment".into(),
+                    new: "remark".into(),
+                    old_name_accepted: true,
+                },
+                false
+            ),
+            (
+                "order",
+                &ChangeKind::ChildAdded {
+                    name: "priority".into(),
+                    cardinality: Cardinality::Optional,
+                },
+                false
+            ),
+        ]
+    );
+    assert!(!diff.is_compatible());
+    assert_eq!(diff.breaking().count(), 2);
+}
+
+#[test]
+fn attribute_rules_inject_and_suppress() {
+    use facet_xml as xml;
+    use facet_xml::{AttributeRules, SerializeOptions};
+
+    #[derive(Facet)]
+    struct Item {
+        #[facet(xml::attribute)]
+        id: u32,
+        #[facet(xml::attribute, rename = "internalNote")]
+        note: String,
+    }
+
+    #[derive(Facet)]
+    struct Catalog {
+        #[facet(xml::attribute)]
+        version: String,
+        #[facet(xml::elements)]
+        items: Vec<Item>,
+    }
+
+    let catalog = Catalog {
+        version: "2".into(),
+        items: vec![Item {
+            id: 7,
+            note: "draft".into(),
+        }],
+    };
+    let rules = AttributeRules::new()
+        .inject_on_root("version", "ignored")
+        .inject_on_root("generator", "catalog-tool")
+        .inject_everywhere("audited", "true")
+        .retain(|name| name != "internalNote");
+    let options = SerializeOptions::new().attribute_rules(rules);
+    let xml = facet_xml::to_string_with_options(&catalog, &options).unwrap();
+    assert_eq!(
+        xml,
+        r#"<catalog version="2" generator="catalog-tool" audited="true"><item id="7" audited="true"></item></catalog>"#
+    );
+}
+
+#[test]
+fn attribute_rules_apply_to_reused_serializers_and_document_writers() {
+    use facet_xml as xml;
+    use facet_xml::{AttributeRules, DocumentWriter, SerializeOptions, XmlSerializer};
+
+    #[derive(Facet)]
+    struct Item {
+        #[facet(xml::attribute)]
+        id: u32,
+        #[facet(xml::attribute, rename = "internalNote")]
+        note: String,
+    }
+
+    let rules = AttributeRules::new()
+        .inject_everywhere("audited", "true")
+        .retain(|name| name != "internalNote");
+    let options = SerializeOptions::new().attribute_rules(rules);
+    let item = |id| Item {
+        id,
+        note: "draft".into(),
+    };
+
+    // The rules survive each call, so every document gets them
+    let mut serializer = XmlSerializer::with_options(options.clone());
+    for id in 0..2 {
+        let mut out = Vec::new();
+        serializer.serialize(&item(id), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(r#"<item id="{id}" audited="true"></item>"#)
+        );
+    }
+
+    let mut writer = DocumentWriter::with_options(Vec::new(), options);
+    writer.start_root("items").unwrap();
+    writer.write_child(&item(0)).unwrap();
+    writer.write_child(&item(1)).unwrap();
+    assert_eq!(
+        String::from_utf8(writer.finish().unwrap()).unwrap(),
+        r#"<items><item id="0" audited="true"></item><item id="1" audited="true"></item></items>"#
+    );
+}
+
+#[test]
+fn serialize_hooks_surround_elements() {
+    use facet_reflect::Peek;
+    use facet_xml::{HookOutput, SerializeHooks, SerializeOptions};
+
+    #[derive(Facet)]
+    struct Line {
+        sku: String,
+        qty: u32,
+    }
+
+    #[derive(Facet)]
+    struct Invoice {
+        line: Line,
+        note: String,
+    }
+
+    struct Checksums;
+
+    impl SerializeHooks for Checksums {
+        fn on_element_end(&self, tag: &str, value: Peek<'_, '_>, out: &mut HookOutput<'_>) {
+            if let Ok(qty) = value.get::<u32>() {
+                out.comment(&format!("{tag}={qty}"));
+            } else if tag == "line" {
+                out.comment("end--of line");
+            }
+        }
+
+        fn on_element_start(&self, tag: &str, _value: Peek<'_, '_>, out: &mut HookOutput<'_>) {
+            if tag == "note" {
+                out.processing_instruction("review", "by=ops ?>");
+            }
+        }
+    }
+
+    let invoice = Invoice {
+        line: Line {
+            sku: "A-1".into(),
+            qty: 3,
+        },
+        note: "rush -- today".into(),
+    };
+    let options = SerializeOptions::new().hooks(Checksums);
+    let xml = facet_xml::to_string_with_options(&invoice, &options).unwrap();
+    assert_eq!(
+        xml,
+        "<invoice><line><sku>A-1</sku><qty>3</qty><!--qty=3--></line><!--end- -of line-->\
+         <?review by=ops ? >?><note>rush -- today</note></invoice>"
+    );
+}
+
+#[test]
+fn intercept_sees_and_redacts_field_values() {
+    use facet_xml as xml;
+    use std::sync::{Arc, Mutex};
+    use xml::DeserializeOptions;
+
+    #[derive(Facet, Debug)]
+    struct Login {
+        #[facet(xml::attribute)]
+        user: String,
+        password: String,
+        attempts: u32,
+        #[facet(xml::element)]
+        note: Note,
+    }
+
+    #[derive(Facet, Debug)]
+    struct Note {
+        #[facet(xml::text)]
+        body: String,
+    }
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    let options = DeserializeOptions::new().intercept(move |value| {
+        log.lock().unwrap().push(format!(
+            "{} {} {}",
+            value.path, value.text, value.shape.type_identifier
+        ));
+        value
+            .path
+            .ends_with("/password")
+            .then(|| "[redacted]".to_string())
+    });
+    let login: Login = xml::from_str_with_options(
+        r#"<login user="ada"><password>hunter2</password><attempts>3</attempts><note>hi</note></login>"#,
+        &options,
+    )
+    .unwrap();
+
+    assert_eq!(login.user, "ada");
+    assert_eq!(login.password, "[redacted]");
+    assert_eq!(login.attempts, 3);
+    assert_eq!(login.note.body, "hi");
+    assert_eq!(
+        *seen.lock().unwrap(),
+        [
+            "login/@user ada String",
+            "login/password hunter2 String",
+            "login/attempts 3 u32",
+            "login/note hi String",
+        ]
+    );
+}
+
+#[test]
+fn invalid_input_is_located_with_a_hex_snippet() {
+    use facet_xml::{DeserializeError, XmlError};
+
+    #[derive(Facet, Debug)]
+    struct Feed {
+        title: String,
+    }
+
+    let mut bytes = b"<feed><title>caf".to_vec();
+    bytes.push(0xe9);
+    bytes.extend_from_slice(b"</title></feed>");
+    let err = facet_xml::from_slice::<Feed>(&bytes).unwrap_err();
+    let DeserializeError::Parser(XmlError::InvalidInput {
+        offset,
+        character,
+        snippet,
+    }) = &err
+    else {
+        panic!("unexpected error: {err:?}");
+    };
+    assert_eq!(*offset, 16);
+    assert_eq!(*character, None);
+    assert_eq!(
+        snippet,
+        "69 74 6c 65 3e 63 61 66 [e9] 3c 2f 74 69 74 6c 65 3e"
+    );
+
+    let err = facet_xml::from_str::<Feed>("<feed><title>a\u{1}b</title></feed>").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Character U+0001 not allowed in XML at byte 14: 74 6c 65 3e 61 [01] 62 3c"),
+        "{err}"
+    );
+}
+
+#[cfg(feature = "stacker")]
+#[test]
+fn stacker_handles_deeply_nested_documents() {
+    #[derive(Facet, Debug)]
+    struct Node {
+        #[facet(recursive_type)]
+        node: Option<Box<Node>>,
+    }
+
+    const DEPTH: usize = 20_000;
+    let xml = format!("{}{}", "<node>".repeat(DEPTH), "</node>".repeat(DEPTH));
+    let root: Node = facet_xml::from_str(&xml).unwrap();
+    let out = facet_xml::to_string(&root).unwrap();
+    assert_eq!(out.matches("<node").count(), DEPTH);
+
+    let mut depth = 1;
+    let mut node = &root;
+    while let Some(child) = &node.node {
+        depth += 1;
+        node = child;
+    }
+    assert_eq!(depth, DEPTH);
+    // Dropping the chain recurses too
+    std::mem::forget(root);
+}
+
+#[test]
+fn singular_overrides_name_list_items() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Catalog {
+        #[facet(xml::singular = "schema")]
+        schemata: Vec<String>,
+        staff: Vec<String>,
+    }
+
+    xml::register_singular("staff", "staffMember");
+    let catalog: Catalog = xml::from_str(
+        "<catalog><schema>a</schema><schema>b</schema>\
+         <staffMember>ada</staffMember><staffMember>bob</staffMember></catalog>",
+    )
+    .unwrap();
+    assert_eq!(
+        catalog,
+        Catalog {
+            schemata: vec!["a".into(), "b".into()],
+            staff: vec!["ada".into(), "bob".into()],
+        }
+    );
+}
+
+#[test]
+fn repeated_aliases_accept_every_spelling() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Account {
+        #[facet(
+            xml::attribute,
+            alias = "acct-id",
+            xml::alias = "account_id",
+            xml::alias = "AccountID"
+        )]
+        id: String,
+        #[facet(alias = "mail", xml::alias = "e-mail", xml::alias = "emailAddress")]
+        email: String,
+    }
+
+    for input in [
+        r#"<account id="1"><email>a@x</email></account>"#,
+        r#"<account acct-id="1"><mail>a@x</mail></account>"#,
+        r#"<account account_id="1"><e-mail>a@x</e-mail></account>"#,
+        r#"<account AccountID="1"><emailAddress>a@x</emailAddress></account>"#,
+    ] {
+        let account: Account = xml::from_str(input).unwrap();
+        assert_eq!(
+            account,
+            Account {
+                id: "1".into(),
+                email: "a@x".into(),
+            },
+            "{input}"
+        );
+    }
+
+    let description = xml::describe::<Account>();
+    let mut aliases = description.attribute("id").unwrap().aliases.clone();
+    aliases.sort();
+    assert_eq!(aliases, ["AccountID", "account_id", "acct-id"]);
+    assert!(description.child("e-mail").is_some());
+}
+
+#[test]
+fn lexical_values_keep_their_original_text() {
+    use facet_xml::{self as xml, Lexical};
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Reading {
+        #[facet(xml::attribute)]
+        seq: Lexical<u32>,
+        #[facet(xml::attribute)]
+        valid: Lexical<bool>,
+        value: Lexical<f64>,
+        offset: Option<Lexical<i32>>,
+    }
+
+    let input =
+        r#"<reading seq="007" valid="1"><value>+1.500</value><offset>-0</offset></reading>"#;
+    let reading: Reading = xml::from_str(input).unwrap();
+    assert_eq!(*reading.seq, 7);
+    assert!(*reading.valid);
+    assert_eq!(*reading.value, 1.5);
+    assert_eq!(reading.value.original(), Some("+1.500"));
+    assert_eq!(xml::to_string(&reading).unwrap(), input);
+
+    let fresh = Reading {
+        seq: 7.into(),
+        valid: true.into(),
+        value: 1.5.into(),
+        offset: None,
+    };
+    assert_eq!(fresh, reading);
+    assert_eq!(
+        xml::to_string(&fresh).unwrap(),
+        r#"<reading seq="7" valid="true"><value>1.5</value></reading>"#
+    );
+
+    let err = xml::from_str::<Reading>(r#"<reading seq="x" valid="1"><value>1</value></reading>"#)
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid u32"), "{err}");
+}
+
+#[test]
+fn large_text_nodes_deserialize_and_errors_quote_only_their_start() {
+    use facet_xml::{self as xml, DeserializeError};
+
+    #[derive(Facet, Debug)]
+    struct Attachment {
+        name: String,
+        #[facet(xml::codec = "hex")]
+        data: Vec<u8>,
+        #[facet(xml::element)]
+        note: Note,
+    }
+
+    #[derive(Facet, Debug)]
+    struct Note {
+        #[facet(xml::text)]
+        body: String,
+    }
+
+    let body = "lorem ipsum ".repeat(200_000);
+    let data = "ab".repeat(1_000_000);
+    let input = format!(
+        "<attachment><name>blob.bin</name><data>{data}</data><note>{body}</note></attachment>"
+    );
+    let attachment: Attachment = xml::from_str(&input).unwrap();
+    assert_eq!(attachment.data.len(), 1_000_000);
+    assert!(attachment.data.iter().all(|&b| b == 0xab));
+    assert_eq!(attachment.note.body, body.trim());
+
+    #[derive(Facet, Debug)]
+    struct Counter {
+        count: u64,
+    }
+
+    let input = format!("<counter><count>{}</count></counter>", "9".repeat(100_000));
+    match xml::from_str::<Counter>(&input).unwrap_err() {
+        DeserializeError::InvalidValue { path, text, .. } => {
+            assert_eq!(path, "counter/count");
+            let text = text.unwrap();
+            assert!(text.len() < 1100, "{} bytes quoted", text.len());
+            assert!(text.ends_with("... (100000 bytes)"), "{text}");
+        }
+        other => panic!("expected InvalidValue, got {other:?}"),
+    }
+}
+
+#[test]
+fn skip_on_error_drops_only_the_malformed_items() {
+    use facet_xml::{self as xml, DeserializeOptions};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(xml::skip_on_error)]
+    struct Record {
+        #[facet(xml::attribute)]
+        id: u32,
+        value: i32,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Feed {
+        title: String,
+        records: Vec<Record>,
+    }
+
+    let input = r#"<feed><title>daily</title>
+        <record id="1"><value>10</value></record>
+        <record id="2"><value>oops</value></record>
+        <record id="x"><value>30</value></record>
+        <record id="4"><value>40</value></record>
+    </feed>"#;
+
+    let skipped = Arc::new(Mutex::new(Vec::new()));
+    let sink = skipped.clone();
+    let options = DeserializeOptions::new().on_skipped_item(move |item| {
+        sink.lock()
+            .unwrap()
+            .push((item.path.to_string(), item.error.to_string()));
+    });
+    let feed: Feed = xml::from_str_with_options(input, &options).unwrap();
+    assert_eq!(feed.title, "daily");
+    assert_eq!(
+        feed.records,
+        vec![Record { id: 1, value: 10 }, Record { id: 4, value: 40 }]
+    );
+
+    let skipped = skipped.lock().unwrap();
+    assert_eq!(skipped.len(), 2);
+    assert!(skipped.iter().all(|(path, _)| path == "feed/record"));
+    assert!(skipped[0].1.contains("oops"), "{}", skipped[0].1);
+
+    // Without the attribute, one bad record still fails the document
+    #[derive(Facet, Debug)]
+    struct Strict {
+        #[facet(xml::attribute)]
+        id: u32,
+    }
+
+    #[derive(Facet, Debug)]
+    struct StrictFeed {
+        stricts: Vec<Strict>,
+    }
+
+    assert!(
+        xml::from_str::<StrictFeed>(r#"<strictFeed><strict id="1"/><strict id="x"/></strictFeed>"#)
+            .is_err()
+    );
+}
+
+#[test]
+fn from_reader_parses_as_it_reads() {
+    use facet_xml::{self as xml, DeserializeOptions};
+    use std::io::Read;
+
+    /// Hands out at most 7 bytes per read, to split tokens across reads.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(7);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Export {
+        #[facet(xml::attribute)]
+        version: u32,
+        #[facet(xml::since = "2")]
+        label: Option<String>,
+        rows: Vec<Row>,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Row {
+        #[facet(xml::attribute)]
+        id: u32,
+        #[facet(xml::text)]
+        text: String,
+    }
+
+    let mut input = String::from(
+        r#"<?xml version="1.0"?><export version="2"><label>nightly &amp; full</label>"#,
+    );
+    for id in 0..500 {
+        input.push_str(&format!(r#"<row id="{id}">row {id}</row>"#));
+    }
+    input.push_str("</export>");
+
+    let options = DeserializeOptions::new().version_attribute("version");
+    let export: Export =
+        xml::from_reader_with_options(Trickle(input.as_bytes()), &options).unwrap();
+    assert_eq!(
+        export,
+        xml::from_str_with_options::<Export>(&input, &options).unwrap()
+    );
+    assert_eq!(export.label.as_deref(), Some("nightly & full"));
+    assert_eq!(export.rows.len(), 500);
+    assert_eq!(export.rows[499].text, "row 499");
+
+    struct Unplugged;
+
+    impl Read for Unplugged {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("device unplugged"))
+        }
+    }
+
+    let err = xml::from_reader::<Export, _>(Unplugged).unwrap_err();
+    assert!(err.to_string().contains("device unplugged"), "{err}");
+}
+
+#[test]
+fn to_writer_streams_output_in_chunks() {
+    use facet_xml::{self as xml, SerializeOptions};
+    use std::io::Write;
+
+    /// Records the size of every write.
+    #[derive(Default)]
+    struct Chunks {
+        bytes: Vec<u8>,
+        writes: Vec<usize>,
+    }
+
+    impl Write for Chunks {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.bytes.extend_from_slice(buf);
+            self.writes.push(buf.len());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Log {
+        #[facet(xml::attribute)]
+        host: String,
+        entries: Vec<Entry>,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Entry {
+        #[facet(xml::attribute)]
+        level: String,
+        #[facet(xml::text)]
+        message: String,
+    }
+
+    let log = Log {
+        host: "web-1".into(),
+        entries: (0..2_000)
+            .map(|i| Entry {
+                level: "info".into(),
+                message: format!("request {i} served"),
+            })
+            .collect(),
+    };
+
+    let mut chunks = Chunks::default();
+    xml::to_writer(&mut chunks, &log).unwrap();
+    assert_eq!(chunks.bytes, xml::to_vec(&log).unwrap());
+    assert!(chunks.writes.len() > 1, "{:?}", chunks.writes);
+    assert!(
+        chunks.writes.iter().all(|&n| n < 16 * 1024),
+        "{:?}",
+        chunks.writes
+    );
+    assert_eq!(xml::from_slice::<Log>(&chunks.bytes).unwrap(), log);
+
+    let options = SerializeOptions::new().pretty();
+    let mut chunks = Chunks::default();
+    xml::to_writer_with_options(&mut chunks, &log, &options).unwrap();
+    assert_eq!(
+        String::from_utf8(chunks.bytes).unwrap(),
+        xml::to_string_with_options(&log, &options).unwrap()
+    );
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn from_async_reader_parses_without_buffering_the_input() {
+    use facet_xml as xml;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Export {
+        rows: Vec<Row>,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Row {
+        #[facet(xml::attribute)]
+        id: u32,
+        #[facet(xml::text)]
+        text: String,
+    }
+
+    // Spans several chunks
+    let mut input = String::from("<export>");
+    for id in 0..2000 {
+        input.push_str(&format!(r#"<row id="{id}">row {id}</row>"#));
+    }
+    input.push_str("</export>");
+
+    let export: Export = xml::from_async_reader(input.as_bytes()).await.unwrap();
+    assert_eq!(export, xml::from_str::<Export>(&input).unwrap());
+    assert_eq!(export.rows[1999].text, "row 1999");
+
+    struct Unplugged;
+
+    impl AsyncRead for Unplugged {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            _: &mut ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Err(std::io::Error::other("device unplugged")))
+        }
+    }
+
+    let err = xml::from_async_reader::<Export, _>(b"<export><row".chain(Unplugged))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("device unplugged"), "{err}");
+}
+
+#[cfg(all(feature = "uuid", feature = "url"))]
+#[test]
+fn uuid_and_url_are_scalars() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Link {
+        #[facet(xml::attribute)]
+        id: uuid::Uuid,
+        #[facet(xml::attribute)]
+        href: url::Url,
+        mirror: Option<url::Url>,
+        revision: uuid::Uuid,
+    }
+
+    let link = Link {
+        id: "67e55044-10b1-426f-9247-bb680e5fe0c8".parse().unwrap(),
+        href: "https://example.com/a?b=1&c=2".parse().unwrap(),
+        mirror: Some("ftp://mirror.example.com/".parse().unwrap()),
+        revision: "a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8".parse().unwrap(),
+    };
+    let xml = xml::to_string(&link).unwrap();
+    assert_eq!(
+        xml,
+        concat!(
+            r#"<link id="67e55044-10b1-426f-9247-bb680e5fe0c8" href="https://example.com/a?b=1&amp;c=2">"#,
+            r#"<mirror>ftp://mirror.example.com/</mirror>"#,
+            r#"<revision>a1a2a3a4-b1b2-c1c2-d1d2-d3d4d5d6d7d8</revision>"#,
+            r#"</link>"#
+        )
+    );
+    assert_eq!(xml::from_str::<Link>(&xml).unwrap(), link);
+
+    let err = xml::from_str::<Link>(r#"<link id="not-a-uuid" href="https://example.com/"></link>"#)
+        .unwrap_err();
+    assert!(err.to_string().contains("link/@id"), "{err}");
+}
+
+#[test]
+fn scale_fixes_the_digits_after_the_point() {
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Line {
+        #[facet(xml::attribute, xml::scale = 0)]
+        quantity: f64,
+        #[facet(xml::scale = 2)]
+        price: f64,
+        #[facet(xml::scale = 2)]
+        discount: Option<f64>,
+        #[facet(xml::scale = 3)]
+        rate: f32,
+    }
+
+    let line = Line {
+        quantity: 3.0,
+        price: 10.5,
+        discount: None,
+        rate: 0.0625,
+    };
+    let xml = xml::to_string(&line).unwrap();
+    assert_eq!(
+        xml,
+        r#"<line quantity="3"><price>10.50</price><rate>0.062</rate></line>"#
+    );
+
+    let line = Line {
+        quantity: 2.5,
+        price: -0.001,
+        discount: Some(99.995),
+        rate: 1.0,
+    };
+    let xml = xml::to_string(&line).unwrap();
+    assert_eq!(
+        xml,
+        r#"<line quantity="2"><price>0.00</price><discount>100.00</discount><rate>1.000</rate></line>"#
+    );
+}
+
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn decimals_round_trip_exactly() {
+    use facet_xml as xml;
+    use rust_decimal::Decimal;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Invoice {
+        #[facet(xml::attribute)]
+        total: Decimal,
+        #[facet(xml::scale = 2)]
+        tax: Decimal,
+        paid: Option<Decimal>,
+    }
+
+    let xml = r#"<invoice total="1234.5600"><tax>0.1</tax><paid>0.10</paid></invoice>"#;
+    let invoice: Invoice = xml::from_str(xml).unwrap();
+    assert_eq!(invoice.total.to_string(), "1234.5600");
+    assert_eq!(invoice.paid.unwrap().to_string(), "0.10");
+    assert_eq!(
+        xml::to_string(&invoice).unwrap(),
+        r#"<invoice total="1234.5600"><tax>0.10</tax><paid>0.10</paid></invoice>"#
+    );
+}
+
+#[cfg(feature = "bigdecimal")]
+#[test]
+fn big_decimals_take_a_scale() {
+    use bigdecimal::BigDecimal;
+    use facet_xml as xml;
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Payment {
+        #[facet(xml::attribute, xml::scale = 2)]
+        amount: BigDecimal,
+        #[facet(xml::scale = 3)]
+        rate: Option<BigDecimal>,
+        fee: BigDecimal,
+    }
+
+    let xml = r#"<payment amount="10.5"><rate>2.3455</rate><fee>12345678901234567890.123456789</fee></payment>"#;
+    let payment: Payment = xml::from_str(xml).unwrap();
+    assert_eq!(payment.amount.to_string(), "10.5");
+    assert_eq!(payment.fee.to_string(), "12345678901234567890.123456789");
+
+    // Padded to the scale, rounded half to even, and exact without one
+    assert_eq!(
+        xml::to_string(&payment).unwrap(),
+        r#"<payment amount="10.50"><rate>2.346</rate><fee>12345678901234567890.123456789</fee></payment>"#
+    );
+}
+
+#[test]
+fn booleans_accept_xml_schema_forms_and_configurable_styles() {
+    use facet_xml as xml;
+    use facet_xml::{BoolStyle, SerializeOptions};
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Settings {
+        #[facet(xml::attribute)]
+        enabled: bool,
+        #[facet(xml::attribute, xml::bool_style = "yes_no")]
+        visible: bool,
+        #[facet(xml::bool_style = "true_false")]
+        strict: bool,
+        verbose: Option<bool>,
+    }
+
+    let settings: Settings = xml::from_str(
+        r#"<settings enabled="1" visible="no"><strict> 0 </strict><verbose>true</verbose></settings>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        settings,
+        Settings {
+            enabled: true,
+            visible: false,
+            strict: false,
+            verbose: Some(true),
+        }
+    );
+
+    assert_eq!(
+        xml::to_string(&settings).unwrap(),
+        r#"<settings enabled="true" visible="no"><strict>false</strict><verbose>true</verbose></settings>"#
+    );
+    let options = SerializeOptions::new().bool_style(BoolStyle::OneZero);
+    let xml = xml::to_string_with_options(&settings, &options).unwrap();
+    assert_eq!(
+        xml,
+        r#"<settings enabled="1" visible="no"><strict>false</strict><verbose>1</verbose></settings>"#
+    );
+    assert_eq!(xml::from_str::<Settings>(&xml).unwrap(), settings);
+
+    // yes/no is only read where the field asks for it
+    assert!(
+        xml::from_str::<Settings>(
+            r#"<settings enabled="yes" visible="yes"><strict>1</strict></settings>"#
+        )
+        .is_err()
+    );
+}
@@ -0,0 +1,227 @@
+//! Tests for WBXML input against documents tokenized outside this crate.
+//!
+//! The fixtures are the two worked examples of the WBXML specification
+//! (WAP-192-WBXML, section 8), with the code pages given there. Between
+//! them they cover inline strings, the string table, character entities,
+//! attribute-start tokens with a value prefix and attribute-value tokens.
+
+#![cfg(feature = "wbxml")]
+
+use std::borrow::Cow;
+
+use facet::Facet;
+use facet_dom::{DomEvent, DomParser};
+use facet_testhelpers::test;
+use facet_xml::{self as xml, CodePages, WbxmlError, WbxmlParser};
+
+/// Section 8.1: a US-ASCII document with an empty string table.
+///
+/// ```xml
+/// <XYZ>
+/// <CARD>
+/// X & Y<BR/>
+/// X&nbsp;=&nbsp;1
+/// </CARD>
+/// </XYZ>
+/// ```
+const EXAMPLE_1: &[u8] = b"\x01\x01\x03\x00\
+    \x47\x46\
+    \x03 X & Y\x00\
+    \x05\
+    \x03 X\x00\
+    \x02\x81\x20\x03=\x00\x02\x81\x20\
+    \x03\x31 \x00\
+    \x01\x01";
+
+/// Section 8.2: a UTF-8 document with a string table and attributes.
+///
+/// ```xml
+/// <XYZ>
+///   <CARD NAME="abc" STYLE="LIST">
+///     <DO TYPE="ACCEPT" URL="http://xyz.org/s"/>
+///     Enter name: <INPUT TYPE="TEXT" KEY="N"/>
+///   </CARD>
+/// </XYZ>
+/// ```
+const EXAMPLE_2: &[u8] = b"\x01\x01\x6A\x12abc\x00 Enter name: \x00\
+    \x47\
+    \xC5\x09\x83\x00\x05\x01\
+    \x88\x06\x86\x08\x03xyz\x00\x85\x03/s\x00\x01\
+    \x83\x04\
+    \x86\x07\x0A\x03N\x00\x01\
+    \x01\x01";
+
+fn example_1_pages() -> CodePages {
+    CodePages::new().tag_page(0, None, &[(0x05, "BR"), (0x06, "CARD"), (0x07, "XYZ")])
+}
+
+fn example_2_pages() -> CodePages {
+    CodePages::new()
+        .tag_page(
+            0,
+            None,
+            &[(0x05, "CARD"), (0x06, "INPUT"), (0x07, "XYZ"), (0x08, "DO")],
+        )
+        .attribute_page(
+            0,
+            &[
+                (0x05, "STYLE", "LIST"),
+                (0x06, "TYPE", ""),
+                (0x07, "TYPE", "TEXT"),
+                (0x08, "URL", "http://"),
+                (0x09, "NAME", ""),
+                (0x0A, "KEY", ""),
+            ],
+            &[(0x85, ".org"), (0x86, "ACCEPT")],
+        )
+}
+
+fn events(input: &[u8], pages: &CodePages) -> Result<Vec<DomEvent<'static>>, WbxmlError> {
+    let mut parser = WbxmlParser::new(input, pages)?;
+    let mut events = Vec::new();
+    while let Some(event) = parser.next_event()? {
+        events.push(event);
+    }
+    Ok(events)
+}
+
+fn node(tag: &'static str) -> DomEvent<'static> {
+    DomEvent::NodeStart {
+        tag: Cow::Borrowed(tag),
+        namespace: None,
+    }
+}
+
+fn attribute(name: &'static str, value: &'static str) -> DomEvent<'static> {
+    DomEvent::Attribute {
+        name: Cow::Borrowed(name),
+        value: Cow::Borrowed(value),
+        namespace: None,
+    }
+}
+
+fn empty(events: &mut Vec<DomEvent<'static>>) {
+    events.extend([
+        DomEvent::ChildrenStart,
+        DomEvent::ChildrenEnd,
+        DomEvent::NodeEnd,
+    ]);
+}
+
+#[test]
+fn spec_example_1_decodes_entities_and_mixed_content() {
+    let mut expected = vec![
+        node("XYZ"),
+        DomEvent::ChildrenStart,
+        node("CARD"),
+        DomEvent::ChildrenStart,
+        DomEvent::Text(" X & Y".into()),
+        node("BR"),
+    ];
+    empty(&mut expected);
+    expected.extend([
+        // The two ENTITY tokens are U+00A0, joined with the strings around them
+        DomEvent::Text(" X\u{a0}=\u{a0}1 ".into()),
+        DomEvent::ChildrenEnd,
+        DomEvent::NodeEnd,
+        DomEvent::ChildrenEnd,
+        DomEvent::NodeEnd,
+    ]);
+    assert_eq!(events(EXAMPLE_1, &example_1_pages()).unwrap(), expected);
+}
+
+#[test]
+fn spec_example_2_decodes_string_table_and_attribute_tokens() {
+    let mut expected = vec![
+        node("XYZ"),
+        DomEvent::ChildrenStart,
+        node("CARD"),
+        attribute("NAME", "abc"),
+        attribute("STYLE", "LIST"),
+        DomEvent::ChildrenStart,
+        node("DO"),
+        attribute("TYPE", "ACCEPT"),
+        attribute("URL", "http://xyz.org/s"),
+    ];
+    empty(&mut expected);
+    expected.extend([DomEvent::Text(" Enter name: ".into()), node("INPUT")]);
+    expected.extend([attribute("TYPE", "TEXT"), attribute("KEY", "N")]);
+    empty(&mut expected);
+    expected.extend([
+        DomEvent::ChildrenEnd,
+        DomEvent::NodeEnd,
+        DomEvent::ChildrenEnd,
+        DomEvent::NodeEnd,
+    ]);
+    assert_eq!(events(EXAMPLE_2, &example_2_pages()).unwrap(), expected);
+}
+
+#[test]
+fn spec_example_2_deserializes() {
+    #[derive(Facet, Debug, PartialEq)]
+    #[facet(rename = "XYZ")]
+    struct Deck {
+        #[facet(rename = "CARD")]
+        card: Card,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Card {
+        #[facet(xml::attribute, rename = "NAME")]
+        name: String,
+        #[facet(xml::attribute, rename = "STYLE")]
+        style: String,
+        #[facet(rename = "DO")]
+        action: Action,
+        #[facet(xml::text)]
+        prompt: String,
+        #[facet(rename = "INPUT")]
+        input: Input,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Action {
+        #[facet(xml::attribute, rename = "TYPE")]
+        kind: String,
+        #[facet(xml::attribute, rename = "URL")]
+        url: String,
+    }
+
+    #[derive(Facet, Debug, PartialEq)]
+    struct Input {
+        #[facet(xml::attribute, rename = "TYPE")]
+        kind: String,
+        #[facet(xml::attribute, rename = "KEY")]
+        key: String,
+    }
+
+    let deck: Deck = xml::from_wbxml(EXAMPLE_2, &example_2_pages()).unwrap();
+    assert_eq!(deck.card.name, "abc");
+    assert_eq!(deck.card.style, "LIST");
+    assert_eq!(deck.card.prompt.trim(), "Enter name:");
+    assert_eq!(
+        deck.card.action,
+        Action {
+            kind: "ACCEPT".into(),
+            url: "http://xyz.org/s".into()
+        }
+    );
+    assert_eq!(
+        deck.card.input,
+        Input {
+            kind: "TEXT".into(),
+            key: "N".into()
+        }
+    );
+}
+
+#[test]
+fn tokens_missing_from_the_code_pages_are_errors() {
+    assert_eq!(
+        events(EXAMPLE_2, &example_1_pages()),
+        Err(WbxmlError::UnknownToken {
+            page: 0,
+            token: 0x09
+        })
+    );
+}
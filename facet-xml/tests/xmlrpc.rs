@@ -0,0 +1,152 @@
+//! Tests for XML-RPC against documents written outside this crate.
+//!
+//! `spec-*.xml` are the examples of the XML-RPC specification;
+//! `python-*.xml` were written by Python's `xmlrpc.client.dumps`.
+
+#![cfg(feature = "xmlrpc")]
+
+use facet::Facet;
+use facet_testhelpers::test;
+use facet_xml::xmlrpc::{self, Fault, XmlRpcError};
+
+const SPEC_CALL: &str = include_str!("fixtures/xmlrpc/spec-call.xml");
+const SPEC_RESPONSE: &str = include_str!("fixtures/xmlrpc/spec-response.xml");
+const SPEC_FAULT: &str = include_str!("fixtures/xmlrpc/spec-fault.xml");
+const PYTHON_CALL: &str = include_str!("fixtures/xmlrpc/python-call.xml");
+const PYTHON_RESPONSE: &str = include_str!("fixtures/xmlrpc/python-response.xml");
+const PYTHON_FAULT: &str = include_str!("fixtures/xmlrpc/python-fault.xml");
+
+#[derive(Facet, Debug, Clone, PartialEq)]
+struct Post {
+    title: String,
+    #[facet(rename = "post_id")]
+    id: i32,
+    draft: bool,
+    #[facet(default)]
+    tags: Vec<String>,
+    rating: Option<f64>,
+}
+
+/// `document` without the whitespace between its elements, and with the
+/// XML declaration in the form `method_call` writes it.
+fn compact(document: &str) -> String {
+    document
+        .lines()
+        .map(str::trim)
+        .collect::<String>()
+        .replace("<?xml version='1.0'?>", "<?xml version=\"1.0\"?>")
+}
+
+fn assert_fault(result: Result<String, XmlRpcError>) {
+    match result {
+        Err(XmlRpcError::Fault(fault)) => assert_eq!(
+            fault,
+            Fault {
+                code: 4,
+                message: "Too many parameters.".into(),
+            }
+        ),
+        other => panic!("expected a fault, got {other:?}"),
+    }
+}
+
+#[test]
+fn spec_call_matches_method_call() {
+    // `<i4>` and `<int>` are the same type; `method_call` writes `<int>`
+    let expected = compact(SPEC_CALL).replace("i4>", "int>");
+    assert_eq!(
+        xmlrpc::method_call("examples.getStateName", &41i32).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn spec_response_is_parsed() {
+    let state: String = xmlrpc::parse_response(SPEC_RESPONSE).unwrap();
+    assert_eq!(state, "South Dakota");
+}
+
+#[test]
+fn spec_fault_is_an_error() {
+    assert_fault(xmlrpc::parse_response(SPEC_FAULT));
+}
+
+#[test]
+fn python_call_matches_method_call() {
+    #[derive(Facet)]
+    struct Draft {
+        title: String,
+        draft: bool,
+        tags: Vec<String>,
+        rating: Option<f64>,
+    }
+
+    let draft = Draft {
+        title: "Hello".into(),
+        draft: false,
+        tags: vec!["x".into(), "y".into()],
+        rating: Some(4.5),
+    };
+    assert_eq!(
+        xmlrpc::method_call("blog.newPost", &(7i32, "a < b", draft)).unwrap(),
+        compact(PYTHON_CALL)
+    );
+}
+
+#[test]
+fn python_response_is_parsed() {
+    let posts: Vec<Post> = xmlrpc::parse_response(PYTHON_RESPONSE).unwrap();
+    assert_eq!(
+        posts,
+        [
+            Post {
+                title: "Hello".into(),
+                id: 7,
+                draft: true,
+                tags: vec!["x".into()],
+                rating: Some(4.5),
+            },
+            Post {
+                title: "b".into(),
+                id: 8,
+                draft: false,
+                tags: vec![],
+                rating: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn python_fault_is_an_error() {
+    assert_fault(xmlrpc::parse_response(PYTHON_FAULT));
+}
+
+#[test]
+fn value_without_a_type_is_a_string() {
+    // The spec: "If no type is indicated, the type is string."
+    let response = "<methodResponse><params><param>\
+        <value>South Dakota</value>\
+        </param></params></methodResponse>";
+    let state: String = xmlrpc::parse_response(response).unwrap();
+    assert_eq!(state, "South Dakota");
+
+    // Its whitespace is part of the string, as inside <string>
+    let response = "<methodResponse><params><param>\
+        <value><struct><member><name>title</name><value>  spaced out </value></member>\
+        <member><name>post_id</name><value><int>1</int></value></member>\
+        <member><name>draft</name><value><boolean>0</boolean></value></member>\
+        </struct></value>\
+        </param></params></methodResponse>";
+    let post: Post = xmlrpc::parse_response(response).unwrap();
+    assert_eq!(post.title, "  spaced out ");
+
+    // Text next to a type element is not an untyped value
+    let response = "<methodResponse><params><param>\
+        <value>text<string>more</string></value>\
+        </param></params></methodResponse>";
+    assert!(matches!(
+        xmlrpc::parse_response::<String>(response),
+        Err(XmlRpcError::Protocol(_))
+    ));
+}